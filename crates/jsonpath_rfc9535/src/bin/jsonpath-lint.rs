@@ -0,0 +1,174 @@
+//! Check JSONPath queries stored in files, one query per line.
+//!
+//! ```text
+//! jsonpath-lint [--format human|json] [FILE]...
+//! ```
+//!
+//! Queries are read from standard input if no files are given, or if a file
+//! is `-`. Blank lines and lines starting with `#` are ignored.
+//!
+//! With `--format json`, diagnostics are written as a SARIF log. The exit
+//! status is 1 if any query has an error, 2 if a file can't be read, and 0
+//! otherwise, even if there are warnings.
+
+#![forbid(unsafe_code)]
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use jsonpath_rfc9535::lint::{Diagnostic, Linter, Severity};
+
+const USAGE: &str = "usage: jsonpath-lint [--format human|json] [FILE]...";
+
+#[derive(PartialEq)]
+enum Format {
+    Human,
+    Json,
+}
+
+struct Found {
+    file: String,
+    line: usize,
+    diagnostic: Diagnostic,
+}
+
+fn main() -> ExitCode {
+    let mut format = Format::Human;
+    let mut files = Vec::new();
+    let mut args = env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next().as_deref() {
+                Some("human") => format = Format::Human,
+                Some("json") => format = Format::Json,
+                _ => {
+                    eprintln!("{USAGE}");
+                    return ExitCode::from(2);
+                }
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    if files.is_empty() {
+        files.push(String::from("-"));
+    }
+
+    let linter = Linter::new();
+    let mut found = Vec::new();
+
+    for file in files {
+        let text = match read(&file) {
+            Ok(text) => text,
+            Err(err) => {
+                eprintln!("jsonpath-lint: {file}: {err}");
+                return ExitCode::from(2);
+            }
+        };
+
+        for (i, line) in text.lines().enumerate() {
+            let query = line.trim_end();
+            if query.trim_start().is_empty() || query.trim_start().starts_with('#') {
+                continue;
+            }
+
+            found.extend(linter.lint(query).into_iter().map(|diagnostic| Found {
+                file: file.clone(),
+                line: i + 1,
+                diagnostic,
+            }));
+        }
+    }
+
+    match format {
+        Format::Human => {
+            for f in &found {
+                println!(
+                    "{}:{}:{}: {}[{}]: {}",
+                    f.file,
+                    f.line,
+                    f.diagnostic.span.char_range().start + 1,
+                    f.diagnostic.severity,
+                    f.diagnostic.rule,
+                    f.diagnostic.msg
+                );
+            }
+        }
+        Format::Json => println!("{}", sarif(&found)),
+    }
+
+    if found
+        .iter()
+        .any(|f| f.diagnostic.severity == Severity::Error)
+    {
+        ExitCode::from(1)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn read(file: &str) -> io::Result<String> {
+    if file == "-" {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    } else {
+        fs::read_to_string(file)
+    }
+}
+
+fn sarif(found: &[Found]) -> String {
+    let mut out = String::from(
+        r#"{"version":"2.1.0","runs":[{"tool":{"driver":{"name":"jsonpath-lint"}},"results":["#,
+    );
+
+    for (i, f) in found.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let columns = f.diagnostic.span.char_range();
+        let _ = write!(
+            out,
+            r#"{{"ruleId":{},"level":"{}","message":{{"text":{}}},"locations":[{{"physicalLocation":{{"artifactLocation":{{"uri":{}}},"region":{{"startLine":{},"startColumn":{},"endColumn":{}}}}}}}]}}"#,
+            json_string(f.diagnostic.rule),
+            f.diagnostic.severity,
+            json_string(&f.diagnostic.msg),
+            json_string(&f.file),
+            f.line,
+            columns.start + 1,
+            columns.end + 1,
+        );
+    }
+
+    out.push_str("]}]}");
+    out
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,148 @@
+//! A compact binary encoding of parsed queries.
+//!
+//! With the `binary` feature, [`Query::to_bytes`] encodes a query's syntax
+//! tree, spans and source labels included, using [postcard], and
+//! [`Query::from_bytes`] decodes it without parsing or type checking the
+//! query again. This suits compiled queries stored in configuration blobs
+//! or sent between processes.
+//!
+//! ```
+//! use jsonpath_rfc9535::Query;
+//!
+//! let query = Query::standard("$.users[?@.age > 18].name").unwrap();
+//! let bytes = query.to_bytes();
+//! let decoded = Query::from_bytes(&bytes).unwrap();
+//!
+//! assert_eq!(decoded.to_string(), query.to_string());
+//! ```
+//!
+//! Encoded queries start with a header, [`MAGIC`] followed by
+//! [`FORMAT_VERSION`] and a [`Stamp`] recording the dialect and the version
+//! of this crate that produced them. `from_bytes` refuses to decode a query
+//! with a different format version or dialect, or one stamped by a release
+//! that isn't semver compatible with this one, so queries stored before an
+//! upgrade fail with an encoding error rather than decoding to the wrong
+//! tree. Re-encode stored queries from their query text after upgrading.
+//! Use [`stamp`] to read the stamp without decoding the query.
+//!
+//! `from_bytes` trusts that its input came from `to_bytes`. A hand-crafted
+//! tree could contain a query the parser would have rejected.
+//!
+//! [postcard]: https://docs.rs/postcard
+
+use serde::{Deserialize, Serialize};
+
+use crate::{errors::JSONPathError, query::Query};
+
+/// The first three bytes of an encoded query.
+pub const MAGIC: [u8; 3] = *b"JPQ";
+
+/// The version of the header and syntax tree encoding written by
+/// `Query::to_bytes`.
+pub const FORMAT_VERSION: u8 = 2;
+
+/// The query language encoded queries are written in.
+pub const DIALECT: &str = "rfc9535";
+
+/// The version of this crate, which is recorded in every encoded query.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const HEADER_LEN: usize = MAGIC.len() + 1;
+
+/// The dialect and crate version an encoded query was produced by.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+    pub dialect: String,
+    pub crate_version: String,
+}
+
+impl Stamp {
+    /// The stamp for queries encoded by this build.
+    pub fn current() -> Self {
+        Stamp {
+            dialect: DIALECT.to_owned(),
+            crate_version: CRATE_VERSION.to_owned(),
+        }
+    }
+
+    /// Return `true` if queries with this stamp can be decoded by this build.
+    /// Crate versions are compatible if they share a major version, or, for
+    /// `0.x` releases, a minor version.
+    pub fn is_compatible(&self) -> bool {
+        self.dialect == DIALECT
+            && compatibility_key(&self.crate_version) == compatibility_key(CRATE_VERSION)
+    }
+}
+
+/// The part of a semver version that must match for releases to be
+/// compatible, or `None` if `version` isn't a version number.
+fn compatibility_key(version: &str) -> Option<(u64, Option<u64>)> {
+    let mut parts = version.split(['.', '-', '+']);
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, (major == 0).then_some(minor)))
+}
+
+/// Read the stamp from the header of an encoded query.
+pub fn stamp(bytes: &[u8]) -> Result<Stamp, JSONPathError> {
+    read_stamp(bytes).map(|(stamp, _)| stamp)
+}
+
+/// The stamp in `bytes` and the encoded query following it.
+fn read_stamp(bytes: &[u8]) -> Result<(Stamp, &[u8]), JSONPathError> {
+    if bytes.len() < HEADER_LEN || bytes[..MAGIC.len()] != MAGIC {
+        return Err(JSONPathError::encoding(String::from(
+            "not an encoded query",
+        )));
+    }
+
+    let version = bytes[MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(JSONPathError::encoding(format!(
+            "unsupported encoding version {} (expected {})",
+            version, FORMAT_VERSION
+        )));
+    }
+
+    postcard::take_from_bytes(&bytes[HEADER_LEN..])
+        .map_err(|err| JSONPathError::encoding(format!("malformed header: {}", err)))
+}
+
+impl Query<'_> {
+    /// Encode this query as a versioned, compact binary blob.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::from(MAGIC);
+        bytes.push(FORMAT_VERSION);
+        let bytes =
+            postcard::to_extend(&Stamp::current(), bytes).expect("stamps are always serializable");
+        postcard::to_extend(self, bytes).expect("queries are always serializable")
+    }
+
+    /// Decode a query encoded by [`Query::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Query<'static>, JSONPathError> {
+        let (stamp, bytes) = read_stamp(bytes)?;
+
+        if stamp.dialect != DIALECT {
+            return Err(JSONPathError::encoding(format!(
+                "query was encoded for dialect `{}` (expected `{}`)",
+                stamp.dialect, DIALECT
+            )));
+        }
+
+        if !stamp.is_compatible() {
+            return Err(JSONPathError::encoding(format!(
+                "query was encoded by version {} of this crate, \
+                 which is incompatible with version {}",
+                stamp.crate_version, CRATE_VERSION
+            )));
+        }
+
+        match postcard::take_from_bytes(bytes) {
+            Ok((query, [])) => Ok(query),
+            Ok(_) => Err(JSONPathError::encoding(String::from(
+                "unexpected data after the encoded query",
+            ))),
+            Err(err) => Err(JSONPathError::encoding(format!("malformed query: {}", err))),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Writing query results as comma separated values, following RFC 4180.
+//!
+//! Evaluators that write CSV use these helpers so they agree on quoting,
+//! line endings and how values are written. Every record ends with CRLF,
+//! including the last one. A field is quoted when it contains the
+//! delimiter, a double quote or a line break, with double quotes doubled.
+//!
+//! ```
+//! use jsonpath_rfc9535::csv;
+//!
+//! let mut buf = String::new();
+//! csv::push_record(&mut buf, ["path", "value"], ',');
+//! csv::push_record(&mut buf, ["$['a']", "say \"hi\""], ',');
+//! assert_eq!(buf, "path,value\r\n$['a'],\"say \"\"hi\"\"\"\r\n");
+//! ```
+use std::borrow::Cow;
+
+/// The line ending written after every record.
+pub const RECORD_END: &str = "\r\n";
+
+/// `field` as it should be written, quoted if it contains `delimiter`, a
+/// double quote, a carriage return or a line feed.
+pub fn quote(field: &str, delimiter: char) -> Cow<'_, str> {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Append a record made from `fields` to `buf`, quoting each field and
+/// ending the record with [`RECORD_END`].
+pub fn push_record<I, S>(buf: &mut String, fields: I, delimiter: char)
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            buf.push(delimiter);
+        }
+        buf.push_str(&quote(field.as_ref(), delimiter));
+    }
+    buf.push_str(RECORD_END);
+}
+
+/// The unquoted text of a field for `value`.
+///
+/// A missing value, `None`, is an empty field and JSON `null` is `null`.
+/// Strings are written without JSON quotes. Numbers, booleans, arrays and
+/// objects are written as JSON.
+#[cfg(feature = "options")]
+pub fn value_field(value: Option<&serde_json::Value>) -> Cow<'_, str> {
+    match value {
+        None => Cow::Borrowed(""),
+        Some(serde_json::Value::String(s)) => Cow::Borrowed(s),
+        Some(v) => Cow::Owned(v.to_string()),
+    }
+}
@@ -0,0 +1,398 @@
+//! Semantic differences between two queries.
+//!
+//! [`diff_queries`] compares the syntax trees of two queries rather than
+//! their text, so reformatting a stored query, like changing `$.a` to
+//! `$['a']`, is not a change. Segments are matched up in order, and when a
+//! segment's filters are all that changed, the smallest changed parts of
+//! each filter are reported instead of the whole segment.
+//!
+//! ```
+//! use jsonpath_rfc9535::{diff::{diff_queries, ChangeKind}, Query};
+//!
+//! let old = Query::standard("$.users[?@.age > 18].name").unwrap();
+//! let new = Query::standard("$['users'][?@.age >= 21].name.first").unwrap();
+//! let diff = diff_queries(&old, &new);
+//!
+//! assert_eq!(diff.changes.len(), 2);
+//! assert_eq!(diff.changes[0].kind, ChangeKind::Changed);
+//! assert_eq!(diff.changes[0].to_string(), "changed filter `@['age'] > 18` (9..19) to `@['age'] >= 21` (12..23)");
+//! assert_eq!(diff.changes[1].to_string(), "added segment `['first']` (29..35)");
+//! ```
+//!
+//! Spans in a change refer to the source text of the query the part came
+//! from. The span of a function call ends at its last argument rather than
+//! the closing parenthesis.
+
+use std::fmt;
+
+use crate::{
+    query::{FilterExpression, Query, Segment, Selector},
+    span::Span,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => f.write_str("added"),
+            ChangeKind::Removed => f.write_str("removed"),
+            ChangeKind::Changed => f.write_str("changed"),
+        }
+    }
+}
+
+/// The kind of syntax tree node that changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Target {
+    Segment,
+    /// A filter expression, or part of one.
+    Filter,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Segment => f.write_str("segment"),
+            Target::Filter => f.write_str("filter"),
+        }
+    }
+}
+
+/// A segment or filter expression from one side of a diff, in canonical
+/// form, and its span in that side's source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Part {
+    pub text: String,
+    pub span: Span,
+}
+
+impl Part {
+    fn segment(segment: &Segment) -> Self {
+        Part {
+            text: segment.to_string(),
+            span: segment.span(),
+        }
+    }
+
+    fn filter(expr: &FilterExpression) -> Self {
+        Part {
+            text: expr.to_string(),
+            span: extent(expr),
+        }
+    }
+}
+
+/// The span of `expr` and all of its operands. The parser gives operators
+/// and function calls the span of their first token.
+fn extent(expr: &FilterExpression) -> Span {
+    match expr {
+        FilterExpression::Not { span, expression } => span.to(extent(expression)),
+        FilterExpression::Logical { left, right, .. }
+        | FilterExpression::Comparison { left, right, .. } => extent(left).to(extent(right)),
+        FilterExpression::Function { span, args, .. } => {
+            args.last().map_or(*span, |arg| span.to(extent(arg)))
+        }
+        _ => expr.span(),
+    }
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` ({})", self.text, self.span)
+    }
+}
+
+/// One difference between two queries. `old` is `None` for additions and
+/// `new` is `None` for removals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub target: Target,
+    pub old: Option<Part>,
+    pub new: Option<Part>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.target)?;
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(f, " {old} to {new}"),
+            (Some(part), None) | (None, Some(part)) => write!(f, " {part}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// The differences between two queries, in the order they appear.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueryDiff {
+    pub changes: Vec<Change>,
+}
+
+impl QueryDiff {
+    /// Return `true` if the queries are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for QueryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The segments and filter expressions added, removed or changed going from
+/// query `a` to query `b`.
+pub fn diff_queries(a: &Query, b: &Query) -> QueryDiff {
+    let mut changes = Vec::new();
+    diff_segments(&a.segments, &b.segments, &mut changes);
+    QueryDiff { changes }
+}
+
+fn diff_segments(old: &[Segment], new: &[Segment], changes: &mut Vec<Change>) {
+    let old_text: Vec<String> = old.iter().map(|s| s.to_string()).collect();
+    let new_text: Vec<String> = new.iter().map(|s| s.to_string()).collect();
+
+    // Segments that aren't part of the longest common subsequence are
+    // changes. Between two common segments, removed and added segments are
+    // paired up in order, so editing a segment is one change rather than a
+    // removal and an addition.
+    let mut removed = Vec::new();
+    let mut added = Vec::new();
+    let mut matched = common_subsequence(&old_text, &new_text).into_iter();
+    let (mut i, mut j) = (0, 0);
+
+    loop {
+        let next = matched.next();
+        let (stop_i, stop_j) = next.unwrap_or((old.len(), new.len()));
+        removed.extend(old.iter().take(stop_i).skip(i));
+        added.extend(new.iter().take(stop_j).skip(j));
+        pair_segments(&mut removed, &mut added, changes);
+
+        match next {
+            Some((mi, mj)) => (i, j) = (mi + 1, mj + 1),
+            None => break,
+        }
+    }
+}
+
+fn pair_segments(
+    removed: &mut Vec<&Segment>,
+    added: &mut Vec<&Segment>,
+    changes: &mut Vec<Change>,
+) {
+    let paired = removed.len().min(added.len());
+    for (old, new) in removed.iter().zip(added.iter()) {
+        diff_segment(old, new, changes);
+    }
+
+    changes.extend(removed.iter().skip(paired).map(|old| Change {
+        kind: ChangeKind::Removed,
+        target: Target::Segment,
+        old: Some(Part::segment(old)),
+        new: None,
+    }));
+
+    changes.extend(added.iter().skip(paired).map(|new| Change {
+        kind: ChangeKind::Added,
+        target: Target::Segment,
+        old: None,
+        new: Some(Part::segment(new)),
+    }));
+
+    removed.clear();
+    added.clear();
+}
+
+/// Report changes to filters in `old` and `new` if nothing else about the
+/// segments differs, or report the segment as changed.
+fn diff_segment(old: &Segment, new: &Segment, changes: &mut Vec<Change>) {
+    let filters_only = match (old, new) {
+        (Segment::Child { selectors: o, .. }, Segment::Child { selectors: n, .. })
+        | (Segment::Recursive { selectors: o, .. }, Segment::Recursive { selectors: n, .. }) => {
+            o.len() == n.len()
+                && o.iter().zip(n).all(|pair| match pair {
+                    (Selector::Filter { .. }, Selector::Filter { .. }) => true,
+                    (o, n) => o.to_string() == n.to_string(),
+                })
+        }
+        _ => false,
+    };
+
+    if !filters_only {
+        changes.push(Change {
+            kind: ChangeKind::Changed,
+            target: Target::Segment,
+            old: Some(Part::segment(old)),
+            new: Some(Part::segment(new)),
+        });
+        return;
+    }
+
+    let (Segment::Child { selectors: o, .. } | Segment::Recursive { selectors: o, .. }) = old;
+    let (Segment::Child { selectors: n, .. } | Segment::Recursive { selectors: n, .. }) = new;
+    for pair in o.iter().zip(n) {
+        if let (Selector::Filter { expression: o, .. }, Selector::Filter { expression: n, .. }) =
+            pair
+        {
+            diff_expressions(o, n, changes);
+        }
+    }
+}
+
+/// Descend into `old` and `new` while they have the same shape, and report
+/// the outermost parts that differ.
+fn diff_expressions(old: &FilterExpression, new: &FilterExpression, changes: &mut Vec<Change>) {
+    if old.to_string() == new.to_string() {
+        return;
+    }
+
+    match (old, new) {
+        (
+            FilterExpression::Not { expression: o, .. },
+            FilterExpression::Not { expression: n, .. },
+        ) => diff_expressions(o, n, changes),
+        // A condition joined onto an unchanged one, like `@.a` becoming
+        // `@.a && @.b`, is an addition.
+        (_, FilterExpression::Logical { left, right, .. }) if same(old, left) => {
+            changes.push(added(right))
+        }
+        (_, FilterExpression::Logical { left, right, .. }) if same(old, right) => {
+            changes.push(added(left))
+        }
+        (FilterExpression::Logical { left, right, .. }, _) if same(left, new) => {
+            changes.push(removed(right))
+        }
+        (FilterExpression::Logical { left, right, .. }, _) if same(right, new) => {
+            changes.push(removed(left))
+        }
+        (
+            FilterExpression::Logical {
+                left: ol,
+                operator: oo,
+                right: or,
+                ..
+            },
+            FilterExpression::Logical {
+                left: nl,
+                operator: no,
+                right: nr,
+                ..
+            },
+        ) if oo == no => {
+            diff_expressions(ol, nl, changes);
+            diff_expressions(or, nr, changes);
+        }
+        (
+            FilterExpression::Comparison {
+                left: ol,
+                operator: oo,
+                right: or,
+                ..
+            },
+            FilterExpression::Comparison {
+                left: nl,
+                operator: no,
+                right: nr,
+                ..
+            },
+        ) if oo == no => {
+            diff_expressions(ol, nl, changes);
+            diff_expressions(or, nr, changes);
+        }
+        (
+            FilterExpression::RelativeQuery { query: o, .. },
+            FilterExpression::RelativeQuery { query: n, .. },
+        )
+        | (
+            FilterExpression::RootQuery { query: o, .. },
+            FilterExpression::RootQuery { query: n, .. },
+        ) => diff_segments(&o.segments, &n.segments, changes),
+        (
+            FilterExpression::Function {
+                name: on, args: oa, ..
+            },
+            FilterExpression::Function {
+                name: nn, args: na, ..
+            },
+        ) if on == nn && oa.len() == na.len() => {
+            for (o, n) in oa.iter().zip(na) {
+                diff_expressions(o, n, changes);
+            }
+        }
+        _ => changes.push(Change {
+            kind: ChangeKind::Changed,
+            target: Target::Filter,
+            old: Some(Part::filter(old)),
+            new: Some(Part::filter(new)),
+        }),
+    }
+}
+
+fn same(left: &FilterExpression, right: &FilterExpression) -> bool {
+    left.to_string() == right.to_string()
+}
+
+fn added(expr: &FilterExpression) -> Change {
+    Change {
+        kind: ChangeKind::Added,
+        target: Target::Filter,
+        old: None,
+        new: Some(Part::filter(expr)),
+    }
+}
+
+fn removed(expr: &FilterExpression) -> Change {
+    Change {
+        kind: ChangeKind::Removed,
+        target: Target::Filter,
+        old: Some(Part::filter(expr)),
+        new: None,
+    }
+}
+
+/// Index pairs of a longest common subsequence of `a` and `b`, in order.
+fn common_subsequence(a: &[String], b: &[String]) -> Vec<(usize, usize)> {
+    // lengths[i][j] is the length of the longest common subsequence of
+    // a[i..] and b[j..].
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
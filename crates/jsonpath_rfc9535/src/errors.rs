@@ -1,52 +1,216 @@
 use std::fmt;
 
-#[derive(Debug)]
+use crate::span::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum JSONPathErrorType {
     LexerError,
     SyntaxError,
     TypeError,
     NameError,
+    LimitError,
+    /// A query nested deeper than the parser allows.
+    DepthError,
+    EncodingError,
+    /// A failure while evaluating a query, reported by an evaluator.
+    RuntimeError,
+    /// A document that couldn't be read, reported by an evaluator.
+    DocumentError,
+}
+
+impl JSONPathErrorType {
+    /// A number identifying this kind of error, the same for every crate in
+    /// this workspace. The C interface returns these as status codes, and
+    /// keeps 7 to 10 for its own errors.
+    pub fn code(&self) -> i32 {
+        match self {
+            JSONPathErrorType::LexerError => 1,
+            JSONPathErrorType::SyntaxError => 2,
+            JSONPathErrorType::TypeError => 3,
+            JSONPathErrorType::NameError => 4,
+            JSONPathErrorType::LimitError => 5,
+            JSONPathErrorType::EncodingError => 6,
+            JSONPathErrorType::DepthError => 11,
+            JSONPathErrorType::RuntimeError => 12,
+            JSONPathErrorType::DocumentError => 13,
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct JSONPathError {
     pub kind: JSONPathErrorType,
     pub msg: String,
-    pub span: (usize, usize),
+    pub span: Span,
+    /// A label for the query fragment `span` belongs to, if the query was
+    /// assembled from labelled fragments.
+    pub source: Option<String>,
 }
 
 impl JSONPathError {
-    pub fn new(error: JSONPathErrorType, msg: String, span: (usize, usize)) -> Self {
+    pub fn new(error: JSONPathErrorType, msg: String, span: Span) -> Self {
         Self {
             kind: error,
             msg,
             span,
+            source: None,
         }
     }
 
-    pub fn syntax(msg: String, span: (usize, usize)) -> Self {
+    pub fn syntax(msg: String, span: Span) -> Self {
         Self {
             kind: JSONPathErrorType::SyntaxError,
             msg,
             span,
+            source: None,
         }
     }
 
-    pub fn typ(msg: String, span: (usize, usize)) -> Self {
+    pub fn typ(msg: String, span: Span) -> Self {
         Self {
             kind: JSONPathErrorType::TypeError,
             msg,
             span,
+            source: None,
         }
     }
 
-    pub fn name(msg: String, span: (usize, usize)) -> Self {
+    pub fn name(msg: String, span: Span) -> Self {
         Self {
             kind: JSONPathErrorType::NameError,
             msg,
             span,
+            source: None,
+        }
+    }
+
+    pub fn limit(msg: String, span: Span) -> Self {
+        Self {
+            kind: JSONPathErrorType::LimitError,
+            msg,
+            span,
+            source: None,
+        }
+    }
+
+    pub fn depth(msg: String, span: Span) -> Self {
+        Self {
+            kind: JSONPathErrorType::DepthError,
+            msg,
+            span,
+            source: None,
+        }
+    }
+
+    pub fn encoding(msg: String) -> Self {
+        Self {
+            kind: JSONPathErrorType::EncodingError,
+            msg,
+            span: Span::default(),
+            source: None,
         }
     }
+
+    /// This error's message followed by the line of `query` it refers to,
+    /// with carets underlining its span.
+    ///
+    /// `query` is the text the span refers to, which is the fragment
+    /// labelled [`JSONPathError::source`] when that is set. Errors without
+    /// a location, like encoding errors, are rendered without a snippet.
+    ///
+    /// ```
+    /// use jsonpath_rfc9535::Query;
+    ///
+    /// let err = Query::standard("$.a[?@.b == ]").unwrap_err();
+    /// assert_eq!(
+    ///     err.render("$.a[?@.b == ]"),
+    ///     "syntax error: expected a filter expression, found `]`\n  $.a[?@.b == ]\n              ^"
+    /// );
+    /// ```
+    pub fn render(&self, query: &str) -> String {
+        let header = match &self.source {
+            Some(source) => format!("{} {} (in `{}`)", self.kind, self.msg, source),
+            None => format!("{} {}", self.kind, self.msg),
+        };
+
+        if matches!(self.kind, JSONPathErrorType::EncodingError) {
+            header
+        } else {
+            format!("{}\n{}", header, snippet(query, self.span))
+        }
+    }
+
+    /// Attribute this error to the query fragment labelled `source`.
+    pub fn with_source(self, source: &str) -> Self {
+        Self {
+            source: Some(source.to_owned()),
+            ..self
+        }
+    }
+}
+
+/// An error from one of the other parsers or evaluators in this workspace.
+///
+/// Each engine reports errors with its own `JSONPathError`, shaped by what
+/// it knows about where an error is. They all implement this trait, so they
+/// convert into this crate's [`JSONPathError`] with `From`, and code using
+/// more than one engine can handle their errors in one place. An error
+/// without a location converts with an empty span at the start of the query.
+pub trait EngineError {
+    fn kind(&self) -> JSONPathErrorType;
+
+    fn into_msg(self) -> String;
+
+    /// The part of the query the error is about, if the engine knows.
+    fn span(&self) -> Option<Span>;
+}
+
+impl<E: EngineError> From<E> for JSONPathError {
+    fn from(err: E) -> Self {
+        let kind = err.kind();
+        let span = err.span().unwrap_or_default();
+        JSONPathError::new(kind, err.into_msg(), span)
+    }
+}
+
+/// The line of `query` containing the start of `span`, with carets under
+/// `span`, or under its start if the span is empty. Spans running onto
+/// another line are underlined to the end of the first.
+///
+/// Every engine in this workspace renders errors with this.
+///
+/// ```
+/// use jsonpath_rfc9535::{errors::snippet, Span};
+///
+/// assert_eq!(snippet("$.a[?@.b == ]", Span::new(12, 13)), "  $.a[?@.b == ]\n              ^");
+/// ```
+pub fn snippet(query: &str, span: Span) -> String {
+    let mut start = span.start.min(query.len());
+    while !query.is_char_boundary(start) {
+        start -= 1;
+    }
+
+    let line_start = query[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = query[start..].find('\n').map_or(query.len(), |i| start + i);
+
+    let mut end = span.end.clamp(start, line_end);
+    while !query.is_char_boundary(end) {
+        end += 1;
+    }
+
+    // Keep tabs so the carets line up with the line above them.
+    let indent: String = query[line_start..start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let width = query[start..end].chars().count().max(1);
+
+    format!(
+        "  {}\n  {}{}",
+        &query[line_start..line_end],
+        indent,
+        "^".repeat(width)
+    )
 }
 
 impl fmt::Display for JSONPathErrorType {
@@ -56,6 +220,11 @@ impl fmt::Display for JSONPathErrorType {
             JSONPathErrorType::SyntaxError => f.write_str("syntax error:"),
             JSONPathErrorType::TypeError => f.write_str("type error:"),
             JSONPathErrorType::NameError => f.write_str("name error:"),
+            JSONPathErrorType::LimitError => f.write_str("limit error:"),
+            JSONPathErrorType::DepthError => f.write_str("depth error:"),
+            JSONPathErrorType::EncodingError => f.write_str("encoding error:"),
+            JSONPathErrorType::RuntimeError => f.write_str("runtime error:"),
+            JSONPathErrorType::DocumentError => f.write_str("document error:"),
         }
     }
 }
@@ -64,10 +233,13 @@ impl std::error::Error for JSONPathError {}
 
 impl fmt::Display for JSONPathError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{} {} ({}..{})",
-            self.kind, self.msg, self.span.0, self.span.1
-        )
+        match &self.source {
+            Some(source) => write!(
+                f,
+                "{} {} ({} in `{}`)",
+                self.kind, self.msg, self.span, source
+            ),
+            None => write!(f, "{} {} ({})", self.kind, self.msg, self.span),
+        }
     }
 }
@@ -0,0 +1,129 @@
+//! Parse a JSONPath query into your own data structures.
+//!
+//! [`Parser::parse_events`] calls methods on a [`ParseSink`] as each segment
+//! and selector is parsed, instead of building a [`Query`](crate::Query).
+//! Filter selectors are passed as whole [`FilterExpression`]s, including any
+//! queries nested inside them.
+//!
+//! ```
+//! use jsonpath_rfc9535::{
+//!     events::{ParseSink, SegmentKind},
+//!     query::Selector,
+//!     Parser, Span,
+//! };
+//!
+//! /// Collect the names a query selects from, like `a/*/b`.
+//! #[derive(Default)]
+//! struct Pointer(Vec<String>);
+//!
+//! impl ParseSink<'_> for Pointer {
+//!     fn segment_start(&mut self, kind: SegmentKind, _span: Span) {
+//!         if kind == SegmentKind::Recursive {
+//!             self.0.push(String::from("**"));
+//!         }
+//!     }
+//!
+//!     fn selector(&mut self, selector: Selector) {
+//!         match selector {
+//!             Selector::Name { name, .. } => self.0.push(name.into_owned()),
+//!             _ => self.0.push(String::from("*")),
+//!         }
+//!     }
+//! }
+//!
+//! let mut pointer = Pointer::default();
+//! Parser::new()
+//!     .parse_events("$.a[0]..b", &mut pointer)
+//!     .unwrap();
+//! assert_eq!(pointer.0.join("/"), "a/*/**/b");
+//! ```
+
+use crate::{
+    errors::JSONPathError,
+    lexer::lex_with_options,
+    parser::Parser,
+    query::{FilterExpression, Segment, Selector},
+    span::Span,
+};
+
+/// The kind of segment started by [`ParseSink::segment_start`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Child,
+    Recursive,
+}
+
+/// Receives segments and selectors from [`Parser::parse_events`] in the
+/// order they appear in the query.
+///
+/// Every method does nothing by default, except [`ParseSink::filter`], which
+/// passes a filter selector to [`ParseSink::selector`]. Names and strings in
+/// selectors and filters borrow from the query text, which lives for `'q`.
+pub trait ParseSink<'q> {
+    /// Called at the start of each segment, with the span of its leading
+    /// `.`, `..` or `[`.
+    fn segment_start(&mut self, _kind: SegmentKind, _span: Span) {}
+
+    /// Called for each name, index, slice and wildcard selector.
+    fn selector(&mut self, _selector: Selector<'q>) {}
+
+    /// Called for each filter selector, after its expression is parsed and
+    /// type checked.
+    fn filter(&mut self, span: Span, expression: FilterExpression<'q>) {
+        self.selector(Selector::Filter {
+            span,
+            expression: Box::new(expression),
+        });
+    }
+
+    /// Called at the end of each segment, with the span of the whole
+    /// segment.
+    fn segment_end(&mut self, _span: Span) {}
+
+    /// Called once after the last segment of a valid query.
+    fn end(&mut self) {}
+}
+
+/// Builds this crate's segments from parse events.
+#[derive(Default)]
+pub(crate) struct SegmentBuilder<'q> {
+    pub(crate) segments: Vec<Segment<'q>>,
+    kind: Option<SegmentKind>,
+    selectors: Vec<Selector<'q>>,
+}
+
+impl<'q> ParseSink<'q> for SegmentBuilder<'q> {
+    fn segment_start(&mut self, kind: SegmentKind, _span: Span) {
+        self.kind = Some(kind);
+    }
+
+    fn selector(&mut self, selector: Selector<'q>) {
+        self.selectors.push(selector);
+    }
+
+    fn segment_end(&mut self, span: Span) {
+        let selectors = std::mem::take(&mut self.selectors);
+        self.segments.push(match self.kind.take() {
+            Some(SegmentKind::Recursive) => Segment::Recursive { span, selectors },
+            _ => Segment::Child { span, selectors },
+        });
+    }
+}
+
+impl Parser {
+    /// Parse `query`, calling methods on `sink` for each segment and
+    /// selector as it is parsed.
+    ///
+    /// If `query` is invalid, `sink` will have received events for the part
+    /// of the query before the error, but [`ParseSink::end`] is not called.
+    pub fn parse_events<'q>(
+        &self,
+        query: &'q str,
+        sink: &mut impl ParseSink<'q>,
+    ) -> Result<(), JSONPathError> {
+        self.parse_tokens_into(
+            lex_with_options(query, &self.limits, &self.lexer_options)?,
+            sink,
+        )
+    }
+}
@@ -0,0 +1,62 @@
+//! Non-standard syntax that a [`Parser`] can opt in to.
+//!
+//! By default, a [`Parser`] accepts RFC 9535 JSONPath only. Enable
+//! extensions with [`Parser::with_extensions`].
+//!
+//! ```
+//! use jsonpath_rfc9535::{Extensions, Parser, Query};
+//!
+//! assert!(Query::standard("$.a[$.b]").is_err());
+//!
+//! let parser = Parser::with_extensions(Extensions::SINGULAR_QUERY_SELECTOR);
+//! let q = parser.parse("$.a[$.b]").unwrap();
+//! assert_eq!(q.to_string(), "$['a'][$['b']]");
+//! ```
+//!
+//! [`Parser`]: crate::Parser
+//! [`Parser::with_extensions`]: crate::Parser::with_extensions
+
+use std::ops::{BitOr, BitOrAssign};
+
+/// A set of non-standard syntax extensions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Extensions(u32);
+
+impl Extensions {
+    /// Strict RFC 9535 syntax.
+    pub const NONE: Extensions = Extensions(0);
+
+    /// Allow an absolute singular query as a selector, like `$.sort` in
+    /// `$.items[$.sort]`, producing a [`Selector::SingularQuery`]. The query's
+    /// value, a name or an index, selects a member or element.
+    ///
+    /// [`Selector::SingularQuery`]: crate::query::Selector::SingularQuery
+    pub const SINGULAR_QUERY_SELECTOR: Extensions = Extensions(1);
+
+    /// Every extension.
+    pub const ALL: Extensions = Extensions(1);
+
+    /// Return `true` if all extensions in `other` are in this set.
+    pub fn contains(self, other: Extensions) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for Extensions {
+    type Output = Extensions;
+
+    fn bitor(self, rhs: Extensions) -> Extensions {
+        Extensions(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for Extensions {
+    fn bitor_assign(&mut self, rhs: Extensions) {
+        self.0 |= rhs.0;
+    }
+}
@@ -0,0 +1,316 @@
+//! Writing queries in a configurable style.
+//!
+//! A [`Query`]'s `Display` implementation always writes its canonical form,
+//! with bracketed names and parentheses around every logical expression.
+//! [`Query::format`] writes an equivalent query following [`FormatOptions`],
+//! so tools like a query formatter can produce text that people would
+//! rather read.
+//!
+//! ```
+//! use jsonpath_rfc9535::{
+//!     format::{FormatOptions, QuoteStyle},
+//!     Query,
+//! };
+//!
+//! let q = Query::standard("$['store'].book[?@.price<10 && @['author']!=\"Nigel\"][0:2]").unwrap();
+//! assert_eq!(
+//!     q.to_string(),
+//!     "$['store']['book'][?(@['price'] < 10 && @['author'] != \"Nigel\")][0:2:1]"
+//! );
+//!
+//! let options = FormatOptions {
+//!     shorthand: true,
+//!     quotes: QuoteStyle::Double,
+//!     ..FormatOptions::default()
+//! };
+//! assert_eq!(
+//!     q.format(&options),
+//!     "$.store.book[?@.price < 10 && @.author != \"Nigel\"][0:2]"
+//! );
+//! ```
+//!
+//! Formatted queries parse back to queries with the same canonical form.
+//! Names and string literals are escaped as needed, and parentheses are only
+//! written where they are needed to keep the meaning of a filter. Calls to
+//! function extensions and other non-standard syntax are written as-is, so
+//! use [`Query::try_to_standard_string`] if the result has to be standard
+//! JSONPath.
+
+use std::fmt::Write;
+
+use crate::{
+    lexer::{is_name_char, is_name_first, is_noncharacter},
+    query::{infinite_literal, FilterExpression, LogicalOperator, Query, Segment, Selector},
+};
+
+/// The quote character used for names and string literals.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// `$['a']`, as in normalized paths.
+    #[default]
+    Single,
+    /// `$["a"]`, as in JSON.
+    Double,
+}
+
+/// How [`Query::format`] writes a query.
+///
+/// The default writes bracketed names in single quotes, with spaces around
+/// comparison operators but not slice colons, like `$['a'][?@['b'] > 1][1:]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Write `.name`, `..name`, `.*` and `..*` instead of a segment with
+    /// a single name or wildcard selector, when the name is valid as a name
+    /// shorthand.
+    pub shorthand: bool,
+    /// Quote names and string literals with this character.
+    pub quotes: QuoteStyle,
+    /// Put a space between each colon in a slice and the bounds either side
+    /// of it, like `1 : 5 : 2` and `:: 2`.
+    pub slice_spacing: bool,
+    /// Put spaces around comparison operators, like `@.a == 1`.
+    pub operator_spacing: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            shorthand: false,
+            quotes: QuoteStyle::Single,
+            slice_spacing: false,
+            operator_spacing: true,
+        }
+    }
+}
+
+impl Query<'_> {
+    /// Write this query in the style described by `options`. See [`format`].
+    ///
+    /// [`format`]: crate::format
+    pub fn format(&self, options: &FormatOptions) -> String {
+        let mut writer = Writer {
+            out: String::new(),
+            options,
+        };
+        writer.query('$', self);
+        writer.out
+    }
+}
+
+/// The binding strength of `expr`'s outermost operator, loosest first, for
+/// deciding whether it needs parentheses as an operand.
+fn precedence(expr: &FilterExpression) -> u8 {
+    match expr {
+        FilterExpression::Logical {
+            operator: LogicalOperator::Or,
+            ..
+        } => 1,
+        FilterExpression::Logical {
+            operator: LogicalOperator::And,
+            ..
+        } => 2,
+        FilterExpression::Comparison { .. } => 3,
+        _ => 4,
+    }
+}
+
+/// Return `true` if `name` can be written as a name shorthand.
+fn is_shorthand_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_name_first)
+        && chars.all(is_name_char)
+        && !name.chars().any(is_noncharacter)
+}
+
+struct Writer<'o> {
+    out: String,
+    options: &'o FormatOptions,
+}
+
+impl Writer<'_> {
+    fn query(&mut self, identifier: char, query: &Query) {
+        self.out.push(identifier);
+        for segment in &query.segments {
+            self.segment(segment);
+        }
+    }
+
+    fn segment(&mut self, segment: &Segment) {
+        let selectors = match segment {
+            Segment::Child { selectors, .. } => selectors,
+            Segment::Recursive { selectors, .. } => {
+                self.out.push_str("..");
+                selectors
+            }
+        };
+
+        if self.options.shorthand {
+            match selectors.as_slice() {
+                [Selector::Name { name, .. }] if is_shorthand_name(name) => {
+                    if matches!(segment, Segment::Child { .. }) {
+                        self.out.push('.');
+                    }
+                    self.out.push_str(name);
+                    return;
+                }
+                [Selector::Wild { .. }] => {
+                    if matches!(segment, Segment::Child { .. }) {
+                        self.out.push('.');
+                    }
+                    self.out.push('*');
+                    return;
+                }
+                _ => (),
+            }
+        }
+
+        self.out.push('[');
+        for (i, selector) in selectors.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.selector(selector);
+        }
+        self.out.push(']');
+    }
+
+    fn selector(&mut self, selector: &Selector) {
+        match selector {
+            Selector::Name { name, .. } => self.string(name),
+            Selector::Index { index, .. } => {
+                let _ = write!(self.out, "{index}");
+            }
+            Selector::Slice {
+                start, stop, step, ..
+            } => {
+                let mut bounds = vec![*start, *stop];
+                if step.is_some() {
+                    bounds.push(*step);
+                }
+                for (i, bound) in bounds.iter().enumerate() {
+                    if i > 0 {
+                        if self.options.slice_spacing && bounds[i - 1].is_some() {
+                            self.out.push(' ');
+                        }
+                        self.out.push(':');
+                        if self.options.slice_spacing && bound.is_some() {
+                            self.out.push(' ');
+                        }
+                    }
+                    if let Some(bound) = bound {
+                        let _ = write!(self.out, "{bound}");
+                    }
+                }
+            }
+            Selector::Wild { .. } => self.out.push('*'),
+            Selector::Filter { expression, .. } => {
+                self.out.push('?');
+                self.expression(expression, 0);
+            }
+            Selector::SingularQuery { query, .. } => self.query('$', query),
+        }
+    }
+
+    /// Write `expr`, in parentheses if its operator binds more loosely than
+    /// `min_precedence`.
+    fn expression(&mut self, expr: &FilterExpression, min_precedence: u8) {
+        if precedence(expr) < min_precedence {
+            self.out.push('(');
+            self.expression(expr, 0);
+            self.out.push(')');
+            return;
+        }
+
+        match expr {
+            FilterExpression::True { .. } => self.out.push_str("true"),
+            FilterExpression::False { .. } => self.out.push_str("false"),
+            FilterExpression::Null { .. } => self.out.push_str("null"),
+            FilterExpression::String { value, .. } => self.string(value),
+            FilterExpression::Int { value, .. } => {
+                let _ = write!(self.out, "{value}");
+            }
+            FilterExpression::Float { value, .. } if value.is_infinite() => {
+                self.out.push_str(infinite_literal(*value));
+            }
+            FilterExpression::Float { value, .. } => {
+                let _ = write!(self.out, "{value:?}");
+            }
+            FilterExpression::Not { expression, .. } => {
+                self.out.push('!');
+                self.expression(expression, 4);
+            }
+            FilterExpression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                // The parser groups a chain of the same logical operator
+                // from the right, so a left operand with the same operator
+                // keeps its parentheses.
+                let precedence = precedence(expr);
+                self.expression(left, precedence + 1);
+                let _ = write!(self.out, " {operator} ");
+                self.expression(right, precedence);
+            }
+            FilterExpression::Comparison {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.expression(left, 4);
+                if self.options.operator_spacing {
+                    let _ = write!(self.out, " {operator} ");
+                } else {
+                    let _ = write!(self.out, "{operator}");
+                }
+                self.expression(right, 4);
+            }
+            FilterExpression::RelativeQuery { query, .. } => self.query('@', query),
+            FilterExpression::RootQuery { query, .. } => self.query('$', query),
+            FilterExpression::Function { name, args, .. } => {
+                self.out.push_str(name);
+                self.out.push('(');
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.expression(arg, 3);
+                }
+                self.out.push(')');
+            }
+        }
+    }
+
+    /// Write `value` as a string literal quoted with the configured quote
+    /// character, escaping that character, backslashes and control
+    /// characters.
+    fn string(&mut self, value: &str) {
+        let quote = match self.options.quotes {
+            QuoteStyle::Single => '\'',
+            QuoteStyle::Double => '"',
+        };
+
+        self.out.push(quote);
+        for c in value.chars() {
+            match c {
+                '\u{8}' => self.out.push_str("\\b"),
+                '\u{c}' => self.out.push_str("\\f"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                '\\' => self.out.push_str("\\\\"),
+                c if c == quote => {
+                    self.out.push('\\');
+                    self.out.push(c);
+                }
+                c if c < ' ' => {
+                    let _ = write!(self.out, "\\u{:04x}", c as u32);
+                }
+                c => self.out.push(c),
+            }
+        }
+        self.out.push(quote);
+    }
+}
@@ -0,0 +1,141 @@
+//! Function extensions with implementations.
+//!
+//! A [`Parser`] only needs a function extension's signature to check a
+//! query. A [`FunctionRegistry`] pairs each signature with an implementation,
+//! so one registry can configure both a parser and the evaluator that runs
+//! its queries. The implementation type, `T`, is chosen by the evaluator, as
+//! it depends on how the evaluator represents JSON values, and is usually a
+//! trait object like `dyn Fn(Vec<Arg>) -> Arg`.
+//!
+//! ```
+//! use jsonpath_rfc9535::{function::FunctionRegistry, ExpressionType, FunctionSignature, Parser};
+//!
+//! let mut functions: FunctionRegistry<dyn Fn(&str) -> bool> = FunctionRegistry::new();
+//! functions.register(
+//!     "is_upper",
+//!     FunctionSignature {
+//!         param_types: vec![ExpressionType::Value],
+//!         return_type: ExpressionType::Logical,
+//!         deterministic: true,
+//!         pure: true,
+//!         cost: None,
+//!     },
+//!     Box::new(|s| s.chars().all(char::is_uppercase)),
+//! );
+//!
+//! let mut parser = Parser::new();
+//! parser.add_functions(&functions);
+//! assert!(parser.parse("$[?is_upper(@.code)]").is_ok());
+//!
+//! let is_upper = functions.get("is_upper").unwrap();
+//! assert!(is_upper("ABC"));
+//! ```
+//!
+//! [`Parser`]: crate::Parser
+
+use std::{collections::HashMap, fmt};
+
+use crate::parser::{standard_functions, FunctionSignature, Parser};
+
+/// Function extensions by name, each with a signature and an implementation
+/// of type `T`.
+pub struct FunctionRegistry<T: ?Sized> {
+    functions: HashMap<String, (FunctionSignature, Box<T>)>,
+}
+
+impl<T: ?Sized> Default for FunctionRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> FunctionRegistry<T> {
+    pub fn new() -> Self {
+        FunctionRegistry {
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Register function `name`, replacing any existing function with the
+    /// same name.
+    pub fn register(&mut self, name: &str, signature: FunctionSignature, implementation: Box<T>) {
+        self.functions
+            .insert(name.to_owned(), (signature, implementation));
+    }
+
+    /// Register standard function `name`, implemented by `implementation`,
+    /// with its signature from [`standard_functions`]. Returns `false`,
+    /// without registering anything, if `name` isn't a standard function.
+    pub fn register_standard(&mut self, name: &str, implementation: Box<T>) -> bool {
+        match standard_functions().remove(name) {
+            Some(signature) => {
+                self.register(name, signature, implementation);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove function `name`, returning `true` if it was registered.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.functions.remove(name).is_some()
+    }
+
+    /// The implementation of function `name`.
+    pub fn get(&self, name: &str) -> Option<&T> {
+        self.functions.get(name).map(|(_, f)| f.as_ref())
+    }
+
+    pub fn signature(&self, name: &str) -> Option<&FunctionSignature> {
+        self.functions.get(name).map(|(signature, _)| signature)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Names, signatures and implementations of registered functions, in
+    /// arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &FunctionSignature, &T)> {
+        self.functions
+            .iter()
+            .map(|(name, (signature, f))| (name.as_str(), signature, f.as_ref()))
+    }
+
+    /// A copy of each registered function's signature, keyed by name, as
+    /// expected by parsers.
+    pub fn signatures(&self) -> HashMap<String, FunctionSignature> {
+        self.functions
+            .iter()
+            .map(|(name, (signature, _))| (name.clone(), signature.clone()))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for FunctionRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.functions
+                    .iter()
+                    .map(|(name, (signature, _))| (name, signature)),
+            )
+            .finish()
+    }
+}
+
+impl Parser {
+    /// Register the signature of each function in `registry`, replacing any
+    /// existing signatures with the same names.
+    pub fn add_functions<T: ?Sized>(&mut self, registry: &FunctionRegistry<T>) {
+        self.functions.extend(registry.signatures());
+    }
+}
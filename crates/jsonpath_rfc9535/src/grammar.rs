@@ -0,0 +1,667 @@
+//! A description of the query syntax accepted by a [`Parser`].
+//!
+//! [`Parser::grammar`] builds the RFC 9535 grammar, specialized for the
+//! parser's function extensions, index range and limits. Write it as W3C
+//! style EBNF with [`Grammar::to_ebnf`] (or `Display`), or as JSON for
+//! drawing railroad diagrams with [`Grammar::to_railroad_json`].
+//!
+//! ```
+//! use jsonpath_rfc9535::Parser;
+//!
+//! let grammar = Parser::new().grammar();
+//! assert!(grammar
+//!     .to_ebnf()
+//!     .contains(r#"count-function-expr ::= "count" "(" S nodes-argument S ")""#));
+//! ```
+
+use std::fmt::{self, Write};
+
+use crate::{
+    extensions::Extensions,
+    parser::{ExpressionType, Parser},
+};
+
+/// A grammar expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    /// Literal text.
+    Literal(String),
+    /// Any one character from the inclusive range.
+    Range(char, char),
+    /// A reference to another rule, by name.
+    Rule(String),
+    Sequence(Vec<Expr>),
+    Choice(Vec<Expr>),
+    Optional(Box<Expr>),
+    ZeroOrMore(Box<Expr>),
+    OneOrMore(Box<Expr>),
+}
+
+/// A named grammar rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: String,
+    pub expr: Expr,
+    /// Constraints that the grammar can't express, like the range of valid
+    /// array indexes.
+    pub comment: Option<String>,
+}
+
+/// Grammar rules, starting with `jsonpath-query`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grammar {
+    pub rules: Vec<Rule>,
+}
+
+impl Grammar {
+    /// Find a rule by name.
+    pub fn rule(&self, name: &str) -> Option<&Rule> {
+        self.rules.iter().find(|rule| rule.name == name)
+    }
+
+    /// This grammar in W3C style EBNF, one rule per line.
+    pub fn to_ebnf(&self) -> String {
+        self.to_string()
+    }
+
+    /// This grammar as JSON, with a diagram for each rule made of nodes
+    /// named after the constructors from the railroad-diagrams JavaScript
+    /// library, like `{"type": "Terminal", "text": "$"}`.
+    pub fn to_railroad_json(&self) -> String {
+        let mut out = String::from("{\"rules\":[");
+        for (i, rule) in self.rules.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "{{\"name\":{},", json_string(&rule.name));
+            if let Some(comment) = &rule.comment {
+                let _ = write!(out, "\"comment\":{},", json_string(comment));
+            }
+            out.push_str("\"diagram\":{\"type\":\"Diagram\",\"items\":[");
+            railroad(&rule.expr, &mut out);
+            out.push_str("]}}");
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+impl fmt::Display for Grammar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rule in &self.rules {
+            if let Some(comment) = &rule.comment {
+                writeln!(f, "/* {} */", comment)?;
+            }
+            writeln!(f, "{} ::= {}", rule.name, rule.expr)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Literal(text) if text.contains('"') => write!(f, "'{}'", text),
+            Expr::Literal(text) => write!(f, "\"{}\"", text),
+            Expr::Range(start, end) if start == end => write!(f, "#x{:02X}", *start as u32),
+            Expr::Range(start, end) => {
+                write!(f, "[#x{:02X}-#x{:02X}]", *start as u32, *end as u32)
+            }
+            Expr::Rule(name) => f.write_str(name),
+            Expr::Sequence(items) => write_items(f, items, " "),
+            Expr::Choice(items) => write_items(f, items, " | "),
+            Expr::Optional(item) => write_postfix(f, item, '?'),
+            Expr::ZeroOrMore(item) => write_postfix(f, item, '*'),
+            Expr::OneOrMore(item) => write_postfix(f, item, '+'),
+        }
+    }
+}
+
+fn write_items(f: &mut fmt::Formatter<'_>, items: &[Expr], separator: &str) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(separator)?;
+        }
+        // A choice binds looser than a sequence.
+        if matches!(item, Expr::Choice(_)) && separator == " " {
+            write!(f, "({})", item)?;
+        } else {
+            write!(f, "{}", item)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_postfix(f: &mut fmt::Formatter<'_>, item: &Expr, op: char) -> fmt::Result {
+    match item {
+        Expr::Sequence(_) | Expr::Choice(_) => write!(f, "({}){}", item, op),
+        _ => write!(f, "{}{}", item, op),
+    }
+}
+
+fn railroad(expr: &Expr, out: &mut String) {
+    let items = |out: &mut String, kind: &str, items: &[Expr]| {
+        let _ = write!(out, "{{\"type\":\"{}\",\"items\":[", kind);
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            railroad(item, out);
+        }
+        out.push_str("]}");
+    };
+
+    let item = |out: &mut String, kind: &str, item: &Expr| {
+        let _ = write!(out, "{{\"type\":\"{}\",\"item\":", kind);
+        railroad(item, out);
+        out.push('}');
+    };
+
+    match expr {
+        Expr::Literal(text) => {
+            let _ = write!(
+                out,
+                "{{\"type\":\"Terminal\",\"text\":{}}}",
+                json_string(text)
+            );
+        }
+        Expr::Range(..) => {
+            let _ = write!(
+                out,
+                "{{\"type\":\"Terminal\",\"text\":{}}}",
+                json_string(&expr.to_string())
+            );
+        }
+        Expr::Rule(name) => {
+            let _ = write!(
+                out,
+                "{{\"type\":\"NonTerminal\",\"text\":{}}}",
+                json_string(name)
+            );
+        }
+        Expr::Sequence(exprs) => items(out, "Sequence", exprs),
+        Expr::Choice(exprs) => items(out, "Choice", exprs),
+        Expr::Optional(expr) => item(out, "Optional", expr),
+        Expr::ZeroOrMore(expr) => item(out, "ZeroOrMore", expr),
+        Expr::OneOrMore(expr) => item(out, "OneOrMore", expr),
+    }
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn lit(text: &str) -> Expr {
+    Expr::Literal(text.to_owned())
+}
+
+fn chr(c: char) -> Expr {
+    Expr::Range(c, c)
+}
+
+fn range(start: char, end: char) -> Expr {
+    Expr::Range(start, end)
+}
+
+fn r(name: &str) -> Expr {
+    Expr::Rule(name.to_owned())
+}
+
+fn seq(items: Vec<Expr>) -> Expr {
+    Expr::Sequence(items)
+}
+
+fn alt(items: Vec<Expr>) -> Expr {
+    Expr::Choice(items)
+}
+
+fn opt(item: Expr) -> Expr {
+    Expr::Optional(Box::new(item))
+}
+
+fn star(item: Expr) -> Expr {
+    Expr::ZeroOrMore(Box::new(item))
+}
+
+fn plus(item: Expr) -> Expr {
+    Expr::OneOrMore(Box::new(item))
+}
+
+fn lits(texts: &[&str]) -> Expr {
+    alt(texts.iter().map(|text| lit(text)).collect())
+}
+
+impl Parser {
+    /// The grammar accepted by this parser, including a rule for each of its
+    /// functions.
+    pub fn grammar(&self) -> Grammar {
+        let mut names: Vec<&String> = self.functions.keys().collect();
+        names.sort();
+
+        let function_rule = |name: &String| r(&format!("{name}-function-expr"));
+        let returning = |kind: fn(&ExpressionType) -> bool| -> Vec<Expr> {
+            names
+                .iter()
+                .filter(|name| kind(&self.functions[**name].return_type))
+                .map(|name| function_rule(name))
+                .collect()
+        };
+
+        let value_functions = returning(|t| matches!(t, ExpressionType::Value));
+        let test_functions = returning(|t| !matches!(t, ExpressionType::Value));
+        let nodes_functions = returning(|t| matches!(t, ExpressionType::Nodes));
+
+        let mut rules = Vec::new();
+        let mut rule = |name: &str, expr: Expr| {
+            rules.push(Rule {
+                name: name.to_owned(),
+                expr,
+                comment: None,
+            });
+        };
+
+        rule(
+            "jsonpath-query",
+            seq(vec![r("root-identifier"), r("segments")]),
+        );
+        rule("segments", star(seq(vec![r("S"), r("segment")])));
+        rule(
+            "B",
+            alt(vec![
+                chr('\u{20}'),
+                chr('\u{09}'),
+                chr('\u{0A}'),
+                chr('\u{0D}'),
+            ]),
+        );
+        rule("S", star(r("B")));
+        rule("root-identifier", lit("$"));
+        let singular_query_selector = self
+            .lexer_options
+            .extensions
+            .contains(Extensions::SINGULAR_QUERY_SELECTOR);
+
+        let mut selectors = vec![
+            r("name-selector"),
+            r("wildcard-selector"),
+            r("slice-selector"),
+            r("index-selector"),
+            r("filter-selector"),
+        ];
+        if singular_query_selector {
+            selectors.push(r("singular-query-selector"));
+        }
+        rule("selector", alt(selectors));
+        if singular_query_selector {
+            rule("singular-query-selector", r("abs-singular-query"));
+        }
+        rule("name-selector", r("string-literal"));
+        rule(
+            "string-literal",
+            alt(vec![
+                seq(vec![lit("\""), star(r("double-quoted")), lit("\"")]),
+                seq(vec![lit("'"), star(r("single-quoted")), lit("'")]),
+            ]),
+        );
+        rule(
+            "double-quoted",
+            alt(vec![
+                r("unescaped"),
+                lit("'"),
+                seq(vec![r("ESC"), lit("\"")]),
+                seq(vec![r("ESC"), r("escapable")]),
+            ]),
+        );
+        rule(
+            "single-quoted",
+            alt(vec![
+                r("unescaped"),
+                lit("\""),
+                seq(vec![r("ESC"), lit("'")]),
+                seq(vec![r("ESC"), r("escapable")]),
+            ]),
+        );
+        rule("ESC", lit("\\"));
+        rule(
+            "unescaped",
+            alt(vec![
+                range('\u{20}', '\u{21}'),
+                range('\u{23}', '\u{26}'),
+                range('\u{28}', '\u{5B}'),
+                range('\u{5D}', '\u{D7FF}'),
+                range('\u{E000}', '\u{10FFFF}'),
+            ]),
+        );
+        rule(
+            "escapable",
+            alt(vec![
+                lits(&["b", "f", "n", "r", "t", "/", "\\"]),
+                seq(vec![lit("u"), r("hexchar")]),
+            ]),
+        );
+        rule(
+            "hexchar",
+            alt(vec![
+                r("non-surrogate"),
+                seq(vec![r("high-surrogate"), lit("\\u"), r("low-surrogate")]),
+            ]),
+        );
+        rule(
+            "non-surrogate",
+            alt(vec![
+                seq(vec![
+                    alt(vec![r("DIGIT"), lits(&["A", "B", "C", "E", "F"])]),
+                    r("HEXDIG"),
+                    r("HEXDIG"),
+                    r("HEXDIG"),
+                ]),
+                seq(vec![lit("D"), range('0', '7'), r("HEXDIG"), r("HEXDIG")]),
+            ]),
+        );
+        rule(
+            "high-surrogate",
+            seq(vec![
+                lit("D"),
+                lits(&["8", "9", "A", "B"]),
+                r("HEXDIG"),
+                r("HEXDIG"),
+            ]),
+        );
+        rule(
+            "low-surrogate",
+            seq(vec![
+                lit("D"),
+                lits(&["C", "D", "E", "F"]),
+                r("HEXDIG"),
+                r("HEXDIG"),
+            ]),
+        );
+        rule(
+            "HEXDIG",
+            alt(vec![r("DIGIT"), range('A', 'F'), range('a', 'f')]),
+        );
+        rule("wildcard-selector", lit("*"));
+        rule("index-selector", r("int"));
+        rule(
+            "int",
+            alt(vec![
+                lit("0"),
+                seq(vec![opt(lit("-")), r("DIGIT1"), star(r("DIGIT"))]),
+            ]),
+        );
+        rule("DIGIT1", range('1', '9'));
+        rule("DIGIT", range('0', '9'));
+        rule(
+            "slice-selector",
+            seq(vec![
+                opt(seq(vec![r("start"), r("S")])),
+                lit(":"),
+                r("S"),
+                opt(seq(vec![r("end"), r("S")])),
+                opt(seq(vec![lit(":"), opt(seq(vec![r("S"), r("step")]))])),
+            ]),
+        );
+        rule("start", r("int"));
+        rule("end", r("int"));
+        rule("step", r("int"));
+        rule(
+            "filter-selector",
+            seq(vec![lit("?"), r("S"), r("logical-expr")]),
+        );
+        rule("logical-expr", r("logical-or-expr"));
+        rule(
+            "logical-or-expr",
+            seq(vec![
+                r("logical-and-expr"),
+                star(seq(vec![r("S"), lit("||"), r("S"), r("logical-and-expr")])),
+            ]),
+        );
+        rule(
+            "logical-and-expr",
+            seq(vec![
+                r("basic-expr"),
+                star(seq(vec![r("S"), lit("&&"), r("S"), r("basic-expr")])),
+            ]),
+        );
+        rule(
+            "basic-expr",
+            alt(vec![r("paren-expr"), r("comparison-expr"), r("test-expr")]),
+        );
+        rule(
+            "paren-expr",
+            seq(vec![
+                opt(seq(vec![r("logical-not-op"), r("S")])),
+                lit("("),
+                r("S"),
+                r("logical-expr"),
+                r("S"),
+                lit(")"),
+            ]),
+        );
+        rule("logical-not-op", lit("!"));
+
+        let mut tests = vec![r("filter-query")];
+        tests.extend(test_functions);
+        rule(
+            "test-expr",
+            seq(vec![
+                opt(seq(vec![r("logical-not-op"), r("S")])),
+                alt(tests),
+            ]),
+        );
+        rule(
+            "filter-query",
+            alt(vec![r("rel-query"), r("jsonpath-query")]),
+        );
+        rule(
+            "rel-query",
+            seq(vec![r("current-node-identifier"), r("segments")]),
+        );
+        rule("current-node-identifier", lit("@"));
+        rule(
+            "comparison-expr",
+            seq(vec![
+                r("comparable"),
+                r("S"),
+                r("comparison-op"),
+                r("S"),
+                r("comparable"),
+            ]),
+        );
+        rule(
+            "literal",
+            alt(vec![
+                r("number"),
+                r("string-literal"),
+                r("true"),
+                r("false"),
+                r("null"),
+            ]),
+        );
+
+        let mut comparables = vec![r("literal"), r("singular-query")];
+        comparables.extend(value_functions.iter().cloned());
+        rule("comparable", alt(comparables));
+        rule("comparison-op", lits(&["==", "!=", "<=", ">=", "<", ">"]));
+        rule(
+            "singular-query",
+            alt(vec![r("rel-singular-query"), r("abs-singular-query")]),
+        );
+        rule(
+            "rel-singular-query",
+            seq(vec![
+                r("current-node-identifier"),
+                r("singular-query-segments"),
+            ]),
+        );
+        rule(
+            "abs-singular-query",
+            seq(vec![r("root-identifier"), r("singular-query-segments")]),
+        );
+        rule(
+            "singular-query-segments",
+            star(seq(vec![
+                r("S"),
+                alt(vec![r("name-segment"), r("index-segment")]),
+            ])),
+        );
+        rule(
+            "name-segment",
+            alt(vec![
+                seq(vec![lit("["), r("name-selector"), lit("]")]),
+                seq(vec![lit("."), r("member-name-shorthand")]),
+            ]),
+        );
+        rule(
+            "index-segment",
+            seq(vec![lit("["), r("index-selector"), lit("]")]),
+        );
+        rule(
+            "number",
+            seq(vec![
+                alt(vec![r("int"), lit("-0")]),
+                opt(r("frac")),
+                opt(r("exp")),
+            ]),
+        );
+        rule("frac", seq(vec![lit("."), plus(r("DIGIT"))]));
+        rule(
+            "exp",
+            seq(vec![
+                lits(&["e", "E"]),
+                opt(lits(&["-", "+"])),
+                plus(r("DIGIT")),
+            ]),
+        );
+        rule("true", lit("true"));
+        rule("false", lit("false"));
+        rule("null", lit("null"));
+
+        for name in &names {
+            let signature = &self.functions[*name];
+            let mut items = vec![lit(name), lit("("), r("S")];
+            for (i, param) in signature.param_types.iter().enumerate() {
+                if i > 0 {
+                    items.extend([r("S"), lit(","), r("S")]);
+                }
+                items.push(r(match param {
+                    ExpressionType::Logical => "logical-argument",
+                    ExpressionType::Nodes => "nodes-argument",
+                    ExpressionType::Value => "value-argument",
+                }));
+            }
+            items.extend([r("S"), lit(")")]);
+            rule(&format!("{name}-function-expr"), seq(items));
+        }
+
+        let mut values = vec![r("literal"), r("singular-query")];
+        values.extend(value_functions);
+        rule("value-argument", alt(values));
+        rule("logical-argument", r("logical-expr"));
+        let mut nodes = vec![r("filter-query")];
+        nodes.extend(nodes_functions);
+        rule("nodes-argument", alt(nodes));
+
+        rule(
+            "segment",
+            alt(vec![r("child-segment"), r("descendant-segment")]),
+        );
+        rule(
+            "child-segment",
+            alt(vec![
+                r("bracketed-selection"),
+                seq(vec![
+                    lit("."),
+                    alt(vec![r("wildcard-selector"), r("member-name-shorthand")]),
+                ]),
+            ]),
+        );
+        rule(
+            "bracketed-selection",
+            seq(vec![
+                lit("["),
+                r("S"),
+                r("selector"),
+                star(seq(vec![r("S"), lit(","), r("S"), r("selector")])),
+                r("S"),
+                lit("]"),
+            ]),
+        );
+        rule(
+            "member-name-shorthand",
+            seq(vec![r("name-first"), star(r("name-char"))]),
+        );
+        rule(
+            "name-first",
+            alt(vec![
+                r("ALPHA"),
+                lit("_"),
+                range('\u{80}', '\u{D7FF}'),
+                range('\u{E000}', '\u{10FFFF}'),
+            ]),
+        );
+        rule("name-char", alt(vec![r("name-first"), r("DIGIT")]));
+        rule("ALPHA", alt(vec![range('A', 'Z'), range('a', 'z')]));
+        rule(
+            "descendant-segment",
+            seq(vec![
+                lit(".."),
+                alt(vec![
+                    r("bracketed-selection"),
+                    r("wildcard-selector"),
+                    r("member-name-shorthand"),
+                ]),
+            ]),
+        );
+
+        let mut grammar = Grammar { rules };
+        self.annotate(&mut grammar);
+        grammar
+    }
+
+    /// Describe constraints from this parser's configuration that aren't
+    /// part of the grammar itself.
+    fn annotate(&self, grammar: &mut Grammar) {
+        let mut limits = Vec::new();
+        if self.limits.max_query_length != usize::MAX {
+            limits.push(format!(
+                "at most {} bytes long",
+                self.limits.max_query_length
+            ));
+        }
+        if self.limits.max_tokens != usize::MAX {
+            limits.push(format!("at most {} tokens", self.limits.max_tokens));
+        }
+
+        for rule in &mut grammar.rules {
+            rule.comment = match rule.name.as_str() {
+                "jsonpath-query" if !limits.is_empty() => {
+                    Some(format!("A query is {}.", limits.join(" and ")))
+                }
+                "string-literal" if self.limits.max_string_length != usize::MAX => Some(format!(
+                    "At most {} bytes between quotes.",
+                    self.limits.max_string_length
+                )),
+                "index-selector" | "start" | "end" | "step" => Some(format!(
+                    "An integer from {} to {}.",
+                    self.index_range.start(),
+                    self.index_range.end()
+                )),
+                _ => None,
+            };
+        }
+    }
+}
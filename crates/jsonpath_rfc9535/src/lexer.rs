@@ -59,10 +59,8 @@
 //! ```
 //! use jsonpath_rfc9535::lexer::tokenize;
 //!
-//! fn main() {
-//!     let tokens = tokenize("$foo"); // deliberate error
-//!     println!("{:#?}", tokens);
-//! }
+//! let tokens = tokenize("$foo"); // deliberate error
+//! println!("{:#?}", tokens);
 //! ```
 //!
 //! ```text
@@ -85,9 +83,29 @@
 //!    },
 //! ]
 //! ```
+//!
+//! Tokens don't cover whitespace, the dot before a name or the quotes around
+//! a string. Set [`LexerOptions::trivia`] and [`tokenize_with_options`] fills
+//! those gaps with [`TokenType::Trivia`] tokens, so tools like formatters
+//! and syntax highlighters can reproduce a query exactly from its tokens.
+//!
+//! ```
+//! use jsonpath_rfc9535::lexer::{tokenize_with_options, LexerOptions, Limits};
+//!
+//! let query = "$.a[ 'b' ]";
+//! let options = LexerOptions {
+//!     trivia: true,
+//!     ..LexerOptions::default()
+//! };
+//! let tokens = tokenize_with_options(query, &Limits::default(), &options);
+//! let text: String = tokens.iter().map(|t| &query[t.span.range()]).collect();
+//! assert_eq!(text, query);
+//! ```
 
 use crate::{
     errors::JSONPathError,
+    extensions::Extensions,
+    span::Span,
     token::{Token, TokenType, EOQ},
 };
 
@@ -108,37 +126,148 @@ enum State {
     LexInsideDoubleQuotedFilterString,
 }
 
+impl State {
+    fn in_string(&self) -> bool {
+        matches!(
+            self,
+            State::LexInsideSingleQuotedString
+                | State::LexInsideDoubleQuotedString
+                | State::LexInsideSingleQuotedFilterString
+                | State::LexInsideDoubleQuotedFilterString
+        )
+    }
+}
+
+/// Upper bounds on the size of a query, checked while lexing.
+///
+/// The default is no limits. Services accepting queries from untrusted
+/// sources should set limits appropriate to their use case, so
+/// unreasonably large queries are rejected before they're parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of tokens in a query, including the end of query token.
+    pub max_tokens: usize,
+    /// The maximum length of a query in bytes.
+    pub max_query_length: usize,
+    /// The maximum length of a string literal or quoted name in bytes, before
+    /// escape sequences are decoded.
+    pub max_string_length: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_tokens: usize::MAX,
+            max_query_length: usize::MAX,
+            max_string_length: usize::MAX,
+        }
+    }
+}
+
+/// Lexer behaviour that isn't a limit on query size.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LexerOptions {
+    /// Skip a leading byte order mark (U+FEFF), as some editors and HTTP
+    /// clients add one. A byte order mark is rejected by default, as RFC 9535
+    /// doesn't allow it. Spans are still relative to the start of the query,
+    /// including the byte order mark.
+    pub skip_bom: bool,
+    /// Reject noncharacters in name shorthands, like `$.a\u{FFFF}`.
+    ///
+    /// RFC 9535 allows any code point from U+0080 up, other than
+    /// surrogates, in a name shorthand. That includes noncharacters, which
+    /// Unicode reserves for internal use and which can't be safely
+    /// interchanged, so they're allowed by default. Surrogates can't appear
+    /// in a `&str`. Noncharacters in quoted names are always allowed.
+    pub strict_names: bool,
+    /// Non-standard syntax to accept. See [`Extensions`].
+    pub extensions: Extensions,
+    /// Emit a [`TokenType::Trivia`] token for the text between each pair of
+    /// tokens, so the tokens' spans cover the whole query and tools like
+    /// formatters can reproduce it exactly. Only [`tokenize_with_options`]
+    /// emits trivia. [`lex_with_options`] and parsers ignore this option.
+    pub trivia: bool,
+}
+
 /// A lexer for JSONPath expressions.
 struct Lexer<'q> {
     query: &'q str,
-    tokens: Vec<Token>,
+    tokens: Vec<Token<'q>>,
+    limits: Limits,
+    options: LexerOptions,
+    limit_exceeded: bool,
 
     chars: CharIndices<'q>,
     start: usize,
     pos: usize,
 
-    filter_depth: u32,
+    // Char offsets of `start` and `pos`, only used for non-ASCII queries.
+    ascii: bool,
+    char_start: usize,
+    char_pos: usize,
+
+    // The length of `paren_stack` at the start of each enclosing filter, so
+    // a filter nested in a function argument doesn't see the function's
+    // parentheses as its own.
+    filters: Vec<usize>,
     paren_stack: Vec<u32>,
+
+    // The length of `filters` at the start of each enclosing singular query
+    // selector, so `lex_segment` knows whether to return to a bracketed
+    // selection or a filter once the query ends.
+    selector_queries: Vec<usize>,
 }
 
 impl<'q> Lexer<'q> {
-    fn new(query: &'q str) -> Self {
+    fn new(query: &'q str, limits: Limits, options: LexerOptions) -> Self {
         Self {
             query,
             tokens: Vec::new(),
+            limits,
+            options,
+            limit_exceeded: false,
             start: 0,
             pos: 0,
+            ascii: query.is_ascii(),
+            char_start: 0,
+            char_pos: 0,
             chars: query.char_indices(),
-            filter_depth: 0,
+            filters: Vec::new(),
             paren_stack: Vec::new(),
+            selector_queries: Vec::new(),
         }
     }
 
     fn run(&mut self) {
+        if self.query.len() > self.limits.max_query_length {
+            self.limit_error(
+                format!(
+                    "query is too long ({} bytes, limit {})",
+                    self.query.len(),
+                    self.limits.max_query_length
+                ),
+                Span::new(self.limits.max_query_length, self.query.len()).with_chars(self.query),
+            );
+            return;
+        }
+
+        if self.accept('\u{FEFF}') {
+            if !self.options.skip_bom {
+                self.error(String::from("unexpected byte order mark"));
+                return;
+            }
+            self.ignore();
+        }
+
         let mut state = State::LexRoot;
-        loop {
+        while self.tokens.len() <= self.limits.max_tokens {
             match state {
                 State::Error | State::EndOfQuery => break,
+                // Control characters are only allowed in string literals,
+                // where the parser rejects them unless they're escaped.
+                _ if !state.in_string() && self.peek_control().is_some() => {
+                    state = self.control_error()
+                }
                 State::LexRoot => state = lex_root(self),
                 State::LexSegment => state = lex_segment(self),
                 State::LexDescendantSegment => state = lex_descendant_segment(self),
@@ -159,26 +288,41 @@ impl<'q> Lexer<'q> {
                 }
             }
         }
+
+        if self.tokens.len() > self.limits.max_tokens {
+            let span = self.tokens[self.limits.max_tokens].span;
+            self.tokens.truncate(self.limits.max_tokens);
+            self.limit_error(
+                format!("too many tokens (limit {})", self.limits.max_tokens),
+                span,
+            );
+        }
     }
 
-    fn emit(&mut self, t: TokenType) {
-        self.tokens.push(Token::new(t, self.start, self.pos));
-        self.start = self.pos;
+    fn emit(&mut self, t: TokenType<'q>) {
+        let span = self.span();
+        self.tokens.push(Token { kind: t, span });
+        self.ignore();
+    }
+
+    fn span(&self) -> Span {
+        Span {
+            start: self.start,
+            end: self.pos,
+            chars: (!self.ascii).then_some((self.char_start, self.char_pos)),
+        }
     }
 
-    fn value(&self) -> &str {
+    fn value(&self) -> &'q str {
         self.query
             .get(self.start..self.pos)
             .expect("lexer error: slice out of bounds or not on codepoint boundary")
     }
 
-    fn boxed_value(&self) -> Box<str> {
-        self.value().to_string().into_boxed_str()
-    }
-
     fn next(&mut self) -> Option<char> {
         if let Some((pos, ch)) = self.chars.next() {
             self.pos = pos + ch.len_utf8();
+            self.char_pos += 1;
 
             #[cfg(debug_assertions)]
             debug_assert!(
@@ -194,6 +338,7 @@ impl<'q> Lexer<'q> {
 
     fn ignore(&mut self) {
         self.start = self.pos;
+        self.char_start = self.char_pos;
     }
 
     fn peek(&mut self) -> char {
@@ -204,6 +349,17 @@ impl<'q> Lexer<'q> {
         }
     }
 
+    /// The next character, if it's a control character other than whitespace.
+    /// Unlike [`Lexer::peek`], a NUL byte is distinguished from the end of the
+    /// query.
+    fn peek_control(&self) -> Option<char> {
+        self.chars
+            .clone()
+            .next()
+            .map(|(_, ch)| ch)
+            .filter(|ch| ch.is_ascii_control() && !is_whitespace_char(*ch))
+    }
+
     fn accept(&mut self, ch: char) -> bool {
         if self.peek() == ch {
             self.next();
@@ -246,37 +402,185 @@ impl<'q> Lexer<'q> {
         }
     }
 
+    fn limit_error(&mut self, msg: String, span: Span) {
+        self.limit_exceeded = true;
+        self.tokens.push(Token {
+            kind: TokenType::Error {
+                msg: msg.into_boxed_str(),
+            },
+            span,
+        });
+    }
+
+    fn control_error(&mut self) -> State {
+        self.ignore();
+        let ch = self.next().unwrap_or(EOQ);
+        self.error(format!("unexpected control character U+{:04X}", ch as u32))
+    }
+
     fn error(&mut self, msg: String) -> State {
-        self.tokens.push(Token::new(
-            TokenType::Error {
+        self.tokens.push(Token {
+            kind: TokenType::Error {
                 msg: msg.into_boxed_str(),
             },
-            self.start,
-            self.pos,
-        ));
+            span: self.span(),
+        });
         State::Error
     }
 }
 
-pub fn tokenize(query: &str) -> Vec<Token> {
-    let mut lexer = Lexer::new(query);
+pub fn tokenize(query: &str) -> Vec<Token<'_>> {
+    tokenize_with_limits(query, &Limits::default())
+}
+
+/// Like [`tokenize`], but stops with an error token if `query` exceeds `limits`.
+pub fn tokenize_with_limits<'q>(query: &'q str, limits: &Limits) -> Vec<Token<'q>> {
+    tokenize_with_options(query, limits, &LexerOptions::default())
+}
+
+/// Like [`tokenize_with_limits`], with non-default lexer behaviour.
+pub fn tokenize_with_options<'q>(
+    query: &'q str,
+    limits: &Limits,
+    options: &LexerOptions,
+) -> Vec<Token<'q>> {
+    let mut lexer = Lexer::new(query, *limits, *options);
     lexer.run();
-    lexer.tokens
+    if options.trivia {
+        with_trivia(query, lexer.tokens)
+    } else {
+        lexer.tokens
+    }
+}
+
+/// `tokens` with a [`TokenType::Trivia`] token filling each gap between
+/// them.
+fn with_trivia<'q>(query: &'q str, tokens: Vec<Token<'q>>) -> Vec<Token<'q>> {
+    let ascii = query.is_ascii();
+    let mut rv = Vec::with_capacity(tokens.len() * 2);
+    let mut pos = 0;
+    let mut char_pos = 0;
+
+    for token in tokens {
+        if let Some(value) = query.get(pos..token.span.start).filter(|v| !v.is_empty()) {
+            let char_start = char_pos;
+            char_pos += value.chars().count();
+            rv.push(Token {
+                kind: TokenType::Trivia { value },
+                span: Span {
+                    start: pos,
+                    end: token.span.start,
+                    chars: (!ascii).then_some((char_start, char_pos)),
+                },
+            });
+            pos = token.span.start;
+        }
+
+        if let Some(value) = query.get(pos..token.span.end) {
+            char_pos += value.chars().count();
+            pos = token.span.end;
+        }
+        rv.push(token);
+    }
+
+    rv
+}
+
+pub fn lex(query: &str) -> Result<Vec<Token<'_>>, JSONPathError> {
+    lex_with_limits(query, &Limits::default())
+}
+
+/// Like [`lex`], but fails with a [`JSONPathErrorType::LimitError`] if `query`
+/// exceeds `limits`.
+///
+/// [`JSONPathErrorType::LimitError`]: crate::errors::JSONPathErrorType::LimitError
+pub fn lex_with_limits<'q>(
+    query: &'q str,
+    limits: &Limits,
+) -> Result<Vec<Token<'q>>, JSONPathError> {
+    lex_with_options(query, limits, &LexerOptions::default())
 }
 
-pub fn lex(query: &str) -> Result<Vec<Token>, JSONPathError> {
-    let tokens = tokenize(query);
+/// Like [`lex_with_limits`], with non-default lexer behaviour.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "lex",
+        level = "debug",
+        skip_all,
+        fields(fingerprint = crate::query::fingerprint(query), len = query.len()),
+        err(Display)
+    )
+)]
+pub fn lex_with_options<'q>(
+    query: &'q str,
+    limits: &Limits,
+    options: &LexerOptions,
+) -> Result<Vec<Token<'q>>, JSONPathError> {
+    let mut lexer = Lexer::new(query, *limits, *options);
+    lexer.run();
+    let tokens = lexer.tokens;
 
     match tokens.last() {
         Some(Token {
             kind: TokenType::Error { msg },
             span,
             ..
-        }) => Err(JSONPathError::syntax((*msg).to_string(), *span)),
+        }) => {
+            if lexer.limit_exceeded {
+                Err(JSONPathError::limit((*msg).to_string(), *span))
+            } else {
+                Err(JSONPathError::syntax((*msg).to_string(), *span))
+            }
+        }
         _ => Ok(tokens),
     }
 }
 
+/// Tokenize `query` and format the result as a table with one row per token,
+/// showing each token's kind, span and source text.
+///
+/// Error tokens show their error message in place of source text.
+///
+/// ```
+/// use jsonpath_rfc9535::lexer::dump_tokens;
+///
+/// print!("{}", dump_tokens("$.foo[0]"));
+/// ```
+///
+/// ```text
+/// KIND      SPAN  TEXT
+/// Root      0..1  "$"
+/// Name      2..5  "foo"
+/// LBracket  5..6  "["
+/// Index     6..7  "0"
+/// RBracket  7..8  "]"
+/// Eoq       8..8  ""
+/// ```
+pub fn dump_tokens(query: &str) -> String {
+    let rows: Vec<(&str, String, String)> = tokenize(query)
+        .iter()
+        .map(|token| {
+            let text = match &token.kind {
+                TokenType::Error { msg } => format!("error: {}", msg),
+                _ => format!("{:?}", query.get(token.span.range()).unwrap_or_default()),
+            };
+            (token.kind.name(), token.span.to_string(), text)
+        })
+        .collect();
+
+    let kind_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0).max(4);
+    let span_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0).max(4);
+
+    let mut buf = format!("{:kind_width$}  {:span_width$}  TEXT\n", "KIND", "SPAN");
+
+    for (kind, span, text) in rows {
+        buf.push_str(&format!("{kind:kind_width$}  {span:span_width$}  {text}\n"));
+    }
+
+    buf
+}
+
 fn lex_root(l: &mut Lexer) -> State {
     if l.accept('$') {
         l.emit(TokenType::Root);
@@ -302,7 +606,10 @@ fn lex_segment(l: &mut Lexer) -> State {
     } else if l.accept('[') {
         l.emit(TokenType::LBracket);
         State::LexInsideBracketedSegment
-    } else if l.filter_depth > 0 {
+    } else if l.selector_queries.last() == Some(&l.filters.len()) {
+        l.selector_queries.pop();
+        State::LexInsideBracketedSegment
+    } else if !l.filters.is_empty() {
         State::LexInsideFilter
     } else if l.peek() == EOQ {
         l.next();
@@ -324,12 +631,10 @@ fn lex_descendant_segment(l: &mut Lexer) -> State {
     } else if l.accept('[') {
         l.emit(TokenType::LBracket);
         State::LexInsideBracketedSegment
-    } else if l.accept_if(is_name_first) {
-        l.accept_run(is_name_char);
-        l.emit(TokenType::Name {
-            value: l.boxed_value(),
-        });
-        State::LexSegment
+    } else if is_name_first(l.peek()) {
+        lex_name(l)
+    } else if l.peek() == EOQ {
+        l.error(String::from("unexpected end of query"))
     } else {
         let msg = format!(
             "unexpected descendant selection token '{}'",
@@ -349,12 +654,10 @@ fn lex_shorthand_selector(l: &mut Lexer) -> State {
     if l.accept('*') {
         l.emit(TokenType::Wild);
         State::LexSegment
-    } else if l.accept_if(is_name_first) {
-        l.accept_run(is_name_char);
-        l.emit(TokenType::Name {
-            value: l.boxed_value(),
-        });
-        State::LexSegment
+    } else if is_name_first(l.peek()) {
+        lex_name(l)
+    } else if l.peek() == EOQ {
+        l.error(String::from("unexpected end of query"))
     } else {
         let msg = format!(
             "unexpected shorthand selector '{}'",
@@ -364,6 +667,25 @@ fn lex_shorthand_selector(l: &mut Lexer) -> State {
     }
 }
 
+/// Lex a name shorthand, starting at a character for which `is_name_first`
+/// is true.
+fn lex_name(l: &mut Lexer) -> State {
+    while is_name_char(l.peek()) {
+        if l.options.strict_names && is_noncharacter(l.peek()) {
+            l.ignore();
+            let msg = format!(
+                "noncharacter U+{:04X} is not allowed in a name shorthand",
+                l.next().unwrap_or(EOQ) as u32
+            );
+            return l.error(msg);
+        }
+        l.next();
+    }
+
+    l.emit(TokenType::Name { value: l.value() });
+    State::LexSegment
+}
+
 fn lex_inside_bracketed_segment(l: &mut Lexer) -> State {
     l.ignore_whitespace();
 
@@ -371,11 +693,9 @@ fn lex_inside_bracketed_segment(l: &mut Lexer) -> State {
         ']' => {
             l.next();
             l.emit(TokenType::RBracket);
-            if l.filter_depth > 0 {
-                State::LexInsideFilter
-            } else {
-                State::LexSegment
-            }
+            // More segments can follow, even inside a filter, where
+            // `lex_segment` hands back to `lex_inside_filter`.
+            State::LexSegment
         }
         '*' => {
             l.next();
@@ -385,7 +705,7 @@ fn lex_inside_bracketed_segment(l: &mut Lexer) -> State {
         '?' => {
             l.next();
             l.emit(TokenType::Filter);
-            l.filter_depth += 1;
+            l.filters.push(l.paren_stack.len());
             State::LexInsideFilter
         }
         ',' => {
@@ -398,6 +718,16 @@ fn lex_inside_bracketed_segment(l: &mut Lexer) -> State {
             l.emit(TokenType::Colon);
             State::LexInsideBracketedSegment
         }
+        '$' if l
+            .options
+            .extensions
+            .contains(Extensions::SINGULAR_QUERY_SELECTOR) =>
+        {
+            l.next();
+            l.emit(TokenType::Root);
+            l.selector_queries.push(l.filters.len());
+            State::LexSegment
+        }
         '\'' => {
             l.next();
             State::LexInsideSingleQuotedString
@@ -410,9 +740,7 @@ fn lex_inside_bracketed_segment(l: &mut Lexer) -> State {
             // negative array index or slice
             l.next();
             if l.accept_run(is_digit) {
-                l.emit(TokenType::Index {
-                    value: l.boxed_value(),
-                });
+                l.emit(TokenType::Index { value: l.value() });
                 State::LexInsideBracketedSegment
             } else {
                 let msg = format!(
@@ -425,9 +753,7 @@ fn lex_inside_bracketed_segment(l: &mut Lexer) -> State {
         EOQ => l.error(String::from("unclosed bracketed selection")),
         _ => {
             if l.accept_run(is_digit) {
-                l.emit(TokenType::Index {
-                    value: l.boxed_value(),
-                });
+                l.emit(TokenType::Index { value: l.value() });
                 State::LexInsideBracketedSegment
             } else {
                 let msg = format!(
@@ -446,8 +772,7 @@ fn lex_inside_filter(l: &mut Lexer) -> State {
     match l.peek() {
         EOQ => l.error(String::from("unclosed bracketed selection")),
         ']' => {
-            l.filter_depth -= 1;
-            if l.paren_stack.len() == 1 {
+            if l.paren_stack.len() > l.filters.pop().unwrap_or_default() {
                 l.error(String::from("unbalanced parentheses"))
             } else {
                 State::LexInsideBracketedSegment
@@ -458,10 +783,10 @@ fn lex_inside_filter(l: &mut Lexer) -> State {
             l.emit(TokenType::Comma);
             // If we have unbalanced parens, we are inside a function call and a
             // comma separates arguments. Otherwise a comma separates selectors.
-            if !l.paren_stack.is_empty() {
+            if l.paren_stack.len() > l.filters.last().copied().unwrap_or_default() {
                 State::LexInsideFilter
             } else {
-                l.filter_depth -= 1;
+                l.filters.pop();
                 State::LexInsideBracketedSegment
             }
         }
@@ -580,9 +905,7 @@ fn lex_inside_filter(l: &mut Lexer) -> State {
                         if l.peek() == '(' {
                             // a function call
                             l.paren_stack.push(1);
-                            l.emit(TokenType::Function {
-                                name: l.boxed_value(),
-                            });
+                            l.emit(TokenType::Function { name: l.value() });
                             l.next();
                             l.ignore(); // discard the left paren
                         } else {
@@ -625,12 +948,8 @@ fn lex_string(l: &mut Lexer, quote: char, next_state: State) -> State {
             ch => {
                 if ch == quote {
                     l.emit(match quote {
-                        '\'' => TokenType::SingleQuoteString {
-                            value: l.boxed_value(),
-                        },
-                        '"' => TokenType::DoubleQuoteString {
-                            value: l.boxed_value(),
-                        },
+                        '\'' => TokenType::SingleQuoteString { value: l.value() },
+                        '"' => TokenType::DoubleQuoteString { value: l.value() },
                         _ => panic!("unexpected quote delimiter '{}'", quote),
                     });
                     l.next();
@@ -640,6 +959,15 @@ fn lex_string(l: &mut Lexer, quote: char, next_state: State) -> State {
                 l.next();
             }
         }
+
+        if l.pos - l.start > l.limits.max_string_length {
+            let msg = format!(
+                "string literal is too long (limit {} bytes)",
+                l.limits.max_string_length
+            );
+            l.limit_error(msg, l.span());
+            return State::Error;
+        }
     }
 }
 
@@ -665,9 +993,7 @@ fn lex_number(l: &mut Lexer) -> State {
             }
         }
 
-        l.emit(TokenType::Float {
-            value: l.boxed_value(),
-        });
+        l.emit(TokenType::Float { value: l.value() });
     } else {
         // exponent
         if l.accept('e') {
@@ -676,29 +1002,23 @@ fn lex_number(l: &mut Lexer) -> State {
                 if !l.accept_run(is_digit) {
                     return l.error(String::from("at least one exponent digit is required"));
                 }
-                l.emit(TokenType::Float {
-                    value: l.boxed_value(),
-                });
+                l.emit(TokenType::Float { value: l.value() });
             } else {
                 l.accept('+');
                 if !l.accept_run(is_digit) {
                     return l.error(String::from("at least one exponent digit is required"));
                 }
-                l.emit(TokenType::Int {
-                    value: l.boxed_value(),
-                })
+                l.emit(TokenType::Int { value: l.value() })
             }
         } else {
-            l.emit(TokenType::Int {
-                value: l.boxed_value(),
-            })
+            l.emit(TokenType::Int { value: l.value() })
         }
     }
 
     State::LexInsideFilter
 }
 
-fn is_name_first(ch: char) -> bool {
+pub(crate) fn is_name_first(ch: char) -> bool {
     let code_point = ch as u32;
     // surrogate pair code points are not representable with char
     (0x41..=0x5A).contains(&code_point)
@@ -707,7 +1027,7 @@ fn is_name_first(ch: char) -> bool {
         || code_point >= 0x80
 }
 
-fn is_name_char(ch: char) -> bool {
+pub(crate) fn is_name_char(ch: char) -> bool {
     let code_point = ch as u32;
     // surrogate pair code points are not representable with char
     (0x30..=0x39).contains(&code_point)
@@ -717,12 +1037,25 @@ fn is_name_char(ch: char) -> bool {
         || code_point >= 0x80
 }
 
+/// U+FDD0 to U+FDEF, and the last two code points of each plane, which
+/// Unicode reserves for internal use.
+pub(crate) fn is_noncharacter(ch: char) -> bool {
+    let code_point = ch as u32;
+    (0xFDD0..=0xFDEF).contains(&code_point) || code_point & 0xFFFE == 0xFFFE
+}
+
 fn is_digit(ch: char) -> bool {
     // 0-9
     let code_point = ch as u32;
     (0x30..=0x39).contains(&code_point)
 }
 
+/// `true` if `name` is a function name a query can call.
+pub(crate) fn is_function_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_function_name_first) && chars.all(is_function_name_char)
+}
+
 fn is_function_name_first(ch: char) -> bool {
     // a-z
     let code_point = ch as u32;
@@ -755,20 +1088,8 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
-                Token::new(
-                    TokenType::Name {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    6,
-                    9
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
+                Token::new(TokenType::Name { value: "bar" }, 6, 9),
                 Token::new(TokenType::Eoq, 9, 9),
             ]
         )
@@ -783,22 +1104,10 @@ mod tests {
             vec![
                 Token::new(TokenType::Root, 0, 1),
                 Token::new(TokenType::LBracket, 1, 2),
-                Token::new(
-                    TokenType::SingleQuoteString {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    3,
-                    6
-                ),
+                Token::new(TokenType::SingleQuoteString { value: "foo" }, 3, 6),
                 Token::new(TokenType::RBracket, 7, 8),
                 Token::new(TokenType::LBracket, 8, 9),
-                Token::new(
-                    TokenType::SingleQuoteString {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    10,
-                    13
-                ),
+                Token::new(TokenType::SingleQuoteString { value: "bar" }, 10, 13),
                 Token::new(TokenType::RBracket, 14, 15),
                 Token::new(TokenType::Eoq, 15, 15),
             ]
@@ -813,21 +1122,9 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5,
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5,),
                 Token::new(TokenType::LBracket, 5, 6),
-                Token::new(
-                    TokenType::Index {
-                        value: "1".to_string().into_boxed_str()
-                    },
-                    6,
-                    7
-                ),
+                Token::new(TokenType::Index { value: "1" }, 6, 7),
                 Token::new(TokenType::RBracket, 7, 8),
                 Token::new(TokenType::Eoq, 8, 8),
             ]
@@ -842,21 +1139,9 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
-                Token::new(
-                    TokenType::Index {
-                        value: "-1".to_string().into_boxed_str()
-                    },
-                    6,
-                    8
-                ),
+                Token::new(TokenType::Index { value: "-1" }, 6, 8),
                 Token::new(TokenType::RBracket, 8, 9),
                 Token::new(TokenType::Eoq, 9, 9),
             ]
@@ -871,13 +1156,7 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
                 Token::new(
                     TokenType::Error {
@@ -937,20 +1216,8 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    3,
-                    6
-                ),
-                Token::new(
-                    TokenType::Name {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    7,
-                    10
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 3, 6),
+                Token::new(TokenType::Name { value: "bar" }, 7, 10),
                 Token::new(TokenType::Eoq, 10, 10),
             ]
         )
@@ -985,20 +1252,8 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
-                Token::new(
-                    TokenType::Name {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    7,
-                    10
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
+                Token::new(TokenType::Name { value: "bar" }, 7, 10),
                 Token::new(TokenType::Eoq, 10, 10),
             ]
         )
@@ -1012,13 +1267,7 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::Wild, 6, 7),
                 Token::new(TokenType::Eoq, 7, 7),
             ]
@@ -1034,13 +1283,7 @@ mod tests {
             vec![
                 Token::new(TokenType::Root, 0, 1),
                 Token::new(TokenType::DoubleDot, 1, 3),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    3,
-                    6
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 3, 6),
                 Token::new(TokenType::Eoq, 6, 6),
             ]
         )
@@ -1056,13 +1299,7 @@ mod tests {
                 Token::new(TokenType::Root, 0, 1),
                 Token::new(TokenType::DoubleDot, 1, 3),
                 Token::new(TokenType::LBracket, 3, 4),
-                Token::new(
-                    TokenType::SingleQuoteString {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    5,
-                    8
-                ),
+                Token::new(TokenType::SingleQuoteString { value: "foo" }, 5, 8),
                 Token::new(TokenType::RBracket, 9, 10),
                 Token::new(TokenType::Eoq, 10, 10),
             ]
@@ -1106,6 +1343,70 @@ mod tests {
         )
     }
 
+    #[test]
+    fn recurse_at_end_of_query() {
+        let query = "$.a..";
+        let tokens = tokenize(query);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenType::Root, 0, 1),
+                Token::new(TokenType::Name { value: "a" }, 2, 3),
+                Token::new(TokenType::DoubleDot, 3, 5),
+                Token::new(
+                    TokenType::Error {
+                        msg: "unexpected end of query".to_string().into_boxed_str()
+                    },
+                    5,
+                    5
+                ),
+            ]
+        )
+    }
+
+    #[test]
+    fn control_character_outside_string() {
+        let query = "$.a\u{1}";
+        let tokens = tokenize(query);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenType::Root, 0, 1),
+                Token::new(TokenType::Name { value: "a" }, 2, 3),
+                Token::new(
+                    TokenType::Error {
+                        msg: "unexpected control character U+0001"
+                            .to_string()
+                            .into_boxed_str()
+                    },
+                    3,
+                    4
+                ),
+            ]
+        )
+    }
+
+    #[test]
+    fn control_character_inside_string() {
+        let query = "$['\u{1}']";
+        let tokens = tokenize(query);
+        assert_eq!(
+            tokens[2],
+            Token::new(TokenType::SingleQuoteString { value: "\u{1}" }, 3, 4)
+        )
+    }
+
+    #[test]
+    fn skip_byte_order_mark() {
+        let options = LexerOptions {
+            skip_bom: true,
+            ..LexerOptions::default()
+        };
+        let tokens = tokenize_with_options("\u{FEFF}$", &Limits::default(), &options);
+        assert_eq!(tokens[0].kind, TokenType::Root);
+        assert_eq!(tokens[0].span.start, 3);
+    }
+
     #[test]
     fn erroneous_double_recurse() {
         let query = "$....foo";
@@ -1136,21 +1437,9 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
-                Token::new(
-                    TokenType::DoubleQuoteString {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    7,
-                    10
-                ),
+                Token::new(TokenType::DoubleQuoteString { value: "bar" }, 7, 10),
                 Token::new(TokenType::RBracket, 11, 12),
                 Token::new(TokenType::Eoq, 12, 12),
             ]
@@ -1165,21 +1454,9 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
-                Token::new(
-                    TokenType::SingleQuoteString {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    7,
-                    10
-                ),
+                Token::new(TokenType::SingleQuoteString { value: "bar" }, 7, 10),
                 Token::new(TokenType::RBracket, 11, 12),
                 Token::new(TokenType::Eoq, 12, 12),
             ]
@@ -1194,29 +1471,11 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
-                Token::new(
-                    TokenType::SingleQuoteString {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    7,
-                    10
-                ),
+                Token::new(TokenType::SingleQuoteString { value: "bar" }, 7, 10),
                 Token::new(TokenType::Comma, 11, 12),
-                Token::new(
-                    TokenType::Index {
-                        value: "123".to_string().into_boxed_str()
-                    },
-                    13,
-                    16
-                ),
+                Token::new(TokenType::Index { value: "123" }, 13, 16),
                 Token::new(TokenType::Comma, 16, 17),
                 Token::new(TokenType::Wild, 18, 19),
                 Token::new(TokenType::RBracket, 19, 20),
@@ -1233,29 +1492,11 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
-                Token::new(
-                    TokenType::Index {
-                        value: "1".to_string().into_boxed_str()
-                    },
-                    6,
-                    7
-                ),
+                Token::new(TokenType::Index { value: "1" }, 6, 7),
                 Token::new(TokenType::Colon, 7, 8),
-                Token::new(
-                    TokenType::Index {
-                        value: "3".to_string().into_boxed_str()
-                    },
-                    8,
-                    9
-                ),
+                Token::new(TokenType::Index { value: "3" }, 8, 9),
                 Token::new(TokenType::RBracket, 9, 10),
                 Token::new(TokenType::Eoq, 10, 10),
             ]
@@ -1270,23 +1511,11 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
                 Token::new(TokenType::Filter, 6, 7),
                 Token::new(TokenType::Current, 7, 8),
-                Token::new(
-                    TokenType::Name {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    9,
-                    12
-                ),
+                Token::new(TokenType::Name { value: "bar" }, 9, 12),
                 Token::new(TokenType::RBracket, 12, 13),
                 Token::new(TokenType::Eoq, 13, 13),
             ]
@@ -1301,31 +1530,13 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
                 Token::new(TokenType::Filter, 6, 7),
                 Token::new(TokenType::Current, 7, 8),
-                Token::new(
-                    TokenType::Name {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    9,
-                    12
-                ),
+                Token::new(TokenType::Name { value: "bar" }, 9, 12),
                 Token::new(TokenType::Eq, 13, 15),
-                Token::new(
-                    TokenType::SingleQuoteString {
-                        value: "baz".to_string().into_boxed_str()
-                    },
-                    17,
-                    20
-                ),
+                Token::new(TokenType::SingleQuoteString { value: "baz" }, 17, 20),
                 Token::new(TokenType::RBracket, 21, 22),
                 Token::new(TokenType::Eoq, 22, 22),
             ]
@@ -1340,31 +1551,13 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
                 Token::new(TokenType::Filter, 6, 7),
                 Token::new(TokenType::Current, 7, 8),
-                Token::new(
-                    TokenType::Name {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    9,
-                    12
-                ),
+                Token::new(TokenType::Name { value: "bar" }, 9, 12),
                 Token::new(TokenType::Eq, 13, 15),
-                Token::new(
-                    TokenType::DoubleQuoteString {
-                        value: "baz".to_string().into_boxed_str()
-                    },
-                    17,
-                    20
-                ),
+                Token::new(TokenType::DoubleQuoteString { value: "baz" }, 17, 20),
                 Token::new(TokenType::RBracket, 21, 22),
                 Token::new(TokenType::Eoq, 22, 22),
             ]
@@ -1379,24 +1572,12 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
                 Token::new(TokenType::Filter, 6, 7),
                 Token::new(TokenType::LParen, 7, 8),
                 Token::new(TokenType::Current, 8, 9),
-                Token::new(
-                    TokenType::Name {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    10,
-                    13
-                ),
+                Token::new(TokenType::Name { value: "bar" }, 10, 13),
                 Token::new(TokenType::RParen, 13, 14),
                 Token::new(TokenType::RBracket, 14, 15),
                 Token::new(TokenType::Eoq, 15, 15),
@@ -1412,33 +1593,15 @@ mod tests {
             tokens,
             vec![
                 Token::new(TokenType::Root, 0, 1),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    2,
-                    5
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
                 Token::new(TokenType::LBracket, 5, 6),
                 Token::new(TokenType::Filter, 6, 7),
                 Token::new(TokenType::Current, 7, 8),
-                Token::new(
-                    TokenType::Name {
-                        value: "bar".to_string().into_boxed_str()
-                    },
-                    9,
-                    12
-                ),
+                Token::new(TokenType::Name { value: "bar" }, 9, 12),
                 Token::new(TokenType::Comma, 12, 13),
                 Token::new(TokenType::Filter, 14, 15),
                 Token::new(TokenType::Current, 15, 16),
-                Token::new(
-                    TokenType::Name {
-                        value: "baz".to_string().into_boxed_str()
-                    },
-                    17,
-                    20
-                ),
+                Token::new(TokenType::Name { value: "baz" }, 17, 20),
                 Token::new(TokenType::RBracket, 20, 21),
                 Token::new(TokenType::Eoq, 21, 21),
             ]
@@ -1455,30 +1618,12 @@ mod tests {
                 Token::new(TokenType::Root, 0, 1),
                 Token::new(TokenType::LBracket, 1, 2),
                 Token::new(TokenType::Filter, 2, 3),
-                Token::new(
-                    TokenType::Function {
-                        name: "count".to_string().into_boxed_str()
-                    },
-                    3,
-                    8,
-                ),
+                Token::new(TokenType::Function { name: "count" }, 3, 8,),
                 Token::new(TokenType::Current, 9, 10),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    11,
-                    14
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 11, 14),
                 Token::new(TokenType::RParen, 14, 15),
                 Token::new(TokenType::Gt, 15, 16),
-                Token::new(
-                    TokenType::Int {
-                        value: "2".to_string().into_boxed_str()
-                    },
-                    16,
-                    17
-                ),
+                Token::new(TokenType::Int { value: "2" }, 16, 17),
                 Token::new(TokenType::RBracket, 17, 18),
                 Token::new(TokenType::Eoq, 18, 18),
             ]
@@ -1495,38 +1640,14 @@ mod tests {
                 Token::new(TokenType::Root, 0, 1),
                 Token::new(TokenType::LBracket, 1, 2),
                 Token::new(TokenType::Filter, 2, 3),
-                Token::new(
-                    TokenType::Function {
-                        name: "count".to_string().into_boxed_str()
-                    },
-                    3,
-                    8
-                ),
+                Token::new(TokenType::Function { name: "count" }, 3, 8),
                 Token::new(TokenType::Current, 9, 10),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    11,
-                    14
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 11, 14),
                 Token::new(TokenType::Comma, 14, 15),
-                Token::new(
-                    TokenType::Int {
-                        value: "1".to_string().into_boxed_str()
-                    },
-                    16,
-                    17
-                ),
+                Token::new(TokenType::Int { value: "1" }, 16, 17),
                 Token::new(TokenType::RParen, 17, 18),
                 Token::new(TokenType::Gt, 18, 19),
-                Token::new(
-                    TokenType::Int {
-                        value: "2".to_string().into_boxed_str()
-                    },
-                    19,
-                    20
-                ),
+                Token::new(TokenType::Int { value: "2" }, 19, 20),
                 Token::new(TokenType::RBracket, 20, 21),
                 Token::new(TokenType::Eoq, 21, 21),
             ]
@@ -1544,30 +1665,12 @@ mod tests {
                 Token::new(TokenType::LBracket, 1, 2),
                 Token::new(TokenType::Filter, 2, 3),
                 Token::new(TokenType::LParen, 3, 4),
-                Token::new(
-                    TokenType::Function {
-                        name: "count".to_string().into_boxed_str()
-                    },
-                    4,
-                    9
-                ),
+                Token::new(TokenType::Function { name: "count" }, 4, 9),
                 Token::new(TokenType::Current, 10, 11),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    12,
-                    15
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 12, 15),
                 Token::new(TokenType::RParen, 15, 16),
                 Token::new(TokenType::Gt, 16, 17),
-                Token::new(
-                    TokenType::Int {
-                        value: "2".to_string().into_boxed_str()
-                    },
-                    17,
-                    18
-                ),
+                Token::new(TokenType::Int { value: "2" }, 17, 18),
                 Token::new(TokenType::RParen, 18, 19),
                 Token::new(TokenType::RBracket, 19, 20),
                 Token::new(TokenType::Eoq, 20, 20),
@@ -1586,40 +1689,16 @@ mod tests {
                 Token::new(TokenType::LBracket, 1, 2),
                 Token::new(TokenType::Filter, 2, 3),
                 Token::new(TokenType::LParen, 3, 4),
-                Token::new(
-                    TokenType::Function {
-                        name: "count".to_string().into_boxed_str()
-                    },
-                    4,
-                    9
-                ),
+                Token::new(TokenType::Function { name: "count" }, 4, 9),
                 Token::new(TokenType::LParen, 10, 11),
                 Token::new(TokenType::Current, 11, 12),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    13,
-                    16
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 13, 16),
                 Token::new(TokenType::RParen, 16, 17),
                 Token::new(TokenType::Comma, 17, 18),
-                Token::new(
-                    TokenType::Int {
-                        value: "1".to_string().into_boxed_str()
-                    },
-                    18,
-                    19
-                ),
+                Token::new(TokenType::Int { value: "1" }, 18, 19),
                 Token::new(TokenType::RParen, 19, 20),
                 Token::new(TokenType::Gt, 20, 21),
-                Token::new(
-                    TokenType::Int {
-                        value: "2".to_string().into_boxed_str()
-                    },
-                    21,
-                    22
-                ),
+                Token::new(TokenType::Int { value: "2" }, 21, 22),
                 Token::new(TokenType::RParen, 22, 23),
                 Token::new(TokenType::RBracket, 23, 24),
                 Token::new(TokenType::Eoq, 24, 24),
@@ -1642,13 +1721,7 @@ mod tests {
                 Token::new(TokenType::Filter, 5, 6),
                 Token::new(TokenType::Current, 6, 7),
                 Token::new(TokenType::Gt, 7, 8),
-                Token::new(
-                    TokenType::Int {
-                        value: "1".to_string().into_boxed_str()
-                    },
-                    8,
-                    9
-                ),
+                Token::new(TokenType::Int { value: "1" }, 8, 9),
                 Token::new(TokenType::RBracket, 9, 10),
                 Token::new(TokenType::RBracket, 10, 11),
                 Token::new(TokenType::Eoq, 11, 11),
@@ -1671,22 +1744,10 @@ mod tests {
                 Token::new(TokenType::Filter, 5, 6),
                 Token::new(TokenType::Current, 6, 7),
                 Token::new(TokenType::LBracket, 7, 8),
-                Token::new(
-                    TokenType::Index {
-                        value: "1".to_string().into_boxed_str()
-                    },
-                    8,
-                    9
-                ),
+                Token::new(TokenType::Index { value: "1" }, 8, 9),
                 Token::new(TokenType::RBracket, 9, 10),
                 Token::new(TokenType::Gt, 10, 11),
-                Token::new(
-                    TokenType::Int {
-                        value: "1".to_string().into_boxed_str()
-                    },
-                    11,
-                    12
-                ),
+                Token::new(TokenType::Int { value: "1" }, 11, 12),
                 Token::new(TokenType::RBracket, 12, 13),
                 Token::new(TokenType::RBracket, 13, 14),
                 Token::new(TokenType::Eoq, 14, 14),
@@ -1694,6 +1755,66 @@ mod tests {
         )
     }
 
+    #[test]
+    fn filter_consecutive_bracketed_segments() {
+        let query = "$[?@['a'][0]]";
+        let tokens = tokenize(query);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenType::Root, 0, 1),
+                Token::new(TokenType::LBracket, 1, 2),
+                Token::new(TokenType::Filter, 2, 3),
+                Token::new(TokenType::Current, 3, 4),
+                Token::new(TokenType::LBracket, 4, 5),
+                Token::new(TokenType::SingleQuoteString { value: "a" }, 6, 7),
+                Token::new(TokenType::RBracket, 8, 9),
+                Token::new(TokenType::LBracket, 9, 10),
+                Token::new(TokenType::Index { value: "0" }, 10, 11),
+                Token::new(TokenType::RBracket, 11, 12),
+                Token::new(TokenType::RBracket, 12, 13),
+                Token::new(TokenType::Eoq, 13, 13),
+            ]
+        )
+    }
+
+    #[test]
+    fn filter_in_function_argument() {
+        let query = "$[?count(@[?@]) > 1]";
+        let tokens = tokenize(query);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenType::Root, 0, 1),
+                Token::new(TokenType::LBracket, 1, 2),
+                Token::new(TokenType::Filter, 2, 3),
+                Token::new(TokenType::Function { name: "count" }, 3, 8),
+                Token::new(TokenType::Current, 9, 10),
+                Token::new(TokenType::LBracket, 10, 11),
+                Token::new(TokenType::Filter, 11, 12),
+                Token::new(TokenType::Current, 12, 13),
+                Token::new(TokenType::RBracket, 13, 14),
+                Token::new(TokenType::RParen, 14, 15),
+                Token::new(TokenType::Gt, 16, 17),
+                Token::new(TokenType::Int { value: "1" }, 18, 19),
+                Token::new(TokenType::RBracket, 19, 20),
+                Token::new(TokenType::Eoq, 20, 20),
+            ]
+        )
+    }
+
+    #[test]
+    fn unclosed_function_call_in_nested_filter() {
+        let tokens = tokenize("$[?count(@[?length(@]) > 1]");
+        assert!(matches!(
+            tokens.last(),
+            Some(Token {
+                kind: TokenType::Error { msg },
+                ..
+            }) if &**msg == "unbalanced parentheses"
+        ));
+    }
+
     #[test]
     fn function() {
         let query = "$[?foo()]";
@@ -1704,13 +1825,7 @@ mod tests {
                 Token::new(TokenType::Root, 0, 1),
                 Token::new(TokenType::LBracket, 1, 2),
                 Token::new(TokenType::Filter, 2, 3),
-                Token::new(
-                    TokenType::Function {
-                        name: "foo".to_string().into_boxed_str()
-                    },
-                    3,
-                    6
-                ),
+                Token::new(TokenType::Function { name: "foo" }, 3, 6),
                 Token::new(TokenType::RParen, 7, 8),
                 Token::new(TokenType::RBracket, 8, 9),
                 Token::new(TokenType::Eoq, 9, 9),
@@ -1728,20 +1843,8 @@ mod tests {
                 Token::new(TokenType::Root, 0, 1),
                 Token::new(TokenType::LBracket, 1, 2),
                 Token::new(TokenType::Filter, 2, 3),
-                Token::new(
-                    TokenType::Function {
-                        name: "foo".to_string().into_boxed_str()
-                    },
-                    3,
-                    6
-                ),
-                Token::new(
-                    TokenType::Int {
-                        value: "42".to_string().into_boxed_str()
-                    },
-                    7,
-                    9
-                ),
+                Token::new(TokenType::Function { name: "foo" }, 3, 6),
+                Token::new(TokenType::Int { value: "42" }, 7, 9),
                 Token::new(TokenType::RParen, 9, 10),
                 Token::new(TokenType::RBracket, 10, 11),
                 Token::new(TokenType::Eoq, 11, 11),
@@ -1759,28 +1862,10 @@ mod tests {
                 Token::new(TokenType::Root, 0, 1),
                 Token::new(TokenType::LBracket, 1, 2),
                 Token::new(TokenType::Filter, 2, 3),
-                Token::new(
-                    TokenType::Function {
-                        name: "foo".to_string().into_boxed_str()
-                    },
-                    3,
-                    6
-                ),
-                Token::new(
-                    TokenType::Int {
-                        value: "42".to_string().into_boxed_str()
-                    },
-                    7,
-                    9
-                ),
+                Token::new(TokenType::Function { name: "foo" }, 3, 6),
+                Token::new(TokenType::Int { value: "42" }, 7, 9),
                 Token::new(TokenType::Comma, 9, 10),
-                Token::new(
-                    TokenType::Int {
-                        value: "-7".to_string().into_boxed_str()
-                    },
-                    11,
-                    13
-                ),
+                Token::new(TokenType::Int { value: "-7" }, 11, 13),
                 Token::new(TokenType::RParen, 13, 14),
                 Token::new(TokenType::RBracket, 14, 15),
                 Token::new(TokenType::Eoq, 15, 15),
@@ -1837,21 +1922,9 @@ mod tests {
                 Token::new(TokenType::LBracket, 1, 2),
                 Token::new(TokenType::Filter, 2, 3),
                 Token::new(TokenType::Current, 3, 4),
-                Token::new(
-                    TokenType::Name {
-                        value: "foo".to_string().into_boxed_str()
-                    },
-                    5,
-                    8
-                ),
+                Token::new(TokenType::Name { value: "foo" }, 5, 8),
                 Token::new(TokenType::Gt, 9, 10),
-                Token::new(
-                    TokenType::Float {
-                        value: "42.7".to_string().into_boxed_str()
-                    },
-                    11,
-                    15
-                ),
+                Token::new(TokenType::Float { value: "42.7" }, 11, 15),
                 Token::new(TokenType::RBracket, 15, 16),
                 Token::new(TokenType::Eoq, 16, 16),
             ]
@@ -1878,4 +1951,205 @@ mod tests {
             ]
         )
     }
+
+    #[test]
+    fn query_too_long() {
+        let limits = Limits {
+            max_query_length: 5,
+            ..Limits::default()
+        };
+        let tokens = tokenize_with_limits("$.foo.bar", &limits);
+        assert_eq!(
+            tokens,
+            vec![Token::new(
+                TokenType::Error {
+                    msg: "query is too long (9 bytes, limit 5)"
+                        .to_string()
+                        .into_boxed_str()
+                },
+                5,
+                9
+            )]
+        )
+    }
+
+    #[test]
+    fn too_many_tokens() {
+        let limits = Limits {
+            max_tokens: 2,
+            ..Limits::default()
+        };
+        let tokens = tokenize_with_limits("$.foo.bar", &limits);
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(TokenType::Root, 0, 1),
+                Token::new(TokenType::Name { value: "foo" }, 2, 5),
+                Token::new(
+                    TokenType::Error {
+                        msg: "too many tokens (limit 2)".to_string().into_boxed_str()
+                    },
+                    6,
+                    9
+                ),
+            ]
+        )
+    }
+
+    #[test]
+    fn end_of_query_counts_towards_token_limit() {
+        let limits = Limits {
+            max_tokens: 2,
+            ..Limits::default()
+        };
+        assert!(lex_with_limits("$.foo", &limits).is_err());
+
+        let limits = Limits {
+            max_tokens: 3,
+            ..Limits::default()
+        };
+        assert_eq!(lex_with_limits("$.foo", &limits).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn dump() {
+        assert_eq!(
+            dump_tokens("$.foo[?@ <= 'a b']"),
+            concat!(
+                "KIND               SPAN    TEXT\n",
+                "Root               0..1    \"$\"\n",
+                "Name               2..5    \"foo\"\n",
+                "LBracket           5..6    \"[\"\n",
+                "Filter             6..7    \"?\"\n",
+                "Current            7..8    \"@\"\n",
+                "Le                 9..11   \"<=\"\n",
+                "SingleQuoteString  13..16  \"a b\"\n",
+                "RBracket           17..18  \"]\"\n",
+                "Eoq                18..18  \"\"\n",
+            )
+        )
+    }
+
+    #[test]
+    fn dump_error() {
+        assert_eq!(
+            dump_tokens("$foo"),
+            concat!(
+                "KIND   SPAN  TEXT\n",
+                "Root   0..1  \"$\"\n",
+                "Error  1..2  error: expected '.', '..' or a bracketed selection, found 'f'\n",
+            )
+        )
+    }
+
+    #[test]
+    fn display_token() {
+        let tokens = tokenize("$[1:2]");
+        assert_eq!(tokens[3].to_string(), "`:` (3..4)");
+    }
+
+    #[test]
+    fn ascii_spans_have_no_char_offsets() {
+        let tokens = tokenize("$.foo");
+        assert!(tokens.iter().all(|t| t.span.chars.is_none()));
+    }
+
+    #[test]
+    fn non_ascii_char_offsets() {
+        let query = "$['ü'].é";
+        let tokens = tokenize(query);
+        let spans: Vec<_> = tokens.iter().map(|t| t.span).collect();
+        assert_eq!(
+            spans,
+            vec![
+                Span {
+                    start: 0,
+                    end: 1,
+                    chars: Some((0, 1))
+                },
+                Span {
+                    start: 1,
+                    end: 2,
+                    chars: Some((1, 2))
+                },
+                Span {
+                    start: 3,
+                    end: 5,
+                    chars: Some((3, 4))
+                },
+                Span {
+                    start: 6,
+                    end: 7,
+                    chars: Some((5, 6))
+                },
+                Span {
+                    start: 8,
+                    end: 10,
+                    chars: Some((7, 8))
+                },
+                Span {
+                    start: 10,
+                    end: 10,
+                    chars: Some((8, 8))
+                },
+            ]
+        );
+        assert!(spans
+            .iter()
+            .all(|s| *s == Span::new(s.start, s.end).with_chars(query)));
+    }
+
+    fn trivia(query: &str) -> Vec<Token<'_>> {
+        let options = LexerOptions {
+            trivia: true,
+            skip_bom: true,
+            ..LexerOptions::default()
+        };
+        tokenize_with_options(query, &Limits::default(), &options)
+    }
+
+    #[test]
+    fn trivia_tokens() {
+        let tokens = trivia("$.a[ 'b' ]");
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| (t.kind.name(), t.span.start, t.span.end))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Root", 0, 1),
+                ("Trivia", 1, 2),
+                ("Name", 2, 3),
+                ("LBracket", 3, 4),
+                ("Trivia", 4, 6),
+                ("SingleQuoteString", 6, 7),
+                ("Trivia", 7, 9),
+                ("RBracket", 9, 10),
+                ("Eoq", 10, 10),
+            ]
+        );
+        assert_eq!(tokens[4].kind, TokenType::Trivia { value: " '" });
+    }
+
+    #[test]
+    fn trivia_reproduces_the_query() {
+        for query in [
+            "$.foo.bar",
+            "$..[ 0 , 'a' , \"b\" ]",
+            "\u{FEFF}$[?@.a == 'x'\t&& length(@.b) > 1]",
+            "$['ü'] [?@.ä == \"ö\"]",
+            "$ .a  [0]",
+        ] {
+            let tokens = trivia(query);
+            let text: String = tokens.iter().map(|t| &query[t.span.range()]).collect();
+            assert_eq!(text, query);
+            assert!(tokens
+                .iter()
+                .all(|t| t.span == Span::new(t.span.start, t.span.end).with_chars(query)));
+        }
+
+        assert!(tokenize("$ .a")
+            .iter()
+            .all(|t| !matches!(t.kind, TokenType::Trivia { .. })));
+    }
 }
@@ -22,36 +22,25 @@
 //! Query {
 //!     segments: [
 //!         Recursive {
-//!             span: (
-//!                 1,
-//!                 3,
-//!             ),
+//!             span: 1..3,
 //!             selectors: [
 //!                 Name {
-//!                     span: (
-//!                         3,
-//!                         6,
-//!                     ),
+//!                     span: 3..6,
 //!                     name: "foo",
 //!                 },
 //!             ],
 //!         },
 //!         Child {
-//!             span: (
-//!                 6,
-//!                 7,
-//!             ),
+//!             span: 6..7,
 //!             selectors: [
 //!                 Index {
-//!                     span: (
-//!                         7,
-//!                         8,
-//!                     ),
+//!                     span: 7..8,
 //!                     index: 0,
 //!                 },
 //!             ],
 //!         },
 //!     ],
+//!     sources: [],
 //! }
 //! ```
 //!
@@ -88,20 +77,43 @@
 //! `kind` set to [`JSONPathErrorType::NameError`].
 //!
 //! ```text
-//! Error: JSONPathError { kind: NameError, msg: "unknown function `foo`", span: (8, 11) }
+//! Error: JSONPathError { kind: NameError, msg: "unknown function `foo`", span: 8..11, source: None }
 //! ```
 //!
 //! [function extensions]: https://datatracker.ietf.org/doc/html/rfc9535#name-function-extensions
+
+#![forbid(unsafe_code)]
+
+#[cfg(feature = "binary")]
+pub mod binary;
+pub mod csv;
+pub mod diff;
 pub mod errors;
+pub mod events;
+pub mod extensions;
+pub mod format;
+pub mod function;
+pub mod grammar;
 pub mod lexer;
+pub mod lint;
+pub mod number;
+pub mod optimize;
+#[cfg(feature = "options")]
+pub mod options;
 pub mod parser;
 pub mod query;
+pub mod span;
+pub mod standard;
 pub mod token;
 
 pub use errors::JSONPathError;
 pub use errors::JSONPathErrorType;
+pub use extensions::Extensions;
 pub use parser::standard_functions;
+pub use parser::EscapeMode;
 pub use parser::ExpressionType;
 pub use parser::FunctionSignature;
 pub use parser::Parser;
+pub use query::resolve_index;
 pub use query::Query;
+pub use span::Span;
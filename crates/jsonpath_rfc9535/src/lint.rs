@@ -0,0 +1,267 @@
+//! Checks for stored queries, beyond what it takes to parse them.
+//!
+//! A [`Linter`] reports syntax, name and type errors from its [`Parser`], as
+//! well as warnings about queries that are likely to be expensive to
+//! evaluate, or that contain parts with no effect.
+//!
+//! ```
+//! use jsonpath_rfc9535::lint::Linter;
+//!
+//! let diagnostics = Linter::new().lint("$.a['b', 'b']");
+//! assert_eq!(diagnostics[0].rule, "duplicate-selector");
+//! ```
+
+use std::{cmp, collections::HashSet, fmt};
+
+use crate::{
+    errors::JSONPathErrorType,
+    parser::Parser,
+    query::{FilterExpression, Query, Segment, Selector},
+    span::Span,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => f.write_str("error"),
+            Severity::Warning => f.write_str("warning"),
+        }
+    }
+}
+
+/// A problem found in a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// A short, stable name for the check that found the problem, like
+    /// `syntax` or `duplicate-selector`.
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub msg: String,
+    pub span: Span,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {} ({})",
+            self.severity, self.rule, self.msg, self.span
+        )
+    }
+}
+
+pub struct Linter {
+    pub parser: Parser,
+    /// Warn about queries with filter selectors and function calls nested
+    /// more deeply than this.
+    pub max_nesting_depth: usize,
+    /// Warn about queries with more descendant segments than this, including
+    /// descendant segments in filters.
+    pub max_descendant_segments: usize,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    pub fn new() -> Self {
+        Self {
+            parser: Parser::new(),
+            max_nesting_depth: 3,
+            max_descendant_segments: 2,
+        }
+    }
+
+    /// Check `query`, returning diagnostics in the order they appear in the
+    /// query. A query that fails to parse has exactly one diagnostic.
+    pub fn lint(&self, query: &str) -> Vec<Diagnostic> {
+        let parsed = match self.parser.parse(query) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                let rule = match err.kind {
+                    JSONPathErrorType::LexerError | JSONPathErrorType::SyntaxError => "syntax",
+                    JSONPathErrorType::TypeError => "type",
+                    JSONPathErrorType::NameError => "name",
+                    JSONPathErrorType::LimitError | JSONPathErrorType::DepthError => "limit",
+                    JSONPathErrorType::EncodingError => "encoding",
+                    JSONPathErrorType::RuntimeError => "runtime",
+                    JSONPathErrorType::DocumentError => "document",
+                };
+                return vec![Diagnostic {
+                    rule,
+                    severity: Severity::Error,
+                    msg: err.msg,
+                    span: err.span.with_chars(query),
+                }];
+            }
+        };
+
+        let mut diagnostics = Vec::new();
+        let depth = nesting_depth(&parsed);
+        if depth > self.max_nesting_depth {
+            diagnostics.push(Diagnostic {
+                rule: "complexity",
+                severity: Severity::Warning,
+                msg: format!(
+                    "filters and function calls are nested {} deep (limit {})",
+                    depth, self.max_nesting_depth
+                ),
+                span: Span::new(0, query.len()).with_chars(query),
+            });
+        }
+
+        let descendants = descendant_segments(&parsed);
+        if descendants > self.max_descendant_segments {
+            diagnostics.push(Diagnostic {
+                rule: "complexity",
+                severity: Severity::Warning,
+                msg: format!(
+                    "{} descendant segments (limit {})",
+                    descendants, self.max_descendant_segments
+                ),
+                span: Span::new(0, query.len()).with_chars(query),
+            });
+        }
+
+        redundancies(&parsed, &mut diagnostics);
+        diagnostics.sort_by_key(|d| d.span.start);
+        diagnostics
+            .into_iter()
+            .map(|d| Diagnostic {
+                span: d.span.with_chars(query),
+                ..d
+            })
+            .collect()
+    }
+}
+
+fn selectors<'a, 'q>(segment: &'a Segment<'q>) -> &'a [Selector<'q>] {
+    let (Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) = segment;
+    selectors
+}
+
+fn filters<'a, 'q>(query: &'a Query<'q>) -> impl Iterator<Item = &'a FilterExpression<'q>> {
+    query
+        .segments
+        .iter()
+        .flat_map(selectors)
+        .filter_map(|selector| match selector {
+            Selector::Filter { expression, .. } => Some(expression.as_ref()),
+            _ => None,
+        })
+}
+
+/// Queries embedded in `expr`, not counting queries in nested filters.
+fn subqueries<'a, 'q>(expr: &'a FilterExpression<'q>, queries: &mut Vec<&'a Query<'q>>) {
+    match expr {
+        FilterExpression::Not { expression, .. } => subqueries(expression, queries),
+        FilterExpression::Logical { left, right, .. }
+        | FilterExpression::Comparison { left, right, .. } => {
+            subqueries(left, queries);
+            subqueries(right, queries);
+        }
+        FilterExpression::RelativeQuery { query, .. }
+        | FilterExpression::RootQuery { query, .. } => queries.push(query),
+        FilterExpression::Function { args, .. } => {
+            args.iter().for_each(|arg| subqueries(arg, queries));
+        }
+        _ => (),
+    }
+}
+
+fn nesting_depth(query: &Query) -> usize {
+    filters(query)
+        .map(|expr| expression_depth(expr) + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+fn expression_depth(expr: &FilterExpression) -> usize {
+    match expr {
+        FilterExpression::Not { expression, .. } => expression_depth(expression),
+        FilterExpression::Logical { left, right, .. }
+        | FilterExpression::Comparison { left, right, .. } => {
+            cmp::max(expression_depth(left), expression_depth(right))
+        }
+        FilterExpression::RelativeQuery { query, .. }
+        | FilterExpression::RootQuery { query, .. } => nesting_depth(query),
+        FilterExpression::Function { args, .. } => {
+            args.iter().map(expression_depth).max().unwrap_or(0) + 1
+        }
+        _ => 0,
+    }
+}
+
+fn descendant_segments(query: &Query) -> usize {
+    let own = query
+        .segments
+        .iter()
+        .filter(|segment| matches!(segment, Segment::Recursive { .. }))
+        .count();
+
+    let mut queries = Vec::new();
+    filters(query).for_each(|expr| subqueries(expr, &mut queries));
+    own + queries.into_iter().map(descendant_segments).sum::<usize>()
+}
+
+fn redundancies(query: &Query, diagnostics: &mut Vec<Diagnostic>) {
+    for segment in &query.segments {
+        let mut seen = HashSet::new();
+        for selector in selectors(segment) {
+            match selector {
+                Selector::Name { span, .. } | Selector::Index { span, .. } => {
+                    if !seen.insert(selector.to_string()) {
+                        diagnostics.push(Diagnostic {
+                            rule: "duplicate-selector",
+                            severity: Severity::Warning,
+                            msg: format!(
+                                "selector {} appears more than once in the same segment",
+                                selector
+                            ),
+                            span: *span,
+                        });
+                    }
+                }
+                Selector::Filter { expression, .. } => {
+                    constant_comparisons(expression, diagnostics);
+                    let mut queries = Vec::new();
+                    subqueries(expression, &mut queries);
+                    for query in queries {
+                        redundancies(query, diagnostics);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+fn constant_comparisons(expr: &FilterExpression, diagnostics: &mut Vec<Diagnostic>) {
+    match expr {
+        FilterExpression::Not { expression, .. } => constant_comparisons(expression, diagnostics),
+        FilterExpression::Logical { left, right, .. } => {
+            constant_comparisons(left, diagnostics);
+            constant_comparisons(right, diagnostics);
+        }
+        FilterExpression::Comparison { left, right, .. }
+            if left.is_literal() && right.is_literal() =>
+        {
+            diagnostics.push(Diagnostic {
+                rule: "constant-comparison",
+                severity: Severity::Warning,
+                msg: format!("comparison `{}` has the same result for every node", expr),
+                span: left.span().to(right.span()),
+            })
+        }
+        _ => (),
+    }
+}
@@ -1,3 +1,5 @@
+#![forbid(unsafe_code)]
+
 use jsonpath_rfc9535::{errors::JSONPathError, Parser};
 
 fn main() -> Result<(), JSONPathError> {
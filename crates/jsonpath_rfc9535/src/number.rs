@@ -0,0 +1,61 @@
+//! Exact comparison of numbers of mixed types.
+//!
+//! Converting an integer to an `f64` rounds it once it's above 2<sup>53</sup>,
+//! so `9007199254740993 == 9007199254740992.0` would be true. Evaluators
+//! compare numbers with [`compare`] instead, which compares integers with
+//! floats without converting either.
+//!
+//! ```
+//! use std::cmp::Ordering;
+//!
+//! use jsonpath_rfc9535::number::{compare, Number};
+//!
+//! let int = Number::Int(9007199254740993);
+//! let float = Number::Float(9007199254740992.0);
+//! assert_eq!(compare(int, float), Some(Ordering::Greater));
+//! assert_eq!(compare(Number::Int(u64::MAX.into()), Number::Float(f64::NAN)), None);
+//! ```
+
+use std::cmp::Ordering;
+
+/// A JSON number. `Int` is wide enough for any `i64` or `u64`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+/// How `left` orders relative to `right`, or `None` if either is NaN.
+pub fn compare(left: Number, right: Number) -> Option<Ordering> {
+    match (left, right) {
+        (Number::Int(l), Number::Int(r)) => Some(l.cmp(&r)),
+        (Number::Float(l), Number::Float(r)) => l.partial_cmp(&r),
+        (Number::Int(l), Number::Float(r)) => compare_int_float(l, r),
+        (Number::Float(l), Number::Int(r)) => compare_int_float(r, l).map(Ordering::reverse),
+    }
+}
+
+fn compare_int_float(int: i128, float: f64) -> Option<Ordering> {
+    // 2^127, the first float too big for an i128.
+    const LIMIT: f64 = 170141183460469231731687303715884105728.0;
+
+    if float.is_nan() {
+        return None;
+    }
+
+    if float >= LIMIT {
+        return Some(Ordering::Less);
+    }
+
+    if float < -LIMIT {
+        return Some(Ordering::Greater);
+    }
+
+    // `float` is in range, so its integer part converts exactly, and any
+    // fractional part breaks a tie.
+    let whole = float.trunc();
+    Some(
+        int.cmp(&(whole as i128))
+            .then_with(|| 0.0.partial_cmp(&(float - whole)).unwrap()),
+    )
+}
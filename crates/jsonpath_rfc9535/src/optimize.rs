@@ -0,0 +1,401 @@
+//! Rewriting queries into equivalent queries that are cheaper to evaluate.
+//!
+//! [`optimize`] folds parts of filter expressions that have the same value
+//! for every node, then simplifies selectors and segments accordingly.
+//!
+//! - Comparisons between literals, like `1 == 1`, are replaced with their
+//!   result, and logical operators with a constant operand are simplified.
+//! - A filter that is always true selects every child, so it becomes a
+//!   wildcard selector. `$[?1 == 1]` becomes `$[*]`.
+//! - A filter that is always false selects nothing, so it is removed from its
+//!   segment, as are slices that select nothing from any array, like `3:1`.
+//! - A slice that selects at most one element, like `5:6` or `-3:-2`, becomes
+//!   an index selector, so the element is looked up directly.
+//! - A query with a segment that can't select anything, like a segment whose
+//!   filters are all false, selects nothing from any document. It is replaced
+//!   with `$[0:0:1]`, an empty slice, so no other segments are evaluated, and
+//!   the same query embedded in a filter is false when used as a test.
+//!
+//! Optimized queries are still RFC 9535 queries, so an evaluator that parses
+//! query text can use an optimized query's standard string, from
+//! [`Query::try_to_standard_string`].
+//!
+//! ```
+//! use jsonpath_rfc9535::Query;
+//!
+//! let q = Query::standard("$.a[?@.b && 'x' == 'x'][?!(1 < 2)].c").unwrap();
+//! assert_eq!(q.optimized().to_string(), "$[0:0:1]");
+//!
+//! let q = Query::standard("$.a[?@.b || 1 > 2, ?1 <= 1.0]").unwrap();
+//! assert_eq!(q.optimized().to_string(), "$['a'][?@['b'], *]");
+//! ```
+//!
+//! Rewrites that change the structure of a query are not attempted, because
+//! this syntax tree can't represent a better query. Adjacent name segments,
+//! like `$.a.b`, are evaluated one at a time, and `$..*.a` is not the same as
+//! `$..a`, which also selects a member of the root value named `a`. Similarly,
+//! `$[0:100][5]` selects the sixth element of each of the first 100 elements,
+//! not the sixth element of the array, so consecutive slice and index
+//! segments are not fused.
+
+use std::cmp::Ordering;
+
+use crate::{
+    number::{self, Number},
+    query::{
+        ComparisonOperator, FilterExpression, LogicalOperator, Query, Segment, Selector, Source,
+    },
+    span::Span,
+};
+
+impl<'q> Query<'q> {
+    /// An equivalent query that is cheaper to evaluate. See [`optimize`].
+    pub fn optimized(&self) -> Query<'q> {
+        optimize(self.clone())
+    }
+}
+
+/// Rewrite `query` into an equivalent query that is cheaper to evaluate.
+pub fn optimize(query: Query<'_>) -> Query<'_> {
+    let Query { segments, sources } = query;
+    let mut optimized = Vec::with_capacity(segments.len());
+
+    for (index, segment) in segments.into_iter().enumerate() {
+        let span = segment.span();
+        match optimize_segment(segment) {
+            Some(segment) => optimized.push(segment),
+            None => return nothing(span, sources, index),
+        }
+    }
+
+    Query {
+        segments: optimized,
+        sources,
+    }
+}
+
+/// Return `true` if `query` selects nothing from any document, because one
+/// of its segments can't select anything.
+fn selects_nothing(query: &Query) -> bool {
+    query.segments.iter().any(|segment| {
+        let (Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) = segment;
+        selectors.iter().all(is_empty_slice)
+    })
+}
+
+/// A query that selects nothing, keeping the label of the segment at
+/// `index`, if any.
+fn nothing(span: Span, sources: Vec<Source>, index: usize) -> Query<'static> {
+    Query {
+        segments: vec![Segment::Child {
+            span,
+            selectors: vec![Selector::Slice {
+                span,
+                start: Some(0),
+                stop: Some(0),
+                step: None,
+            }],
+        }],
+        sources: sources
+            .into_iter()
+            .filter(|source| source.segments.contains(&index))
+            .map(|source| Source {
+                segments: 0..1,
+                ..source
+            })
+            .collect(),
+    }
+}
+
+/// Optimize the selectors in `segment`, returning `None` if it can't select
+/// anything.
+fn optimize_segment(segment: Segment<'_>) -> Option<Segment<'_>> {
+    let (segment, selectors) = match segment {
+        Segment::Child { span, selectors } => (
+            Segment::Child {
+                span,
+                selectors: Vec::new(),
+            },
+            selectors,
+        ),
+        Segment::Recursive { span, selectors } => (
+            Segment::Recursive {
+                span,
+                selectors: Vec::new(),
+            },
+            selectors,
+        ),
+    };
+
+    let selectors: Vec<Selector> = selectors
+        .into_iter()
+        .filter_map(optimize_selector)
+        .collect();
+
+    if selectors.is_empty() {
+        return None;
+    }
+
+    Some(match segment {
+        Segment::Child { span, .. } => Segment::Child { span, selectors },
+        Segment::Recursive { span, .. } => Segment::Recursive { span, selectors },
+    })
+}
+
+fn optimize_selector(selector: Selector<'_>) -> Option<Selector<'_>> {
+    match selector {
+        Selector::Filter { span, expression } => match fold_test(*expression) {
+            FilterExpression::True { .. } => Some(Selector::Wild { span }),
+            FilterExpression::False { .. } => None,
+            expression => Some(Selector::Filter {
+                span,
+                expression: Box::new(expression),
+            }),
+        },
+        selector if is_empty_slice(&selector) => None,
+        Selector::Slice {
+            span,
+            start,
+            stop,
+            step,
+        } => match single_index(start, stop, step) {
+            Some(index) => Some(Selector::Index { span, index }),
+            None => Some(Selector::Slice {
+                span,
+                start,
+                stop,
+                step,
+            }),
+        },
+        selector => Some(selector),
+    }
+}
+
+/// The start index of a slice, with the default for `step` filled in. For a
+/// negative step, the default start is the last element, which `-1` also
+/// refers to.
+fn slice_start(start: Option<i64>, step: i64) -> i64 {
+    start.unwrap_or(if step < 0 { -1 } else { 0 })
+}
+
+/// Return `true` if `selector` is a slice that selects nothing from any
+/// array.
+///
+/// Start and stop indices with the same sign keep their order when they are
+/// normalized against an array's length, so if the start is not before the
+/// stop in the direction of the step, no indices are in range.
+fn is_empty_slice(selector: &Selector) -> bool {
+    let Selector::Slice {
+        start, stop, step, ..
+    } = *selector
+    else {
+        return false;
+    };
+
+    let step = step.unwrap_or(1);
+    let (start, Some(stop)) = (slice_start(start, step), stop) else {
+        return step == 0;
+    };
+
+    step == 0
+        || ((start >= 0) == (stop >= 0)
+            && if step > 0 {
+                start >= stop
+            } else {
+                start <= stop
+            })
+}
+
+/// The index of the only element a slice can select, if its start and stop
+/// are adjacent in the direction of its step and have the same sign. `5:6`
+/// and `-3:-2` select the same element as `5` and `-3`, if it exists, but
+/// `-1:0` selects nothing.
+fn single_index(start: Option<i64>, stop: Option<i64>, step: Option<i64>) -> Option<i64> {
+    let step = step.unwrap_or(1);
+    let start = slice_start(start, step);
+    let stop = stop?;
+
+    let adjacent = if step > 0 {
+        stop.checked_sub(start) == Some(1)
+    } else {
+        start.checked_sub(stop) == Some(1)
+    };
+
+    (step != 0 && adjacent && (start >= 0) == (stop >= 0)).then_some(start)
+}
+
+/// Fold `expr`, which is used as a test, like a filter selector's expression
+/// or an operand of a logical operator.
+fn fold_test(expr: FilterExpression<'_>) -> FilterExpression<'_> {
+    match expr {
+        FilterExpression::Not { span, expression } => match fold_test(*expression) {
+            FilterExpression::True { .. } => FilterExpression::False { span },
+            FilterExpression::False { .. } => FilterExpression::True { span },
+            expression => FilterExpression::Not {
+                span,
+                expression: Box::new(expression),
+            },
+        },
+        FilterExpression::Logical {
+            span,
+            left,
+            operator,
+            right,
+        } => match (operator, fold_test(*left), fold_test(*right)) {
+            (LogicalOperator::And, FilterExpression::False { .. }, _)
+            | (LogicalOperator::And, _, FilterExpression::False { .. }) => {
+                FilterExpression::False { span }
+            }
+            (LogicalOperator::Or, FilterExpression::True { .. }, _)
+            | (LogicalOperator::Or, _, FilterExpression::True { .. }) => {
+                FilterExpression::True { span }
+            }
+            (LogicalOperator::And, FilterExpression::True { .. }, expr)
+            | (LogicalOperator::And, expr, FilterExpression::True { .. })
+            | (LogicalOperator::Or, FilterExpression::False { .. }, expr)
+            | (LogicalOperator::Or, expr, FilterExpression::False { .. }) => expr,
+            (operator, left, right) => FilterExpression::Logical {
+                span,
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            },
+        },
+        FilterExpression::Comparison {
+            span,
+            left,
+            operator,
+            right,
+        } => {
+            let left = fold_operand(*left);
+            let right = fold_operand(*right);
+            match compare_literals(&left, operator, &right) {
+                Some(true) => FilterExpression::True { span },
+                Some(false) => FilterExpression::False { span },
+                None => FilterExpression::Comparison {
+                    span,
+                    left: Box::new(left),
+                    operator,
+                    right: Box::new(right),
+                },
+            }
+        }
+        FilterExpression::RelativeQuery { .. } | FilterExpression::RootQuery { .. } => {
+            match fold_operand(expr) {
+                FilterExpression::RelativeQuery { span, query }
+                | FilterExpression::RootQuery { span, query }
+                    if selects_nothing(&query) =>
+                {
+                    FilterExpression::False { span }
+                }
+                expr => expr,
+            }
+        }
+        expr => fold_operand(expr),
+    }
+}
+
+/// Optimize queries embedded in `expr`, which is used as a value, like a
+/// comparison operand or function argument.
+fn fold_operand(expr: FilterExpression<'_>) -> FilterExpression<'_> {
+    match expr {
+        FilterExpression::RelativeQuery { span, query } => FilterExpression::RelativeQuery {
+            span,
+            query: Box::new(optimize(*query)),
+        },
+        FilterExpression::RootQuery { span, query } => FilterExpression::RootQuery {
+            span,
+            query: Box::new(optimize(*query)),
+        },
+        FilterExpression::Function {
+            span,
+            name,
+            args,
+            deterministic,
+        } => FilterExpression::Function {
+            span,
+            name,
+            args: args.into_iter().map(fold_argument).collect(),
+            deterministic,
+        },
+        expr => expr,
+    }
+}
+
+/// Fold a function argument. A logical expression is left alone if it folds
+/// to a constant, as `true` or `false` would be a value, not a logical
+/// argument.
+fn fold_argument(arg: FilterExpression<'_>) -> FilterExpression<'_> {
+    match arg {
+        FilterExpression::Not { .. }
+        | FilterExpression::Logical { .. }
+        | FilterExpression::Comparison { .. } => {
+            let folded = fold_test(arg.clone());
+            if folded.is_literal() {
+                arg
+            } else {
+                folded
+            }
+        }
+        arg => fold_operand(arg),
+    }
+}
+
+/// The result of comparing two literals, following RFC 9535, or `None` if
+/// either operand is not a literal.
+///
+/// Strings are only compared for equality. How they order depends on the
+/// collation a query is evaluated with, so `'a' < 'b'` is left as it is.
+fn compare_literals(
+    left: &FilterExpression,
+    operator: ComparisonOperator,
+    right: &FilterExpression,
+) -> Option<bool> {
+    if !left.is_literal() || !right.is_literal() {
+        return None;
+    }
+
+    if matches!(
+        (left, right),
+        (FilterExpression::String { .. }, FilterExpression::String { .. })
+    ) && !matches!(operator, ComparisonOperator::Eq | ComparisonOperator::Ne)
+    {
+        return None;
+    }
+
+    let eq = || literal_ordering(left, right) == Some(Ordering::Equal);
+    let lt = || literal_ordering(left, right) == Some(Ordering::Less);
+    let gt = || literal_ordering(left, right) == Some(Ordering::Greater);
+
+    Some(match operator {
+        ComparisonOperator::Eq => eq(),
+        ComparisonOperator::Ne => !eq(),
+        ComparisonOperator::Lt => lt(),
+        ComparisonOperator::Le => lt() || eq(),
+        ComparisonOperator::Gt => gt(),
+        ComparisonOperator::Ge => gt() || eq(),
+    })
+}
+
+/// How `left` orders relative to `right`. Literals of different types are
+/// never equal. `true`, `false` and `null` are only equal to themselves, and
+/// are not ordered.
+fn literal_ordering(left: &FilterExpression, right: &FilterExpression) -> Option<Ordering> {
+    use FilterExpression::*;
+
+    match (left, right) {
+        (Int { value: l, .. }, Int { value: r, .. }) => Some(l.cmp(r)),
+        (Int { value: l, .. }, Float { value: r, .. }) => {
+            number::compare(Number::Int((*l).into()), Number::Float(*r))
+        }
+        (Float { value: l, .. }, Int { value: r, .. }) => {
+            number::compare(Number::Float(*l), Number::Int((*r).into()))
+        }
+        (Float { value: l, .. }, Float { value: r, .. }) => l.partial_cmp(r),
+        (String { value: l, .. }, String { value: r, .. }) => Some(l.cmp(r)),
+        (True { .. }, True { .. }) | (False { .. }, False { .. }) | (Null { .. }, Null { .. }) => {
+            Some(Ordering::Equal)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,308 @@
+//! Options for the `find_with_options` function of every evaluator in this
+//! workspace, and the code they share to apply them.
+//!
+//! Each evaluator re-exports [`FindOptions`], [`Output`] and [`ResultOrder`]
+//! from its own `options` module, along with `Found` and `FindResult` for
+//! its node list type.
+//!
+//! ```
+//! use jsonpath_rfc9535::options::{FindOptions, Output, ResultOrder};
+//!
+//! let options = FindOptions::new()
+//!     .order(ResultOrder::Document)
+//!     .dedup(true)
+//!     .max_nodes(1000)
+//!     .output(Output::Paths);
+//! ```
+//!
+//! Evaluators add nodes to a [`Collector`] as they select them, so a query
+//! that selects too many nodes stops soon after passing the limit rather
+//! than after it has been evaluated.
+//!
+//! There's no parallelism option. With its `rayon` feature,
+//! `jsonpath_rfc9535_serde` evaluates descendant segments in parallel with
+//! `find_parallel` instead.
+
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+
+/// The order of nodes returned by `find_with_options`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResultOrder {
+    /// The order nodes were selected by the query.
+    #[default]
+    Selection,
+    /// The order values appear in the document, parents before their
+    /// children. Object members are in the order given by
+    /// `Environment::ordering`.
+    Document,
+}
+
+/// What `find_with_options` returns for each node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Output {
+    #[default]
+    Nodes,
+    Values,
+    Paths,
+}
+
+/// Options for `find_with_options`, built by chaining methods on
+/// `FindOptions::new()`.
+#[derive(Debug, Clone)]
+pub struct FindOptions {
+    max_query_length: usize,
+    max_nodes: usize,
+    order: ResultOrder,
+    dedup: bool,
+    output: Output,
+    stats: bool,
+    trace: bool,
+}
+
+impl Default for FindOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FindOptions {
+    /// Options that return every selected node, in selection order, without
+    /// limits, stats or a trace.
+    pub fn new() -> Self {
+        FindOptions {
+            max_query_length: usize::MAX,
+            max_nodes: usize::MAX,
+            order: ResultOrder::Selection,
+            dedup: false,
+            output: Output::Nodes,
+            stats: false,
+            trace: false,
+        }
+    }
+
+    /// Fail with a limit error if a query is longer than `length` bytes.
+    pub fn max_query_length(mut self, length: usize) -> Self {
+        self.max_query_length = length;
+        self
+    }
+
+    /// Fail with a limit error if a query returns more than `count` nodes,
+    /// counted after removing duplicates.
+    pub fn max_nodes(mut self, count: usize) -> Self {
+        self.max_nodes = count;
+        self
+    }
+
+    pub fn order(mut self, order: ResultOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Return each location at most once, keeping the first node selected
+    /// at that location.
+    pub fn dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    pub fn output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    /// Include `FindStats` in the result.
+    pub fn stats(mut self, stats: bool) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Include a `Trace` of the query's evaluation in the result, with
+    /// evaluators that record one. Tracing records every node each selector
+    /// visits, so it's much slower than evaluating without it.
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// `true` if a trace was asked for.
+    pub fn traced(&self) -> bool {
+        self.trace
+    }
+
+    /// The message of a limit error if `expr` is too long.
+    pub fn check_query(&self, expr: &str) -> Result<(), String> {
+        if expr.len() > self.max_query_length {
+            return Err(format!(
+                "query is too long (limit {} bytes)",
+                self.max_query_length
+            ));
+        }
+        Ok(())
+    }
+
+    /// A collector for the nodes selected by a query that started
+    /// evaluating at `started`.
+    pub fn collector<'v, N: FoundNode<'v>>(&self, started: Instant) -> Collector<'_, N> {
+        Collector {
+            options: self,
+            nodes: Vec::new(),
+            seen: HashSet::new(),
+            selected: 0,
+            started,
+        }
+    }
+}
+
+/// A node selected by an evaluator.
+pub trait FoundNode<'v> {
+    /// The value at this node, borrowed from the document.
+    fn value(&self) -> &'v Value;
+
+    /// The normalized path to this node.
+    fn path(&self) -> String;
+}
+
+impl<'v, N: FoundNode<'v>> FoundNode<'v> for Rc<N> {
+    fn value(&self) -> &'v Value {
+        self.as_ref().value()
+    }
+
+    fn path(&self) -> String {
+        self.as_ref().path()
+    }
+}
+
+/// Applies [`FindOptions`] to nodes as an evaluator selects them.
+pub struct Collector<'o, N> {
+    options: &'o FindOptions,
+    nodes: Vec<N>,
+    seen: HashSet<*const Value>,
+    selected: usize,
+    started: Instant,
+}
+
+impl<'v, N: FoundNode<'v>> Collector<'_, N> {
+    /// Add a selected node, or return the message of a limit error if
+    /// there are now too many.
+    pub fn push(&mut self, node: N) -> Result<(), String> {
+        self.selected += 1;
+
+        if self.options.dedup && !self.seen.insert(node.value()) {
+            return Ok(());
+        }
+
+        if self.nodes.len() == self.options.max_nodes {
+            return Err(format!(
+                "query selected more than {0} nodes (limit {0})",
+                self.options.max_nodes
+            ));
+        }
+
+        self.nodes.push(node);
+        Ok(())
+    }
+
+    /// Add each of `nodes`, stopping at the first limit error.
+    pub fn extend(&mut self, nodes: impl IntoIterator<Item = N>) -> Result<(), String> {
+        nodes.into_iter().try_for_each(|node| self.push(node))
+    }
+
+    /// The collected nodes from the document `root`, in the requested order
+    /// and form. `L` is the evaluator's node list type.
+    pub fn finish<L: FromIterator<N>>(self, root: &'v Value) -> (Found<'v, L>, Option<FindStats>) {
+        let mut nodes = self.nodes;
+
+        if self.options.order == ResultOrder::Document {
+            let positions = document_order(root);
+            nodes.sort_by_key(|node| {
+                positions
+                    .get(&(node.value() as *const Value))
+                    .copied()
+                    .unwrap_or(usize::MAX)
+            });
+        }
+
+        let found = match self.options.output {
+            Output::Nodes => Found::Nodes(nodes.into_iter().collect()),
+            Output::Values => Found::Values(nodes.iter().map(|node| node.value()).collect()),
+            Output::Paths => Found::Paths(nodes.iter().map(|node| node.path()).collect()),
+        };
+
+        let stats = self.options.stats.then(|| FindStats {
+            selected: self.selected,
+            elapsed: self.started.elapsed(),
+        });
+
+        (found, stats)
+    }
+}
+
+/// The position of every value in `root` in a pre-order walk of `root`,
+/// keyed by address. Nodes borrow their values from the document, so a
+/// node's address identifies its location.
+fn document_order(root: &Value) -> HashMap<*const Value, usize> {
+    let mut positions = HashMap::new();
+    let mut stack = vec![root];
+    while let Some(value) = stack.pop() {
+        positions.insert(value as *const Value, positions.len());
+        match value {
+            Value::Array(arr) => stack.extend(arr.iter().rev()),
+            Value::Object(obj) => stack.extend(obj.values().rev()),
+            _ => (),
+        }
+    }
+    positions
+}
+
+/// Nodes, values or paths, depending on `FindOptions::output`. `L` is an
+/// evaluator's node list type.
+#[derive(Debug)]
+pub enum Found<'v, L> {
+    Nodes(L),
+    Values(Vec<&'v Value>),
+    Paths(Vec<String>),
+}
+
+impl<L> Found<'_, L>
+where
+    for<'a> &'a L: IntoIterator,
+{
+    pub fn len(&self) -> usize {
+        match self {
+            Found::Nodes(nodes) => nodes.into_iter().count(),
+            Found::Values(values) => values.len(),
+            Found::Paths(paths) => paths.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Statistics about one call to `find_with_options`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FindStats {
+    /// The number of nodes selected by the query, before removing duplicates.
+    pub selected: usize,
+    /// How long it took to parse and evaluate the query.
+    pub elapsed: Duration,
+}
+
+/// The result of `find_with_options`. `T` is the type of an evaluator's
+/// trace, for those that record one.
+#[derive(Debug)]
+pub struct FindResult<'v, L, T = ()> {
+    pub found: Found<'v, L>,
+    /// `Some` if `FindOptions::stats` is set.
+    pub stats: Option<FindStats>,
+    /// `Some` if `FindOptions::trace` is set and the evaluator records
+    /// traces.
+    pub trace: Option<T>,
+}
@@ -15,7 +15,10 @@
 //! ```
 //!
 //! New [`Parser`]s are created with the [standard functions] defined by RFC 9535. Use
-//! [`Parser::add_function`] to register additional function extensions.
+//! [`Parser::add_function`] to register additional function extensions,
+//! [`Parser::try_add_function`] to register one with a
+//! [`FunctionSignature`] built by hand, or [`Parser::add_functions`] to
+//! register those in a [`FunctionRegistry`](crate::function::FunctionRegistry).
 //!
 //! ```
 //! use jsonpath_rfc9535::{errors::JSONPathError, ExpressionType, Parser};
@@ -38,18 +41,75 @@
 //! [standard functions]: https://datatracker.ietf.org/doc/html/rfc9535#name-function-extensions
 use crate::{
     errors::{JSONPathError, JSONPathErrorType},
-    lexer::lex,
+    events::{ParseSink, SegmentBuilder, SegmentKind},
+    extensions::Extensions,
+    lexer::{is_function_name, lex_with_options, LexerOptions, Limits},
     query::{ComparisonOperator, FilterExpression, LogicalOperator, Query, Segment, Selector},
+    span::Span,
     token::{Token, TokenType},
 };
-use std::{collections::HashMap, iter::Peekable, ops::RangeInclusive, vec::IntoIter};
+use std::{borrow::Cow, collections::HashMap, iter::Peekable, ops::RangeInclusive, vec::IntoIter};
 
 use TokenType::*;
 
-const EOF_TOKEN: Token = Token {
-    kind: Eoq,
-    span: (0, 1),
-};
+/// The default [`Parser::max_nesting_depth`].
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = 64;
+
+/// Fail if brackets and parentheses in `query` nest deeper than `max_depth`,
+/// ignoring any inside string literals.
+///
+/// This is the nesting depth every parser in this workspace limits. Each
+/// `[` and `(`, including the one after a function name, is a level deeper
+/// than the brackets and parentheses around it, so `$.a` has a depth of
+/// zero, `$[?@.a]` a depth of one and `$[?!@[?(@.a)]]` a depth of three.
+///
+/// [`Parser`] counts the same brackets in its tokens. Parsers that don't
+/// have a separate lexer call this before parsing, so a deeply nested query
+/// can't exhaust the stack.
+pub fn check_nesting_depth(query: &str, max_depth: usize) -> Result<(), JSONPathError> {
+    let mut quote = None;
+    let mut escaped = false;
+    let brackets = query.char_indices().filter_map(move |(i, c)| {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None => match c {
+                '\'' | '"' => quote = Some(c),
+                '[' | '(' => return Some((true, i)),
+                ']' | ')' => return Some((false, i)),
+                _ => (),
+            },
+        }
+        None
+    });
+
+    check_depth(brackets, max_depth)
+        .map_err(|(msg, i)| JSONPathError::depth(msg, Span::new(i, i + 1).with_chars(query)))
+}
+
+/// Fail at the first opening bracket in `brackets` nested deeper than
+/// `max_depth`, given each bracket's position and whether it opens or closes.
+fn check_depth<P>(
+    brackets: impl Iterator<Item = (bool, P)>,
+    max_depth: usize,
+) -> Result<(), (String, P)> {
+    let mut depth: usize = 0;
+    for (opens, position) in brackets {
+        if !opens {
+            depth = depth.saturating_sub(1);
+        } else if depth < max_depth {
+            depth += 1;
+        } else {
+            return Err((
+                format!("query nesting depth exceeds the maximum of {max_depth}"),
+                position,
+            ));
+        }
+    }
+    Ok(())
+}
 
 const PRECEDENCE_LOWEST: u8 = 1;
 const PRECEDENCE_LOGICAL_OR: u8 = 3;
@@ -57,15 +117,61 @@ const PRECEDENCE_LOGICAL_AND: u8 = 4;
 const PRECEDENCE_RELATIONAL: u8 = 5;
 const PRECEDENCE_LOGICAL_NOT: u8 = 7;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExpressionType {
     Logical,
     Nodes,
     Value,
 }
 
+#[derive(Debug, Clone)]
 pub struct FunctionSignature {
     pub param_types: Vec<ExpressionType>,
     pub return_type: ExpressionType,
+    /// `false` if the function can return different results for the same
+    /// arguments, like a function that reads the current time.
+    pub deterministic: bool,
+    /// `true` if the function is deterministic and has no side effects, so
+    /// a query planner can evaluate a call with literal arguments once, or
+    /// move it ahead of other operands.
+    pub pure: bool,
+    /// The relative cost of calling the function, not counting its
+    /// arguments. `None` means unknown, and planners assume an expensive
+    /// function.
+    pub cost: Option<u32>,
+}
+
+impl FunctionSignature {
+    /// The signature of a deterministic function of unknown cost, that
+    /// planners treat as impure.
+    pub fn new(param_types: Vec<ExpressionType>, return_type: ExpressionType) -> Self {
+        FunctionSignature {
+            param_types,
+            return_type,
+            deterministic: true,
+            pure: false,
+            cost: None,
+        }
+    }
+
+    /// Set whether the function always returns the same result for the same
+    /// arguments.
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Set whether the function is deterministic and has no side effects.
+    pub fn with_pure(mut self, pure: bool) -> Self {
+        self.pure = pure;
+        self
+    }
+
+    /// Set the relative cost of calling the function.
+    pub fn with_cost(mut self, cost: u32) -> Self {
+        self.cost = Some(cost);
+        self
+    }
 }
 
 pub fn standard_functions() -> HashMap<String, FunctionSignature> {
@@ -76,6 +182,9 @@ pub fn standard_functions() -> HashMap<String, FunctionSignature> {
         FunctionSignature {
             param_types: vec![ExpressionType::Nodes],
             return_type: ExpressionType::Value,
+            deterministic: true,
+            pure: true,
+            cost: Some(1),
         },
     );
 
@@ -84,6 +193,9 @@ pub fn standard_functions() -> HashMap<String, FunctionSignature> {
         FunctionSignature {
             param_types: vec![ExpressionType::Value],
             return_type: ExpressionType::Value,
+            deterministic: true,
+            pure: true,
+            cost: Some(1),
         },
     );
 
@@ -92,6 +204,9 @@ pub fn standard_functions() -> HashMap<String, FunctionSignature> {
         FunctionSignature {
             param_types: vec![ExpressionType::Value, ExpressionType::Value],
             return_type: ExpressionType::Logical,
+            deterministic: true,
+            pure: true,
+            cost: Some(10),
         },
     );
 
@@ -100,6 +215,9 @@ pub fn standard_functions() -> HashMap<String, FunctionSignature> {
         FunctionSignature {
             param_types: vec![ExpressionType::Value, ExpressionType::Value],
             return_type: ExpressionType::Logical,
+            deterministic: true,
+            pure: true,
+            cost: Some(10),
         },
     );
 
@@ -108,30 +226,61 @@ pub fn standard_functions() -> HashMap<String, FunctionSignature> {
         FunctionSignature {
             param_types: vec![ExpressionType::Nodes],
             return_type: ExpressionType::Value,
+            deterministic: true,
+            pure: true,
+            cost: Some(1),
         },
     );
 
     functions
 }
 
-struct TokenStream {
-    tokens: Peekable<IntoIter<Token>>,
+struct TokenStream<'q> {
+    tokens: Peekable<IntoIter<Token<'q>>>,
+    /// The span of the source text of the last token taken from the
+    /// stream, including the quotes around a string.
+    last: Span,
+    /// Returned once `tokens` is exhausted, with an empty span at the end
+    /// of the last token, so errors at the end of a query point there.
+    eoq: Token<'q>,
 }
 
-impl TokenStream {
-    fn next(&mut self) -> Token {
+impl<'q> TokenStream<'q> {
+    fn new(tokens: Vec<Token<'q>>) -> Self {
+        let end = tokens.last().map_or(Span::default(), |token| Span {
+            start: token.span.end,
+            end: token.span.end,
+            chars: token.span.chars.map(|(_, end)| (end, end)),
+        });
+
+        TokenStream {
+            tokens: tokens.into_iter().peekable(),
+            last: Span::default(),
+            eoq: Token {
+                kind: Eoq,
+                span: end,
+            },
+        }
+    }
+
+    fn next(&mut self) -> Token<'q> {
         if let Some(token) = self.tokens.next() {
+            // String tokens span their contents, without the quotes.
+            self.last = match token.kind {
+                SingleQuoteString { .. } | DoubleQuoteString { .. } => token.span.widen(1, 1),
+                _ => token.span,
+            };
             token
         } else {
-            EOF_TOKEN
+            self.eoq.clone()
         }
     }
 
-    fn peek(&mut self) -> &Token {
+    fn peek(&mut self) -> &Token<'q> {
         if let Some(token) = self.tokens.peek() {
             token
         } else {
-            &EOF_TOKEN
+            &self.eoq
         }
     }
 }
@@ -139,6 +288,29 @@ impl TokenStream {
 pub struct Parser {
     pub index_range: RangeInclusive<i64>,
     pub functions: HashMap<String, FunctionSignature>,
+    pub limits: Limits,
+    pub lexer_options: LexerOptions,
+    /// The deepest brackets and parentheses can nest before parsing fails
+    /// with a [`JSONPathErrorType::DepthError`]. See [`check_nesting_depth`].
+    ///
+    /// The parser recurses at each level, so this bounds its stack use.
+    pub max_nesting_depth: usize,
+    /// How to handle `\uXXXX` escapes in string literals and quoted names
+    /// that don't spell a Unicode scalar value.
+    pub escape_mode: EscapeMode,
+}
+
+/// How a [`Parser`] handles `\uXXXX` escapes that are unpaired surrogates,
+/// like `"\uD800"`, `"\uDC00"` or `"\uD800\u0041"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EscapeMode {
+    /// Reject unpaired surrogates with a syntax error, as RFC 9535 requires.
+    #[default]
+    Strict,
+    /// Replace each unpaired surrogate with U+FFFD REPLACEMENT CHARACTER,
+    /// like a lossy UTF-16 decoder. Escapes that aren't four hex digits are
+    /// still rejected.
+    Lenient,
 }
 
 impl Default for Parser {
@@ -152,39 +324,211 @@ impl Parser {
         Parser {
             index_range: ((-2_i64).pow(53) + 1..=2_i64.pow(53) - 1),
             functions: standard_functions(),
+            limits: Limits::default(),
+            lexer_options: LexerOptions::default(),
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            escape_mode: EscapeMode::default(),
         }
     }
 
+    /// A new parser that accepts the non-standard syntax in `extensions`, as
+    /// well as RFC 9535 JSONPath.
+    pub fn with_extensions(extensions: Extensions) -> Self {
+        let mut parser = Parser::new();
+        parser.lexer_options.extensions = extensions;
+        parser
+    }
+
+    /// Register a deterministic function extension, of unknown cost, that
+    /// planners treat as impure.
     pub fn add_function(
         &mut self,
         name: &str,
         params: Vec<ExpressionType>,
         returns: ExpressionType,
     ) {
-        self.functions.insert(
-            name.to_owned(),
-            FunctionSignature {
-                param_types: params,
-                return_type: returns,
-            },
-        );
+        self.functions
+            .insert(name.to_owned(), FunctionSignature::new(params, returns));
+    }
+
+    /// Register a function extension with `signature`, for example, one built
+    /// with [`FunctionSignature::with_deterministic`].
+    ///
+    /// Fails with a name error if `name` isn't a valid RFC 9535 function
+    /// name, which queries could never call.
+    pub fn try_add_function(
+        &mut self,
+        name: &str,
+        signature: FunctionSignature,
+    ) -> Result<(), JSONPathError> {
+        if !is_function_name(name) {
+            return Err(JSONPathError::name(
+                format!("invalid function name `{name}`"),
+                Span::default(),
+            ));
+        }
+
+        self.functions.insert(name.to_owned(), signature);
+        Ok(())
     }
 
-    pub fn parse(&self, query: &str) -> Result<Query, JSONPathError> {
-        Ok(Query::new(self.parse_tokens(lex(query)?)?))
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "parse",
+            level = "debug",
+            skip_all,
+            fields(fingerprint = crate::query::fingerprint(query), len = query.len()),
+            err(Display)
+        )
+    )]
+    pub fn parse(&self, query: &str) -> Result<Query<'static>, JSONPathError> {
+        self.parse_borrowed(query).map(Query::into_owned)
     }
 
-    pub fn parse_tokens(&self, tokens: Vec<Token>) -> Result<Vec<Segment>, JSONPathError> {
-        let mut it = TokenStream {
-            tokens: tokens.into_iter().peekable(),
-        };
+    /// Like [`Parser::parse`], but name selectors, string literals and
+    /// function names that are written without escape sequences borrow from
+    /// `query` instead of being copied.
+    ///
+    /// Use [`Query::into_owned`] to keep a borrowed query longer than its
+    /// text.
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    ///
+    /// use jsonpath_rfc9535::{query::Selector, Parser};
+    ///
+    /// let expr = String::from(r#"$.users['j\u00f6rg']"#);
+    /// let query = Parser::new().parse_borrowed(&expr).unwrap();
+    ///
+    /// let names: Vec<&Cow<str>> = query
+    ///     .segments
+    ///     .iter()
+    ///     .flat_map(|segment| segment.selectors())
+    ///     .filter_map(|selector| match selector {
+    ///         Selector::Name { name, .. } => Some(name),
+    ///         _ => None,
+    ///     })
+    ///     .collect();
+    ///
+    /// assert!(matches!(names[0], Cow::Borrowed("users")));
+    /// assert!(matches!(names[1], Cow::Owned(name) if name == "jörg"));
+    /// ```
+    pub fn parse_borrowed<'q>(&self, query: &'q str) -> Result<Query<'q>, JSONPathError> {
+        Ok(Query::new(self.parse_tokens(lex_with_options(
+            query,
+            &self.limits,
+            &self.lexer_options,
+        )?)?))
+    }
+
+    /// Check that function calls in `query` are well-typed according to this
+    /// parser's function signatures.
+    ///
+    /// Use this when a query was parsed, or assembled from fragments, with a
+    /// different set of function extensions. Errors are attributed to the
+    /// labelled fragment containing the offending segment, if any.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "typecheck",
+            level = "debug",
+            skip_all,
+            fields(fingerprint = query.fingerprint()),
+            err(Display)
+        )
+    )]
+    pub fn typecheck(&self, query: &Query<'_>) -> Result<(), JSONPathError> {
+        self.typecheck_query(query)
+    }
+
+    fn typecheck_query(&self, query: &Query<'_>) -> Result<(), JSONPathError> {
+        for (index, segment) in query.segments.iter().enumerate() {
+            let (Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) = segment;
+            for selector in selectors {
+                if let Selector::Filter { expression, .. } = selector {
+                    self.assert_testable(expression)
+                        .and_then(|_| self.typecheck_expression(expression))
+                        .map_err(|err| match query.source_of(index) {
+                            Some(label) => err.with_source(label),
+                            None => err,
+                        })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn typecheck_expression(&self, expr: &FilterExpression<'_>) -> Result<(), JSONPathError> {
+        match expr {
+            FilterExpression::Not { expression, .. } => self.typecheck_expression(expression),
+            FilterExpression::Logical { left, right, .. } => {
+                self.typecheck_expression(left)?;
+                self.typecheck_expression(right)
+            }
+            FilterExpression::Comparison { left, right, .. } => {
+                self.assert_comparable(left, left.span())?;
+                self.assert_comparable(right, right.span())?;
+                self.typecheck_expression(left)?;
+                self.typecheck_expression(right)
+            }
+            FilterExpression::RelativeQuery { query, .. }
+            | FilterExpression::RootQuery { query, .. } => self.typecheck_query(query),
+            FilterExpression::Function {
+                name, args, span, ..
+            } => {
+                self.assert_well_typed(name, args, *span)?;
+                args.iter()
+                    .try_for_each(|arg| self.typecheck_expression(arg))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn parse_tokens<'q>(
+        &self,
+        tokens: Vec<Token<'q>>,
+    ) -> Result<Vec<Segment<'q>>, JSONPathError> {
+        let mut segments = SegmentBuilder::default();
+        self.parse_tokens_into(tokens, &mut segments)?;
+        Ok(segments.segments)
+    }
+
+    /// Parse `tokens`, passing segments and selectors to `sink` as they are
+    /// parsed.
+    pub(crate) fn parse_tokens_into<'q>(
+        &self,
+        tokens: Vec<Token<'q>>,
+        sink: &mut impl ParseSink<'q>,
+    ) -> Result<(), JSONPathError> {
+        let brackets = tokens.iter().filter_map(|token| match token.kind {
+            LBracket | LParen => Some((true, token.span)),
+            // The span of the parenthesis after the function name.
+            Function { .. } => Some((
+                true,
+                Span {
+                    start: token.span.end,
+                    end: token.span.end + 1,
+                    chars: token.span.chars.map(|(_, end)| (end, end + 1)),
+                },
+            )),
+            RBracket | RParen => Some((false, token.span)),
+            _ => None,
+        });
+        check_depth(brackets, self.max_nesting_depth)
+            .map_err(|(msg, span)| JSONPathError::depth(msg, span))?;
+
+        let mut it = TokenStream::new(tokens);
 
         match it.next() {
             Token { kind: Root, .. } => {
-                let segments = self.parse_segments(&mut it)?;
-                // parse_segments should have consumed all tokens
+                self.parse_segments_into(&mut it, sink)?;
+                // parse_segments_into should have consumed all tokens
                 match it.next() {
-                    Token { kind: Eoq, .. } => Ok(segments),
+                    Token { kind: Eoq, .. } => {
+                        sink.end();
+                        Ok(())
+                    }
                     token => Err(JSONPathError::syntax(
                         format!("expected end of query, found {}", token.kind),
                         token.span,
@@ -198,57 +542,69 @@ impl Parser {
         }
     }
 
-    fn parse_segments(&self, it: &mut TokenStream) -> Result<Vec<Segment>, JSONPathError> {
-        let mut segments: Vec<Segment> = Vec::new();
+    fn parse_segments<'q>(
+        &self,
+        it: &mut TokenStream<'q>,
+    ) -> Result<Vec<Segment<'q>>, JSONPathError> {
+        let mut segments = SegmentBuilder::default();
+        self.parse_segments_into(it, &mut segments)?;
+        Ok(segments.segments)
+    }
+
+    fn parse_segments_into<'q>(
+        &self,
+        it: &mut TokenStream<'q>,
+        sink: &mut impl ParseSink<'q>,
+    ) -> Result<(), JSONPathError> {
         loop {
-            match it.peek().kind {
-                DoubleDot => {
-                    let token = it.next();
-                    let selectors = self.parse_selectors(it)?;
-                    segments.push(Segment::Recursive {
-                        span: token.span,
-                        selectors,
-                    });
-                }
-                LBracket | Name { .. } | Wild => {
-                    let token = (*it.peek()).clone();
-                    let selectors = self.parse_selectors(it)?;
-                    segments.push(Segment::Child {
-                        span: token.span,
-                        selectors,
-                    });
-                }
-                _ => {
-                    break;
-                }
-            }
+            let (kind, start) = match it.peek().kind {
+                DoubleDot => (SegmentKind::Recursive, it.next().span),
+                LBracket => (SegmentKind::Child, it.peek().span),
+                // the dot before a shorthand selector
+                Name { .. } | Wild => (SegmentKind::Child, it.peek().span.preceding(1)),
+                _ => break,
+            };
+
+            sink.segment_start(kind, start);
+            self.parse_selectors(it, sink)?;
+            sink.segment_end(start.to(it.last));
         }
 
-        Ok(segments)
+        Ok(())
     }
 
-    fn parse_selectors(&self, it: &mut TokenStream) -> Result<Vec<Selector>, JSONPathError> {
+    fn parse_selectors<'q>(
+        &self,
+        it: &mut TokenStream<'q>,
+        sink: &mut impl ParseSink<'q>,
+    ) -> Result<(), JSONPathError> {
         match it.peek() {
             Token {
                 kind: Name { value },
                 span,
             } => {
-                let name = unescape_string(value, span)?;
+                let name = unescape(value, '"', *span, self.escape_mode)?;
                 let token = it.next();
-                Ok(vec![Selector::Name {
+                sink.selector(Selector::Name {
                     span: token.span,
                     name,
-                }])
+                });
             }
-            Token { kind: Wild, .. } => Ok(vec![Selector::Wild {
+            Token { kind: Wild, .. } => sink.selector(Selector::Wild {
                 span: it.next().span,
-            }]),
-            Token { kind: LBracket, .. } => self.parse_bracketed(it),
-            _ => Ok(Vec::new()),
+            }),
+            Token { kind: LBracket, .. } => self.parse_bracketed(it, sink)?,
+            _ => (),
         }
+
+        Ok(())
     }
 
-    fn parse_bracketed(&self, it: &mut TokenStream) -> Result<Vec<Selector>, JSONPathError> {
+    fn parse_bracketed<'q>(
+        &self,
+        it: &mut TokenStream<'q>,
+        sink: &mut impl ParseSink<'q>,
+    ) -> Result<(), JSONPathError> {
         #[cfg(debug_assertions)]
         debug_assert!(
             matches!(it.peek(), Token { kind: LBracket, .. }),
@@ -256,7 +612,7 @@ impl Parser {
         );
 
         let token = it.next(); // LBracket
-        let mut selectors: Vec<Selector> = Vec::new();
+        let mut count = 0;
 
         loop {
             match it.peek() {
@@ -269,16 +625,16 @@ impl Parser {
                     ..
                 } => {
                     let selector = self.parse_slice_or_index(it)?;
-                    selectors.push(selector);
+                    sink.selector(selector);
                 }
                 Token {
                     kind: DoubleQuoteString { value },
                     span,
                 } => {
-                    let name = unescape_string(value, span)?;
-                    let token = it.next();
-                    selectors.push(Selector::Name {
-                        span: token.span,
+                    let name = unescape(value, '"', *span, self.escape_mode)?;
+                    it.next();
+                    sink.selector(Selector::Name {
+                        span: it.last,
                         name,
                     });
                 }
@@ -286,25 +642,34 @@ impl Parser {
                     kind: SingleQuoteString { value },
                     span,
                 } => {
-                    let name = unescape_string(&value.replace("\\'", "'"), span)?;
-                    let token = it.next();
-                    selectors.push(Selector::Name {
-                        span: token.span,
+                    let name = unescape(value, '\'', *span, self.escape_mode)?;
+                    it.next();
+                    sink.selector(Selector::Name {
+                        span: it.last,
                         name,
                     });
                 }
                 Token { kind: Wild, .. } => {
                     let token = it.next();
-                    selectors.push(Selector::Wild { span: token.span });
+                    sink.selector(Selector::Wild { span: token.span });
                 }
                 Token { kind: Filter, .. } => {
-                    let selector = self.parse_filter(it)?;
-                    selectors.push(selector);
+                    let (span, expression) = self.parse_filter(it)?;
+                    sink.filter(span, expression);
+                }
+                Token { kind: Root, .. }
+                    if self
+                        .lexer_options
+                        .extensions
+                        .contains(Extensions::SINGULAR_QUERY_SELECTOR) =>
+                {
+                    let selector = self.parse_singular_query_selector(it)?;
+                    sink.selector(selector);
                 }
-                Token { kind: Eoq, .. } => {
+                Token { kind: Eoq, span } => {
                     return Err(JSONPathError::syntax(
                         String::from("unexpected end of query"),
-                        token.span,
+                        *span,
                     ));
                 }
                 token => {
@@ -315,6 +680,8 @@ impl Parser {
                 }
             }
 
+            count += 1;
+
             #[cfg(debug_assertions)]
             debug_assert!(
                 matches!(
@@ -344,7 +711,7 @@ impl Parser {
             }
         }
 
-        if selectors.is_empty() {
+        if count == 0 {
             return Err(JSONPathError::new(
                 JSONPathErrorType::SyntaxError,
                 String::from("empty bracketed selection"),
@@ -352,10 +719,13 @@ impl Parser {
             ));
         }
 
-        Ok(selectors)
+        Ok(())
     }
 
-    fn parse_slice_or_index(&self, it: &mut TokenStream) -> Result<Selector, JSONPathError> {
+    fn parse_slice_or_index<'q>(
+        &self,
+        it: &mut TokenStream<'q>,
+    ) -> Result<Selector<'q>, JSONPathError> {
         let token = it.next(); // index or colon
 
         #[cfg(debug_assertions)]
@@ -378,7 +748,7 @@ impl Parser {
 
             // 1: or :
             if let Token {
-                kind: Index { ref value },
+                kind: Index { value },
                 span,
             } = &token
             {
@@ -389,7 +759,7 @@ impl Parser {
             // 1 or 1: or : or ?
             if matches!(it.peek().kind, Index { .. } | Colon) {
                 if let Token {
-                    kind: Index { ref value },
+                    kind: Index { value },
                     span,
                 } = it.next()
                 {
@@ -403,7 +773,7 @@ impl Parser {
             // 1 or ?
             if matches!(it.peek().kind, Index { .. }) {
                 if let Token {
-                    kind: Index { ref value },
+                    kind: Index { value },
                     span,
                 } = it.next()
                 {
@@ -412,7 +782,7 @@ impl Parser {
             }
 
             Ok(Selector::Slice {
-                span: token.span,
+                span: token.span.to(it.last),
                 start,
                 stop,
                 step,
@@ -421,7 +791,7 @@ impl Parser {
             // an index
             match token {
                 Token {
-                    kind: Index { ref value },
+                    kind: Index { value },
                     ..
                 } => {
                     let array_index = self.parse_i_json_int(value, token.span)?;
@@ -438,7 +808,32 @@ impl Parser {
         }
     }
 
-    fn parse_filter(&self, it: &mut TokenStream) -> Result<Selector, JSONPathError> {
+    fn parse_singular_query_selector<'q>(
+        &self,
+        it: &mut TokenStream<'q>,
+    ) -> Result<Selector<'q>, JSONPathError> {
+        let token = it.next(); // Root
+        let query = Query::new(self.parse_segments(it)?);
+        let span = token.span.to(it.last);
+
+        if !query.is_singular() {
+            return Err(JSONPathError::syntax(
+                String::from("a query used as a selector must be a singular query"),
+                span,
+            ));
+        }
+
+        Ok(Selector::SingularQuery {
+            span,
+            query: Box::new(query),
+        })
+    }
+
+    /// Parse a filter selector, returning its span and expression.
+    fn parse_filter<'q>(
+        &self,
+        it: &mut TokenStream<'q>,
+    ) -> Result<(Span, FilterExpression<'q>), JSONPathError> {
         #[cfg(debug_assertions)]
         debug_assert!(
             matches!(it.peek(), Token { kind: Filter, .. }),
@@ -447,13 +842,18 @@ impl Parser {
 
         let token = it.next();
         let expr = self.parse_filter_expression(it, PRECEDENCE_LOWEST)?;
+        self.assert_testable(&expr)?;
 
-        match expr {
+        Ok((token.span.to(it.last), expr))
+    }
+
+    fn assert_testable(&self, expr: &FilterExpression<'_>) -> Result<(), JSONPathError> {
+        match *expr {
             FilterExpression::Function { ref name, span, .. } => {
                 if let Some(FunctionSignature {
                     return_type: ExpressionType::Value,
                     ..
-                }) = self.functions.get(name)
+                }) = self.functions.get(name.as_ref())
                 {
                     return Err(JSONPathError::typ(
                         format!("result of {}() must be compared", name),
@@ -475,29 +875,35 @@ impl Parser {
             _ => (),
         }
 
-        Ok(Selector::Filter {
-            span: token.span,
-            expression: Box::new(expr),
-        })
+        Ok(())
     }
 
-    fn parse_not_expression(
+    fn parse_not_expression<'q>(
         &self,
-        it: &mut TokenStream,
-    ) -> Result<FilterExpression, JSONPathError> {
-        let token = it.next();
+        it: &mut TokenStream<'q>,
+    ) -> Result<FilterExpression<'q>, JSONPathError> {
+        // Consecutive logical nots are collected in a loop rather than by
+        // recursion, as they don't count towards the nesting depth.
+        let mut spans = vec![it.next().span];
+        while matches!(it.peek().kind, Not) {
+            spans.push(it.next().span);
+        }
+
         let expr = self.parse_filter_expression(it, PRECEDENCE_LOGICAL_NOT)?;
-        Ok(FilterExpression::Not {
-            span: token.span,
-            expression: Box::new(expr),
-        })
+        Ok(spans
+            .into_iter()
+            .rev()
+            .fold(expr, |expr, span| FilterExpression::Not {
+                span,
+                expression: Box::new(expr),
+            }))
     }
 
-    fn parse_infix_expression(
+    fn parse_infix_expression<'q>(
         &self,
-        it: &mut TokenStream,
-        left: FilterExpression,
-    ) -> Result<FilterExpression, JSONPathError> {
+        it: &mut TokenStream<'q>,
+        left: FilterExpression<'q>,
+    ) -> Result<FilterExpression<'q>, JSONPathError> {
         let op_token = it.next();
         let precedence = self.precedence(&op_token.kind);
         let right = self.parse_filter_expression(it, precedence)?;
@@ -600,10 +1006,10 @@ impl Parser {
         }
     }
 
-    fn parse_grouped_expression(
+    fn parse_grouped_expression<'q>(
         &self,
-        it: &mut TokenStream,
-    ) -> Result<FilterExpression, JSONPathError> {
+        it: &mut TokenStream<'q>,
+    ) -> Result<FilterExpression<'q>, JSONPathError> {
         it.next(); // eat open paren
         let mut expr = self.parse_filter_expression(it, PRECEDENCE_LOWEST)?;
 
@@ -642,19 +1048,19 @@ impl Parser {
         Ok(expr)
     }
 
-    fn parse_basic_expression(
+    fn parse_primary_expression<'q>(
         &self,
-        it: &mut TokenStream,
-    ) -> Result<FilterExpression, JSONPathError> {
+        it: &mut TokenStream<'q>,
+    ) -> Result<FilterExpression<'q>, JSONPathError> {
         match it.peek() {
             Token {
                 kind: DoubleQuoteString { value },
                 span,
             } => {
-                let value = unescape_string(value, span)?;
-                let token = it.next();
+                let value = unescape(value, '"', *span, self.escape_mode)?;
+                it.next();
                 Ok(FilterExpression::String {
-                    span: token.span,
+                    span: it.last,
                     value,
                 })
             }
@@ -663,7 +1069,7 @@ impl Parser {
                 Ok(FilterExpression::False { span: token.span })
             }
             Token {
-                kind: Float { ref value },
+                kind: Float { value },
                 span,
             } => {
                 let f = value.parse::<f64>().map_err(|_| {
@@ -683,15 +1089,33 @@ impl Parser {
                 kind: Int { value },
                 span,
             } => {
-                let i = value.parse::<f64>().map_err(|_| {
+                if let Ok(i) = value.parse::<i64>() {
+                    let token = it.next();
+                    return Ok(FilterExpression::Int {
+                        span: token.span,
+                        value: i,
+                    });
+                }
+
+                let f = value.parse::<f64>().map_err(|_| {
                     JSONPathError::syntax(String::from("invalid integer literal"), *span)
-                })? as i64;
+                })?;
 
+                // Integers with an exponent, like `1e3`, go through a float.
+                // Those out of range for an i64, like `1e400`, stay floats
+                // rather than saturating at `i64::MAX`.
                 let token = it.next();
-                Ok(FilterExpression::Int {
-                    span: token.span,
-                    value: i,
-                })
+                if f.abs() < i64::MAX as f64 {
+                    Ok(FilterExpression::Int {
+                        span: token.span,
+                        value: f as i64,
+                    })
+                } else {
+                    Ok(FilterExpression::Float {
+                        span: token.span,
+                        value: f,
+                    })
+                }
             }
             Token { kind: Null, .. } => {
                 let token = it.next();
@@ -701,26 +1125,26 @@ impl Parser {
                 let token = it.next();
                 let segments = self.parse_segments(it)?;
                 Ok(FilterExpression::RootQuery {
-                    span: token.span,
-                    query: Box::new(Query { segments }),
+                    span: token.span.to(it.last),
+                    query: Box::new(Query::new(segments)),
                 })
             }
             Token { kind: Current, .. } => {
                 let token = it.next();
                 let segments = self.parse_segments(it)?;
                 Ok(FilterExpression::RelativeQuery {
-                    span: token.span,
-                    query: Box::new(Query { segments }),
+                    span: token.span.to(it.last),
+                    query: Box::new(Query::new(segments)),
                 })
             }
             Token {
                 kind: SingleQuoteString { value },
                 span,
             } => {
-                let value = unescape_string(&value.replace("\\'", "'"), span)?;
-                let token = it.next();
+                let value = unescape(value, '\'', *span, self.escape_mode)?;
+                it.next();
                 Ok(FilterExpression::String {
-                    span: token.span,
+                    span: it.last,
                     value,
                 })
             }
@@ -737,12 +1161,15 @@ impl Parser {
         }
     }
 
-    fn parse_function_call(&self, it: &mut TokenStream) -> Result<FilterExpression, JSONPathError> {
+    fn parse_function_call<'q>(
+        &self,
+        it: &mut TokenStream<'q>,
+    ) -> Result<FilterExpression<'q>, JSONPathError> {
         let token = it.next();
         let mut arguments: Vec<FilterExpression> = Vec::new();
 
         while it.peek().kind != RParen {
-            let mut expr = self.parse_basic_expression(it)?;
+            let mut expr = self.parse_primary_expression(it)?;
 
             while matches!(it.peek().kind, Eq | Ge | Gt | Le | Lt | Ne | And | Or) {
                 expr = self.parse_infix_expression(it, expr)?
@@ -769,13 +1196,14 @@ impl Parser {
 
         it.next(); // eat closing paren
 
-        if let Function { ref name } = &token.kind {
-            let function_name = name.to_string();
-            self.assert_well_typed(&function_name, &arguments, &token)?;
+        if let Function { name } = token.kind {
+            self.assert_well_typed(name, &arguments, token.span)?;
+            let deterministic = self.functions[name].deterministic;
             Ok(FilterExpression::Function {
                 span: token.span,
-                name: function_name,
+                name: Cow::Borrowed(name),
                 args: arguments,
+                deterministic,
             })
         } else {
             Err(JSONPathError::syntax(
@@ -785,12 +1213,12 @@ impl Parser {
         }
     }
 
-    fn parse_filter_expression(
+    fn parse_filter_expression<'q>(
         &self,
-        it: &mut TokenStream,
+        it: &mut TokenStream<'q>,
         precedence: u8,
-    ) -> Result<FilterExpression, JSONPathError> {
-        let mut left = self.parse_basic_expression(it)?;
+    ) -> Result<FilterExpression<'q>, JSONPathError> {
+        let mut left = self.parse_primary_expression(it)?;
 
         loop {
             let peek_kind = &it.peek().kind;
@@ -807,7 +1235,7 @@ impl Parser {
         Ok(left)
     }
 
-    fn precedence(&self, kind: &TokenType) -> u8 {
+    fn precedence(&self, kind: &TokenType<'_>) -> u8 {
         match kind {
             And => PRECEDENCE_LOGICAL_AND,
             Eq | Ge | Gt | Le | Lt | Ne => PRECEDENCE_RELATIONAL,
@@ -819,8 +1247,8 @@ impl Parser {
 
     fn assert_comparable(
         &self,
-        expr: &FilterExpression,
-        span: (usize, usize),
+        expr: &FilterExpression<'_>,
+        span: Span,
     ) -> Result<(), JSONPathError> {
         match expr {
             FilterExpression::RelativeQuery { query, .. }
@@ -838,7 +1266,7 @@ impl Parser {
                 if let Some(FunctionSignature {
                     return_type: ExpressionType::Value,
                     ..
-                }) = self.functions.get(name)
+                }) = self.functions.get(name.as_ref())
                 {
                     Ok(())
                 } else {
@@ -855,11 +1283,11 @@ impl Parser {
     fn assert_well_typed(
         &self,
         func_name: &str,
-        args: &[FilterExpression],
-        token: &Token,
+        args: &[FilterExpression<'_>],
+        span: Span,
     ) -> Result<(), JSONPathError> {
         let signature = self.functions.get(func_name).ok_or_else(|| {
-            JSONPathError::name(format!("unknown function `{}`", func_name), token.span)
+            JSONPathError::name(format!("unknown function `{}`", func_name), span)
         })?;
 
         // correct number of arguments?
@@ -876,7 +1304,7 @@ impl Parser {
                     },
                     args.len()
                 ),
-                token.span,
+                span,
             ));
         }
 
@@ -892,7 +1320,7 @@ impl Parser {
                                 idx + 1,
                                 func_name
                             ),
-                            token.span,
+                            span,
                         ));
                     }
                 }
@@ -910,7 +1338,7 @@ impl Parser {
                                 idx + 1,
                                 func_name
                             ),
-                            token.span,
+                            span,
                         ));
                     }
                 }
@@ -922,7 +1350,7 @@ impl Parser {
                                 idx + 1,
                                 func_name
                             ),
-                            token.span,
+                            span,
                         ));
                     }
                 }
@@ -932,7 +1360,7 @@ impl Parser {
         Ok(())
     }
 
-    fn is_value_type(&self, expr: &FilterExpression) -> bool {
+    fn is_value_type(&self, expr: &FilterExpression<'_>) -> bool {
         // literals are values
         if expr.is_literal() {
             return true;
@@ -947,7 +1375,7 @@ impl Parser {
             FilterExpression::Function { name, .. } => {
                 // some functions return a value
                 matches!(
-                    self.functions.get(name),
+                    self.functions.get(name.as_ref()),
                     Some(FunctionSignature {
                         return_type: ExpressionType::Value,
                         ..
@@ -958,12 +1386,12 @@ impl Parser {
         }
     }
 
-    fn is_nodes_type(&self, expr: &FilterExpression) -> bool {
+    fn is_nodes_type(&self, expr: &FilterExpression<'_>) -> bool {
         match expr {
             FilterExpression::RelativeQuery { .. } | FilterExpression::RootQuery { .. } => true,
             FilterExpression::Function { name, .. } => {
                 matches!(
-                    self.functions.get(name),
+                    self.functions.get(name.as_ref()),
                     Some(FunctionSignature {
                         return_type: ExpressionType::Nodes,
                         ..
@@ -974,11 +1402,7 @@ impl Parser {
         }
     }
 
-    fn parse_i_json_int(
-        &self,
-        value: &str,
-        token_span: (usize, usize),
-    ) -> Result<i64, JSONPathError> {
+    fn parse_i_json_int(&self, value: &str, token_span: Span) -> Result<i64, JSONPathError> {
         if value.len() > 1 && (value.starts_with('0') || value.starts_with("-0")) {
             return Err(JSONPathError::syntax(
                 format!("invalid index `{}`", value),
@@ -1001,21 +1425,65 @@ impl Parser {
     }
 }
 
-fn unescape_string(value: &str, token_span: &(usize, usize)) -> Result<String, JSONPathError> {
+/// `value`, the contents of a string literal or quoted name in `quote`s,
+/// with escape sequences replaced. Values without escape sequences are
+/// borrowed.
+fn unescape<'q>(
+    value: &'q str,
+    quote: char,
+    token_span: Span,
+    mode: EscapeMode,
+) -> Result<Cow<'q, str>, JSONPathError> {
+    if !value.contains('\\') {
+        if value.chars().all(|c| c as u32 > 0x1F) {
+            return Ok(Cow::Borrowed(value));
+        }
+        return unescape_string(value, &token_span, mode).map(Cow::Owned);
+    }
+
+    if quote == '\'' {
+        unescape_string(&value.replace("\\'", "'"), &token_span, mode).map(Cow::Owned)
+    } else {
+        unescape_string(value, &token_span, mode).map(Cow::Owned)
+    }
+}
+
+fn unescape_string(
+    value: &str,
+    token_span: &Span,
+    mode: EscapeMode,
+) -> Result<String, JSONPathError> {
     let chars = value.chars().collect::<Vec<char>>();
     let length = chars.len();
     let mut rv = String::new();
     let mut index: usize = 0;
 
+    // The span of chars `start..end` of `value`, for error reporting.
+    let offsets = value
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([value.len()])
+        .collect::<Vec<usize>>();
+    let span = |start: usize, end: usize| {
+        let byte = |i: usize| token_span.start + offsets.get(i).copied().unwrap_or(value.len());
+        Span {
+            start: byte(start),
+            end: byte(end),
+            chars: token_span
+                .chars
+                .map(|(first, _)| (first + start, first + end)),
+        }
+    };
+
     while index < length {
-        let start_index = token_span.0 + index; // for error reporting
+        let start_index = index;
 
         match chars[index] {
             '\\' => {
                 if index + 1 >= length {
                     return Err(JSONPathError::syntax(
                         String::from("invalid escape"),
-                        (start_index, index + 1),
+                        span(start_index, length),
                     ));
                 }
 
@@ -1031,79 +1499,59 @@ fn unescape_string(value: &str, token_span: &(usize, usize)) -> Result<String, J
                     'r' => rv.push('\r'),
                     't' => rv.push('\t'),
                     'u' => {
-                        // expect four hex digits
-                        if index + 4 >= length {
-                            return Err(JSONPathError::syntax(
-                                String::from("invalid \\uXXXX escape"),
-                                (start_index, length),
-                            ));
-                        }
-
-                        index += 1;
-
-                        let digits = chars
-                            .get(index..index + 4)
-                            .unwrap()
-                            .iter()
-                            .collect::<String>();
-
-                        let mut codepoint = u32::from_str_radix(&digits, 16).map_err(|_| {
+                        let invalid = |end: usize| {
                             JSONPathError::syntax(
                                 String::from("invalid \\uXXXX escape"),
-                                (start_index, index + 4),
+                                span(start_index, end),
                             )
-                        })?;
-
-                        if index + 5 < length && chars[index + 4] == '\\' && chars[index + 5] == 'u'
-                        {
-                            // expect a surrogate pair
-                            if index + 9 >= length {
+                        };
+
+                        let first = hex_digits(&chars, index + 1)
+                            .ok_or_else(|| invalid(length.min(index + 5)))?;
+                        index += 4;
+
+                        let codepoint = if is_high_surrogate(first) {
+                            // expect a low surrogate in the next escape
+                            let low = match chars.get(index + 1..index + 3) {
+                                Some(['\\', 'u']) => hex_digits(&chars, index + 3)
+                                    .filter(|&low| is_low_surrogate(low)),
+                                _ => None,
+                            };
+
+                            low.map(|low| {
+                                index += 6;
+                                0x10000 + (((first & 0x03FF) << 10) | (low & 0x03FF))
+                            })
+                        } else if is_low_surrogate(first) {
+                            None
+                        } else {
+                            Some(first)
+                        };
+
+                        let unescaped = match codepoint.and_then(char::from_u32) {
+                            Some(c) => c,
+                            None if mode == EscapeMode::Lenient => char::REPLACEMENT_CHARACTER,
+                            None => {
                                 return Err(JSONPathError::syntax(
-                                    String::from("invalid \\uXXXX escape"),
-                                    (start_index, length),
-                                ));
+                                    String::from("unpaired surrogate in \\uXXXX escape"),
+                                    span(start_index, index + 1),
+                                ))
                             }
-
-                            let digits = &chars
-                                .get(index + 6..index + 10)
-                                .unwrap()
-                                .iter()
-                                .collect::<String>();
-
-                            let low_surrogate = u32::from_str_radix(digits, 16).map_err(|_| {
-                                JSONPathError::syntax(
-                                    String::from("invalid \\uXXXX escape"),
-                                    (start_index, index + 10),
-                                )
-                            })?;
-
-                            codepoint =
-                                0x10000 + (((codepoint & 0x03FF) << 10) | (low_surrogate & 0x03FF));
-
-                            index += 6;
-                        }
-
-                        let unescaped = char::from_u32(codepoint).ok_or_else(|| {
-                            JSONPathError::syntax(
-                                String::from("invalid \\uXXXX escape"),
-                                (start_index, index + 3),
-                            )
-                        })?;
+                        };
 
                         if unescaped as u32 <= 0x1F {
                             return Err(JSONPathError::syntax(
                                 String::from("invalid character"),
-                                (start_index, start_index + 1),
+                                span(start_index, start_index + 1),
                             ));
                         }
 
                         rv.push(unescaped);
-                        index += 3;
                     }
                     _ => {
                         return Err(JSONPathError::syntax(
                             String::from("invalid escape"),
-                            (start_index, index + 1),
+                            span(start_index, index + 1),
                         ));
                     }
                 }
@@ -1112,7 +1560,7 @@ fn unescape_string(value: &str, token_span: &(usize, usize)) -> Result<String, J
                 if c as u32 <= 0x1F {
                     return Err(JSONPathError::syntax(
                         String::from("invalid character"),
-                        (start_index, index + 1),
+                        span(start_index, index + 1),
                     ));
                 }
                 rv.push(c);
@@ -1124,3 +1572,19 @@ fn unescape_string(value: &str, token_span: &(usize, usize)) -> Result<String, J
 
     Ok(rv)
 }
+
+/// The code point spelled by the four hex digits at `chars[index..]`.
+fn hex_digits(chars: &[char], index: usize) -> Option<u32> {
+    chars
+        .get(index..index + 4)?
+        .iter()
+        .try_fold(0, |acc, c| Some((acc << 4) | c.to_digit(16)?))
+}
+
+fn is_high_surrogate(codepoint: u32) -> bool {
+    (0xD800..=0xDBFF).contains(&codepoint)
+}
+
+fn is_low_surrogate(codepoint: u32) -> bool {
+    (0xDC00..=0xDFFF).contains(&codepoint)
+}
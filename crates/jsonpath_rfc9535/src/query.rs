@@ -10,20 +10,47 @@
 //!
 //! [RFC 9535]: https://datatracker.ietf.org/doc/html/rfc9535
 
-use crate::{errors::JSONPathError, parser::Parser};
+use crate::{errors::JSONPathError, parser::Parser, span::Span};
 use lazy_static::lazy_static;
-use std::fmt::{self, Write};
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+    ops::Range,
+};
 
 lazy_static! {
     static ref PARSER: Parser = Parser::new();
 }
 
-#[derive(Debug)]
-pub struct Query {
-    pub segments: Vec<Segment>,
+/// A 64-bit FNV-1a hash of `query`, for identifying queries in logs and
+/// cache keys without keeping the query text. The hash is the same across
+/// runs and platforms.
+pub fn fingerprint(query: &str) -> u64 {
+    query.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
 }
 
-impl fmt::Display for Query {
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Query<'q> {
+    pub segments: Vec<Segment<'q>>,
+    /// Labelled fragments this query was assembled from, if any.
+    pub sources: Vec<Source>,
+}
+
+/// A labelled range of segments in a query assembled from fragments.
+///
+/// Spans in a fragment's segments are relative to that fragment's source text,
+/// so errors use the label to say which fragment they refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Source {
+    pub label: String,
+    pub segments: Range<usize>,
+}
+
+impl fmt::Display for Query<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
@@ -37,45 +64,252 @@ impl fmt::Display for Query {
     }
 }
 
-impl Query {
-    pub fn new(segments: Vec<Segment>) -> Self {
-        Query { segments }
+impl<'q> Query<'q> {
+    pub fn new(segments: Vec<Segment<'q>>) -> Self {
+        Query {
+            segments,
+            sources: Vec::new(),
+        }
     }
 
-    pub fn standard(expr: &str) -> Result<Self, JSONPathError> {
+    pub fn standard(expr: &str) -> Result<Query<'static>, JSONPathError> {
         PARSER.parse(expr)
     }
 
+    /// A copy of this query that doesn't borrow from the text it was parsed
+    /// from. See [`Parser::parse_borrowed`].
+    pub fn into_owned(self) -> Query<'static> {
+        Query {
+            segments: self.segments.into_iter().map(Segment::into_owned).collect(),
+            sources: self.sources,
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
         self.segments.is_empty()
     }
 
+    /// Label all of this query's segments with `label`, replacing any
+    /// existing labels.
+    pub fn labelled(self, label: &str) -> Self {
+        let sources = vec![Source {
+            label: label.to_owned(),
+            segments: 0..self.segments.len(),
+        }];
+        Query { sources, ..self }
+    }
+
+    /// Scope `other` to the nodes selected by this query, by appending its
+    /// segments to ours. Source labels from both queries are kept.
+    pub fn concat(mut self, other: Query<'q>) -> Self {
+        let offset = self.segments.len();
+        self.segments.extend(other.segments);
+        self.sources
+            .extend(other.sources.into_iter().map(|source| Source {
+                segments: source.segments.start + offset..source.segments.end + offset,
+                ..source
+            }));
+        self
+    }
+
+    /// The label of the fragment containing the segment at `index`, if any.
+    pub fn source_of(&self, index: usize) -> Option<&str> {
+        self.sources
+            .iter()
+            .find(|source| source.segments.contains(&index))
+            .map(|source| source.label.as_str())
+    }
+
     pub fn is_singular(&self) -> bool {
         self.segments.iter().all(|segment| {
             if let Segment::Child { selectors, .. } = segment {
                 return selectors.len() == 1
                     && selectors.first().is_some_and(|selector| {
-                        matches!(selector, Selector::Name { .. } | Selector::Index { .. })
+                        matches!(
+                            selector,
+                            Selector::Name { .. }
+                                | Selector::Index { .. }
+                                | Selector::SingularQuery { .. }
+                        )
                     });
             }
             false
         })
     }
+
+    /// A fingerprint of this query's canonical form, so equivalent queries,
+    /// like `$.a` and `$['a']`, have the same fingerprint.
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint(&self.to_string())
+    }
+
+    /// Return `false` if this query calls a function extension that was
+    /// registered as non-deterministic, so its results should not be cached.
+    pub fn is_deterministic(&self) -> bool {
+        self.segments.iter().all(|segment| {
+            let (Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) = segment;
+            selectors.iter().all(|selector| match selector {
+                Selector::Filter { expression, .. } => expression.is_deterministic(),
+                _ => true,
+            })
+        })
+    }
+
+    /// Absolute queries embedded in this query's filters, like `$.limit` in
+    /// `$.items[?@.price < $.limit]`, with their spans, in the order they
+    /// appear. Absolute queries nested in other embedded queries are
+    /// included too.
+    ///
+    /// Use this to check that user supplied filters only refer to parts of a
+    /// document they are allowed to see.
+    pub fn embedded_root_queries(&self) -> Vec<(Span, &Query<'q>)> {
+        let mut queries = Vec::new();
+        self.collect_root_queries(&mut queries);
+        queries
+    }
+
+    /// Return `true` if this query's first segments are equivalent to all
+    /// of `prefix`'s segments, so `$.user.ssn` starts with `$.user`.
+    pub fn starts_with(&self, prefix: &Query<'_>) -> bool {
+        prefix.segments.len() <= self.segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(&prefix.segments)
+                .all(|(segment, p)| segment.to_string() == p.to_string())
+    }
+
+    /// Filter expressions in this query that match `pattern`, in the order
+    /// they appear, outer expressions before their operands. Expressions in
+    /// filters of embedded queries are included.
+    ///
+    /// Expressions match if they have the same canonical form, ignoring
+    /// spans, except that a query pattern is anchored at its start, so the
+    /// pattern `$.user` matches `$.user.ssn` but not `$.admin.user`.
+    ///
+    /// ```
+    /// use jsonpath_rfc9535::{query::FilterExpression, Query, Span};
+    ///
+    /// let pattern = FilterExpression::RootQuery {
+    ///     span: Span::default(),
+    ///     query: Box::new(Query::standard("$.user.ssn").unwrap()),
+    /// };
+    ///
+    /// let q = Query::standard("$.orders[?@.ssn == $.user.ssn.last4]").unwrap();
+    /// let found = q.find_subexpressions(&pattern);
+    /// assert_eq!(found.len(), 1);
+    /// assert_eq!(found[0].to_string(), "$['user']['ssn']['last4']");
+    /// ```
+    pub fn find_subexpressions(
+        &self,
+        pattern: &FilterExpression<'_>,
+    ) -> Vec<&FilterExpression<'q>> {
+        let mut found = Vec::new();
+        self.visit_expressions(&mut |expr| {
+            if expr.matches(pattern) {
+                found.push(expr);
+            }
+        });
+        found
+    }
+
+    /// Return `true` if this query, or any query embedded in it, has a
+    /// segment equivalent to `pattern`.
+    pub fn contains_segment(&self, pattern: &Segment<'_>) -> bool {
+        let pattern = pattern.to_string();
+        let mut queries = vec![self];
+        self.visit_expressions(&mut |expr| {
+            if let FilterExpression::RelativeQuery { query, .. }
+            | FilterExpression::RootQuery { query, .. } = expr
+            {
+                queries.push(query);
+            }
+        });
+
+        queries.iter().any(|query| {
+            query.segments.iter().any(|segment| {
+                let (Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) =
+                    segment;
+                segment.to_string() == pattern
+                    || selectors.iter().any(|selector| match selector {
+                        Selector::SingularQuery { query, .. } => query
+                            .segments
+                            .iter()
+                            .any(|segment| segment.to_string() == pattern),
+                        _ => false,
+                    })
+            })
+        })
+    }
+
+    /// Call `f` with every filter expression in this query, including those
+    /// in filters of embedded queries.
+    fn visit_expressions<'a>(&'a self, f: &mut dyn FnMut(&'a FilterExpression<'q>)) {
+        for segment in &self.segments {
+            let (Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) = segment;
+            for selector in selectors {
+                if let Selector::Filter { expression, .. } = selector {
+                    expression.visit(f);
+                }
+            }
+        }
+    }
+
+    fn collect_root_queries<'a>(&'a self, queries: &mut Vec<(Span, &'a Query<'q>)>) {
+        for segment in &self.segments {
+            let (Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) = segment;
+            for selector in selectors {
+                if let Selector::Filter { expression, .. } = selector {
+                    expression.collect_root_queries(queries);
+                }
+            }
+        }
+    }
 }
 
-#[derive(Debug)]
-pub enum Segment {
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Segment<'q> {
     Child {
-        span: (usize, usize),
-        selectors: Vec<Selector>,
+        span: Span,
+        selectors: Vec<Selector<'q>>,
     },
     Recursive {
-        span: (usize, usize),
-        selectors: Vec<Selector>,
+        span: Span,
+        selectors: Vec<Selector<'q>>,
     },
 }
 
-impl fmt::Display for Segment {
+impl<'q> Segment<'q> {
+    pub fn selectors(&self) -> &[Selector<'q>] {
+        match self {
+            Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. } => selectors,
+        }
+    }
+
+    pub fn into_owned(self) -> Segment<'static> {
+        match self {
+            Segment::Child { span, selectors } => Segment::Child {
+                span,
+                selectors: selectors.into_iter().map(Selector::into_owned).collect(),
+            },
+            Segment::Recursive { span, selectors } => Segment::Recursive {
+                span,
+                selectors: selectors.into_iter().map(Selector::into_owned).collect(),
+            },
+        }
+    }
+
+    /// The span of this segment's source text, from its leading `.`, `..`
+    /// or `[` to its last selector or closing bracket.
+    pub fn span(&self) -> Span {
+        match self {
+            Segment::Child { span, .. } | Segment::Recursive { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for Segment<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Segment::Child { selectors, .. } => {
@@ -104,32 +338,103 @@ impl fmt::Display for Segment {
     }
 }
 
-#[derive(Debug)]
-pub enum Selector {
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Selector<'q> {
     Name {
-        span: (usize, usize),
-        name: String,
+        span: Span,
+        name: Cow<'q, str>,
     },
     Index {
-        span: (usize, usize),
+        span: Span,
         index: i64,
     },
     Slice {
-        span: (usize, usize),
+        span: Span,
         start: Option<i64>,
         stop: Option<i64>,
         step: Option<i64>,
     },
     Wild {
-        span: (usize, usize),
+        span: Span,
     },
     Filter {
-        span: (usize, usize),
-        expression: Box<FilterExpression>,
+        span: Span,
+        expression: Box<FilterExpression<'q>>,
     },
+    /// An absolute singular query whose value selects a member or element,
+    /// like `$.sort` in `$.items[$.sort]`. This is not standard JSONPath and
+    /// is only parsed with [`Extensions::SINGULAR_QUERY_SELECTOR`].
+    ///
+    /// [`Extensions::SINGULAR_QUERY_SELECTOR`]: crate::Extensions::SINGULAR_QUERY_SELECTOR
+    SingularQuery {
+        span: Span,
+        query: Box<Query<'q>>,
+    },
+}
+
+impl Selector<'_> {
+    pub fn into_owned(self) -> Selector<'static> {
+        match self {
+            Selector::Name { span, name } => Selector::Name {
+                span,
+                name: Cow::Owned(name.into_owned()),
+            },
+            Selector::Index { span, index } => Selector::Index { span, index },
+            Selector::Slice {
+                span,
+                start,
+                stop,
+                step,
+            } => Selector::Slice {
+                span,
+                start,
+                stop,
+                step,
+            },
+            Selector::Wild { span } => Selector::Wild { span },
+            Selector::Filter { span, expression } => Selector::Filter {
+                span,
+                expression: Box::new(expression.into_owned()),
+            },
+            Selector::SingularQuery { span, query } => Selector::SingularQuery {
+                span,
+                query: Box::new(query.into_owned()),
+            },
+        }
+    }
+
+    /// The span of this selector's source text, including the quotes around
+    /// a quoted name and the whole expression of a filter.
+    pub fn span(&self) -> Span {
+        match self {
+            Selector::Name { span, .. }
+            | Selector::Index { span, .. }
+            | Selector::Slice { span, .. }
+            | Selector::Wild { span }
+            | Selector::Filter { span, .. }
+            | Selector::SingularQuery { span, .. } => *span,
+        }
+    }
+}
+
+/// Resolve `index`, an array index that counts from the end of the array
+/// when negative, against an array with `length` elements. Evaluators use
+/// this for [`Selector::Index`].
+///
+/// Returns `None` if `index` is out of range, including indices that don't
+/// fit in a `usize` on this platform.
+pub fn resolve_index(index: i64, length: usize) -> Option<usize> {
+    if index < 0 {
+        usize::try_from(index.unsigned_abs())
+            .ok()
+            .and_then(|i| length.checked_sub(i))
+    } else {
+        usize::try_from(index).ok().filter(|i| *i < length)
+    }
 }
 
-impl fmt::Display for Selector {
+impl fmt::Display for Selector<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Selector::Name { name, .. } => write!(f, "'{name}'"),
@@ -153,11 +458,13 @@ impl fmt::Display for Selector {
             }
             Selector::Wild { .. } => f.write_char('*'),
             Selector::Filter { expression, .. } => write!(f, "?{expression}"),
+            Selector::SingularQuery { query, .. } => write!(f, "{query}"),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LogicalOperator {
     And,
     Or,
@@ -172,7 +479,8 @@ impl fmt::Display for LogicalOperator {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ComparisonOperator {
     Eq,
     Ne,
@@ -195,61 +503,193 @@ impl fmt::Display for ComparisonOperator {
     }
 }
 
-#[derive(Debug)]
-pub enum FilterExpression {
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilterExpression<'q> {
     True {
-        span: (usize, usize),
+        span: Span,
     },
     False {
-        span: (usize, usize),
+        span: Span,
     },
     Null {
-        span: (usize, usize),
+        span: Span,
     },
     String {
-        span: (usize, usize),
-        value: String,
+        span: Span,
+        value: Cow<'q, str>,
     },
     Int {
-        span: (usize, usize),
+        span: Span,
         value: i64,
     },
     Float {
-        span: (usize, usize),
+        span: Span,
         value: f64,
     },
     Not {
-        span: (usize, usize),
-        expression: Box<FilterExpression>,
+        span: Span,
+        expression: Box<FilterExpression<'q>>,
     },
     Logical {
-        span: (usize, usize),
-        left: Box<FilterExpression>,
+        span: Span,
+        left: Box<FilterExpression<'q>>,
         operator: LogicalOperator,
-        right: Box<FilterExpression>,
+        right: Box<FilterExpression<'q>>,
     },
     Comparison {
-        span: (usize, usize),
-        left: Box<FilterExpression>,
+        span: Span,
+        left: Box<FilterExpression<'q>>,
         operator: ComparisonOperator,
-        right: Box<FilterExpression>,
+        right: Box<FilterExpression<'q>>,
     },
     RelativeQuery {
-        span: (usize, usize),
-        query: Box<Query>,
+        span: Span,
+        query: Box<Query<'q>>,
     },
     RootQuery {
-        span: (usize, usize),
-        query: Box<Query>,
+        span: Span,
+        query: Box<Query<'q>>,
     },
     Function {
-        span: (usize, usize),
-        name: String,
-        args: Vec<FilterExpression>,
+        span: Span,
+        name: Cow<'q, str>,
+        args: Vec<FilterExpression<'q>>,
+        deterministic: bool,
     },
 }
 
-impl FilterExpression {
+impl<'q> FilterExpression<'q> {
+    pub fn into_owned(self) -> FilterExpression<'static> {
+        match self {
+            FilterExpression::True { span } => FilterExpression::True { span },
+            FilterExpression::False { span } => FilterExpression::False { span },
+            FilterExpression::Null { span } => FilterExpression::Null { span },
+            FilterExpression::String { span, value } => FilterExpression::String {
+                span,
+                value: Cow::Owned(value.into_owned()),
+            },
+            FilterExpression::Int { span, value } => FilterExpression::Int { span, value },
+            FilterExpression::Float { span, value } => FilterExpression::Float { span, value },
+            FilterExpression::Not { span, expression } => FilterExpression::Not {
+                span,
+                expression: Box::new(expression.into_owned()),
+            },
+            FilterExpression::Logical {
+                span,
+                left,
+                operator,
+                right,
+            } => FilterExpression::Logical {
+                span,
+                left: Box::new(left.into_owned()),
+                operator,
+                right: Box::new(right.into_owned()),
+            },
+            FilterExpression::Comparison {
+                span,
+                left,
+                operator,
+                right,
+            } => FilterExpression::Comparison {
+                span,
+                left: Box::new(left.into_owned()),
+                operator,
+                right: Box::new(right.into_owned()),
+            },
+            FilterExpression::RelativeQuery { span, query } => FilterExpression::RelativeQuery {
+                span,
+                query: Box::new(query.into_owned()),
+            },
+            FilterExpression::RootQuery { span, query } => FilterExpression::RootQuery {
+                span,
+                query: Box::new(query.into_owned()),
+            },
+            FilterExpression::Function {
+                span,
+                name,
+                args,
+                deterministic,
+            } => FilterExpression::Function {
+                span,
+                name: Cow::Owned(name.into_owned()),
+                args: args.into_iter().map(FilterExpression::into_owned).collect(),
+                deterministic,
+            },
+        }
+    }
+
+    /// Return `false` if this expression calls a non-deterministic function.
+    pub fn is_deterministic(&self) -> bool {
+        match self {
+            FilterExpression::Not { expression, .. } => expression.is_deterministic(),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                left.is_deterministic() && right.is_deterministic()
+            }
+            FilterExpression::RelativeQuery { query, .. }
+            | FilterExpression::RootQuery { query, .. } => query.is_deterministic(),
+            FilterExpression::Function {
+                args,
+                deterministic,
+                ..
+            } => *deterministic && args.iter().all(|arg| arg.is_deterministic()),
+            _ => true,
+        }
+    }
+
+    fn visit<'a>(&'a self, f: &mut dyn FnMut(&'a FilterExpression<'q>)) {
+        f(self);
+        match self {
+            FilterExpression::Not { expression, .. } => expression.visit(f),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                left.visit(f);
+                right.visit(f);
+            }
+            FilterExpression::RelativeQuery { query, .. }
+            | FilterExpression::RootQuery { query, .. } => query.visit_expressions(f),
+            FilterExpression::Function { args, .. } => args.iter().for_each(|arg| arg.visit(f)),
+            _ => (),
+        }
+    }
+
+    /// See [`Query::find_subexpressions`].
+    fn matches(&self, pattern: &FilterExpression<'_>) -> bool {
+        match (self, pattern) {
+            (
+                FilterExpression::RelativeQuery { query, .. },
+                FilterExpression::RelativeQuery { query: prefix, .. },
+            )
+            | (
+                FilterExpression::RootQuery { query, .. },
+                FilterExpression::RootQuery { query: prefix, .. },
+            ) => query.starts_with(prefix),
+            _ => self.to_string() == pattern.to_string(),
+        }
+    }
+
+    fn collect_root_queries<'a>(&'a self, queries: &mut Vec<(Span, &'a Query<'q>)>) {
+        match self {
+            FilterExpression::Not { expression, .. } => expression.collect_root_queries(queries),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                left.collect_root_queries(queries);
+                right.collect_root_queries(queries);
+            }
+            FilterExpression::RelativeQuery { query, .. } => query.collect_root_queries(queries),
+            FilterExpression::RootQuery { span, query } => {
+                queries.push((*span, query));
+                query.collect_root_queries(queries);
+            }
+            FilterExpression::Function { args, .. } => {
+                args.iter()
+                    .for_each(|arg| arg.collect_root_queries(queries));
+            }
+            _ => (),
+        }
+    }
+
     pub fn is_literal(&self) -> bool {
         matches!(
             self,
@@ -262,7 +702,7 @@ impl FilterExpression {
         )
     }
 
-    pub fn span(&self) -> (usize, usize) {
+    pub fn span(&self) -> Span {
         match self {
             FilterExpression::True { span, .. }
             | FilterExpression::False { span, .. }
@@ -280,7 +720,7 @@ impl FilterExpression {
     }
 }
 
-impl fmt::Display for FilterExpression {
+impl fmt::Display for FilterExpression<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             FilterExpression::True { .. } => f.write_str("true"),
@@ -288,6 +728,9 @@ impl fmt::Display for FilterExpression {
             FilterExpression::Null { .. } => f.write_str("null"),
             FilterExpression::String { value, .. } => write!(f, "\"{value}\""),
             FilterExpression::Int { value, .. } => write!(f, "{value}"),
+            FilterExpression::Float { value, .. } if value.is_infinite() => {
+                f.write_str(infinite_literal(*value))
+            }
             FilterExpression::Float { value, .. } => write!(f, "{value}"),
             FilterExpression::Not { expression, .. } => write!(f, "!{expression}"),
             FilterExpression::Logical {
@@ -340,3 +783,14 @@ impl fmt::Display for FilterExpression {
         }
     }
 }
+
+/// A literal that parses back to the infinite float `value`. Float literals
+/// too big for an `f64`, like `1e400`, are infinite, and `f64` writes them
+/// as `inf`, which isn't a literal.
+pub(crate) fn infinite_literal(value: f64) -> &'static str {
+    if value < 0.0 {
+        "-1e400"
+    } else {
+        "1e400"
+    }
+}
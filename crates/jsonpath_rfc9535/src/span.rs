@@ -0,0 +1,131 @@
+use core::fmt;
+use std::ops::Range;
+
+/// The location of a token, syntax tree node or error in a JSONPath query.
+///
+/// `start` and `end` are byte offsets into the query. When a query contains
+/// non-ASCII characters, the lexer also records `chars`, the same location as
+/// offsets counted in Unicode scalar values, so editors that don't index
+/// strings by byte don't have to recompute positions. `chars` is `None` when
+/// char offsets are the same as byte offsets, or when they are unknown.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub chars: Option<(usize, usize)>,
+}
+
+impl Span {
+    pub const fn new(start: usize, end: usize) -> Self {
+        Self {
+            start,
+            end,
+            chars: None,
+        }
+    }
+
+    /// A copy of this span with char offsets calculated from `query`, or
+    /// without char offsets if `query` is ASCII or the span does not fall
+    /// on char boundaries.
+    pub fn with_chars(self, query: &str) -> Self {
+        let chars = if query.is_ascii() {
+            None
+        } else {
+            query
+                .get(..self.start)
+                .zip(query.get(self.start..self.end))
+                .map(|(before, inside)| {
+                    let start = before.chars().count();
+                    (start, start + inside.chars().count())
+                })
+        };
+
+        Self { chars, ..self }
+    }
+
+    /// A span from the start of this span to the end of `other`.
+    pub fn to(self, other: Span) -> Self {
+        Self {
+            start: self.start,
+            end: other.end,
+            chars: self
+                .chars
+                .zip(other.chars)
+                .map(|((start, _), (_, end))| (start, end)),
+        }
+    }
+
+    /// A span extended by `before` bytes at the start and `after` bytes at
+    /// the end, which must be ASCII characters, like the quotes around a
+    /// string token.
+    pub(crate) fn widen(self, before: usize, after: usize) -> Self {
+        Self {
+            start: self.start - before,
+            end: self.end + after,
+            chars: self.chars.map(|(start, end)| (start - before, end + after)),
+        }
+    }
+
+    /// The `len` bytes before this span, which must be ASCII characters,
+    /// like the dot before a shorthand name.
+    pub(crate) fn preceding(self, len: usize) -> Self {
+        Self {
+            start: self.start - len,
+            end: self.start,
+            chars: self.chars.map(|(start, _)| (start - len, start)),
+        }
+    }
+
+    /// The byte range covered by this span.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// The char range covered by this span, falling back to byte offsets
+    /// when char offsets were not recorded.
+    pub fn char_range(&self) -> Range<usize> {
+        match self.chars {
+            Some((start, end)) => start..end,
+            None => self.start..self.end,
+        }
+    }
+
+    /// The length of this span in bytes.
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Self {
+        span.range()
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.chars {
+            Some((start, end)) => {
+                write!(f, "{}..{} (chars {}..{})", self.start, self.end, start, end)
+            }
+            None => write!(f, "{}..{}", self.start, self.end),
+        }
+    }
+}
@@ -0,0 +1,345 @@
+//! Writing queries as standard RFC 9535 JSONPath.
+//!
+//! A [`Query`]'s `Display` implementation writes its canonical form, which is
+//! convenient for logging and comparing queries, but is not always valid
+//! RFC 9535 syntax. Names and string literals are not escaped, for example,
+//! and calls to function extensions are written as-is, even though no other
+//! implementation will know what they mean.
+//!
+//! [`Query::try_to_standard_string`] writes standard text or fails with a
+//! [`NonStandardError`] listing everything that couldn't be written.
+//! [`Query::downgrade_to_standard`] goes further, replacing calls to function
+//! extensions with standard expressions.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
+
+use crate::{
+    parser::Parser,
+    query::{infinite_literal, FilterExpression, Query, Segment, Selector},
+    span::Span,
+};
+
+/// Functions defined by RFC 9535.
+pub const STANDARD_FUNCTIONS: [&str; 5] = ["count", "length", "match", "search", "value"];
+
+/// A part of a query that has no standard JSONPath representation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonStandard {
+    pub msg: String,
+    pub span: Span,
+}
+
+impl fmt::Display for NonStandard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.msg, self.span)
+    }
+}
+
+/// The error returned by [`Query::try_to_standard_string`].
+#[derive(Debug)]
+pub struct NonStandardError {
+    pub constructs: Vec<NonStandard>,
+}
+
+impl fmt::Display for NonStandardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "query is not standard JSONPath: {}",
+            self.constructs
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+impl std::error::Error for NonStandardError {}
+
+impl Query<'_> {
+    /// Write this query as standard RFC 9535 JSONPath, with names and
+    /// strings escaped so the result parses back to an equivalent query.
+    ///
+    /// Fails if the query uses constructs that are not part of RFC 9535,
+    /// like calls to function extensions.
+    pub fn try_to_standard_string(&self) -> Result<String, NonStandardError> {
+        let mut writer = StandardWriter::default();
+        writer.query('$', self);
+
+        if writer.non_standard.is_empty() {
+            Ok(writer.out)
+        } else {
+            Err(NonStandardError {
+                constructs: writer.non_standard,
+            })
+        }
+    }
+
+    /// Rewrite calls to function extensions using `rewrites`, a map of
+    /// function names to templates written in standard JSONPath, returning
+    /// an equivalent standard query.
+    ///
+    /// In a template, `{0}`, `{1}` and so on are replaced with the function
+    /// call's arguments. For example, with an `is_empty` function extension
+    /// and a rewrite from `is_empty` to `count({0}) == 0`, `$[?is_empty(@.a)]`
+    /// becomes `$[?(count(@['a']) == 0)]`.
+    ///
+    /// Fails with a [`NonStandardError`] listing function extensions without
+    /// a rewrite, or describing why the rewritten query is not valid standard
+    /// JSONPath.
+    pub fn downgrade_to_standard(
+        &self,
+        rewrites: &HashMap<String, String>,
+    ) -> Result<Query<'static>, NonStandardError> {
+        let mut writer = StandardWriter {
+            rewrites: Some(rewrites),
+            ..Default::default()
+        };
+        writer.query('$', self);
+
+        if !writer.non_standard.is_empty() {
+            return Err(NonStandardError {
+                constructs: writer.non_standard,
+            });
+        }
+
+        Parser::new()
+            .parse(&writer.out)
+            .map_err(|err| NonStandardError {
+                constructs: vec![NonStandard {
+                    msg: format!(
+                        "downgraded query `{}` is not valid: {}",
+                        writer.out, err.msg
+                    ),
+                    span: err.span,
+                }],
+            })
+    }
+}
+
+#[derive(Default)]
+struct StandardWriter<'r> {
+    out: String,
+    non_standard: Vec<NonStandard>,
+    rewrites: Option<&'r HashMap<String, String>>,
+}
+
+impl StandardWriter<'_> {
+    fn query(&mut self, identifier: char, query: &Query) {
+        self.out.push(identifier);
+        for segment in &query.segments {
+            self.segment(segment);
+        }
+    }
+
+    fn segment(&mut self, segment: &Segment) {
+        let selectors = match segment {
+            Segment::Child { selectors, .. } => selectors,
+            Segment::Recursive { selectors, .. } => {
+                self.out.push_str("..");
+                selectors
+            }
+        };
+
+        self.out.push('[');
+        for (i, selector) in selectors.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.selector(selector);
+        }
+        self.out.push(']');
+    }
+
+    fn selector(&mut self, selector: &Selector) {
+        match selector {
+            Selector::Name { name, .. } => self.string(name),
+            Selector::Index { index, .. } => {
+                let _ = write!(self.out, "{index}");
+            }
+            Selector::Slice {
+                start, stop, step, ..
+            } => {
+                if let Some(start) = start {
+                    let _ = write!(self.out, "{start}");
+                }
+                self.out.push(':');
+                if let Some(stop) = stop {
+                    let _ = write!(self.out, "{stop}");
+                }
+                if let Some(step) = step {
+                    let _ = write!(self.out, ":{step}");
+                }
+            }
+            Selector::Wild { .. } => self.out.push('*'),
+            Selector::Filter { expression, .. } => {
+                self.out.push('?');
+                self.test(expression);
+            }
+            Selector::SingularQuery { span, query } => {
+                self.non_standard.push(NonStandard {
+                    msg: String::from("singular query selector"),
+                    span: *span,
+                });
+                self.query('$', query);
+            }
+        }
+    }
+
+    /// Write `expr` where a logical expression is expected, as opposed to
+    /// a comparison operand or function argument.
+    fn test(&mut self, expr: &FilterExpression) {
+        match expr {
+            FilterExpression::Function { name, args, .. } if self.rewrite(name).is_some() => {
+                // The template might be any logical expression, like a
+                // comparison, which would bind differently without parens.
+                self.out.push('(');
+                self.function(expr, name, args);
+                self.out.push(')');
+            }
+            _ => self.expression(expr),
+        }
+    }
+
+    fn rewrite(&self, name: &str) -> Option<&String> {
+        self.rewrites.and_then(|rewrites| rewrites.get(name))
+    }
+
+    fn expression(&mut self, expr: &FilterExpression) {
+        match expr {
+            FilterExpression::True { .. } => self.out.push_str("true"),
+            FilterExpression::False { .. } => self.out.push_str("false"),
+            FilterExpression::Null { .. } => self.out.push_str("null"),
+            FilterExpression::String { value, .. } => self.string(value),
+            FilterExpression::Int { value, .. } => {
+                let _ = write!(self.out, "{value}");
+            }
+            FilterExpression::Float { value, .. } if value.is_infinite() => {
+                self.out.push_str(infinite_literal(*value));
+            }
+            FilterExpression::Float { value, .. } => {
+                let _ = write!(self.out, "{value:?}");
+            }
+            FilterExpression::Not { expression, .. } => {
+                // `!` binds tighter than comparison operators.
+                self.out.push('!');
+                if matches!(**expression, FilterExpression::Comparison { .. }) {
+                    self.out.push('(');
+                    self.expression(expression);
+                    self.out.push(')');
+                } else {
+                    self.test(expression);
+                }
+            }
+            FilterExpression::Logical {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.out.push('(');
+                self.test(left);
+                let _ = write!(self.out, " {operator} ");
+                self.test(right);
+                self.out.push(')');
+            }
+            FilterExpression::Comparison {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.expression(left);
+                let _ = write!(self.out, " {operator} ");
+                self.expression(right);
+            }
+            FilterExpression::RelativeQuery { query, .. } => self.query('@', query),
+            FilterExpression::RootQuery { query, .. } => self.query('$', query),
+            FilterExpression::Function { name, args, .. } => self.function(expr, name, args),
+        }
+    }
+
+    fn function(&mut self, expr: &FilterExpression, name: &str, args: &[FilterExpression]) {
+        if STANDARD_FUNCTIONS.contains(&name) {
+            self.out.push_str(name);
+            self.out.push('(');
+            for (i, arg) in args.iter().enumerate() {
+                if i > 0 {
+                    self.out.push_str(", ");
+                }
+                self.expression(arg);
+            }
+            self.out.push(')');
+            return;
+        }
+
+        let Some(template) = self.rewrite(name).cloned() else {
+            self.non_standard.push(NonStandard {
+                msg: match self.rewrites {
+                    Some(_) => format!("no rewrite for function extension `{name}`"),
+                    None => format!("function extension `{name}`"),
+                },
+                span: expr.span(),
+            });
+            self.out.push_str(name);
+            self.out.push_str("(...)");
+            return;
+        };
+
+        let args: Vec<String> = args
+            .iter()
+            .map(|arg| {
+                let out = std::mem::take(&mut self.out);
+                self.expression(arg);
+                std::mem::replace(&mut self.out, out)
+            })
+            .collect();
+
+        // Replace `{n}` with the nth argument, leaving anything else as is.
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            self.out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let arg = rest
+                .find('}')
+                .and_then(|end| Some((end, args.get(rest[1..end].parse::<usize>().ok()?)?)));
+            match arg {
+                Some((end, arg)) => {
+                    self.out.push_str(arg);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    self.out.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        self.out.push_str(rest);
+    }
+
+    /// Write `value` as a single quoted string literal, escaped like names in
+    /// normalized paths.
+    fn string(&mut self, value: &str) {
+        self.out.push('\'');
+        for c in value.chars() {
+            match c {
+                '\u{8}' => self.out.push_str("\\b"),
+                '\u{c}' => self.out.push_str("\\f"),
+                '\n' => self.out.push_str("\\n"),
+                '\r' => self.out.push_str("\\r"),
+                '\t' => self.out.push_str("\\t"),
+                '\'' => self.out.push_str("\\'"),
+                '\\' => self.out.push_str("\\\\"),
+                c if c < ' ' => {
+                    let _ = write!(self.out, "\\u{:04x}", c as u32);
+                }
+                c => self.out.push(c),
+            }
+        }
+        self.out.push('\'');
+    }
+}
@@ -1,33 +1,49 @@
 use core::fmt;
 
+use crate::span::Span;
+
 pub const EOQ: char = '\0';
 
 #[derive(Debug, PartialEq, Clone)]
-pub enum TokenType {
+pub enum TokenType<'q> {
     Eoq,
-    Error { msg: Box<str> },
+    Error {
+        msg: Box<str>,
+    },
 
     Colon,
     Comma,
     DoubleDot,
     Filter,
-    Index { value: Box<str> },
+    Index {
+        value: &'q str,
+    },
     LBracket,
-    Name { value: Box<str> },
+    Name {
+        value: &'q str,
+    },
     RBracket,
     Root,
     Wild,
 
     And,
     Current,
-    DoubleQuoteString { value: Box<str> },
+    DoubleQuoteString {
+        value: &'q str,
+    },
     Eq,
     False,
-    Float { value: Box<str> },
-    Function { name: Box<str> },
+    Float {
+        value: &'q str,
+    },
+    Function {
+        name: &'q str,
+    },
     Ge,
     Gt,
-    Int { value: Box<str> },
+    Int {
+        value: &'q str,
+    },
     Le,
     LParen,
     Lt,
@@ -36,16 +52,28 @@ pub enum TokenType {
     Null,
     Or,
     RParen,
-    SingleQuoteString { value: Box<str> },
+    SingleQuoteString {
+        value: &'q str,
+    },
     True,
+
+    /// Source text between two tokens, like whitespace, the dot before a
+    /// name or the quotes around a string. Only produced by
+    /// [`tokenize_with_options`] when [`LexerOptions::trivia`] is set.
+    ///
+    /// [`tokenize_with_options`]: crate::lexer::tokenize_with_options
+    /// [`LexerOptions::trivia`]: crate::lexer::LexerOptions::trivia
+    Trivia {
+        value: &'q str,
+    },
 }
 
-impl fmt::Display for TokenType {
+impl fmt::Display for TokenType<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TokenType::Eoq => f.write_str("`end of query`"),
             TokenType::Error { msg } => write!(f, "error: {}", *msg),
-            TokenType::Colon => f.write_str("`;`"),
+            TokenType::Colon => f.write_str("`:`"),
             TokenType::Comma => f.write_str("`,`"),
             TokenType::DoubleDot => f.write_str("`..`"),
             TokenType::Filter => f.write_str("`?`"),
@@ -65,32 +93,81 @@ impl fmt::Display for TokenType {
             TokenType::Ge => f.write_str("`>=`"),
             TokenType::Gt => f.write_str("`>`"),
             TokenType::Int { value } => write!(f, "{}", *value),
-            TokenType::Le => f.write_str("<=`"),
+            TokenType::Le => f.write_str("`<=`"),
             TokenType::LParen => f.write_str("`(`"),
             TokenType::Lt => f.write_str("`<`"),
             TokenType::Ne => f.write_str("`!=`"),
             TokenType::Not => f.write_str("`!`"),
             TokenType::Null => f.write_str("`null`"),
-            TokenType::Or => f.write_str("`or`"),
+            TokenType::Or => f.write_str("`||`"),
             TokenType::RParen => f.write_str("`)`"),
             TokenType::SingleQuoteString { value } => write!(f, "`{}`", *value),
             TokenType::True => f.write_str("`true`"),
+            TokenType::Trivia { value } => write!(f, "`{}`", *value),
         }
     }
 }
 
-/// A JSONPath expression token, as produced by the lexer.
+impl TokenType<'_> {
+    /// The name of this token's kind, without any associated value.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenType::Eoq => "Eoq",
+            TokenType::Error { .. } => "Error",
+            TokenType::Colon => "Colon",
+            TokenType::Comma => "Comma",
+            TokenType::DoubleDot => "DoubleDot",
+            TokenType::Filter => "Filter",
+            TokenType::Index { .. } => "Index",
+            TokenType::LBracket => "LBracket",
+            TokenType::Name { .. } => "Name",
+            TokenType::RBracket => "RBracket",
+            TokenType::Root => "Root",
+            TokenType::Wild => "Wild",
+            TokenType::And => "And",
+            TokenType::Current => "Current",
+            TokenType::DoubleQuoteString { .. } => "DoubleQuoteString",
+            TokenType::Eq => "Eq",
+            TokenType::False => "False",
+            TokenType::Float { .. } => "Float",
+            TokenType::Function { .. } => "Function",
+            TokenType::Ge => "Ge",
+            TokenType::Gt => "Gt",
+            TokenType::Int { .. } => "Int",
+            TokenType::Le => "Le",
+            TokenType::LParen => "LParen",
+            TokenType::Lt => "Lt",
+            TokenType::Ne => "Ne",
+            TokenType::Not => "Not",
+            TokenType::Null => "Null",
+            TokenType::Or => "Or",
+            TokenType::RParen => "RParen",
+            TokenType::SingleQuoteString { .. } => "SingleQuoteString",
+            TokenType::True => "True",
+            TokenType::Trivia { .. } => "Trivia",
+        }
+    }
+}
+
+/// A JSONPath expression token, as produced by the lexer. Token values are
+/// slices of the query they were read from.
 #[derive(Debug, PartialEq, Clone)]
-pub struct Token {
-    pub kind: TokenType,
-    pub span: (usize, usize),
+pub struct Token<'q> {
+    pub kind: TokenType<'q>,
+    pub span: Span,
 }
 
-impl Token {
-    pub fn new(kind: TokenType, start: usize, end: usize) -> Self {
+impl<'q> Token<'q> {
+    pub fn new(kind: TokenType<'q>, start: usize, end: usize) -> Self {
         Self {
             kind,
-            span: (start, end),
+            span: Span::new(start, end),
         }
     }
 }
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.kind, self.span)
+    }
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "binary")]
+
+use jsonpath_rfc9535::{
+    binary::{self, Stamp, CRATE_VERSION, DIALECT, FORMAT_VERSION, MAGIC},
+    JSONPathErrorType, Query,
+};
+
+fn round_trip(query: &Query<'_>) -> Query<'static> {
+    Query::from_bytes(&query.to_bytes()).unwrap()
+}
+
+#[test]
+fn round_trip_keeps_the_syntax_tree() {
+    for expr in [
+        "$",
+        "$.a['b'][0, -1][1:5:2][::-1].*..c..*",
+        "$['j\\u00f6rg', \"x\"]",
+        "$[?@.a == 'b' && !(@.c < 1.5 || @.d != null)]",
+        "$[?count(@..*) > 2 && match(@.name, '^a.*')]",
+        "$.a[?$.b == @.c][?value(@.d) == true]",
+    ] {
+        let query = Query::standard(expr).unwrap();
+        let decoded = round_trip(&query);
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", query), "{}", expr);
+        assert_eq!(decoded.to_string(), query.to_string(), "{}", expr);
+    }
+}
+
+#[test]
+fn round_trip_keeps_source_labels_and_char_spans() {
+    let query = Query::standard("$['é']")
+        .unwrap()
+        .labelled("first")
+        .concat(Query::standard("$.b").unwrap().labelled("second"));
+    let decoded = round_trip(&query);
+
+    assert_eq!(decoded.sources, query.sources);
+    assert_eq!(decoded.segments[0].span(), query.segments[0].span());
+    assert!(decoded.segments[0].span().chars.is_some());
+}
+
+#[test]
+fn encoding_starts_with_a_header() {
+    let bytes = Query::standard("$.a").unwrap().to_bytes();
+    assert_eq!(bytes[..MAGIC.len()], MAGIC);
+    assert_eq!(bytes[MAGIC.len()], FORMAT_VERSION);
+}
+
+#[test]
+fn unknown_versions_are_rejected() {
+    let mut bytes = Query::standard("$.a").unwrap().to_bytes();
+    bytes[MAGIC.len()] = FORMAT_VERSION + 1;
+
+    let err = Query::from_bytes(&bytes).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::EncodingError));
+    assert_eq!(
+        err.msg,
+        format!(
+            "unsupported encoding version {} (expected {})",
+            FORMAT_VERSION + 1,
+            FORMAT_VERSION
+        )
+    );
+}
+
+#[test]
+fn malformed_input_is_an_error() {
+    let bytes = Query::standard("$[?@.a == 'b']").unwrap().to_bytes();
+
+    for input in [
+        &b""[..],
+        &b"JPQ"[..],
+        &b"{\"segments\": []}"[..],
+        &bytes[..bytes.len() - 1],
+    ] {
+        let err = Query::from_bytes(input).unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::EncodingError));
+    }
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    let err = Query::from_bytes(&trailing).unwrap_err();
+    assert_eq!(err.msg, "unexpected data after the encoded query");
+}
+
+/// Replace the stamp on an encoded query. Postcard writes each string as a
+/// one byte length, for strings this short, followed by its bytes.
+fn restamp(bytes: &[u8], dialect: &str, crate_version: &str) -> Vec<u8> {
+    let header = MAGIC.len() + 1;
+    let stamp = 2 + DIALECT.len() + CRATE_VERSION.len();
+
+    let mut restamped = bytes[..header].to_vec();
+    for s in [dialect, crate_version] {
+        restamped.push(s.len() as u8);
+        restamped.extend(s.as_bytes());
+    }
+    restamped.extend(&bytes[header + stamp..]);
+    restamped
+}
+
+#[test]
+fn encoded_queries_are_stamped() {
+    let bytes = Query::standard("$.a").unwrap().to_bytes();
+    assert_eq!(
+        binary::stamp(&bytes).unwrap(),
+        Stamp {
+            dialect: String::from("rfc9535"),
+            crate_version: String::from(env!("CARGO_PKG_VERSION")),
+        }
+    );
+    assert!(Stamp::current().is_compatible());
+}
+
+#[test]
+fn other_dialects_are_rejected() {
+    let bytes = Query::standard("$.a").unwrap().to_bytes();
+    let bytes = restamp(&bytes, "jsonpath-plus", CRATE_VERSION);
+    assert_eq!(binary::stamp(&bytes).unwrap().dialect, "jsonpath-plus");
+
+    let err = Query::from_bytes(&bytes).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::EncodingError));
+    assert_eq!(
+        err.msg,
+        "query was encoded for dialect `jsonpath-plus` (expected `rfc9535`)"
+    );
+}
+
+#[test]
+fn incompatible_crate_versions_are_rejected() {
+    let query = Query::standard("$.a[?@.b]").unwrap();
+    let bytes = query.to_bytes();
+
+    let mut parts: Vec<u64> = CRATE_VERSION
+        .split('.')
+        .map(|part| part.parse().unwrap())
+        .collect();
+    let compatible = format!("{}.{}.{}", parts[0], parts[1], parts[2] + 1);
+    if parts[0] == 0 {
+        parts[1] += 1;
+    } else {
+        parts[0] += 1;
+    }
+    let incompatible = format!("{}.{}.0", parts[0], parts[1]);
+
+    let decoded = Query::from_bytes(&restamp(&bytes, DIALECT, &compatible)).unwrap();
+    assert_eq!(decoded.to_string(), query.to_string());
+
+    for version in [incompatible.as_str(), "not a version"] {
+        let err = Query::from_bytes(&restamp(&bytes, DIALECT, version)).unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::EncodingError));
+        assert_eq!(
+            err.msg,
+            format!(
+                "query was encoded by version {} of this crate, \
+                 which is incompatible with version {}",
+                version, CRATE_VERSION
+            )
+        );
+    }
+}
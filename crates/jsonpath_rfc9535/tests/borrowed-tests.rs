@@ -0,0 +1,144 @@
+use std::borrow::Cow;
+
+use jsonpath_rfc9535::{
+    errors::JSONPathError,
+    lexer::lex,
+    query::{FilterExpression, Selector},
+    token::TokenType,
+    JSONPathErrorType, Parser, Query,
+};
+
+fn first_selector<'a, 'q>(query: &'a Query<'q>) -> &'a Selector<'q> {
+    &query.segments[0].selectors()[0]
+}
+
+fn filter<'a, 'q>(query: &'a Query<'q>) -> &'a FilterExpression<'q> {
+    match first_selector(query) {
+        Selector::Filter { expression, .. } => expression,
+        selector => panic!("expected a filter, found {:?}", selector),
+    }
+}
+
+#[test]
+fn tokens_borrow_from_the_query() -> Result<(), JSONPathError> {
+    let expr = String::from("$.thing");
+    let tokens = lex(&expr)?;
+    let TokenType::Name { value } = tokens[1].kind else {
+        panic!("expected a name, found {}", tokens[1].kind);
+    };
+    assert!(std::ptr::eq(value, &expr[2..]));
+    Ok(())
+}
+
+#[test]
+fn shorthand_names_are_borrowed() -> Result<(), JSONPathError> {
+    let expr = String::from("$.thing");
+    let query = Parser::new().parse_borrowed(&expr)?;
+    assert!(matches!(
+        first_selector(&query),
+        Selector::Name {
+            name: Cow::Borrowed("thing"),
+            ..
+        }
+    ));
+    Ok(())
+}
+
+#[test]
+fn quoted_names_are_borrowed() -> Result<(), JSONPathError> {
+    let expr = String::from(r#"$['a b', "c"]"#);
+    let query = Parser::new().parse_borrowed(&expr)?;
+    let selectors = query.segments[0].selectors();
+    assert!(matches!(
+        &selectors[0],
+        Selector::Name {
+            name: Cow::Borrowed("a b"),
+            ..
+        }
+    ));
+    assert!(matches!(
+        &selectors[1],
+        Selector::Name {
+            name: Cow::Borrowed("c"),
+            ..
+        }
+    ));
+    Ok(())
+}
+
+#[test]
+fn escaped_names_are_owned() -> Result<(), JSONPathError> {
+    let expr = String::from(r"$['it\'s', 'a\nb']");
+    let query = Parser::new().parse_borrowed(&expr)?;
+    let selectors = query.segments[0].selectors();
+    assert!(matches!(
+        &selectors[0],
+        Selector::Name { name: Cow::Owned(name), .. } if name == "it's"
+    ));
+    assert!(matches!(
+        &selectors[1],
+        Selector::Name { name: Cow::Owned(name), .. } if name == "a\nb"
+    ));
+    Ok(())
+}
+
+#[test]
+fn string_literals_and_function_names() -> Result<(), JSONPathError> {
+    let expr = String::from("$[?length(@.a) == 'x']");
+    let query = Parser::new().parse_borrowed(&expr)?;
+    let FilterExpression::Comparison { left, right, .. } = filter(&query) else {
+        panic!("expected a comparison");
+    };
+    assert!(matches!(
+        left.as_ref(),
+        FilterExpression::Function {
+            name: Cow::Borrowed("length"),
+            ..
+        }
+    ));
+    assert!(matches!(
+        right.as_ref(),
+        FilterExpression::String {
+            value: Cow::Borrowed("x"),
+            ..
+        }
+    ));
+    Ok(())
+}
+
+#[test]
+fn nested_queries() -> Result<(), JSONPathError> {
+    let expr = String::from("$[?@.a == $.b]");
+    let query = Parser::new().parse_borrowed(&expr)?;
+    let FilterExpression::Comparison { right, .. } = filter(&query) else {
+        panic!("expected a comparison");
+    };
+    let FilterExpression::RootQuery { query, .. } = right.as_ref() else {
+        panic!("expected a root query");
+    };
+    assert!(matches!(
+        first_selector(query),
+        Selector::Name {
+            name: Cow::Borrowed("b"),
+            ..
+        }
+    ));
+    Ok(())
+}
+
+#[test]
+fn into_owned() -> Result<(), JSONPathError> {
+    let expr = String::from(r#"$..a['bé', 1, 1:2][?@.c && match(@.d, "e.*") || !@[*]]"#);
+    let borrowed = Parser::new().parse_borrowed(&expr)?;
+    assert_eq!(
+        borrowed.into_owned().to_string(),
+        Query::standard(&expr)?.to_string()
+    );
+    Ok(())
+}
+
+#[test]
+fn errors() {
+    let err = Parser::new().parse_borrowed("$.a[").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::SyntaxError));
+}
@@ -0,0 +1,19 @@
+use jsonpath_rfc9535::csv;
+
+#[test]
+fn quote() {
+    assert_eq!(csv::quote("plain", ','), "plain");
+    assert_eq!(csv::quote("a,b", ','), "\"a,b\"");
+    assert_eq!(csv::quote("a,b", '\t'), "a,b");
+    assert_eq!(csv::quote("a\tb", '\t'), "\"a\tb\"");
+    assert_eq!(csv::quote("say \"hi\"", ','), "\"say \"\"hi\"\"\"");
+    assert_eq!(csv::quote("a\r\nb", ','), "\"a\r\nb\"");
+}
+
+#[test]
+fn records_end_with_crlf() {
+    let mut buf = String::new();
+    csv::push_record(&mut buf, ["a", "b"], ',');
+    csv::push_record(&mut buf, ["", "c"], ',');
+    assert_eq!(buf, "a,b\r\n,c\r\n");
+}
@@ -0,0 +1,118 @@
+use jsonpath_rfc9535::{
+    diff::{diff_queries, ChangeKind, Target},
+    Query, Span,
+};
+
+fn diff(a: &str, b: &str) -> Vec<String> {
+    let a = Query::standard(a).unwrap();
+    let b = Query::standard(b).unwrap();
+    diff_queries(&a, &b)
+        .changes
+        .iter()
+        .map(|change| change.to_string())
+        .collect()
+}
+
+#[test]
+fn equivalent_queries() {
+    let a = Query::standard("$.a[0]..b[?@.c == 'd']").unwrap();
+    let b = Query::standard("$['a'][0]..['b'][?(@['c']=='d')]").unwrap();
+    assert!(diff_queries(&a, &b).is_empty());
+}
+
+#[test]
+fn added_and_removed_segments() {
+    assert_eq!(
+        diff("$.a.c", "$.a.b.c"),
+        vec!["added segment `['b']` (3..5)"]
+    );
+    assert_eq!(
+        diff("$.a.b.c", "$.a.c"),
+        vec!["removed segment `['b']` (3..5)"]
+    );
+    assert_eq!(
+        diff("$.a", "$.a.b[*]"),
+        vec!["added segment `['b']` (3..5)", "added segment `[*]` (5..8)"]
+    );
+}
+
+#[test]
+fn changed_segments() {
+    let a = Query::standard("$.a.b.c").unwrap();
+    let b = Query::standard("$.a..b.c").unwrap();
+    let changes = diff_queries(&a, &b).changes;
+
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].kind, ChangeKind::Changed);
+    assert_eq!(changes[0].target, Target::Segment);
+    assert_eq!(changes[0].old.as_ref().unwrap().text, "['b']");
+    assert_eq!(changes[0].old.as_ref().unwrap().span, Span::new(3, 5));
+    assert_eq!(changes[0].new.as_ref().unwrap().text, "..['b']");
+    assert_eq!(changes[0].new.as_ref().unwrap().span, Span::new(3, 6));
+
+    assert_eq!(
+        diff("$[?@.a > 1]", "$[?@.a > 1, 0]"),
+        vec!["changed segment `[?@['a'] > 1]` (1..11) to `[?@['a'] > 1, 0]` (1..14)"]
+    );
+}
+
+#[test]
+fn changed_filter_operands() {
+    assert_eq!(
+        diff("$.a[?@.price < 10]", "$.a[?@.price < 20]"),
+        vec!["changed filter `10` (15..17) to `20` (15..17)"]
+    );
+    assert_eq!(
+        diff("$[?@.a == 1 && @.b == 2]", "$[?@.a == 1 && @.c == 2]"),
+        vec!["changed segment `['b']` (16..18) to `['c']` (16..18)"]
+    );
+    assert_eq!(
+        diff("$[?match(@.a, 'x')]", "$[?match(@.a, 'y')]"),
+        vec!["changed filter `\"x\"` (14..17) to `\"y\"` (14..17)"]
+    );
+}
+
+#[test]
+fn changed_filter_operators() {
+    assert_eq!(
+        diff("$[?@.a == 1 && @.b]", "$[?@.a != 1 && @.b]"),
+        vec!["changed filter `@['a'] == 1` (3..11) to `@['a'] != 1` (3..11)"]
+    );
+    assert_eq!(
+        diff("$[?length(@.a) > 1]", "$[?count(@.a) > 1]"),
+        vec!["changed filter `length(@['a'])` (3..13) to `count(@['a'])` (3..12)"]
+    );
+}
+
+#[test]
+fn added_and_removed_conditions() {
+    assert_eq!(
+        diff("$[?@.a]", "$[?@.a && @.b > 1]"),
+        vec!["added filter `@['b'] > 1` (10..17)"]
+    );
+    assert_eq!(
+        diff("$[?@.a || @.b]", "$[?@.b]"),
+        vec!["removed filter `@['a']` (3..6)"]
+    );
+}
+
+#[test]
+fn root_queries_in_filters() {
+    assert_eq!(
+        diff("$[?@.a < $.limit]", "$[?@.a < $.limits.max]"),
+        vec![
+            "changed segment `['limit']` (10..16) to `['limits']` (10..17)",
+            "added segment `['max']` (17..21)"
+        ]
+    );
+}
+
+#[test]
+fn display() {
+    let a = Query::standard("$.a[?@.b]").unwrap();
+    let b = Query::standard("$.a[?@.c].d").unwrap();
+    assert_eq!(
+        diff_queries(&a, &b).to_string(),
+        "changed segment `['b']` (6..8) to `['c']` (6..8)\nadded segment `['d']` (9..11)\n"
+    );
+}
@@ -1,4 +1,8 @@
-use jsonpath_rfc9535::Query;
+use jsonpath_rfc9535::{
+    lexer::{lex, Limits},
+    parser::check_nesting_depth,
+    JSONPathError, JSONPathErrorType, Parser, Query, Span,
+};
 
 mod errors {
     use super::*;
@@ -61,4 +65,284 @@ mod errors {
     fn filter_comparison_and_literal() {
         Query::standard("$[?true == false && false]").unwrap();
     }
+
+    #[test]
+    fn query_length_limit() {
+        let mut parser = Parser::new();
+        parser.limits = Limits {
+            max_query_length: 8,
+            ..Limits::default()
+        };
+        let err = parser.parse("$.foo.bar").unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+        assert_eq!(
+            err.to_string(),
+            "limit error: query is too long (9 bytes, limit 8) (8..9)"
+        );
+    }
+
+    #[test]
+    fn token_count_limit() {
+        let mut parser = Parser::new();
+        parser.limits = Limits {
+            max_tokens: 10,
+            ..Limits::default()
+        };
+        assert!(parser.parse("$.a.b.c.d").is_ok());
+        let err = parser
+            .parse(&format!("${}", ".a".repeat(1 << 20)))
+            .unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+        assert_eq!(err.msg, "too many tokens (limit 10)");
+    }
+
+    #[test]
+    fn nesting_depth_limit() {
+        let mut parser = Parser::new();
+        parser.max_nesting_depth = 3;
+        assert!(parser.parse("$[?!@[?(@.a)]]").is_ok());
+        assert!(parser.parse("$[?@.a && (@.b || @.c)][?@.d]").is_ok());
+        assert!(parser.parse("$[?!!!!@.a]").is_ok());
+
+        let err = parser.parse("$[?!@[?(@[?@.a])]]").unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::DepthError));
+        assert_eq!(
+            err.to_string(),
+            "depth error: query nesting depth exceeds the maximum of 3 (9..10)"
+        );
+
+        let err = parser.parse("$[?count(@[?@[?@.a]])]").unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::DepthError));
+        assert_eq!(err.span, Span::new(13, 14));
+    }
+
+    #[test]
+    fn nesting_depth_corpus() {
+        for line in include_str!("../../../tests/nesting-depth.tsv").lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let [query, depth, offset] = line.split('\t').collect::<Vec<_>>()[..] else {
+                panic!("malformed corpus line {line:?}");
+            };
+            let depth: usize = depth.parse().unwrap();
+            let mut parser = Parser::new();
+            parser.max_nesting_depth = depth;
+            assert!(parser.parse(query).is_ok(), "{query:?}");
+
+            if depth > 0 {
+                parser.max_nesting_depth = depth - 1;
+                let err = parser.parse(query).unwrap_err();
+                assert!(
+                    matches!(err.kind, JSONPathErrorType::DepthError),
+                    "{query:?}"
+                );
+                assert_eq!(
+                    err.span.start,
+                    offset.parse::<usize>().unwrap(),
+                    "{query:?}"
+                );
+
+                // Checking the text gives the same result as parsing.
+                let err = check_nesting_depth(query, depth - 1).unwrap_err();
+                assert_eq!(
+                    err.span.start,
+                    offset.parse::<usize>().unwrap(),
+                    "{query:?}"
+                );
+            }
+            assert!(check_nesting_depth(query, depth).is_ok(), "{query:?}");
+        }
+    }
+
+    #[test]
+    fn deeply_nested_filters() {
+        let query = format!("${}{}", "[?@".repeat(1_000), "]".repeat(1_000));
+        let err = Query::standard(&query).unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::DepthError));
+    }
+
+    #[test]
+    fn string_length_limit() {
+        let mut parser = Parser::new();
+        parser.limits = Limits {
+            max_string_length: 5,
+            ..Limits::default()
+        };
+        assert!(parser.parse("$['abcde'][?@.a == \"vwxyz\"]").is_ok());
+
+        let err = parser.parse("$['abcdef']").unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+        assert_eq!(
+            err.to_string(),
+            "limit error: string literal is too long (limit 5 bytes) (3..9)"
+        );
+
+        let err = parser
+            .parse(&format!("$[?@.a == '{}']", "x".repeat(1 << 20)))
+            .unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+        assert_eq!(err.span, Span::from(11..17));
+
+        // Escape sequences count towards the limit before they're decoded.
+        assert!(parser.parse(r#"$["\\\\\\"]"#).is_err());
+    }
+
+    #[test]
+    fn error_span_char_offsets() {
+        let err = Query::standard("$['ü'][?nosuchthing()]").unwrap_err();
+        assert_eq!(
+            err.span,
+            Span::from(9..20).with_chars("$['ü'][?nosuchthing()]")
+        );
+        assert_eq!(err.span.char_range(), 8..19);
+        assert_eq!(
+            err.to_string(),
+            "name error: unknown function `nosuchthing` (9..20)"
+        );
+    }
+
+    #[test]
+    fn escape_error_spans_count_chars() {
+        let query = r#"$['ü\uZZZZ']"#;
+        let err = Query::standard(query).unwrap_err();
+        assert_eq!(&query[err.span.range()], "\\uZZZZ");
+        assert_eq!(err.span.char_range(), 4..10);
+
+        let query = r#"$["ü\uD800A"]"#;
+        let err = Query::standard(query).unwrap_err();
+        assert_eq!(&query[err.span.range()], "\\uD800");
+        assert_eq!(err.span.char_range(), 4..10);
+    }
+
+    #[test]
+    fn trailing_content() {
+        for line in include_str!("../../../tests/trailing-content.tsv").lines() {
+            if line.starts_with('#') {
+                continue;
+            }
+            let [query, offset, msg] = line.split('\t').collect::<Vec<_>>()[..] else {
+                panic!("malformed corpus line {line:?}");
+            };
+            let err = Query::standard(query).unwrap_err();
+            assert_eq!(
+                (err.span.start, err.msg.as_str()),
+                (offset.parse().unwrap(), msg),
+                "{query:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn render_underlines_the_span() {
+        let query = "$[?count(@.a, @.b) > 1]";
+        let err = Query::standard(query).unwrap_err();
+        let rendered = err.render(query);
+        let caret = rendered.lines().last().unwrap();
+        assert_eq!(caret.trim_start().len(), err.span.len());
+        assert_eq!(caret.len() - caret.trim_start().len(), 2 + err.span.start);
+        assert!(rendered.starts_with("type error: count() takes 1 argument"));
+    }
+
+    #[test]
+    fn render_multi_line_and_non_ascii_queries() {
+        let query = "$['ü']\n  .a ]";
+        let err = Query::standard(query).unwrap_err();
+        assert_eq!(
+            err.render(query),
+            format!("syntax error: {}\n    .a ]\n       ^", err.msg)
+        );
+
+        let query = "$['ü'] x";
+        let err = Query::standard(query).unwrap_err();
+        assert_eq!(err.render(query).lines().last(), Some("         ^"));
+    }
+
+    #[test]
+    fn render_names_the_source_fragment() {
+        let err =
+            JSONPathError::syntax(String::from("oops"), Span::new(1, 3)).with_source("filter");
+        assert_eq!(
+            err.render("@.a"),
+            "syntax error: oops (in `filter`)\n  @.a\n   ^^"
+        );
+    }
+
+    #[test]
+    fn byte_order_mark() {
+        let err = Query::standard("\u{FEFF}$.a").unwrap_err();
+        assert_eq!(err.msg, "unexpected byte order mark");
+        assert_eq!(err.span.char_range(), 0..1);
+
+        let mut parser = Parser::new();
+        parser.lexer_options.skip_bom = true;
+        let query = parser.parse("\u{FEFF}$.a").unwrap();
+        assert_eq!(query.to_string(), "$['a']");
+
+        let err = parser.parse("\u{FEFF}$.a]").unwrap_err();
+        assert_eq!(err.span, Span::from(6..7).with_chars("\u{FEFF}$.a]"));
+    }
+
+    #[test]
+    fn control_characters() {
+        for (query, span) in [
+            ("$\u{0}", 1..2),
+            ("$.a\u{1}b", 3..4),
+            ("$[\u{1}0]", 2..3),
+            ("$..\u{2}", 3..4),
+            ("$[0:\u{3}]", 4..5),
+            ("$[?@.a\u{1}==1]", 6..7),
+            ("$[?@.a==\u{7F}1]", 8..9),
+        ] {
+            let err = Query::standard(query).unwrap_err();
+            assert_eq!(
+                (err.msg.as_str(), err.span),
+                (
+                    format!(
+                        "unexpected control character U+{:04X}",
+                        query[span.start..].chars().next().unwrap() as u32
+                    )
+                    .as_str(),
+                    Span::from(span)
+                ),
+                "{query:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_queries_point_at_the_end() {
+        for query in [
+            "$.",
+            "$..",
+            "$[",
+            "$[1",
+            "$[1:",
+            "$[?@.a ==",
+            "$[?length(@",
+            "$['a'",
+        ] {
+            let err = Query::standard(query).unwrap_err();
+            assert_eq!(err.span, Span::new(query.len(), query.len()), "{query:?}");
+        }
+    }
+
+    #[test]
+    fn token_streams_without_an_end_token() {
+        let parser = Parser::new();
+        for (query, keep) in [("$['é']", 2), ("$[1, 2]", 4), ("$[?@.a == 1]", 6)] {
+            let tokens = lex(query).unwrap();
+            let end = tokens[keep - 1].span;
+            let err = parser
+                .parse_tokens(tokens.into_iter().take(keep).collect())
+                .unwrap_err();
+            assert!(matches!(err.kind, JSONPathErrorType::SyntaxError));
+            assert_eq!(err.span.range(), end.end..end.end, "{query:?}");
+            assert_eq!(
+                err.span.char_range().start,
+                end.char_range().end,
+                "{query:?}"
+            );
+        }
+    }
 }
@@ -0,0 +1,80 @@
+use jsonpath_rfc9535::{EscapeMode, JSONPathErrorType, Parser, Query};
+
+/// Invalid `\u` escapes from the compliance test suite's name selector and
+/// string literal cases, with what lenient mode decodes them to, or `None`
+/// if they're invalid in lenient mode too.
+const INVALID: &[(&str, Option<&str>)] = &[
+    // single high surrogate
+    (r#"$["\uD800"]"#, Some("$['\u{FFFD}']")),
+    // single low surrogate
+    (r#"$["\uDC00"]"#, Some("$['\u{FFFD}']")),
+    // high high surrogate
+    (r#"$["\uD800\uD800"]"#, Some("$['\u{FFFD}\u{FFFD}']")),
+    // low high surrogate
+    (r#"$["\uDC00\uD800"]"#, Some("$['\u{FFFD}\u{FFFD}']")),
+    // surrogate non-surrogate
+    (r#"$["\uD800A"]"#, Some("$['\u{FFFD}A']")),
+    // non-surrogate surrogate
+    (r#"$["A\uDC00"]"#, Some("$['A\u{FFFD}']")),
+    // surrogate followed by an escaped non-surrogate
+    (r#"$["\uD800\u00E9"]"#, Some("$['\u{FFFD}\u{E9}']")),
+    (r#"$['\uDBFF']"#, Some("$['\u{FFFD}']")),
+    (r#"$[?@.a == "\uDFFF"]"#, Some("$[?@['a'] == \"\u{FFFD}\"]")),
+    // incomplete and non-hex escapes
+    (r#"$["\u00"]"#, None),
+    (r#"$["\uD800\u"]"#, None),
+    (r#"$["\uD800\uDC0"]"#, None),
+    (r#"$["\u+041"]"#, None),
+    (r#"$["\uGGGG"]"#, None),
+];
+
+const VALID: &[(&str, &str)] = &[
+    // surrogate pairs
+    (r#"$["\uD834\uDD1E"]"#, "$['\u{1D11E}']"),
+    (r#"$['\uD83D\uDE00']"#, "$['\u{1F600}']"),
+    // before high and after low surrogates
+    (r#"$["\uD7FF\uD7FF"]"#, "$['\u{D7FF}\u{D7FF}']"),
+    (r#"$["\uE000\uE000"]"#, "$['\u{E000}\u{E000}']"),
+    // consecutive escapes that aren't surrogates
+    (r#"$["\u0041\u0042"]"#, "$['AB']"),
+    (r#"$["\u263A"]"#, "$['\u{263A}']"),
+];
+
+#[test]
+fn strict_escapes() {
+    for (query, _) in INVALID {
+        let err = Query::standard(query).unwrap_err();
+        assert!(
+            matches!(err.kind, JSONPathErrorType::SyntaxError),
+            "{query}"
+        );
+    }
+
+    for (query, want) in VALID {
+        assert_eq!(Query::standard(query).unwrap().to_string(), *want);
+    }
+}
+
+#[test]
+fn lenient_escapes() {
+    let mut parser = Parser::new();
+    parser.escape_mode = EscapeMode::Lenient;
+
+    for (query, want) in INVALID {
+        match want {
+            Some(want) => assert_eq!(parser.parse(query).unwrap().to_string(), *want),
+            None => assert!(parser.parse(query).is_err(), "{query}"),
+        }
+    }
+
+    for (query, want) in VALID {
+        assert_eq!(parser.parse(query).unwrap().to_string(), *want);
+    }
+}
+
+#[test]
+fn unpaired_surrogate_span() {
+    let err = Query::standard(r#"$["\uD800A"]"#).unwrap_err();
+    assert_eq!(err.msg, "unpaired surrogate in \\uXXXX escape");
+    assert_eq!(err.span.start, 3);
+}
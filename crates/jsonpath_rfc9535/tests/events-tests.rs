@@ -0,0 +1,99 @@
+use jsonpath_rfc9535::{
+    events::{ParseSink, SegmentKind},
+    query::{FilterExpression, Selector},
+    Parser, Span,
+};
+
+/// Records each event as a string.
+#[derive(Default)]
+struct Recorder(Vec<String>);
+
+impl ParseSink<'_> for Recorder {
+    fn segment_start(&mut self, kind: SegmentKind, span: Span) {
+        self.0.push(format!("start {kind:?} {span}"));
+    }
+
+    fn selector(&mut self, selector: Selector) {
+        self.0
+            .push(format!("selector {selector} {}", selector.span()));
+    }
+
+    fn filter(&mut self, span: Span, expression: FilterExpression) {
+        self.0.push(format!("filter {expression} {span}"));
+    }
+
+    fn segment_end(&mut self, span: Span) {
+        self.0.push(format!("end {span}"));
+    }
+
+    fn end(&mut self) {
+        self.0.push(String::from("end of query"));
+    }
+}
+
+fn events(query: &str) -> (Vec<String>, bool) {
+    let mut recorder = Recorder::default();
+    let ok = Parser::new().parse_events(query, &mut recorder).is_ok();
+    (recorder.0, ok)
+}
+
+#[test]
+fn events_in_query_order() {
+    let (events, ok) = events("$.a..['b', 1:][?@.c > 1]");
+    assert!(ok);
+    assert_eq!(
+        events,
+        vec![
+            "start Child 1..2",
+            "selector 'a' 2..3",
+            "end 1..3",
+            "start Recursive 3..5",
+            "selector 'b' 6..9",
+            "selector 1::1 11..13",
+            "end 3..14",
+            "start Child 14..15",
+            "filter @['c'] > 1 15..23",
+            "end 14..24",
+            "end of query",
+        ]
+    );
+}
+
+#[test]
+fn just_root() {
+    assert_eq!(events("$"), (vec![String::from("end of query")], true));
+}
+
+#[test]
+fn errors_stop_events() {
+    let (events, ok) = events("$.a[?nosuchthing()]");
+    assert!(!ok);
+    assert_eq!(
+        events,
+        vec![
+            "start Child 1..2",
+            "selector 'a' 2..3",
+            "end 1..3",
+            "start Child 3..4"
+        ]
+    );
+}
+
+/// Counts selectors, relying on the default `filter` method.
+#[derive(Default)]
+struct Counter(usize);
+
+impl ParseSink<'_> for Counter {
+    fn selector(&mut self, _selector: Selector) {
+        self.0 += 1;
+    }
+}
+
+#[test]
+fn filters_are_selectors_by_default() {
+    let mut counter = Counter::default();
+    Parser::new()
+        .parse_events("$[?@.a, 0]..*", &mut counter)
+        .unwrap();
+    assert_eq!(counter.0, 3);
+}
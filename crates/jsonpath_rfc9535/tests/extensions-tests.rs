@@ -0,0 +1,87 @@
+use jsonpath_rfc9535::{
+    query::{Segment, Selector},
+    Extensions, JSONPathErrorType, Parser, Query,
+};
+
+fn parser() -> Parser {
+    Parser::with_extensions(Extensions::SINGULAR_QUERY_SELECTOR)
+}
+
+#[test]
+fn strict_by_default() {
+    let err = Query::standard("$.a.j[$['c d'].x.y]").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::SyntaxError));
+
+    let err = Parser::with_extensions(Extensions::NONE)
+        .parse("$.a[$.b]")
+        .unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::SyntaxError));
+}
+
+#[test]
+fn singular_query_selectors() {
+    let query = parser().parse("$.a.j[$['c d'].x.y]").unwrap();
+    assert_eq!(query.to_string(), "$['a']['j'][$['c d']['x']['y']]");
+
+    let Segment::Child { selectors, .. } = &query.segments[2] else {
+        panic!("expected a child segment");
+    };
+    let Selector::SingularQuery { span, query } = &selectors[0] else {
+        panic!("expected a singular query selector");
+    };
+    assert_eq!(span.range(), 6..18);
+    assert_eq!(query.segments.len(), 3);
+}
+
+#[test]
+fn singular_query_selectors_mix_with_other_selectors() {
+    let cases = [
+        ("$[$.a, 0, 'b']", "$[$['a'], 0, 'b']"),
+        ("$[ $.a[0] ]", "$[$['a'][0]]"),
+        ("$..[$.a]", "$..[$['a']]"),
+        ("$[$]", "$[$]"),
+        ("$[$[$.a]]", "$[$[$['a']]]"),
+        ("$[?@[$.k] == 1]", "$[?@[$['k']] == 1]"),
+        ("$[?@[$.k]][$.j]", "$[?@[$['k']]][$['j']]"),
+    ];
+
+    for (expr, want) in cases {
+        assert_eq!(parser().parse(expr).unwrap().to_string(), want, "{}", expr);
+    }
+}
+
+#[test]
+fn selector_queries_must_be_singular() {
+    for expr in [
+        "$[$.a[*]]",
+        "$[$..a]",
+        "$[$.a[0, 1]]",
+        "$[$.a[?@.b]]",
+        "$[@.a]",
+    ] {
+        let err = parser().parse(expr).unwrap_err();
+        assert!(
+            matches!(err.kind, JSONPathErrorType::SyntaxError),
+            "{}",
+            expr
+        );
+    }
+}
+
+#[test]
+fn singular_query_selectors_are_not_standard() {
+    let query = parser().parse("$.a[$.b]").unwrap();
+    let err = query.try_to_standard_string().unwrap_err();
+    assert_eq!(err.constructs.len(), 1);
+    assert_eq!(err.constructs[0].msg, "singular query selector");
+}
+
+#[test]
+fn grammar_includes_singular_query_selectors() {
+    let ebnf = parser().grammar().to_ebnf();
+    assert!(ebnf.contains("singular-query-selector ::= abs-singular-query"));
+    assert!(!Parser::new()
+        .grammar()
+        .to_ebnf()
+        .contains("singular-query-selector"));
+}
@@ -0,0 +1,139 @@
+use jsonpath_rfc9535::{
+    format::{FormatOptions, QuoteStyle},
+    Query,
+};
+
+fn format(query: &str, options: &FormatOptions) -> String {
+    let q = Query::standard(query).unwrap();
+    let rv = q.format(options);
+    // The result must parse back to the same query.
+    assert_eq!(Query::standard(&rv).unwrap().to_string(), q.to_string());
+    rv
+}
+
+fn shorthand() -> FormatOptions {
+    FormatOptions {
+        shorthand: true,
+        ..FormatOptions::default()
+    }
+}
+
+#[test]
+fn default_options() {
+    let options = FormatOptions::default();
+    assert_eq!(
+        format("$.a[0, -1]..b[*]", &options),
+        "$['a'][0, -1]..['b'][*]"
+    );
+    assert_eq!(
+        format("$[?@.a=='x'&&count($.b[*])>1.5]", &options),
+        "$[?@['a'] == 'x' && count($['b'][*]) > 1.5]"
+    );
+}
+
+#[test]
+fn shorthand_names() {
+    let options = shorthand();
+    assert_eq!(format("$['a']..['b'][*]..[*]", &options), "$.a..b.*..*");
+    assert_eq!(format("$['_x1', 'y'][0]", &options), "$['_x1', 'y'][0]");
+    assert_eq!(format("$['1a']['a b']['']", &options), "$['1a']['a b']['']");
+    assert_eq!(format("$['é']..['ü']", &options), "$.é..ü");
+    assert_eq!(
+        format("$[?@['a']['b'] == $['c']]", &options),
+        "$[?@.a.b == $.c]"
+    );
+    // Noncharacters are rejected in shorthands by strict parsers.
+    assert_eq!(format("$['a\u{FFFF}']", &options), "$['a\u{FFFF}']");
+}
+
+#[test]
+fn quote_styles() {
+    let query = r#"$["it's", 'say "hi"'][?@.a == 'b\\c\n']"#;
+    assert_eq!(
+        format(query, &FormatOptions::default()),
+        r#"$['it\'s', 'say "hi"'][?@['a'] == 'b\\c\n']"#
+    );
+
+    let options = FormatOptions {
+        quotes: QuoteStyle::Double,
+        ..FormatOptions::default()
+    };
+    assert_eq!(
+        format(query, &options),
+        r#"$["it's", "say \"hi\""][?@["a"] == "b\\c\n"]"#
+    );
+}
+
+#[test]
+fn slice_spacing() {
+    let query = "$[1:5:2, 1:, :5, ::2, 1::2, :]";
+    assert_eq!(
+        format(query, &FormatOptions::default()),
+        "$[1:5:2, 1:, :5, ::2, 1::2, :]"
+    );
+
+    let options = FormatOptions {
+        slice_spacing: true,
+        ..FormatOptions::default()
+    };
+    assert_eq!(
+        format(query, &options),
+        "$[1 : 5 : 2, 1 :, : 5, :: 2, 1 :: 2, :]"
+    );
+}
+
+#[test]
+fn operator_spacing() {
+    let options = FormatOptions {
+        operator_spacing: false,
+        ..shorthand()
+    };
+    assert_eq!(
+        format("$[?@.a == 1 && @.b >= 2.5 || !(@.c != 'x')]", &options),
+        "$[?@.a==1 && @.b>=2.5 || !(@.c!='x')]"
+    );
+}
+
+#[test]
+fn minimal_parentheses() {
+    let options = shorthand();
+    for (query, want) in [
+        ("$[?(@.a)]", "$[?@.a]"),
+        ("$[?(@.a && @.b) || @.c]", "$[?@.a && @.b || @.c]"),
+        ("$[?@.a && (@.b || @.c)]", "$[?@.a && (@.b || @.c)]"),
+        ("$[?(@.a || @.b) && @.c]", "$[?(@.a || @.b) && @.c]"),
+        // Chains of the same operator are grouped from the right.
+        ("$[?@.a || @.b || @.c]", "$[?@.a || @.b || @.c]"),
+        ("$[?(@.a || @.b) || @.c]", "$[?(@.a || @.b) || @.c]"),
+        ("$[?!(@.a && @.b)]", "$[?!(@.a && @.b)]"),
+        ("$[?!(@.a == 1)]", "$[?!(@.a == 1)]"),
+        ("$[?!(@.a)]", "$[?!@.a]"),
+        ("$[?!!@.a]", "$[?!!@.a]"),
+        ("$[?((@.a == 1))]", "$[?@.a == 1]"),
+        ("$[?@[?(@.b || @.c)]]", "$[?@[?@.b || @.c]]"),
+        ("$[?length(@.a) > 1]", "$[?length(@.a) > 1]"),
+    ] {
+        assert_eq!(format(query, &options), want, "{query}");
+    }
+}
+
+#[test]
+fn literals() {
+    assert_eq!(
+        format(
+            "$[?@.a == true || @.a == null || @.a == 1.0 || @.a == -2e300]",
+            &shorthand()
+        ),
+        "$[?@.a == true || @.a == null || @.a == 1.0 || @.a == -2e300]"
+    );
+}
+
+#[test]
+fn infinite_literals() {
+    let query = "$[?@.a < 1e400 && @.a > -1e400]";
+    assert_eq!(
+        Query::standard(query).unwrap().to_string(),
+        "$[?(@['a'] < 1e400 && @['a'] > -1e400)]"
+    );
+    assert_eq!(format(query, &shorthand()), query);
+}
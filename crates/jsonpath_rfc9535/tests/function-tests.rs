@@ -0,0 +1,83 @@
+use jsonpath_rfc9535::{
+    function::FunctionRegistry, ExpressionType, FunctionSignature, JSONPathErrorType, Parser,
+};
+
+type Function = dyn Fn(Vec<i64>) -> i64;
+
+fn signature(param_types: Vec<ExpressionType>, return_type: ExpressionType) -> FunctionSignature {
+    FunctionSignature {
+        param_types,
+        return_type,
+        deterministic: true,
+        pure: true,
+        cost: None,
+    }
+}
+
+fn registry() -> FunctionRegistry<Function> {
+    let mut functions: FunctionRegistry<Function> = FunctionRegistry::new();
+    functions.register(
+        "sum",
+        signature(
+            vec![ExpressionType::Value, ExpressionType::Value],
+            ExpressionType::Value,
+        ),
+        Box::new(|args| args.iter().sum()),
+    );
+    functions.register(
+        "neg",
+        signature(vec![ExpressionType::Value], ExpressionType::Value),
+        Box::new(|args| -args[0]),
+    );
+    functions
+}
+
+#[test]
+fn implementations_are_registered_with_signatures() {
+    let functions = registry();
+
+    assert_eq!(functions.len(), 2);
+    assert_eq!(functions.get("sum").unwrap()(vec![1, 2, 3]), 6);
+    assert_eq!(functions.get("neg").unwrap()(vec![4]), -4);
+    assert!(functions.get("nosuchthing").is_none());
+
+    let sum = functions.signature("sum").unwrap();
+    assert_eq!(sum.param_types.len(), 2);
+    assert_eq!(sum.return_type, ExpressionType::Value);
+
+    let mut names: Vec<&str> = functions.iter().map(|(name, _, _)| name).collect();
+    names.sort();
+    assert_eq!(names, vec!["neg", "sum"]);
+}
+
+#[test]
+fn registering_replaces_existing_functions() {
+    let mut functions = registry();
+    functions.register(
+        "neg",
+        signature(vec![ExpressionType::Value], ExpressionType::Value),
+        Box::new(|args| args[0]),
+    );
+    assert_eq!(functions.len(), 2);
+    assert_eq!(functions.get("neg").unwrap()(vec![4]), 4);
+
+    assert!(functions.remove("neg"));
+    assert!(!functions.remove("neg"));
+    assert!(!functions.contains("neg"));
+}
+
+#[test]
+fn parsers_check_registered_signatures() {
+    let functions = registry();
+    let mut parser = Parser::new();
+
+    let err = parser.parse("$[?sum(@.a, 1) == 2]").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::NameError));
+
+    parser.add_functions(&functions);
+    assert!(parser.parse("$[?sum(@.a, 1) == neg(2)]").is_ok());
+    assert!(parser.parse("$[?length(@.a) == 2]").is_ok());
+
+    let err = parser.parse("$[?sum(@.a) == 2]").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::TypeError));
+}
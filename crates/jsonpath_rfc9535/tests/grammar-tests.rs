@@ -0,0 +1,145 @@
+use std::collections::HashSet;
+
+use jsonpath_rfc9535::{
+    grammar::{Expr, Grammar},
+    ExpressionType, Parser,
+};
+
+fn references<'g>(expr: &'g Expr, names: &mut HashSet<&'g str>) {
+    match expr {
+        Expr::Rule(name) => {
+            names.insert(name);
+        }
+        Expr::Sequence(items) | Expr::Choice(items) => {
+            items.iter().for_each(|item| references(item, names))
+        }
+        Expr::Optional(item) | Expr::ZeroOrMore(item) | Expr::OneOrMore(item) => {
+            references(item, names)
+        }
+        Expr::Literal(_) | Expr::Range(..) => (),
+    }
+}
+
+fn undefined(grammar: &Grammar) -> Vec<String> {
+    let mut names = HashSet::new();
+    grammar
+        .rules
+        .iter()
+        .for_each(|rule| references(&rule.expr, &mut names));
+    let mut undefined: Vec<String> = names
+        .into_iter()
+        .filter(|name| grammar.rule(name).is_none())
+        .map(String::from)
+        .collect();
+    undefined.sort();
+    undefined
+}
+
+#[test]
+fn standard_function_rules() {
+    let ebnf = Parser::new().grammar().to_ebnf();
+    assert!(ebnf.contains(r#"length-function-expr ::= "length" "(" S value-argument S ")""#));
+    assert!(ebnf.contains(
+        r#"match-function-expr ::= "match" "(" S value-argument S "," S value-argument S ")""#
+    ));
+    assert!(ebnf.contains(
+        "comparable ::= literal | singular-query | count-function-expr | length-function-expr | value-function-expr"
+    ));
+    assert!(ebnf.contains(
+        "test-expr ::= (logical-not-op S)? (filter-query | match-function-expr | search-function-expr)"
+    ));
+}
+
+#[test]
+fn function_extensions() {
+    let mut parser = Parser::new();
+    parser.add_function(
+        "is_even",
+        vec![ExpressionType::Value],
+        ExpressionType::Logical,
+    );
+
+    let grammar = parser.grammar();
+    let rule = grammar.rule("is_even-function-expr").unwrap();
+    assert_eq!(
+        rule.expr.to_string(),
+        r#""is_even" "(" S value-argument S ")""#
+    );
+    assert!(grammar
+        .rule("test-expr")
+        .unwrap()
+        .expr
+        .to_string()
+        .contains("is_even-function-expr"));
+}
+
+#[test]
+fn removed_functions() {
+    let mut parser = Parser::new();
+    parser.functions.remove("search");
+    let grammar = parser.grammar();
+    assert!(grammar.rule("search-function-expr").is_none());
+    assert_eq!(undefined(&grammar), Vec::<String>::new());
+}
+
+#[test]
+fn every_rule_is_defined() {
+    assert_eq!(undefined(&Parser::new().grammar()), Vec::<String>::new());
+}
+
+#[test]
+fn index_range_comment() {
+    let mut parser = Parser::new();
+    parser.index_range = -100..=100;
+    let ebnf = parser.grammar().to_ebnf();
+    assert!(ebnf.contains("/* An integer from -100 to 100. */\nindex-selector ::= int\n"));
+}
+
+#[test]
+fn limit_comment() {
+    let mut parser = Parser::new();
+    assert!(parser
+        .grammar()
+        .rule("jsonpath-query")
+        .unwrap()
+        .comment
+        .is_none());
+
+    parser.limits.max_query_length = 256;
+    assert_eq!(
+        parser
+            .grammar()
+            .rule("jsonpath-query")
+            .unwrap()
+            .comment
+            .as_deref(),
+        Some("A query is at most 256 bytes long.")
+    );
+
+    parser.limits.max_string_length = 64;
+    assert!(parser
+        .grammar()
+        .to_ebnf()
+        .contains("/* At most 64 bytes between quotes. */\nstring-literal ::= "));
+}
+
+#[test]
+fn character_ranges() {
+    let ebnf = Parser::new().grammar().to_ebnf();
+    assert!(ebnf.contains("B ::= #x20 | #x09 | #x0A | #x0D\n"));
+    assert!(ebnf.contains("ALPHA ::= [#x41-#x5A] | [#x61-#x7A]\n"));
+    assert!(ebnf.contains(r#"ESC ::= "\""#));
+    assert!(ebnf.contains(r#"double-quoted ::= unescaped | "'" | ESC '"' | ESC escapable"#));
+}
+
+#[test]
+fn railroad_json() {
+    let json = Parser::new().grammar().to_railroad_json();
+    assert!(json.starts_with(
+        r#"{"rules":[{"name":"jsonpath-query","diagram":{"type":"Diagram","items":[{"type":"Sequence","items":[{"type":"NonTerminal","text":"root-identifier"},{"type":"NonTerminal","text":"segments"}]}]}},"#
+    ));
+    assert!(json.contains(
+        r#"{"name":"ESC","diagram":{"type":"Diagram","items":[{"type":"Terminal","text":"\\"}]}}"#
+    ));
+    assert!(json.ends_with("]}"));
+}
@@ -0,0 +1,131 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use jsonpath_rfc9535::{
+    lint::{Linter, Severity},
+    Span,
+};
+
+fn rules(query: &str) -> Vec<&'static str> {
+    Linter::new().lint(query).iter().map(|d| d.rule).collect()
+}
+
+#[test]
+fn clean_query() {
+    assert!(Linter::new().lint("$.users[?@.age > 18].name").is_empty());
+}
+
+#[test]
+fn parse_errors() {
+    let diagnostics = Linter::new().lint("$.users[?@.age >]");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "syntax");
+    assert_eq!(diagnostics[0].severity, Severity::Error);
+
+    assert_eq!(rules("$[?foo(@)]"), vec!["name"]);
+    assert_eq!(rules("$[?length(@.*) > 1]"), vec!["type"]);
+}
+
+#[test]
+fn duplicate_selectors() {
+    let diagnostics = Linter::new().lint("$['a', 'b', 'a'][0, 1, 0]");
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].rule, "duplicate-selector");
+    assert_eq!(diagnostics[0].severity, Severity::Warning);
+    assert_eq!(diagnostics[0].span, Span::new(12, 15));
+    assert_eq!(
+        diagnostics[1].msg,
+        "selector 0 appears more than once in the same segment"
+    );
+
+    // Including in nested queries.
+    assert_eq!(rules("$[?@[0, 0]]"), vec!["duplicate-selector"]);
+    assert!(rules("$['a'].a").is_empty());
+}
+
+#[test]
+fn constant_comparisons() {
+    let diagnostics = Linter::new().lint("$[?@.a && 1 == 1]");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].rule, "constant-comparison");
+    assert_eq!(diagnostics[0].span, Span::new(10, 16));
+    assert!(rules("$[?@.a == 1]").is_empty());
+}
+
+#[test]
+fn complexity() {
+    let linter = Linter::new();
+    assert!(linter.lint("$[?@[?@[?@]]]").is_empty());
+    assert_eq!(rules("$[?@[?@[?@[?@]]]]"), vec!["complexity"]);
+    assert_eq!(rules("$[?count(@[?@[?@]]) > 1]"), vec!["complexity"]);
+
+    assert!(linter.lint("$..a..b").is_empty());
+    let diagnostics = linter.lint("$..a[?@..b]..c");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].msg, "3 descendant segments (limit 2)");
+}
+
+#[test]
+fn char_offsets() {
+    let diagnostics = Linter::new().lint("$['é', 'é']");
+    assert_eq!(diagnostics[0].span.char_range(), 7..10);
+}
+
+fn run(args: &[&str], input: &str) -> (i32, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jsonpath-lint"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+const QUERIES: &str = "# stored queries\n$.a\n\n$['a', 'a']\n$[?@.a ==]\n";
+
+#[test]
+fn binary_human_output() {
+    let (status, out) = run(&[], QUERIES);
+    assert_eq!(status, 1);
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("-:4:8: warning[duplicate-selector]: "));
+    assert!(lines[1].starts_with("-:5:"));
+    assert!(lines[1].contains("error[syntax]"));
+
+    let (status, out) = run(&["-"], "$.a\n$['a', 'a']\n");
+    assert_eq!(status, 0);
+    assert_eq!(out.lines().count(), 1);
+}
+
+#[test]
+fn binary_json_output() {
+    let (status, out) = run(&["--format", "json"], "$['a', 'a']\n");
+    assert_eq!(status, 0);
+    assert!(out.starts_with(r#"{"version":"2.1.0","runs":[{"tool":{"driver":{"name":"jsonpath-lint"}},"results":[{"ruleId":"duplicate-selector","level":"warning","#));
+    assert!(out.contains(r#""region":{"startLine":1,"startColumn":8,"endColumn":11}"#));
+
+    let (_, out) = run(&["--format", "json"], "$.a\n");
+    assert_eq!(
+        out.trim_end(),
+        r#"{"version":"2.1.0","runs":[{"tool":{"driver":{"name":"jsonpath-lint"}},"results":[]}]}"#
+    );
+}
+
+#[test]
+fn binary_missing_file() {
+    let (status, _) = run(&["does-not-exist.jsonpath"], "");
+    assert_eq!(status, 2);
+}
@@ -0,0 +1,52 @@
+use std::cmp::Ordering::{self, Equal, Greater, Less};
+
+use jsonpath_rfc9535::number::{compare, Number};
+
+fn int_float(int: i128, float: f64) -> Option<Ordering> {
+    compare(Number::Int(int), Number::Float(float))
+}
+
+#[test]
+fn integers_above_2_pow_53_compare_exactly() {
+    let big = 1_i128 << 53;
+    assert_eq!(int_float(big, big as f64), Some(Equal));
+    assert_eq!(int_float(big + 1, big as f64), Some(Greater));
+    assert_eq!(int_float(-big - 1, -big as f64), Some(Less));
+    assert_eq!(
+        compare(Number::Float(big as f64), Number::Int(big + 1)),
+        Some(Less)
+    );
+}
+
+#[test]
+fn extremes() {
+    let u64_max = i128::from(u64::MAX);
+    let i64_min = i128::from(i64::MIN);
+    assert_eq!(int_float(u64_max, 18446744073709551616.0), Some(Less));
+    assert_eq!(int_float(u64_max, 18446744073709549568.0), Some(Greater));
+    assert_eq!(int_float(i64_min, -9223372036854775808.0), Some(Equal));
+    assert_eq!(int_float(i64_min, -9223372036854777856.0), Some(Greater));
+    assert_eq!(int_float(i128::MAX, 1e300), Some(Less));
+    assert_eq!(int_float(i128::MIN, -1e300), Some(Greater));
+    assert_eq!(int_float(0, f64::INFINITY), Some(Less));
+    assert_eq!(int_float(0, f64::NEG_INFINITY), Some(Greater));
+}
+
+#[test]
+fn fractions_break_ties() {
+    assert_eq!(int_float(1, 1.5), Some(Less));
+    assert_eq!(int_float(1, 0.5), Some(Greater));
+    assert_eq!(int_float(-1, -1.5), Some(Greater));
+    assert_eq!(int_float(-1, -0.5), Some(Less));
+    assert_eq!(int_float(0, -0.0), Some(Equal));
+}
+
+#[test]
+fn nan_is_unordered() {
+    assert_eq!(int_float(0, f64::NAN), None);
+    assert_eq!(compare(Number::Float(f64::NAN), Number::Int(0)), None);
+    assert_eq!(
+        compare(Number::Float(f64::NAN), Number::Float(f64::NAN)),
+        None
+    );
+}
@@ -0,0 +1,146 @@
+use jsonpath_rfc9535::{optimize::optimize, ExpressionType, Parser, Query};
+
+fn optimized(expr: &str) -> String {
+    let query = Query::standard(expr).unwrap().optimized();
+    let text = query.try_to_standard_string().unwrap();
+    assert!(
+        Query::standard(&text).is_ok(),
+        "{} optimized as {}",
+        expr,
+        text
+    );
+    query.to_string()
+}
+
+#[test]
+fn queries_without_constants_are_unchanged() {
+    for expr in [
+        "$",
+        "$.a.b[0]",
+        "$..a[1:3]",
+        "$[?@.a > 1]",
+        "$[?count(@.*) == $.n]",
+    ] {
+        let query = Query::standard(expr).unwrap();
+        assert_eq!(optimized(expr), query.to_string());
+    }
+}
+
+#[test]
+fn fold_comparisons_of_literals() {
+    assert_eq!(optimized("$[?@.a && 1 == 1]"), "$[?@['a']]");
+    assert_eq!(optimized("$[?@.a || 1 == 2]"), "$[?@['a']]");
+    assert_eq!(optimized("$[?@.a && 1 == 1.0 && 'a' == 'a']"), "$[?@['a']]");
+    assert_eq!(optimized("$[?@.a && null == null]"), "$[?@['a']]");
+    assert_eq!(optimized("$[?@.a || true != false]"), "$[*]");
+    assert_eq!(
+        optimized("$[?@.a || (1 > 'a' || null < null)]"),
+        "$[?@['a']]"
+    );
+    assert_eq!(optimized("$[?@.a || !(2 <= 1)]"), "$[*]");
+}
+
+#[test]
+fn string_ordering_is_not_folded() {
+    // Strings order by the collation a query is evaluated with.
+    for expr in ["$[?'a' < 'b']", "$[?'b' >= 'a']", "$[?'a' <= 'a']"] {
+        let query = Query::standard(expr).unwrap();
+        assert_eq!(optimized(expr), query.to_string());
+    }
+    assert_eq!(optimized("$[?'a' != 'b']"), "$[*]");
+}
+
+#[test]
+fn always_true_filters_become_wildcards() {
+    assert_eq!(optimized("$[?1 == 1]"), "$[*]");
+    assert_eq!(optimized("$.a[?1 == 1, 0]"), "$['a'][*, 0]");
+    assert_eq!(optimized("$..[?'x' == 'x']"), "$..[*]");
+}
+
+#[test]
+fn always_false_filters_are_removed() {
+    assert_eq!(optimized("$.a[?1 == 2, 0]"), "$['a'][0]");
+    assert_eq!(optimized("$.a[?@.b && 1 == 2, ?@.c]"), "$['a'][?@['c']]");
+}
+
+#[test]
+fn queries_that_select_nothing_are_short_circuited() {
+    assert_eq!(optimized("$.a[?1 == 2].b.c"), "$[0:0:1]");
+    assert_eq!(optimized("$..a[?1 == 2, ?true != true]..b"), "$[0:0:1]");
+    assert_eq!(optimized("$.a[2:2].b"), "$[0:0:1]");
+    assert_eq!(optimized("$.a[2:2, 0].b"), "$['a'][0]['b']");
+}
+
+#[test]
+fn empty_slices_are_removed() {
+    for expr in [
+        "$[3:1]",
+        "$[-1:-3]",
+        "$[::0]",
+        "$[1:3:0]",
+        "$[:0]",
+        "$[1:3:-1]",
+        "$[-3:-1:-1]",
+        "$[:-1:-1]",
+    ] {
+        assert_eq!(optimized(expr), "$[0:0:1]", "{}", expr);
+    }
+    for expr in [
+        "$[1:]",
+        "$[-1:2]",
+        "$[2:-1]",
+        "$[::-1]",
+        "$[3:1:-1]",
+        "$[:-3:-1]",
+    ] {
+        let query = Query::standard(expr).unwrap();
+        assert_eq!(optimized(expr), query.to_string(), "{}", expr);
+    }
+}
+
+#[test]
+fn single_element_slices_become_indices() {
+    assert_eq!(optimized("$[5:6]"), "$[5]");
+    assert_eq!(optimized("$[:1]"), "$[0]");
+    assert_eq!(optimized("$[5:6:3]"), "$[5]");
+    assert_eq!(optimized("$[-3:-2]"), "$[-3]");
+    assert_eq!(optimized("$[5:4:-1]"), "$[5]");
+    assert_eq!(optimized("$[:-2:-1]"), "$[-1]");
+    assert_eq!(optimized("$.a[0:100][5]"), "$['a'][0:100:1][5]");
+    assert_eq!(optimized("$[-1:0]"), "$[-1:0:1]");
+    assert_eq!(optimized("$[0:-1:-1]"), "$[0:-1:-1]");
+}
+
+#[test]
+fn embedded_queries_are_optimized() {
+    assert_eq!(optimized("$[?@.a[?1 == 1]]"), "$[?@['a'][*]]");
+    assert_eq!(optimized("$[?@.a[?1 == 2]]"), "$[0:0:1]");
+    assert_eq!(optimized("$[?!@.a[?1 == 2]]"), "$[*]");
+    assert_eq!(optimized("$[?@.b || $.a[?1 == 2]]"), "$[?@['b']]");
+    assert_eq!(
+        optimized("$[?count(@.a[?1 == 2]) == 0]"),
+        "$[?count(@[0:0:1]) == 0]"
+    );
+}
+
+#[test]
+fn logical_function_arguments_are_not_folded_to_literals() {
+    let mut parser = Parser::new();
+    parser.add_function("f", vec![ExpressionType::Logical], ExpressionType::Logical);
+    let query = parser.parse("$[?f(1 == 1)]").unwrap();
+    assert_eq!(optimize(query).to_string(), "$[?f(1 == 1)]");
+
+    let query = parser.parse("$[?f(@.a && 1 == 1)]").unwrap();
+    assert_eq!(optimize(query).to_string(), "$[?f(@['a'])]");
+}
+
+#[test]
+fn labels_are_kept() {
+    let query = Query::standard("$.a")
+        .unwrap()
+        .labelled("scope")
+        .concat(Query::standard("$[?1 == 2]").unwrap().labelled("filter"))
+        .optimized();
+    assert_eq!(query.to_string(), "$[0:0:1]");
+    assert_eq!(query.source_of(0), Some("filter"));
+}
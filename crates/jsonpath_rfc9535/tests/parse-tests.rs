@@ -46,6 +46,7 @@ parse_tests! {
     filter_string_literal: ("$.some[?(@.thing == 'foo')]", "$['some'][?@['thing'] == \"foo\"]"),
     filter_integer_literal: ("$.some[?(@.thing == 1)]", "$['some'][?@['thing'] == 1]"),
     filter_float_literal: ("$.some[?(@.thing == 1.1)]", "$['some'][?@['thing'] == 1.1]"),
+    filter_out_of_range_int_literal: ("$[?@ == 1e19]", "$[?@ == 10000000000000000000]"),
     filter_logical_not: (
         "$.some[?(@.thing > 1 && !$.other)]",
         "$['some'][?(@['thing'] > 1 && !$['other'])]"
@@ -76,3 +77,379 @@ parse_tests! {
     ),
     function_count: ("$[?count(@..*)>2]", "$[?count(@..[*]) > 2]"),
 }
+
+mod deterministic {
+    use jsonpath_rfc9535::{ExpressionType, FunctionSignature, JSONPathErrorType, Parser};
+
+    use super::*;
+
+    fn parser() -> Parser {
+        let mut parser = Parser::new();
+        parser
+            .try_add_function(
+                "now",
+                FunctionSignature::new(vec![], ExpressionType::Value).with_deterministic(false),
+            )
+            .unwrap();
+        parser.add_function("upper", vec![ExpressionType::Value], ExpressionType::Value);
+        parser
+    }
+
+    #[test]
+    fn standard_functions_are_deterministic() -> Result<(), JSONPathError> {
+        let query = Query::standard("$[?count(@.*) > 1 && match(@.a, 'b.*')]")?;
+        assert!(query.is_deterministic());
+        Ok(())
+    }
+
+    #[test]
+    fn non_deterministic_function() -> Result<(), JSONPathError> {
+        assert!(!parser().parse("$[?@.t < now()]")?.is_deterministic());
+        assert!(parser().parse("$[?upper(@.t) == 'A']")?.is_deterministic());
+        Ok(())
+    }
+
+    #[test]
+    fn non_deterministic_argument() -> Result<(), JSONPathError> {
+        assert!(!parser()
+            .parse("$[?upper(now()) == 'A']")?
+            .is_deterministic());
+        Ok(())
+    }
+
+    #[test]
+    fn non_deterministic_nested_query() -> Result<(), JSONPathError> {
+        assert!(!parser()
+            .parse("$..a[?@[?!(@.t > now())]]")?
+            .is_deterministic());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_function_names() {
+        let mut parser = Parser::new();
+        for name in ["", "Upper", "1st", "a-b", "_a"] {
+            let err = parser
+                .try_add_function(name, FunctionSignature::new(vec![], ExpressionType::Value))
+                .unwrap_err();
+            assert_eq!(err.kind, JSONPathErrorType::NameError, "{name:?}");
+        }
+        assert_eq!(parser.functions.len(), Parser::new().functions.len());
+    }
+}
+
+mod fingerprint {
+    use jsonpath_rfc9535::query::fingerprint;
+
+    use super::*;
+
+    #[test]
+    fn fnv1a() {
+        assert_eq!(fingerprint(""), 0xcbf29ce484222325);
+        assert_eq!(fingerprint("a"), 0xaf63dc4c8601ec8c);
+    }
+
+    #[test]
+    fn equivalent_queries() -> Result<(), JSONPathError> {
+        assert_eq!(
+            Query::standard("$.a[?@.b]")?.fingerprint(),
+            Query::standard("$['a'][?@['b']]")?.fingerprint()
+        );
+        assert_ne!(
+            Query::standard("$.a")?.fingerprint(),
+            Query::standard("$.b")?.fingerprint()
+        );
+        Ok(())
+    }
+}
+
+mod strict_names {
+    use jsonpath_rfc9535::Parser;
+
+    use super::*;
+
+    fn parser() -> Parser {
+        let mut parser = Parser::new();
+        parser.lexer_options.strict_names = true;
+        parser
+    }
+
+    // Name shorthand queries from the compliance test suite are unaffected.
+    #[test]
+    fn compliance_test_suite() -> Result<(), JSONPathError> {
+        for query in ["$.a", "$.☺", "$._", "$.c", "$.*.a", "$..a"] {
+            assert_eq!(
+                parser().parse(query)?.to_string(),
+                Query::standard(query)?.to_string()
+            );
+        }
+
+        for query in ["$.&", "$.1"] {
+            assert!(parser().parse(query).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn boundaries() -> Result<(), JSONPathError> {
+        for query in [
+            "$.\u{80}",
+            "$.\u{D7FF}",
+            "$.\u{E000}",
+            "$.\u{FDCF}",
+            "$.\u{FDF0}",
+            "$.\u{FFFD}",
+            "$.\u{10FFFD}",
+        ] {
+            parser().parse(query)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn noncharacters() -> Result<(), JSONPathError> {
+        for (query, offset) in [
+            ("$.\u{FDD0}", 2),
+            ("$.a\u{FDEF}", 3),
+            ("$..\u{FFFE}", 3),
+            ("$.a\u{FFFF}b", 3),
+            ("$.\u{1FFFE}", 2),
+            ("$.\u{10FFFF}", 2),
+            ("$[?@.\u{FDD0} == 1]", 5),
+        ] {
+            let err = parser().parse(query).unwrap_err();
+            assert_eq!(err.span.start, offset, "{query:?}");
+            assert!(err.msg.starts_with("noncharacter U+"), "{}", err.msg);
+
+            // Allowed by default, as RFC 9535 allows them.
+            Query::standard(query)?;
+        }
+
+        // Quoted names are not affected.
+        parser().parse("$['\u{FDD0}']")?;
+        Ok(())
+    }
+}
+
+mod embedded_root_queries {
+    use jsonpath_rfc9535::Span;
+
+    use super::*;
+
+    fn embedded(expr: &str) -> Result<Vec<(String, String)>, JSONPathError> {
+        let query = Query::standard(expr)?;
+        Ok(query
+            .embedded_root_queries()
+            .into_iter()
+            .map(|(span, query)| (expr[span.range()].to_owned(), query.to_string()))
+            .collect())
+    }
+
+    #[test]
+    fn no_filters() -> Result<(), JSONPathError> {
+        assert!(embedded("$..password")?.is_empty());
+        assert!(embedded("$[?@.a == 1]")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn comparison() -> Result<(), JSONPathError> {
+        assert_eq!(
+            embedded("$.items[?@.price < $.limit]")?,
+            vec![("$.limit".to_owned(), "$['limit']".to_owned())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn bracketed_and_descendant() -> Result<(), JSONPathError> {
+        assert_eq!(
+            embedded("$[?@.a == $['x'][0] || count($..password) > 0]")?,
+            vec![
+                ("$['x'][0]".to_owned(), "$['x'][0]".to_owned()),
+                ("$..password".to_owned(), "$..['password']".to_owned()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn nested() -> Result<(), JSONPathError> {
+        assert_eq!(
+            embedded("$[?@[?!$.a[?$.b]]]")?,
+            vec![
+                ("$.a[?$.b]".to_owned(), "$['a'][?$['b']]".to_owned()),
+                ("$.b".to_owned(), "$['b']".to_owned()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn non_ascii_span() -> Result<(), JSONPathError> {
+        let expr = "$[?@.ü == $.ö]";
+        let query = Query::standard(expr)?;
+        let (span, _) = query.embedded_root_queries()[0];
+        assert_eq!(span, Span::from(11..15).with_chars(expr));
+        assert_eq!(span.char_range(), 10..13);
+        Ok(())
+    }
+}
+
+mod clone {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+
+    #[test]
+    fn clone_query() -> Result<(), JSONPathError> {
+        let query = Query::standard("$.a[?@.b > 1 && match(@.c, 'x.*')][1:3]..d")?;
+        let expected = query.to_string();
+        let copy = query.clone();
+        drop(query);
+        assert_eq!(copy.to_string(), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn share_between_threads() -> Result<(), JSONPathError> {
+        let query = Arc::new(Query::standard("$..[?@.a == $.b]")?);
+        let expected = query.to_string();
+
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let query = Arc::clone(&query);
+                thread::spawn(move || query.to_string())
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+        Ok(())
+    }
+}
+
+mod subexpressions {
+    use jsonpath_rfc9535::{
+        query::{FilterExpression, Segment, Selector},
+        Extensions, Parser, Span,
+    };
+
+    use super::*;
+
+    fn root_query(expr: &str) -> FilterExpression<'static> {
+        FilterExpression::RootQuery {
+            span: Span::default(),
+            query: Box::new(Query::standard(expr).unwrap()),
+        }
+    }
+
+    fn relative_query(expr: &str) -> FilterExpression<'static> {
+        FilterExpression::RelativeQuery {
+            span: Span::default(),
+            query: Box::new(Query::standard(expr).unwrap()),
+        }
+    }
+
+    fn found(expr: &str, pattern: &FilterExpression) -> Vec<String> {
+        Query::standard(expr)
+            .unwrap()
+            .find_subexpressions(pattern)
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    fn segment(expr: &str) -> Segment<'static> {
+        Query::standard(expr).unwrap().segments.remove(0)
+    }
+
+    #[test]
+    fn anchored_root_queries() {
+        let pattern = root_query("$.user.ssn");
+        assert_eq!(
+            found(
+                "$[?@.id == $.user.ssn || @.id == $.user.ssn.last4]",
+                &pattern
+            ),
+            vec!["$['user']['ssn']", "$['user']['ssn']['last4']"]
+        );
+        assert!(found("$[?@.id == $.admin.user.ssn]", &pattern).is_empty());
+        assert!(found("$[?@.id == $.user]", &pattern).is_empty());
+        assert!(found("$.user.ssn", &pattern).is_empty());
+    }
+
+    #[test]
+    fn relative_and_root_queries_differ() {
+        assert_eq!(
+            found("$[?@.ssn && $.ssn]", &relative_query("$.ssn")),
+            vec!["@['ssn']"]
+        );
+    }
+
+    #[test]
+    fn nested_filters() {
+        assert_eq!(
+            found("$.a[?@.b[?@.c == $.secret]]", &root_query("$.secret")),
+            vec!["$['secret']"]
+        );
+        assert_eq!(
+            found("$[?count(@..[?$.secret]) > 0]", &root_query("$.secret")),
+            vec!["$['secret']"]
+        );
+    }
+
+    #[test]
+    fn other_expressions() {
+        let pattern = Query::standard("$[?@.age > 18]").unwrap();
+        let Some(Segment::Child { selectors, .. }) = pattern.segments.first() else {
+            panic!("expected a child segment");
+        };
+        let Selector::Filter { expression, .. } = &selectors[0] else {
+            panic!("expected a filter selector");
+        };
+
+        assert_eq!(
+            found("$.users[?@['age']>18 && @.active].name", expression),
+            vec!["@['age'] > 18"]
+        );
+        assert!(found("$.users[?@.age > 21]", expression).is_empty());
+    }
+
+    #[test]
+    fn segments() {
+        let ssn = segment("$.ssn");
+        assert!(Query::standard("$.user.ssn")
+            .unwrap()
+            .contains_segment(&ssn));
+        assert!(Query::standard("$.users[?@.ssn]")
+            .unwrap()
+            .contains_segment(&ssn));
+        assert!(Query::standard("$.users[?@.id == $.admin['ssn']]")
+            .unwrap()
+            .contains_segment(&ssn));
+        assert!(!Query::standard("$.user..ssn")
+            .unwrap()
+            .contains_segment(&ssn));
+        assert!(Query::standard("$.user..ssn")
+            .unwrap()
+            .contains_segment(&segment("$..ssn")));
+    }
+
+    #[test]
+    fn singular_query_selectors() {
+        let parser = Parser::with_extensions(Extensions::ALL);
+        let query = parser.parse("$.users[$.current.ssn]").unwrap();
+        assert!(query.contains_segment(&segment("$.ssn")));
+    }
+
+    #[test]
+    fn starts_with() {
+        let query = Query::standard("$.user.ssn").unwrap();
+        assert!(query.starts_with(&Query::standard("$").unwrap()));
+        assert!(query.starts_with(&Query::standard("$['user']").unwrap()));
+        assert!(!query.starts_with(&Query::standard("$.ssn").unwrap()));
+        assert!(!query.starts_with(&Query::standard("$.user.ssn.last4").unwrap()));
+    }
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "serde")]
+
+use jsonpath_rfc9535::{
+    query::{FilterExpression, Segment, Selector},
+    Query,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+fn is_serde<T: Serialize + DeserializeOwned>() {}
+
+#[test]
+fn syntax_tree_types_implement_serde() {
+    is_serde::<Query>();
+    is_serde::<Segment>();
+    is_serde::<Selector>();
+    is_serde::<FilterExpression>();
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn round_trip_with_any_serde_format() {
+    for expr in [
+        "$.a['b'][0, -1][1:5:2][::-1].*..c..*",
+        "$[?@.a == 'b' && !(@.c < 1.5 || @.d != null)]",
+        "$[?count(@..*) > 2 && match(@.name, '^a.*')]",
+        "$.a[?$.b == @.c][?value(@.d) == true]",
+    ] {
+        let query = Query::standard(expr).unwrap();
+        let bytes = postcard::to_allocvec(&query).unwrap();
+        let decoded: Query = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", query), "{}", expr);
+    }
+}
@@ -0,0 +1,86 @@
+use jsonpath_rfc9535::{query::Source, ExpressionType, JSONPathErrorType, Parser, Query};
+
+fn extended_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.add_function("foo", vec![ExpressionType::Nodes], ExpressionType::Logical);
+    parser
+}
+
+#[test]
+fn concat_keeps_labels() {
+    let base = Query::standard("$.users[*]").unwrap().labelled("base");
+    let scope = Query::standard("$.name").unwrap().labelled("scope");
+    let query = base.concat(scope);
+
+    assert_eq!(query.to_string(), "$['users'][*]['name']");
+    assert_eq!(
+        query.sources,
+        vec![
+            Source {
+                label: String::from("base"),
+                segments: 0..2,
+            },
+            Source {
+                label: String::from("scope"),
+                segments: 2..3,
+            },
+        ]
+    );
+    assert_eq!(query.source_of(1), Some("base"));
+    assert_eq!(query.source_of(2), Some("scope"));
+    assert_eq!(query.source_of(3), None);
+}
+
+#[test]
+fn concat_unlabelled() {
+    let query = Query::standard("$.a")
+        .unwrap()
+        .concat(Query::standard("$.b").unwrap().labelled("b"));
+    assert_eq!(query.source_of(0), None);
+    assert_eq!(query.source_of(1), Some("b"));
+}
+
+#[test]
+fn typecheck_well_typed() {
+    let query = Query::standard("$[?length(@.a) > 1]").unwrap();
+    assert!(Parser::new().typecheck(&query).is_ok());
+}
+
+#[test]
+fn typecheck_against_a_different_registry() {
+    let base = Query::standard("$.users").unwrap().labelled("base");
+    let filter = extended_parser()
+        .parse("$[?foo(@.tags)]")
+        .unwrap()
+        .labelled("user filter");
+    let query = base.concat(filter);
+
+    assert!(extended_parser().typecheck(&query).is_ok());
+
+    let err = Parser::new().typecheck(&query).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::NameError));
+    assert_eq!(err.source.as_deref(), Some("user filter"));
+    assert_eq!(
+        err.to_string(),
+        "name error: unknown function `foo` (3..6 in `user filter`)"
+    );
+}
+
+#[test]
+fn typecheck_nested_query() {
+    let query = extended_parser()
+        .parse("$[?@[?foo(@.x)]]")
+        .unwrap()
+        .labelled("nested");
+    let err = Parser::new().typecheck(&query).unwrap_err();
+    assert_eq!(err.span, (6..9).into());
+    assert_eq!(err.source.as_deref(), Some("nested"));
+}
+
+#[test]
+fn typecheck_unlabelled_error() {
+    let query = extended_parser().parse("$[?foo(@.x)]").unwrap();
+    let err = Parser::new().typecheck(&query).unwrap_err();
+    assert_eq!(err.source, None);
+    assert_eq!(err.to_string(), "name error: unknown function `foo` (3..6)");
+}
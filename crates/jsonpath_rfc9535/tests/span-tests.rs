@@ -0,0 +1,94 @@
+use jsonpath_rfc9535::{
+    query::{FilterExpression, Segment, Selector},
+    Query,
+};
+
+/// The source text of each segment in `query`.
+fn segments(query: &str) -> Vec<&str> {
+    Query::standard(query)
+        .unwrap()
+        .segments
+        .iter()
+        .map(|segment| &query[segment.span().range()])
+        .collect()
+}
+
+/// The source text of each selector in `query`'s first segment.
+fn selectors(query: &str) -> Vec<&str> {
+    let parsed = Query::standard(query).unwrap();
+    let (Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) =
+        &parsed.segments[0];
+    selectors
+        .iter()
+        .map(|selector| &query[selector.span().range()])
+        .collect()
+}
+
+#[test]
+fn shorthand_segments() {
+    assert_eq!(segments("$.foo.*"), vec![".foo", ".*"]);
+    assert_eq!(segments("$..foo..*"), vec!["..foo", "..*"]);
+}
+
+#[test]
+fn bracketed_segments() {
+    assert_eq!(
+        segments("$['a', 0][ 1:2 ]..[?@.b]"),
+        vec!["['a', 0]", "[ 1:2 ]", "..[?@.b]"]
+    );
+}
+
+#[test]
+fn selectors_in_brackets() {
+    assert_eq!(
+        selectors(r#"$['a', "b", 1, *, 1:, ::-1, :2:3]"#),
+        vec!["'a'", r#""b""#, "1", "*", "1:", "::-1", ":2:3"]
+    );
+}
+
+#[test]
+fn filter_selectors() {
+    assert_eq!(
+        selectors("$[?@.a && length(@.b) == 1, ?(@.c)]"),
+        vec!["?@.a && length(@.b) == 1", "?(@.c)"]
+    );
+}
+
+#[test]
+fn filter_selectors_ending_with_a_string() {
+    assert_eq!(
+        selectors(r#"$[?@.a == 'x', ?@.b == "y"]"#),
+        vec!["?@.a == 'x'", r#"?@.b == "y""#]
+    );
+    assert_eq!(segments("$..[?@.a == 'x']"), vec!["..[?@.a == 'x']"]);
+}
+
+#[test]
+fn nested_queries() {
+    let query = "$[?@.a[0]]";
+    let parsed = Query::standard(query).unwrap();
+    let Segment::Child { selectors, .. } = &parsed.segments[0] else {
+        unreachable!()
+    };
+    let Selector::Filter { expression, .. } = &selectors[0] else {
+        unreachable!()
+    };
+    let FilterExpression::RelativeQuery { query: nested, .. } = expression.as_ref() else {
+        unreachable!()
+    };
+
+    let spans: Vec<&str> = nested
+        .segments
+        .iter()
+        .map(|segment| &query[segment.span().range()])
+        .collect();
+    assert_eq!(spans, vec![".a", "[0]"]);
+}
+
+#[test]
+fn char_offsets() {
+    let query = "$['é'].ü";
+    let parsed = Query::standard(query).unwrap();
+    assert_eq!(parsed.segments[0].span().char_range(), 1..6);
+    assert_eq!(parsed.segments[1].span().char_range(), 6..8);
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use jsonpath_rfc9535::{
+    standard::{NonStandard, NonStandardError},
+    ExpressionType, Parser, Query,
+};
+
+fn standard(query: &str) -> String {
+    let rv = Query::standard(query)
+        .unwrap()
+        .try_to_standard_string()
+        .unwrap();
+    // The result must parse back to the same query.
+    assert_eq!(
+        Query::standard(&rv).unwrap().to_string(),
+        Query::standard(query).unwrap().to_string()
+    );
+    rv
+}
+
+#[test]
+fn canonical_form() {
+    assert_eq!(standard("$.a[0, -1]..b[*]"), "$['a'][0, -1]..['b'][*]");
+    assert_eq!(standard("$[1:][:2][::-1][1:5:2]"), "$[1:][:2][::-1][1:5:2]");
+    assert_eq!(
+        standard("$[?@.a == 'x' && count($.b[*]) > 1.5]"),
+        "$[?(@['a'] == 'x' && count($['b'][*]) > 1.5)]"
+    );
+}
+
+#[test]
+fn infinite_literals() {
+    assert_eq!(
+        standard("$[?@.a < 1e400 && @.a > -1e400]"),
+        "$[?(@['a'] < 1e400 && @['a'] > -1e400)]"
+    );
+}
+
+#[test]
+fn escape_names_and_strings() {
+    assert_eq!(standard(r#"$["it's"]"#), r"$['it\'s']");
+    assert_eq!(standard(r#"$["a\\b\n\t"]"#), r"$['a\\b\n\t']");
+    assert_eq!(standard(r#"$[?@.a == "\"'"]"#), r#"$[?@['a'] == '"\'']"#);
+}
+
+#[test]
+fn negated_comparison() {
+    assert_eq!(standard("$[?!(@.a == 1)]"), "$[?!(@['a'] == 1)]");
+    assert_eq!(standard("$[?!@.a]"), "$[?!@['a']]");
+}
+
+#[test]
+fn function_extensions_are_not_standard() {
+    let mut parser = Parser::new();
+    parser.add_function("foo", vec![ExpressionType::Value], ExpressionType::Logical);
+
+    let query = parser.parse("$[?foo(@.a) && length(@.b) > 1]").unwrap();
+    let err = query.try_to_standard_string().unwrap_err();
+    assert_eq!(err.constructs.len(), 1);
+    assert_eq!(
+        err.to_string(),
+        "query is not standard JSONPath: function extension `foo` (3..6)"
+    );
+
+    // Display still writes the extended dialect.
+    assert_eq!(query.to_string(), "$[?(foo(@['a']) && length(@['b']) > 1)]");
+}
+
+fn extended_parser() -> Parser {
+    let mut parser = Parser::new();
+    parser.add_function(
+        "is_empty",
+        vec![ExpressionType::Nodes],
+        ExpressionType::Logical,
+    );
+    parser.add_function(
+        "starts_with",
+        vec![ExpressionType::Value, ExpressionType::Value],
+        ExpressionType::Logical,
+    );
+    parser.add_function("len", vec![ExpressionType::Value], ExpressionType::Value);
+    parser
+}
+
+fn rewrites() -> HashMap<String, String> {
+    HashMap::from([
+        (String::from("is_empty"), String::from("count({0}) == 0")),
+        (String::from("len"), String::from("length({0})")),
+    ])
+}
+
+fn downgrade(query: &str) -> Result<String, NonStandardError> {
+    extended_parser()
+        .parse(query)
+        .unwrap()
+        .downgrade_to_standard(&rewrites())
+        .map(|query| query.try_to_standard_string().unwrap())
+}
+
+#[test]
+fn downgrade_function_extensions() {
+    assert_eq!(
+        downgrade("$[?is_empty(@.a)]").unwrap(),
+        "$[?count(@['a']) == 0]"
+    );
+    assert_eq!(
+        downgrade("$[?!is_empty(@.a) && len(@.b) > 2]").unwrap(),
+        "$[?(!(count(@['a']) == 0) && length(@['b']) > 2)]"
+    );
+    assert_eq!(
+        downgrade("$[?len(len(@.b)) == 1]").unwrap(),
+        "$[?length(length(@['b'])) == 1]"
+    );
+}
+
+#[test]
+fn downgrade_reports_missing_rewrites() {
+    let err = downgrade("$[?starts_with(@.a, 'x') || is_empty(@.b)]").unwrap_err();
+    assert_eq!(
+        err.constructs,
+        vec![NonStandard {
+            msg: String::from("no rewrite for function extension `starts_with`"),
+            span: (3..14).into(),
+        }]
+    );
+}
+
+#[test]
+fn downgrade_reports_invalid_rewrites() {
+    let rewrites = HashMap::from([(String::from("is_empty"), String::from("count({1}) == 0"))]);
+    let err = extended_parser()
+        .parse("$[?is_empty(@.a)]")
+        .unwrap()
+        .downgrade_to_standard(&rewrites)
+        .unwrap_err();
+    assert_eq!(err.constructs.len(), 1);
+    assert!(err.constructs[0]
+        .msg
+        .starts_with("downgraded query `$[?(count({1}) == 0)]` is not valid"));
+}
@@ -0,0 +1,61 @@
+//! Every library and binary in the workspace forbids unsafe code. Code that
+//! needs `unsafe` belongs in its own crate, which forbids unsafe code when
+//! built with `--cfg jsonpath_rfc9535_forbid_unsafe`, so the guarantee holds
+//! for everyone else and can be checked by anyone building with that flag.
+use std::{fs, path::Path};
+
+/// Crates that exist to hold unsafe code, like the C interface. These must
+/// forbid unsafe code under the cfg, and put each unsafe operation in an
+/// `unsafe` block.
+const UNSAFE_CRATES: [&str; 1] = ["jsonpath_rfc9535_ffi"];
+
+fn crate_roots(crates: &Path) -> Vec<std::path::PathBuf> {
+    let mut roots = Vec::new();
+    for entry in fs::read_dir(crates).unwrap() {
+        let src = entry.unwrap().path().join("src");
+        for name in ["lib.rs", "main.rs"] {
+            let root = src.join(name);
+            if root.exists() {
+                roots.push(root);
+            }
+        }
+        if let Ok(bins) = fs::read_dir(src.join("bin")) {
+            roots.extend(bins.map(|bin| bin.unwrap().path()));
+        }
+    }
+    roots.sort();
+    roots
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn forbid_unsafe_code() {
+    let crates = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let roots = crate_roots(&crates);
+    assert!(!roots.is_empty());
+
+    let missing: Vec<String> = roots
+        .iter()
+        .filter(|root| {
+            let attributes: &[&str] = if UNSAFE_CRATES
+                .iter()
+                .any(|name| root.components().any(|c| c.as_os_str() == *name))
+            {
+                &[
+                    "#![cfg_attr(jsonpath_rfc9535_forbid_unsafe, forbid(unsafe_code))]",
+                    "#![deny(unsafe_op_in_unsafe_fn)]",
+                ]
+            } else {
+                &["#![forbid(unsafe_code)]"]
+            };
+            let source = fs::read_to_string(root).unwrap();
+            !attributes.iter().all(|attribute| source.contains(attribute))
+        })
+        .map(|root| root.display().to_string())
+        .collect();
+
+    assert!(
+        missing.is_empty(),
+        "missing #![forbid(unsafe_code)], or the cfg-gated forbid and #![deny(unsafe_op_in_unsafe_fn)] in unsafe crates, in {missing:?}"
+    );
+}
@@ -0,0 +1,151 @@
+//! A derive macro for populating structs from JSONPath queries.
+//!
+//! Annotate each field of a struct with a JSONPath expression and derive
+//! `JsonPathExtract`. The generated `extract` function evaluates each
+//! expression against a JSON value and deserializes the result into the
+//! field's type.
+//!
+//! - `T` takes the first node matched by its query, failing if there are none.
+//! - `Option<T>` takes the first node matched by its query, or `None`.
+//! - `Vec<T>` takes all nodes matched by its query, in order.
+//!
+//! ```ignore
+//! use jsonpath_rfc9535_locations::JsonPathExtract;
+//!
+//! #[derive(JsonPathExtract)]
+//! struct Order {
+//!     #[jsonpath("$.id")]
+//!     id: u64,
+//!     #[jsonpath("$.customer.email")]
+//!     email: Option<String>,
+//!     #[jsonpath("$.lines[*].sku")]
+//!     skus: Vec<String>,
+//! }
+//! ```
+//!
+//! Expressions are checked at compile time.
+//!
+//! Use this macro through `jsonpath_rfc9535_locations`, which re-exports it
+//! with its `derive` feature. Generated code only refers to that crate, so
+//! it's the only dependency needed.
+
+#![forbid(unsafe_code)]
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, GenericArgument, LitStr,
+    PathArguments, Type,
+};
+
+#[proc_macro_derive(JsonPathExtract, attributes(jsonpath))]
+pub fn derive_jsonpath_extract(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+enum Arity {
+    One,
+    Optional,
+    All,
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    "JsonPathExtract requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "JsonPathExtract can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut inits = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().unwrap();
+        let field_name = ident.to_string();
+
+        let attr = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path().is_ident("jsonpath"))
+            .ok_or_else(|| {
+                Error::new(
+                    field.span(),
+                    format!(
+                        "missing #[jsonpath(\"...\")] attribute on field `{}`",
+                        field_name
+                    ),
+                )
+            })?;
+
+        let expr: LitStr = attr.parse_args()?;
+
+        if let Err(err) = jsonpath_rfc9535::Query::standard(&expr.value()) {
+            return Err(Error::new(expr.span(), err.to_string()));
+        }
+
+        let extract = match arity(&field.ty) {
+            Arity::One => quote!(::jsonpath_rfc9535_locations::extract::one),
+            Arity::Optional => quote!(::jsonpath_rfc9535_locations::extract::optional),
+            Arity::All => quote!(::jsonpath_rfc9535_locations::extract::all),
+        };
+
+        inits.push(quote! {
+            #ident: {
+                static QUERY: ::std::sync::OnceLock<::jsonpath_rfc9535_locations::Query> =
+                    ::std::sync::OnceLock::new();
+                let query = ::jsonpath_rfc9535_locations::extract::query(&QUERY, #expr)?;
+                #extract(query, #field_name, value)?
+            }
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::jsonpath_rfc9535_locations::extract::JsonPathExtract
+            for #name #ty_generics #where_clause
+        {
+            fn extract(
+                value: &::jsonpath_rfc9535_locations::extract::Value,
+            ) -> ::std::result::Result<Self, ::jsonpath_rfc9535_locations::errors::JSONPathError> {
+                Ok(Self {
+                    #(#inits),*
+                })
+            }
+        }
+    })
+}
+
+fn arity(ty: &Type) -> Arity {
+    if let Type::Path(path) = ty {
+        if let Some(segment) = path.path.segments.last() {
+            if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                if args.args.len() == 1 && matches!(args.args[0], GenericArgument::Type(_)) {
+                    if segment.ident == "Option" {
+                        return Arity::Optional;
+                    }
+                    if segment.ident == "Vec" {
+                        return Arity::All;
+                    }
+                }
+            }
+        }
+    }
+    Arity::One
+}
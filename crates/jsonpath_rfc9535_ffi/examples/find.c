@@ -0,0 +1,56 @@
+/*
+ * Parse a query and print the values and paths it selects from a document.
+ *
+ *     cargo build -p jsonpath_rfc9535_ffi
+ *     cc -I crates/jsonpath_rfc9535_ffi/include crates/jsonpath_rfc9535_ffi/examples/find.c \
+ *         -L target/debug -ljsonpath_rfc9535_ffi -o find
+ *     LD_LIBRARY_PATH=target/debug ./find '$.users[?@.age > 18].name'
+ */
+
+#include <stdio.h>
+
+#include "jsonpath_rfc9535.h"
+
+static const char *DOCUMENT =
+    "{\"users\": [{\"name\": \"a\", \"age\": 30}, {\"name\": \"b\", \"age\": 17}]}";
+
+int main(int argc, char **argv) {
+    jsonpath_error err = JSONPATH_ERROR_INIT;
+    jsonpath_query *query = NULL;
+    char *result = NULL;
+    int status = 0;
+
+    if (argc != 2) {
+        fprintf(stderr, "usage: %s QUERY\n", argv[0]);
+        return 2;
+    }
+
+    if (jsonpath_parse(argv[1], &query, &err) != JSONPATH_OK) {
+        fprintf(stderr, "error %d: %s (%zu..%zu)\n", err.code, err.msg, err.span_start,
+                err.span_end);
+        jsonpath_error_clear(&err);
+        return 1;
+    }
+
+    if (jsonpath_find(query, DOCUMENT, &result, &err) == JSONPATH_OK) {
+        printf("values: %s\n", result);
+        jsonpath_string_free(result);
+    } else {
+        status = 1;
+    }
+
+    if (jsonpath_find_paths(query, DOCUMENT, &result, &err) == JSONPATH_OK) {
+        printf("paths: %s\n", result);
+        jsonpath_string_free(result);
+    } else {
+        status = 1;
+    }
+
+    if (status != 0) {
+        fprintf(stderr, "error %d: %s\n", err.code, err.msg);
+    }
+
+    jsonpath_query_free(query);
+    jsonpath_error_clear(&err);
+    return status;
+}
@@ -0,0 +1,105 @@
+/*
+ * C interface to the RFC 9535 JSONPath parser and evaluator.
+ *
+ * Link against the jsonpath_rfc9535_ffi static or dynamic library, built
+ * with `cargo build --release -p jsonpath_rfc9535_ffi`.
+ *
+ *     jsonpath_error err = JSONPATH_ERROR_INIT;
+ *     jsonpath_query *query = NULL;
+ *     char *result = NULL;
+ *
+ *     if (jsonpath_parse("$.users[?@.age > 18].name", &query, &err) != JSONPATH_OK) {
+ *         fprintf(stderr, "%s at %zu..%zu\n", err.msg, err.span_start, err.span_end);
+ *         jsonpath_error_clear(&err);
+ *         return 1;
+ *     }
+ *
+ *     if (jsonpath_find(query, "{\"users\": [{\"name\": \"a\", \"age\": 30}]}", &result, &err) == JSONPATH_OK) {
+ *         puts(result);  // ["a"]
+ *         jsonpath_string_free(result);
+ *     }
+ *
+ *     jsonpath_query_free(query);
+ *     jsonpath_error_clear(&err);
+ */
+
+#ifndef JSONPATH_RFC9535_H
+#define JSONPATH_RFC9535_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define JSONPATH_OK 0
+#define JSONPATH_LEXER_ERROR 1
+#define JSONPATH_SYNTAX_ERROR 2
+#define JSONPATH_TYPE_ERROR 3
+#define JSONPATH_NAME_ERROR 4
+#define JSONPATH_LIMIT_ERROR 5
+#define JSONPATH_ENCODING_ERROR 6
+/* A required pointer argument was null. */
+#define JSONPATH_NULL_ARGUMENT 7
+/* A string argument was not valid UTF-8. */
+#define JSONPATH_INVALID_UTF8 8
+/* The document was not valid JSON. */
+#define JSONPATH_INVALID_JSON 9
+/* A bug in the library. The message says what went wrong. */
+#define JSONPATH_INTERNAL_ERROR 10
+/* The query was nested deeper than the parser allows. */
+#define JSONPATH_DEPTH_ERROR 11
+/* Evaluating the query failed. */
+#define JSONPATH_RUNTIME_ERROR 12
+/* The document could not be read. */
+#define JSONPATH_DOCUMENT_ERROR 13
+
+/*
+ * Details of a failed call. Initialize with JSONPATH_ERROR_INIT before first
+ * use, and free the message with jsonpath_error_clear. The span is a range
+ * of byte offsets into the query, both zero when the error isn't about part
+ * of the query.
+ */
+typedef struct jsonpath_error {
+    int32_t code;
+    char *msg;
+    size_t span_start;
+    size_t span_end;
+} jsonpath_error;
+
+#define JSONPATH_ERROR_INIT {JSONPATH_OK, NULL, 0, 0}
+
+/* A parsed query. */
+typedef struct jsonpath_query jsonpath_query;
+
+/*
+ * Parse a NUL-terminated UTF-8 query and store it in *out. Free it with
+ * jsonpath_query_free. Returns JSONPATH_OK or an error code, in which case
+ * err, if it isn't NULL, says what went wrong.
+ */
+int32_t jsonpath_parse(const char *query, jsonpath_query **out, jsonpath_error *err);
+
+/*
+ * Evaluate query against a NUL-terminated JSON document and store a JSON
+ * array of the selected values in *out. Free it with jsonpath_string_free.
+ */
+int32_t jsonpath_find(const jsonpath_query *query, const char *json, char **out,
+                      jsonpath_error *err);
+
+/* Like jsonpath_find, but store a JSON array of normalized paths. */
+int32_t jsonpath_find_paths(const jsonpath_query *query, const char *json, char **out,
+                            jsonpath_error *err);
+
+void jsonpath_query_free(jsonpath_query *query);
+
+void jsonpath_string_free(char *s);
+
+/* Free err's message and reset it, so it can be used again. */
+void jsonpath_error_clear(jsonpath_error *err);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* JSONPATH_RFC9535_H */
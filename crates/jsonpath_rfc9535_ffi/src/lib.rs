@@ -0,0 +1,299 @@
+//! A C interface to parse JSONPath queries and evaluate them against JSON
+//! text, so the parser can be used from other languages.
+//!
+//! Queries are checked with the hand-crafted parser, so errors carry a span,
+//! and evaluated with `jsonpath_rfc9535_locations`. Results are returned as
+//! JSON text. See `include/jsonpath_rfc9535.h` for the C declarations.
+//!
+//! Every function that can fail returns a status code, `JSONPATH_OK` on
+//! success, and fills in a caller-provided [`JsonPathError`] otherwise.
+//! Strings returned by this library must be freed with
+//! [`jsonpath_string_free`], and error messages with
+//! [`jsonpath_error_clear`].
+
+// Building with `--cfg jsonpath_rfc9535_forbid_unsafe` makes this crate fail
+// to compile, so a build that must be free of unsafe code can't depend on it.
+#![cfg_attr(jsonpath_rfc9535_forbid_unsafe, forbid(unsafe_code))]
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use jsonpath_rfc9535::{JSONPathError as ParseError, Span};
+use jsonpath_rfc9535_locations::{node::NodeList, Query, ENV};
+use serde_json::Value;
+
+pub const JSONPATH_OK: i32 = 0;
+// Errors in a query are reported with `JSONPathErrorType::code`.
+pub const JSONPATH_LEXER_ERROR: i32 = 1;
+pub const JSONPATH_SYNTAX_ERROR: i32 = 2;
+pub const JSONPATH_TYPE_ERROR: i32 = 3;
+pub const JSONPATH_NAME_ERROR: i32 = 4;
+pub const JSONPATH_LIMIT_ERROR: i32 = 5;
+pub const JSONPATH_ENCODING_ERROR: i32 = 6;
+/// A required pointer argument was null.
+pub const JSONPATH_NULL_ARGUMENT: i32 = 7;
+/// A string argument was not valid UTF-8.
+pub const JSONPATH_INVALID_UTF8: i32 = 8;
+/// The document was not valid JSON.
+pub const JSONPATH_INVALID_JSON: i32 = 9;
+/// A bug in this library. The message says what went wrong.
+pub const JSONPATH_INTERNAL_ERROR: i32 = 10;
+/// The query was nested deeper than the parser allows.
+pub const JSONPATH_DEPTH_ERROR: i32 = 11;
+/// Evaluating the query failed.
+pub const JSONPATH_RUNTIME_ERROR: i32 = 12;
+/// The document could not be read.
+pub const JSONPATH_DOCUMENT_ERROR: i32 = 13;
+
+/// Details of a failed call. `msg` is owned by the caller once set, and is
+/// freed by [`jsonpath_error_clear`]. `span_start` and `span_end` are byte
+/// offsets into the query, both zero when the error isn't about part of the
+/// query.
+#[repr(C)]
+#[derive(Debug)]
+pub struct JsonPathError {
+    pub code: i32,
+    pub msg: *mut c_char,
+    pub span_start: usize,
+    pub span_end: usize,
+}
+
+/// A parsed query, opaque to C.
+pub struct JsonPathQuery {
+    query: Query,
+}
+
+/// A failure to report through a [`JsonPathError`].
+struct Failure {
+    code: i32,
+    msg: String,
+    span: Span,
+}
+
+impl Failure {
+    fn new(code: i32, msg: impl Into<String>) -> Self {
+        Failure {
+            code,
+            msg: msg.into(),
+            span: Span::default(),
+        }
+    }
+}
+
+impl From<ParseError> for Failure {
+    fn from(err: ParseError) -> Self {
+        Failure {
+            code: err.kind.code(),
+            msg: err.msg,
+            span: err.span,
+        }
+    }
+}
+
+/// Parse `query`, a NUL-terminated UTF-8 string, and store the parsed query
+/// in `*out`. Free it with [`jsonpath_query_free`].
+///
+/// # Safety
+///
+/// `query` must be null or a valid NUL-terminated string. `out` must be null
+/// or valid for writes. `error` must be null or point to a `JsonPathError`
+/// whose `msg` is null or was set by this library.
+#[no_mangle]
+pub unsafe extern "C" fn jsonpath_parse(
+    query: *const c_char,
+    out: *mut *mut JsonPathQuery,
+    error: *mut JsonPathError,
+) -> i32 {
+    let rv = guard(|| {
+        let expr = unsafe { str_arg(query, "query") }?;
+        if out.is_null() {
+            return Err(Failure::new(JSONPATH_NULL_ARGUMENT, "`out` is null"));
+        }
+
+        // The hand-crafted parser accepts the same queries as the evaluator's,
+        // and reports where a query is wrong, so it's only run on failure.
+        let query = Query::standard(expr).map_err(|err| {
+            jsonpath_rfc9535::Query::standard(expr)
+                .err()
+                .unwrap_or_else(|| ParseError::from(err))
+        })?;
+
+        unsafe { *out = Box::into_raw(Box::new(JsonPathQuery { query })) };
+        Ok(())
+    });
+    unsafe { report(rv, error) }
+}
+
+/// Evaluate `query` against `json`, a NUL-terminated JSON document, and
+/// store a JSON array of the selected values in `*out`. Free it with
+/// [`jsonpath_string_free`].
+///
+/// # Safety
+///
+/// `query` must be null or a pointer returned by [`jsonpath_parse`] that
+/// hasn't been freed. `json` must be null or a valid NUL-terminated string.
+/// `out` and `error` are as for [`jsonpath_parse`].
+#[no_mangle]
+pub unsafe extern "C" fn jsonpath_find(
+    query: *const JsonPathQuery,
+    json: *const c_char,
+    out: *mut *mut c_char,
+    error: *mut JsonPathError,
+) -> i32 {
+    unsafe {
+        find(query, json, out, error, |nodes| {
+            serde_json::to_string(&nodes.values())
+        })
+    }
+}
+
+/// Like [`jsonpath_find`], but store a JSON array of the normalized paths
+/// of the selected values.
+///
+/// # Safety
+///
+/// As for [`jsonpath_find`].
+#[no_mangle]
+pub unsafe extern "C" fn jsonpath_find_paths(
+    query: *const JsonPathQuery,
+    json: *const c_char,
+    out: *mut *mut c_char,
+    error: *mut JsonPathError,
+) -> i32 {
+    unsafe {
+        find(query, json, out, error, |nodes| {
+            serde_json::to_string(&nodes.paths())
+        })
+    }
+}
+
+/// Free a query returned by [`jsonpath_parse`]. Does nothing if `query` is
+/// null.
+///
+/// # Safety
+///
+/// `query` must be null or a pointer returned by [`jsonpath_parse`] that
+/// hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn jsonpath_query_free(query: *mut JsonPathQuery) {
+    if !query.is_null() {
+        drop(unsafe { Box::from_raw(query) });
+    }
+}
+
+/// Free a string returned by this library. Does nothing if `s` is null.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library that hasn't been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn jsonpath_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Free `error`'s message and reset it, so it can be used again.
+///
+/// # Safety
+///
+/// `error` must be null or point to a `JsonPathError` whose `msg` is null or
+/// was set by this library.
+#[no_mangle]
+pub unsafe extern "C" fn jsonpath_error_clear(error: *mut JsonPathError) {
+    if let Some(error) = unsafe { error.as_mut() } {
+        unsafe { jsonpath_string_free(error.msg) };
+        *error = JsonPathError {
+            code: JSONPATH_OK,
+            msg: ptr::null_mut(),
+            span_start: 0,
+            span_end: 0,
+        };
+    }
+}
+
+unsafe fn find(
+    query: *const JsonPathQuery,
+    json: *const c_char,
+    out: *mut *mut c_char,
+    error: *mut JsonPathError,
+    result: impl FnOnce(NodeList) -> serde_json::Result<String>,
+) -> i32 {
+    let rv = guard(|| {
+        let query = match unsafe { query.as_ref() } {
+            Some(query) => &query.query,
+            None => return Err(Failure::new(JSONPATH_NULL_ARGUMENT, "`query` is null")),
+        };
+        let json = unsafe { str_arg(json, "json") }?;
+        if out.is_null() {
+            return Err(Failure::new(JSONPATH_NULL_ARGUMENT, "`out` is null"));
+        }
+
+        let value: Value = serde_json::from_str(json)
+            .map_err(|err| Failure::new(JSONPATH_INVALID_JSON, err.to_string()))?;
+        let internal =
+            |err: &dyn std::error::Error| Failure::new(JSONPATH_INTERNAL_ERROR, err.to_string());
+        let text = result(query.find(&value, &ENV)).map_err(|err| internal(&err))?;
+        let text = CString::new(text).map_err(|err| internal(&err))?;
+
+        unsafe { *out = text.into_raw() };
+        Ok(())
+    });
+    unsafe { report(rv, error) }
+}
+
+/// Borrow a string argument, failing if it's null or not UTF-8.
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, Failure> {
+    if s.is_null() {
+        return Err(Failure::new(
+            JSONPATH_NULL_ARGUMENT,
+            format!("`{name}` is null"),
+        ));
+    }
+
+    unsafe { CStr::from_ptr(s) }.to_str().map_err(|err| {
+        Failure::new(
+            JSONPATH_INVALID_UTF8,
+            format!("`{name}` is not valid UTF-8: {err}"),
+        )
+    })
+}
+
+/// Run `f`, turning a panic into an internal error rather than unwinding
+/// into the caller, which is undefined behavior.
+fn guard(f: impl FnOnce() -> Result<(), Failure>) -> Result<(), Failure> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let msg = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| String::from("unknown panic"));
+        Err(Failure::new(JSONPATH_INTERNAL_ERROR, msg))
+    })
+}
+
+/// Fill in `error`, if it isn't null, and return the status code.
+unsafe fn report(rv: Result<(), Failure>, error: *mut JsonPathError) -> i32 {
+    let failure = match rv {
+        Ok(()) => return JSONPATH_OK,
+        Err(failure) => failure,
+    };
+
+    unsafe { jsonpath_error_clear(error) };
+    if let Some(error) = unsafe { error.as_mut() } {
+        // Messages never contain NUL, but don't lose the error if one does.
+        let msg = CString::new(failure.msg.replace('\0', " ")).unwrap_or_default();
+        *error = JsonPathError {
+            code: failure.code,
+            msg: msg.into_raw(),
+            span_start: failure.span.start,
+            span_end: failure.span.end,
+        };
+    }
+    failure.code
+}
@@ -0,0 +1,172 @@
+use std::{
+    ffi::{CStr, CString},
+    ptr,
+};
+
+use jsonpath_rfc9535::JSONPathErrorType;
+use jsonpath_rfc9535_ffi::*;
+
+fn new_error() -> JsonPathError {
+    JsonPathError {
+        code: JSONPATH_OK,
+        msg: ptr::null_mut(),
+        span_start: 0,
+        span_end: 0,
+    }
+}
+
+fn parse(query: &str, error: &mut JsonPathError) -> *mut JsonPathQuery {
+    let query = CString::new(query).unwrap();
+    let mut out = ptr::null_mut();
+    unsafe { jsonpath_parse(query.as_ptr(), &mut out, error) };
+    out
+}
+
+fn find(query: &str, json: &str) -> Result<String, (i32, String)> {
+    let mut error = new_error();
+    let parsed = parse(query, &mut error);
+    let json = CString::new(json).unwrap();
+    let mut out = ptr::null_mut();
+
+    let code = unsafe { jsonpath_find(parsed, json.as_ptr(), &mut out, &mut error) };
+    let rv = if code == JSONPATH_OK {
+        let result = unsafe { CStr::from_ptr(out) }.to_str().unwrap().to_owned();
+        unsafe { jsonpath_string_free(out) };
+        Ok(result)
+    } else {
+        let msg = unsafe { CStr::from_ptr(error.msg) }
+            .to_str()
+            .unwrap()
+            .to_owned();
+        Err((error.code, msg))
+    };
+
+    unsafe {
+        jsonpath_query_free(parsed);
+        jsonpath_error_clear(&mut error);
+    }
+    rv
+}
+
+#[test]
+fn find_values() {
+    assert_eq!(
+        find(
+            "$.users[?@.age > 18].name",
+            r#"{"users": [{"name": "a", "age": 30}, {"name": "b", "age": 17}]}"#
+        ),
+        Ok(String::from(r#"["a"]"#))
+    );
+    assert_eq!(find("$.nosuchthing", "{}"), Ok(String::from("[]")));
+}
+
+#[test]
+fn find_paths() {
+    let mut error = new_error();
+    let query = parse("$..b", &mut error);
+    let json = CString::new(r#"{"a": {"b": 1}, "b": [2]}"#).unwrap();
+    let mut out = ptr::null_mut();
+
+    let code = unsafe { jsonpath_find_paths(query, json.as_ptr(), &mut out, &mut error) };
+    assert_eq!(code, JSONPATH_OK);
+    let paths: Vec<String> =
+        serde_json::from_str(unsafe { CStr::from_ptr(out) }.to_str().unwrap()).unwrap();
+    assert_eq!(paths.len(), 2);
+    assert!(paths.contains(&String::from("$['a']['b']")));
+    assert!(paths.contains(&String::from("$['b']")));
+
+    unsafe {
+        jsonpath_string_free(out);
+        jsonpath_query_free(query);
+    }
+}
+
+#[test]
+fn parse_errors_have_spans() {
+    let mut error = new_error();
+    let query = parse("$.a[?foo(@)]", &mut error);
+    assert!(query.is_null());
+    assert_eq!(error.code, JSONPATH_NAME_ERROR);
+    assert_eq!((error.span_start, error.span_end), (5, 8));
+    let msg = unsafe { CStr::from_ptr(error.msg) }.to_str().unwrap();
+    assert_eq!(msg, "unknown function `foo`");
+
+    // A second failure replaces the first.
+    parse("$[", &mut error);
+    assert_eq!(error.code, JSONPATH_SYNTAX_ERROR);
+
+    unsafe { jsonpath_error_clear(&mut error) };
+    assert_eq!(error.code, JSONPATH_OK);
+    assert!(error.msg.is_null());
+}
+
+#[test]
+fn invalid_arguments() {
+    assert_eq!(
+        find("$", "{").map_err(|(code, _)| code),
+        Err(JSONPATH_INVALID_JSON)
+    );
+
+    let mut error = new_error();
+    let mut out = ptr::null_mut();
+    let code = unsafe { jsonpath_parse(ptr::null(), &mut out, &mut error) };
+    assert_eq!(code, JSONPATH_NULL_ARGUMENT);
+
+    let json = CString::new("{}").unwrap();
+    let code = unsafe {
+        jsonpath_find(
+            ptr::null(),
+            json.as_ptr(),
+            &mut ptr::null_mut(),
+            ptr::null_mut(),
+        )
+    };
+    assert_eq!(code, JSONPATH_NULL_ARGUMENT);
+
+    let invalid = [b'$', 0xff, 0];
+    let code = unsafe { jsonpath_parse(invalid.as_ptr().cast(), &mut out, &mut error) };
+    assert_eq!(code, JSONPATH_INVALID_UTF8);
+
+    unsafe { jsonpath_error_clear(&mut error) };
+}
+
+#[test]
+fn error_codes_match_error_kinds() {
+    for (kind, code) in [
+        (JSONPathErrorType::LexerError, JSONPATH_LEXER_ERROR),
+        (JSONPathErrorType::SyntaxError, JSONPATH_SYNTAX_ERROR),
+        (JSONPathErrorType::TypeError, JSONPATH_TYPE_ERROR),
+        (JSONPathErrorType::NameError, JSONPATH_NAME_ERROR),
+        (JSONPathErrorType::LimitError, JSONPATH_LIMIT_ERROR),
+        (JSONPathErrorType::EncodingError, JSONPATH_ENCODING_ERROR),
+        (JSONPathErrorType::DepthError, JSONPATH_DEPTH_ERROR),
+        (JSONPathErrorType::RuntimeError, JSONPATH_RUNTIME_ERROR),
+        (JSONPathErrorType::DocumentError, JSONPATH_DOCUMENT_ERROR),
+    ] {
+        assert_eq!(kind.code(), code, "{kind}");
+    }
+}
+
+#[test]
+fn header_defines_every_status_code() {
+    let header = include_str!("../include/jsonpath_rfc9535.h");
+    for (name, code) in [
+        ("JSONPATH_OK", JSONPATH_OK),
+        ("JSONPATH_LEXER_ERROR", JSONPATH_LEXER_ERROR),
+        ("JSONPATH_SYNTAX_ERROR", JSONPATH_SYNTAX_ERROR),
+        ("JSONPATH_TYPE_ERROR", JSONPATH_TYPE_ERROR),
+        ("JSONPATH_NAME_ERROR", JSONPATH_NAME_ERROR),
+        ("JSONPATH_LIMIT_ERROR", JSONPATH_LIMIT_ERROR),
+        ("JSONPATH_ENCODING_ERROR", JSONPATH_ENCODING_ERROR),
+        ("JSONPATH_NULL_ARGUMENT", JSONPATH_NULL_ARGUMENT),
+        ("JSONPATH_INVALID_UTF8", JSONPATH_INVALID_UTF8),
+        ("JSONPATH_INVALID_JSON", JSONPATH_INVALID_JSON),
+        ("JSONPATH_INTERNAL_ERROR", JSONPATH_INTERNAL_ERROR),
+        ("JSONPATH_DEPTH_ERROR", JSONPATH_DEPTH_ERROR),
+        ("JSONPATH_RUNTIME_ERROR", JSONPATH_RUNTIME_ERROR),
+        ("JSONPATH_DOCUMENT_ERROR", JSONPATH_DOCUMENT_ERROR),
+    ] {
+        let define = format!("#define {name} {code}\n");
+        assert!(header.contains(&define), "{define:?}");
+    }
+}
@@ -1,28 +1,41 @@
 use std::fmt;
 
-#[derive(Debug)]
-pub enum JSONPathErrorType {
-    LexerError,
-    SyntaxError,
-    TypeError,
-    NameError,
-}
+pub use jsonpath_rfc9535::errors::JSONPathErrorType;
+use jsonpath_rfc9535::{
+    errors::{snippet, EngineError},
+    span::Span,
+};
 
 #[derive(Debug)]
 pub struct JSONPathError {
     pub kind: JSONPathErrorType,
     pub msg: String,
+    /// Where in the query a syntax error was found.
+    pub span: Option<Span>,
 }
 
 impl JSONPathError {
     pub fn new(error: JSONPathErrorType, msg: String) -> Self {
-        Self { kind: error, msg }
+        Self {
+            kind: error,
+            msg,
+            span: None,
+        }
     }
 
     pub fn syntax(msg: String) -> Self {
         Self {
             kind: JSONPathErrorType::SyntaxError,
             msg,
+            span: None,
+        }
+    }
+
+    pub fn syntax_at(msg: String, span: Span) -> Self {
+        Self {
+            kind: JSONPathErrorType::SyntaxError,
+            msg,
+            span: Some(span),
         }
     }
 
@@ -30,6 +43,7 @@ impl JSONPathError {
         Self {
             kind: JSONPathErrorType::TypeError,
             msg,
+            span: None,
         }
     }
 
@@ -37,17 +51,32 @@ impl JSONPathError {
         Self {
             kind: JSONPathErrorType::NameError,
             msg,
+            span: None,
         }
     }
-}
 
-impl fmt::Display for JSONPathErrorType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            JSONPathErrorType::LexerError => f.write_str("lexer error:"),
-            JSONPathErrorType::SyntaxError => f.write_str("syntax error:"),
-            JSONPathErrorType::TypeError => f.write_str("type error:"),
-            JSONPathErrorType::NameError => f.write_str("name error:"),
+    pub fn limit(msg: String) -> Self {
+        Self {
+            kind: JSONPathErrorType::LimitError,
+            msg,
+            span: None,
+        }
+    }
+
+    pub fn depth_at(msg: String, span: Span) -> Self {
+        Self {
+            kind: JSONPathErrorType::DepthError,
+            msg,
+            span: Some(span),
+        }
+    }
+
+    /// This error's kind and message, followed by the line of `query` with a
+    /// caret under the syntax error, if it has a location.
+    pub fn render(&self, query: &str) -> String {
+        match self.span {
+            Some(span) => format!("{} {}\n{}", self.kind, self.msg, snippet(query, span)),
+            None => format!("{} {}", self.kind, self.msg),
         }
     }
 }
@@ -59,3 +88,17 @@ impl fmt::Display for JSONPathError {
         write!(f, "{}", self.msg)
     }
 }
+
+impl EngineError for JSONPathError {
+    fn kind(&self) -> JSONPathErrorType {
+        self.kind
+    }
+
+    fn into_msg(self) -> String {
+        self.msg
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+}
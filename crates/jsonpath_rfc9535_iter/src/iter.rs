@@ -5,14 +5,16 @@ use std::{
     vec::IntoIter,
 };
 
+use jsonpath_rfc9535::resolve_index;
 use serde_json::{Map, Value};
 
 use crate::{
     env::Environment,
+    errors::JSONPathError,
     filter::{is_truthy, FilterExpression},
     node::{Node, NodeIter},
     segment::{visit_iter, Segment},
-    selector::{norm_index, slice, Selector},
+    selector::{slice, Selector},
     Query,
 };
 
@@ -40,13 +42,27 @@ impl<'v> QueryIter<'v> {
         let it = query
             .segments
             .into_iter()
-            .filter(|s| !matches!(s, Segment::Eoi {}))
+            .filter(|s| !matches!(s, Segment::Eoi))
             .fold(init, |values, segment| {
                 SegmentIter::new(env, root, segment, Box::new(values))
             });
 
         Self { it: Box::new(it) }
     }
+
+    /// Like [`QueryIter::new`], but failing with a name error if `query`
+    /// calls a function `env` doesn't have, which happens if it was parsed
+    /// with a different environment. `new` evaluates such calls to nothing.
+    pub fn try_new(
+        env: &'static Environment,
+        root: &'v Value,
+        query: Query,
+    ) -> Result<Self, JSONPathError> {
+        if let Some(name) = query.find_call(&|name| env.function_register.get(name).is_none()) {
+            return Err(JSONPathError::name(format!("unknown function `{name}`")));
+        }
+        Ok(Self::new(env, root, query))
+    }
 }
 
 pub struct SegmentIter<'v> {
@@ -100,7 +116,8 @@ impl<'v> SegmentIter<'v> {
                     }
                 }
             }
-            Segment::Eoi {} => unreachable!(),
+            // The end of the query selects its input unchanged.
+            Segment::Eoi => its.push(SelectorIter { it: nodes }),
         };
 
         let mut its = its.into_iter();
@@ -141,9 +158,10 @@ impl<'v> SelectorIter<'v> {
             }
             Selector::Index { index } => {
                 if let Some(array) = node.value.as_array() {
-                    let norm = norm_index(index, array.len());
-                    if let Some(v) = array.get(norm) {
-                        Box::new(iter::once(node.new_child_element(v, norm)))
+                    if let Some((norm, value)) = resolve_index(index, array.len())
+                        .and_then(|norm| Some((norm, array.get(norm)?)))
+                    {
+                        Box::new(iter::once(node.new_child_element(value, norm)))
                     } else {
                         Box::new(iter::empty())
                     }
@@ -163,7 +181,7 @@ impl<'v> SelectorIter<'v> {
                     Box::new(iter::empty())
                 }
             }
-            Selector::Wild {} => match node.value {
+            Selector::Wild => match node.value {
                 Value::Array(arr) => Box::new(
                     arr.iter()
                         .enumerate()
@@ -176,10 +194,10 @@ impl<'v> SelectorIter<'v> {
             },
             Selector::Filter { expression } => match node.value {
                 Value::Array(arr) => {
-                    Box::new(ArrayFilterIter::new(env, root, *expression, &arr, node))
+                    Box::new(ArrayFilterIter::new(env, root, *expression, arr, node))
                 }
                 Value::Object(obj) => {
-                    Box::new(ObjectFilterIter::new(env, root, *expression, &obj, node))
+                    Box::new(ObjectFilterIter::new(env, root, *expression, obj, node))
                 }
                 _ => Box::new(iter::empty()),
             },
@@ -218,7 +236,7 @@ impl<'v> ArrayFilterIter<'v> {
         env: &'static Environment,
         root: &'v Value,
         expr: FilterExpression,
-        arr: &'v Vec<Value>,
+        arr: &'v [Value],
         node: Rc<Node<'v>>,
     ) -> Self {
         Self {
@@ -1,13 +1,28 @@
 use lazy_static::lazy_static;
 use serde_json::Value;
 
-use crate::{env::Environment, errors::JSONPathError, iter::QueryIter, Query};
+use crate::{
+    env::Environment,
+    errors::JSONPathError,
+    iter::QueryIter,
+    options::{FindOptions, FindResult},
+    Query,
+};
 
 lazy_static! {
-    static ref ENV: Environment = Environment::new();
+    pub static ref ENV: Environment = Environment::new();
 }
 
-pub fn find<'a, 'v>(expr: &str, value: &'v Value) -> Result<QueryIter<'v>, JSONPathError> {
+pub fn find<'v>(expr: &str, value: &'v Value) -> Result<QueryIter<'v>, JSONPathError> {
     let query = Query::standard(expr)?;
     Ok(QueryIter::new(&ENV, value, query))
 }
+
+/// Same as `find`, configured by `options`.
+pub fn find_with_options<'v>(
+    expr: &str,
+    value: &'v Value,
+    options: &FindOptions,
+) -> Result<FindResult<'v>, JSONPathError> {
+    ENV.find_with_options(expr, value, options)
+}
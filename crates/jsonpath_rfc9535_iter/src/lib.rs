@@ -1,3 +1,13 @@
+#![forbid(unsafe_code)]
+// Evaluating a query must not panic. Tests may, as `clippy.toml` allows.
+#![deny(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::panic,
+    clippy::unreachable,
+    clippy::unwrap_used
+)]
+
 pub mod env;
 pub mod errors;
 pub mod filter;
@@ -5,13 +15,43 @@ pub mod function;
 pub mod iter;
 pub mod jsonpath;
 pub mod node;
+pub mod options;
 pub mod parser;
 pub mod query;
 pub mod segment;
 pub mod selector;
 pub mod standard_functions;
 mod unescape;
+pub mod writer;
 
 pub use jsonpath::find;
+pub use jsonpath::find_with_options;
+pub use jsonpath_rfc9535::resolve_index;
 pub use parser::JSONPathParser;
 pub use query::Query;
+pub use writer::{write_results, Format};
+
+/// The `serde_json` this crate was built with, so callers can name the exact
+/// `Value` type its functions take.
+#[cfg(feature = "reexport_serde_json")]
+pub use serde_json;
+
+/// Fail to compile unless the calling crate's `serde_json` is the one this
+/// crate was built with.
+///
+/// With two semver-incompatible versions of `serde_json` in a dependency
+/// tree, passing a `serde_json::Value` to this crate fails with "expected
+/// `Value`, found `Value`" wherever it happens. Invoke this once, at module
+/// level, to get that error in one obvious place instead. Use
+/// `jsonpath_rfc9535_iter::serde_json` to avoid the mismatch altogether.
+///
+/// ```
+/// jsonpath_rfc9535_iter::assert_serde_json_version!();
+/// ```
+#[cfg(feature = "reexport_serde_json")]
+#[macro_export]
+macro_rules! assert_serde_json_version {
+    () => {
+        const _: fn(::serde_json::Value) -> $crate::serde_json::Value = |value| value;
+    };
+}
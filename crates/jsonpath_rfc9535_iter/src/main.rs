@@ -1,16 +1,19 @@
-use std::{fs::File, io::BufReader};
+#![forbid(unsafe_code)]
+
+use std::{error::Error, fs::File, io::BufReader};
 
 use jsonpath_rfc9535_iter::{jsonpath::find, node::NodeList};
 use serde_json::Value;
 
-fn main() {
-    let file = File::open("/tmp/datasets/citylots.json").expect("could not open data file");
+fn main() -> Result<(), Box<dyn Error>> {
+    let file = File::open("/tmp/datasets/citylots.json")?;
     let reader = BufReader::new(file);
-    let v: Value = serde_json::from_reader(reader).expect("error reading data file");
+    let v: Value = serde_json::from_reader(reader)?;
 
     // let q = "$.features..properties";
     // let q = "$.features..properties.BLOCK_NUM";
     let q = "$.features[?@.properties.STREET=='UNKNOWN'].properties.BLOCK_NUM";
-    let nodes: NodeList = find(q, &v).unwrap().collect();
+    let nodes: NodeList = find(q, &v)?.collect();
     println!("{:?}", nodes.len());
+    Ok(())
 }
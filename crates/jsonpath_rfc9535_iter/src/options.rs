@@ -0,0 +1,45 @@
+//! Options for [`find_with_options`](crate::find_with_options) and
+//! [`Environment::find_with_options`](crate::env::Environment::find_with_options).
+//! See [`jsonpath_rfc9535::options`], which every evaluator shares.
+//!
+//! ```
+//! use jsonpath_rfc9535_iter::{
+//!     find_with_options,
+//!     options::{FindOptions, Found, Output, ResultOrder},
+//! };
+//! use serde_json::json;
+//!
+//! let value = json!({"a": [1, 2], "b": 3});
+//! let options = FindOptions::new()
+//!     .order(ResultOrder::Document)
+//!     .dedup(true)
+//!     .output(Output::Paths);
+//!
+//! let result = find_with_options("$[1, 'a', 'a']", &value, &options).unwrap();
+//! let Found::Paths(paths) = result.found else {
+//!     unreachable!()
+//! };
+//! assert_eq!(paths, vec!["$['a']"]);
+//! ```
+use jsonpath_rfc9535::options::{self, FoundNode};
+use serde_json::Value;
+
+use crate::node::{Node, NodeList};
+
+pub use jsonpath_rfc9535::options::{Collector, FindOptions, FindStats, Output, ResultOrder};
+
+/// Nodes, values or paths, depending on `FindOptions::output`.
+pub type Found<'v> = options::Found<'v, NodeList<'v>>;
+
+/// The result of `find_with_options`.
+pub type FindResult<'v> = options::FindResult<'v, NodeList<'v>>;
+
+impl<'v> FoundNode<'v> for Node<'v> {
+    fn value(&self) -> &'v Value {
+        self.value
+    }
+
+    fn path(&self) -> String {
+        self.location.clone()
+    }
+}
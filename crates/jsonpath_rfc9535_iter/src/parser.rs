@@ -7,7 +7,13 @@
 
 use std::{collections::HashMap, ops::RangeInclusive};
 
-use pest::{iterators::Pair, Parser};
+use jsonpath_rfc9535::{parser::check_nesting_depth, span::Span};
+
+use pest::{
+    error::{Error, ErrorVariant, InputLocation},
+    iterators::{Pair, Pairs},
+    Parser,
+};
 use pest_derive::Parser;
 
 use crate::{
@@ -20,6 +26,9 @@ use crate::{
     unescape::unescape,
 };
 
+/// The default [`JSONPathParser::max_nesting_depth`].
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = jsonpath_rfc9535::parser::DEFAULT_MAX_NESTING_DEPTH;
+
 #[derive(Parser)]
 #[grammar = "jsonpath.pest"]
 struct JSONPath;
@@ -27,6 +36,16 @@ struct JSONPath;
 pub struct JSONPathParser {
     pub index_range: RangeInclusive<i64>,
     pub functions: HashMap<String, FunctionSignature>,
+    /// Skip a leading byte order mark (U+FEFF) instead of rejecting it.
+    pub skip_bom: bool,
+    /// The deepest brackets and parentheses can nest, outside of string
+    /// literals, before parsing fails with a
+    /// [`JSONPathErrorType::DepthError`](crate::errors::JSONPathErrorType::DepthError).
+    ///
+    /// `$[?@.a]` has a depth of one and `$[?count(@[?@.a]) > 1]` a depth of
+    /// three. Queries are checked before they're given to pest, which
+    /// recurses at each level, so this bounds its stack use.
+    pub max_nesting_depth: usize,
 }
 
 impl Default for JSONPathParser {
@@ -40,12 +59,28 @@ impl JSONPathParser {
         JSONPathParser {
             index_range: ((-2_i64).pow(53) + 1..=2_i64.pow(53) - 1),
             functions: standard_functions(),
+            skip_bom: false,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
         }
     }
 
     pub fn parse(&self, query: &str) -> Result<Query, JSONPathError> {
-        let segments: Result<Vec<_>, _> = JSONPath::parse(Rule::jsonpath, query)
-            .map_err(|err| JSONPathError::syntax(err.to_string()))?
+        let start = match query.strip_prefix('\u{FEFF}') {
+            Some(_) if !self.skip_bom => {
+                return Err(JSONPathError::syntax_at(
+                    String::from("unexpected byte order mark"),
+                    Span::new(0, 0),
+                ))
+            }
+            Some(_) => '\u{FEFF}'.len_utf8(),
+            None => 0,
+        };
+
+        check_nesting_depth(query, self.max_nesting_depth)
+            .map_err(|err| JSONPathError::depth_at(err.msg, err.span))?;
+
+        let segments: Result<Vec<_>, _> = JSONPath::parse(Rule::jsonpath, &query[start..])
+            .map_err(|err| syntax_error(query, start, err))?
             .map(|segment| self.parse_segment(segment))
             .collect();
 
@@ -57,16 +92,16 @@ impl JSONPathParser {
     fn parse_segment(&self, segment: Pair<Rule>) -> Result<Segment, JSONPathError> {
         Ok(match segment.as_rule() {
             Rule::child_segment => Segment::Child {
-                selectors: self.parse_segment_inner(segment.into_inner().next().unwrap())?,
+                selectors: self.parse_segment_inner(next_pair(&mut segment.into_inner())?)?,
             },
             Rule::descendant_segment => Segment::Recursive {
-                selectors: self.parse_segment_inner(segment.into_inner().next().unwrap())?,
+                selectors: self.parse_segment_inner(next_pair(&mut segment.into_inner())?)?,
             },
             Rule::name_segment | Rule::index_segment => Segment::Child {
-                selectors: vec![self.parse_selector(segment.into_inner().next().unwrap())?],
+                selectors: vec![self.parse_selector(next_pair(&mut segment.into_inner())?)?],
             },
             Rule::EOI => Segment::Eoi,
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -84,7 +119,7 @@ impl JSONPathParser {
                 // for child_segment
                 name: segment.as_str().to_owned(),
             }],
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -106,7 +141,7 @@ impl JSONPathParser {
                 // for name_segment
                 name: selector.as_str().to_owned(),
             },
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -120,7 +155,7 @@ impl JSONPathParser {
                 Rule::start => start = Some(self.parse_i_json_int(i.as_str())?),
                 Rule::stop => stop = Some(self.parse_i_json_int(i.as_str())?),
                 Rule::step => step = Some(self.parse_i_json_int(i.as_str())?),
-                _ => unreachable!(),
+                rule => return Err(unexpected(rule)),
             }
         }
 
@@ -130,7 +165,7 @@ impl JSONPathParser {
     fn parse_filter_selector(&self, selector: Pair<Rule>) -> Result<Selector, JSONPathError> {
         Ok(Selector::Filter {
             expression: Box::new(
-                self.parse_logical_or_expression(selector.into_inner().next().unwrap(), true)?,
+                self.parse_logical_or_expression(next_pair(&mut selector.into_inner())?, true)?,
             ),
         })
     }
@@ -141,7 +176,7 @@ impl JSONPathParser {
         assert_compared: bool,
     ) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let mut or_expr = self.parse_logical_and_expression(it.next().unwrap(), assert_compared)?;
+        let mut or_expr = self.parse_logical_and_expression(next_pair(&mut it)?, assert_compared)?;
 
         if assert_compared {
             self.assert_compared(&or_expr)?;
@@ -168,7 +203,7 @@ impl JSONPathParser {
         assert_compared: bool,
     ) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let mut and_expr = self.parse_basic_expression(it.next().unwrap())?;
+        let mut and_expr = self.parse_basic_expression(next_pair(&mut it)?)?;
 
         if assert_compared {
             self.assert_compared(&and_expr)?;
@@ -196,19 +231,19 @@ impl JSONPathParser {
             Rule::paren_expr => self.parse_paren_expression(expr),
             Rule::comparison_expr => self.parse_comparison_expression(expr),
             Rule::test_expr => self.parse_test_expression(expr),
-            _ => unreachable!(),
+            rule => Err(unexpected(rule)),
         }
     }
 
     fn parse_paren_expression(&self, expr: Pair<Rule>) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let p = it.next().unwrap();
+        let p = next_pair(&mut it)?;
         match p.as_rule() {
             Rule::logical_not_op => Ok(FilterExpression::Not {
-                expression: Box::new(self.parse_logical_or_expression(it.next().unwrap(), true)?),
+                expression: Box::new(self.parse_logical_or_expression(next_pair(&mut it)?, true)?),
             }),
             Rule::logical_or_expr => self.parse_logical_or_expression(p, true),
-            _ => unreachable!(),
+            rule => Err(unexpected(rule)),
         }
     }
 
@@ -217,19 +252,23 @@ impl JSONPathParser {
         expr: Pair<Rule>,
     ) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let left = self.parse_comparable(it.next().unwrap())?;
+        let left = self.parse_comparable(next_pair(&mut it)?)?;
 
-        let operator = match it.next().unwrap().as_str() {
+        let operator = match next_pair(&mut it)?.as_str() {
             "==" => ComparisonOperator::Eq,
             "!=" => ComparisonOperator::Ne,
             "<=" => ComparisonOperator::Le,
             ">=" => ComparisonOperator::Ge,
             "<" => ComparisonOperator::Lt,
             ">" => ComparisonOperator::Gt,
-            _ => unreachable!(),
+            op => {
+                return Err(JSONPathError::syntax(format!(
+                    "unexpected comparison operator `{op}`"
+                )))
+            }
         };
 
-        let right = self.parse_comparable(it.next().unwrap())?;
+        let right = self.parse_comparable(next_pair(&mut it)?)?;
         self.assert_comparable(&left)?;
         self.assert_comparable(&right)?;
 
@@ -277,7 +316,7 @@ impl JSONPathParser {
                 }
             }
             Rule::function_expr => self.parse_function_expression(expr)?,
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -289,7 +328,7 @@ impl JSONPathParser {
         // TODO: change pest grammar to indicate positive or negative exponent?
         let mut it = expr.into_inner();
         let mut is_float = false;
-        let mut n = it.next().unwrap().as_str().to_string(); // int
+        let mut n = next_pair(&mut it)?.as_str().to_string(); // int
 
         if let Some(pair) = it.next() {
             match pair.as_rule() {
@@ -304,7 +343,7 @@ impl JSONPathParser {
                     }
                     n.push_str(exp_str);
                 }
-                _ => unreachable!(),
+                rule => return Err(unexpected(rule)),
             }
         }
 
@@ -322,22 +361,38 @@ impl JSONPathParser {
                     .parse::<f64>()
                     .map_err(|_| JSONPathError::syntax(String::from("invalid float literal")))?,
             })
+        } else if let Ok(value) = n.parse::<i64>() {
+            Ok(FilterExpression::Int { value })
+        } else if let Ok(value) = n.parse::<u64>() {
+            Ok(FilterExpression::UInt { value })
         } else {
-            Ok(FilterExpression::Int {
-                value: n
-                    .parse::<f64>()
-                    .map_err(|_| JSONPathError::syntax(String::from("invalid integer literal")))?
-                    as i64,
-            })
+            let value = n
+                .parse::<f64>()
+                .map_err(|_| JSONPathError::syntax(String::from("invalid integer literal")))?;
+
+            // Integers with an exponent, like `1e3`, go through a float.
+            // Those out of range for an i64 or u64, like `1e400`, stay floats
+            // rather than saturating.
+            if value.abs() < i64::MAX as f64 {
+                Ok(FilterExpression::Int {
+                    value: value as i64,
+                })
+            } else if value > 0.0 && value < u64::MAX as f64 {
+                Ok(FilterExpression::UInt {
+                    value: value as u64,
+                })
+            } else {
+                Ok(FilterExpression::Float { value })
+            }
         }
     }
 
     fn parse_test_expression(&self, expr: Pair<Rule>) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let pair = it.next().unwrap();
+        let pair = next_pair(&mut it)?;
         Ok(match pair.as_rule() {
             Rule::logical_not_op => FilterExpression::Not {
-                expression: Box::new(self.parse_test_expression_inner(it.next().unwrap())?),
+                expression: Box::new(self.parse_test_expression_inner(next_pair(&mut it)?)?),
             },
             _ => self.parse_test_expression_inner(pair)?,
         })
@@ -373,7 +428,7 @@ impl JSONPathParser {
                 }
             }
             Rule::function_expr => self.parse_function_expression(expr)?,
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -382,7 +437,7 @@ impl JSONPathParser {
         expr: Pair<Rule>,
     ) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let name = it.next().unwrap().as_str();
+        let name = next_pair(&mut it)?.as_str();
         let args: Result<Vec<_>, _> = it.map(|ex| self.parse_function_argument(ex)).collect();
 
         Ok(FilterExpression::Function {
@@ -429,7 +484,7 @@ impl JSONPathParser {
             }
             Rule::logical_or_expr => self.parse_logical_or_expression(expr, false)?,
             Rule::function_expr => self.parse_function_expression(expr)?,
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -525,8 +580,7 @@ impl JSONPathParser {
         }
 
         // correct argument types?
-        for (idx, typ) in signature.param_types.iter().enumerate() {
-            let arg = &args[idx];
+        for (idx, (typ, arg)) in signature.param_types.iter().zip(&args).enumerate() {
             match typ {
                 ExpressionType::Value => {
                     if !self.is_value_type(arg) {
@@ -610,6 +664,58 @@ impl JSONPathParser {
     }
 }
 
+/// Convert a pest error into a syntax error, using the same message and
+/// position as the hand-crafted parser in `jsonpath_rfc9535` for trailing
+/// content after a complete query, queries ending after a dot and control
+/// characters. `start` is the offset into `query` that parsing started from,
+/// after any skipped byte order mark.
+fn syntax_error(query: &str, start: usize, err: Error<Rule>) -> JSONPathError {
+    let position = start
+        + match err.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((pos, _)) => pos,
+        };
+
+    let before = query[start..position].trim_end_matches([' ', '\t', '\n', '\r']);
+    let complete = || JSONPath::parse(Rule::jsonpath, before).is_ok();
+
+    let msg = match query[position..].chars().next() {
+        Some(ch) if ch.is_ascii_control() && !matches!(ch, '\t' | '\n' | '\r') => {
+            format!("unexpected control character U+{:04X}", ch as u32)
+        }
+        Some(ch) if complete() => format!(
+            "expected '.', '..' or a bracketed selection, found '{}'",
+            ch
+        ),
+        None if before.len() < position - start && complete() => {
+            String::from("unexpected trailing whitespace")
+        }
+        None if matches!(
+            &err.variant,
+            ErrorVariant::ParsingError { positives, .. }
+                if positives.contains(&Rule::member_name_shorthand)
+        ) =>
+        {
+            String::from("unexpected end of query")
+        }
+        _ => err.to_string(),
+    };
+
+    JSONPathError::syntax_at(msg, Span::new(position, position).with_chars(query))
+}
+
+/// The next pair in `pairs`, which the grammar guarantees is there.
+fn next_pair<'i>(pairs: &mut Pairs<'i, Rule>) -> Result<Pair<'i, Rule>, JSONPathError> {
+    pairs
+        .next()
+        .ok_or_else(|| JSONPathError::syntax(String::from("unexpected end of parse tree")))
+}
+
+/// An error for a rule the grammar doesn't produce where it was found.
+fn unexpected(rule: Rule) -> JSONPathError {
+    JSONPathError::syntax(format!("unexpected {rule:?} in parse tree"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1064,3 +1170,4 @@ mod tests {
         whitespace__selectors__return_between_recursive_descent_and_name_0: "$..\ra",
     }
 }
+
@@ -1,6 +1,8 @@
 use std::fmt;
 
+use jsonpath_rfc9535::resolve_index;
 use lazy_static::lazy_static;
+use serde_json::Value;
 
 use crate::{errors::JSONPathError, segment::Segment, selector::Selector, JSONPathParser};
 
@@ -26,6 +28,40 @@ impl Query {
         self.segments.is_empty()
     }
 
+    /// Resolve a singular query against `value` directly, without building a
+    /// node list. Returns `None` if the query selects nothing, or if it is
+    /// not singular.
+    pub fn singular_value<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                Segment::Child { selectors } => match selectors.as_slice() {
+                    [Selector::Name { name }] => value.as_object()?.get(name),
+                    [Selector::Index { index }] => {
+                        let array = value.as_array()?;
+                        array.get(resolve_index(*index, array.len())?)
+                    }
+                    _ => None,
+                },
+                Segment::Eoi => Some(value),
+                Segment::Recursive { .. } => None,
+            })
+    }
+
+    /// The name of the first function called by a filter in this query for
+    /// which `f` is `true`.
+    pub(crate) fn find_call(&self, f: &dyn Fn(&str) -> bool) -> Option<&str> {
+        self.segments.iter().find_map(|segment| match segment {
+            Segment::Child { selectors } | Segment::Recursive { selectors } => {
+                selectors.iter().find_map(|selector| match selector {
+                    Selector::Filter { expression, .. } => expression.find_call(f),
+                    _ => None,
+                })
+            }
+            Segment::Eoi => None,
+        })
+    }
+
     pub fn is_singular(&self) -> bool {
         self.segments.iter().all(|segment| {
             if let Segment::Child { selectors, .. } = segment {
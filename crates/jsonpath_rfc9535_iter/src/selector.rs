@@ -1,7 +1,4 @@
-use std::{
-    cmp,
-    fmt::{self, Write},
-};
+use std::fmt::{self, Write};
 
 use serde_json::Value;
 
@@ -48,18 +45,18 @@ impl fmt::Display for Selector {
                         .unwrap_or(String::from("1")),
                 )
             }
-            Selector::Wild { .. } => f.write_char('*'),
+            Selector::Wild => f.write_char('*'),
             Selector::Filter { expression, .. } => write!(f, "?{expression}"),
         }
     }
 }
 
-pub fn slice<'a>(
-    array: &'a Vec<Value>,
+pub fn slice(
+    array: &[Value],
     start: Option<i64>,
     stop: Option<i64>,
     step: Option<i64>,
-) -> Vec<(i64, &'a Value)> {
+) -> Vec<(i64, &Value)> {
     let array_length = array.len() as i64; // TODO: try_from
     if array_length == 0 {
         return Vec::new();
@@ -71,14 +68,19 @@ pub fn slice<'a>(
         return Vec::new();
     }
 
-    let n_start = match start {
-        Some(i) => {
-            if i < 0 {
-                cmp::max(array_length + i, 0)
-            } else {
-                cmp::min(i, array_length - 1)
-            }
+    // Negative indices count from the end of the array. Either way, clamp them
+    // to where a slice going in the direction of `n_step` can start or stop.
+    let bound = |i: i64| {
+        let i = if i < 0 { array_length + i } else { i };
+        if n_step > 0 {
+            i.clamp(0, array_length)
+        } else {
+            i.clamp(-1, array_length - 1)
         }
+    };
+
+    let n_start = match start {
+        Some(i) => bound(i),
         None => {
             if n_step < 0 {
                 array_length - 1
@@ -89,13 +91,7 @@ pub fn slice<'a>(
     };
 
     let n_stop = match stop {
-        Some(i) => {
-            if i < 0 {
-                cmp::max(array_length + i, -1)
-            } else {
-                cmp::min(i, array_length)
-            }
-        }
+        Some(i) => bound(i),
         None => {
             if n_step < 0 {
                 -1
@@ -110,23 +106,19 @@ pub fn slice<'a>(
     // TODO: try_from instead of as
     if n_step > 0 {
         for i in (n_start..n_stop).step_by(n_step as usize) {
-            sliced_array.push((i, array.get(i as usize).unwrap()));
+            if let Some(value) = array.get(i as usize) {
+                sliced_array.push((i, value));
+            }
         }
     } else {
         let mut i = n_start;
         while i > n_stop {
-            sliced_array.push((i, array.get(i as usize).unwrap()));
+            if let Some(value) = array.get(i as usize) {
+                sliced_array.push((i, value));
+            }
             i += n_step;
         }
     }
 
     sliced_array
 }
-
-pub fn norm_index(index: i64, length: usize) -> usize {
-    if index < 0 && length >= index.abs() as usize {
-        (length as i64 + index) as usize
-    } else {
-        index as usize
-    }
-}
@@ -0,0 +1,81 @@
+use std::{
+    borrow::Cow,
+    io::{self, Write},
+};
+
+use jsonpath_rfc9535::csv;
+use serde_json::Value;
+
+use crate::{iter::QueryIter, jsonpath::ENV, Query};
+
+/// Serialization formats supported by `write_results`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// A single JSON array of matched values.
+    Json,
+    /// One JSON value per line.
+    Ndjson,
+    /// A `path,value` header followed by one row per match, following the
+    /// conventions of [`jsonpath_rfc9535::csv`]. Only scalar values
+    /// (strings, numbers, booleans and null) can be written as CSV.
+    Csv,
+}
+
+/// Write values matched by `query` to `writer` as they are found, without
+/// collecting a node list first.
+///
+/// Returns an error of kind `io::ErrorKind::InvalidData` if a non-scalar
+/// value is matched when writing CSV. Output written before such an error
+/// is not rolled back.
+pub fn write_results<W: Write>(
+    query: Query,
+    value: &Value,
+    mut writer: W,
+    format: Format,
+) -> io::Result<()> {
+    let nodes = QueryIter::new(&ENV, value, query);
+
+    match format {
+        Format::Json => {
+            writer.write_all(b"[")?;
+            for (i, node) in nodes.enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut writer, node.value)?;
+            }
+            writer.write_all(b"]")?;
+        }
+        Format::Ndjson => {
+            for node in nodes {
+                serde_json::to_writer(&mut writer, node.value)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Format::Csv => {
+            let mut buf = String::new();
+            csv::push_record(&mut buf, ["path", "value"], ',');
+            writer.write_all(buf.as_bytes())?;
+            for node in nodes {
+                if node.value.is_array() || node.value.is_object() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("can't write non-scalar value at {} as CSV", node.location),
+                    ));
+                }
+                buf.clear();
+                csv::push_record(
+                    &mut buf,
+                    [
+                        Cow::Borrowed(node.location.as_str()),
+                        csv::value_field(Some(node.value)),
+                    ],
+                    ',',
+                );
+                writer.write_all(buf.as_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()
+}
@@ -0,0 +1,21 @@
+use jsonpath_rfc9535_iter::{errors::JSONPathErrorType, iter::QueryIter, jsonpath::ENV};
+use serde_json::json;
+
+#[test]
+fn function_missing_from_environment() {
+    let mut parser = ENV.parser();
+    let signature = parser.functions["length"].clone();
+    parser.functions.insert(String::from("size"), signature);
+    let query = parser.parse("$[?size(@) > 1]").unwrap();
+    let value = json!(["a", "bc"]);
+
+    // The query was parsed with `size`, but the default environment doesn't
+    // have it, so the function evaluates to nothing.
+    assert_eq!(QueryIter::new(&ENV, &value, query.clone()).count(), 0);
+
+    let Err(err) = QueryIter::try_new(&ENV, &value, query) else {
+        panic!("expected a name error");
+    };
+    assert!(matches!(err.kind, JSONPathErrorType::NameError));
+    assert_eq!(err.msg, "unknown function `size`");
+}
@@ -0,0 +1,24 @@
+use jsonpath_rfc9535_iter::find;
+use serde_json::{json, Value};
+
+fn paths(query: &str, value: &Value) -> Vec<String> {
+    find(query, value)
+        .unwrap()
+        .map(|node| node.location.to_owned())
+        .collect()
+}
+
+#[test]
+fn large_integer_literals_do_not_saturate() {
+    let value = json!([1e19, 9.3e18, 1]);
+    assert_eq!(paths("$[?@ == 1e19]", &value), vec!["$[0]"]);
+    assert_eq!(paths("$[?@ < 1e19]", &value), vec!["$[1]", "$[2]"]);
+}
+
+#[test]
+fn infinite_literals_are_ordered() {
+    let value = json!([1e300, -1e300, "a", null]);
+    assert_eq!(paths("$[?@ < 1e400]", &value), vec!["$[0]", "$[1]"]);
+    assert_eq!(paths("$[?@ > -1e400]", &value), vec!["$[0]", "$[1]"]);
+    assert!(paths("$[?@ == 1e400]", &value).is_empty());
+}
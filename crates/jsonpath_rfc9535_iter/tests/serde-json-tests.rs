@@ -0,0 +1,11 @@
+#![cfg(feature = "reexport_serde_json")]
+
+use jsonpath_rfc9535_iter::{find, serde_json::json};
+
+jsonpath_rfc9535_iter::assert_serde_json_version!();
+
+#[test]
+fn reexported_values() {
+    let value = json!({"a": [1, 2]});
+    assert_eq!(find("$.a[1]", &value).unwrap().count(), 1);
+}
@@ -0,0 +1,52 @@
+use jsonpath_rfc9535_iter::{errors::JSONPathErrorType, Query};
+
+#[test]
+fn trailing_content() {
+    for line in include_str!("../../../tests/trailing-content.tsv").lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let [query, offset, msg] = line.split('\t').collect::<Vec<_>>()[..] else {
+            panic!("malformed corpus line {line:?}");
+        };
+        let err = Query::standard(query).unwrap_err();
+        assert!(
+            matches!(err.kind, JSONPathErrorType::SyntaxError),
+            "{query:?}"
+        );
+        assert_eq!(
+            (err.span.map(|span| span.start), err.msg.as_str()),
+            (Some(offset.parse().unwrap()), msg),
+            "{query:?}"
+        );
+    }
+}
+
+#[test]
+fn render_points_at_trailing_content() {
+    for line in include_str!("../../../tests/trailing-content.tsv").lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let [query, offset, msg] = line.split('\t').collect::<Vec<_>>()[..] else {
+            panic!("malformed corpus line {line:?}");
+        };
+        let offset: usize = offset.parse().unwrap();
+        let caret = " ".repeat(query[..offset].chars().count());
+        assert_eq!(
+            Query::standard(query).unwrap_err().render(query),
+            format!("syntax error: {msg}\n  {query}\n  {caret}^"),
+            "{query:?}"
+        );
+    }
+}
+
+#[test]
+fn render_without_a_position() {
+    let err = Query::standard("$[?nosuchthing()]").unwrap_err();
+    assert!(err.span.is_none());
+    assert_eq!(
+        err.render("$[?nosuchthing()]"),
+        format!("{} {}", err.kind, err.msg)
+    );
+}
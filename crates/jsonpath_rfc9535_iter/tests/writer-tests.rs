@@ -0,0 +1,59 @@
+use jsonpath_rfc9535_iter::{write_results, Format, Query};
+use serde_json::{json, Value};
+
+fn write(query: &str, value: &Value, format: Format) -> String {
+    let mut buf: Vec<u8> = Vec::new();
+    write_results(Query::standard(query).unwrap(), value, &mut buf, format).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn json_array() {
+    let value = json!({"a": [1, "two", {"b": null}]});
+    assert_eq!(
+        write("$.a.*", &value, Format::Json),
+        r#"[1,"two",{"b":null}]"#
+    );
+}
+
+#[test]
+fn empty_json_array() {
+    let value = json!({"a": [1, 2]});
+    assert_eq!(write("$.b", &value, Format::Json), "[]");
+}
+
+#[test]
+fn ndjson() {
+    let value = json!({"a": [1, "two", {"b": null}]});
+    assert_eq!(
+        write("$.a.*", &value, Format::Ndjson),
+        "1\n\"two\"\n{\"b\":null}\n"
+    );
+}
+
+#[test]
+fn csv() {
+    let value = json!({"a": {"x,y": "say \"hi\"", "z": 1.5, "n": null}});
+    assert_eq!(
+        write("$.a['x,y', 'z', 'n']", &value, Format::Csv),
+        "path,value\r\n\"$['a']['x,y']\",\"say \"\"hi\"\"\"\r\n$['a']['z'],1.5\r\n$['a']['n'],null\r\n"
+    );
+}
+
+#[test]
+fn csv_non_scalar() {
+    let value = json!({"a": [1, [2]]});
+    let mut buf: Vec<u8> = Vec::new();
+    let err = write_results(
+        Query::standard("$.a.*").unwrap(),
+        &value,
+        &mut buf,
+        Format::Csv,
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert_eq!(
+        err.to_string(),
+        "can't write non-scalar value at $['a'][1] as CSV"
+    );
+}
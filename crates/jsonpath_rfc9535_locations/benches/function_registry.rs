@@ -0,0 +1,35 @@
+#![feature(test)]
+
+extern crate test;
+
+#[cfg(test)]
+mod tests {
+    use jsonpath_rfc9535_locations::{Query, ENV, STANDARD_ENV};
+    use serde_json::{json, Value};
+    use test::Bencher;
+
+    fn data() -> Value {
+        Value::Array(
+            (0..1000)
+                .map(|i| json!({"name": format!("item{i}"), "tags": [i, i + 1]}))
+                .collect(),
+        )
+    }
+
+    const QUERY: &str =
+        "$[?length(@.name) > 6 && count(@.tags[*]) == 2 && match(@.name, 'item.*')]";
+
+    #[bench]
+    fn bench_dynamic_functions(b: &mut Bencher) {
+        let value = data();
+        let query = Query::standard(QUERY).unwrap();
+        b.iter(|| query.find(&value, &ENV));
+    }
+
+    #[bench]
+    fn bench_static_functions(b: &mut Bencher) {
+        let value = data();
+        let query = Query::standard(QUERY).unwrap();
+        b.iter(|| query.find(&value, &STANDARD_ENV));
+    }
+}
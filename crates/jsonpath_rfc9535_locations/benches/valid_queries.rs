@@ -92,7 +92,7 @@ mod tests {
 
         b.iter(|| {
             for (q, v) in compiled_queries.iter() {
-                q.find(v, &ENV);
+                q.find(*v, &ENV);
             }
         })
     }
@@ -0,0 +1,244 @@
+//! Evaluate queries against the parts of a document a caller may read.
+//!
+//! [`Query::find_authorized`] consults an [`AccessPolicy`] for each object
+//! member and array element before looking inside it. Denied values, and
+//! everything below them, are treated as absent: selectors don't select
+//! them, descendant segments don't visit them and filter expressions don't
+//! see them. Selected values are returned as copies with their denied
+//! descendants removed, so a query like `$` can't be used to read them. The
+//! normalized paths of denied values are returned with the selected nodes,
+//! so callers can tell a redacted result from an empty one.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::{
+//!     node::{Node, PathElement},
+//!     Query, ENV,
+//! };
+//! use serde_json::json;
+//!
+//! let value = json!({"users": [{"name": "a", "salary": 10}, {"name": "b", "salary": 20}]});
+//! let policy = |_: &Node, key: &PathElement| *key != PathElement::Name(String::from("salary"));
+//!
+//! let query = Query::standard("$.users[?@.salary > 15].name").unwrap();
+//! let result = query.find_authorized(&value, &ENV, &policy);
+//!
+//! assert!(result.nodes.is_empty());
+//! assert_eq!(
+//!     result.denied,
+//!     vec!["$['users'][0]['salary']", "$['users'][1]['salary']"]
+//! );
+//!
+//! let query = Query::standard("$.users[0]").unwrap();
+//! let result = query.find_authorized(&value, &ENV, &policy);
+//! assert_eq!(result.nodes[0].value, json!({"name": "a"}));
+//! ```
+//!
+//! Policies are consulted once for every member and element of the document
+//! that isn't inside a denied value, whatever the query, so `denied` lists
+//! every outermost denied value in document order.
+//!
+//! Array elements keep their indices when a sibling is denied, so `$[1]`
+//! selects the same element with or without access to `$[0]`. Filter
+//! expressions see arrays with denied elements removed, so an index inside
+//! a filter, like `@.tags[0]`, counts allowed elements only.
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    env::Environment,
+    function::Functions,
+    node::{normalized_path, Location, Node, PathElement},
+    walk::{children, Hooks, Walker},
+    Query,
+};
+
+/// Decides which object members and array elements a query may read.
+///
+/// Any `Fn(&Node, &PathElement) -> bool` is a policy.
+pub trait AccessPolicy {
+    /// Return `true` if the member or element `key` of `parent` may be read.
+    fn allows(&self, parent: &Node, key: &PathElement) -> bool;
+}
+
+impl<T: Fn(&Node, &PathElement) -> bool> AccessPolicy for T {
+    fn allows(&self, parent: &Node, key: &PathElement) -> bool {
+        self(parent, key)
+    }
+}
+
+/// A node selected by [`Query::find_authorized`], with a copy of its value
+/// that doesn't include denied values.
+#[derive(Debug, Clone)]
+pub struct AuthorizedNode {
+    pub value: Value,
+    pub location: Location,
+}
+
+impl AuthorizedNode {
+    /// The location of this node's value as a normalized path.
+    pub fn path(&self) -> String {
+        normalized_path(&self.location)
+    }
+}
+
+/// The result of [`Query::find_authorized`].
+#[derive(Debug)]
+pub struct AuthorizedNodes {
+    pub nodes: Vec<AuthorizedNode>,
+    /// Normalized paths of denied values, in document order.
+    pub denied: Vec<String>,
+}
+
+impl Query {
+    /// Same as `find`, but values `policy` denies access to are treated as
+    /// absent, and removed from selected values.
+    pub fn find_authorized<F: Functions, P: AccessPolicy>(
+        &self,
+        value: &Value,
+        env: &'static Environment<F>,
+        policy: &P,
+    ) -> AuthorizedNodes {
+        let mut denied = Denied {
+            values: HashSet::new(),
+            paths: Vec::new(),
+        };
+        denied.check(
+            policy,
+            &Node {
+                value,
+                location: Location::new(),
+            },
+        );
+
+        // Filters are evaluated against a copy of the document without
+        // denied values.
+        let redacted = denied.redact(value);
+        let mut views = HashMap::new();
+        denied.pair(value, &redacted, &mut views);
+
+        let mut walker = Walker {
+            env,
+            root: value,
+            hooks: Redactor {
+                denied: &denied,
+                views,
+            },
+        };
+
+        let nodes = walker
+            .find(self)
+            .into_iter()
+            .map(|node| AuthorizedNode {
+                value: walker.hooks.view(node.value).clone(),
+                location: node.location,
+            })
+            .collect();
+
+        AuthorizedNodes {
+            nodes,
+            denied: denied.paths,
+        }
+    }
+}
+
+/// Values denied by a policy, identified by address, and their paths.
+struct Denied {
+    values: HashSet<*const Value>,
+    paths: Vec<String>,
+}
+
+impl Denied {
+    /// Ask `policy` about each child of `node`, and the children of those it
+    /// allows.
+    fn check<P: AccessPolicy>(&mut self, policy: &P, node: &Node) {
+        for (key, child) in children(node.value) {
+            if policy.allows(node, &key) {
+                let child = Node {
+                    value: child,
+                    location: node.location.append(key),
+                };
+                self.check(policy, &child);
+            } else {
+                self.values.insert(child as *const Value);
+                self.paths.push(normalized_path(&node.location.append(key)));
+            }
+        }
+    }
+
+    fn is_denied(&self, value: &Value) -> bool {
+        self.values.contains(&(value as *const Value))
+    }
+
+    /// A copy of `value` without denied values.
+    fn redact(&self, value: &Value) -> Value {
+        match value {
+            Value::Array(arr) => Value::Array(
+                arr.iter()
+                    .filter(|v| !self.is_denied(v))
+                    .map(|v| self.redact(v))
+                    .collect(),
+            ),
+            Value::Object(obj) => Value::Object(
+                obj.iter()
+                    .filter(|(_, v)| !self.is_denied(v))
+                    .map(|(k, v)| (k.to_owned(), self.redact(v)))
+                    .collect::<Map<String, Value>>(),
+            ),
+            _ => value.clone(),
+        }
+    }
+
+    /// Map the address of each allowed value in `value` to its copy in
+    /// `redacted`, the result of `self.redact(value)`.
+    fn pair<'r>(
+        &self,
+        value: &Value,
+        redacted: &'r Value,
+        views: &mut HashMap<*const Value, &'r Value>,
+    ) {
+        views.insert(value as *const Value, redacted);
+        match (value, redacted) {
+            (Value::Array(arr), Value::Array(copies)) => {
+                let allowed = arr.iter().filter(|v| !self.is_denied(v));
+                for (v, copy) in allowed.zip(copies) {
+                    self.pair(v, copy, views);
+                }
+            }
+            (Value::Object(obj), Value::Object(copies)) => {
+                for (k, copy) in copies {
+                    if let Some(v) = obj.get(k) {
+                        self.pair(v, copy, views);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Skips denied values, and shows filters copies of allowed values without
+/// their denied descendants.
+struct Redactor<'d, 'r> {
+    denied: &'d Denied,
+    views: HashMap<*const Value, &'r Value>,
+}
+
+impl<'v> Hooks<'v> for Redactor<'_, '_> {
+    fn allows(&self, value: &'v Value) -> bool {
+        !self.denied.is_denied(value)
+    }
+
+    fn view<'h>(&'h self, value: &'v Value) -> &'h Value
+    where
+        'v: 'h,
+    {
+        // Every allowed value has a copy, and denied values are never
+        // visited.
+        self.views
+            .get(&(value as *const Value))
+            .copied()
+            .unwrap_or(value)
+    }
+}
@@ -0,0 +1,34 @@
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encode `bytes` as base64url without padding, as recommended by
+/// RFC 8949 section 6.1 for converting byte strings to JSON.
+pub fn encode_url(bytes: &[u8]) -> String {
+    let mut rv = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, b)| acc | (*b as u32) << (16 - i * 8));
+        for i in 0..=chunk.len() {
+            if let Some(&c) = ALPHABET.get((n >> (18 - i * 6) & 0x3f) as usize) {
+                rv.push(c as char);
+            }
+        }
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        assert_eq!(encode_url(b""), "");
+        assert_eq!(encode_url(b"f"), "Zg");
+        assert_eq!(encode_url(b"fo"), "Zm8");
+        assert_eq!(encode_url(b"foo"), "Zm9v");
+        assert_eq!(encode_url(b"foob"), "Zm9vYg");
+        assert_eq!(encode_url(&[0xfb, 0xff]), "-_8");
+    }
+}
@@ -0,0 +1,168 @@
+//! Explore a JSON document interactively.
+//!
+//! ```text
+//! jsonpath-repl FILE
+//! ```
+//!
+//! Each line is a query evaluated against the document in `FILE`. Results
+//! are shown with the query's canonical form, the number of nodes it
+//! selected, each node's normalized path and value, and how long the query
+//! took. Press tab to complete member names. Lines starting with `:` are
+//! commands, see `:help`.
+
+#![forbid(unsafe_code)]
+
+use std::{env, fs, process::ExitCode, time::Instant};
+
+use jsonpath_rfc9535_locations::{suggest::suggest, Query, ENV};
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+    Context, Editor, Helper,
+};
+use serde_json::Value;
+
+const HELP: &str = "\
+<query>      evaluate a query against the current document
+:load FILE   load a different document
+:help        show this message
+:quit        exit (or press Ctrl-D)";
+
+/// The longest value to print before truncating it.
+const MAX_VALUE_LENGTH: usize = 80;
+
+struct Document {
+    value: Value,
+}
+
+impl Completer for Document {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let candidates = suggest(&line[..pos], &self.value)
+            .into_iter()
+            .map(|query| Pair {
+                display: query.clone(),
+                replacement: query,
+            })
+            .collect();
+        Ok((0, candidates))
+    }
+}
+
+impl Hinter for Document {
+    type Hint = String;
+}
+
+impl Highlighter for Document {}
+
+impl Validator for Document {}
+
+impl Helper for Document {}
+
+fn main() -> ExitCode {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("usage: jsonpath-repl FILE");
+        return ExitCode::from(2);
+    };
+
+    let value = match load(&path) {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("jsonpath-repl: {err}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let mut editor = match Editor::<Document, DefaultHistory>::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("jsonpath-repl: {err}");
+            return ExitCode::from(2);
+        }
+    };
+    editor.set_helper(Some(Document { value }));
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("jsonpath-repl: {err}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let _ = editor.add_history_entry(line);
+
+        match line.split_once(' ').unwrap_or((line, "")) {
+            (":quit" | ":q", _) => break,
+            (":help" | ":h", _) => println!("{HELP}"),
+            (":load", path) => match load(path.trim()) {
+                Ok(value) => editor.set_helper(Some(Document { value })),
+                Err(err) => println!("error: {err}"),
+            },
+            (command, _) if command.starts_with(':') => {
+                println!("unknown command `{command}`, try :help")
+            }
+            _ => {
+                if let Some(document) = editor.helper() {
+                    evaluate(line, &document.value);
+                }
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn load(path: &str) -> Result<Value, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{path}: {err}"))?;
+    serde_json::from_str(&text).map_err(|err| format!("{path}: {err}"))
+}
+
+fn evaluate(expr: &str, value: &Value) {
+    let started = Instant::now();
+    let query = match Query::standard(expr) {
+        Ok(query) => query,
+        Err(err) => {
+            println!("{} {}", err.kind, err.msg);
+            return;
+        }
+    };
+    let nodes = query.find(value, &ENV);
+    let elapsed = started.elapsed();
+
+    println!("{query}");
+    for node in nodes.iter() {
+        println!("  {} = {}", node.path(), truncate(node.value.to_string()));
+    }
+    println!(
+        "{} node{} in {:?}",
+        nodes.len(),
+        if nodes.len() == 1 { "" } else { "s" },
+        elapsed
+    );
+}
+
+fn truncate(mut value: String) -> String {
+    if let Some((i, _)) = value.char_indices().nth(MAX_VALUE_LENGTH) {
+        value.truncate(i);
+        value.push_str("...");
+    }
+    value
+}
@@ -0,0 +1,435 @@
+//! Query JSON from the command line.
+//!
+//! ```text
+//! jsonpath QUERY [FILE]
+//! jsonpath --queries-file QUERIES [FILE]
+//! jsonpath fetch URL QUERY
+//! jsonpath fetch URL --queries-file QUERIES
+//! jsonpath completions SHELL
+//! ```
+//!
+//! `jsonpath` evaluates `QUERY` against the JSON document in `FILE`, or read
+//! from standard input if there's no `FILE` or it's `-`, and prints the
+//! normalized path and value of each node it selects, separated by a tab.
+//! With `--queries-file`, each non-empty line of `QUERIES` is a query, and
+//! each node is printed after the query that selected it.
+//!
+//! With `--lines`, or a `FILE` ending in `.ndjson` or `.jsonl`, input is
+//! newline delimited JSON. Each line is a document, evaluated as soon as
+//! it's read, and each of its nodes is printed after the line number.
+//!
+//! With the `http` feature, `fetch` reads the document from `URL` instead.
+//! Newline delimited JSON responses, those with a content type of
+//! `application/x-ndjson`, `application/jsonl` or `application/jsonlines`,
+//! or from a URL ending in `.ndjson` or `.jsonl`, are read one line at a
+//! time as they arrive.
+//!
+//! `--pretty` prints each node's path on its own line followed by its value,
+//! indented and, when writing to a terminal, colored. `--paths-only` prints
+//! just the paths and `--count` prints the number of nodes each query
+//! selected from each document.
+//!
+//! `completions` prints a completion script for `bash`, `elvish`, `fish`,
+//! `powershell` or `zsh`.
+
+#![forbid(unsafe_code)]
+
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, IsTerminal, Read},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use jsonpath_rfc9535_locations::{Query, ENV};
+#[cfg(feature = "http")]
+use reqwest::{header::CONTENT_TYPE, Response};
+use serde_json::Value;
+
+#[cfg(feature = "http")]
+const NDJSON_CONTENT_TYPES: [&str; 3] = [
+    "application/x-ndjson",
+    "application/jsonl",
+    "application/jsonlines",
+];
+
+const PATH_COLOR: &str = "1;33";
+const KEY_COLOR: &str = "1;34";
+const STRING_COLOR: &str = "32";
+const NULL_COLOR: &str = "1;30";
+
+/// Query JSON from the command line.
+#[derive(Parser)]
+#[command(
+    name = "jsonpath",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    select: Select,
+    /// The JSON document to query, read from standard input if it's missing
+    /// or `-`. With --queries-file, the only positional argument is FILE.
+    file: Option<PathBuf>,
+    /// Read newline delimited JSON, one document per line.
+    #[arg(long)]
+    lines: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Evaluate queries against a JSON document fetched over HTTP.
+    #[cfg(feature = "http")]
+    Fetch {
+        /// The URL of a JSON or newline delimited JSON document.
+        url: String,
+        #[command(flatten)]
+        select: Select,
+    },
+    /// Print a completion script for SHELL.
+    Completions { shell: Shell },
+}
+
+/// The queries to evaluate and what to print for the nodes they select.
+#[derive(Args)]
+struct Select {
+    /// The query to evaluate.
+    #[arg(required_unless_present = "queries_file")]
+    query: Option<String>,
+    /// Evaluate each line of QUERIES as a query instead.
+    #[arg(long, value_name = "QUERIES")]
+    queries_file: Option<PathBuf>,
+    /// Print each path on its own line, followed by its value indented
+    /// and colored.
+    #[arg(long, group = "output")]
+    pretty: bool,
+    /// Print paths without values.
+    #[arg(long, group = "output")]
+    paths_only: bool,
+    /// Print the number of nodes selected instead of the nodes.
+    #[arg(long, group = "output")]
+    count: bool,
+}
+
+/// What to print for the nodes selected by each query.
+#[derive(Clone, Copy)]
+enum Output {
+    Plain,
+    Pretty,
+    Paths,
+    Count,
+}
+
+/// Prints nodes and counts, prefixed with the document's line number and
+/// the query's text where there's more than one of either.
+struct Printer {
+    output: Output,
+    color: bool,
+}
+
+/// Queries to evaluate, with the text they were parsed from.
+struct Queries {
+    queries: Vec<(String, Query)>,
+    /// Print each node after the query that selected it.
+    keyed: bool,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        None => {
+            let Cli {
+                mut select,
+                mut file,
+                lines,
+                ..
+            } = cli;
+            // With a queries file, clap takes the only positional argument
+            // to be the query.
+            if select.queries_file.is_some() && file.is_none() {
+                file = select.query.take().map(PathBuf::from);
+            }
+            select
+                .queries()
+                .and_then(|queries| read(file.as_deref(), lines, &queries, &select.printer()))
+        }
+        #[cfg(feature = "http")]
+        Some(Command::Fetch { url, select }) => select.queries().and_then(|queries| {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|err| err.to_string())?;
+            runtime.block_on(fetch(&url, &queries, &select.printer()))
+        }),
+        Some(Command::Completions { shell }) => {
+            clap_complete::generate(shell, &mut Cli::command(), "jsonpath", &mut io::stdout());
+            Ok(())
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("jsonpath: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+impl Select {
+    fn queries(&self) -> Result<Queries, String> {
+        match (&self.query, &self.queries_file) {
+            (_, Some(path)) => read_queries(path),
+            (Some(expr), None) => parse(expr).map(|query| Queries {
+                queries: vec![(expr.clone(), query)],
+                keyed: false,
+            }),
+            (None, None) => Err(String::from("expected a query or --queries-file")),
+        }
+    }
+
+    fn printer(&self) -> Printer {
+        let output = match (self.pretty, self.paths_only, self.count) {
+            (true, _, _) => Output::Pretty,
+            (_, true, _) => Output::Paths,
+            (_, _, true) => Output::Count,
+            _ => Output::Plain,
+        };
+        Printer {
+            output,
+            color: io::stdout().is_terminal(),
+        }
+    }
+}
+
+fn parse(expr: &str) -> Result<Query, String> {
+    ENV.parser()
+        .parse(expr)
+        .map_err(|err| format!("{} {}", err.kind, err.msg))
+}
+
+/// Parse each non-empty line of the file at `path` as a query.
+fn read_queries(path: &Path) -> Result<Queries, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+    let queries = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let expr = line.trim();
+            parse(expr)
+                .map(|query| (expr.to_owned(), query))
+                .map_err(|err| format!("{}:{}: {err}", path.display(), i + 1))
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Queries {
+        queries,
+        keyed: true,
+    })
+}
+
+/// Evaluate `queries` against the document in the file at `path`, or
+/// standard input if `path` is `None` or `-`.
+fn read(
+    path: Option<&Path>,
+    lines: bool,
+    queries: &Queries,
+    printer: &Printer,
+) -> Result<(), String> {
+    let (reader, name): (Box<dyn Read>, _) = match path {
+        Some(path) if path != Path::new("-") => {
+            let file = File::open(path).map_err(|err| format!("{}: {err}", path.display()))?;
+            (Box::new(file), path.display().to_string())
+        }
+        _ => (Box::new(io::stdin().lock()), String::from("<stdin>")),
+    };
+    let lines = lines
+        || path.is_some_and(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "ndjson" || ext == "jsonl")
+        });
+
+    if lines {
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.map_err(|err| format!("{name}: {err}"))?;
+            evaluate_line(queries, printer, line.as_bytes(), i + 1)?;
+        }
+    } else {
+        let value: Value = serde_json::from_reader(BufReader::new(reader))
+            .map_err(|err| format!("{name}: {err}"))?;
+        printer.print(queries, &value, None);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "http")]
+async fn fetch(url: &str, queries: &Queries, printer: &Printer) -> Result<(), String> {
+    let response = reqwest::get(url)
+        .await
+        .and_then(Response::error_for_status)
+        .map_err(|err| err.to_string())?;
+
+    if is_ndjson(&response) {
+        fetch_lines(queries, printer, response).await
+    } else {
+        let body = response.bytes().await.map_err(|err| err.to_string())?;
+        let value: Value = serde_json::from_slice(&body).map_err(|err| err.to_string())?;
+        printer.print(queries, &value, None);
+        Ok(())
+    }
+}
+
+/// Evaluate `queries` against each line of `response` as it arrives.
+#[cfg(feature = "http")]
+async fn fetch_lines(
+    queries: &Queries,
+    printer: &Printer,
+    mut response: Response,
+) -> Result<(), String> {
+    let mut buffer = Vec::new();
+    let mut line = 0;
+
+    while let Some(chunk) = response.chunk().await.map_err(|err| err.to_string())? {
+        buffer.extend_from_slice(&chunk);
+        // Evaluate every complete line and keep the rest for the next chunk.
+        if let Some(end) = buffer.iter().rposition(|b| *b == b'\n') {
+            let (complete, _) = buffer.split_at(end + 1);
+            for text in complete.lines() {
+                line += 1;
+                let text = text.map_err(|err| format!("line {line}: {err}"))?;
+                evaluate_line(queries, printer, text.as_bytes(), line)?;
+            }
+            buffer.drain(..=end);
+        }
+    }
+
+    if !buffer.is_empty() {
+        evaluate_line(queries, printer, &buffer, line + 1)?;
+    }
+
+    Ok(())
+}
+
+fn evaluate_line(
+    queries: &Queries,
+    printer: &Printer,
+    text: &[u8],
+    line: usize,
+) -> Result<(), String> {
+    if text.trim_ascii().is_empty() {
+        return Ok(());
+    }
+    let value: Value = serde_json::from_slice(text).map_err(|err| format!("line {line}: {err}"))?;
+    printer.print(queries, &value, Some(line));
+    Ok(())
+}
+
+impl Printer {
+    fn print(&self, queries: &Queries, value: &Value, line: Option<usize>) {
+        for (expr, query) in queries.queries.iter() {
+            let mut prefix = Vec::with_capacity(2);
+            if let Some(line) = line {
+                prefix.push(line.to_string());
+            }
+            if queries.keyed {
+                prefix.push(expr.clone());
+            }
+
+            let nodes = query.find(value, &ENV);
+            match self.output {
+                Output::Count => println!("{}", join(&prefix, nodes.len().to_string())),
+                Output::Plain => {
+                    for node in nodes.iter() {
+                        println!("{}\t{}", join(&prefix, node.path()), node.value);
+                    }
+                }
+                Output::Paths => {
+                    for node in nodes.iter() {
+                        println!("{}", join(&prefix, node.path()));
+                    }
+                }
+                Output::Pretty => {
+                    for node in nodes.iter() {
+                        let mut pretty = String::new();
+                        self.write_value(node.value, 0, &mut pretty);
+                        let path = join(&prefix, node.path());
+                        println!("{}\n{pretty}", self.paint(PATH_COLOR, &path));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Write `value` to `out`, indented two spaces per level of nesting.
+    fn write_value(&self, value: &Value, depth: usize, out: &mut String) {
+        let indent = |depth: usize| "  ".repeat(depth);
+        match value {
+            Value::Array(arr) if !arr.is_empty() => {
+                out.push_str("[\n");
+                for (i, item) in arr.iter().enumerate() {
+                    out.push_str(&indent(depth + 1));
+                    self.write_value(item, depth + 1, out);
+                    out.push_str(if i + 1 < arr.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&indent(depth));
+                out.push(']');
+            }
+            Value::Object(obj) if !obj.is_empty() => {
+                out.push_str("{\n");
+                for (i, (key, item)) in obj.iter().enumerate() {
+                    out.push_str(&indent(depth + 1));
+                    out.push_str(&self.paint(KEY_COLOR, &Value::String(key.clone()).to_string()));
+                    out.push_str(": ");
+                    self.write_value(item, depth + 1, out);
+                    out.push_str(if i + 1 < obj.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&indent(depth));
+                out.push('}');
+            }
+            Value::Null => out.push_str(&self.paint(NULL_COLOR, "null")),
+            Value::String(_) => out.push_str(&self.paint(STRING_COLOR, &value.to_string())),
+            _ => out.push_str(&value.to_string()),
+        }
+    }
+
+    /// `text` wrapped in the ANSI escape sequences for `color`, if colors
+    /// are enabled.
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{color}m{text}\x1b[0m")
+        } else {
+            text.to_owned()
+        }
+    }
+}
+
+/// `prefix` and `last` separated by tabs.
+fn join(prefix: &[String], last: String) -> String {
+    let mut fields = prefix.to_vec();
+    fields.push(last);
+    fields.join("\t")
+}
+
+#[cfg(feature = "http")]
+fn is_ndjson(response: &Response) -> bool {
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase());
+
+    match content_type {
+        Some(content_type) if NDJSON_CONTENT_TYPES.contains(&content_type.as_str()) => true,
+        _ => {
+            let path = response.url().path();
+            path.ends_with(".ndjson") || path.ends_with(".jsonl")
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Cached query results for read-heavy workloads.
+//!
+//! A [`ResultCache`] remembers the locations of nodes selected by a query
+//! from a document, keyed by the query's text and a SHA-256 hash of the
+//! document. Evaluating the same query against an unchanged document again
+//! rebuilds the node list from those locations instead of evaluating the
+//! query.
+//!
+//! Hashing a large document on every call can cost more than the query, so
+//! [`ResultCache::find_cached_by_id`] keys results on an id the caller gives
+//! the document instead.
+//!
+//! ```
+//! use std::num::NonZeroUsize;
+//!
+//! use jsonpath_rfc9535_locations::cache::ResultCache;
+//! use serde_json::json;
+//!
+//! let cache = ResultCache::new(NonZeroUsize::new(100).unwrap());
+//! let value = json!({"users": [{"name": "a"}, {"name": "b"}]});
+//!
+//! let nodes = cache.find_cached("$.users[*].name", &value).unwrap();
+//! assert_eq!(nodes.len(), 2);
+//!
+//! let nodes = cache.find_cached("$.users[*].name", &value).unwrap();
+//! assert_eq!(nodes.len(), 2);
+//! assert_eq!(cache.stats().hits, 1);
+//! ```
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use lru::LruCache;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    conslist::ConsList,
+    errors::JSONPathError,
+    node::{Node, NodeList, PathElement},
+    Query, ENV,
+};
+
+/// Identifies the results of one query against one document.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    /// The query, exactly as it was written. Canonical forms don't escape
+    /// names, so different queries can share one.
+    pub query: String,
+    pub document: DocumentKey,
+}
+
+/// Identifies a document in a [`ResultCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DocumentKey {
+    /// The hash of the document, from [`document_hash`].
+    Hash([u8; 32]),
+    /// The id the caller gave the document.
+    Id(u64),
+}
+
+/// Node locations remembered for a [`CacheKey`], each a list of path
+/// elements starting from the root.
+pub type CachedLocations = Arc<Vec<Vec<PathElement>>>;
+
+/// Where a [`ResultCache`] keeps its entries.
+pub trait CacheStorage: Send {
+    fn get(&mut self, key: &CacheKey) -> Option<CachedLocations>;
+
+    fn put(&mut self, key: CacheKey, locations: CachedLocations);
+
+    /// Forget all entries for `document`.
+    fn remove_document(&mut self, document: DocumentKey);
+
+    /// Forget all entries.
+    fn clear(&mut self);
+}
+
+/// In-memory storage that forgets the least recently used entry when full.
+pub struct LruStorage {
+    entries: LruCache<CacheKey, CachedLocations>,
+}
+
+impl LruStorage {
+    /// Storage for at most `capacity` query results.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        LruStorage {
+            entries: LruCache::new(capacity),
+        }
+    }
+}
+
+impl CacheStorage for LruStorage {
+    fn get(&mut self, key: &CacheKey) -> Option<CachedLocations> {
+        self.entries.get(key).cloned()
+    }
+
+    fn put(&mut self, key: CacheKey, locations: CachedLocations) {
+        self.entries.put(key, locations);
+    }
+
+    fn remove_document(&mut self, document: DocumentKey) {
+        let keys: Vec<CacheKey> = self
+            .entries
+            .iter()
+            .filter(|(key, _)| key.document == document)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in keys {
+            self.entries.pop(&key);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Cache hit and miss counts for a [`ResultCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// Query results cached by query text and document hash.
+///
+/// A `ResultCache` can be shared between threads.
+pub struct ResultCache<S: CacheStorage = LruStorage> {
+    storage: Mutex<S>,
+    stats: Mutex<CacheStats>,
+}
+
+impl ResultCache<LruStorage> {
+    /// A cache holding the results of at most `capacity` queries in memory.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        ResultCache::with_storage(LruStorage::new(capacity))
+    }
+}
+
+impl<S: CacheStorage> ResultCache<S> {
+    pub fn with_storage(storage: S) -> Self {
+        ResultCache {
+            storage: Mutex::new(storage),
+            stats: Mutex::new(CacheStats::default()),
+        }
+    }
+
+    /// Like [`crate::find`], but reusing the locations of nodes found by an
+    /// earlier call with the same query text and an identical document.
+    pub fn find_cached<'v>(
+        &self,
+        expr: &str,
+        value: &'v Value,
+    ) -> Result<NodeList<'v>, JSONPathError> {
+        self.find_cached_by_key(expr, DocumentKey::Hash(document_hash(value)), value)
+    }
+
+    /// Like [`ResultCache::find_cached`], but keyed on `document`, an id the
+    /// caller gives `value`, instead of a hash of it.
+    ///
+    /// Results for a document are reused until it's invalidated, so give
+    /// each version of a document its own id, or call
+    /// [`ResultCache::invalidate_document`] after changing it.
+    pub fn find_cached_by_id<'v>(
+        &self,
+        expr: &str,
+        document: u64,
+        value: &'v Value,
+    ) -> Result<NodeList<'v>, JSONPathError> {
+        self.find_cached_by_key(expr, DocumentKey::Id(document), value)
+    }
+
+    fn find_cached_by_key<'v>(
+        &self,
+        expr: &str,
+        document: DocumentKey,
+        value: &'v Value,
+    ) -> Result<NodeList<'v>, JSONPathError> {
+        let query = Query::standard(expr)?;
+        let key = CacheKey {
+            query: expr.to_string(),
+            document,
+        };
+
+        let cached = self.storage().get(&key);
+
+        if let Some(nodes) = cached.and_then(|locations| rebuild(value, &locations)) {
+            self.counts().hits += 1;
+            return Ok(nodes);
+        }
+
+        self.counts().misses += 1;
+        let nodes = query.find(value, &ENV);
+        let locations = nodes
+            .iter()
+            .map(|node| node.path_elements().into_iter().cloned().collect())
+            .collect();
+        self.storage().put(key, Arc::new(locations));
+        Ok(nodes)
+    }
+
+    /// Forget cached results for `value`. Call this before changing a
+    /// document in place, so its old results don't take up space.
+    pub fn invalidate(&self, value: &Value) {
+        self.storage()
+            .remove_document(DocumentKey::Hash(document_hash(value)));
+    }
+
+    /// Forget cached results for the document with id `document`. Call
+    /// this after changing a document cached by id in place.
+    pub fn invalidate_document(&self, document: u64) {
+        self.storage().remove_document(DocumentKey::Id(document));
+    }
+
+    /// Forget all cached results.
+    pub fn clear(&self) {
+        self.storage().clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        *self.counts()
+    }
+
+    // Cached locations are checked against the document before they're
+    // used, so storage poisoned by a panic is still safe to read.
+    fn storage(&self) -> MutexGuard<'_, S> {
+        self.storage.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn counts(&self) -> MutexGuard<'_, CacheStats> {
+        self.stats.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A SHA-256 hash of `value` serialized as JSON.
+///
+/// Results are rebuilt from cached locations without evaluating the query,
+/// so a collision would return another document's nodes. SHA-256 makes that
+/// impractical, even for documents built to collide.
+///
+/// Objects are hashed in member order, so with the `preserve_order` feature,
+/// documents that differ only in member order have different hashes.
+pub fn document_hash(value: &Value) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    // Neither serializing a `Value` nor writing to the hasher can fail.
+    let _ = serde_json::to_writer(&mut hasher, value);
+    hasher.finalize().into()
+}
+
+/// Nodes at `locations` in `value`, or `None` if any location doesn't
+/// exist, which happens if a document cached by id changed without being
+/// invalidated.
+fn rebuild<'v>(value: &'v Value, locations: &[Vec<PathElement>]) -> Option<NodeList<'v>> {
+    locations
+        .iter()
+        .map(|elements| {
+            elements.iter().try_fold(
+                Node {
+                    value,
+                    location: ConsList::new(),
+                },
+                |node, element| match (node.value, element) {
+                    (Value::Array(arr), PathElement::Index(i)) => arr
+                        .get(*i)
+                        .map(|v| Node::new_array_element(v, &node.location, *i)),
+                    (Value::Object(obj), PathElement::Name(name)) => obj
+                        .get(name)
+                        .map(|v| Node::new_object_member(v, &node.location, name.clone())),
+                    _ => None,
+                },
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,119 @@
+//! Evaluate JSONPath queries against CBOR documents.
+//!
+//! CBOR values are converted to JSON following RFC 8949 section 6.1. Byte
+//! strings become base64url encoded strings, tags are ignored in favour of
+//! their content, non-finite floats become `null` and integers outside the
+//! range of a 64-bit integer become floats. Map keys must be text strings or
+//! integers, the latter being matched by their decimal representation.
+//!
+//! Keys with the same name, like `1` and `"1"`, are resolved by a
+//! [`DuplicateKeys`] policy, the last one winning with [`to_json`] and
+//! [`find`].
+use ciborium::Value as CborValue;
+use serde_json::{Map, Value};
+
+use crate::{
+    base64,
+    document::DuplicateKeys,
+    env::Environment,
+    errors::JSONPathError,
+    format::{self, float, Format},
+    function::Functions,
+    jsonpath::ENV,
+    node::PathElement,
+};
+
+/// Convert a CBOR value to a JSON value.
+pub fn to_json(value: &CborValue) -> Result<Value, JSONPathError> {
+    to_json_with(value, DuplicateKeys::default())
+}
+
+/// Convert a CBOR value to a JSON value, resolving map keys with the same
+/// name according to `duplicate_keys`.
+pub fn to_json_with(
+    value: &CborValue,
+    duplicate_keys: DuplicateKeys,
+) -> Result<Value, JSONPathError> {
+    Ok(match value {
+        CborValue::Null => Value::Null,
+        CborValue::Bool(b) => Value::Bool(*b),
+        CborValue::Integer(i) => {
+            let i = i128::from(*i);
+            if let Ok(n) = i64::try_from(i) {
+                Value::from(n)
+            } else if let Ok(n) = u64::try_from(i) {
+                Value::from(n)
+            } else {
+                float(i as f64)
+            }
+        }
+        CborValue::Float(f) => float(*f),
+        CborValue::Text(s) => Value::String(s.to_owned()),
+        CborValue::Bytes(b) => Value::String(base64::encode_url(b)),
+        CborValue::Tag(_, v) => to_json_with(v, duplicate_keys)?,
+        CborValue::Array(arr) => Value::Array(
+            arr.iter()
+                .map(|v| to_json_with(v, duplicate_keys))
+                .collect::<Result<_, _>>()?,
+        ),
+        CborValue::Map(map) => {
+            let mut obj = Map::new();
+            for (k, v) in map {
+                duplicate_keys
+                    .insert(&mut obj, key_name(k)?, to_json_with(v, duplicate_keys)?)
+                    .map_err(JSONPathError::document)?;
+            }
+            Value::Object(obj)
+        }
+        _ => return Err(JSONPathError::typ(String::from("unsupported CBOR value"))),
+    })
+}
+
+/// Find values in a CBOR document matching the JSONPath expression `expr`.
+///
+/// Returns `(normalized path, value)` pairs borrowing from `value`.
+pub fn find<'a>(
+    expr: &str,
+    value: &'a CborValue,
+) -> Result<Vec<(String, &'a CborValue)>, JSONPathError> {
+    find_with(&ENV, expr, value)
+}
+
+/// Like [`find`], parsing `expr` and evaluating it with `env`, and resolving
+/// map keys with the same name according to `env.duplicate_keys`.
+pub fn find_with<'a, F: Functions>(
+    env: &'static Environment<F>,
+    expr: &str,
+    value: &'a CborValue,
+) -> Result<Vec<(String, &'a CborValue)>, JSONPathError> {
+    format::find_in(env, expr, value)
+}
+
+fn key_name(key: &CborValue) -> Result<String, JSONPathError> {
+    match key {
+        CborValue::Text(s) => Ok(s.to_owned()),
+        CborValue::Integer(i) => Ok(i128::from(*i).to_string()),
+        CborValue::Tag(_, v) => key_name(v),
+        _ => Err(JSONPathError::typ(String::from(
+            "CBOR map keys must be text strings or integers",
+        ))),
+    }
+}
+
+impl Format for CborValue {
+    fn to_json(&self, duplicate_keys: DuplicateKeys) -> Result<Value, JSONPathError> {
+        to_json_with(self, duplicate_keys)
+    }
+
+    fn child(&self, element: &PathElement, duplicate_keys: DuplicateKeys) -> Option<&Self> {
+        match (self, element) {
+            (CborValue::Tag(_, v), _) => v.child(element, duplicate_keys),
+            (CborValue::Array(arr), PathElement::Index(i)) => arr.get(*i),
+            (CborValue::Map(map), PathElement::Name(name)) => duplicate_keys
+                .select(map.iter().map(|(k, v)| (k, v)), |k| {
+                    key_name(k).is_ok_and(|k| &k == name)
+                }),
+            _ => None,
+        }
+    }
+}
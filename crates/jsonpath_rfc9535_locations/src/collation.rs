@@ -0,0 +1,59 @@
+//! Ordering strings compared with `<`, `<=`, `>` and `>=`.
+//!
+//! RFC 9535 orders strings by Unicode scalar value, so `'B' < 'a'`. An
+//! [`Environment`](crate::env::Environment) can use another [`Collation`]
+//! instead, like [`CaseInsensitive`], or a closure wrapping a locale-aware
+//! collator from a crate like `icu_collator`. `==` and `!=` always compare
+//! strings exactly.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::{collation::CaseInsensitive, env::Environment, Query};
+//! use serde_json::json;
+//!
+//! let env = Box::leak(Box::new(Environment::new().with_collation(CaseInsensitive)));
+//! let query = Query::standard("$[?@ >= 'b']").unwrap();
+//! let value = json!(["a", "B", "c"]);
+//! assert_eq!(query.find(&value, env).values(), vec!["B", "c"]);
+//!
+//! let env = Box::leak(Box::new(
+//!     Environment::new().with_collation(|a: &str, b: &str| a.len().cmp(&b.len())),
+//! ));
+//! let query = Query::standard("$[?@ < 'xy']").unwrap();
+//! assert_eq!(query.find(&value, env).values(), vec!["a", "B", "c"]);
+//! ```
+use std::cmp::Ordering;
+
+/// Orders strings for `<`, `<=`, `>` and `>=` comparisons in filter
+/// expressions.
+pub trait Collation: Sync {
+    fn compare(&self, left: &str, right: &str) -> Ordering;
+}
+
+impl<F: Fn(&str, &str) -> Ordering + Sync> Collation for F {
+    fn compare(&self, left: &str, right: &str) -> Ordering {
+        self(left, right)
+    }
+}
+
+/// The RFC 9535 order, by Unicode scalar value. This is the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodePoint;
+
+impl Collation for CodePoint {
+    fn compare(&self, left: &str, right: &str) -> Ordering {
+        // UTF-8 byte order is the same as scalar value order.
+        left.cmp(right)
+    }
+}
+
+/// Scalar value order of lowercased strings, so `'a' < 'B'` and `'A' >= 'a'`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CaseInsensitive;
+
+impl Collation for CaseInsensitive {
+    fn compare(&self, left: &str, right: &str) -> Ordering {
+        left.chars()
+            .flat_map(char::to_lowercase)
+            .cmp(right.chars().flat_map(char::to_lowercase))
+    }
+}
@@ -86,10 +86,10 @@ impl<T> ConsList<T> {
             let len = self.len() - n;
             let mut head = self.front.as_ref();
             for _ in 0..n {
-                head = head.unwrap().next.as_ref();
+                head = head.and_then(|node| node.next.as_ref());
             }
             Self {
-                front: Some(head.unwrap().clone()),
+                front: head.cloned(),
                 length: len,
             }
         }
@@ -121,6 +121,12 @@ impl<T> ConsList<T> {
         self.length
     }
 
+    /// The size of the allocation holding each element, including its
+    /// reference counts and the link to the next element.
+    pub(crate) fn cell_size() -> usize {
+        2 * std::mem::size_of::<usize>() + std::mem::size_of::<Node<T>>()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
@@ -242,7 +248,8 @@ impl<'a, T> IntoIterator for &'a ConsList<T> {
 
 #[cfg(test)]
 mod tests {
-    use std::hash;
+    use std::cmp::Ordering;
+    use std::hash::{Hash, Hasher};
 
     use super::ConsList;
     use std::collections::hash_map::DefaultHasher;
@@ -351,14 +358,18 @@ mod tests {
         let mut x = ConsList::new();
         let mut y = ConsList::new();
 
-        let mut h = DefaultHasher::new();
-
-        assert!(hash::Hash::hash(&x, &mut h) == hash::Hash::hash(&y, &mut h));
+        assert_eq!(hash_of(&x), hash_of(&y));
 
         x = x.append(1).append(2).append(3);
         y = y.append(1).append(4).tail().append(2).append(3);
 
-        assert!(hash::Hash::hash(&x, &mut h) == hash::Hash::hash(&y, &mut h));
+        assert_eq!(hash_of(&x), hash_of(&y));
+    }
+
+    fn hash_of<T: Hash>(value: &T) -> u64 {
+        let mut h = DefaultHasher::new();
+        value.hash(&mut h);
+        h.finish()
     }
 
     #[test]
@@ -373,34 +384,23 @@ mod tests {
 
     #[test]
     fn test_ord_nan() {
-        let nan = 0.0f64 / 0.0;
+        let nan = f64::NAN;
         let n = list_from(&[nan]);
         let m = list_from(&[nan]);
-        assert!(!(n < m));
-        assert!(!(n > m));
-        assert!(!(n <= m));
-        assert!(!(n >= m));
+        assert_eq!(n.partial_cmp(&m), None);
 
         let n = list_from(&[nan]);
         let one = list_from(&[1.0f64]);
-        assert!(!(n < one));
-        assert!(!(n > one));
-        assert!(!(n <= one));
-        assert!(!(n >= one));
+        assert_eq!(n.partial_cmp(&one), None);
 
         let u = list_from(&[1.0f64, 2.0, nan]);
         let v = list_from(&[1.0f64, 2.0, 3.0]);
-        assert!(!(u < v));
-        assert!(!(u > v));
-        assert!(!(u <= v));
-        assert!(!(u >= v));
+        assert_eq!(u.partial_cmp(&v), None);
 
         let s = list_from(&[1.0f64, 2.0, 4.0, 2.0]);
         let t = list_from(&[1.0f64, 2.0, 3.0, 2.0]);
-        assert!(!(s < t));
-        assert!(s > one);
-        assert!(!(s <= one));
-        assert!(s >= one);
+        assert_eq!(s.partial_cmp(&t), Some(Ordering::Greater));
+        assert_eq!(s.partial_cmp(&one), Some(Ordering::Greater));
     }
 
     #[test]
@@ -408,10 +408,10 @@ mod tests {
         let list: ConsList<i32> = (0..10).rev().collect();
         assert_eq!(format!("{:?}", list), "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]");
 
-        let list: ConsList<&str> = vec!["just", "one", "test", "more"]
+        let list: ConsList<&str> = ["just", "one", "test", "more"]
             .iter()
             .rev()
-            .map(|&s| s)
+            .copied()
             .collect();
         assert_eq!(format!("{:?}", list), r#"["just", "one", "test", "more"]"#);
     }
@@ -0,0 +1,226 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{
+    errors::JSONPathError,
+    node::{Node, PathElement},
+    path::{resolve, resolve_mut, write_elements, NormalizedPath},
+};
+
+/// A JSON Patch style edit to a JSON document.
+///
+/// Paths are lists of path elements starting from the root, like those
+/// given by `Node::path_elements` and `Cursor::elements`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Insert `value` into an array at the index given by the last path
+    /// element, or add or replace an object member.
+    Add {
+        path: Vec<PathElement>,
+        value: Value,
+    },
+    /// Remove an array element or object member.
+    Remove { path: Vec<PathElement> },
+    /// Replace the value at `path`.
+    Replace {
+        path: Vec<PathElement>,
+        value: Value,
+    },
+}
+
+impl Edit {
+    pub fn path(&self) -> &[PathElement] {
+        match self {
+            Edit::Add { path, .. } | Edit::Remove { path } | Edit::Replace { path, .. } => path,
+        }
+    }
+
+    /// Apply this edit to `doc`.
+    pub fn apply(&self, doc: &mut Value) -> Result<(), JSONPathError> {
+        let Some((last, parent)) = self.path().split_last() else {
+            return match self {
+                Edit::Remove { .. } => Err(JSONPathError::typ(String::from(
+                    "can't remove the root value",
+                ))),
+                Edit::Add { value, .. } | Edit::Replace { value, .. } => {
+                    *doc = value.clone();
+                    Ok(())
+                }
+            };
+        };
+
+        let target = resolve_mut(parent, doc).ok_or_else(|| missing(self.path()))?;
+
+        match (self, target, last) {
+            (Edit::Add { value, .. }, Value::Array(arr), PathElement::Index(i))
+                if *i <= arr.len() =>
+            {
+                arr.insert(*i, value.clone());
+            }
+            (Edit::Remove { .. }, Value::Array(arr), PathElement::Index(i)) if *i < arr.len() => {
+                arr.remove(*i);
+            }
+            (Edit::Replace { value, .. }, Value::Array(arr), PathElement::Index(i)) => {
+                let item = arr.get_mut(*i).ok_or_else(|| missing(self.path()))?;
+                *item = value.clone();
+            }
+            (Edit::Add { value, .. }, Value::Object(obj), PathElement::Name(name)) => {
+                obj.insert(name.to_owned(), value.clone());
+            }
+            (Edit::Remove { .. }, Value::Object(obj), PathElement::Name(name))
+                if obj.contains_key(name) =>
+            {
+                obj.remove(name);
+            }
+            (Edit::Replace { value, .. }, Value::Object(obj), PathElement::Name(name))
+                if obj.contains_key(name) =>
+            {
+                obj.insert(name.to_owned(), value.clone());
+            }
+            _ => return Err(missing(self.path())),
+        }
+
+        Ok(())
+    }
+}
+
+/// A normalized path bound to a document, kept up to date as edits are
+/// applied to that document.
+///
+/// Inserting or removing an array element shifts cursors pointing at later
+/// elements of the same array. A cursor pointing at (or below) a value that
+/// is removed becomes invalid, as does a cursor pointing below a value that
+/// is replaced. A cursor pointing at a replaced value stays valid, and points
+/// at the new value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    elements: Vec<PathElement>,
+    valid: bool,
+}
+
+impl Cursor {
+    pub fn new(elements: Vec<PathElement>) -> Self {
+        Cursor {
+            elements,
+            valid: true,
+        }
+    }
+
+    /// This cursor's location as a list of path elements, starting from the root.
+    pub fn elements(&self) -> &[PathElement] {
+        &self.elements
+    }
+
+    /// This cursor's location as a normalized path.
+    pub fn path(&self) -> String {
+        self.to_string()
+    }
+
+    /// Returns `false` if the value this cursor pointed to has been removed,
+    /// or was inside a value that has been removed or replaced.
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    /// The value this cursor points to in `doc`, if it is valid.
+    pub fn get<'v>(&self, doc: &'v Value) -> Option<&'v Value> {
+        if !self.valid {
+            return None;
+        }
+
+        resolve(&self.elements, doc)
+    }
+
+    /// A mutable reference to the value this cursor points to in `doc`, if
+    /// it is valid.
+    pub fn get_mut<'v>(&self, doc: &'v mut Value) -> Option<&'v mut Value> {
+        if !self.valid {
+            return None;
+        }
+
+        resolve_mut(&self.elements, doc)
+    }
+
+    /// Update this cursor to account for `edit`, which has been or is about
+    /// to be applied to its document.
+    pub fn update(&mut self, edit: &Edit) {
+        if !self.valid {
+            return;
+        }
+
+        let path = edit.path();
+
+        let Some((last, parent)) = path.split_last() else {
+            // An edit to the root value invalidates everything but the root.
+            self.valid = self.elements.is_empty() && !matches!(edit, Edit::Remove { .. });
+            return;
+        };
+
+        if self.elements.len() < path.len() || !self.elements.starts_with(parent) {
+            return;
+        }
+
+        let below = self.elements.len() > path.len();
+        let at = parent.len();
+
+        let Some(element) = self.elements.get_mut(at) else {
+            return;
+        };
+
+        match (edit, element, last) {
+            (Edit::Add { .. }, PathElement::Index(j), PathElement::Index(i)) if *j >= *i => {
+                *j += 1;
+            }
+            (Edit::Remove { .. }, PathElement::Index(j), PathElement::Index(i)) => {
+                if *j == *i {
+                    self.valid = false;
+                } else if *j > *i {
+                    *j -= 1;
+                }
+            }
+            (Edit::Replace { .. }, PathElement::Index(j), PathElement::Index(i)) if *j == *i => {
+                self.valid = !below;
+            }
+            (Edit::Remove { .. }, PathElement::Name(b), PathElement::Name(a)) if a == b => {
+                self.valid = false;
+            }
+            (
+                Edit::Add { .. } | Edit::Replace { .. },
+                PathElement::Name(b),
+                PathElement::Name(a),
+            ) if a == b => {
+                self.valid = !below;
+            }
+            _ => (),
+        }
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_elements(f, &self.elements)
+    }
+}
+
+impl From<&Node<'_>> for Cursor {
+    fn from(node: &Node<'_>) -> Self {
+        Cursor::new(node.path_elements().into_iter().cloned().collect())
+    }
+}
+
+/// Apply `edit` to `doc` and update `cursors` to match.
+pub fn apply(doc: &mut Value, edit: &Edit, cursors: &mut [Cursor]) -> Result<(), JSONPathError> {
+    edit.apply(doc)?;
+    for cursor in cursors.iter_mut() {
+        cursor.update(edit);
+    }
+    Ok(())
+}
+
+fn missing(path: &[PathElement]) -> JSONPathError {
+    JSONPathError::typ(format!(
+        "no value at {}",
+        NormalizedPath::new(path.to_vec())
+    ))
+}
@@ -0,0 +1,148 @@
+//! Reading documents with duplicate object member names.
+//!
+//! JSON text can name the same member more than once, and parsers disagree
+//! about what that means. The evaluator only ever sees one value per name,
+//! so [`Environment::deserialize_document`](crate::env::Environment::deserialize_document)
+//! resolves duplicates as the environment's [`DuplicateKeys`] policy says,
+//! whatever format the document is in. So do the `find_with` functions of
+//! the CBOR, MessagePack, YAML and TOML adapters, where distinct keys can
+//! share a name, like the integer `1` and the string `"1"`.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::{document::DuplicateKeys, env::Environment};
+//!
+//! let env = Environment::new().with_duplicate_keys(DuplicateKeys::First);
+//! let value = env.parse_document(r#"{"a": 1, "a": 2}"#).unwrap();
+//! assert_eq!(value["a"], 1);
+//!
+//! let env = Environment::new().with_duplicate_keys(DuplicateKeys::Error);
+//! assert!(env.parse_document(r#"{"a": 1, "a": 2}"#).is_err());
+//! ```
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+
+/// What to do when an object names the same member more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Keep the first value, ignoring later ones.
+    First,
+    /// Keep the last value. This is what serde_json does.
+    #[default]
+    Last,
+    /// Fail to read the document.
+    Error,
+}
+
+impl DuplicateKeys {
+    /// Add member `key` to `obj`, or return an error message if `key` is a
+    /// duplicate and duplicates are errors.
+    pub(crate) fn insert(
+        self,
+        obj: &mut Map<String, Value>,
+        key: String,
+        value: Value,
+    ) -> Result<(), String> {
+        if obj.contains_key(&key) {
+            match self {
+                DuplicateKeys::First => return Ok(()),
+                DuplicateKeys::Last => (),
+                DuplicateKeys::Error => return Err(format!("duplicate member name `{key}`")),
+            }
+        }
+        obj.insert(key, value);
+        Ok(())
+    }
+
+    /// The value of the member of `members` for which `is_named` is `true`,
+    /// choosing the same member as [`DuplicateKeys::insert`] would.
+    #[cfg(any(feature = "cbor", feature = "msgpack", feature = "yaml"))]
+    pub(crate) fn select<'a, K: 'a, V: 'a>(
+        self,
+        members: impl Iterator<Item = (&'a K, &'a V)>,
+        is_named: impl Fn(&K) -> bool,
+    ) -> Option<&'a V> {
+        let mut named = members.filter(|(k, _)| is_named(k)).map(|(_, v)| v);
+        match self {
+            DuplicateKeys::First | DuplicateKeys::Error => named.next(),
+            DuplicateKeys::Last => named.last(),
+        }
+    }
+}
+
+/// Deserializes a `Value`, resolving duplicate member names with a
+/// `DuplicateKeys` policy.
+#[derive(Clone, Copy)]
+pub(crate) struct Document(pub(crate) DuplicateKeys);
+
+impl<'de> DeserializeSeed<'de> for Document {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Document {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any valid JSON value")
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_f64<E>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::from(v))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut arr = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(value) = seq.next_element_seed(self)? {
+            arr.push(value);
+        }
+        Ok(Value::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut obj = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(self)?;
+            self.0
+                .insert(&mut obj, key, value)
+                .map_err(de::Error::custom)?;
+        }
+        Ok(Value::Object(obj))
+    }
+}
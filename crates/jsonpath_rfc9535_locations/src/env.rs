@@ -1,15 +1,41 @@
-use std::collections::HashMap;
+use std::time::Instant;
+
+use serde::de::{DeserializeSeed, Deserializer};
 
 use crate::{
+    collation::{CodePoint, Collation},
+    document::{Document, DuplicateKeys},
     errors::JSONPathError,
-    function::FunctionRegister,
+    function::{FunctionRegister, Functions},
     node::NodeList,
-    standard_functions::{Count, Length, Match, Search, Value},
-    Query,
+    options::{FindOptions, FindResult},
+    standard_functions::{Count, Length, Match, Search, StandardFunctions, Value},
+    JSONPathParser,
 };
 
-pub struct Environment {
-    pub function_register: FunctionRegister,
+/// Function extensions available to queries, held by a [`Functions`]
+/// implementation.
+///
+/// The default registry, [`FunctionRegister`], accepts custom function
+/// extensions. [`Environment::standard`] is limited to the standard
+/// functions, and is cheaper to call them through.
+pub struct Environment<F = FunctionRegister> {
+    pub function_register: F,
+    /// How documents read with [`Environment::deserialize_document`] resolve
+    /// duplicate object member names.
+    pub duplicate_keys: DuplicateKeys,
+    /// How `<`, `<=`, `>` and `>=` order strings.
+    pub collation: Box<dyn Collation>,
+}
+
+/// The order in which wildcard and descendant segments visit object members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberOrder {
+    /// Members are visited in the order they appear in the document. This
+    /// is the case when serde_json's `preserve_order` feature is enabled.
+    Document,
+    /// Members are visited in lexicographic order of their names.
+    Lexical,
 }
 
 impl Default for Environment {
@@ -20,14 +46,95 @@ impl Default for Environment {
 
 impl Environment {
     pub fn new() -> Self {
-        let mut function_register: FunctionRegister = HashMap::new();
-        function_register.insert("count".to_string(), Box::new(Count::new()));
-        function_register.insert("length".to_string(), Box::new(Length::new()));
-        function_register.insert("match".to_string(), Box::new(Match::new()));
-        function_register.insert("search".to_string(), Box::new(Search::new()));
-        function_register.insert("value".to_string(), Box::new(Value::new()));
+        let mut function_register = FunctionRegister::new();
+        function_register.register_standard("count", Box::new(Count::new()));
+        function_register.register_standard("length", Box::new(Length::new()));
+        function_register.register_standard("match", Box::new(Match::new()));
+        function_register.register_standard("search", Box::new(Search::new()));
+        function_register.register_standard("value", Box::new(Value::new()));
+
+        Self::with_functions(function_register)
+    }
+}
+
+impl Environment<StandardFunctions> {
+    /// An environment with only the standard functions, which are called
+    /// without a hash lookup or dynamic dispatch.
+    pub fn standard() -> Self {
+        Environment::with_functions(StandardFunctions::new())
+    }
+}
+
+impl<F: Functions> Environment<F> {
+    /// An environment calling functions in `function_register`.
+    pub fn with_functions(function_register: F) -> Self {
+        Environment {
+            function_register,
+            duplicate_keys: DuplicateKeys::default(),
+            collation: Box::new(CodePoint),
+        }
+    }
+
+    pub fn with_duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+
+    /// Order strings compared with `<`, `<=`, `>` and `>=` by `collation`
+    /// instead of by code point.
+    pub fn with_collation(mut self, collation: impl Collation + 'static) -> Self {
+        self.collation = Box::new(collation);
+        self
+    }
+
+    /// Read a document from any serde data format, resolving duplicate
+    /// object member names according to `duplicate_keys`.
+    pub fn deserialize_document<'de, D: Deserializer<'de>>(
+        &self,
+        deserializer: D,
+    ) -> Result<serde_json::Value, D::Error> {
+        Document(self.duplicate_keys).deserialize(deserializer)
+    }
+
+    /// Parse a JSON document, resolving duplicate object member names
+    /// according to `duplicate_keys`.
+    pub fn parse_document(&self, json: &str) -> Result<serde_json::Value, JSONPathError> {
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let value = self
+            .deserialize_document(&mut deserializer)
+            .and_then(|value| deserializer.end().map(|_| value))
+            .map_err(|err| JSONPathError::document(err.to_string()))?;
+        Ok(value)
+    }
+
+    /// A parser that knows the signatures of this environment's functions,
+    /// and which of them read locations.
+    pub fn parser(&self) -> JSONPathParser {
+        let mut parser = JSONPathParser::new();
+        parser.functions = self.function_register.signatures();
+        parser.location_functions = parser
+            .functions
+            .keys()
+            .filter(|name| self.function_register.reads_location(name))
+            .cloned()
+            .collect();
+        parser
+    }
 
-        Self { function_register }
+    /// The order in which wildcard and descendant segments visit object
+    /// members.
+    ///
+    /// This follows serde_json's `Map`, so it reflects `preserve_order`
+    /// whether it was enabled by this crate's feature of the same name or
+    /// by any other crate in the dependency graph.
+    pub fn ordering(&self) -> MemberOrder {
+        let mut map = serde_json::Map::new();
+        map.insert("b".to_string(), serde_json::Value::Null);
+        map.insert("a".to_string(), serde_json::Value::Null);
+        match map.keys().next().map(String::as_str) {
+            Some("b") => MemberOrder::Document,
+            _ => MemberOrder::Lexical,
+        }
     }
 
     pub fn find<'a>(
@@ -35,7 +142,41 @@ impl Environment {
         expr: &str,
         value: &'a serde_json::Value,
     ) -> Result<NodeList<'a>, JSONPathError> {
-        let query = Query::standard(expr)?;
+        let query = self.parser().parse(expr)?;
         Ok(query.find(value, self))
     }
+
+    /// Same as `find`, configured by `options`. Nodes are counted as the
+    /// query selects them, so evaluation stops soon after there are more
+    /// than `options` allows. With a trace, the query is evaluated in full
+    /// before counting.
+    pub fn find_with_options<'a>(
+        &'static self,
+        expr: &str,
+        value: &'a serde_json::Value,
+        options: &FindOptions,
+    ) -> Result<FindResult<'a>, JSONPathError> {
+        let started = Instant::now();
+        options.check_query(expr).map_err(JSONPathError::limit)?;
+        let query = self.parser().parse(expr)?;
+
+        let mut collector = options.collector(started);
+        let trace = if options.traced() {
+            let (nodes, trace) = query.find_traced(value, self);
+            collector.extend(nodes).map_err(JSONPathError::limit)?;
+            Some(trace)
+        } else {
+            query
+                .find_each(value, self, |node| collector.push(node))
+                .map_err(JSONPathError::limit)?;
+            None
+        };
+
+        let (found, stats) = collector.finish(value);
+        Ok(FindResult {
+            found,
+            stats,
+            trace,
+        })
+    }
 }
@@ -0,0 +1,77 @@
+//! Populate values from the results of JSONPath queries.
+//!
+//! With the `derive` feature enabled, `#[derive(JsonPathExtract)]` implements
+//! [`JsonPathExtract`] for structs whose fields are annotated with JSONPath
+//! expressions. The functions in this module are used by generated code,
+//! which refers to nothing outside of this crate.
+use std::sync::OnceLock;
+
+use serde::de::DeserializeOwned;
+pub use serde_json::Value;
+
+use crate::{errors::JSONPathError, jsonpath::ENV, node::Node, Query};
+
+pub trait JsonPathExtract: Sized {
+    fn extract(value: &Value) -> Result<Self, JSONPathError>;
+}
+
+/// The query `expr`, parsed the first time it's needed and kept in `cell`.
+pub fn query<'c>(cell: &'c OnceLock<Query>, expr: &str) -> Result<&'c Query, JSONPathError> {
+    if let Some(query) = cell.get() {
+        return Ok(query);
+    }
+    let query = Query::standard(expr)?;
+    Ok(cell.get_or_init(|| query))
+}
+
+/// Deserialize the first node matched by `query`, failing if there are none.
+pub fn one<T: DeserializeOwned>(
+    query: &Query,
+    field: &str,
+    value: &Value,
+) -> Result<T, JSONPathError> {
+    match query.find(value, &ENV).first() {
+        Some(node) => deserialize(node, field),
+        None => Err(JSONPathError::typ(format!(
+            "expected a value for `{}` at {}, found nothing",
+            field, query
+        ))),
+    }
+}
+
+/// Deserialize the first node matched by `query`, if there is one.
+pub fn optional<T: DeserializeOwned>(
+    query: &Query,
+    field: &str,
+    value: &Value,
+) -> Result<Option<T>, JSONPathError> {
+    query
+        .find(value, &ENV)
+        .first()
+        .map(|node| deserialize(node, field))
+        .transpose()
+}
+
+/// Deserialize all nodes matched by `query`.
+pub fn all<T: DeserializeOwned>(
+    query: &Query,
+    field: &str,
+    value: &Value,
+) -> Result<Vec<T>, JSONPathError> {
+    query
+        .find(value, &ENV)
+        .iter()
+        .map(|node| deserialize(node, field))
+        .collect()
+}
+
+fn deserialize<T: DeserializeOwned>(node: &Node, field: &str) -> Result<T, JSONPathError> {
+    T::deserialize(node.value).map_err(|err| {
+        JSONPathError::typ(format!(
+            "can't extract `{}` from {}: {}",
+            field,
+            node.path(),
+            err
+        ))
+    })
+}
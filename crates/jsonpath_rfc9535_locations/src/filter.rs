@@ -1,10 +1,18 @@
-use std::fmt;
+use std::{borrow::Cow, cmp::Ordering, fmt};
 
+use jsonpath_rfc9535::number::{self, Number};
 use serde_json::Value;
 
-use crate::{env::Environment, function::ExpressionType, node::NodeList, Query};
+use crate::{
+    collation::{CodePoint, Collation},
+    env::Environment,
+    function::{ExpressionType, FunctionContext, Functions},
+    json_like::JsonLike,
+    node::{Location, NodeList},
+    Query,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum FilterExpression {
     True,
     False,
@@ -15,6 +23,10 @@ pub enum FilterExpression {
     Int {
         value: i64,
     },
+    /// An integer literal too big for an `i64`.
+    UInt {
+        value: u64,
+    },
     Float {
         value: f64,
     },
@@ -41,38 +53,111 @@ pub enum FilterExpression {
         name: String,
         args: Vec<FilterExpression>,
     },
+    /// `count()` of a relative or root query, that stops counting at `limit`.
+    ///
+    /// The planner rewrites comparisons between `count()` and an integer
+    /// literal to use this, as counting to one more than the literal is enough
+    /// to decide the comparison.
+    BoundedCount {
+        query: Box<Query>,
+        /// `true` if `query` is a root query, `false` if it's relative.
+        root: bool,
+        limit: usize,
+    },
+    /// An expression simplified by the planner. Evaluates `value` and
+    /// displays as `expression`, the expression it replaced.
+    Folded {
+        value: Box<FilterExpression>,
+        expression: Box<FilterExpression>,
+    },
 }
 
 impl FilterExpression {
     pub fn is_literal(&self) -> bool {
         matches!(
             self,
-            FilterExpression::True { .. }
-                | FilterExpression::False { .. }
-                | FilterExpression::Null { .. }
+            FilterExpression::True
+                | FilterExpression::False
+                | FilterExpression::Null
                 | FilterExpression::String { .. }
                 | FilterExpression::Int { .. }
+                | FilterExpression::UInt { .. }
                 | FilterExpression::Float { .. }
         )
     }
+
+    /// The expression evaluated in place of this one, which is `self` unless
+    /// the planner has simplified it.
+    pub fn folded_value(&self) -> &FilterExpression {
+        match self {
+            FilterExpression::Folded { value, .. } => value.folded_value(),
+            _ => self,
+        }
+    }
+
+    /// Return `true` if `f` is `true` for the name of any function this
+    /// expression calls, including those in filters of embedded queries.
+    pub(crate) fn any_call(&self, f: &dyn Fn(&str) -> bool) -> bool {
+        self.find_call(f).is_some()
+    }
+
+    /// The name of the first function this expression calls for which `f`
+    /// is `true`, including those in filters of embedded queries.
+    pub(crate) fn find_call(&self, f: &dyn Fn(&str) -> bool) -> Option<&str> {
+        match self {
+            FilterExpression::Not { expression } => expression.find_call(f),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                left.find_call(f).or_else(|| right.find_call(f))
+            }
+            FilterExpression::RelativeQuery { query }
+            | FilterExpression::RootQuery { query }
+            | FilterExpression::BoundedCount { query, .. } => query.find_call(f),
+            FilterExpression::Folded { value, .. } => value.find_call(f),
+            FilterExpression::Function { name, args } => {
+                if f(name) {
+                    Some(name)
+                } else {
+                    args.iter().find_map(|arg| arg.find_call(f))
+                }
+            }
+            _ => None,
+        }
+    }
 }
 
 impl FilterExpression {
-    pub fn evaluate<'v>(
-        &self,
-        env: &'static Environment,
-        root: &'v Value,
-        current: &'v Value,
-    ) -> FilterExpressionResult<'v> {
+    pub fn evaluate<'e: 'v, 'v, J: JsonLike, F: Functions<J>>(
+        &'e self,
+        env: &'static Environment<F>,
+        root: &'v J,
+        current: &'v J,
+    ) -> FilterExpressionResult<'v, J> {
+        self.evaluate_at(env, root, current, None)
+    }
+
+    /// Like [`FilterExpression::evaluate`], but with the location of
+    /// `current`, for function extensions that need it and for the locations
+    /// of nodes selected by relative queries.
+    pub fn evaluate_at<'e: 'v, 'v, J: JsonLike, F: Functions<J>>(
+        &'e self,
+        env: &'static Environment<F>,
+        root: &'v J,
+        current: &'v J,
+        location: Option<&Location>,
+    ) -> FilterExpressionResult<'v, J> {
         match self {
             FilterExpression::True => FilterExpressionResult::Bool(true),
             FilterExpression::False => FilterExpressionResult::Bool(false),
             FilterExpression::Null => FilterExpressionResult::Null,
-            FilterExpression::String { value } => FilterExpressionResult::String(value.to_owned()),
+            FilterExpression::String { value } => {
+                FilterExpressionResult::String(Cow::Borrowed(value))
+            }
             FilterExpression::Int { value } => FilterExpressionResult::Int(*value),
+            FilterExpression::UInt { value } => FilterExpressionResult::UInt(*value),
             FilterExpression::Float { value } => FilterExpressionResult::Float(*value),
             FilterExpression::Not { expression } => {
-                if !is_truthy(expression.evaluate(env, root, current)) {
+                if !is_truthy(expression.evaluate_at(env, root, current, location)) {
                     FilterExpressionResult::Bool(true)
                 } else {
                     FilterExpressionResult::Bool(false)
@@ -83,25 +168,28 @@ impl FilterExpression {
                 operator,
                 right,
             } => {
-                if logical(
-                    left.evaluate(env, root, current),
-                    operator,
-                    right.evaluate(env, root, current),
-                ) {
-                    FilterExpressionResult::Bool(true)
-                } else {
-                    FilterExpressionResult::Bool(false)
-                }
+                // Short-circuit, so the planner can put cheap operands first.
+                let left = is_truthy(left.evaluate_at(env, root, current, location));
+                let rv = match operator {
+                    LogicalOperator::And => {
+                        left && is_truthy(right.evaluate_at(env, root, current, location))
+                    }
+                    LogicalOperator::Or => {
+                        left || is_truthy(right.evaluate_at(env, root, current, location))
+                    }
+                };
+                FilterExpressionResult::Bool(rv)
             }
             FilterExpression::Comparison {
                 left,
                 operator,
                 right,
             } => {
-                if compare(
-                    left.evaluate(env, root, current),
+                if compare_with(
+                    left.evaluate_at(env, root, current, location),
                     operator,
-                    right.evaluate(env, root, current),
+                    right.evaluate_at(env, root, current, location),
+                    env.collation.as_ref(),
                 ) {
                     FilterExpressionResult::Bool(true)
                 } else {
@@ -109,25 +197,65 @@ impl FilterExpression {
                 }
             }
             FilterExpression::RelativeQuery { query } => {
-                FilterExpressionResult::Nodes(query.find(current, env))
+                FilterExpressionResult::Nodes(match location {
+                    Some(location) => query.find_at(current, location.clone(), env),
+                    None => query.find(current, env),
+                })
             }
             FilterExpression::RootQuery { query } => {
                 FilterExpressionResult::Nodes(query.find(root, env))
             }
             FilterExpression::Function { name, args } => {
-                let fn_ext = env
-                    .function_register
-                    .get(name)
-                    .unwrap_or_else(|| panic!("unknown function '{}'", name));
-
+                let Some(param_types) = env.function_register.param_types(name) else {
+                    return FilterExpressionResult::Nothing;
+                };
                 let _args = args
                     .iter()
-                    .map(|expr| expr.evaluate(env, root, current))
                     .enumerate()
-                    .map(|(i, rv)| unpack_result(rv, &fn_ext.sig().param_types, i))
+                    .map(|(i, expr)| match (param_types.get(i), expr) {
+                        // Resolve singular queries directly when a value is
+                        // expected, so `length(@.big_array)` never builds a
+                        // node list.
+                        (
+                            Some(ExpressionType::Value),
+                            FilterExpression::RelativeQuery { query },
+                        ) if query.is_singular() => {
+                            FilterExpressionResult::from_singular(query.singular_value(current))
+                        }
+                        (Some(ExpressionType::Value), FilterExpression::RootQuery { query })
+                            if query.is_singular() =>
+                        {
+                            FilterExpressionResult::from_singular(query.singular_value(root))
+                        }
+                        _ => unpack_result(
+                            expr.evaluate_at(env, root, current, location),
+                            &param_types,
+                            i,
+                        ),
+                    })
                     .collect();
 
-                fn_ext.call(_args)
+                env.function_register.call(
+                    name,
+                    _args,
+                    &FunctionContext {
+                        env,
+                        root,
+                        current,
+                        location,
+                    },
+                )
+            }
+            FilterExpression::BoundedCount {
+                query,
+                root: is_root,
+                limit,
+            } => {
+                let value = if *is_root { root } else { current };
+                FilterExpressionResult::Int(query.count_up_to(value, env, *limit) as i64)
+            }
+            FilterExpression::Folded { value, .. } => {
+                value.evaluate_at(env, root, current, location)
             }
         }
     }
@@ -136,11 +264,12 @@ impl FilterExpression {
 impl fmt::Display for FilterExpression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            FilterExpression::True { .. } => f.write_str("true"),
-            FilterExpression::False { .. } => f.write_str("false"),
-            FilterExpression::Null { .. } => f.write_str("null"),
+            FilterExpression::True => f.write_str("true"),
+            FilterExpression::False => f.write_str("false"),
+            FilterExpression::Null => f.write_str("null"),
             FilterExpression::String { value, .. } => write!(f, "'{value}'"),
             FilterExpression::Int { value, .. } => write!(f, "{value}"),
+            FilterExpression::UInt { value, .. } => write!(f, "{value}"),
             FilterExpression::Float { value, .. } => write!(f, "{value}"),
             FilterExpression::Not { expression, .. } => write!(f, "!{expression}"),
             FilterExpression::Logical {
@@ -179,6 +308,20 @@ impl fmt::Display for FilterExpression {
                         .join("")
                 )
             }
+            FilterExpression::BoundedCount { query, root, .. } => {
+                write!(
+                    f,
+                    "count({}{})",
+                    if *root { '$' } else { '@' },
+                    query
+                        .segments
+                        .iter()
+                        .map(|s| s.to_string())
+                        .collect::<Vec<String>>()
+                        .join("")
+                )
+            }
+            FilterExpression::Folded { expression, .. } => write!(f, "{expression}"),
             FilterExpression::Function { name, args, .. } => {
                 write!(
                     f,
@@ -194,7 +337,7 @@ impl fmt::Display for FilterExpression {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum LogicalOperator {
     And,
     Or,
@@ -209,7 +352,7 @@ impl fmt::Display for LogicalOperator {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum ComparisonOperator {
     Eq,
     Ne,
@@ -232,39 +375,60 @@ impl fmt::Display for ComparisonOperator {
     }
 }
 
+/// The result of evaluating a filter expression against a document of
+/// `J` values.
 #[derive(Debug)]
-pub enum FilterExpressionResult<'a> {
+#[non_exhaustive]
+pub enum FilterExpressionResult<'a, J = Value> {
     Bool(bool),
     Int(i64),
+    /// An integer too big for an `i64`.
+    UInt(u64),
     Float(f64),
     Null,
-    String(String),
-    Array(&'a Value),
-    Object(&'a Value),
-    Nodes(NodeList<'a>),
+    String(Cow<'a, str>),
+    Array(&'a J),
+    Object(&'a J),
+    Nodes(NodeList<'a, J>),
     Nothing,
 }
 
-impl<'a> FilterExpressionResult<'a> {
-    pub fn from_json_value(value: &'a Value) -> Self {
-        match value {
-            Value::Bool(v) => FilterExpressionResult::Bool(*v),
-            Value::Null => FilterExpressionResult::Null,
-            Value::Number(n) => {
-                if n.is_f64() {
-                    FilterExpressionResult::Float(n.as_f64().unwrap())
-                } else {
-                    FilterExpressionResult::Int(n.as_i64().unwrap())
-                }
+impl<'a, J: JsonLike> FilterExpressionResult<'a, J> {
+    /// The result of a singular query argument, `Nothing` if the query
+    /// selected nothing.
+    pub fn from_singular(value: Option<&'a J>) -> Self {
+        value.map_or(
+            FilterExpressionResult::Nothing,
+            FilterExpressionResult::from_json_value,
+        )
+    }
+
+    pub fn from_json_value(value: &'a J) -> Self {
+        if let Some(s) = value.as_str() {
+            FilterExpressionResult::String(Cow::Borrowed(s))
+        } else if let Some(n) = value.as_number() {
+            match n {
+                Number::Int(i) => i64::try_from(i)
+                    .map(FilterExpressionResult::Int)
+                    .or_else(|_| u64::try_from(i).map(FilterExpressionResult::UInt))
+                    .unwrap_or(FilterExpressionResult::Float(i as f64)),
+                Number::Float(f) => FilterExpressionResult::Float(f),
             }
-            Value::String(s) => FilterExpressionResult::String(s.to_owned()),
-            Value::Array(_) => FilterExpressionResult::Array(value),
-            Value::Object(_) => FilterExpressionResult::Object(value),
+        } else if let Some(b) = value.as_bool() {
+            FilterExpressionResult::Bool(b)
+        } else if value.as_array().is_some() {
+            FilterExpressionResult::Array(value)
+        } else if value.members().is_some() {
+            FilterExpressionResult::Object(value)
+        } else if value.is_null() {
+            FilterExpressionResult::Null
+        } else {
+            FilterExpressionResult::Nothing
         }
     }
 }
 
-pub fn is_truthy(rv: FilterExpressionResult) -> bool {
+pub fn is_truthy<J>(rv: FilterExpressionResult<J>) -> bool {
     match rv {
         FilterExpressionResult::Nothing => false,
         FilterExpressionResult::Nodes(nodes) => !nodes.is_empty(),
@@ -273,7 +437,7 @@ pub fn is_truthy(rv: FilterExpressionResult) -> bool {
     }
 }
 
-pub fn is_truthy_ref(rv: &FilterExpressionResult) -> bool {
+pub fn is_truthy_ref<J>(rv: &FilterExpressionResult<J>) -> bool {
     match rv {
         FilterExpressionResult::Nothing => false,
         FilterExpressionResult::Nodes(nodes) => !nodes.is_empty(),
@@ -282,10 +446,10 @@ pub fn is_truthy_ref(rv: &FilterExpressionResult) -> bool {
     }
 }
 
-pub fn logical(
-    left: FilterExpressionResult,
+pub fn logical<J>(
+    left: FilterExpressionResult<J>,
     op: &LogicalOperator,
-    right: FilterExpressionResult,
+    right: FilterExpressionResult<J>,
 ) -> bool {
     match op {
         LogicalOperator::And => is_truthy(left) && is_truthy(right),
@@ -293,27 +457,49 @@ pub fn logical(
     }
 }
 
-fn nodes_or_singular(rv: FilterExpressionResult<'_>) -> FilterExpressionResult<'_> {
+fn nodes_or_singular<J: JsonLike>(
+    rv: FilterExpressionResult<'_, J>,
+) -> FilterExpressionResult<'_, J> {
     match rv {
-        FilterExpressionResult::Nodes(ref nodes) => {
-            if nodes.len() == 1 {
-                FilterExpressionResult::from_json_value(nodes.first().unwrap().value)
-            } else {
-                rv
-            }
-        }
+        FilterExpressionResult::Nodes(ref nodes) => match nodes.as_slice() {
+            [node] => FilterExpressionResult::from_json_value(node.value),
+            _ => rv,
+        },
         _ => rv,
     }
 }
 
-pub fn compare(
-    left: FilterExpressionResult,
+pub fn compare<J: JsonLike>(
+    left: FilterExpressionResult<J>,
+    op: &ComparisonOperator,
+    right: FilterExpressionResult<J>,
+) -> bool {
+    compare_with(left, op, right, &CodePoint)
+}
+
+/// Like [`compare`], but ordering strings by `collation`.
+pub fn compare_with<J: JsonLike>(
+    left: FilterExpressionResult<J>,
     op: &ComparisonOperator,
-    right: FilterExpressionResult,
+    right: FilterExpressionResult<J>,
+    collation: &dyn Collation,
 ) -> bool {
     use ComparisonOperator::*;
     let left = nodes_or_singular(left);
     let right = nodes_or_singular(right);
+
+    if let (FilterExpressionResult::String(l), FilterExpressionResult::String(r)) = (&left, &right)
+    {
+        return match op {
+            Eq => l == r,
+            Ne => l != r,
+            Lt => collation.compare(l, r).is_lt(),
+            Gt => collation.compare(l, r).is_gt(),
+            Ge => collation.compare(l, r).is_ge(),
+            Le => collation.compare(l, r).is_le(),
+        };
+    }
+
     match op {
         Eq => eq(&left, &right),
         Ne => !eq(&left, &right),
@@ -324,58 +510,62 @@ pub fn compare(
     }
 }
 
-fn eq(left: &FilterExpressionResult, right: &FilterExpressionResult) -> bool {
+/// A numeric result as a number that compares exactly with the others.
+fn as_number<J>(rv: &FilterExpressionResult<J>) -> Option<Number> {
+    match rv {
+        FilterExpressionResult::Int(i) => Some(Number::Int((*i).into())),
+        FilterExpressionResult::UInt(u) => Some(Number::Int((*u).into())),
+        FilterExpressionResult::Float(f) => Some(Number::Float(*f)),
+        _ => None,
+    }
+}
+
+fn eq<J: JsonLike>(left: &FilterExpressionResult<J>, right: &FilterExpressionResult<J>) -> bool {
+    if let (Some(l), Some(r)) = (as_number(left), as_number(right)) {
+        return number::compare(l, r) == Some(Ordering::Equal);
+    }
+
     use FilterExpressionResult::*;
     match (left, right) {
         (Nothing, Nothing) => true,
         (Nodes(nodes), Nothing) | (Nothing, Nodes(nodes)) => nodes.is_empty(),
         (Nothing, _) | (_, Nothing) => false,
-        (Nodes(left), Nodes(right)) => {
-            if left.is_empty() && right.is_empty() {
-                true
-            } else {
-                // Only singular queries can be compared
-                unreachable!()
-            }
-        }
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Int(r)) => l == r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Float(r)) => l == r,
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Float(r)) => *l as f64 == *r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Int(r)) => *l == *r as f64,
+        // Only singular queries can be compared, so non-empty node lists
+        // never get here.
+        (Nodes(left), Nodes(right)) => left.is_empty() && right.is_empty(),
         (FilterExpressionResult::Null, FilterExpressionResult::Null) => true,
         (FilterExpressionResult::Bool(l), FilterExpressionResult::Bool(r)) => l == r,
         (FilterExpressionResult::String(l), FilterExpressionResult::String(r)) => l == r,
-        (FilterExpressionResult::Array(l), FilterExpressionResult::Array(r)) => *l == *r,
-        (FilterExpressionResult::Object(l), FilterExpressionResult::Object(r)) => *l == *r,
+        (FilterExpressionResult::Array(l), FilterExpressionResult::Array(r)) => l.json_eq(r),
+        (FilterExpressionResult::Object(l), FilterExpressionResult::Object(r)) => l.json_eq(r),
         _ => false,
     }
 }
 
-fn lt(left: &FilterExpressionResult, right: &FilterExpressionResult) -> bool {
+fn lt<J>(left: &FilterExpressionResult<J>, right: &FilterExpressionResult<J>) -> bool {
+    if let (Some(l), Some(r)) = (as_number(left), as_number(right)) {
+        return number::compare(l, r) == Some(Ordering::Less);
+    }
+
     match (left, right) {
-        (FilterExpressionResult::String(l), FilterExpressionResult::String(r)) => l < r,
         (FilterExpressionResult::Bool(_), FilterExpressionResult::Bool(_)) => false,
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Int(r)) => l < r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Float(r)) => l < r,
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Float(r)) => (*l as f64) < *r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Int(r)) => *l < *r as f64,
         _ => false,
     }
 }
 
-pub fn unpack_result<'a>(
-    rv: FilterExpressionResult<'a>,
+pub fn unpack_result<'a, J: JsonLike>(
+    rv: FilterExpressionResult<'a, J>,
     param_types: &[ExpressionType],
     index: usize,
-) -> FilterExpressionResult<'a> {
-    if matches!(param_types.get(index).unwrap(), ExpressionType::Nodes) {
+) -> FilterExpressionResult<'a, J> {
+    if matches!(param_types.get(index), Some(ExpressionType::Nodes)) {
         return rv;
     }
 
     match &rv {
-        FilterExpressionResult::Nodes(nodes) => match nodes.len() {
-            0 => FilterExpressionResult::Nothing,
-            1 => FilterExpressionResult::from_json_value(nodes.first().unwrap().value),
+        FilterExpressionResult::Nodes(nodes) => match nodes.as_slice() {
+            [] => FilterExpressionResult::Nothing,
+            [node] => FilterExpressionResult::from_json_value(node.value),
             _ => rv,
         },
         _ => rv,
@@ -0,0 +1,48 @@
+//! Code shared by the CBOR, MessagePack, YAML and TOML adapters, which
+//! evaluate queries against a document by converting it to JSON.
+use serde_json::Value;
+
+use crate::{
+    document::DuplicateKeys, env::Environment, errors::JSONPathError, function::Functions,
+    node::PathElement,
+};
+
+/// A document format that queries are evaluated against by converting it
+/// to JSON.
+pub(crate) trait Format: Sized {
+    /// Convert to JSON, resolving keys with the same name according to
+    /// `duplicate_keys`.
+    fn to_json(&self, duplicate_keys: DuplicateKeys) -> Result<Value, JSONPathError>;
+
+    /// The child at `element`, being the one [`Format::to_json`] kept if
+    /// several keys have the same name.
+    fn child(&self, element: &PathElement, duplicate_keys: DuplicateKeys) -> Option<&Self>;
+}
+
+/// Find values in `value` matching the JSONPath expression `expr`, parsing
+/// and evaluating it with `env`.
+///
+/// Returns `(normalized path, value)` pairs borrowing from `value`.
+pub(crate) fn find_in<'a, D: Format, F: Functions>(
+    env: &'static Environment<F>,
+    expr: &str,
+    value: &'a D,
+) -> Result<Vec<(String, &'a D)>, JSONPathError> {
+    let query = env.parser().parse(expr)?;
+    let json = value.to_json(env.duplicate_keys)?;
+    Ok(query
+        .find(&json, env)
+        .iter()
+        .filter_map(|node| {
+            node.path_elements()
+                .into_iter()
+                .try_fold(value, |v, element| v.child(element, env.duplicate_keys))
+                .map(|v| (node.path(), v))
+        })
+        .collect())
+}
+
+/// A JSON number, or `null` if `f` isn't finite.
+pub(crate) fn float(f: f64) -> Value {
+    serde_json::Number::from_f64(f).map_or(Value::Null, Value::Number)
+}
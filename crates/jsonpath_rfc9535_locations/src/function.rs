@@ -1,68 +1,123 @@
-use std::collections::HashMap;
+use std::{borrow::Cow, collections::HashMap};
 
-use crate::filter::FilterExpressionResult;
+pub use jsonpath_rfc9535::{
+    function::FunctionRegistry, standard_functions, ExpressionType, FunctionSignature,
+};
+use serde_json::Value;
 
-#[derive(Debug)]
-pub enum ExpressionType {
-    Logical,
-    Nodes,
-    Value,
+use crate::{
+    env::Environment,
+    filter::FilterExpressionResult,
+    node::{normalized_path, Location},
+};
+
+/// Where a function extension is being called from.
+pub struct FunctionContext<'c, 'v, F: 'static = FunctionRegister, J = Value> {
+    pub env: &'static Environment<F>,
+    /// The value the query is being evaluated against, `$` in a filter.
+    pub root: &'v J,
+    /// The value being tested by the filter, `@` in a filter.
+    pub current: &'v J,
+    /// The location of `current`, or `None` if the filter is being evaluated
+    /// without tracking locations.
+    ///
+    /// Locations are tracked for filters calling a function that
+    /// [reads locations](FunctionExtension::reads_location), and left out
+    /// otherwise, or
+    /// when calling
+    /// [`FilterExpression::evaluate`](crate::filter::FilterExpression::evaluate)
+    /// directly.
+    pub location: Option<&'c Location>,
 }
 
-pub struct FunctionSignature {
-    pub param_types: Vec<ExpressionType>,
-    pub return_type: ExpressionType,
+impl<F, J> FunctionContext<'_, '_, F, J> {
+    /// The normalized path of `current`, if its location is known.
+    pub fn path(&self) -> Option<String> {
+        self.location.map(normalized_path)
+    }
 }
 
-pub fn standard_functions() -> HashMap<String, FunctionSignature> {
-    let mut functions = HashMap::new();
-
-    functions.insert(
-        "count".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
-        },
-    );
-
-    functions.insert(
-        "length".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value],
-            return_type: ExpressionType::Value,
-        },
-    );
-
-    functions.insert(
-        "match".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        },
-    );
-
-    functions.insert(
-        "search".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        },
-    );
-
-    functions.insert(
-        "value".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
-        },
-    );
-
-    functions
+/// A filter function called with arguments borrowed from a document of `J`
+/// values. Signatures are kept alongside implementations in a
+/// [`FunctionRegister`].
+pub trait FunctionExtension<J = Value> {
+    fn call<'a>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        context: &FunctionContext<'_, 'a, FunctionRegister<J>, J>,
+    ) -> FilterExpressionResult<'a, J>;
+
+    /// `true` if the function reads the location of the current node from
+    /// its [`FunctionContext`], or the locations of nodes passed to it.
+    /// Filters only build the locations of the nodes they test when they
+    /// call such a function.
+    fn reads_location(&self) -> bool {
+        false
+    }
 }
 
-pub trait FunctionExtension {
-    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a>;
-    fn sig(&self) -> FunctionSignature;
+pub type FunctionRegister<J = Value> = FunctionRegistry<dyn FunctionExtension<J> + Sync>;
+
+/// Function extensions available to filter expressions, looked up by name.
+///
+/// [`FunctionRegister`] maps names to boxed extensions, so any function can
+/// be registered at runtime. [`StandardFunctions`](crate::standard_functions::StandardFunctions)
+/// only has the standard functions, resolved without a hash lookup or
+/// dynamic dispatch, and works with any [`JsonLike`](crate::json_like::JsonLike)
+/// value type.
+pub trait Functions<J = Value>: Sized + 'static {
+    fn signature(&self, name: &str) -> Option<&FunctionSignature>;
+
+    /// A copy of each function's signature, keyed by name, as expected by
+    /// parsers.
+    fn signatures(&self) -> HashMap<String, FunctionSignature>;
+
+    /// `true` if function `name` [reads locations](FunctionExtension::reads_location).
+    fn reads_location(&self, _name: &str) -> bool {
+        false
+    }
+
+    /// The parameter types of function `name`. Called every time the function
+    /// is called, so implementations should avoid allocating if they can.
+    fn param_types(&self, name: &str) -> Option<Cow<'_, [ExpressionType]>> {
+        self.signature(name)
+            .map(|sig| Cow::Borrowed(sig.param_types.as_slice()))
+    }
+
+    /// Call function `name`. Filter expressions are checked against function
+    /// signatures when they're parsed, so `name` is always a function that
+    /// was registered when the query was parsed.
+    fn call<'a>(
+        &self,
+        name: &str,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        context: &FunctionContext<'_, 'a, Self, J>,
+    ) -> FilterExpressionResult<'a, J>;
 }
 
-pub type FunctionRegister = HashMap<String, Box<dyn FunctionExtension + Sync>>;
+impl<J: 'static> Functions<J> for FunctionRegister<J> {
+    fn signature(&self, name: &str) -> Option<&FunctionSignature> {
+        FunctionRegistry::signature(self, name)
+    }
+
+    fn signatures(&self) -> HashMap<String, FunctionSignature> {
+        FunctionRegistry::signatures(self)
+    }
+
+    fn reads_location(&self, name: &str) -> bool {
+        self.get(name).is_some_and(|f| f.reads_location())
+    }
+
+    fn call<'a>(
+        &self,
+        name: &str,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        context: &FunctionContext<'_, 'a, Self, J>,
+    ) -> FilterExpressionResult<'a, J> {
+        // Queries are checked against the register when they're parsed, so
+        // an unknown name only happens if a query is evaluated with a
+        // different environment, which `Query::try_find` reports.
+        self.get(name)
+            .map_or(FilterExpressionResult::Nothing, |f| f.call(args, context))
+    }
+}
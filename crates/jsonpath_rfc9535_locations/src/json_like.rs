@@ -0,0 +1,269 @@
+//! Evaluate queries against JSON values other than [`serde_json::Value`].
+//!
+//! Queries are evaluated against any value that implements [`JsonLike`].
+//! Implement it for a value type and pass a value to [`Query::find`], or to
+//! [`find`], to evaluate a query without converting the document to a
+//! `serde_json::Value` first. `JsonLike` is implemented for
+//! `serde_json::Value` and, with the `simd` feature, for simd-json's owned
+//! and borrowed values.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::json_like;
+//! use serde_json::json;
+//!
+//! let value = json!({"users": [{"name": "a", "age": 30}, {"name": "b", "age": 17}]});
+//! let nodes = json_like::find("$.users[?@.age >= 18].name", &value).unwrap();
+//!
+//! assert_eq!(nodes.len(), 1);
+//! assert_eq!(nodes[0].path(), "$['users'][0]['name']");
+//! assert_eq!(nodes[0].value.as_str(), Some("a"));
+//! ```
+//!
+//! Function extensions are called with arguments borrowed from the document,
+//! so an extension has to implement
+//! [`FunctionExtension<J>`](crate::function::FunctionExtension) for the
+//! value type `J` it's called with. [`STANDARD_ENV`] works with any value
+//! type. Members of an object are visited in the order the value type
+//! iterates them, which for simd-json is not the order they appear in the
+//! source document.
+//!
+//! [`Query::find`]: crate::Query::find
+
+use std::cmp::Ordering;
+
+use jsonpath_rfc9535::number::{self, Number};
+use serde_json::Value;
+
+use crate::{errors::JSONPathError, node::NodeList, Query, STANDARD_ENV};
+
+/// A JSON value that queries can be evaluated against.
+pub trait JsonLike: Sized {
+    /// An iterator over the members of an object.
+    type Members<'a>: Iterator<Item = (&'a str, &'a Self)>
+    where
+        Self: 'a;
+
+    fn is_null(&self) -> bool;
+
+    fn as_bool(&self) -> Option<bool>;
+
+    /// This value as a number, if it is one. Integers that fit in an `i128`
+    /// should be `Number::Int`, so they compare exactly.
+    fn as_number(&self) -> Option<Number>;
+
+    fn as_str(&self) -> Option<&str>;
+
+    /// This value's elements, if it is an array.
+    fn as_array(&self) -> Option<&[Self]>;
+
+    /// This value's members, in iteration order, if it is an object.
+    fn members(&self) -> Option<Self::Members<'_>>;
+
+    /// The value of member `name`, if this is an object with that member.
+    fn member(&self, name: &str) -> Option<&Self>;
+
+    /// The number of members, if this is an object.
+    fn object_len(&self) -> Option<usize> {
+        self.members().map(Iterator::count)
+    }
+
+    /// Return `true` if this value and `other` are the same JSON value.
+    /// Numbers are equal if they have the same value, so `[1]` equals
+    /// `[1.0]`.
+    fn json_eq(&self, other: &Self) -> bool {
+        if let (Some(l), Some(r)) = (self.as_array(), other.as_array()) {
+            return l.len() == r.len() && l.iter().zip(r).all(|(l, r)| l.json_eq(r));
+        }
+
+        if let (Some(l), Some(r)) = (self.members(), other.object_len()) {
+            let mut count = 0;
+            return l
+                .inspect(|_| count += 1)
+                .all(|(k, v)| other.member(k).is_some_and(|r| v.json_eq(r)))
+                && count == r;
+        }
+
+        if let (Some(l), Some(r)) = (self.as_number(), other.as_number()) {
+            return number::compare(l, r) == Some(Ordering::Equal);
+        }
+
+        match (self.as_str(), other.as_str()) {
+            (Some(l), Some(r)) => l == r,
+            (None, None) => match (self.as_bool(), other.as_bool()) {
+                (Some(l), Some(r)) => l == r,
+                (None, None) => self.is_null() && other.is_null(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl JsonLike for Value {
+    type Members<'a> = std::iter::Map<
+        serde_json::map::Iter<'a>,
+        fn((&'a String, &'a Value)) -> (&'a str, &'a Value),
+    >;
+
+    fn is_null(&self) -> bool {
+        Value::is_null(self)
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        Value::as_bool(self)
+    }
+
+    fn as_number(&self) -> Option<Number> {
+        let n = self.as_number()?;
+        Some(match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => Number::Int(i.into()),
+            (None, Some(u)) => Number::Int(u.into()),
+            _ => Number::Float(n.as_f64()?),
+        })
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        Value::as_str(self)
+    }
+
+    fn as_array(&self) -> Option<&[Self]> {
+        Value::as_array(self).map(Vec::as_slice)
+    }
+
+    fn members(&self) -> Option<Self::Members<'_>> {
+        Some(self.as_object()?.iter().map(|(k, v)| (k.as_str(), v)))
+    }
+
+    fn member(&self, name: &str) -> Option<&Self> {
+        self.as_object()?.get(name)
+    }
+
+    fn object_len(&self) -> Option<usize> {
+        self.as_object().map(serde_json::Map::len)
+    }
+}
+
+#[cfg(feature = "simd")]
+fn static_number(node: &simd_json::StaticNode) -> Option<Number> {
+    use simd_json::prelude::ValueAsScalar;
+
+    match node {
+        simd_json::StaticNode::F64(f) => Some(Number::Float(*f)),
+        simd_json::StaticNode::U64(u) => Some(Number::Int((*u).into())),
+        node => node.as_i128().map(Number::Int),
+    }
+}
+
+#[cfg(feature = "simd")]
+impl JsonLike for simd_json::OwnedValue {
+    type Members<'a>
+        = Box<dyn Iterator<Item = (&'a str, &'a Self)> + 'a>
+    where
+        Self: 'a;
+
+    fn is_null(&self) -> bool {
+        matches!(self, Self::Static(simd_json::StaticNode::Null))
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Static(simd_json::StaticNode::Bool(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn as_number(&self) -> Option<Number> {
+        match self {
+            Self::Static(node) => static_number(node),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    fn members(&self) -> Option<Self::Members<'_>> {
+        match self {
+            Self::Object(obj) => Some(Box::new(obj.iter().map(|(k, v)| (k.as_str(), v)))),
+            _ => None,
+        }
+    }
+
+    fn member(&self, name: &str) -> Option<&Self> {
+        match self {
+            Self::Object(obj) => obj.get(name),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "simd")]
+impl JsonLike for simd_json::BorrowedValue<'_> {
+    type Members<'a>
+        = Box<dyn Iterator<Item = (&'a str, &'a Self)> + 'a>
+    where
+        Self: 'a;
+
+    fn is_null(&self) -> bool {
+        matches!(self, Self::Static(simd_json::StaticNode::Null))
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Static(simd_json::StaticNode::Bool(b)) => Some(*b),
+            _ => None,
+        }
+    }
+
+    fn as_number(&self) -> Option<Number> {
+        match self {
+            Self::Static(node) => static_number(node),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn as_array(&self) -> Option<&[Self]> {
+        match self {
+            Self::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    fn members(&self) -> Option<Self::Members<'_>> {
+        match self {
+            Self::Object(obj) => Some(Box::new(obj.iter().map(|(k, v)| (k.as_ref(), v)))),
+            _ => None,
+        }
+    }
+
+    fn member(&self, name: &str) -> Option<&Self> {
+        match self {
+            Self::Object(obj) => obj.get(name),
+            _ => None,
+        }
+    }
+}
+
+/// Parse `expr` and find nodes in `value`, calling only the standard
+/// functions. See [`Query::find`].
+pub fn find<'v, J: JsonLike>(expr: &str, value: &'v J) -> Result<NodeList<'v, J>, JSONPathError> {
+    Ok(Query::standard(expr)?.find(value, &STANDARD_ENV))
+}
@@ -1,12 +1,216 @@
-use crate::{env::Environment, errors::JSONPathError, node::NodeList, Query};
+use std::collections::HashMap;
+
+use crate::{
+    conslist::ConsList,
+    env::Environment,
+    errors::JSONPathError,
+    node::{Node, NodeList, PathElement},
+    options::{FindOptions, FindResult},
+    path::NormalizedPath,
+    standard_functions::StandardFunctions,
+    unescape::unescape,
+    Query,
+};
 use lazy_static::lazy_static;
 use serde_json::Value;
 
 lazy_static! {
     pub static ref ENV: Environment = Environment::new();
+    /// Like `ENV`, but limited to the standard functions, which makes
+    /// filters that call them cheaper to evaluate.
+    pub static ref STANDARD_ENV: Environment<StandardFunctions> = Environment::standard();
 }
 
 pub fn find<'a>(expr: &str, value: &'a Value) -> Result<NodeList<'a>, JSONPathError> {
     let query = Query::standard(expr)?;
     Ok(query.find(value, &ENV))
 }
+
+/// Like `find`, for a `value` that is the subtree at `base_path` of a larger
+/// document. Node locations start with `base_path`, so they are locations in
+/// the larger document.
+///
+/// `$` in a filter expression refers to `value`, as the rest of the document
+/// isn't available.
+pub fn find_at<'a>(
+    expr: &str,
+    value: &'a Value,
+    base_path: &NormalizedPath,
+) -> Result<NodeList<'a>, JSONPathError> {
+    let query = Query::standard(expr)?;
+    Ok(query.find_at(value, base_path.location(), &ENV))
+}
+
+/// Same as `find`, configured by `options`.
+pub fn find_with_options<'a>(
+    expr: &str,
+    value: &'a Value,
+    options: &FindOptions,
+) -> Result<FindResult<'a>, JSONPathError> {
+    ENV.find_with_options(expr, value, options)
+}
+
+/// Find nodes in a collection of named documents.
+///
+/// A query starting with `$doc('<name>')` instead of `$` is evaluated
+/// against the document called `<name>`, so `$doc('users').admins[0]` is
+/// `$.admins[0]` in the `users` document. Any other query is evaluated
+/// against every document, in order of their names.
+///
+/// Node locations start with the name of the document the node came from,
+/// as if the documents were members of one object. `$` in a filter
+/// expression refers to the document being queried.
+pub fn find_in_roots<'a>(
+    expr: &str,
+    roots: &HashMap<String, &'a Value>,
+) -> Result<NodeList<'a>, JSONPathError> {
+    match split_doc_selector(expr)? {
+        Some((name, rest)) => {
+            let query = Query::standard(&format!("${rest}"))?;
+            let value = roots
+                .get(&name)
+                .ok_or_else(|| JSONPathError::name(format!("unknown document `{name}`")))?;
+            Ok(query.find_at(value, ConsList::new().append(PathElement::Name(name)), &ENV))
+        }
+        None => {
+            let query = Query::standard(expr)?;
+            let mut names: Vec<&String> = roots.keys().collect();
+            names.sort();
+            Ok(names
+                .into_iter()
+                .flat_map(|name| {
+                    let location = ConsList::new().append(PathElement::Name(name.to_owned()));
+                    query.find_at(roots[name], location, &ENV)
+                })
+                .collect())
+        }
+    }
+}
+
+/// Split a leading `$doc('<name>')` from `expr`, returning the unescaped
+/// document name and the rest of the query.
+fn split_doc_selector(expr: &str) -> Result<Option<(String, &str)>, JSONPathError> {
+    let Some(rest) = expr.strip_prefix("$doc(") else {
+        return Ok(None);
+    };
+
+    let quote = match rest.chars().next() {
+        Some(c @ ('\'' | '"')) => c,
+        _ => {
+            return Err(JSONPathError::syntax(
+                "expected a string literal document name".to_owned(),
+            ))
+        }
+    };
+
+    let mut escaped = false;
+    let end = rest[1..].find(|c| {
+        let end = !escaped && c == quote;
+        escaped = !escaped && c == '\\';
+        end
+    });
+
+    let Some(end) = end.map(|i| i + 1) else {
+        return Err(JSONPathError::syntax(
+            "unclosed document name string literal".to_owned(),
+        ));
+    };
+
+    let Some(rest_of_query) = rest[end + 1..].strip_prefix(')') else {
+        return Err(JSONPathError::syntax(
+            "expected `)` after document name".to_owned(),
+        ));
+    };
+
+    let name = match quote {
+        '\'' => unescape(&rest[1..end].replace("\\'", "'"))?,
+        _ => unescape(&rest[1..end])?,
+    };
+
+    Ok(Some((name, rest_of_query)))
+}
+
+/// Correlate the results of two queries by their nearest common ancestor.
+///
+/// A node from `query_a` is paired with a node from `query_b` when no other
+/// node from either result shares a deeper common ancestor with them. For
+/// example, `$.products[*].name` and `$.products[*].price` pair each name
+/// with the price from the same product object, and a product without a
+/// price does not borrow one from its siblings.
+///
+/// Pairs are in the order of `query_a`'s results, then `query_b`'s results.
+pub fn join<'a>(
+    query_a: &str,
+    query_b: &str,
+    value: &'a Value,
+) -> Result<Vec<(Node<'a>, Node<'a>)>, JSONPathError> {
+    let left = find(query_a, value)?;
+    let right = find(query_b, value)?;
+
+    let left_parents: Vec<Vec<&PathElement>> = left.iter().map(parent_elements).collect();
+    let right_parents: Vec<Vec<&PathElement>> = right.iter().map(parent_elements).collect();
+    let left_groups = group_by_ancestor(&left_parents);
+    let right_groups = group_by_ancestor(&right_parents);
+
+    let right_best: Vec<Option<usize>> = right_parents
+        .iter()
+        .map(|parent| deepest_shared_ancestor(parent, &left_groups))
+        .collect();
+
+    let mut pairs = Vec::new();
+    for (l, parent) in left.iter().zip(&left_parents) {
+        let Some(depth) = deepest_shared_ancestor(parent, &right_groups) else {
+            continue;
+        };
+        let group = parent
+            .get(..depth)
+            .and_then(|ancestor| right_groups.get(ancestor));
+        for j in group.into_iter().flatten() {
+            if let (Some(r), Some(&Some(best))) = (right.get(*j), right_best.get(*j)) {
+                if best == depth {
+                    pairs.push((l.clone(), r.clone()));
+                }
+            }
+        }
+    }
+
+    Ok(pairs)
+}
+
+/// The root-first path elements of `node`'s parent, or no elements for the
+/// root node. Two nodes' nearest common ancestor is the longest prefix their
+/// parents' paths share.
+fn parent_elements<'a>(node: &'a Node<'_>) -> Vec<&'a PathElement> {
+    let mut elements = node.path_elements();
+    elements.pop();
+    elements
+}
+
+/// The indices of `parents` grouped by each of their ancestor paths,
+/// including the path itself and the root's empty path.
+fn group_by_ancestor<'p, 'e>(
+    parents: &'p [Vec<&'e PathElement>],
+) -> HashMap<&'p [&'e PathElement], Vec<usize>> {
+    let mut groups: HashMap<&[&PathElement], Vec<usize>> = HashMap::new();
+    for (i, parent) in parents.iter().enumerate() {
+        for depth in 0..=parent.len() {
+            if let Some(ancestor) = parent.get(..depth) {
+                groups.entry(ancestor).or_default().push(i);
+            }
+        }
+    }
+    groups
+}
+
+/// The length of the longest ancestor path of `parent` in `groups`, or
+/// `None` if `groups` is empty.
+fn deepest_shared_ancestor(
+    parent: &[&PathElement],
+    groups: &HashMap<&[&PathElement], Vec<usize>>,
+) -> Option<usize> {
+    (0..=parent.len()).rev().find(|depth| {
+        parent
+            .get(..*depth)
+            .is_some_and(|ancestor| groups.contains_key(ancestor))
+    })
+}
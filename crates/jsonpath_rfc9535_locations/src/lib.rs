@@ -1,18 +1,106 @@
+#![forbid(unsafe_code)]
+// Evaluating a query must not panic. Tests may, as `clippy.toml` allows.
+#![deny(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::panic,
+    clippy::unreachable,
+    clippy::unwrap_used
+)]
+
+pub mod access;
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+mod base64;
+pub mod cache;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod collation;
 mod conslist;
+pub mod cursor;
+pub mod document;
 pub mod env;
 pub mod errors;
+pub mod extract;
 pub mod filter;
+#[cfg(any(
+    feature = "cbor",
+    feature = "msgpack",
+    feature = "toml",
+    feature = "yaml"
+))]
+mod format;
 pub mod function;
+pub mod json_like;
 pub mod jsonpath;
+mod memory;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
 pub mod node;
+pub mod options;
+pub mod order;
+pub mod page;
 pub mod parser;
+pub mod path;
+mod planner;
+pub mod predicate;
+pub mod projection;
 pub mod query;
+pub mod query_set;
+#[cfg(feature = "raw_value")]
+pub mod raw;
+mod sample;
 mod segment;
 mod selector;
+pub mod shard;
 pub mod standard_functions;
+pub mod suggest;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod trace;
 mod unescape;
+mod walk;
+#[cfg(feature = "yaml")]
+pub mod yaml;
 
+pub use cursor::{Cursor, Edit};
+pub use extract::JsonPathExtract;
 pub use jsonpath::find;
+pub use jsonpath::find_at;
+pub use jsonpath::find_in_roots;
+pub use jsonpath::find_with_options;
+pub use jsonpath::join;
 pub use jsonpath::ENV;
+pub use jsonpath::STANDARD_ENV;
+pub use jsonpath_rfc9535::resolve_index;
 pub use parser::JSONPathParser;
+pub use path::{resolve_path, resolve_path_mut};
+pub use projection::Projection;
 pub use query::Query;
+
+#[cfg(feature = "derive")]
+pub use jsonpath_rfc9535_derive::JsonPathExtract;
+
+/// The `serde_json` this crate was built with, so callers can name the exact
+/// `Value` type its functions take.
+#[cfg(feature = "reexport_serde_json")]
+pub use serde_json;
+
+/// Fail to compile unless the calling crate's `serde_json` is the one this
+/// crate was built with.
+///
+/// With two semver-incompatible versions of `serde_json` in a dependency
+/// tree, passing a `serde_json::Value` to this crate fails with "expected
+/// `Value`, found `Value`" wherever it happens. Invoke this once, at module
+/// level, to get that error in one obvious place instead. Use
+/// `jsonpath_rfc9535_locations::serde_json` to avoid the mismatch altogether.
+///
+/// ```
+/// jsonpath_rfc9535_locations::assert_serde_json_version!();
+/// ```
+#[cfg(feature = "reexport_serde_json")]
+#[macro_export]
+macro_rules! assert_serde_json_version {
+    () => {
+        const _: fn(::serde_json::Value) -> $crate::serde_json::Value = |value| value;
+    };
+}
@@ -0,0 +1,127 @@
+//! Approximate memory usage of compiled queries and node lists.
+//!
+//! Estimates add the inline size of each value to the heap memory it owns,
+//! going by capacity rather than length. They don't include allocator
+//! overhead, so they are a lower bound, but they grow with the real figure,
+//! which is what matters when enforcing a memory budget on a cache.
+
+use std::{collections::HashSet, mem};
+
+use crate::{
+    filter::FilterExpression,
+    node::{Location, Node, NodeList, PathElement},
+    segment::Segment,
+    selector::Selector,
+    Query,
+};
+
+/// Heap memory owned by a value, not counting the value itself.
+trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        mem::size_of::<T>() + self.as_ref().heap_size()
+    }
+}
+
+impl HeapSize for Query {
+    fn heap_size(&self) -> usize {
+        self.segments.heap_size()
+    }
+}
+
+impl HeapSize for Segment {
+    fn heap_size(&self) -> usize {
+        match self {
+            Segment::Child { selectors } | Segment::Recursive { selectors } => {
+                selectors.heap_size()
+            }
+            Segment::Eoi => 0,
+        }
+    }
+}
+
+impl HeapSize for Selector {
+    fn heap_size(&self) -> usize {
+        match self {
+            Selector::Name { name } => name.heap_size(),
+            Selector::Filter { expression, .. } => expression.heap_size(),
+            _ => 0,
+        }
+    }
+}
+
+impl HeapSize for FilterExpression {
+    fn heap_size(&self) -> usize {
+        match self {
+            FilterExpression::String { value } => value.heap_size(),
+            FilterExpression::Not { expression } => expression.heap_size(),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                left.heap_size() + right.heap_size()
+            }
+            FilterExpression::RelativeQuery { query }
+            | FilterExpression::RootQuery { query }
+            | FilterExpression::BoundedCount { query, .. } => query.heap_size(),
+            FilterExpression::Function { name, args } => name.heap_size() + args.heap_size(),
+            FilterExpression::Folded { value, expression } => {
+                value.heap_size() + expression.heap_size()
+            }
+            _ => 0,
+        }
+    }
+}
+
+impl HeapSize for PathElement {
+    fn heap_size(&self) -> usize {
+        match self {
+            PathElement::Index(_) => 0,
+            PathElement::Name(name) => name.heap_size(),
+        }
+    }
+}
+
+impl Query {
+    /// An estimate of the memory used by this query, in bytes, including its
+    /// segments, selectors, names and filter expressions.
+    pub fn approx_memory(&self) -> usize {
+        mem::size_of::<Self>() + self.heap_size()
+    }
+}
+
+impl NodeList<'_> {
+    /// An estimate of the memory used by this node list, in bytes.
+    ///
+    /// Nodes selected from the same parent share the parent's location, so
+    /// shared locations are counted once. Values are borrowed from the
+    /// queried document, so they are not counted.
+    pub fn approx_memory(&self) -> usize {
+        let mut seen: HashSet<*const PathElement> = HashSet::new();
+        let locations: usize = self
+            .iter()
+            .map(|node| {
+                node.location
+                    .iter()
+                    .take_while(|element| seen.insert(*element))
+                    .map(|element| Location::cell_size() + element.heap_size())
+                    .sum::<usize>()
+            })
+            .sum();
+
+        mem::size_of::<Self>() + self.capacity() * mem::size_of::<Node>() + locations
+    }
+}
@@ -0,0 +1,119 @@
+//! Evaluate JSONPath queries against MessagePack documents.
+//!
+//! MessagePack values are converted to JSON before evaluation. Binary and
+//! extension payloads become base64url encoded strings, non-finite floats
+//! become `null` and strings must be valid UTF-8. Map keys must be strings
+//! or integers, the latter being matched by their decimal representation.
+//!
+//! Keys with the same name, like `1` and `"1"`, are resolved by a
+//! [`DuplicateKeys`] policy, the last one winning with [`to_json`] and
+//! [`find`].
+use rmpv::Value as MsgPackValue;
+use serde_json::{Map, Value};
+
+use crate::{
+    base64,
+    document::DuplicateKeys,
+    env::Environment,
+    errors::JSONPathError,
+    format::{self, float, Format},
+    function::Functions,
+    jsonpath::ENV,
+    node::PathElement,
+};
+
+/// Convert a MessagePack value to a JSON value.
+pub fn to_json(value: &MsgPackValue) -> Result<Value, JSONPathError> {
+    to_json_with(value, DuplicateKeys::default())
+}
+
+/// Convert a MessagePack value to a JSON value, resolving map keys with the
+/// same name according to `duplicate_keys`.
+pub fn to_json_with(
+    value: &MsgPackValue,
+    duplicate_keys: DuplicateKeys,
+) -> Result<Value, JSONPathError> {
+    Ok(match value {
+        MsgPackValue::Nil => Value::Null,
+        MsgPackValue::Boolean(b) => Value::Bool(*b),
+        MsgPackValue::Integer(i) => match (i.as_i64(), i.as_u64()) {
+            (Some(n), _) => Value::from(n),
+            (None, Some(n)) => Value::from(n),
+            _ => {
+                return Err(JSONPathError::typ(format!(
+                    "MessagePack integer {i} doesn't fit in i64 or u64"
+                )))
+            }
+        },
+        MsgPackValue::F32(f) => float(*f as f64),
+        MsgPackValue::F64(f) => float(*f),
+        MsgPackValue::String(s) => Value::String(text(s)?.to_owned()),
+        MsgPackValue::Binary(b) | MsgPackValue::Ext(_, b) => Value::String(base64::encode_url(b)),
+        MsgPackValue::Array(arr) => Value::Array(
+            arr.iter()
+                .map(|v| to_json_with(v, duplicate_keys))
+                .collect::<Result<_, _>>()?,
+        ),
+        MsgPackValue::Map(map) => {
+            let mut obj = Map::new();
+            for (k, v) in map {
+                duplicate_keys
+                    .insert(&mut obj, key_name(k)?, to_json_with(v, duplicate_keys)?)
+                    .map_err(JSONPathError::document)?;
+            }
+            Value::Object(obj)
+        }
+    })
+}
+
+/// Find values in a MessagePack document matching the JSONPath expression `expr`.
+///
+/// Returns `(normalized path, value)` pairs borrowing from `value`.
+pub fn find<'a>(
+    expr: &str,
+    value: &'a MsgPackValue,
+) -> Result<Vec<(String, &'a MsgPackValue)>, JSONPathError> {
+    find_with(&ENV, expr, value)
+}
+
+/// Like [`find`], parsing `expr` and evaluating it with `env`, and resolving
+/// map keys with the same name according to `env.duplicate_keys`.
+pub fn find_with<'a, F: Functions>(
+    env: &'static Environment<F>,
+    expr: &str,
+    value: &'a MsgPackValue,
+) -> Result<Vec<(String, &'a MsgPackValue)>, JSONPathError> {
+    format::find_in(env, expr, value)
+}
+
+fn text(s: &rmpv::Utf8String) -> Result<&str, JSONPathError> {
+    s.as_str()
+        .ok_or_else(|| JSONPathError::typ(String::from("MessagePack strings must be valid UTF-8")))
+}
+
+fn key_name(key: &MsgPackValue) -> Result<String, JSONPathError> {
+    match key {
+        MsgPackValue::String(s) => Ok(text(s)?.to_owned()),
+        MsgPackValue::Integer(i) => Ok(i.to_string()),
+        _ => Err(JSONPathError::typ(String::from(
+            "MessagePack map keys must be strings or integers",
+        ))),
+    }
+}
+
+impl Format for MsgPackValue {
+    fn to_json(&self, duplicate_keys: DuplicateKeys) -> Result<Value, JSONPathError> {
+        to_json_with(self, duplicate_keys)
+    }
+
+    fn child(&self, element: &PathElement, duplicate_keys: DuplicateKeys) -> Option<&Self> {
+        match (self, element) {
+            (MsgPackValue::Array(arr), PathElement::Index(i)) => arr.get(*i),
+            (MsgPackValue::Map(map), PathElement::Name(name)) => duplicate_keys
+                .select(map.iter().map(|(k, v)| (k, v)), |k| {
+                    key_name(k).is_ok_and(|k| &k == name)
+                }),
+            _ => None,
+        }
+    }
+}
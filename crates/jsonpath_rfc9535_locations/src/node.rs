@@ -1,33 +1,60 @@
-use std::{collections::VecDeque, iter};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    hash::Hash,
+    iter,
+    num::NonZeroUsize,
+    ops::{Deref, DerefMut},
+};
 
-use crate::conslist::ConsList;
+use crate::{conslist::ConsList, errors::JSONPathError, page::Pages, path, Query, ENV};
 use serde_json::Value;
 
 pub type Location = ConsList<PathElement>;
-pub type NodeList<'v> = Vec<Node<'v>>;
 
 #[derive(Debug)]
-pub struct Node<'v> {
-    pub value: &'v Value,
+pub struct Node<'v, J = Value> {
+    pub value: &'v J,
     pub location: Location,
 }
 
+impl<J> Clone for Node<'_, J> {
+    fn clone(&self) -> Self {
+        Node {
+            value: self.value,
+            location: self.location.clone(),
+        }
+    }
+}
+
 /// An array element index or object member name in a Node's location.
-#[derive(Debug, Clone)]
+///
+/// Indices sort before names, indices sort numerically and names sort by
+/// code point.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum PathElement {
     Index(usize),
     Name(String),
 }
 
-impl<'v> Node<'v> {
-    pub fn new_array_element(value: &'v Value, location: &Location, index: usize) -> Self {
+impl fmt::Display for PathElement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathElement::Index(i) => write!(f, "[{}]", i),
+            PathElement::Name(s) => write!(f, "['{}']", path::escape(s)),
+        }
+    }
+}
+
+impl<'v, J> Node<'v, J> {
+    pub fn new_array_element(value: &'v J, location: &Location, index: usize) -> Self {
         Node {
             value,
             location: location.append(PathElement::Index(index)),
         }
     }
 
-    pub fn new_object_member(value: &'v Value, location: &Location, name: String) -> Self {
+    pub fn new_object_member(value: &'v J, location: &Location, name: String) -> Self {
         Node {
             value,
             location: location.append(PathElement::Name(name)),
@@ -36,17 +63,292 @@ impl<'v> Node<'v> {
 
     /// The location of this node's value in the query argument as a normalized path.
     pub fn path(&self) -> String {
-        iter::once(String::from("$"))
-            .chain(
-                VecDeque::from_iter(self.location.iter().map(|e| match e {
-                    PathElement::Index(i) => format!("[{}]", i),
-                    PathElement::Name(s) => format!("['{}']", s),
-                }))
+        normalized_path(&self.location)
+    }
+
+    /// This node's location as a list of path elements, starting from the root.
+    pub fn path_elements(&self) -> Vec<&PathElement> {
+        let mut elements: Vec<&PathElement> = self.location.iter().collect();
+        elements.reverse();
+        elements
+    }
+
+    /// The normalized path of this node's parent, or `$` if this is the root node.
+    pub fn parent_path(&self) -> String {
+        normalized_path(&self.location.tail())
+    }
+
+    /// The location of this node's value as an RFC 6901 JSON Pointer, or an
+    /// empty string if this is the root node.
+    pub fn to_json_pointer(&self) -> String {
+        let mut tokens: Vec<String> = self
+            .location
+            .iter()
+            .map(|element| match element {
+                PathElement::Index(i) => i.to_string(),
+                PathElement::Name(name) => escape_pointer_token(name),
+            })
+            .collect();
+        tokens.reverse();
+        tokens.iter().map(|token| format!("/{token}")).collect()
+    }
+}
+
+impl<'v> Node<'v> {
+    /// Evaluate `query` against this node's value, as if it were the whole
+    /// document, with locations that start at this node's location.
+    ///
+    /// `$`, in the query and in its filters, refers to this node's value,
+    /// like `@` at the top level of a filter.
+    pub fn query(&self, query: &Query) -> NodeList<'v> {
+        query.find_at(self.value, self.location.clone(), &ENV)
+    }
+}
+
+/// Escape `~` and `/` in a JSON Pointer reference token.
+pub(crate) fn escape_pointer_token(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+pub(crate) fn normalized_path(location: &Location) -> String {
+    iter::once(String::from("$"))
+        .chain(
+            VecDeque::from_iter(location.iter().map(|e| e.to_string()))
                 .into_iter()
                 .rev(),
-            )
-            .collect::<Vec<String>>()
-            .join("")
+        )
+        .collect::<Vec<String>>()
+        .join("")
+}
+
+/// The nodes selected by a query, in the order they were selected.
+///
+/// A `NodeList` dereferences to a `Vec<Node>`, so all the usual vector and slice
+/// methods are available.
+#[derive(Debug)]
+pub struct NodeList<'v, J = Value>(Vec<Node<'v, J>>);
+
+impl<J> Default for NodeList<'_, J> {
+    fn default() -> Self {
+        NodeList(Vec::new())
+    }
+}
+
+impl<'v, J> NodeList<'v, J> {
+    pub fn new() -> Self {
+        NodeList(Vec::new())
+    }
+
+    /// The value of every node, in order.
+    pub fn values(&self) -> Vec<&'v J> {
+        self.iter().map(|node| node.value).collect()
+    }
+
+    /// The normalized path of every node, in order.
+    pub fn paths(&self) -> Vec<String> {
+        self.iter().map(Node::path).collect()
+    }
+
+    /// The value of the first node, or `None` if the list is empty.
+    pub fn first_value(&self) -> Option<&'v J> {
+        self.first().map(|node| node.value)
+    }
+
+    /// The only node in this list, or a type error if the list is empty or
+    /// has more than one node. The error lists the nodes' normalized paths.
+    pub fn exactly_one(&self) -> Result<&Node<'v, J>, JSONPathError> {
+        match self.as_slice() {
+            [node] => Ok(node),
+            [] => Err(JSONPathError::typ(String::from(
+                "expected exactly one node, found none",
+            ))),
+            nodes => Err(JSONPathError::typ(format!(
+                "expected exactly one node, found {}: {}",
+                nodes.len(),
+                self.paths().join(", ")
+            ))),
+        }
+    }
+
+    /// Group nodes by the normalized path of their parent, so that values
+    /// selected from the same object or array can be re-associated.
+    ///
+    /// Nodes within each group are in the order they appear in this list.
+    pub fn group_by_parent(&self) -> HashMap<String, Vec<&Node<'v, J>>> {
+        self.group_by(|node| node.parent_path())
+    }
+
+    /// Group nodes by a key computed from each node.
+    ///
+    /// Nodes within each group are in the order they appear in this list.
+    pub fn group_by<K, F>(&self, key: F) -> HashMap<K, Vec<&Node<'v, J>>>
+    where
+        K: Eq + Hash,
+        F: Fn(&Node<'v, J>) -> K,
+    {
+        let mut groups: HashMap<K, Vec<&Node<'v, J>>> = HashMap::new();
+        for node in self.iter() {
+            groups.entry(key(node)).or_default().push(node);
+        }
+        groups
+    }
+
+    /// Nodes from this list followed by nodes from `other`, without
+    /// repeating a location.
+    ///
+    /// Nodes are compared by location, not value, so this is the union of
+    /// the nodes selected by two queries against the same document.
+    pub fn union(&self, other: &NodeList<'v, J>) -> NodeList<'v, J> {
+        let mut seen = HashSet::new();
+        self.iter()
+            .chain(other.iter())
+            .filter(|node| seen.insert(node.path_elements()))
+            .cloned()
+            .collect()
+    }
+
+    /// Nodes from this list that have the same location as a node in
+    /// `other`, without repeating a location.
+    pub fn intersection(&self, other: &NodeList<'v, J>) -> NodeList<'v, J> {
+        let others = other.locations();
+        let mut seen = HashSet::new();
+        self.iter()
+            .filter(|node| {
+                let location = node.path_elements();
+                others.contains(&location) && seen.insert(location)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Nodes from this list that don't have the same location as any node
+    /// in `other`, without repeating a location.
+    pub fn difference(&self, other: &NodeList<'v, J>) -> NodeList<'v, J> {
+        let others = other.locations();
+        let mut seen = HashSet::new();
+        self.iter()
+            .filter(|node| {
+                let location = node.path_elements();
+                !others.contains(&location) && seen.insert(location)
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn locations(&self) -> HashSet<Vec<&PathElement>> {
+        self.iter().map(|node| node.path_elements()).collect()
+    }
+}
+
+impl<'v> NodeList<'v> {
+    /// The value of every node as a string slice, or a type error with the
+    /// normalized path of the first node that is not a string.
+    pub fn as_strings(&self) -> Result<Vec<&'v str>, JSONPathError> {
+        self.iter()
+            .map(|node| {
+                node.value
+                    .as_str()
+                    .ok_or_else(|| type_error(node, "a string"))
+            })
+            .collect()
+    }
+
+    /// The value of every node as an `i64`, or a type error with the normalized
+    /// path of the first node that is not an integer representable as an `i64`.
+    pub fn as_i64s(&self) -> Result<Vec<i64>, JSONPathError> {
+        self.iter()
+            .map(|node| {
+                node.value
+                    .as_i64()
+                    .ok_or_else(|| type_error(node, "an integer"))
+            })
+            .collect()
+    }
+
+    /// The value of every node as an `f64`, or a type error with the normalized
+    /// path of the first node that is not a number.
+    pub fn as_f64s(&self) -> Result<Vec<f64>, JSONPathError> {
+        self.iter()
+            .map(|node| {
+                node.value
+                    .as_f64()
+                    .ok_or_else(|| type_error(node, "a number"))
+            })
+            .collect()
+    }
+
+    /// Split this list into pages of at most `page_size` nodes, ordered by
+    /// location rather than by the order nodes were selected.
+    ///
+    /// A location is on at most one page, even if it was selected more than
+    /// once. Use `Pages::after` to resume from a page's `next` cursor.
+    pub fn paginate(&self, page_size: NonZeroUsize) -> Pages<'_, 'v> {
+        Pages::new(self.iter(), page_size)
+    }
+}
+
+fn type_error(node: &Node, expected: &str) -> JSONPathError {
+    JSONPathError::typ(format!(
+        "expected {} at {}, found {}",
+        expected,
+        node.path(),
+        type_name(node.value)
+    ))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(n) if n.is_f64() => "a float",
+        Value::Number(_) => "an integer",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+impl<'v, J> Deref for NodeList<'v, J> {
+    type Target = Vec<Node<'v, J>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<J> DerefMut for NodeList<'_, J> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<'v, J> From<Vec<Node<'v, J>>> for NodeList<'v, J> {
+    fn from(nodes: Vec<Node<'v, J>>) -> Self {
+        NodeList(nodes)
+    }
+}
+
+impl<'v, J> FromIterator<Node<'v, J>> for NodeList<'v, J> {
+    fn from_iter<I: IntoIterator<Item = Node<'v, J>>>(iter: I) -> Self {
+        NodeList(iter.into_iter().collect())
+    }
+}
+
+impl<'v, J> IntoIterator for NodeList<'v, J> {
+    type Item = Node<'v, J>;
+    type IntoIter = std::vec::IntoIter<Node<'v, J>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, 'v, J> IntoIterator for &'a NodeList<'v, J> {
+    type Item = &'a Node<'v, J>;
+    type IntoIter = std::slice::Iter<'a, Node<'v, J>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
     }
 }
 
@@ -0,0 +1,147 @@
+//! Sorting and merging nodes in document order.
+//!
+//! Document order is the order values appear in a pre-order walk of a
+//! document, parents before their children. Object members are visited in
+//! the order given by [`Environment::ordering`](crate::env::Environment::ordering).
+//!
+//! [`merge`] evaluates several queries against one document and yields all
+//! of their nodes as a single stream in document order, tagged with the
+//! index of the query that selected them.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::{order::merge, Query, ENV};
+//! use serde_json::json;
+//!
+//! let rules = vec![
+//!     Query::standard("$..[?@.level == 'error']").unwrap(),
+//!     Query::standard("$..[?@.id]").unwrap(),
+//! ];
+//! let value = json!([{"id": 1}, {"id": 2, "level": "error"}]);
+//!
+//! let matches: Vec<(usize, String)> = merge(&rules, &value, &ENV)
+//!     .map(|(rule, node)| (rule, node.path()))
+//!     .collect();
+//!
+//! assert_eq!(
+//!     matches,
+//!     vec![
+//!         (1, "$[0]".to_owned()),
+//!         (0, "$[1]".to_owned()),
+//!         (1, "$[1]".to_owned()),
+//!     ]
+//! );
+//! ```
+
+use std::{cmp::Reverse, collections::BinaryHeap, collections::HashMap, marker::PhantomData};
+
+use serde_json::Value;
+
+use crate::{env::Environment, function::Functions, node::Node, Query};
+
+/// The position of every value in a document in document order.
+///
+/// Values are identified by address, so an index only answers for values
+/// borrowed from the document it was built from, like those in nodes
+/// selected from that document.
+pub struct DocumentIndex<'v> {
+    positions: HashMap<*const Value, usize>,
+    document: PhantomData<&'v Value>,
+}
+
+impl<'v> DocumentIndex<'v> {
+    pub fn new(root: &'v Value) -> Self {
+        let mut positions = HashMap::new();
+        let mut stack = vec![root];
+        while let Some(value) = stack.pop() {
+            positions.insert(value as *const Value, positions.len());
+            match value {
+                Value::Array(arr) => stack.extend(arr.iter().rev()),
+                Value::Object(obj) => stack.extend(obj.values().rev()),
+                _ => (),
+            }
+        }
+
+        DocumentIndex {
+            positions,
+            document: PhantomData,
+        }
+    }
+
+    /// The position of `value` in document order, or `None` if `value` is
+    /// not part of this index's document.
+    pub fn position(&self, value: &Value) -> Option<usize> {
+        self.positions.get(&(value as *const Value)).copied()
+    }
+
+    /// Sort `nodes` into document order. Nodes with the same location keep
+    /// their relative order.
+    pub fn sort(&self, nodes: &mut [Node<'v>]) {
+        nodes.sort_by_key(|node| self.position(node.value).unwrap_or(usize::MAX));
+    }
+}
+
+/// Evaluate each of `queries` against `value` and merge their nodes into
+/// one iterator in document order.
+///
+/// Each item is the index of the query in `queries` and a node it selected.
+/// A node selected by more than one query is yielded once for each query,
+/// in the order of `queries`.
+pub fn merge<'v, F: Functions>(
+    queries: &[Query],
+    value: &'v Value,
+    env: &'static Environment<F>,
+) -> Merge<'v> {
+    let index = DocumentIndex::new(value);
+    let mut lists = Vec::with_capacity(queries.len());
+    let mut heads = Vec::with_capacity(queries.len());
+    let mut heap = BinaryHeap::new();
+
+    for (i, query) in queries.iter().enumerate() {
+        let mut nodes: Vec<(usize, Node<'v>)> = query
+            .find(value, env)
+            .into_iter()
+            .map(|node| (index.position(node.value).unwrap_or(usize::MAX), node))
+            .collect();
+        nodes.sort_by_key(|(position, _)| *position);
+
+        let mut nodes = nodes.into_iter();
+        let head = nodes.next();
+        if let Some((position, _)) = head {
+            heap.push(Reverse((position, i)));
+        }
+        heads.push(head.map(|(_, node)| node));
+        lists.push(nodes);
+    }
+
+    Merge { lists, heads, heap }
+}
+
+/// An iterator over nodes selected by several queries in document order,
+/// created by [`merge`].
+pub struct Merge<'v> {
+    lists: Vec<std::vec::IntoIter<(usize, Node<'v>)>>,
+    heads: Vec<Option<Node<'v>>>,
+    heap: BinaryHeap<Reverse<(usize, usize)>>,
+}
+
+impl<'v> Iterator for Merge<'v> {
+    type Item = (usize, Node<'v>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((_, i)) = self.heap.pop()?;
+        let head = self.heads.get_mut(i)?;
+        let node = head.take()?;
+
+        if let Some((position, next)) = self.lists.get_mut(i).and_then(Iterator::next) {
+            *head = Some(next);
+            self.heap.push(Reverse((position, i)));
+        }
+
+        Some((i, node))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.heap.len() + self.lists.iter().map(|list| list.len()).sum::<usize>();
+        (len, Some(len))
+    }
+}
@@ -0,0 +1,150 @@
+//! Pages of nodes in a stable order, for paginated APIs.
+//!
+//! Pages are ordered by node location, not by the order nodes were
+//! selected, so the same query against the same document always gives the
+//! same pages. Each page carries a [`PageCursor`], the normalized path of
+//! its last node, which a later request can use to resume after that page.
+//!
+//! ```
+//! use std::num::NonZeroUsize;
+//!
+//! use jsonpath_rfc9535_locations::{find, page::PageCursor};
+//! use serde_json::json;
+//!
+//! let page_size = NonZeroUsize::new(2).unwrap();
+//! let value = json!({"users": ["a", "b", "c"]});
+//! let nodes = find("$.users.*", &value).unwrap();
+//!
+//! let first = nodes.paginate(page_size).next().unwrap();
+//! assert_eq!(first.nodes.len(), 2);
+//!
+//! let cursor = first.next.unwrap().to_string();
+//! assert_eq!(cursor, "$['users'][1]");
+//!
+//! // Later, from a new request.
+//! let nodes = find("$.users.*", &value).unwrap();
+//! let cursor: PageCursor = cursor.parse().unwrap();
+//! let second = nodes.paginate(page_size).after(&cursor).next().unwrap();
+//! assert_eq!(second.nodes[0].value, "c");
+//! assert!(second.next.is_none());
+//! ```
+
+use std::{cmp::Ordering, fmt, num::NonZeroUsize, str::FromStr};
+
+use crate::{
+    errors::JSONPathError,
+    node::{Node, PathElement},
+    path::{parse_elements, write_elements},
+};
+
+/// The location of the last node on a page, from which the next page starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageCursor {
+    elements: Vec<PathElement>,
+}
+
+impl PageCursor {
+    /// This cursor's location as a list of path elements, starting from the root.
+    pub fn elements(&self) -> &[PathElement] {
+        &self.elements
+    }
+
+    fn cmp_location(&self, location: &[&PathElement]) -> Ordering {
+        self.elements.iter().cmp(location.iter().copied())
+    }
+}
+
+impl fmt::Display for PageCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_elements(f, &self.elements)
+    }
+}
+
+impl FromStr for PageCursor {
+    type Err = JSONPathError;
+
+    /// Parse a normalized path, like one given by a page's `next` cursor.
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Ok(PageCursor {
+            elements: parse_elements(path)?,
+        })
+    }
+}
+
+/// One page of nodes.
+#[derive(Debug)]
+pub struct Page<'n, 'v> {
+    /// Nodes on this page, ordered by location.
+    pub nodes: Vec<&'n Node<'v>>,
+    /// Where the next page starts, or `None` if this is the last page.
+    pub next: Option<PageCursor>,
+}
+
+/// An iterator over pages of a `NodeList`, created by `NodeList::paginate`.
+#[derive(Debug)]
+pub struct Pages<'n, 'v> {
+    nodes: Vec<(Vec<&'n PathElement>, &'n Node<'v>)>,
+    page_size: NonZeroUsize,
+    offset: usize,
+}
+
+impl<'n, 'v> Pages<'n, 'v> {
+    pub(crate) fn new(nodes: impl Iterator<Item = &'n Node<'v>>, page_size: NonZeroUsize) -> Self {
+        let mut nodes: Vec<(Vec<&'n PathElement>, &'n Node<'v>)> =
+            nodes.map(|node| (node.path_elements(), node)).collect();
+        nodes.sort_by(|a, b| a.0.cmp(&b.0));
+        nodes.dedup_by(|a, b| a.0 == b.0);
+
+        Pages {
+            nodes,
+            page_size,
+            offset: 0,
+        }
+    }
+
+    /// Skip to the first node after `cursor`.
+    ///
+    /// Nodes are found by location, so a cursor still works if nodes before
+    /// it have since been removed from the document.
+    pub fn after(mut self, cursor: &PageCursor) -> Self {
+        self.offset = self
+            .nodes
+            .partition_point(|(location, _)| cursor.cmp_location(location) != Ordering::Less);
+        self
+    }
+
+    /// The number of nodes on every page.
+    pub fn page_size(&self) -> NonZeroUsize {
+        self.page_size
+    }
+}
+
+impl<'n, 'v> Iterator for Pages<'n, 'v> {
+    type Item = Page<'n, 'v>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.nodes.len() {
+            return None;
+        }
+
+        let end = self
+            .offset
+            .saturating_add(self.page_size.get())
+            .min(self.nodes.len());
+        let page = self.nodes.get(self.offset..end)?;
+        self.offset = end;
+
+        let next = if end < self.nodes.len() {
+            page.last().map(|(location, _)| PageCursor {
+                elements: location.iter().map(|&e| e.clone()).collect(),
+            })
+        } else {
+            None
+        };
+
+        Some(Page {
+            nodes: page.iter().map(|(_, node)| *node).collect(),
+            next,
+        })
+    }
+}
@@ -0,0 +1,174 @@
+//! Normalized paths, and looking them up in documents.
+//!
+//! A normalized path identifies one value in a document, like the location of
+//! a node selected by an earlier query. Resolving it takes time proportional
+//! to the length of the path, rather than re-running the query.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::{find, path::NormalizedPath, resolve_path_mut};
+//! use serde_json::json;
+//!
+//! let mut value = json!({"users": [{"name": "a"}, {"name": "b"}]});
+//! let paths: Vec<NormalizedPath> = find("$.users[?@.name == 'b']", &value)
+//!     .unwrap()
+//!     .iter()
+//!     .map(NormalizedPath::from)
+//!     .collect();
+//!
+//! for path in &paths {
+//!     resolve_path_mut(path, &mut value).unwrap()["name"] = json!("c");
+//! }
+//!
+//! assert_eq!(value["users"][1]["name"], "c");
+//! assert_eq!(paths[0].to_string(), "$['users'][1]");
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use serde_json::Value;
+
+use crate::{
+    errors::JSONPathError,
+    node::{Location, Node, PathElement},
+    segment::Segment,
+    selector::Selector,
+    Query,
+};
+
+/// The location of a single value, as a list of path elements starting from
+/// the root.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NormalizedPath {
+    elements: Vec<PathElement>,
+}
+
+impl NormalizedPath {
+    pub fn new(elements: Vec<PathElement>) -> Self {
+        NormalizedPath { elements }
+    }
+
+    pub fn elements(&self) -> &[PathElement] {
+        &self.elements
+    }
+
+    pub(crate) fn location(&self) -> Location {
+        self.elements
+            .iter()
+            .fold(Location::new(), |location, element| {
+                location.append(element.clone())
+            })
+    }
+}
+
+impl From<&Node<'_>> for NormalizedPath {
+    fn from(node: &Node<'_>) -> Self {
+        NormalizedPath::new(node.path_elements().into_iter().cloned().collect())
+    }
+}
+
+impl fmt::Display for NormalizedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_elements(f, &self.elements)
+    }
+}
+
+impl FromStr for NormalizedPath {
+    type Err = JSONPathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Ok(NormalizedPath::new(parse_elements(path)?))
+    }
+}
+
+/// The value at `path` in `value`, or `None` if there isn't one.
+pub fn resolve_path<'v>(path: &NormalizedPath, value: &'v Value) -> Option<&'v Value> {
+    resolve(&path.elements, value)
+}
+
+/// A mutable reference to the value at `path` in `value`, or `None` if there
+/// isn't one.
+pub fn resolve_path_mut<'v>(path: &NormalizedPath, value: &'v mut Value) -> Option<&'v mut Value> {
+    resolve_mut(&path.elements, value)
+}
+
+pub(crate) fn resolve<'v>(elements: &[PathElement], value: &'v Value) -> Option<&'v Value> {
+    elements
+        .iter()
+        .try_fold(value, |value, element| match (value, element) {
+            (Value::Array(arr), PathElement::Index(i)) => arr.get(*i),
+            (Value::Object(obj), PathElement::Name(name)) => obj.get(name),
+            _ => None,
+        })
+}
+
+pub(crate) fn resolve_mut<'v>(
+    elements: &[PathElement],
+    value: &'v mut Value,
+) -> Option<&'v mut Value> {
+    elements
+        .iter()
+        .try_fold(value, |value, element| match (value, element) {
+            (Value::Array(arr), PathElement::Index(i)) => arr.get_mut(*i),
+            (Value::Object(obj), PathElement::Name(name)) => obj.get_mut(name),
+            _ => None,
+        })
+}
+
+/// Write `elements` as a normalized path, escaping names so the path parses
+/// back to the same elements.
+pub(crate) fn write_elements(f: &mut fmt::Formatter<'_>, elements: &[PathElement]) -> fmt::Result {
+    write!(f, "$")?;
+    for element in elements {
+        write!(f, "{element}")?;
+    }
+    Ok(())
+}
+
+/// Parse a normalized path into its path elements.
+///
+/// Any query made up of child segments with a single name selector or
+/// non-negative index selector is accepted, so `$.a[0]` is the same as
+/// `$['a'][0]`.
+pub(crate) fn parse_elements(path: &str) -> Result<Vec<PathElement>, JSONPathError> {
+    let query = Query::standard(path)?;
+    let mut elements = Vec::new();
+
+    for segment in &query.segments {
+        match segment {
+            Segment::Child { selectors } => match selectors.as_slice() {
+                [Selector::Name { name }] => elements.push(PathElement::Name(name.clone())),
+                [Selector::Index { index }] if *index >= 0 => {
+                    elements.push(PathElement::Index(*index as usize))
+                }
+                _ => return Err(not_normalized(path)),
+            },
+            Segment::Eoi => (),
+            Segment::Recursive { .. } => return Err(not_normalized(path)),
+        }
+    }
+
+    Ok(elements)
+}
+
+/// Escape `name` for a single-quoted name selector.
+pub(crate) fn escape(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\'' => escaped.push_str("\\'"),
+            '\u{08}' => escaped.push_str("\\b"),
+            '\u{0C}' => escaped.push_str("\\f"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c < '\u{20}' => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn not_normalized(path: &str) -> JSONPathError {
+    JSONPathError::syntax(format!("expected a normalized path, found '{}'", path))
+}
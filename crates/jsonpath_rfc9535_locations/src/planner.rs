@@ -0,0 +1,420 @@
+use serde_json::Value;
+
+use crate::{
+    env::Environment,
+    filter::{ComparisonOperator, FilterExpression, FilterExpressionResult, LogicalOperator},
+    function::{FunctionSignature, Functions},
+    segment::Segment,
+    selector::Selector,
+    Query,
+};
+
+// The cost of calling a function that doesn't declare a cost, not counting
+// its arguments.
+const DEFAULT_FUNCTION_COST: u32 = 100;
+
+// The cost of visiting a node's children, like with a wildcard selector.
+const CHILDREN_COST: u32 = 10;
+
+// The cost of visiting a node's descendants.
+const DESCENDANTS_COST: u32 = 100;
+
+impl Query {
+    /// Rewrite this query's filter expressions so they are cheaper to evaluate,
+    /// without changing the query's results.
+    ///
+    /// Expressions made up of literals and calls to pure functions with
+    /// literal arguments are evaluated once, here, instead of once per node.
+    /// Operands of `&&` and `||` are reordered so the cheapest is evaluated
+    /// first, if both are pure, using the cost declared in each function's
+    /// signature. Rewritten expressions still display as they were written.
+    /// And comparisons between `count()` and an integer literal stop counting
+    /// nodes once the result of the comparison is known.
+    ///
+    /// Filters that don't depend on the node being tested, like `?$.enabled`,
+    /// are moved before the wildcard segments preceding them, so they're
+    /// tested against fewer nodes.
+    pub fn plan<F: Functions>(self, env: &'static Environment<F>) -> Self {
+        let mut segments: Vec<Segment> = self
+            .segments
+            .into_iter()
+            .map(|segment| plan_segment(segment, env))
+            .collect();
+        push_down_filters(&mut segments, env);
+        Query::new(segments)
+    }
+}
+
+/// Move each segment made of one filter selector that doesn't depend on the
+/// current node before any wildcard segments immediately preceding it.
+///
+/// Such a filter selects all of a node's children or none of them, as a
+/// wildcard selects all of them, so `[*][?$.on]` and `[?$.on][*]` both select
+/// every grandchild, in the same order, if `$.on` exists. The first tests the
+/// filter once per grandchild and the second once per child.
+///
+/// A filter that refers to `@` can't be moved like this, as it would test
+/// different nodes. `[*][?@.on]` selects grandchildren with an `on` member,
+/// while `[?@.on][*]` selects all children of children with one.
+fn push_down_filters<F: Functions>(segments: &mut [Segment], env: &Environment<F>) {
+    for i in 1..segments.len() {
+        if !segments
+            .get(i)
+            .is_some_and(|segment| is_independent_filter(segment, env))
+        {
+            continue;
+        }
+
+        let mut j = i;
+        while j > 0 && segments.get(j - 1).is_some_and(is_wildcard) {
+            segments.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+fn is_wildcard(segment: &Segment) -> bool {
+    matches!(segment, Segment::Child { selectors } if matches!(selectors.as_slice(), [Selector::Wild]))
+}
+
+/// Return `true` if `segment` is a child segment with one filter selector
+/// whose expression has the same value for every node.
+fn is_independent_filter<F: Functions>(segment: &Segment, env: &Environment<F>) -> bool {
+    match segment {
+        Segment::Child { selectors } => match selectors.as_slice() {
+            [Selector::Filter { expression, .. }] => {
+                !refers_to_current(expression)
+                    && is_pure(expression, env)
+                    && !reads_location(expression, env)
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Return `true` if `expr` contains a query relative to the node being
+/// tested. Relative queries in filters of embedded queries refer to the
+/// nodes those filters test, so they don't count.
+fn refers_to_current(expr: &FilterExpression) -> bool {
+    match expr {
+        FilterExpression::Not { expression } => refers_to_current(expression),
+        FilterExpression::Logical { left, right, .. }
+        | FilterExpression::Comparison { left, right, .. } => {
+            refers_to_current(left) || refers_to_current(right)
+        }
+        FilterExpression::RelativeQuery { .. } => true,
+        FilterExpression::BoundedCount { root, .. } => !root,
+        FilterExpression::Function { args, .. } => args.iter().any(refers_to_current),
+        FilterExpression::Folded { value, .. } => refers_to_current(value),
+        _ => false,
+    }
+}
+
+fn plan_segment<F: Functions>(segment: Segment, env: &'static Environment<F>) -> Segment {
+    match segment {
+        Segment::Child { selectors } => Segment::Child {
+            selectors: plan_selectors(selectors, env),
+        },
+        Segment::Recursive { selectors } => Segment::Recursive {
+            selectors: plan_selectors(selectors, env),
+        },
+        Segment::Eoi => Segment::Eoi,
+    }
+}
+
+fn plan_selectors<F: Functions>(
+    selectors: Vec<Selector>,
+    env: &'static Environment<F>,
+) -> Vec<Selector> {
+    selectors
+        .into_iter()
+        .map(|selector| match selector {
+            Selector::Filter {
+                expression,
+                reads_location,
+            } => Selector::Filter {
+                expression: Box::new(plan_expression(*expression, env)),
+                reads_location,
+            },
+            _ => selector,
+        })
+        .collect()
+}
+
+fn plan_expression<F: Functions>(
+    expr: FilterExpression,
+    env: &'static Environment<F>,
+) -> FilterExpression {
+    let expr = match expr {
+        FilterExpression::Not { expression } => FilterExpression::Not {
+            expression: Box::new(plan_expression(*expression, env)),
+        },
+        FilterExpression::Logical {
+            left,
+            operator,
+            right,
+        } => plan_logical(
+            plan_expression(*left, env),
+            operator,
+            plan_expression(*right, env),
+            env,
+        ),
+        FilterExpression::Comparison {
+            left,
+            operator,
+            right,
+        } => bound_count(
+            plan_expression(*left, env),
+            operator,
+            plan_expression(*right, env),
+            env,
+        ),
+        FilterExpression::RelativeQuery { query } => FilterExpression::RelativeQuery {
+            query: Box::new(query.plan(env)),
+        },
+        FilterExpression::RootQuery { query } => FilterExpression::RootQuery {
+            query: Box::new(query.plan(env)),
+        },
+        FilterExpression::Function { name, args } => FilterExpression::Function {
+            name,
+            args: args
+                .into_iter()
+                .map(|arg| plan_expression(arg, env))
+                .collect(),
+        },
+        _ => expr,
+    };
+
+    if expr.folded_value().is_literal() || !is_constant(&expr, env) {
+        return expr;
+    }
+
+    match fold(&expr, env) {
+        Some(value) => FilterExpression::Folded {
+            value: Box::new(value),
+            expression: Box::new(expr),
+        },
+        None => expr,
+    }
+}
+
+fn plan_logical<F: Functions>(
+    left: FilterExpression,
+    operator: LogicalOperator,
+    right: FilterExpression,
+    env: &'static Environment<F>,
+) -> FilterExpression {
+    use FilterExpression::{False, True};
+
+    let pure = is_pure(&left, env) && is_pure(&right, env);
+    let value = match (left.folded_value(), &operator, right.folded_value()) {
+        (False, LogicalOperator::And, _) | (_, LogicalOperator::And, False) if pure => Some(False),
+        (True, LogicalOperator::Or, _) | (_, LogicalOperator::Or, True) if pure => Some(True),
+        // A literal operand that doesn't decide the result is cheap, but
+        // there's nothing to gain from evaluating it first.
+        _ if pure && !right.folded_value().is_literal() && cost(&right, env) < cost(&left, env) => {
+            Some(FilterExpression::Logical {
+                left: Box::new(right.clone()),
+                operator,
+                right: Box::new(left.clone()),
+            })
+        }
+        _ => None,
+    };
+
+    let expression = FilterExpression::Logical {
+        left: Box::new(left),
+        operator,
+        right: Box::new(right),
+    };
+
+    match value {
+        Some(value) => FilterExpression::Folded {
+            value: Box::new(value),
+            expression: Box::new(expression),
+        },
+        None => expression,
+    }
+}
+
+/// Rewrite `count(<query>) <op> <int>` so counting stops as soon as the
+/// comparison is decided.
+fn bound_count<F: Functions>(
+    left: FilterExpression,
+    operator: ComparisonOperator,
+    right: FilterExpression,
+    env: &Environment<F>,
+) -> FilterExpression {
+    let (left, right) = match (left, right) {
+        (FilterExpression::Function { name, args }, FilterExpression::Int { value })
+            if is_count(&name, &args, env) =>
+        {
+            (bounded(name, args, value), FilterExpression::Int { value })
+        }
+        (FilterExpression::Int { value }, FilterExpression::Function { name, args })
+            if is_count(&name, &args, env) =>
+        {
+            (FilterExpression::Int { value }, bounded(name, args, value))
+        }
+        (left, right) => (left, right),
+    };
+
+    FilterExpression::Comparison {
+        left: Box::new(left),
+        operator,
+        right: Box::new(right),
+    }
+}
+
+fn is_count<F: Functions>(name: &str, args: &[FilterExpression], env: &Environment<F>) -> bool {
+    name == "count"
+        && env
+            .function_register
+            .signature(name)
+            .is_some_and(|sig| sig.pure)
+        // Bounded counts don't track locations.
+        && matches!(args, [arg] if is_query(arg)
+            && !reads_location(arg, env))
+}
+
+fn bounded(name: String, mut args: Vec<FilterExpression>, value: i64) -> FilterExpression {
+    // Counting to one more than `value` is enough to compare with `value`. Any
+    // count is greater than a negative `value`, so there's no need to count.
+    let limit = usize::try_from(value).map_or(0, |n| n.saturating_add(1));
+    match args.pop() {
+        Some(FilterExpression::RelativeQuery { query }) => FilterExpression::BoundedCount {
+            query,
+            root: false,
+            limit,
+        },
+        Some(FilterExpression::RootQuery { query }) => FilterExpression::BoundedCount {
+            query,
+            root: true,
+            limit,
+        },
+        arg => {
+            args.extend(arg);
+            FilterExpression::Function { name, args }
+        }
+    }
+}
+
+fn is_query(expr: &FilterExpression) -> bool {
+    matches!(
+        expr,
+        FilterExpression::RelativeQuery { .. } | FilterExpression::RootQuery { .. }
+    )
+}
+
+/// Evaluate a constant expression, returning it as a literal.
+fn fold<F: Functions>(
+    expr: &FilterExpression,
+    env: &'static Environment<F>,
+) -> Option<FilterExpression> {
+    let null = Value::Null;
+    match expr.evaluate(env, &null, &null) {
+        FilterExpressionResult::Bool(true) => Some(FilterExpression::True),
+        FilterExpressionResult::Bool(false) => Some(FilterExpression::False),
+        FilterExpressionResult::Null => Some(FilterExpression::Null),
+        FilterExpressionResult::Int(value) => Some(FilterExpression::Int { value }),
+        FilterExpressionResult::UInt(value) => Some(FilterExpression::UInt { value }),
+        FilterExpressionResult::Float(value) => Some(FilterExpression::Float { value }),
+        FilterExpressionResult::String(value) => Some(FilterExpression::String {
+            value: value.into_owned(),
+        }),
+        _ => None,
+    }
+}
+
+/// Return `true` if `expr` has the same value for every node, and can be
+/// evaluated without side effects.
+fn is_constant<F: Functions>(expr: &FilterExpression, env: &Environment<F>) -> bool {
+    match expr {
+        FilterExpression::Not { expression } => is_constant(expression, env),
+        FilterExpression::Logical { left, right, .. }
+        | FilterExpression::Comparison { left, right, .. } => {
+            is_constant(left, env) && is_constant(right, env)
+        }
+        FilterExpression::RelativeQuery { .. }
+        | FilterExpression::RootQuery { .. }
+        | FilterExpression::BoundedCount { .. } => false,
+        FilterExpression::Function { name, args } => {
+            env.function_register
+                .signature(name)
+                .is_some_and(|sig| sig.pure)
+                && args.iter().all(|arg| is_constant(arg, env))
+        }
+        FilterExpression::Folded { value, .. } => is_constant(value, env),
+        _ => true,
+    }
+}
+
+/// Return `true` if `expr` only calls pure functions.
+fn is_pure<F: Functions>(expr: &FilterExpression, env: &Environment<F>) -> bool {
+    all_calls(expr, env, &|sig| sig.pure)
+}
+
+/// Return `true` if every function called by `expr`, including those in
+/// filters of embedded queries, is registered and satisfies `f`.
+fn all_calls<F: Functions>(
+    expr: &FilterExpression,
+    env: &Environment<F>,
+    f: &dyn Fn(&FunctionSignature) -> bool,
+) -> bool {
+    !expr.any_call(&|name| !env.function_register.signature(name).is_some_and(f))
+}
+
+/// Return `true` if `expr` calls a function that reads locations.
+fn reads_location<F: Functions>(expr: &FilterExpression, env: &Environment<F>) -> bool {
+    expr.any_call(&|name| env.function_register.reads_location(name))
+}
+
+/// An estimate of the relative cost of evaluating `expr` once.
+fn cost<F: Functions>(expr: &FilterExpression, env: &Environment<F>) -> u32 {
+    match expr {
+        FilterExpression::Not { expression } => cost(expression, env),
+        FilterExpression::Logical { left, right, .. }
+        | FilterExpression::Comparison { left, right, .. } => {
+            cost(left, env).saturating_add(cost(right, env))
+        }
+        FilterExpression::RelativeQuery { query }
+        | FilterExpression::RootQuery { query }
+        | FilterExpression::BoundedCount { query, .. } => query_cost(query, env),
+        FilterExpression::Function { name, args } => {
+            let call = env
+                .function_register
+                .signature(name)
+                .and_then(|sig| sig.cost)
+                .unwrap_or(DEFAULT_FUNCTION_COST);
+            args.iter()
+                .fold(call, |acc, arg| acc.saturating_add(cost(arg, env)))
+        }
+        FilterExpression::Folded { value, .. } => cost(value, env),
+        _ => 0,
+    }
+}
+
+fn query_cost<F: Functions>(query: &Query, env: &Environment<F>) -> u32 {
+    // Each segment multiplies the number of nodes we might visit, so later
+    // segments are weighted by the cost of earlier ones.
+    query.segments.iter().fold(1, |acc, segment| match segment {
+        Segment::Child { selectors } => acc.saturating_mul(selectors_cost(selectors, env).max(1)),
+        Segment::Recursive { selectors } => acc
+            .saturating_mul(DESCENDANTS_COST)
+            .saturating_mul(selectors_cost(selectors, env).max(1)),
+        Segment::Eoi => acc,
+    })
+}
+
+fn selectors_cost<F: Functions>(selectors: &[Selector], env: &Environment<F>) -> u32 {
+    selectors.iter().fold(0, |acc, selector| {
+        acc.saturating_add(match selector {
+            Selector::Name { .. } | Selector::Index { .. } => 1,
+            Selector::Slice { .. } | Selector::Wild => CHILDREN_COST,
+            Selector::Filter { expression, .. } => {
+                CHILDREN_COST.saturating_mul(cost(expression, env).max(1))
+            }
+        })
+    })
+}
@@ -0,0 +1,223 @@
+//! Match documents against many filter expressions at once.
+//!
+//! A [`PredicateSet`] holds rules written as filter expressions, the part of
+//! a filter selector after `?`. `@` and `$` both refer to the document being
+//! matched. Rules are split into their logical operands and identical
+//! operands are stored once, so a comparison like `@.level == 'error'` that
+//! appears in many rules is evaluated once per document.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::predicate::PredicateSet;
+//! use serde_json::json;
+//!
+//! let mut rules = PredicateSet::new();
+//! rules.add("alerts", "@.level == 'error' && @.service == 'api'").unwrap();
+//! rules.add("api", "@.service == 'api'").unwrap();
+//! rules.add("slow", "@.duration > 1000").unwrap();
+//!
+//! let log = json!({"level": "error", "service": "api", "duration": 12});
+//! assert_eq!(rules.matches(&log), vec![&"alerts", &"api"]);
+//! ```
+
+use std::collections::HashMap;
+
+use jsonpath_rfc9535::span::Span;
+use serde_json::Value;
+
+use crate::{
+    errors::JSONPathError,
+    filter::{is_truthy, FilterExpression, LogicalOperator},
+    node::Location,
+    segment::Segment,
+    selector::Selector,
+    Query, ENV,
+};
+
+/// A logical operand shared by one or more rules, referring to other
+/// operands by their index in `PredicateSet::predicates`.
+#[derive(Debug)]
+enum Predicate {
+    And(usize, usize),
+    Or(usize, usize),
+    Not(usize),
+    Test(FilterExpression),
+}
+
+/// Rules identified by `Id`, each a filter expression.
+#[derive(Debug)]
+pub struct PredicateSet<Id> {
+    rules: Vec<(Id, usize)>,
+    predicates: Vec<Predicate>,
+    /// The index of each predicate, keyed by its debug representation,
+    /// which unlike its canonical form is unambiguous.
+    index: HashMap<String, usize>,
+}
+
+impl<Id> Default for PredicateSet<Id> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Id> PredicateSet<Id> {
+    pub fn new() -> Self {
+        PredicateSet {
+            rules: Vec::new(),
+            predicates: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Add a rule matching documents for which the filter expression `expr`
+    /// is true.
+    pub fn add(&mut self, id: Id, expr: &str) -> Result<(), JSONPathError> {
+        let expression = parse_filter(expr)?;
+        let predicate = self.insert(expression);
+        self.rules.push((id, predicate));
+        Ok(())
+    }
+
+    /// IDs of rules matching `value`, in the order they were added.
+    pub fn matches(&self, value: &Value) -> Vec<&Id> {
+        let mut results = vec![None; self.predicates.len()];
+        self.rules
+            .iter()
+            .filter(|(_, predicate)| self.evaluate(*predicate, value, &mut results))
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// The number of rules in this set.
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The number of distinct logical operands across all rules. Operands
+    /// shared by several rules are counted once.
+    pub fn predicate_count(&self) -> usize {
+        self.predicates.len()
+    }
+
+    fn insert(&mut self, expression: FilterExpression) -> usize {
+        let key = format!("{:?}", expression);
+        if let Some(i) = self.index.get(&key) {
+            return *i;
+        }
+
+        let predicate = match expression {
+            FilterExpression::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                let left = self.insert(*left);
+                let right = self.insert(*right);
+                match operator {
+                    LogicalOperator::And => Predicate::And(left, right),
+                    LogicalOperator::Or => Predicate::Or(left, right),
+                }
+            }
+            FilterExpression::Not { expression } => Predicate::Not(self.insert(*expression)),
+            expression => Predicate::Test(expression),
+        };
+
+        self.predicates.push(predicate);
+        self.index.insert(key, self.predicates.len() - 1);
+        self.predicates.len() - 1
+    }
+
+    /// Evaluate the predicate at index `i`, remembering results in
+    /// `results` so shared operands are only evaluated once.
+    fn evaluate(&self, i: usize, value: &Value, results: &mut [Option<bool>]) -> bool {
+        if let Some(&Some(rv)) = results.get(i) {
+            return rv;
+        }
+
+        let Some(predicate) = self.predicates.get(i) else {
+            return false;
+        };
+
+        let rv = match predicate {
+            Predicate::And(left, right) => {
+                self.evaluate(*left, value, results) && self.evaluate(*right, value, results)
+            }
+            Predicate::Or(left, right) => {
+                self.evaluate(*left, value, results) || self.evaluate(*right, value, results)
+            }
+            Predicate::Not(expression) => !self.evaluate(*expression, value, results),
+            Predicate::Test(expression) => {
+                is_truthy(expression.evaluate_at(&ENV, value, value, Some(&Location::new())))
+            }
+        };
+
+        if let Some(result) = results.get_mut(i) {
+            *result = Some(rv);
+        }
+        rv
+    }
+}
+
+/// Example documents for a filter expression, from [`examples`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Examples {
+    /// Documents for which the expression is true.
+    pub satisfying: Vec<Value>,
+    /// Documents for which the expression is false.
+    pub violating: Vec<Value>,
+}
+
+/// Generate documents that do and don't match the filter expression `expr`,
+/// as a starting point for a rule's unit tests.
+///
+/// Violating examples are near misses where possible, with one operand of a
+/// `&&` made false and the other true. Functions other than `match()` and
+/// `search()`, and comparisons between non-singular queries, aren't
+/// analysed, so either list might be empty even when such documents exist.
+///
+/// ```
+/// use jsonpath_rfc9535_locations::predicate::examples;
+/// use serde_json::json;
+///
+/// let examples = examples("@.price > 5 && @.name == 'foo'").unwrap();
+/// assert_eq!(examples.satisfying, vec![json!({"price": 6, "name": "foo"})]);
+/// assert!(examples.violating.contains(&json!({"price": 5, "name": "foo"})));
+/// assert!(examples.violating.contains(&json!({"price": 6})));
+/// ```
+pub fn examples(expr: &str) -> Result<Examples, JSONPathError> {
+    Ok(parse_filter(expr)?.examples())
+}
+
+/// Parse `expr` as the expression of a filter selector.
+fn parse_filter(expr: &str) -> Result<FilterExpression, JSONPathError> {
+    const PREFIX: &str = "$[?";
+
+    let mut query = Query::standard(&format!("{PREFIX}{expr}]")).map_err(|mut err| {
+        err.span = err.span.map(|span| {
+            Span::new(
+                span.start.saturating_sub(PREFIX.len()),
+                span.end.saturating_sub(PREFIX.len()),
+            )
+            .with_chars(expr)
+        });
+        err
+    })?;
+
+    let not_a_filter =
+        || JSONPathError::syntax(format!("expected a filter expression, found '{}'", expr));
+
+    match query.segments.as_mut_slice() {
+        [Segment::Child { selectors }, Segment::Eoi] | [Segment::Child { selectors }] => {
+            match selectors.pop() {
+                Some(Selector::Filter { expression, .. }) if selectors.is_empty() => {
+                    Ok(*expression)
+                }
+                _ => Err(not_a_filter()),
+            }
+        }
+        _ => Err(not_a_filter()),
+    }
+}
@@ -0,0 +1,82 @@
+use jsonpath_rfc9535::csv;
+use serde_json::Value;
+
+use crate::{errors::JSONPathError, jsonpath::ENV, Query};
+
+/// A table extracted from a JSON document.
+///
+/// Rows are the nodes selected by a base query. Each column is a singular
+/// query evaluated with a row's value as its root, so `$.name` selects the
+/// `name` member of each row.
+#[derive(Debug)]
+pub struct Projection {
+    pub rows: Query,
+    pub columns: Vec<(String, Query)>,
+}
+
+impl Projection {
+    /// Parse a base query and `(column name, query)` pairs. Fails if any
+    /// column query is not a singular query.
+    pub fn new(rows: &str, columns: &[(&str, &str)]) -> Result<Self, JSONPathError> {
+        let rows = Query::standard(rows)?;
+        let columns = columns
+            .iter()
+            .map(|(name, expr)| {
+                let query = Query::standard(expr)?;
+                if !query.is_singular() {
+                    return Err(JSONPathError::typ(format!(
+                        "column '{}' must be a singular query, found {}",
+                        name, query
+                    )));
+                }
+                Ok((name.to_string(), query))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Projection { rows, columns })
+    }
+
+    /// Evaluate this projection against `value`, one entry per row and one
+    /// `Option` per column, `None` when a column query selects nothing.
+    pub fn project<'v>(&self, value: &'v Value) -> Vec<Vec<Option<&'v Value>>> {
+        self.rows
+            .find(value, &ENV)
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .map(|(_, query)| query.find(row.value, &ENV).first().map(|n| n.value))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Render this projection as comma separated values with a header row,
+    /// following the conventions of [`jsonpath_rfc9535::csv`]: records end
+    /// with CRLF, missing values are empty fields, JSON `null` is `null`,
+    /// strings are written without JSON quotes and arrays and objects are
+    /// written as JSON.
+    pub fn to_csv(&self, value: &Value) -> String {
+        self.to_delimited(value, ',')
+    }
+
+    /// Like `to_csv`, but with fields separated by tabs.
+    pub fn to_tsv(&self, value: &Value) -> String {
+        self.to_delimited(value, '\t')
+    }
+
+    fn to_delimited(&self, value: &Value, delimiter: char) -> String {
+        let mut buf = String::new();
+        csv::push_record(
+            &mut buf,
+            self.columns.iter().map(|(name, _)| name),
+            delimiter,
+        );
+
+        for row in self.project(value) {
+            csv::push_record(&mut buf, row.into_iter().map(csv::value_field), delimiter);
+        }
+
+        buf
+    }
+}
@@ -1,13 +1,15 @@
 use std::fmt;
 
+use jsonpath_rfc9535::resolve_index;
 use lazy_static::lazy_static;
-use serde_json::Value;
 
 use crate::{
     conslist::ConsList,
     env::Environment,
     errors::JSONPathError,
-    node::{Node, NodeList},
+    function::Functions,
+    json_like::JsonLike,
+    node::{escape_pointer_token, Location, Node, NodeList},
     segment::Segment,
     selector::Selector,
     JSONPathParser,
@@ -17,7 +19,7 @@ lazy_static! {
     static ref PARSER: JSONPathParser = JSONPathParser::new();
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Query {
     pub segments: Vec<Segment>,
 }
@@ -31,36 +33,277 @@ impl Query {
         PARSER.parse(expr)
     }
 
-    pub fn find<'v>(&self, value: &'v Value, env: &'static Environment) -> NodeList<'v> {
+    pub fn find<'v, J: JsonLike, F: Functions<J>>(
+        &self,
+        value: &'v J,
+        env: &'static Environment<F>,
+    ) -> NodeList<'v, J> {
+        self.find_at(value, ConsList::new(), env)
+    }
+
+    /// Like [`Query::find`], but failing with a name error if the query
+    /// calls a function `env` doesn't have, which happens if it was parsed
+    /// with a different environment. `find` evaluates such calls to nothing.
+    pub fn try_find<'v, J: JsonLike, F: Functions<J>>(
+        &self,
+        value: &'v J,
+        env: &'static Environment<F>,
+    ) -> Result<NodeList<'v, J>, JSONPathError> {
+        if let Some(name) = self.find_call(&|name| env.function_register.signature(name).is_none())
+        {
+            return Err(JSONPathError::name(format!("unknown function `{name}`")));
+        }
+        Ok(self.find(value, env))
+    }
+
+    /// Like [`Query::find`], but with locations relative to `location`
+    /// instead of `value`.
+    pub(crate) fn find_at<'v, J: JsonLike, F: Functions<J>>(
+        &self,
+        value: &'v J,
+        location: Location,
+        env: &'static Environment<F>,
+    ) -> NodeList<'v, J> {
+        let root_node = Node { value, location };
+
+        self.segments
+            .iter()
+            .fold(NodeList::from(vec![root_node]), |nodes, segment| {
+                segment.resolve(nodes, env, value)
+            })
+    }
+
+    /// Count the nodes this query would select from `value`, stopping once
+    /// `limit` nodes have been found.
+    ///
+    /// Locations aren't built while counting, unless a filter calls a
+    /// function that reads them.
+    pub fn count_up_to<J: JsonLike, F: Functions<J>>(
+        &self,
+        value: &J,
+        env: &'static Environment<F>,
+        limit: usize,
+    ) -> usize {
+        if self.reads_location() {
+            let mut count = 0;
+            let _ = self.find_each(value, env, |_| {
+                count += 1;
+                if count < limit {
+                    Ok(())
+                } else {
+                    Err(())
+                }
+            });
+            return count.min(limit);
+        }
+
+        let values: Box<dyn Iterator<Item = &J>> = Box::new(std::iter::once(value));
+        self.segments
+            .iter()
+            .fold(values, |values, segment| {
+                Box::new(values.flat_map(move |v| segment.values(env, v, value)))
+            })
+            .take(limit)
+            .count()
+    }
+
+    /// Return `true` if any of this query's filters needs the locations of
+    /// the nodes it tests.
+    fn reads_location(&self) -> bool {
+        self.segments.iter().any(|segment| match segment {
+            Segment::Child { selectors } | Segment::Recursive { selectors } => {
+                selectors.iter().any(|selector| {
+                    matches!(
+                        selector,
+                        Selector::Filter {
+                            reads_location: true,
+                            ..
+                        }
+                    )
+                })
+            }
+            Segment::Eoi => false,
+        })
+    }
+
+    /// The name of the first function called by a filter in this query for
+    /// which `f` is `true`.
+    pub(crate) fn find_call(&self, f: &dyn Fn(&str) -> bool) -> Option<&str> {
+        self.segments.iter().find_map(|segment| match segment {
+            Segment::Child { selectors } | Segment::Recursive { selectors } => {
+                selectors.iter().find_map(|selector| match selector {
+                    Selector::Filter { expression, .. } => expression.find_call(f),
+                    _ => None,
+                })
+            }
+            Segment::Eoi => None,
+        })
+    }
+
+    /// Like [`Query::find`], but passing each node to `f` as it's selected,
+    /// one input node at a time, and stopping at the first error from `f`.
+    pub(crate) fn find_each<'v, J: JsonLike, F: Functions<J>, E>(
+        &self,
+        value: &'v J,
+        env: &'static Environment<F>,
+        mut f: impl FnMut(Node<'v, J>) -> Result<(), E>,
+    ) -> Result<(), E> {
         let root_node = Node {
             value,
             location: ConsList::new(),
         };
 
+        select_each(&self.segments, root_node, env, value, &mut f)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Resolve a singular query against `value` directly, without building a
+    /// node list. Returns `None` if the query selects nothing, or if it is
+    /// not singular.
+    pub fn singular_value<'v, J: JsonLike>(&self, value: &'v J) -> Option<&'v J> {
         self.segments
             .iter()
-            .fold(vec![root_node], |nodes, segment| {
-                segment.resolve(nodes, env, value)
+            .try_fold(value, |value, segment| match segment {
+                Segment::Child { selectors } => match selectors.as_slice() {
+                    [Selector::Name { name }] => value.member(name),
+                    [Selector::Index { index }] => {
+                        let array = value.as_array()?;
+                        array.get(resolve_index(*index, array.len())?)
+                    }
+                    _ => None,
+                },
+                Segment::Eoi => Some(value),
+                Segment::Recursive { .. } => None,
             })
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.segments.is_empty()
+    /// Returns `false` if this query can't select the root value's member
+    /// `name`, or anything inside it, from any document.
+    ///
+    /// This only looks at the query's first segment, so `true` means a
+    /// document with member `name` might have matches there, not that it
+    /// does. Message routers can use it to skip evaluating stored queries
+    /// against documents that don't have the members they need.
+    pub fn could_match_member(&self, name: &str) -> bool {
+        match self.segments.first() {
+            Some(Segment::Child { selectors }) => selectors.iter().any(|selector| match selector {
+                Selector::Name { name: n } => n == name,
+                Selector::Wild | Selector::Filter { .. } => true,
+                Selector::Index { .. } | Selector::Slice { .. } => false,
+            }),
+            Some(Segment::Recursive { .. }) => true,
+            Some(Segment::Eoi) | None => false,
+        }
+    }
+
+    /// Returns `false` if this query can't select element `index` of the
+    /// root array, or anything inside it, from any document.
+    ///
+    /// Like [`Query::could_match_member`], this is a conservative check of
+    /// the first segment. Negative indices and slice bounds depend on the
+    /// array's length, so they might match any index.
+    pub fn could_match_index(&self, index: usize) -> bool {
+        match self.segments.first() {
+            Some(Segment::Child { selectors }) => selectors.iter().any(|selector| match selector {
+                Selector::Index { index: i } => *i < 0 || usize::try_from(*i) == Ok(index),
+                Selector::Slice { start, stop, step } => {
+                    slice_could_select(*start, *stop, *step, index)
+                }
+                Selector::Wild | Selector::Filter { .. } => true,
+                Selector::Name { .. } => false,
+            }),
+            Some(Segment::Recursive { .. }) => true,
+            Some(Segment::Eoi) | None => false,
+        }
+    }
+
+    /// This query as an RFC 6901 JSON Pointer. Fails if the query is not
+    /// singular, or if it has a negative index, which a pointer can't express
+    /// without knowing the array's length.
+    pub fn to_json_pointer(&self) -> Result<String, JSONPathError> {
+        if !self.is_singular() {
+            return Err(JSONPathError::typ(format!(
+                "only singular queries can be converted to a JSON Pointer, found {}",
+                self
+            )));
+        }
+
+        self.segments
+            .iter()
+            .filter_map(|segment| match segment {
+                Segment::Child { selectors } => selectors.first(),
+                _ => None,
+            })
+            .map(|selector| match selector {
+                Selector::Name { name } => Ok(format!("/{}", escape_pointer_token(name))),
+                Selector::Index { index } if *index >= 0 => Ok(format!("/{}", index)),
+                _ => Err(JSONPathError::typ(format!(
+                    "negative indices can't be converted to a JSON Pointer, found {}",
+                    self
+                ))),
+            })
+            .collect()
     }
 
     pub fn is_singular(&self) -> bool {
-        self.segments.iter().all(|segment| {
-            if let Segment::Child { selectors, .. } = segment {
-                return selectors.len() == 1
+        self.segments.iter().all(|segment| match segment {
+            Segment::Child { selectors, .. } => {
+                selectors.len() == 1
                     && selectors.first().is_some_and(|selector| {
                         matches!(selector, Selector::Name { .. } | Selector::Index { .. })
-                    });
+                    })
             }
-            false
+            Segment::Eoi => true,
+            _ => false,
         })
     }
 }
 
+/// Returns `false` if a slice selector can't select element `index` of an
+/// array, whatever the array's length.
+fn slice_could_select(
+    start: Option<i64>,
+    stop: Option<i64>,
+    step: Option<i64>,
+    index: usize,
+) -> bool {
+    let index = index as i128;
+    let step = step.unwrap_or(1) as i128;
+    let start = start.map(i128::from);
+    let stop = stop.map(i128::from);
+
+    if step > 0 {
+        let start = start.unwrap_or(0);
+        !(stop.is_some_and(|stop| stop >= 0 && index >= stop)
+            || (start >= 0 && (index < start || (index - start) % step != 0)))
+    } else if step < 0 {
+        !(start.is_some_and(|start| start >= 0 && index > start)
+            || stop.is_some_and(|stop| stop >= 0 && index <= stop))
+    } else {
+        false
+    }
+}
+
+/// Pass each node `segments` select from `node` to `f`, depth first.
+fn select_each<'v, J: JsonLike, F: Functions<J>, E>(
+    segments: &[Segment],
+    node: Node<'v, J>,
+    env: &'static Environment<F>,
+    root: &'v J,
+    f: &mut impl FnMut(Node<'v, J>) -> Result<(), E>,
+) -> Result<(), E> {
+    match segments.split_first() {
+        None => f(node),
+        Some((segment, rest)) => segment
+            .resolve(NodeList::from(vec![node]), env, root)
+            .into_iter()
+            .try_for_each(|node| select_each(rest, node, env, root, f)),
+    }
+}
+
 impl fmt::Display for Query {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
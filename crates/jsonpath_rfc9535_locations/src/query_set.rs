@@ -0,0 +1,177 @@
+//! Evaluate many stored queries against the same document.
+//!
+//! A [`QuerySet`] interns the segments of its queries, so a segment that
+//! appears in many queries, like a long filter, is stored once. Queries that
+//! start with the same segments share those steps, and each step is evaluated
+//! once per document, no matter how many queries go through it.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::query_set::QuerySet;
+//! use serde_json::json;
+//!
+//! let mut queries = QuerySet::new();
+//! queries.add("names", "$.users[?@.active == true].name").unwrap();
+//! queries.add("emails", "$.users[?@.active == true].email").unwrap();
+//!
+//! let value = json!({"users": [
+//!     {"name": "a", "email": "a@example.com", "active": true},
+//!     {"name": "b", "email": "b@example.com", "active": false},
+//! ]});
+//!
+//! let results = queries.find(&value);
+//! assert_eq!(results[0].1.as_strings().unwrap(), vec!["a"]);
+//! assert_eq!(results[1].1.as_strings().unwrap(), vec!["a@example.com"]);
+//!
+//! // `.users`, `[?@.active == true]`, `.name` and `.email`
+//! assert_eq!(queries.segment_count(), 4);
+//! ```
+//!
+//! Filters are interned as part of the segment that contains them. Identical
+//! operands inside otherwise different filters are not shared, see
+//! [`PredicateSet`] for that.
+//!
+//! [`PredicateSet`]: crate::predicate::PredicateSet
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    errors::JSONPathError,
+    node::{Location, Node, NodeList},
+    segment::Segment,
+    Query, ENV,
+};
+
+/// An interned segment applied to the nodes selected by `parent`, or to the
+/// root node if `parent` is `None`.
+#[derive(Debug)]
+struct Step {
+    parent: Option<usize>,
+    segment: usize,
+}
+
+/// Queries identified by `Id`.
+#[derive(Debug)]
+pub struct QuerySet<Id> {
+    /// Each query and its last step, `None` for `$`.
+    queries: Vec<(Id, Option<usize>)>,
+    segments: Vec<Segment>,
+    /// The index of each segment, keyed by its debug representation, which
+    /// unlike its canonical form is unambiguous.
+    segment_index: HashMap<String, usize>,
+    /// Steps are always added after their parent.
+    steps: Vec<Step>,
+    step_index: HashMap<(Option<usize>, usize), usize>,
+}
+
+impl<Id> Default for QuerySet<Id> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Id> QuerySet<Id> {
+    pub fn new() -> Self {
+        QuerySet {
+            queries: Vec::new(),
+            segments: Vec::new(),
+            segment_index: HashMap::new(),
+            steps: Vec::new(),
+            step_index: HashMap::new(),
+        }
+    }
+
+    /// Parse `expr` and add it to the set.
+    pub fn add(&mut self, id: Id, expr: &str) -> Result<(), JSONPathError> {
+        self.insert(id, Query::standard(expr)?);
+        Ok(())
+    }
+
+    /// Add an already compiled query to the set.
+    pub fn insert(&mut self, id: Id, query: Query) {
+        let last = query
+            .segments
+            .into_iter()
+            .filter(|segment| !matches!(segment, Segment::Eoi))
+            .fold(None, |parent, segment| {
+                let segment = self.intern(segment);
+                Some(self.step(parent, segment))
+            });
+        self.queries.push((id, last));
+    }
+
+    /// Nodes selected from `value` by each query, in the order queries were
+    /// added.
+    pub fn find<'v>(&self, value: &'v Value) -> Vec<(&Id, NodeList<'v>)> {
+        let root = || {
+            NodeList::from(vec![Node {
+                value,
+                location: Location::new(),
+            }])
+        };
+
+        let mut results: Vec<NodeList<'v>> = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let nodes = match step.parent {
+                Some(parent) => results.get(parent).into_iter().flatten().cloned().collect(),
+                None => root(),
+            };
+            results.push(match self.segments.get(step.segment) {
+                Some(segment) => segment.resolve(nodes, &ENV, value),
+                None => nodes,
+            });
+        }
+
+        self.queries
+            .iter()
+            .map(|(id, last)| {
+                let nodes = match last {
+                    Some(step) => results.get(*step).into_iter().flatten().cloned().collect(),
+                    None => root(),
+                };
+                (id, nodes)
+            })
+            .collect()
+    }
+
+    /// The number of queries in this set.
+    pub fn len(&self) -> usize {
+        self.queries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// The number of distinct segments across all queries. Segments that
+    /// appear in several queries, or several times in one query, are counted
+    /// once.
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// The number of segments evaluated per document, after sharing common
+    /// query prefixes.
+    pub fn step_count(&self) -> usize {
+        self.steps.len()
+    }
+
+    fn intern(&mut self, segment: Segment) -> usize {
+        let key = format!("{:?}", segment);
+        if let Some(i) = self.segment_index.get(&key) {
+            return *i;
+        }
+
+        self.segments.push(segment);
+        self.segment_index.insert(key, self.segments.len() - 1);
+        self.segments.len() - 1
+    }
+
+    fn step(&mut self, parent: Option<usize>, segment: usize) -> usize {
+        *self.step_index.entry((parent, segment)).or_insert_with(|| {
+            self.steps.push(Step { parent, segment });
+            self.steps.len() - 1
+        })
+    }
+}
@@ -0,0 +1,113 @@
+//! Evaluate JSONPath queries against raw JSON text, returning matched
+//! values as slices of the original text.
+//!
+//! Matched values are never re-serialized, so a proxy can splice them into
+//! a response exactly as they were received, with the same number formatting,
+//! escape sequences and whitespace.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::raw;
+//! use serde_json::value::RawValue;
+//!
+//! let json = r#"{"items": [{"id": 1.50, "name": "café"}, {"id": 2}]}"#;
+//! let value: &RawValue = serde_json::from_str(json).unwrap();
+//!
+//! let found = raw::find("$.items[0].*", value).unwrap();
+//! let fragments: Vec<&str> = found.iter().map(|(_, v)| v.get()).collect();
+//! assert_eq!(fragments, vec!["1.50", r#""café""#]);
+//! ```
+use std::collections::HashMap;
+
+use serde_json::{value::RawValue, Value};
+
+use crate::{errors::JSONPathError, jsonpath::ENV, node::PathElement, Query};
+
+/// Find values in `value` matching the JSONPath expression `expr`.
+///
+/// Returns `(normalized path, raw value)` pairs, in the order nodes were
+/// selected, borrowing from `value`.
+pub fn find<'a>(
+    expr: &str,
+    value: &'a RawValue,
+) -> Result<Vec<(String, &'a RawValue)>, JSONPathError> {
+    let query = Query::standard(expr)?;
+    let json: Value =
+        serde_json::from_str(value.get()).map_err(|err| JSONPathError::typ(err.to_string()))?;
+    let nodes = query.find(&json, &ENV);
+
+    let mut wanted: Vec<(usize, Vec<&PathElement>)> = nodes
+        .iter()
+        .map(|node| node.path_elements())
+        .enumerate()
+        .collect();
+    wanted.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+    let mut found = vec![None; nodes.len()];
+    locate(value, 0, &wanted, &mut found);
+
+    Ok(nodes
+        .iter()
+        .zip(found)
+        .filter_map(|(node, raw)| Some((node.path(), raw?)))
+        .collect())
+}
+
+/// Find the raw values at each of `wanted`, which are sorted paths that all
+/// start with the `depth` path elements leading to `value`.
+///
+/// Each array or object on the way to a wanted value is split into its
+/// children once, however many paths pass through it.
+fn locate<'a>(
+    value: &'a RawValue,
+    depth: usize,
+    wanted: &[(usize, Vec<&PathElement>)],
+    found: &mut [Option<&'a RawValue>],
+) {
+    // Shorter paths sort first, so paths ending here are at the start.
+    let here = wanted.partition_point(|(_, path)| path.len() == depth);
+    let (ending, below) = wanted.split_at(here);
+    for (i, _) in ending {
+        if let Some(slot) = found.get_mut(*i) {
+            *slot = Some(value);
+        }
+    }
+
+    if below.is_empty() {
+        return;
+    }
+
+    let Some(children) = Children::split(value) else {
+        return;
+    };
+
+    for group in below.chunk_by(|(_, a), (_, b)| a.get(depth) == b.get(depth)) {
+        let element = group.first().and_then(|(_, path)| path.get(depth));
+        if let Some(child) = element.and_then(|element| children.get(element)) {
+            locate(child, depth + 1, group, found);
+        }
+    }
+}
+
+/// The elements of a raw array, or the members of a raw object.
+enum Children<'a> {
+    Array(Vec<&'a RawValue>),
+    Object(HashMap<String, &'a RawValue>),
+}
+
+impl<'a> Children<'a> {
+    fn split(value: &'a RawValue) -> Option<Self> {
+        match value.get().trim_start().as_bytes().first()? {
+            b'[' => serde_json::from_str(value.get()).ok().map(Children::Array),
+            b'{' => serde_json::from_str(value.get()).ok().map(Children::Object),
+            _ => None,
+        }
+    }
+
+    fn get(&self, element: &PathElement) -> Option<&'a RawValue> {
+        match (self, element) {
+            (Children::Array(arr), PathElement::Index(i)) => arr.get(*i).copied(),
+            (Children::Object(obj), PathElement::Name(name)) => obj.get(name).copied(),
+            _ => None,
+        }
+    }
+}
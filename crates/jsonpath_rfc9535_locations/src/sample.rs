@@ -0,0 +1,388 @@
+use serde_json::{Map, Value};
+
+use crate::{
+    conslist::ConsList,
+    filter::{is_truthy, ComparisonOperator, FilterExpression, LogicalOperator},
+    jsonpath::ENV,
+    node::{Location, PathElement},
+    predicate::Examples,
+    segment::Segment,
+    selector::Selector,
+    Query,
+};
+
+// The longest array we'll try when looking for an index selected by an index
+// or slice selector.
+const MAX_ARRAY_LENGTH: i64 = 1000;
+
+// The most alternative assignments we'll try for a single filter expression.
+const MAX_ALTERNATIVES: usize = 64;
+
+// Values to assign to singular queries so that a filter expression is true.
+type Assignments<'q> = Vec<(&'q Query, Value)>;
+
+impl Query {
+    /// Synthesize a small JSON document for which this query produces at least
+    /// one node.
+    ///
+    /// Filter selectors are satisfied on a best effort basis, using literals
+    /// from comparison expressions to choose member values. Returns `None` if
+    /// no such document could be found, either because one does not exist,
+    /// like with `$[0:0]`, or because a filter is too complex.
+    pub fn sample_document(&self) -> Option<Value> {
+        let doc = sample(&self.segments)?;
+        if self.find(&doc, &ENV).is_empty() {
+            None
+        } else {
+            Some(doc)
+        }
+    }
+}
+
+impl FilterExpression {
+    /// Objects for which this expression is true, and objects for which it is
+    /// false, with `@` and `$` both referring to the object.
+    ///
+    /// Like [`Query::sample_document`], candidates are built from literals in
+    /// comparisons, then checked, so every example is correct but either list
+    /// might be empty.
+    pub(crate) fn examples(&self) -> Examples {
+        Examples {
+            satisfying: self.candidates(alternatives(self), true),
+            violating: self.candidates(counter_alternatives(self), false),
+        }
+    }
+
+    /// Distinct objects built from `alts` for which this expression is
+    /// `expected`.
+    fn candidates(&self, alts: Vec<Assignments<'_>>, expected: bool) -> Vec<Value> {
+        let mut values = Vec::new();
+        for assignments in alts {
+            let mut candidate = Value::Object(Map::new());
+            for (query, value) in assignments {
+                assign(&mut candidate, &query.segments, value);
+            }
+
+            let rv = self.evaluate_at(&ENV, &candidate, &candidate, Some(&Location::new()));
+            if is_truthy(rv) == expected && !values.contains(&candidate) {
+                values.push(candidate);
+            }
+        }
+        values
+    }
+}
+
+fn sample(segments: &[Segment]) -> Option<Value> {
+    match segments.split_first() {
+        None => Some(Value::Null),
+        Some((Segment::Eoi, rest)) => sample(rest),
+        // The descendant segment visits the current node too, so a document
+        // that satisfies a child segment satisfies a descendant segment.
+        Some((Segment::Child { selectors } | Segment::Recursive { selectors }, rest)) => {
+            let inner = sample(rest)?;
+            selectors
+                .iter()
+                .find_map(|selector| sample_selector(selector, inner.clone()))
+        }
+    }
+}
+
+fn sample_selector(selector: &Selector, inner: Value) -> Option<Value> {
+    match selector {
+        Selector::Name { name } => Some(Value::Object(Map::from_iter([(name.to_owned(), inner)]))),
+        Selector::Wild => Some(Value::Array(vec![inner])),
+        Selector::Index { index } => {
+            let len = index.checked_abs()?.checked_add(1)?.min(MAX_ARRAY_LENGTH);
+            sample_array(selector, inner, len)
+        }
+        Selector::Slice { start, stop, .. } => {
+            let len = start
+                .unwrap_or(0)
+                .unsigned_abs()
+                .max(stop.unwrap_or(0).unsigned_abs())
+                .saturating_add(1)
+                .min(MAX_ARRAY_LENGTH as u64) as i64;
+            sample_array(selector, inner, len)
+        }
+        Selector::Filter { expression, .. } => {
+            let mut candidates = vec![inner.clone()];
+            for assignments in alternatives(expression) {
+                let mut candidate = inner.clone();
+                for (query, value) in assignments {
+                    assign(&mut candidate, &query.segments, value);
+                }
+                candidates.push(candidate);
+            }
+
+            candidates.into_iter().find_map(|candidate| {
+                let arr = Value::Array(vec![candidate]);
+                if selector
+                    .resolve(&ENV, &arr, &arr, &ConsList::new())
+                    .is_empty()
+                {
+                    None
+                } else {
+                    Some(arr)
+                }
+            })
+        }
+    }
+}
+
+/// Find the shortest array of up to `max_len` elements for which `selector`
+/// selects something, and put `inner` at the first selected index.
+fn sample_array(selector: &Selector, inner: Value, max_len: i64) -> Option<Value> {
+    (1..=max_len).find_map(|len| {
+        let mut arr = Value::Array(vec![Value::Null; len as usize]);
+        let index = match selector
+            .resolve(&ENV, &arr, &arr, &ConsList::new())
+            .first()?
+            .location
+            .head()?
+        {
+            PathElement::Index(i) => *i,
+            PathElement::Name(_) => return None,
+        };
+        *arr.get_mut(index)? = inner.clone();
+        Some(arr)
+    })
+}
+
+/// Alternative sets of values for relative queries in `expr`, any of which
+/// might make `expr` true.
+fn alternatives(expr: &FilterExpression) -> Vec<Assignments<'_>> {
+    match expr {
+        FilterExpression::RelativeQuery { query } => vec![vec![(query, Value::Null)]],
+        FilterExpression::Logical {
+            left,
+            operator: LogicalOperator::Or,
+            right,
+        } => {
+            let mut alts = alternatives(left);
+            alts.extend(alternatives(right));
+            alts.truncate(MAX_ALTERNATIVES);
+            alts
+        }
+        FilterExpression::Logical {
+            left,
+            operator: LogicalOperator::And,
+            right,
+        } => product(alternatives(left), &alternatives(right)),
+        FilterExpression::Not { expression } => counter_alternatives(expression),
+        FilterExpression::Comparison {
+            left,
+            operator,
+            right,
+        } => match (left.as_ref(), right.as_ref()) {
+            (FilterExpression::RelativeQuery { query }, lit) if lit.is_literal() => {
+                vec![vec![(query, operand(operator, lit))]]
+            }
+            (lit, FilterExpression::RelativeQuery { query }) if lit.is_literal() => {
+                vec![vec![(query, operand(&flip(operator), lit))]]
+            }
+            (
+                FilterExpression::RelativeQuery { query: a },
+                FilterExpression::RelativeQuery { query: b },
+            ) => vec![vec![(a, Value::Null), (b, Value::Null)]],
+            _ => vec![vec![]],
+        },
+        // A regular expression pattern often matches itself.
+        FilterExpression::Function { name, args } if name == "match" || name == "search" => {
+            match args.as_slice() {
+                [FilterExpression::RelativeQuery { query }, FilterExpression::String { value }] => {
+                    vec![vec![(query, Value::String(value.to_owned()))]]
+                }
+                _ => vec![vec![]],
+            }
+        }
+        _ => vec![vec![]],
+    }
+}
+
+/// Alternative sets of values for relative queries in `expr`, any of which
+/// might make `expr` false. Queries without a value are left out, so an empty
+/// set means a document without any of the queried members.
+fn counter_alternatives(expr: &FilterExpression) -> Vec<Assignments<'_>> {
+    match expr {
+        FilterExpression::RelativeQuery { .. } => vec![vec![]],
+        FilterExpression::Not { expression } => alternatives(expression),
+        FilterExpression::Logical {
+            left,
+            operator: LogicalOperator::Or,
+            right,
+        } => product(counter_alternatives(left), &counter_alternatives(right)),
+        // Make one side false at a time, so each alternative is a near miss.
+        FilterExpression::Logical {
+            left,
+            operator: LogicalOperator::And,
+            right,
+        } => {
+            let mut alts = product(counter_alternatives(left), &alternatives(right));
+            alts.extend(product(alternatives(left), &counter_alternatives(right)));
+            alts.truncate(MAX_ALTERNATIVES);
+            alts
+        }
+        FilterExpression::Comparison {
+            left,
+            operator,
+            right,
+        } => match (left.as_ref(), right.as_ref()) {
+            (FilterExpression::RelativeQuery { query }, lit) if lit.is_literal() => {
+                vec![vec![(query, operand(&negate(operator), lit))], vec![]]
+            }
+            (lit, FilterExpression::RelativeQuery { query }) if lit.is_literal() => {
+                vec![
+                    vec![(query, operand(&flip(&negate(operator)), lit))],
+                    vec![],
+                ]
+            }
+            (
+                FilterExpression::RelativeQuery { query: a },
+                FilterExpression::RelativeQuery { query: b },
+            ) => vec![
+                vec![(a, Value::Null), (b, Value::Bool(true))],
+                vec![(a, Value::Null), (b, Value::Null)],
+            ],
+            _ => vec![vec![]],
+        },
+        FilterExpression::Function { name, args } if name == "match" || name == "search" => {
+            match args.as_slice() {
+                [FilterExpression::RelativeQuery { query }, FilterExpression::String { .. }] => {
+                    vec![
+                        vec![(query, Value::String(String::new()))],
+                        vec![(query, Value::Null)],
+                    ]
+                }
+                _ => vec![vec![]],
+            }
+        }
+        _ => vec![vec![]],
+    }
+}
+
+/// Every combination of one alternative from `left` and one from `right`.
+fn product<'q>(left: Vec<Assignments<'q>>, right: &[Assignments<'q>]) -> Vec<Assignments<'q>> {
+    left.into_iter()
+        .flat_map(|l| {
+            right.iter().map(move |r| {
+                let mut both = l.clone();
+                both.extend(r.iter().cloned());
+                both
+            })
+        })
+        .take(MAX_ALTERNATIVES)
+        .collect()
+}
+
+fn negate(operator: &ComparisonOperator) -> ComparisonOperator {
+    match operator {
+        ComparisonOperator::Eq => ComparisonOperator::Ne,
+        ComparisonOperator::Ne => ComparisonOperator::Eq,
+        ComparisonOperator::Ge => ComparisonOperator::Lt,
+        ComparisonOperator::Gt => ComparisonOperator::Le,
+        ComparisonOperator::Le => ComparisonOperator::Gt,
+        ComparisonOperator::Lt => ComparisonOperator::Ge,
+    }
+}
+
+fn flip(operator: &ComparisonOperator) -> ComparisonOperator {
+    match operator {
+        ComparisonOperator::Eq => ComparisonOperator::Eq,
+        ComparisonOperator::Ne => ComparisonOperator::Ne,
+        ComparisonOperator::Ge => ComparisonOperator::Le,
+        ComparisonOperator::Gt => ComparisonOperator::Lt,
+        ComparisonOperator::Le => ComparisonOperator::Ge,
+        ComparisonOperator::Lt => ComparisonOperator::Gt,
+    }
+}
+
+/// A value `v` for which `v <operator> literal` is likely to be true.
+fn operand(operator: &ComparisonOperator, literal: &FilterExpression) -> Value {
+    let value = match literal {
+        FilterExpression::True => Value::Bool(true),
+        FilterExpression::False => Value::Bool(false),
+        FilterExpression::String { value } => Value::String(value.to_owned()),
+        FilterExpression::Int { value } => Value::from(*value),
+        FilterExpression::UInt { value } => Value::from(*value),
+        FilterExpression::Float { value } => Value::from(*value),
+        _ => Value::Null,
+    };
+
+    match operator {
+        ComparisonOperator::Eq | ComparisonOperator::Ge | ComparisonOperator::Le => value,
+        ComparisonOperator::Gt => greater(literal).unwrap_or(value),
+        ComparisonOperator::Lt => lesser(literal).unwrap_or(value),
+        ComparisonOperator::Ne => match literal {
+            FilterExpression::True => Value::Bool(false),
+            FilterExpression::False | FilterExpression::Null => Value::Bool(true),
+            _ => greater(literal).unwrap_or(value),
+        },
+    }
+}
+
+fn greater(literal: &FilterExpression) -> Option<Value> {
+    match literal {
+        FilterExpression::Int { value } => Some(Value::from(value.checked_add(1)?)),
+        FilterExpression::UInt { value } => Some(Value::from(value.checked_add(1)?)),
+        FilterExpression::Float { value } => Some(Value::from(value + 1.0)),
+        FilterExpression::String { value } => Some(Value::String(format!("{}a", value))),
+        _ => None,
+    }
+}
+
+fn lesser(literal: &FilterExpression) -> Option<Value> {
+    match literal {
+        FilterExpression::Int { value } => Some(Value::from(value.checked_sub(1)?)),
+        FilterExpression::UInt { value } => Some(Value::from(value.checked_sub(1)?)),
+        FilterExpression::Float { value } => Some(Value::from(value - 1.0)),
+        FilterExpression::String { value } if !value.is_empty() => {
+            Some(Value::String(String::new()))
+        }
+        _ => None,
+    }
+}
+
+/// Set the value at the location described by singular query `segments`,
+/// creating objects and arrays as needed. Non-singular segments are ignored.
+fn assign(target: &mut Value, segments: &[Segment], value: Value) {
+    match segments.split_first() {
+        None => *target = value,
+        Some((Segment::Eoi, rest)) => assign(target, rest, value),
+        Some((Segment::Child { selectors }, rest)) => match selectors.as_slice() {
+            [Selector::Name { name }] => {
+                if !target.is_object() {
+                    *target = Value::Object(Map::new());
+                }
+                if let Value::Object(obj) = target {
+                    assign(obj.entry(name).or_insert(Value::Null), rest, value);
+                }
+            }
+            [Selector::Index { index }] => {
+                let Some(len) = index.checked_abs().map(|i| i + i64::from(*index >= 0)) else {
+                    return;
+                };
+                if len > MAX_ARRAY_LENGTH {
+                    return;
+                }
+                if !target.is_array() {
+                    *target = Value::Array(Vec::new());
+                }
+                if let Value::Array(arr) = target {
+                    if arr.len() < len as usize {
+                        arr.resize(len as usize, Value::Null);
+                    }
+                    let i = if *index >= 0 {
+                        *index as usize
+                    } else {
+                        arr.len() - index.unsigned_abs() as usize
+                    };
+                    if let Some(item) = arr.get_mut(i) {
+                        assign(item, rest, value);
+                    }
+                }
+            }
+            _ => (),
+        },
+        Some((Segment::Recursive { .. }, _)) => (),
+    }
+}
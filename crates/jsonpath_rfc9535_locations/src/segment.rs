@@ -1,14 +1,14 @@
-use std::fmt;
-
-use serde_json::Value;
+use std::{cell::OnceCell, fmt};
 
 use crate::{
     env::Environment,
-    node::{Location, NodeList, PathElement},
-    selector::Selector,
+    function::Functions,
+    json_like::JsonLike,
+    node::{Location, Node, NodeList},
+    selector::{child_steps, children, Selector, Step},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Segment {
     Child { selectors: Vec<Selector> },
     Recursive { selectors: Vec<Selector> },
@@ -16,12 +16,12 @@ pub enum Segment {
 }
 
 impl Segment {
-    pub fn resolve<'v>(
+    pub fn resolve<'v, J: JsonLike, F: Functions<J>>(
         &self,
-        nodes: NodeList<'v>,
-        env: &'static Environment,
-        root: &'v Value,
-    ) -> NodeList<'v> {
+        nodes: NodeList<'v, J>,
+        env: &'static Environment<F>,
+        root: &'v J,
+    ) -> NodeList<'v, J> {
         match self {
             Segment::Child { selectors } => nodes
                 .into_iter()
@@ -32,66 +32,230 @@ impl Segment {
                 })
                 .flatten()
                 .collect(),
-            Segment::Recursive { selectors } => nodes
-                .into_iter()
-                .flat_map(move |node| self.visit(env, node.value, selectors, root, node.location))
-                .collect(),
-            Segment::Eoi {} => nodes,
+            Segment::Recursive { selectors } => {
+                let mut descendants = NodeList::new();
+                for node in nodes.into_iter() {
+                    let mut visited = Visited::new(node.location);
+                    self.visit(env, node.value, selectors, root, 0, &mut visited);
+                    descendants.extend(visited.into_nodes());
+                }
+                descendants
+            }
+            Segment::Eoi => nodes,
         }
     }
 
-    fn visit<'v>(
-        &self,
-        env: &'static Environment,
-        value: &'v Value,
-        selectors: &Vec<Selector>,
-        root: &'v Value,
-        location: Location,
-    ) -> NodeList<'v> {
-        let mut nodes: NodeList = selectors
-            .iter()
-            .flat_map(|s| s.resolve(env, value, root, &location))
-            .collect();
-
-        nodes.append(&mut self.descend(env, value, selectors, root, &location));
-        nodes
+    /// Add nodes selected by `selectors` from `value` and its descendants
+    /// to `visited`. `link` is the path to `value` in `visited.paths`.
+    fn visit<'a, 'v: 'a, J: JsonLike, F: Functions<J>>(
+        &'a self,
+        env: &'static Environment<F>,
+        value: &'v J,
+        selectors: &'a [Selector],
+        root: &'v J,
+        link: usize,
+        visited: &mut Visited<'a, 'v, J>,
+    ) {
+        // Selectors only select children, so there's nothing to do for a
+        // value without any.
+        if has_children(value) {
+            for selector in selectors {
+                if let Selector::Filter {
+                    reads_location: true,
+                    ..
+                } = selector
+                {
+                    let location = visited.paths.location(link);
+                    visited.selected.extend(
+                        selector
+                            .resolve(env, value, root, &location)
+                            .into_iter()
+                            .map(Selected::Located),
+                    );
+                } else {
+                    visited
+                        .selected
+                        .extend(selector.steps(env, value, root).map(|(step, value)| {
+                            Selected::Linked {
+                                value,
+                                parent: link,
+                                step,
+                            }
+                        }));
+                }
+            }
+        }
+
+        for (step, child) in child_steps(value) {
+            let (mark, count) = (visited.paths.len(), visited.selected.len());
+            let child_link = visited.paths.push(link, step);
+            self.visit(env, child, selectors, root, child_link, visited);
+
+            // Nothing below `child` was selected, so its paths aren't needed.
+            if visited.selected.len() == count {
+                visited.paths.truncate(mark);
+            }
+        }
     }
+}
 
-    fn descend<'v>(
-        &self,
-        env: &'static Environment,
-        value: &'v Value,
-        selectors: &Vec<Selector>,
-        root: &'v Value,
-        location: &Location,
-    ) -> NodeList<'v> {
-        match value {
-            Value::Array(arr) => arr
-                .iter()
-                .enumerate()
-                .flat_map(|(i, v)| {
-                    self.visit(
-                        env,
-                        v,
-                        selectors,
-                        root,
-                        location.append(PathElement::Index(i)),
-                    )
-                })
-                .collect(),
-            Value::Object(obj) => obj
-                .iter()
-                .flat_map(|(k, v)| {
-                    self.visit(
-                        env,
-                        v,
-                        selectors,
-                        root,
-                        location.append(PathElement::Name(k.to_owned())),
-                    )
-                })
-                .collect(),
-            _ => vec![],
+pub(crate) fn has_children<J: JsonLike>(value: &J) -> bool {
+    if let Some(arr) = value.as_array() {
+        !arr.is_empty()
+    } else {
+        value
+            .members()
+            .is_some_and(|mut members| members.next().is_some())
+    }
+}
+
+/// The nodes selected by a descendant segment from one value and its
+/// descendants, in the order they were selected.
+struct Visited<'a, 'v, J> {
+    paths: PathArena<'a>,
+    selected: Vec<Selected<'a, 'v, J>>,
+}
+
+impl<'a, 'v, J> Visited<'a, 'v, J> {
+    fn new(location: Location) -> Self {
+        Visited {
+            paths: PathArena::new(location),
+            selected: Vec::new(),
+        }
+    }
+
+    fn into_nodes(self) -> impl Iterator<Item = Node<'v, J>> + 'a
+    where
+        'v: 'a,
+        J: 'a,
+    {
+        let paths = self.paths;
+        self.selected
+            .into_iter()
+            .map(move |node| node.locate(&paths))
+    }
+}
+
+/// The paths to the values a descendant segment is visiting, each stored as
+/// a step from its parent's path.
+///
+/// Locations are built from these paths only for selected nodes, and only
+/// once for each value, so selected nodes with the same parent share its
+/// location. Most values visited by a descendant segment are never selected,
+/// so this avoids allocating a location, and copying a member name, for
+/// each of them.
+struct PathArena<'a> {
+    links: Vec<Link<'a>>,
+}
+
+struct Link<'a> {
+    parent: usize,
+    step: Option<Step<'a>>,
+    location: OnceCell<Location>,
+}
+
+impl<'a> PathArena<'a> {
+    /// An arena with one path, at index 0, to the value the segment starts
+    /// from.
+    fn new(location: Location) -> Self {
+        PathArena {
+            links: vec![Link {
+                parent: 0,
+                step: None,
+                location: OnceCell::from(location),
+            }],
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    fn truncate(&mut self, len: usize) {
+        self.links.truncate(len);
+    }
+
+    /// Add the path to the child of `parent` at `step`, and return its index.
+    fn push(&mut self, parent: usize, step: Step<'a>) -> usize {
+        self.links.push(Link {
+            parent,
+            step: Some(step),
+            location: OnceCell::new(),
+        });
+        self.links.len() - 1
+    }
+
+    /// The location at the end of path `link`.
+    fn location(&self, link: usize) -> Location {
+        let Some(Link {
+            parent,
+            step,
+            location,
+        }) = self.links.get(link)
+        else {
+            return Location::new();
+        };
+
+        location
+            .get_or_init(|| match step {
+                Some(step) => self.location(*parent).append(step.element()),
+                None => Location::new(),
+            })
+            .clone()
+    }
+}
+
+/// A node selected by a descendant segment.
+enum Selected<'a, 'v, J> {
+    /// A node whose location is a step from a path in a [`PathArena`].
+    Linked {
+        value: &'v J,
+        parent: usize,
+        step: Step<'a>,
+    },
+    /// A node selected by a filter that reads locations, which has one
+    /// already.
+    Located(Node<'v, J>),
+}
+
+impl<'v, J> Selected<'_, 'v, J> {
+    fn locate(self, paths: &PathArena<'_>) -> Node<'v, J> {
+        match self {
+            Selected::Linked {
+                value,
+                parent,
+                step,
+            } => Node {
+                value,
+                location: paths.location(parent).append(step.element()),
+            },
+            Selected::Located(node) => node,
+        }
+    }
+}
+
+impl Segment {
+    /// Like [`Segment::resolve`] for a single value, but lazily yields
+    /// selected values without building their locations.
+    pub fn values<'a, 'v: 'a, J: JsonLike, F: Functions<J>>(
+        &'a self,
+        env: &'static Environment<F>,
+        value: &'v J,
+        root: &'v J,
+    ) -> Box<dyn Iterator<Item = &'v J> + 'a> {
+        match self {
+            Segment::Child { selectors } => Box::new(
+                selectors
+                    .iter()
+                    .flat_map(move |s| s.values(env, value, root)),
+            ),
+            Segment::Recursive { selectors } => Box::new(
+                selectors
+                    .iter()
+                    .flat_map(move |s| s.values(env, value, root))
+                    .chain(children(value).flat_map(move |child| self.values(env, child, root))),
+            ),
+            Segment::Eoi => Box::new(std::iter::once(value)),
         }
     }
 }
@@ -1,17 +1,16 @@
-use std::{
-    cmp,
-    fmt::{self, Write},
-};
+use std::fmt::{self, Write};
 
-use serde_json::Value;
+use jsonpath_rfc9535::resolve_index;
 
 use crate::{
     env::Environment,
     filter::{is_truthy_ref, FilterExpression},
-    node::{Location, Node, NodeList},
+    function::Functions,
+    json_like::JsonLike,
+    node::{Location, Node, NodeList, PathElement},
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Selector {
     Name {
         name: String,
@@ -27,27 +26,30 @@ pub enum Selector {
     Wild,
     Filter {
         expression: Box<FilterExpression>,
+        /// `true` if the expression calls a function that sets
+        /// [`FunctionSignature::reads_location`](crate::function::FunctionSignature::reads_location),
+        /// so it's evaluated with the location of each node it tests.
+        reads_location: bool,
     },
 }
 
 impl Selector {
-    pub fn resolve<'v>(
+    pub fn resolve<'v, J: JsonLike, F: Functions<J>>(
         &self,
-        env: &'static Environment,
-        value: &'v Value,
-        root: &'v Value,
+        env: &'static Environment<F>,
+        value: &'v J,
+        root: &'v J,
         location: &Location,
-    ) -> NodeList<'v> {
+    ) -> NodeList<'v, J> {
         match self {
             Selector::Name { name } => value
-                .as_object()
-                .and_then(|m| m.get_key_value(name))
-                .map(|(k, v)| Node::new_object_member(v, location, k.to_owned()))
+                .member(name)
+                .map(|v| Node::new_object_member(v, location, name.to_owned()))
                 .into_iter()
                 .collect(),
             Selector::Index { index } => value
                 .as_array()
-                .and_then(|array| Some((norm_index(*index, array.len())?, array)))
+                .and_then(|array| Some((resolve_index(*index, array.len())?, array)))
                 .and_then(|(i, array)| Some((i, array.get(i)?)))
                 .map(|(i, v)| Node::new_array_element(v, location, i))
                 .into_iter()
@@ -56,38 +58,168 @@ impl Selector {
                 .as_array()
                 .and_then(|array| slice(array, location, *start, *stop, *step))
                 .unwrap_or_default(),
-            Selector::Wild {} => match value {
-                Value::Array(arr) => arr
-                    .iter()
-                    .enumerate()
-                    .map(|(i, v)| Node::new_array_element(v, location, i))
-                    .collect(),
-                Value::Object(obj) => obj
-                    .iter()
-                    .map(|(k, v)| Node::new_object_member(v, location, k.to_owned()))
-                    .collect(),
-                _ => Vec::new(),
-            },
-            Selector::Filter { expression } => match value {
-                Value::Array(arr) => arr
-                    .iter()
-                    .enumerate()
-                    .map(|(i, v)| (i, v, expression.evaluate(env, root, v)))
-                    .filter(|(_, _, r)| is_truthy_ref(r))
-                    .map(|(i, v, _)| Node::new_array_element(v, location, i))
-                    .collect(),
-                Value::Object(obj) => obj
-                    .iter()
-                    .map(|(k, v)| (k, v, expression.evaluate(env, root, v)))
-                    .filter(|(_, _, r)| is_truthy_ref(r))
-                    .map(|(k, v, _)| Node::new_object_member(v, location, k.to_owned()))
-                    .collect(),
-                _ => Vec::new(),
+            Selector::Wild => {
+                if let Some(arr) = value.as_array() {
+                    arr.iter()
+                        .enumerate()
+                        .map(|(i, v)| Node::new_array_element(v, location, i))
+                        .collect()
+                } else if let Some(members) = value.members() {
+                    members
+                        .map(|(k, v)| Node::new_object_member(v, location, k.to_owned()))
+                        .collect()
+                } else {
+                    NodeList::new()
+                }
+            }
+            Selector::Filter {
+                expression,
+                reads_location: true,
+            } => {
+                let test = |node: &Node<'v, J>| {
+                    is_truthy_ref(&expression.evaluate_at(
+                        env,
+                        root,
+                        node.value,
+                        Some(&node.location),
+                    ))
+                };
+
+                if let Some(arr) = value.as_array() {
+                    arr.iter()
+                        .enumerate()
+                        .map(|(i, v)| Node::new_array_element(v, location, i))
+                        .filter(test)
+                        .collect()
+                } else if let Some(members) = value.members() {
+                    members
+                        .map(|(k, v)| Node::new_object_member(v, location, k.to_owned()))
+                        .filter(test)
+                        .collect()
+                } else {
+                    NodeList::new()
+                }
+            }
+            Selector::Filter { expression, .. } => {
+                let test = |v: &'v J| is_truthy_ref(&expression.evaluate(env, root, v));
+
+                if let Some(arr) = value.as_array() {
+                    arr.iter()
+                        .enumerate()
+                        .filter(|(_, v)| test(v))
+                        .map(|(i, v)| Node::new_array_element(v, location, i))
+                        .collect()
+                } else if let Some(members) = value.members() {
+                    members
+                        .filter(|(_, v)| test(v))
+                        .map(|(k, v)| Node::new_object_member(v, location, k.to_owned()))
+                        .collect()
+                } else {
+                    NodeList::new()
+                }
+            }
+        }
+    }
+}
+
+impl Selector {
+    /// Like [`Selector::resolve`], but lazily yields selected values without
+    /// building their locations.
+    pub fn values<'a, 'v: 'a, J: JsonLike, F: Functions<J>>(
+        &'a self,
+        env: &'static Environment<F>,
+        value: &'v J,
+        root: &'v J,
+    ) -> Box<dyn Iterator<Item = &'v J> + 'a> {
+        Box::new(self.steps(env, value, root).map(|(_, v)| v))
+    }
+
+    /// Like [`Selector::values`], but with the step from `value` to each
+    /// selected value.
+    ///
+    /// Filters are evaluated without locations, so this shouldn't be used
+    /// for a filter that reads them.
+    pub(crate) fn steps<'a, 'v: 'a, J: JsonLike, F: Functions<J>>(
+        &'a self,
+        env: &'static Environment<F>,
+        value: &'v J,
+        root: &'v J,
+    ) -> Box<dyn Iterator<Item = (Step<'a>, &'v J)> + 'a> {
+        match self {
+            Selector::Name { name } => Box::new(
+                value
+                    .member(name)
+                    .map(|v| (Step::Name(name), v))
+                    .into_iter(),
+            ),
+            Selector::Index { index } => Box::new(
+                value
+                    .as_array()
+                    .and_then(|array| {
+                        let i = resolve_index(*index, array.len())?;
+                        Some((Step::Index(i), array.get(i)?))
+                    })
+                    .into_iter(),
+            ),
+            Selector::Slice { start, stop, step } => match value.as_array() {
+                Some(array) => Box::new(
+                    slice_indices(array.len(), *start, *stop, *step)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|i| Some((Step::Index(i), array.get(i)?))),
+                ),
+                None => Box::new(std::iter::empty()),
             },
+            Selector::Wild => child_steps(value),
+            Selector::Filter { expression, .. } => Box::new(
+                child_steps(value)
+                    .filter(move |(_, v)| is_truthy_ref(&expression.evaluate(env, root, v))),
+            ),
         }
     }
 }
 
+/// A step from a value to one of its children, borrowing the member name
+/// from the query or the document.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Step<'a> {
+    Index(usize),
+    Name(&'a str),
+}
+
+impl Step<'_> {
+    pub(crate) fn element(self) -> PathElement {
+        match self {
+            Step::Index(i) => PathElement::Index(i),
+            Step::Name(name) => PathElement::Name(name.to_owned()),
+        }
+    }
+}
+
+/// The steps to, and values of, an array's elements or an object's members.
+pub(crate) fn child_steps<'a, 'v: 'a, J: JsonLike>(
+    value: &'v J,
+) -> Box<dyn Iterator<Item = (Step<'a>, &'v J)> + 'a> {
+    if let Some(arr) = value.as_array() {
+        Box::new(arr.iter().enumerate().map(|(i, v)| (Step::Index(i), v)))
+    } else if let Some(members) = value.members() {
+        Box::new(members.map(|(k, v)| (Step::Name(k), v)))
+    } else {
+        Box::new(std::iter::empty())
+    }
+}
+
+/// The values of an array's elements or an object's members.
+pub fn children<'v, J: JsonLike>(value: &'v J) -> Box<dyn Iterator<Item = &'v J> + 'v> {
+    if let Some(arr) = value.as_array() {
+        Box::new(arr.iter())
+    } else if let Some(members) = value.members() {
+        Box::new(members.map(|(_, v)| v))
+    } else {
+        Box::new(std::iter::empty())
+    }
+}
+
 impl fmt::Display for Selector {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -110,50 +242,59 @@ impl fmt::Display for Selector {
                         .unwrap_or(String::from("1")),
                 )
             }
-            Selector::Wild { .. } => f.write_char('*'),
+            Selector::Wild => f.write_char('*'),
             Selector::Filter { expression, .. } => write!(f, "?{expression}"),
         }
     }
 }
 
-fn norm_index(index: i64, length: usize) -> Option<usize> {
-    if index < 0 {
-        index
-            .checked_abs()
-            .and_then(|i| usize::try_from(i).ok())
-            .and_then(|i| length.checked_sub(i))
-    } else {
-        usize::try_from(index).ok()
-    }
+fn slice<'v, J>(
+    array: &'v [J],
+    location: &Location,
+    start: Option<i64>,
+    stop: Option<i64>,
+    step: Option<i64>,
+) -> Option<NodeList<'v, J>> {
+    Some(
+        slice_indices(array.len(), start, stop, step)?
+            .filter_map(|i| Some(Node::new_array_element(array.get(i)?, location, i)))
+            .collect(),
+    )
 }
 
-fn slice<'v>(
-    array: &'v [Value],
-    location: &Location,
+/// The indices selected by a slice selector from an array of length `len`,
+/// in order.
+pub(crate) fn slice_indices(
+    len: usize,
     start: Option<i64>,
     stop: Option<i64>,
     step: Option<i64>,
-) -> Option<NodeList<'v>> {
-    let len = array.len() as i128;
+) -> Option<impl Iterator<Item = usize>> {
+    let len = len as i128;
 
     if len == 0 {
         return None;
     }
 
-    let step = step.unwrap_or(1);
+    let step = step.unwrap_or(1) as i128;
 
     if step == 0 {
         return None;
     }
 
-    let n_start = match start {
-        Some(i) => {
-            if i < 0 {
-                cmp::max(len + i as i128, 0)
-            } else {
-                cmp::min(i as i128, len - 1)
-            }
+    // Bounds are normalized, then clamped to `0..=len` for forward slices and
+    // `-1..=len - 1` for backward ones.
+    let bound = |i: i64| {
+        let i = if i < 0 { len + i as i128 } else { i as i128 };
+        if step > 0 {
+            i.clamp(0, len)
+        } else {
+            i.clamp(-1, len - 1)
         }
+    };
+
+    let n_start = match start {
+        Some(i) => bound(i),
         None => {
             if step < 0 {
                 len - 1
@@ -164,13 +305,7 @@ fn slice<'v>(
     };
 
     let n_stop = match stop {
-        Some(i) => {
-            if i < 0 {
-                cmp::max(len + i as i128, -1)
-            } else {
-                cmp::min(i as i128, len)
-            }
-        }
+        Some(i) => bound(i),
         None => {
             if step < 0 {
                 -1
@@ -180,31 +315,12 @@ fn slice<'v>(
         }
     };
 
-    let mut slice: NodeList = Vec::new();
-
-    if step > 0 {
-        let step = usize::try_from(step).ok()?;
-        for i in (n_start..n_stop).step_by(step) {
-            let index = usize::try_from(i).ok()?;
-            slice.push(Node::new_array_element(
-                array.get(index).unwrap(),
-                location,
-                index,
-            ));
-        }
+    let distance = if step > 0 {
+        n_stop - n_start
     } else {
-        let step = step as i128;
-        let mut i = n_start;
-        while i > n_stop {
-            let index = usize::try_from(i).ok()?;
-            slice.push(Node::new_array_element(
-                array.get(index).unwrap(),
-                location,
-                index,
-            ));
-            i += step;
-        }
-    }
+        n_start - n_stop
+    };
+    let count = (distance.max(0) + step.abs() - 1) / step.abs();
 
-    Some(slice)
+    Some((0..count).map(move |k| (n_start + k * step) as usize))
 }
@@ -0,0 +1,235 @@
+//! Split query evaluation into work items for a caller's own thread pool.
+//!
+//! [`Query::find_sharded`] evaluates the first few segments of a query on
+//! the calling thread, until there are at least as many nodes as requested
+//! shards, then splits those nodes into contiguous [`Shard`]s. Each shard
+//! evaluates the rest of the query against its nodes, and can be sent to
+//! another thread. Joining the shards' results in order, with
+//! [`NodeList::from_shards`], gives the same nodes, in the same order, as
+//! [`Query::find`].
+//!
+//! A [`NodeList`] can't be sent between threads, because nodes share parts
+//! of their locations, so shards take and return [`ShardNodes`] instead.
+//!
+//! ```
+//! use std::{num::NonZeroUsize, thread};
+//!
+//! use jsonpath_rfc9535_locations::{node::NodeList, Query, ENV};
+//! use serde_json::json;
+//!
+//! let value = json!([{"a": 1}, {"a": 5}, {"a": 3}, {"a": 7}]);
+//! let query = Query::standard("$[?@.a > 2].a").unwrap();
+//!
+//! let nodes = thread::scope(|scope| {
+//!     let workers: Vec<_> = query
+//!         .find_sharded(&value, NonZeroUsize::new(2).unwrap(), &ENV)
+//!         .into_iter()
+//!         .map(|shard| scope.spawn(move || shard.find()))
+//!         .collect();
+//!     NodeList::from_shards(workers.into_iter().map(|worker| worker.join().unwrap()))
+//! });
+//!
+//! assert_eq!(nodes.paths(), vec!["$[1]['a']", "$[2]['a']", "$[3]['a']"]);
+//! ```
+//!
+//! A segment with a single filter selector, like `[?@.a > 2]` above, is
+//! applied by each shard to its own share of the array elements or object
+//! members being filtered, so filtering a large top-level array is spread
+//! across shards. Other segments before the split, like `$.items` in
+//! `$.items[*].price`, are evaluated on the calling thread.
+
+use std::num::NonZeroUsize;
+
+use serde_json::Value;
+
+use crate::{
+    conslist::ConsList,
+    env::Environment,
+    filter::{is_truthy_ref, FilterExpression},
+    function::Functions,
+    node::{Node, NodeList, PathElement},
+    segment::Segment,
+    selector::Selector,
+    Query,
+};
+
+/// Nodes with their locations held as paths, so they can be sent between
+/// threads, unlike a [`NodeList`].
+#[derive(Debug, Clone, Default)]
+pub struct ShardNodes<'v>(Vec<(&'v Value, Vec<PathElement>)>);
+
+impl<'v> ShardNodes<'v> {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_node_list(self) -> NodeList<'v> {
+        self.0
+            .into_iter()
+            .map(|(value, path)| Node {
+                value,
+                location: ConsList::from_iter(path),
+            })
+            .collect()
+    }
+}
+
+impl<'v> From<NodeList<'v>> for ShardNodes<'v> {
+    fn from(nodes: NodeList<'v>) -> Self {
+        ShardNodes(
+            nodes
+                .iter()
+                .map(|node| {
+                    let path = node.path_elements().into_iter().cloned().collect();
+                    (node.value, path)
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A share of the work of evaluating a query, from [`Query::find_sharded`].
+///
+/// A shard can be sent to another thread if the environment's function
+/// register can be shared between threads.
+pub struct Shard<'q, 'v, F: Functions> {
+    env: &'static Environment<F>,
+    root: &'v Value,
+    nodes: ShardNodes<'v>,
+    filter: Option<&'q FilterExpression>,
+    segments: &'q [Segment],
+}
+
+impl<'q: 'v, 'v, F: Functions> Shard<'q, 'v, F> {
+    /// The number of nodes this shard starts from.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Evaluate the rest of the query against this shard's nodes.
+    pub fn find(self) -> ShardNodes<'v> {
+        let mut nodes = self.nodes.into_node_list();
+        if let Some(expression) = self.filter {
+            nodes.retain(|node| {
+                is_truthy_ref(&expression.evaluate_at(
+                    self.env,
+                    self.root,
+                    node.value,
+                    Some(&node.location),
+                ))
+            });
+        }
+
+        self.segments
+            .iter()
+            .fold(nodes, |nodes, segment| {
+                segment.resolve(nodes, self.env, self.root)
+            })
+            .into()
+    }
+}
+
+impl Query {
+    /// Split evaluating this query against `value` into `n` shards that can
+    /// be evaluated independently, in any order. See [`shard`].
+    ///
+    /// Exactly `n` shards are returned, some of which might be empty if
+    /// there are fewer than `n` nodes to split.
+    ///
+    /// [`shard`]: crate::shard
+    pub fn find_sharded<'q: 'v, 'v, F: Functions>(
+        &'q self,
+        value: &'v Value,
+        n: NonZeroUsize,
+        env: &'static Environment<F>,
+    ) -> Vec<Shard<'q, 'v, F>> {
+        let mut nodes = NodeList::from(vec![Node {
+            value,
+            location: ConsList::new(),
+        }]);
+        let mut filter: Option<&FilterExpression> = None;
+        let mut segments = self.segments.as_slice();
+
+        while nodes.len() < n.get() {
+            if let Some(expression) = filter.take() {
+                nodes.retain(|node| {
+                    is_truthy_ref(&expression.evaluate_at(
+                        env,
+                        value,
+                        node.value,
+                        Some(&node.location),
+                    ))
+                });
+                continue;
+            }
+
+            let Some((segment, rest)) = segments.split_first() else {
+                break;
+            };
+            segments = rest;
+
+            // Defer filtering until the filtered children are split between
+            // shards, so the filter is evaluated in parallel.
+            nodes = match segment {
+                Segment::Child { selectors } => match selectors.as_slice() {
+                    [Selector::Filter { expression, .. }] => {
+                        filter = Some(expression);
+                        nodes
+                            .iter()
+                            .flat_map(|node| {
+                                Selector::Wild.resolve(env, node.value, value, &node.location)
+                            })
+                            .collect()
+                    }
+                    _ => segment.resolve(nodes, env, value),
+                },
+                _ => segment.resolve(nodes, env, value),
+            };
+        }
+
+        nodes
+            .shard(n)
+            .into_iter()
+            .map(|nodes| Shard {
+                env,
+                root: value,
+                nodes,
+                filter,
+                segments,
+            })
+            .collect()
+    }
+}
+
+impl<'v> NodeList<'v> {
+    /// Split this list into `n` contiguous parts, in order, with lengths
+    /// that differ by at most one, that can be sent to other threads. Some
+    /// parts are empty if this list has fewer than `n` nodes.
+    pub fn shard(self, n: NonZeroUsize) -> Vec<ShardNodes<'v>> {
+        let n = n.get();
+        let (size, extra) = (self.len() / n, self.len() % n);
+        let mut nodes = self.into_iter();
+        (0..n)
+            .map(|i| {
+                let len = if i < extra { size + 1 } else { size };
+                NodeList::from_iter(nodes.by_ref().take(len)).into()
+            })
+            .collect()
+    }
+
+    /// Join parts from [`NodeList::shard`] or [`Shard::find`], in order.
+    pub fn from_shards<I: IntoIterator<Item = ShardNodes<'v>>>(shards: I) -> Self {
+        shards
+            .into_iter()
+            .flat_map(ShardNodes::into_node_list)
+            .collect()
+    }
+}
@@ -1,11 +1,20 @@
-use std::{num::NonZeroUsize, sync::Mutex};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    num::NonZeroUsize,
+    sync::{Mutex, PoisonError},
+};
 
 use lru::LruCache;
 use regex::Regex;
 
 use crate::{
     filter::FilterExpressionResult,
-    function::{ExpressionType, FunctionExtension, FunctionSignature},
+    function::{
+        standard_functions, ExpressionType, FunctionContext, FunctionExtension, FunctionRegister,
+        FunctionSignature, Functions,
+    },
+    json_like::JsonLike,
 };
 
 pub struct Count;
@@ -14,6 +23,16 @@ impl Count {
     pub fn new() -> Self {
         Self {}
     }
+
+    fn apply<'a, J: JsonLike>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+    ) -> FilterExpressionResult<'a, J> {
+        match args.first().unwrap_or(&FilterExpressionResult::Nothing) {
+            FilterExpressionResult::Nodes(nodes) => FilterExpressionResult::Int(nodes.len() as i64),
+            _ => FilterExpressionResult::Nothing,
+        }
+    }
 }
 
 impl Default for Count {
@@ -22,19 +41,13 @@ impl Default for Count {
     }
 }
 
-impl FunctionExtension for Count {
-    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match args.first().unwrap() {
-            FilterExpressionResult::Nodes(nodes) => FilterExpressionResult::Int(nodes.len() as i64),
-            _ => unreachable!(),
-        }
-    }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
-        }
+impl<J: JsonLike> FunctionExtension<J> for Count {
+    fn call<'a>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        _context: &FunctionContext<'_, 'a, FunctionRegister<J>, J>,
+    ) -> FilterExpressionResult<'a, J> {
+        self.apply(args)
     }
 }
 
@@ -44,39 +57,48 @@ impl Length {
     pub fn new() -> Self {
         Self {}
     }
-}
-
-impl Default for Length {
-    fn default() -> Self {
-        Self::new()
-    }
-}
 
-impl FunctionExtension for Length {
-    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match args.first().unwrap() {
-            // TODO: UInt
+    fn apply<'a, J: JsonLike>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+    ) -> FilterExpressionResult<'a, J> {
+        match args.first().unwrap_or(&FilterExpressionResult::Nothing) {
             FilterExpressionResult::String(s) => {
                 FilterExpressionResult::Int(s.chars().count() as i64)
             }
             FilterExpressionResult::Array(a) => {
-                FilterExpressionResult::Int(a.as_array().unwrap().len() as i64)
+                FilterExpressionResult::Int(a.as_array().map_or(0, <[J]>::len) as i64)
             }
             FilterExpressionResult::Object(o) => {
-                FilterExpressionResult::Int(o.as_object().unwrap().len() as i64)
+                FilterExpressionResult::Int(o.object_len().unwrap_or(0) as i64)
             }
             _ => FilterExpressionResult::Nothing,
         }
     }
+}
 
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value],
-            return_type: ExpressionType::Value,
-        }
+impl Default for Length {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<J: JsonLike> FunctionExtension<J> for Length {
+    fn call<'a>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        _context: &FunctionContext<'_, 'a, FunctionRegister<J>, J>,
+    ) -> FilterExpressionResult<'a, J> {
+        self.apply(args)
     }
 }
 
+/// The number of compiled patterns `match` and `search` keep.
+const REGEX_CACHE_SIZE: NonZeroUsize = match NonZeroUsize::new(100) {
+    Some(size) => size,
+    None => NonZeroUsize::MIN,
+};
+
 pub struct Match {
     cache: Mutex<LruCache<String, Regex>>,
 }
@@ -84,25 +106,20 @@ pub struct Match {
 impl Match {
     pub fn new() -> Self {
         Self {
-            cache: Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap())),
+            cache: Mutex::new(LruCache::new(REGEX_CACHE_SIZE)),
         }
     }
-}
 
-impl Default for Match {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl FunctionExtension for Match {
-    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match (args.first().unwrap(), args.get(1).unwrap()) {
-            (FilterExpressionResult::String(s), FilterExpressionResult::String(p)) => {
+    pub(crate) fn apply<'a, J: JsonLike>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+    ) -> FilterExpressionResult<'a, J> {
+        match (args.first(), args.get(1)) {
+            (Some(FilterExpressionResult::String(s)), Some(FilterExpressionResult::String(p))) => {
                 // TODO: fail early if p is known to be invalid
-                let mut cache = self.cache.lock().unwrap();
+                let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
 
-                match cache.get(p) {
+                match cache.get(p.as_ref()) {
                     Some(re) => FilterExpressionResult::Bool(re.is_match(s)),
                     None => {
                         if !iregexp::check(p) {
@@ -111,7 +128,7 @@ impl FunctionExtension for Match {
 
                         if let Ok(re) = Regex::new(&full_match(p)) {
                             let rv = re.is_match(s);
-                            cache.push(p.to_owned(), re);
+                            cache.push(p.to_string(), re);
                             FilterExpressionResult::Bool(rv)
                         } else {
                             FilterExpressionResult::Bool(false)
@@ -122,12 +139,21 @@ impl FunctionExtension for Match {
             _ => FilterExpressionResult::Bool(false),
         }
     }
+}
 
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        }
+impl Default for Match {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<J: JsonLike> FunctionExtension<J> for Match {
+    fn call<'a>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        _context: &FunctionContext<'_, 'a, FunctionRegister<J>, J>,
+    ) -> FilterExpressionResult<'a, J> {
+        self.apply(args)
     }
 }
 
@@ -138,25 +164,20 @@ pub struct Search {
 impl Search {
     pub fn new() -> Self {
         Self {
-            cache: Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap())),
+            cache: Mutex::new(LruCache::new(REGEX_CACHE_SIZE)),
         }
     }
-}
-
-impl Default for Search {
-    fn default() -> Self {
-        Self::new()
-    }
-}
 
-impl FunctionExtension for Search {
-    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match (args.first().unwrap(), args.get(1).unwrap()) {
-            (FilterExpressionResult::String(s), FilterExpressionResult::String(p)) => {
+    pub(crate) fn apply<'a, J: JsonLike>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+    ) -> FilterExpressionResult<'a, J> {
+        match (args.first(), args.get(1)) {
+            (Some(FilterExpressionResult::String(s)), Some(FilterExpressionResult::String(p))) => {
                 // TODO: fail early if p is known to be invalid
-                let mut cache = self.cache.lock().unwrap();
+                let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
 
-                match cache.get(p) {
+                match cache.get(p.as_ref()) {
                     Some(re) => FilterExpressionResult::Bool(re.is_match(s)),
                     None => {
                         if !iregexp::check(p) {
@@ -165,7 +186,7 @@ impl FunctionExtension for Search {
 
                         if let Ok(re) = Regex::new(&map_regex(p)) {
                             let rv = re.is_match(s);
-                            cache.push(p.to_owned(), re);
+                            cache.push(p.to_string(), re);
                             FilterExpressionResult::Bool(rv)
                         } else {
                             FilterExpressionResult::Bool(false)
@@ -176,12 +197,21 @@ impl FunctionExtension for Search {
             _ => FilterExpressionResult::Bool(false),
         }
     }
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        }
+impl<J: JsonLike> FunctionExtension<J> for Search {
+    fn call<'a>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        _context: &FunctionContext<'_, 'a, FunctionRegister<J>, J>,
+    ) -> FilterExpressionResult<'a, J> {
+        self.apply(args)
     }
 }
 
@@ -191,6 +221,22 @@ impl Value {
     pub fn new() -> Self {
         Self {}
     }
+
+    fn apply<'a, J: JsonLike>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+    ) -> FilterExpressionResult<'a, J> {
+        match args.first().unwrap_or(&FilterExpressionResult::Nothing) {
+            FilterExpressionResult::Nodes(nodes) => {
+                if let [node] = nodes.as_slice() {
+                    FilterExpressionResult::from_json_value(node.value)
+                } else {
+                    FilterExpressionResult::Nothing
+                }
+            }
+            _ => FilterExpressionResult::Nothing,
+        }
+    }
 }
 
 impl Default for Value {
@@ -199,24 +245,110 @@ impl Default for Value {
     }
 }
 
-impl FunctionExtension for Value {
-    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match args.first().unwrap() {
-            FilterExpressionResult::Nodes(nodes) => {
-                if nodes.len() == 1 {
-                    FilterExpressionResult::from_json_value(nodes.first().unwrap().value)
-                } else {
-                    FilterExpressionResult::Nothing
-                }
-            }
-            _ => unreachable!(),
+impl<J: JsonLike> FunctionExtension<J> for Value {
+    fn call<'a>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        _context: &FunctionContext<'_, 'a, FunctionRegister<J>, J>,
+    ) -> FilterExpressionResult<'a, J> {
+        self.apply(args)
+    }
+}
+
+/// A standard function extension.
+#[derive(Debug, Clone, Copy)]
+enum StandardFunction {
+    Count,
+    Length,
+    Match,
+    Search,
+    Value,
+}
+
+impl StandardFunction {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "count" => Some(StandardFunction::Count),
+            "length" => Some(StandardFunction::Length),
+            "match" => Some(StandardFunction::Match),
+            "search" => Some(StandardFunction::Search),
+            "value" => Some(StandardFunction::Value),
+            _ => None,
         }
     }
+}
+
+/// A function registry with only the standard functions, which are
+/// dispatched statically instead of through a map of trait objects.
+///
+/// Queries are evaluated with this registry by passing
+/// [`STANDARD_ENV`](crate::STANDARD_ENV) instead of [`ENV`](crate::ENV).
+pub struct StandardFunctions {
+    matches: Match,
+    search: Search,
+    signatures: HashMap<String, FunctionSignature>,
+}
+
+impl StandardFunctions {
+    pub fn new() -> Self {
+        Self {
+            matches: Match::new(),
+            search: Search::new(),
+            signatures: standard_functions(),
+        }
+    }
+
+    /// The signature of standard function `name`, whatever the value type
+    /// it's called with.
+    pub fn signature(&self, name: &str) -> Option<&FunctionSignature> {
+        self.signatures.get(name)
+    }
+
+    /// The parameter types of standard function `name`, without allocating.
+    pub fn param_types(&self, name: &str) -> Option<Cow<'_, [ExpressionType]>> {
+        let param_types: &'static [ExpressionType] = match StandardFunction::from_name(name)? {
+            StandardFunction::Count | StandardFunction::Value => &[ExpressionType::Nodes],
+            StandardFunction::Length => &[ExpressionType::Value],
+            StandardFunction::Match | StandardFunction::Search => {
+                &[ExpressionType::Value, ExpressionType::Value]
+            }
+        };
+        Some(Cow::Borrowed(param_types))
+    }
+}
+
+impl Default for StandardFunctions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<J: JsonLike> Functions<J> for StandardFunctions {
+    fn signature(&self, name: &str) -> Option<&FunctionSignature> {
+        StandardFunctions::signature(self, name)
+    }
+
+    fn signatures(&self) -> HashMap<String, FunctionSignature> {
+        self.signatures.clone()
+    }
+
+    fn param_types(&self, name: &str) -> Option<Cow<'_, [ExpressionType]>> {
+        StandardFunctions::param_types(self, name)
+    }
 
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
+    fn call<'a>(
+        &self,
+        name: &str,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        _context: &FunctionContext<'_, 'a, Self, J>,
+    ) -> FilterExpressionResult<'a, J> {
+        match StandardFunction::from_name(name) {
+            Some(StandardFunction::Count) => Count.apply(args),
+            Some(StandardFunction::Length) => Length.apply(args),
+            Some(StandardFunction::Match) => self.matches.apply(args),
+            Some(StandardFunction::Search) => self.search.apply(args),
+            Some(StandardFunction::Value) => Value.apply(args),
+            None => FilterExpressionResult::Nothing,
         }
     }
 }
@@ -0,0 +1,136 @@
+//! Completions for partially typed queries.
+//!
+//! When a query ends with an unfinished name selector, like `$.users[0].na`
+//! or `$.users[0]['na`, the query up to that selector is evaluated and the
+//! names of members of the resulting objects are offered as completions.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::suggest::suggest;
+//! use serde_json::json;
+//!
+//! let value = json!({"users": [{"name": "a", "nickname": "b"}]});
+//! assert_eq!(
+//!     suggest("$.users[0].n", &value),
+//!     vec!["$.users[0].name", "$.users[0].nickname"]
+//! );
+//! ```
+
+use serde_json::Value;
+
+use crate::{Query, ENV};
+
+/// Complete the last name selector in `partial` with member names found by
+/// evaluating the rest of `partial` against `value`.
+///
+/// Each suggestion is the whole query, sorted by member name. Returns an
+/// empty list if `partial` doesn't end with a name selector, or if the
+/// query before it is not valid.
+pub fn suggest(partial: &str, value: &Value) -> Vec<String> {
+    let Some((base, prefix, style)) = split_partial(partial) else {
+        return Vec::new();
+    };
+
+    let Ok(query) = Query::standard(base) else {
+        return Vec::new();
+    };
+
+    let mut values: Vec<&Value> = query
+        .find(value, &ENV)
+        .iter()
+        .map(|node| node.value)
+        .collect();
+
+    if style == Style::Descendant {
+        let mut descendants = Vec::new();
+        values
+            .iter()
+            .for_each(|v| collect_descendants(v, &mut descendants));
+        values = descendants;
+    }
+
+    let mut names: Vec<&String> = values
+        .iter()
+        .filter_map(|v| v.as_object())
+        .flat_map(|obj| obj.keys())
+        .filter(|name| name.starts_with(prefix))
+        .collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .map(|name| match style {
+            Style::Bracket => format!("{}['{}']", base, escape(name)),
+            Style::Dot | Style::Descendant if !is_shorthand(name) => {
+                let dots = if style == Style::Dot { "" } else { ".." };
+                format!("{}{}['{}']", base, dots, escape(name))
+            }
+            Style::Dot => format!("{}.{}", base, name),
+            Style::Descendant => format!("{}..{}", base, name),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    /// `.name`
+    Dot,
+    /// `..name`
+    Descendant,
+    /// `['name`
+    Bracket,
+}
+
+/// Split `partial` into the query before its unfinished name selector, the
+/// part of the name typed so far, and how the selector was written.
+fn split_partial(partial: &str) -> Option<(&str, &str, Style)> {
+    let name_start = partial
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_name_char(*c))
+        .last()
+        .map_or(partial.len(), |(i, _)| i);
+
+    let (before, prefix) = partial.split_at(name_start);
+
+    if let Some(base) = before.strip_suffix("..") {
+        Some((base, prefix, Style::Descendant))
+    } else if let Some(base) = before.strip_suffix('.') {
+        Some((base, prefix, Style::Dot))
+    } else {
+        // A quoted name can contain anything but an unescaped quote, which
+        // would close it.
+        let quote = partial.rfind("['")?;
+        let prefix = &partial[quote + 2..];
+        if prefix.contains('\'') {
+            None
+        } else {
+            Some((&partial[..quote], prefix, Style::Bracket))
+        }
+    }
+}
+
+fn collect_descendants<'v>(value: &'v Value, values: &mut Vec<&'v Value>) {
+    values.push(value);
+    match value {
+        Value::Array(arr) => arr.iter().for_each(|v| collect_descendants(v, values)),
+        Value::Object(obj) => obj.values().for_each(|v| collect_descendants(v, values)),
+        _ => (),
+    }
+}
+
+fn is_name_first(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+    is_name_first(c) || c.is_ascii_digit()
+}
+
+fn is_shorthand(name: &str) -> bool {
+    name.chars().next().is_some_and(is_name_first) && name.chars().all(is_name_char)
+}
+
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('\'', "\\'")
+}
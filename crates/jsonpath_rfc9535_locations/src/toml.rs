@@ -0,0 +1,72 @@
+//! Evaluate JSONPath queries against TOML documents.
+//!
+//! TOML values are converted to JSON before evaluation. Offset date-times,
+//! local date-times, dates and times become RFC 3339 formatted strings, and
+//! non-finite floats become `null`.
+//!
+//! TOML documents can't define a key twice, and table keys are always
+//! strings, so unlike the other adapters there are no duplicate names for a
+//! [`DuplicateKeys`](crate::document::DuplicateKeys) policy to resolve.
+use serde_json::Value;
+use toml::Value as TomlValue;
+
+use crate::{
+    document::DuplicateKeys,
+    env::Environment,
+    errors::JSONPathError,
+    format::{self, float, Format},
+    function::Functions,
+    jsonpath::ENV,
+    node::PathElement,
+};
+
+/// Convert a TOML value to a JSON value.
+pub fn to_json(value: &TomlValue) -> Value {
+    match value {
+        TomlValue::String(s) => Value::String(s.to_owned()),
+        TomlValue::Integer(i) => Value::from(*i),
+        TomlValue::Float(f) => float(*f),
+        TomlValue::Boolean(b) => Value::Bool(*b),
+        TomlValue::Datetime(d) => Value::String(d.to_string()),
+        TomlValue::Array(arr) => Value::Array(arr.iter().map(to_json).collect()),
+        TomlValue::Table(table) => Value::Object(
+            table
+                .iter()
+                .map(|(k, v)| (k.to_owned(), to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Find values in a TOML document matching the JSONPath expression `expr`.
+///
+/// Returns `(normalized path, value)` pairs borrowing from `value`.
+pub fn find<'a>(
+    expr: &str,
+    value: &'a TomlValue,
+) -> Result<Vec<(String, &'a TomlValue)>, JSONPathError> {
+    find_with(&ENV, expr, value)
+}
+
+/// Like [`find`], parsing `expr` and evaluating it with `env`.
+pub fn find_with<'a, F: Functions>(
+    env: &'static Environment<F>,
+    expr: &str,
+    value: &'a TomlValue,
+) -> Result<Vec<(String, &'a TomlValue)>, JSONPathError> {
+    format::find_in(env, expr, value)
+}
+
+impl Format for TomlValue {
+    fn to_json(&self, _duplicate_keys: DuplicateKeys) -> Result<Value, JSONPathError> {
+        Ok(to_json(self))
+    }
+
+    fn child(&self, element: &PathElement, _duplicate_keys: DuplicateKeys) -> Option<&Self> {
+        match (self, element) {
+            (TomlValue::Array(arr), PathElement::Index(i)) => arr.get(*i),
+            (TomlValue::Table(table), PathElement::Name(name)) => table.get(name),
+            _ => None,
+        }
+    }
+}
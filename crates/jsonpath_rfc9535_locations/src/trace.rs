@@ -0,0 +1,184 @@
+//! Record every step of a query's evaluation for debugging later.
+//!
+//! [`Query::find_traced`] selects the same nodes as [`Query::find`], and
+//! also returns a [`Trace`] of each node every selector selected, and of
+//! each node a filter selector tested, with the filter's result. A trace is
+//! self-contained and serializable, so it can be saved where a query ran
+//! against a document you can't see, and replayed with [`Trace::events`] or
+//! printed later.
+//!
+//! ```
+//! use jsonpath_rfc9535_locations::{trace::Outcome, Query, ENV};
+//! use serde_json::json;
+//!
+//! let query = Query::standard("$.users[?@.age > 18]").unwrap();
+//! let value = json!({"users": [{"age": 12}, {"age": 42}]});
+//! let (nodes, trace) = query.find_traced(&value, &ENV);
+//!
+//! assert_eq!(nodes.len(), 1);
+//!
+//! let rejected: Vec<&str> = trace
+//!     .events()
+//!     .filter(|event| event.outcome == Outcome::Rejected)
+//!     .map(|event| event.path)
+//!     .collect();
+//! assert_eq!(rejected, vec!["$['users'][0]"]);
+//!
+//! let saved = serde_json::to_string(&trace).unwrap();
+//! let replayed: jsonpath_rfc9535_locations::trace::Trace = serde_json::from_str(&saved).unwrap();
+//! assert_eq!(replayed, trace);
+//! ```
+//!
+//! Each distinct path is stored once, and steps refer to paths and
+//! selectors by index, so traces of queries visiting the same nodes many
+//! times stay small.
+
+use std::{collections::HashMap, fmt};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    env::Environment,
+    function::Functions,
+    node::{normalized_path, Node, NodeList},
+    segment::Segment,
+    walk::{Hooks, Walker},
+    Query,
+};
+
+/// Whether a step's node was selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    Selected,
+    /// A filter selector's expression was false for the node.
+    Rejected,
+}
+
+/// One node selected, or tested by a filter, during evaluation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Step {
+    /// The index of the segment in the query.
+    pub segment: usize,
+    /// The index of the selector in its segment.
+    pub selector: usize,
+    /// The index of the node's normalized path in `Trace::paths`.
+    pub path: usize,
+    pub outcome: Outcome,
+}
+
+/// The evaluation trace of one query against one document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trace {
+    /// The query in its canonical form.
+    pub query: String,
+    /// The canonical form of each selector, by segment.
+    pub selectors: Vec<Vec<String>>,
+    /// Normalized paths referred to by steps.
+    pub paths: Vec<String>,
+    /// Steps in the order they happened.
+    pub steps: Vec<Step>,
+}
+
+/// A [`Step`] with its selector and path looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event<'t> {
+    pub segment: usize,
+    pub selector: &'t str,
+    pub path: &'t str,
+    pub outcome: Outcome,
+}
+
+impl Trace {
+    /// Steps in the order they happened, with their selectors and paths.
+    pub fn events(&self) -> impl Iterator<Item = Event<'_>> {
+        self.steps.iter().filter_map(|step| {
+            Some(Event {
+                segment: step.segment,
+                selector: self.selectors.get(step.segment)?.get(step.selector)?,
+                path: self.paths.get(step.path)?,
+                outcome: step.outcome,
+            })
+        })
+    }
+}
+
+/// One line per step, showing the segment index, selector, path and
+/// outcome.
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.query)?;
+        for event in self.events() {
+            let outcome = match event.outcome {
+                Outcome::Selected => "selected",
+                Outcome::Rejected => "rejected",
+            };
+            write!(
+                f,
+                "\n{}\t{}\t{}\t{}",
+                event.segment, event.selector, event.path, outcome
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Query {
+    /// Same as `find`, but also records a [`Trace`] of the evaluation.
+    pub fn find_traced<'v, F: Functions>(
+        &self,
+        value: &'v Value,
+        env: &'static Environment<F>,
+    ) -> (NodeList<'v>, Trace) {
+        let mut walker = Walker {
+            env,
+            root: value,
+            hooks: Tracer {
+                index: HashMap::new(),
+                trace: Trace {
+                    query: self.to_string(),
+                    selectors: self.segments.iter().map(selector_strings).collect(),
+                    paths: Vec::new(),
+                    steps: Vec::new(),
+                },
+            },
+        };
+
+        let nodes = walker.find(self);
+        (nodes, walker.hooks.trace)
+    }
+}
+
+fn selector_strings(segment: &Segment) -> Vec<String> {
+    match segment {
+        Segment::Child { selectors } | Segment::Recursive { selectors } => {
+            selectors.iter().map(|s| s.to_string()).collect()
+        }
+        Segment::Eoi => Vec::new(),
+    }
+}
+
+struct Tracer {
+    /// The index of each path in `trace.paths`.
+    index: HashMap<String, usize>,
+    trace: Trace,
+}
+
+impl Hooks<'_> for Tracer {
+    fn tested(&mut self, segment: usize, selector: usize, node: &Node, outcome: Outcome) {
+        let path = normalized_path(&node.location);
+        let next = self.trace.paths.len();
+        let path = *self.index.entry(path).or_insert_with_key(|path| {
+            self.trace.paths.push(path.clone());
+            next
+        });
+
+        self.trace.steps.push(Step {
+            segment,
+            selector,
+            path,
+            outcome,
+        });
+    }
+}
@@ -0,0 +1,162 @@
+//! Evaluate a query one selector at a time, for callers that need to see,
+//! or skip, the nodes each selector selects.
+//!
+//! [`Query::find_traced`] records each step and [`Query::find_authorized`]
+//! skips values its policy denies. Both walk the query with a [`Walker`],
+//! supplying [`Hooks`] for what they do differently.
+//!
+//! [`Query::find_traced`]: crate::Query::find_traced
+//! [`Query::find_authorized`]: crate::Query::find_authorized
+
+use serde_json::Value;
+
+use crate::{
+    env::Environment,
+    filter::is_truthy_ref,
+    function::Functions,
+    node::{Location, Node, NodeList, PathElement},
+    segment::{has_children, Segment},
+    selector::Selector,
+    trace::Outcome,
+    Query,
+};
+
+pub(crate) trait Hooks<'v> {
+    /// Return `false` to treat `value`, a child of another value, as absent.
+    fn allows(&self, _value: &'v Value) -> bool {
+        true
+    }
+
+    /// The value filter expressions see in place of `value`.
+    fn view<'h>(&'h self, value: &'v Value) -> &'h Value
+    where
+        'v: 'h,
+    {
+        value
+    }
+
+    /// Called for each node the `selector`th selector of the `segment`th
+    /// segment selects or rejects, in evaluation order.
+    fn tested(&mut self, _segment: usize, _selector: usize, _node: &Node<'v>, _outcome: Outcome) {}
+}
+
+pub(crate) struct Walker<'v, F: Functions + 'static, H> {
+    pub env: &'static Environment<F>,
+    pub root: &'v Value,
+    pub hooks: H,
+}
+
+impl<'v, F: Functions, H: Hooks<'v>> Walker<'v, F, H> {
+    /// Same as `query.find(self.root, self.env)`, calling hooks along the way.
+    pub fn find(&mut self, query: &Query) -> NodeList<'v> {
+        let mut nodes = NodeList::from(vec![Node {
+            value: self.root,
+            location: Location::new(),
+        }]);
+
+        for (i, segment) in query.segments.iter().enumerate() {
+            nodes = self.resolve(i, segment, nodes);
+        }
+
+        nodes
+    }
+
+    /// Like `Segment::resolve`.
+    fn resolve(&mut self, i: usize, segment: &Segment, nodes: NodeList<'v>) -> NodeList<'v> {
+        let mut selected = NodeList::new();
+        match segment {
+            Segment::Child { selectors } => {
+                for node in nodes.iter() {
+                    for (j, selector) in selectors.iter().enumerate() {
+                        self.select(i, j, selector, node, &mut selected);
+                    }
+                }
+            }
+            Segment::Recursive { selectors } => {
+                for node in nodes.iter() {
+                    self.visit(i, selectors, node, &mut selected);
+                }
+            }
+            Segment::Eoi => return nodes,
+        }
+        selected
+    }
+
+    /// Apply `selectors` to `node` and each of its allowed descendants.
+    fn visit(
+        &mut self,
+        i: usize,
+        selectors: &[Selector],
+        node: &Node<'v>,
+        selected: &mut NodeList<'v>,
+    ) {
+        if has_children(node.value) {
+            for (j, selector) in selectors.iter().enumerate() {
+                self.select(i, j, selector, node, selected);
+            }
+        }
+
+        for child in self.children(node) {
+            self.visit(i, selectors, &child, selected);
+        }
+    }
+
+    /// Apply the `j`th selector of segment `i` to `node`.
+    fn select(
+        &mut self,
+        i: usize,
+        j: usize,
+        selector: &Selector,
+        node: &Node<'v>,
+        selected: &mut NodeList<'v>,
+    ) {
+        let Selector::Filter { expression, .. } = selector else {
+            for child in selector.resolve(self.env, node.value, self.root, &node.location) {
+                if self.hooks.allows(child.value) {
+                    self.hooks.tested(i, j, &child, Outcome::Selected);
+                    selected.push(child);
+                }
+            }
+            return;
+        };
+
+        for child in self.children(node) {
+            let root = self.hooks.view(self.root);
+            let current = self.hooks.view(child.value);
+            let rv = expression.evaluate_at(self.env, root, current, Some(&child.location));
+            if is_truthy_ref(&rv) {
+                self.hooks.tested(i, j, &child, Outcome::Selected);
+                selected.push(child);
+            } else {
+                self.hooks.tested(i, j, &child, Outcome::Rejected);
+            }
+        }
+    }
+
+    /// Nodes for the allowed elements of an array or members of an object.
+    fn children(&self, node: &Node<'v>) -> Vec<Node<'v>> {
+        children(node.value)
+            .filter(|(_, value)| self.hooks.allows(value))
+            .map(|(key, value)| Node {
+                value,
+                location: node.location.append(key),
+            })
+            .collect()
+    }
+}
+
+/// The keys and values of an array's elements or an object's members.
+pub(crate) fn children(value: &Value) -> Box<dyn Iterator<Item = (PathElement, &Value)> + '_> {
+    match value {
+        Value::Array(arr) => Box::new(
+            arr.iter()
+                .enumerate()
+                .map(|(i, v)| (PathElement::Index(i), v)),
+        ),
+        Value::Object(obj) => Box::new(
+            obj.iter()
+                .map(|(k, v)| (PathElement::Name(k.to_owned()), v)),
+        ),
+        _ => Box::new(std::iter::empty()),
+    }
+}
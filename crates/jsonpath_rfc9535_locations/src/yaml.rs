@@ -0,0 +1,118 @@
+//! Evaluate JSONPath queries against YAML documents.
+//!
+//! YAML values are converted to JSON before evaluation. Aliases are resolved
+//! by the YAML parser, so an anchored node appears wherever it is referenced.
+//! Merge keys (`<<`) are left as-is unless `serde_yaml::Value::apply_merge`
+//! is called first. Tags are ignored in favour of the tagged value, and
+//! non-finite floats become `null`.
+//!
+//! Mapping keys must be strings, numbers, booleans or null. Non-string keys
+//! are matched by their YAML representation, like `true` or `42`. Keys with
+//! the same representation, like `1` and `"1"`, are resolved by a
+//! [`DuplicateKeys`] policy, the last one winning with [`to_json`] and
+//! [`find`].
+use serde_json::{Map, Value};
+use serde_yaml::Value as YamlValue;
+
+use crate::{
+    document::DuplicateKeys,
+    env::Environment,
+    errors::JSONPathError,
+    format::{self, float, Format},
+    function::Functions,
+    jsonpath::ENV,
+    node::PathElement,
+};
+
+/// Convert a YAML value to a JSON value.
+pub fn to_json(value: &YamlValue) -> Result<Value, JSONPathError> {
+    to_json_with(value, DuplicateKeys::default())
+}
+
+/// Convert a YAML value to a JSON value, resolving mapping keys with the
+/// same representation according to `duplicate_keys`.
+pub fn to_json_with(
+    value: &YamlValue,
+    duplicate_keys: DuplicateKeys,
+) -> Result<Value, JSONPathError> {
+    Ok(match value {
+        YamlValue::Null => Value::Null,
+        YamlValue::Bool(b) => Value::Bool(*b),
+        YamlValue::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(i) = n.as_u64() {
+                Value::from(i)
+            } else {
+                n.as_f64().map_or(Value::Null, float)
+            }
+        }
+        YamlValue::String(s) => Value::String(s.to_owned()),
+        YamlValue::Sequence(seq) => Value::Array(
+            seq.iter()
+                .map(|v| to_json_with(v, duplicate_keys))
+                .collect::<Result<_, _>>()?,
+        ),
+        YamlValue::Mapping(mapping) => {
+            let mut obj = Map::new();
+            for (k, v) in mapping {
+                duplicate_keys
+                    .insert(&mut obj, key_name(k)?, to_json_with(v, duplicate_keys)?)
+                    .map_err(JSONPathError::document)?;
+            }
+            Value::Object(obj)
+        }
+        YamlValue::Tagged(tagged) => to_json_with(&tagged.value, duplicate_keys)?,
+    })
+}
+
+/// Find values in a YAML document matching the JSONPath expression `expr`.
+///
+/// Returns `(normalized path, value)` pairs borrowing from `value`.
+pub fn find<'a>(
+    expr: &str,
+    value: &'a YamlValue,
+) -> Result<Vec<(String, &'a YamlValue)>, JSONPathError> {
+    find_with(&ENV, expr, value)
+}
+
+/// Like [`find`], parsing `expr` and evaluating it with `env`, and resolving
+/// mapping keys with the same representation according to
+/// `env.duplicate_keys`.
+pub fn find_with<'a, F: Functions>(
+    env: &'static Environment<F>,
+    expr: &str,
+    value: &'a YamlValue,
+) -> Result<Vec<(String, &'a YamlValue)>, JSONPathError> {
+    format::find_in(env, expr, value)
+}
+
+fn key_name(key: &YamlValue) -> Result<String, JSONPathError> {
+    match key {
+        YamlValue::String(s) => Ok(s.to_owned()),
+        YamlValue::Number(n) => Ok(n.to_string()),
+        YamlValue::Bool(b) => Ok(b.to_string()),
+        YamlValue::Null => Ok(String::from("null")),
+        YamlValue::Tagged(tagged) => key_name(&tagged.value),
+        _ => Err(JSONPathError::typ(String::from(
+            "YAML mapping keys must be strings, numbers, booleans or null",
+        ))),
+    }
+}
+
+impl Format for YamlValue {
+    fn to_json(&self, duplicate_keys: DuplicateKeys) -> Result<Value, JSONPathError> {
+        to_json_with(self, duplicate_keys)
+    }
+
+    fn child(&self, element: &PathElement, duplicate_keys: DuplicateKeys) -> Option<&Self> {
+        match (self, element) {
+            (YamlValue::Tagged(tagged), _) => tagged.value.child(element, duplicate_keys),
+            (YamlValue::Sequence(seq), PathElement::Index(i)) => seq.get(*i),
+            (YamlValue::Mapping(mapping), PathElement::Name(name)) => {
+                duplicate_keys.select(mapping.iter(), |k| key_name(k).is_ok_and(|k| &k == name))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use jsonpath_rfc9535_locations::{
+    find,
+    node::{Node, PathElement},
+    Query, ENV,
+};
+use serde_json::{json, Value};
+
+fn deny_name(name: &'static str) -> impl Fn(&Node, &PathElement) -> bool {
+    move |_, key| *key != PathElement::Name(name.to_owned())
+}
+
+fn authorized(
+    expr: &str,
+    value: &Value,
+    policy: impl Fn(&Node, &PathElement) -> bool,
+) -> (Vec<String>, Vec<String>) {
+    let result = Query::standard(expr)
+        .unwrap()
+        .find_authorized(value, &ENV, &policy);
+    let paths = result.nodes.iter().map(|node| node.path()).collect();
+    (paths, result.denied)
+}
+
+#[test]
+fn allow_everything() {
+    let value = json!({"a": [1, {"b": 2}], "c": {"b": 3}});
+    for expr in ["$..b", "$.a[*]", "$..[?@.b > 2]", "$.*[-1]", "$..*"] {
+        let expected: Vec<String> = find(expr, &value)
+            .unwrap()
+            .iter()
+            .map(|node| node.path())
+            .collect();
+        let (paths, denied) = authorized(expr, &value, |_, _| true);
+        assert_eq!(paths, expected, "{}", expr);
+        assert!(denied.is_empty());
+    }
+}
+
+#[test]
+fn denied_members_are_absent() {
+    let value = json!({"public": {"id": 1}, "secret": {"id": 2}});
+
+    let (paths, denied) = authorized("$.secret.id", &value, deny_name("secret"));
+    assert!(paths.is_empty());
+    assert_eq!(denied, vec!["$['secret']"]);
+
+    let (paths, _) = authorized("$..id", &value, deny_name("secret"));
+    assert_eq!(paths, vec!["$['public']['id']"]);
+
+    let (paths, _) = authorized("$.*", &value, deny_name("secret"));
+    assert_eq!(paths, vec!["$['public']"]);
+}
+
+#[test]
+fn denied_elements_keep_sibling_indices() {
+    let value = json!(["a", "b", "c"]);
+    let policy = |_: &Node, key: &PathElement| *key != PathElement::Index(0);
+
+    let (paths, denied) = authorized("$[0, 1, -1]", &value, policy);
+    assert_eq!(paths, vec!["$[1]", "$[2]"]);
+    assert_eq!(denied, vec!["$[0]"]);
+
+    let (paths, _) = authorized("$[*]", &value, policy);
+    assert_eq!(paths, vec!["$[1]", "$[2]"]);
+}
+
+#[test]
+fn filters_do_not_see_denied_values() {
+    let value = json!({"users": [
+        {"name": "a", "role": "admin", "salary": 10},
+        {"name": "b", "role": "user", "salary": 20},
+    ]});
+
+    let (paths, denied) = authorized("$.users[?@.salary > 15].name", &value, deny_name("salary"));
+    assert!(paths.is_empty());
+    assert_eq!(
+        denied,
+        vec!["$['users'][0]['salary']", "$['users'][1]['salary']"]
+    );
+
+    let (paths, _) = authorized(
+        "$.users[?@.role == 'user'].name",
+        &value,
+        deny_name("salary"),
+    );
+    assert_eq!(paths, vec!["$['users'][1]['name']"]);
+
+    let (paths, _) = authorized("$.users[?count(@.*) == 2]", &value, deny_name("salary"));
+    assert_eq!(paths, vec!["$['users'][0]", "$['users'][1]"]);
+
+    let (paths, _) = authorized("$.users[?$.users[1].salary]", &value, deny_name("salary"));
+    assert!(paths.is_empty());
+}
+
+#[test]
+fn policies_see_the_parent_node() {
+    let value = json!({"a": {"private": true, "x": 1}, "b": {"x": 2}});
+    let policy = |parent: &Node, _: &PathElement| parent.value.get("private").is_none();
+
+    let (paths, denied) = authorized("$..x", &value, policy);
+    assert_eq!(paths, vec!["$['b']['x']"]);
+    assert_eq!(denied, vec!["$['a']['private']", "$['a']['x']"]);
+}
+
+#[test]
+fn selected_values_do_not_contain_denied_values() {
+    let value = json!({"users": [
+        {"name": "a", "salary": 10},
+        {"name": "b", "salary": 20},
+    ]});
+
+    for expr in ["$", "$.users[0]", "$..[?@.name]"] {
+        let result = Query::standard(expr)
+            .unwrap()
+            .find_authorized(&value, &ENV, &deny_name("salary"));
+        assert!(!result.nodes.is_empty(), "{}", expr);
+        for node in &result.nodes {
+            assert!(
+                !node.value.to_string().contains("salary"),
+                "{}: {}",
+                expr,
+                node.value
+            );
+        }
+    }
+
+    let result = Query::standard("$.users[0]")
+        .unwrap()
+        .find_authorized(&value, &ENV, &deny_name("salary"));
+    assert_eq!(result.nodes[0].value, json!({"name": "a"}));
+    assert_eq!(result.nodes[0].path(), "$['users'][0]");
+}
@@ -0,0 +1,223 @@
+use std::{collections::HashMap, num::NonZeroUsize};
+
+use jsonpath_rfc9535_locations::{
+    cache::{
+        document_hash, CacheKey, CacheStats, CacheStorage, CachedLocations, DocumentKey,
+        ResultCache,
+    },
+    find,
+};
+use serde_json::{json, Value};
+
+fn capacity(n: usize) -> NonZeroUsize {
+    NonZeroUsize::new(n).unwrap()
+}
+
+fn paths(cache: &ResultCache<impl CacheStorage>, query: &str, value: &Value) -> Vec<String> {
+    cache
+        .find_cached(query, value)
+        .unwrap()
+        .iter()
+        .map(|node| node.path())
+        .collect()
+}
+
+fn paths_by_id(
+    cache: &ResultCache<impl CacheStorage>,
+    query: &str,
+    document: u64,
+    value: &Value,
+) -> Vec<String> {
+    cache
+        .find_cached_by_id(query, document, value)
+        .unwrap()
+        .iter()
+        .map(|node| node.path())
+        .collect()
+}
+
+#[test]
+fn cached_results_match_find() {
+    let cache = ResultCache::new(capacity(10));
+    let value = json!({"a": [{"b": 1}, {"b": 2}], "c": {"b": 3}});
+    let query = "$..b";
+    let expected: Vec<String> = find(query, &value)
+        .unwrap()
+        .iter()
+        .map(|node| node.path())
+        .collect();
+
+    assert_eq!(paths(&cache, query, &value), expected);
+    assert_eq!(paths(&cache, query, &value), expected);
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+
+    let nodes = cache.find_cached(query, &value).unwrap();
+    assert_eq!(nodes[0].value, &json!(1));
+}
+
+#[test]
+fn queries_are_keyed_by_text() {
+    let cache = ResultCache::new(capacity(10));
+    let value = json!({"a": 1});
+    cache.find_cached("$.a", &value).unwrap();
+    cache.find_cached("$['a']", &value).unwrap();
+    cache.find_cached("$.a", &value).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+}
+
+#[test]
+fn queries_with_the_same_canonical_form_are_not_confused() {
+    let cache = ResultCache::new(capacity(10));
+    let value = json!({"a": {"b": 1}, "a']['b": 2});
+    assert_eq!(paths(&cache, "$['a']['b']", &value), vec!["$['a']['b']"]);
+    let nodes = cache.find_cached("$['a\\'][\\'b']", &value).unwrap();
+    assert_eq!(nodes.values(), vec![&json!(2)]);
+}
+
+#[test]
+fn changed_documents_miss() {
+    let cache = ResultCache::new(capacity(10));
+    let mut value = json!({"a": [1, 2]});
+    assert_eq!(paths(&cache, "$.a[*]", &value).len(), 2);
+
+    value["a"].as_array_mut().unwrap().push(json!(3));
+    assert_eq!(paths(&cache, "$.a[*]", &value).len(), 3);
+    assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 2 });
+}
+
+#[test]
+fn invalidate() {
+    let cache = ResultCache::new(capacity(10));
+    let a = json!({"x": 1});
+    let b = json!({"x": 2});
+    cache.find_cached("$.x", &a).unwrap();
+    cache.find_cached("$.x", &b).unwrap();
+
+    cache.invalidate(&a);
+    cache.find_cached("$.x", &a).unwrap();
+    cache.find_cached("$.x", &b).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 3 });
+
+    cache.clear();
+    cache.find_cached("$.x", &b).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 4 });
+}
+
+#[test]
+fn documents_are_keyed_by_id() {
+    let cache = ResultCache::new(capacity(10));
+    let a = json!({"x": [1]});
+    let b = json!({"x": [1, 2]});
+    assert_eq!(paths_by_id(&cache, "$.x[*]", 1, &a).len(), 1);
+    assert_eq!(paths_by_id(&cache, "$.x[*]", 2, &b).len(), 2);
+    assert_eq!(paths_by_id(&cache, "$.x[*]", 1, &a).len(), 1);
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+}
+
+#[test]
+fn invalidate_document() {
+    let cache = ResultCache::new(capacity(10));
+    let mut a = json!({"x": [1]});
+    let b = json!({"x": 2});
+    cache.find_cached_by_id("$.x[*]", 1, &a).unwrap();
+    cache.find_cached_by_id("$.x", 2, &b).unwrap();
+
+    a["x"].as_array_mut().unwrap().push(json!(3));
+    cache.invalidate_document(1);
+    assert_eq!(paths_by_id(&cache, "$.x[*]", 1, &a).len(), 2);
+    cache.find_cached_by_id("$.x", 2, &b).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 3 });
+
+    cache.clear();
+    cache.find_cached_by_id("$.x", 2, &b).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 4 });
+}
+
+#[test]
+fn missing_locations_are_evaluated_again() {
+    let cache = ResultCache::new(capacity(10));
+    let mut value = json!({"a": [1, 2]});
+    assert_eq!(paths_by_id(&cache, "$.a[1]", 1, &value), vec!["$['a'][1]"]);
+
+    // Changed without invalidating, so the cached location is gone.
+    value["a"].as_array_mut().unwrap().pop();
+    assert!(paths_by_id(&cache, "$.a[1]", 1, &value).is_empty());
+    assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 2 });
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let cache = ResultCache::new(capacity(2));
+    let value = json!({"a": 1, "b": 2, "c": 3});
+    cache.find_cached("$.a", &value).unwrap();
+    cache.find_cached("$.b", &value).unwrap();
+    cache.find_cached("$.a", &value).unwrap();
+    cache.find_cached("$.c", &value).unwrap();
+
+    cache.find_cached("$.a", &value).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 3 });
+    cache.find_cached("$.b", &value).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 4 });
+}
+
+#[test]
+fn syntax_errors_are_not_cached() {
+    let cache = ResultCache::new(capacity(10));
+    assert!(cache.find_cached("$.", &json!({})).is_err());
+    assert_eq!(cache.stats(), CacheStats::default());
+}
+
+#[test]
+fn ids_and_hashes_are_separate_keys() {
+    let cache = ResultCache::new(capacity(10));
+    let value = json!({"a": 1});
+    cache.find_cached("$.a", &value).unwrap();
+    cache.find_cached_by_id("$.a", 1, &value).unwrap();
+    cache.invalidate_document(1);
+    cache.find_cached("$.a", &value).unwrap();
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+}
+
+#[test]
+fn document_hash_is_stable() {
+    assert_eq!(
+        document_hash(&json!([1, "a"])),
+        document_hash(&json!([1, "a"]))
+    );
+    assert_ne!(
+        document_hash(&json!([1, "a"])),
+        document_hash(&json!(["a", 1]))
+    );
+}
+
+#[derive(Default)]
+struct MapStorage {
+    entries: HashMap<CacheKey, CachedLocations>,
+}
+
+impl CacheStorage for MapStorage {
+    fn get(&mut self, key: &CacheKey) -> Option<CachedLocations> {
+        self.entries.get(key).cloned()
+    }
+
+    fn put(&mut self, key: CacheKey, locations: CachedLocations) {
+        self.entries.insert(key, locations);
+    }
+
+    fn remove_document(&mut self, document: DocumentKey) {
+        self.entries.retain(|key, _| key.document != document);
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[test]
+fn custom_storage() {
+    let cache = ResultCache::with_storage(MapStorage::default());
+    let value = json!({"a": {"b": [true]}});
+    assert_eq!(paths(&cache, "$.a.b[0]", &value), vec!["$['a']['b'][0]"]);
+    assert_eq!(paths(&cache, "$.a.b[0]", &value), vec!["$['a']['b'][0]"]);
+    assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 1 });
+}
@@ -0,0 +1,117 @@
+#![cfg(feature = "cbor")]
+
+use ciborium::Value as CborValue;
+use jsonpath_rfc9535_locations::{cbor, document::DuplicateKeys, env::Environment};
+use serde_json::json;
+
+fn document() -> CborValue {
+    CborValue::Map(vec![
+        (
+            CborValue::Text(String::from("readings")),
+            CborValue::Array(vec![
+                CborValue::Map(vec![
+                    (
+                        CborValue::Text(String::from("id")),
+                        CborValue::Integer(1.into()),
+                    ),
+                    (
+                        CborValue::Text(String::from("temp")),
+                        CborValue::Float(21.5),
+                    ),
+                ]),
+                CborValue::Map(vec![
+                    (
+                        CborValue::Text(String::from("id")),
+                        CborValue::Integer(2.into()),
+                    ),
+                    (
+                        CborValue::Text(String::from("temp")),
+                        CborValue::Float(f64::NAN),
+                    ),
+                ]),
+            ]),
+        ),
+        (
+            CborValue::Integer(7.into()),
+            CborValue::Tag(24, Box::new(CborValue::Bytes(vec![0xfb, 0xff]))),
+        ),
+    ])
+}
+
+#[test]
+fn to_json() {
+    assert_eq!(
+        cbor::to_json(&document()).unwrap(),
+        json!({
+            "readings": [{"id": 1, "temp": 21.5}, {"id": 2, "temp": null}],
+            "7": "-_8"
+        })
+    );
+}
+
+#[test]
+fn find_borrows_cbor_values() {
+    let doc = document();
+    let rv = cbor::find("$.readings[?@.temp > 20].id", &doc).unwrap();
+    assert_eq!(
+        rv,
+        vec![(
+            String::from("$['readings'][0]['id']"),
+            &CborValue::Integer(1.into())
+        )]
+    );
+}
+
+#[test]
+fn find_integer_key() {
+    let doc = document();
+    let rv = cbor::find("$['7']", &doc).unwrap();
+    assert_eq!(rv.len(), 1);
+    assert!(matches!(rv[0].1, CborValue::Tag(24, _)));
+}
+
+#[test]
+fn unsupported_key() {
+    let doc = CborValue::Map(vec![(CborValue::Bool(true), CborValue::Null)]);
+    let err = cbor::find("$.*", &doc).unwrap_err();
+    assert_eq!(err.msg, "CBOR map keys must be text strings or integers");
+}
+
+#[test]
+fn duplicate_keys() {
+    let doc = CborValue::Map(vec![
+        (
+            CborValue::Integer(1.into()),
+            CborValue::Text(String::from("a")),
+        ),
+        (
+            CborValue::Text(String::from("1")),
+            CborValue::Text(String::from("b")),
+        ),
+    ]);
+
+    assert_eq!(cbor::to_json(&doc).unwrap(), json!({"1": "b"}));
+    let rv = cbor::find("$[?@ == 'b']", &doc).unwrap();
+    assert_eq!(
+        rv,
+        vec![(String::from("$['1']"), &doc.as_map().unwrap()[1].1)]
+    );
+
+    let env: &'static Environment = Box::leak(Box::new(
+        Environment::new().with_duplicate_keys(DuplicateKeys::First),
+    ));
+    let rv = cbor::find_with(env, "$['1']", &doc).unwrap();
+    assert_eq!(
+        rv,
+        vec![(String::from("$['1']"), &doc.as_map().unwrap()[0].1)]
+    );
+    assert!(cbor::find_with(env, "$[?@ == 'b']", &doc)
+        .unwrap()
+        .is_empty());
+
+    let env: &'static Environment = Box::leak(Box::new(
+        Environment::new().with_duplicate_keys(DuplicateKeys::Error),
+    ));
+    let err = cbor::find_with(env, "$.*", &doc).unwrap_err();
+    assert_eq!(err.msg, "duplicate member name `1`");
+}
@@ -0,0 +1,62 @@
+use jsonpath_rfc9535_locations::{
+    collation::{CaseInsensitive, Collation},
+    env::Environment,
+    Query, ENV,
+};
+use serde_json::{json, Value};
+
+fn env(collation: impl Collation + 'static) -> &'static Environment {
+    Box::leak(Box::new(Environment::new().with_collation(collation)))
+}
+
+fn find(expr: &str, value: &Value, env: &'static Environment) -> Vec<String> {
+    Query::standard(expr)
+        .unwrap()
+        .find(value, env)
+        .values()
+        .into_iter()
+        .map(|v| v.as_str().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn code_point_order_by_default() {
+    let value = json!(["a", "B", "é", "z"]);
+    assert_eq!(find("$[?@ < 'a']", &value, &ENV), vec!["B"]);
+    assert_eq!(find("$[?@ > 'z']", &value, &ENV), vec!["é"]);
+    assert_eq!(find("$[?@ >= 'a']", &value, &ENV), vec!["a", "é", "z"]);
+}
+
+#[test]
+fn case_insensitive() {
+    let env = env(CaseInsensitive);
+    let value = json!(["a", "A", "B", "c"]);
+    assert_eq!(find("$[?@ < 'b']", &value, env), vec!["a", "A"]);
+    assert_eq!(find("$[?@ <= 'b']", &value, env), vec!["a", "A", "B"]);
+    assert_eq!(find("$[?@ > 'a']", &value, env), vec!["B", "c"]);
+    assert_eq!(find("$[?@ >= 'A']", &value, env), vec!["a", "A", "B", "c"]);
+}
+
+#[test]
+fn equality_ignores_collation() {
+    let env = env(CaseInsensitive);
+    let value = json!(["a", "A"]);
+    assert_eq!(find("$[?@ == 'a']", &value, env), vec!["a"]);
+    assert_eq!(find("$[?@ != 'a']", &value, env), vec!["A"]);
+}
+
+#[test]
+fn custom_collation() {
+    // Longer strings sort after shorter ones.
+    let env = env(|a: &str, b: &str| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    let value = json!({"items": ["bb", "a", "ccc"], "max": "zz"});
+    assert_eq!(find("$.items[?@ <= $.max]", &value, env), vec!["bb", "a"]);
+}
+
+#[test]
+fn constant_comparisons_use_collation() {
+    let env = env(CaseInsensitive);
+    let value = json!(["x"]);
+    assert_eq!(find("$[?'a' < 'B']", &value, env), vec!["x"]);
+    assert!(find("$[?'a' < 'B']", &value, &ENV).is_empty());
+}
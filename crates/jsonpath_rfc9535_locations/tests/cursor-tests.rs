@@ -0,0 +1,162 @@
+use jsonpath_rfc9535_locations::{
+    cursor::apply,
+    find,
+    node::PathElement::{Index, Name},
+    Cursor, Edit,
+};
+use serde_json::json;
+
+#[test]
+fn cursor_from_node() {
+    let doc = json!({"a": [1, {"b": 2}]});
+    let nodes = find("$.a[1].b", &doc).unwrap();
+    let cursor = Cursor::from(&nodes[0]);
+    assert_eq!(cursor.path(), "$['a'][1]['b']");
+    assert_eq!(cursor.get(&doc), Some(&json!(2)));
+}
+
+#[test]
+fn remove_shifts_later_elements() {
+    let mut doc = json!({"users": [{"n": "a", "x": 1}, {"n": "b"}, {"n": "c", "x": 2}]});
+    let mut cursors: Vec<Cursor> = find("$.users[?@.x].n", &doc)
+        .unwrap()
+        .iter()
+        .map(Cursor::from)
+        .collect();
+
+    let edit = Edit::Remove {
+        path: vec![Name(String::from("users")), Index(1)],
+    };
+    apply(&mut doc, &edit, &mut cursors).unwrap();
+
+    assert_eq!(cursors[0].path(), "$['users'][0]['n']");
+    assert_eq!(cursors[1].path(), "$['users'][1]['n']");
+    assert_eq!(cursors[1].get(&doc), Some(&json!("c")));
+}
+
+#[test]
+fn remove_each_match() {
+    let mut doc = json!([1, 5, 2, 6, 7]);
+    let mut cursors: Vec<Cursor> = find("$[?@ > 4]", &doc)
+        .unwrap()
+        .iter()
+        .map(Cursor::from)
+        .collect();
+
+    for i in 0..cursors.len() {
+        let edit = Edit::Remove {
+            path: cursors[i].elements().to_vec(),
+        };
+        apply(&mut doc, &edit, &mut cursors).unwrap();
+        assert!(!cursors[i].is_valid());
+    }
+
+    assert_eq!(doc, json!([1, 2]));
+}
+
+#[test]
+fn insert_shifts_elements_at_and_after_index() {
+    let mut doc = json!([["a", "b", "c"]]);
+    let mut cursors = vec![
+        Cursor::new(vec![Index(0), Index(0)]),
+        Cursor::new(vec![Index(0), Index(1)]),
+        Cursor::new(vec![Index(0), Index(2)]),
+    ];
+
+    let edit = Edit::Add {
+        path: vec![Index(0), Index(1)],
+        value: json!("x"),
+    };
+    apply(&mut doc, &edit, &mut cursors).unwrap();
+
+    let values: Vec<_> = cursors.iter().map(|c| c.get(&doc).unwrap()).collect();
+    assert_eq!(values, vec!["a", "b", "c"]);
+    assert_eq!(cursors[1].path(), "$[0][2]");
+}
+
+#[test]
+fn replace_invalidates_descendants() {
+    let mut doc = json!({"a": {"b": [1, 2]}});
+    let mut cursors = vec![
+        Cursor::new(vec![Name(String::from("a"))]),
+        Cursor::new(vec![
+            Name(String::from("a")),
+            Name(String::from("b")),
+            Index(0),
+        ]),
+    ];
+
+    let edit = Edit::Replace {
+        path: vec![Name(String::from("a"))],
+        value: json!({"b": []}),
+    };
+    apply(&mut doc, &edit, &mut cursors).unwrap();
+
+    assert!(cursors[0].is_valid());
+    assert_eq!(cursors[0].get(&doc), Some(&json!({"b": []})));
+    assert!(!cursors[1].is_valid());
+    assert_eq!(cursors[1].get(&doc), None);
+}
+
+#[test]
+fn unrelated_edits_leave_cursor_alone() {
+    let mut doc = json!({"a": [1, 2], "b": [3, 4]});
+    let mut cursors = vec![Cursor::new(vec![Name(String::from("b")), Index(1)])];
+
+    let edit = Edit::Remove {
+        path: vec![Name(String::from("a")), Index(0)],
+    };
+    apply(&mut doc, &edit, &mut cursors).unwrap();
+
+    assert_eq!(cursors[0].path(), "$['b'][1]");
+    assert_eq!(cursors[0].get(&doc), Some(&json!(4)));
+}
+
+#[test]
+fn edit_missing_value() {
+    let mut doc = json!({"a": [1]});
+    let edit = Edit::Remove {
+        path: vec![Name(String::from("a")), Index(3)],
+    };
+    let err = edit.apply(&mut doc).unwrap_err();
+    assert_eq!(err.msg, "no value at $['a'][3]");
+}
+
+#[test]
+fn replace_array_element() {
+    let mut doc = json!([[1, 2], [3]]);
+    let mut cursors = vec![
+        Cursor::new(vec![Index(0)]),
+        Cursor::new(vec![Index(0), Index(1)]),
+        Cursor::new(vec![Index(1), Index(0)]),
+    ];
+
+    let edit = Edit::Replace {
+        path: vec![Index(0)],
+        value: json!("x"),
+    };
+    apply(&mut doc, &edit, &mut cursors).unwrap();
+
+    assert_eq!(cursors[0].get(&doc), Some(&json!("x")));
+    assert!(!cursors[1].is_valid());
+    assert_eq!(cursors[2].get(&doc), Some(&json!(3)));
+}
+
+#[test]
+fn replace_missing_array_element() {
+    let mut doc = json!({"a": [1]});
+    let edit = Edit::Replace {
+        path: vec![Name(String::from("a")), Index(1)],
+        value: json!(2),
+    };
+    let err = edit.apply(&mut doc).unwrap_err();
+    assert_eq!(err.msg, "no value at $['a'][1]");
+    assert_eq!(doc, json!({"a": [1]}));
+}
+
+#[test]
+fn cursor_paths_are_escaped() {
+    let cursor = Cursor::new(vec![Name(String::from("it's")), Index(0)]);
+    assert_eq!(cursor.path(), r"$['it\'s'][0]");
+    assert_eq!(cursor.to_string(), cursor.path());
+}
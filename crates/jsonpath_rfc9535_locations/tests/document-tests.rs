@@ -0,0 +1,87 @@
+use jsonpath_rfc9535_locations::{
+    document::DuplicateKeys, env::Environment, errors::JSONPathErrorType, Query,
+};
+use serde::de::value::{Error, MapDeserializer};
+use serde_json::json;
+
+const DATA: &str = r#"{"items": [{"id": 1, "id": 2}, {"id": 3}], "items": []}"#;
+
+fn env(duplicate_keys: DuplicateKeys) -> &'static Environment {
+    Box::leak(Box::new(
+        Environment::new().with_duplicate_keys(duplicate_keys),
+    ))
+}
+
+#[test]
+fn last_wins_by_default() {
+    let env: &'static Environment = Box::leak(Box::default());
+    assert_eq!(env.duplicate_keys, DuplicateKeys::Last);
+    assert_eq!(env.parse_document(DATA).unwrap(), json!({"items": []}));
+}
+
+#[test]
+fn first_wins() {
+    let env = env(DuplicateKeys::First);
+    let value = env.parse_document(DATA).unwrap();
+    assert_eq!(value, json!({"items": [{"id": 1}, {"id": 3}]}));
+
+    let query = Query::standard("$.items[?@.id > 1]").unwrap();
+    let nodes = query.find(&value, env);
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].path(), "$['items'][1]");
+}
+
+#[test]
+fn duplicates_are_errors() {
+    let env = env(DuplicateKeys::Error);
+    let err = env.parse_document(DATA).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::DocumentError));
+    assert!(
+        err.msg.contains("duplicate member name `id`"),
+        "{}",
+        err.msg
+    );
+
+    assert_eq!(
+        env.parse_document(r#"{"a": {"b": 1}, "b": [{"a": 2}]}"#)
+            .unwrap(),
+        json!({"a": {"b": 1}, "b": [{"a": 2}]})
+    );
+}
+
+#[test]
+fn invalid_documents() {
+    let env = env(DuplicateKeys::Last);
+    for json in ["", "{", "[1,]", "1 2"] {
+        let err = env.parse_document(json).unwrap_err();
+        assert!(
+            matches!(err.kind, JSONPathErrorType::DocumentError),
+            "{}",
+            json
+        );
+    }
+}
+
+/// A data format other than JSON that can produce duplicate names.
+fn pairs() -> MapDeserializer<'static, std::vec::IntoIter<(&'static str, i64)>, Error> {
+    MapDeserializer::new(vec![("a", 1), ("b", 2), ("a", 3)].into_iter())
+}
+
+#[test]
+fn custom_backend() {
+    assert_eq!(
+        env(DuplicateKeys::First)
+            .deserialize_document(pairs())
+            .unwrap(),
+        json!({"a": 1, "b": 2})
+    );
+    assert_eq!(
+        env(DuplicateKeys::Last)
+            .deserialize_document(pairs())
+            .unwrap(),
+        json!({"a": 3, "b": 2})
+    );
+    assert!(env(DuplicateKeys::Error)
+        .deserialize_document(pairs())
+        .is_err());
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "derive")]
+
+use jsonpath_rfc9535_locations::JsonPathExtract;
+use serde_json::json;
+
+#[derive(Debug, PartialEq, JsonPathExtract)]
+struct Order {
+    #[jsonpath("$.id")]
+    id: u64,
+    #[jsonpath("$.customer.email")]
+    email: Option<String>,
+    #[jsonpath("$.lines[*].sku")]
+    skus: Vec<String>,
+    #[jsonpath("$.lines[?@.qty > 1].qty")]
+    bulk: Vec<i64>,
+}
+
+#[test]
+fn extract_struct() {
+    let value = json!({
+        "id": 42,
+        "customer": {"email": "sue@example.com"},
+        "lines": [{"sku": "a", "qty": 1}, {"sku": "b", "qty": 3}]
+    });
+    assert_eq!(
+        Order::extract(&value).unwrap(),
+        Order {
+            id: 42,
+            email: Some(String::from("sue@example.com")),
+            skus: vec![String::from("a"), String::from("b")],
+            bulk: vec![3],
+        }
+    );
+}
+
+#[test]
+fn missing_optional_and_empty_vec() {
+    let value = json!({"id": 1, "lines": []});
+    assert_eq!(
+        Order::extract(&value).unwrap(),
+        Order {
+            id: 1,
+            email: None,
+            skus: vec![],
+            bulk: vec![],
+        }
+    );
+}
+
+#[test]
+fn missing_required() {
+    let value = json!({"lines": []});
+    let err = Order::extract(&value).unwrap_err();
+    assert_eq!(
+        err.msg,
+        "expected a value for `id` at $['id'], found nothing"
+    );
+}
+
+#[test]
+fn wrong_type() {
+    let value = json!({"id": "x", "lines": []});
+    let err = Order::extract(&value).unwrap_err();
+    assert!(
+        err.msg
+            .starts_with("can't extract `id` from $['id']: invalid type"),
+        "{}",
+        err.msg
+    );
+}
@@ -0,0 +1,72 @@
+use jsonpath_rfc9535_locations::{find, find_at, path::NormalizedPath, resolve_path, Query};
+use serde_json::json;
+
+#[test]
+fn locations_start_at_base_path() {
+    let value = json!({"store": {"book": [{"title": "a"}, {"title": "b", "price": 5}]}});
+    let base: NormalizedPath = "$.store.book".parse().unwrap();
+    let subtree = resolve_path(&base, &value).unwrap();
+
+    let nodes = find_at("$[?@.price < 10].title", subtree, &base).unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].value, &json!("b"));
+    assert_eq!(nodes[0].path(), "$['store']['book'][1]['title']");
+    assert_eq!(nodes[0].to_json_pointer(), "/store/book/1/title");
+
+    // The same paths as querying the whole document.
+    let paths = |nodes: &[jsonpath_rfc9535_locations::node::Node]| -> Vec<String> {
+        nodes.iter().map(|node| node.path()).collect()
+    };
+    assert_eq!(
+        paths(&find_at("$..title", subtree, &base).unwrap()),
+        paths(&find("$.store.book..title", &value).unwrap())
+    );
+}
+
+#[test]
+fn root_path() {
+    let value = json!({"a": [1, 2]});
+    let base: NormalizedPath = "$".parse().unwrap();
+    let nodes = find_at("$.a[1]", &value, &base).unwrap();
+    assert_eq!(nodes[0].path(), "$['a'][1]");
+}
+
+#[test]
+fn root_identifier_refers_to_subtree() {
+    let value = json!({"limits": {"max": 2, "items": [1, 2, 3]}});
+    let base: NormalizedPath = "$['limits']".parse().unwrap();
+    let subtree = &value["limits"];
+
+    let nodes = find_at("$.items[?@ <= $.max]", subtree, &base).unwrap();
+    let paths: Vec<String> = nodes.iter().map(|node| node.path()).collect();
+    assert_eq!(
+        paths,
+        vec!["$['limits']['items'][0]", "$['limits']['items'][1]"]
+    );
+}
+
+#[test]
+fn find_at_fails_on_invalid_query() {
+    let base: NormalizedPath = "$".parse().unwrap();
+    assert!(find_at("$[", &json!({}), &base).is_err());
+}
+
+#[test]
+fn query_from_node() {
+    let value =
+        json!({"users": [{"name": "a", "roles": ["x"]}, {"name": "b", "roles": ["y", "z"]}]});
+    let users = find("$.users[?count(@.roles[*]) > 1]", &value).unwrap();
+    let user = users.exactly_one().unwrap();
+
+    let query = Query::standard("$.roles[?@ != $.name]").unwrap();
+    let roles = user.query(&query);
+    assert_eq!(
+        roles.paths(),
+        vec!["$['users'][1]['roles'][0]", "$['users'][1]['roles'][1]"]
+    );
+    assert_eq!(roles.values(), vec!["y", "z"]);
+
+    // Nodes selected from a node can be queried in turn.
+    let last = roles[1].query(&Query::standard("$").unwrap());
+    assert_eq!(last.paths(), vec!["$['users'][1]['roles'][1]"]);
+}
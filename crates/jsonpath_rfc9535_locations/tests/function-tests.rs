@@ -0,0 +1,195 @@
+use std::borrow::Cow;
+
+use jsonpath_rfc9535_locations::{
+    env::Environment,
+    errors::JSONPathErrorType,
+    filter::FilterExpressionResult,
+    function::{ExpressionType, FunctionContext, FunctionExtension, FunctionSignature},
+    JSONPathParser, ENV,
+};
+use serde_json::{json, Value};
+
+/// `path()`, the normalized path of the current node.
+struct Path;
+
+impl FunctionExtension for Path {
+    fn call<'a>(
+        &self,
+        _args: Vec<FilterExpressionResult<'a>>,
+        context: &FunctionContext<'_, 'a>,
+    ) -> FilterExpressionResult<'a> {
+        match context.path() {
+            Some(path) => FilterExpressionResult::String(Cow::Owned(path)),
+            None => FilterExpressionResult::Nothing,
+        }
+    }
+
+    fn reads_location(&self) -> bool {
+        true
+    }
+}
+
+/// `over_limit(value)`, true if `value` is a number greater than a
+/// configured limit.
+struct OverLimit {
+    limit: f64,
+}
+
+impl FunctionExtension for OverLimit {
+    fn call<'a>(
+        &self,
+        args: Vec<FilterExpressionResult<'a>>,
+        _context: &FunctionContext<'_, 'a>,
+    ) -> FilterExpressionResult<'a> {
+        let over = match args.first() {
+            Some(FilterExpressionResult::Int(i)) => *i as f64 > self.limit,
+            Some(FilterExpressionResult::Float(f)) => *f > self.limit,
+            _ => false,
+        };
+        FilterExpressionResult::Bool(over)
+    }
+}
+
+/// `root_size()`, the number of members or elements in the query argument.
+struct RootSize;
+
+impl FunctionExtension for RootSize {
+    fn call<'a>(
+        &self,
+        _args: Vec<FilterExpressionResult<'a>>,
+        context: &FunctionContext<'_, 'a>,
+    ) -> FilterExpressionResult<'a> {
+        let size = match context.root {
+            Value::Array(arr) => arr.len(),
+            Value::Object(obj) => obj.len(),
+            _ => 0,
+        };
+        FilterExpressionResult::Int(size as i64)
+    }
+}
+
+fn setup() -> (JSONPathParser, &'static Environment) {
+    let mut env = Environment::new();
+    env.function_register.register(
+        "path",
+        FunctionSignature {
+            param_types: vec![],
+            return_type: ExpressionType::Value,
+            deterministic: false,
+            pure: false,
+            cost: None,
+        },
+        Box::new(Path),
+    );
+    env.function_register.register(
+        "over_limit",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value],
+            return_type: ExpressionType::Logical,
+            deterministic: true,
+            pure: true,
+            cost: None,
+        },
+        Box::new(OverLimit { limit: 10.0 }),
+    );
+    env.function_register.register(
+        "root_size",
+        FunctionSignature {
+            param_types: vec![],
+            return_type: ExpressionType::Value,
+            deterministic: false,
+            pure: false,
+            cost: None,
+        },
+        Box::new(RootSize),
+    );
+
+    let env: &'static Environment = Box::leak(Box::new(env));
+    (env.parser(), env)
+}
+
+fn find(query: &str, value: &Value) -> Vec<String> {
+    let (parser, env) = setup();
+    parser
+        .parse(query)
+        .unwrap()
+        .find(value, env)
+        .iter()
+        .map(|node| node.path())
+        .collect()
+}
+
+#[test]
+fn current_path() {
+    let value = json!({"a": [{"b": 1}, {"b": 2}]});
+    assert_eq!(
+        find("$.a[?path() == \"$['a'][1]\"]", &value),
+        vec!["$['a'][1]"]
+    );
+    assert_eq!(
+        find("$..[?match(path(), '.*\\\\[0\\\\]')]", &value),
+        vec!["$['a'][0]"]
+    );
+}
+
+#[test]
+fn nested_filters_see_absolute_paths() {
+    let value = json!({"a": [{"b": [5]}, {"b": [6]}]});
+    assert_eq!(
+        find("$.a[?@.b[?path() == \"$['a'][1]['b'][0]\"]]", &value),
+        vec!["$['a'][1]"]
+    );
+}
+
+#[test]
+fn configured_function() {
+    let value = json!([3, 12, 10.5, "20"]);
+    assert_eq!(find("$[?over_limit(@)]", &value), vec!["$[1]", "$[2]"]);
+}
+
+#[test]
+fn root_value() {
+    let value = json!([1, 2, 3]);
+    assert_eq!(find("$[?@ == root_size()]", &value), vec!["$[2]"]);
+}
+
+#[test]
+fn planned_counts_keep_paths() {
+    let (parser, env) = setup();
+    let value = json!({"a": [1, 2], "b": [3]});
+    let query = parser
+        .parse("$[?count(@[?path() == \"$['a'][1]\"]) > 0]")
+        .unwrap()
+        .plan(env);
+    let paths: Vec<String> = query
+        .find(&value, env)
+        .iter()
+        .map(|node| node.path())
+        .collect();
+    assert_eq!(paths, vec!["$['a']"]);
+}
+
+#[test]
+fn count_with_path() {
+    let (parser, env) = setup();
+    let value = json!([1, 2]);
+    let query = parser.parse("$[?path() == '$[0]']").unwrap();
+    assert_eq!(query.find(&value, env).len(), 1);
+    assert_eq!(query.count_up_to(&value, env, 10), 1);
+    assert_eq!(query.count_up_to(&value, env, 0), 0);
+}
+
+#[test]
+fn function_missing_from_environment() {
+    let (parser, _) = setup();
+    let value = json!([1, 2, 3]);
+    let query = parser.parse("$[?@ == root_size()]").unwrap();
+
+    // The query was parsed with `root_size`, but the default environment
+    // doesn't have it, so the function evaluates to nothing.
+    assert!(query.find(&value, &ENV).is_empty());
+
+    let err = query.try_find(&value, &ENV).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::NameError));
+    assert_eq!(err.msg, "unknown function `root_size`");
+}
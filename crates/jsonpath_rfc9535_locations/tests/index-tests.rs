@@ -0,0 +1,35 @@
+use jsonpath_rfc9535_locations::{find, resolve_index};
+use serde_json::json;
+
+#[test]
+fn resolve_in_range() {
+    assert_eq!(resolve_index(0, 3), Some(0));
+    assert_eq!(resolve_index(2, 3), Some(2));
+    assert_eq!(resolve_index(-1, 3), Some(2));
+    assert_eq!(resolve_index(-3, 3), Some(0));
+}
+
+#[test]
+fn resolve_out_of_range() {
+    assert_eq!(resolve_index(3, 3), None);
+    assert_eq!(resolve_index(-4, 3), None);
+    assert_eq!(resolve_index(0, 0), None);
+    assert_eq!(resolve_index(-1, 0), None);
+    assert_eq!(resolve_index(i64::MAX, 3), None);
+    assert_eq!(resolve_index(i64::MIN, 3), None);
+    assert_eq!(resolve_index(-(1 << 32), 3), None);
+}
+
+#[test]
+fn extreme_indices_select_nothing() {
+    let data = json!([1, 2, 3]);
+    for query in [
+        "$[9007199254740991]",
+        "$[-9007199254740991]",
+        "$[4294967296]",
+        "$[-4294967296]",
+        "$[?@ == $[4294967296]]",
+    ] {
+        assert!(find(query, &data).unwrap().is_empty(), "{}", query);
+    }
+}
@@ -0,0 +1,55 @@
+use jsonpath_rfc9535_locations::join;
+use serde_json::{json, Value};
+
+const DATA: &str = r#"
+{
+    "products": [
+        {"name": "foo", "price": 10},
+        {"name": "bar"},
+        {"name": "baz", "price": 30}
+    ]
+}"#;
+
+#[test]
+fn join_siblings() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let pairs = join("$.products[*].name", "$.products[*].price", &value).unwrap();
+    let rv: Vec<(&Value, &Value)> = pairs.iter().map(|(a, b)| (a.value, b.value)).collect();
+    assert_eq!(
+        rv,
+        vec![(&json!("foo"), &json!(10)), (&json!("baz"), &json!(30))]
+    );
+}
+
+#[test]
+fn join_different_depths() {
+    let value = json!({
+        "orders": [
+            {"id": 1, "lines": [{"sku": "a"}, {"sku": "b"}]},
+            {"id": 2, "lines": [{"sku": "c"}]}
+        ]
+    });
+    let pairs = join("$.orders[*].id", "$..sku", &value).unwrap();
+    let rv: Vec<(&Value, &Value)> = pairs.iter().map(|(a, b)| (a.value, b.value)).collect();
+    assert_eq!(
+        rv,
+        vec![
+            (&json!(1), &json!("a")),
+            (&json!(1), &json!("b")),
+            (&json!(2), &json!("c"))
+        ]
+    );
+}
+
+#[test]
+fn join_no_results() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let pairs = join("$.products[*].name", "$.nosuchthing", &value).unwrap();
+    assert!(pairs.is_empty());
+}
+
+#[test]
+fn join_syntax_error() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    assert!(join("$.products[*].name", "$.products[", &value).is_err());
+}
@@ -0,0 +1,163 @@
+use std::borrow::Cow;
+
+use jsonpath_rfc9535_locations::{
+    env::Environment,
+    filter::FilterExpressionResult,
+    find,
+    function::{
+        ExpressionType, FunctionContext, FunctionExtension, FunctionRegister, FunctionSignature,
+    },
+    json_like::{self, JsonLike},
+    Query, STANDARD_ENV,
+};
+use serde_json::{json, Value};
+
+fn value() -> Value {
+    json!({
+        "store": {
+            "book": [
+                {"title": "a", "price": 8, "tags": ["x"], "isbn": "0-553-21311-3"},
+                {"title": "b", "price": 12.5, "tags": ["y", "z"]},
+                {"title": "c", "price": 22, "meta": {"tags": ["y", "z"]}}
+            ],
+            "bicycle": {"price": 19, "color": "red"},
+            "big": 18446744073709551615u64,
+            "nothing": null
+        }
+    })
+}
+
+const QUERIES: [&str; 24] = [
+    "$",
+    "$.store.book[0].title",
+    "$.store.book[-1]",
+    "$.store.book[::-1].title",
+    "$.store.book[1:]",
+    "$.store.*",
+    "$..price",
+    "$..*",
+    "$..[0]",
+    "$.store.book[?@.price < 10].title",
+    "$.store.book[?@.price >= 12.5]",
+    "$.store.book[?@.isbn]",
+    "$.store.book[?!@.isbn].title",
+    "$.store.book[?@.tags == $.store.book[2].meta.tags]",
+    "$.store.book[?@.tags != $.store.book[2].meta.tags]",
+    "$.store.book[?count(@.tags[*]) > 1]",
+    "$.store.book[?length(@.title) == 1 && length(@.tags) == 2]",
+    "$.store.book[?match(@.title, '[ab]')].title",
+    "$.store.book[?search(@.isbn, '553')].title",
+    "$.store.book[?value(@..price) > 20]",
+    "$.store[?@ == null]",
+    "$.store[?@ > 18446744073709551614]",
+    "$.store[?@ == 18446744073709551615]",
+    "$.store.book[?@.price > 1 || @.missing][?@ == 'x']",
+];
+
+#[test]
+fn same_nodes_as_find() {
+    let value = value();
+    for query in QUERIES {
+        let expected: Vec<(String, &Value)> = find(query, &value)
+            .unwrap()
+            .iter()
+            .map(|node| (node.path(), node.value))
+            .collect();
+        let got: Vec<(String, &Value)> = json_like::find(query, &value)
+            .unwrap()
+            .iter()
+            .map(|node| (node.path(), node.value))
+            .collect();
+        assert_eq!(got, expected, "{query}");
+    }
+}
+
+#[test]
+fn compiled_query() {
+    let value = value();
+    let query = Query::standard("$.store.bicycle.color").unwrap();
+    let nodes = query.find(&value, &STANDARD_ENV);
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].value, &json!("red"));
+}
+
+#[test]
+fn arrays_and_objects_compare_by_value() {
+    let value = json!([[1, 2], {"a": [1, 2.0]}, {"a": [1, 2], "b": 3}, [2, 1]]);
+    let paths = |query| -> Vec<String> {
+        json_like::find(query, &value)
+            .unwrap()
+            .iter()
+            .map(|node| node.path())
+            .collect()
+    };
+    assert_eq!(paths("$[?@ == $[0]]"), vec!["$[0]"]);
+    assert_eq!(paths("$[?@.a == $[0]]"), vec!["$[1]", "$[2]"]);
+    assert_eq!(paths("$[?@ == $[1]]"), vec!["$[1]"]);
+}
+
+#[test]
+fn string_values_are_borrowed() {
+    let value = json!("abc");
+    assert!(matches!(
+        FilterExpressionResult::from_json_value(&value),
+        FilterExpressionResult::String(Cow::Borrowed("abc"))
+    ));
+}
+
+/// `typeof(value)`, the JSON type name of `value`, for any value type.
+struct TypeOf;
+
+impl<J: JsonLike> FunctionExtension<J> for TypeOf {
+    fn call<'a>(
+        &self,
+        args: Vec<FilterExpressionResult<'a, J>>,
+        _context: &FunctionContext<'_, 'a, FunctionRegister<J>, J>,
+    ) -> FilterExpressionResult<'a, J> {
+        let name = match args.first() {
+            Some(FilterExpressionResult::Array(_)) => "array",
+            Some(FilterExpressionResult::Object(_)) => "object",
+            Some(FilterExpressionResult::String(_)) => "string",
+            Some(FilterExpressionResult::Null) => "null",
+            Some(FilterExpressionResult::Bool(_)) => "boolean",
+            Some(FilterExpressionResult::Nothing) | None => return FilterExpressionResult::Nothing,
+            Some(_) => "number",
+        };
+        FilterExpressionResult::String(Cow::Borrowed(name))
+    }
+}
+
+#[test]
+fn function_extensions_see_json_like_values() {
+    let mut functions = FunctionRegister::new();
+    functions.register(
+        "typeof",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value],
+            return_type: ExpressionType::Value,
+            deterministic: true,
+            pure: true,
+            cost: None,
+        },
+        Box::new(TypeOf),
+    );
+    let env: &'static Environment = Box::leak(Box::new(Environment::with_functions(functions)));
+
+    let value = value();
+    let query = env
+        .parser()
+        .parse("$.store.book[*][?typeof(@) == 'array']")
+        .unwrap();
+    let paths: Vec<String> = query
+        .find(&value, env)
+        .iter()
+        .map(|node| node.path())
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "$['store']['book'][0]['tags']",
+            "$['store']['book'][1]['tags']"
+        ]
+    );
+}
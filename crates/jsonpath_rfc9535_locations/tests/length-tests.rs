@@ -0,0 +1,76 @@
+use jsonpath_rfc9535_locations::find;
+use serde_json::{json, Value};
+
+fn values(query: &str, value: &Value) -> Vec<Value> {
+    find(query, value)
+        .unwrap()
+        .iter()
+        .map(|node| node.value.clone())
+        .collect()
+}
+
+fn data() -> Value {
+    json!({
+        "threshold": 2,
+        "items": [
+            {"id": 1, "v": "abc"},
+            {"id": 2, "v": "ü"},
+            {"id": 3, "v": [1, 2, 3]},
+            {"id": 4, "v": []},
+            {"id": 5, "v": {"a": 1, "b": 2}},
+            {"id": 6, "v": 7},
+            {"id": 7, "v": null},
+            {"id": 8},
+        ]
+    })
+}
+
+fn ids(query: &str) -> Vec<Value> {
+    values(query, &data())
+        .into_iter()
+        .map(|item| item["id"].clone())
+        .collect()
+}
+
+#[test]
+fn length_of_string() {
+    assert_eq!(ids("$.items[?length(@.v) == 3]"), vec![json!(1), json!(3)]);
+    assert_eq!(ids("$.items[?length(@.v) == 1]"), vec![json!(2)]);
+}
+
+#[test]
+fn length_of_array_and_object() {
+    assert_eq!(ids("$.items[?length(@.v) == 0]"), vec![json!(4)]);
+    assert_eq!(ids("$.items[?length(@.v) == 2]"), vec![json!(5)]);
+}
+
+#[test]
+fn length_of_nothing() {
+    // Numbers, null and missing members have no length.
+    assert_eq!(
+        ids("$.items[?length(@.v) == length(@.nosuchthing)]"),
+        vec![json!(6), json!(7), json!(8)]
+    );
+}
+
+#[test]
+fn length_of_root_query() {
+    let big = json!({"big": vec![0; 10_000], "items": [1, 2]});
+    assert_eq!(
+        values("$.items[?length($.big) > 9999]", &big),
+        vec![json!(1), json!(2)]
+    );
+    assert_eq!(
+        values("$.items[?length($.big[-1]) > 0]", &big),
+        Vec::<Value>::new()
+    );
+}
+
+#[test]
+fn length_of_nested_members() {
+    let value = json!([{"a": {"b": [1, 2]}}, {"a": {"b": [1]}}, {"a": [[1, 2]]}]);
+    assert_eq!(
+        values("$[?length(@.a.b) == 2 || length(@.a[0]) == 2]", &value),
+        vec![json!({"a": {"b": [1, 2]}}), json!({"a": [[1, 2]]})]
+    );
+}
@@ -0,0 +1,47 @@
+use jsonpath_rfc9535_locations::{find, Query, ENV};
+use serde_json::json;
+
+fn memory(expr: &str) -> usize {
+    Query::standard(expr).unwrap().approx_memory()
+}
+
+#[test]
+fn larger_queries_use_more_memory() {
+    assert!(memory("$") > 0);
+    assert!(memory("$.a") > memory("$"));
+    assert!(memory("$.a.b") > memory("$.a"));
+    assert!(memory("$[?@.a == 1]") > memory("$.a"));
+    assert!(memory("$[?count(@..*) > $.limit]") > memory("$[?@.a == 1]"));
+    assert_eq!(memory("$.a[?@.b]"), memory("$.a[?@.b]"));
+
+    let long_name = format!("$.{}", "a".repeat(1000));
+    assert!(memory(&long_name) >= 1000);
+    assert!(memory(&long_name) < 2000);
+}
+
+#[test]
+fn empty_node_lists_use_little_memory() {
+    let value = json!({"a": 1});
+    let nodes = find("$.b", &value).unwrap();
+    assert!(nodes.approx_memory() < 128);
+}
+
+#[test]
+fn shared_locations_are_counted_once() {
+    let name = "x".repeat(1000);
+    let value = json!({ name.clone(): (0..100).collect::<Vec<i32>>() });
+
+    let nodes = Query::standard(&format!("$.{}[*]", name))
+        .unwrap()
+        .find(&value, &ENV);
+    assert_eq!(nodes.len(), 100);
+
+    let memory = nodes.approx_memory();
+    assert!(memory > 1000);
+    assert!(memory < 1000 + 100 * 256, "{}", memory);
+
+    let fewer = Query::standard(&format!("$.{}[:10]", name))
+        .unwrap()
+        .find(&value, &ENV);
+    assert!(fewer.approx_memory() < memory);
+}
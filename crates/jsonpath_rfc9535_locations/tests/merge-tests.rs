@@ -0,0 +1,93 @@
+use jsonpath_rfc9535_locations::{
+    find,
+    order::{merge, DocumentIndex},
+    Query, ENV,
+};
+use serde_json::{json, Value};
+
+fn queries(exprs: &[&str]) -> Vec<Query> {
+    exprs
+        .iter()
+        .map(|expr| Query::standard(expr).unwrap())
+        .collect()
+}
+
+fn merged(exprs: &[&str], value: &Value) -> Vec<(usize, String)> {
+    merge(&queries(exprs), value, &ENV)
+        .map(|(i, node)| (i, node.path()))
+        .collect()
+}
+
+#[test]
+fn merge_in_document_order() {
+    let value = json!({"a": [1, {"b": 2}], "c": 3});
+    assert_eq!(
+        merged(&["$.c", "$..b", "$.a[1, 0]"], &value),
+        vec![
+            (2, "$['a'][0]".to_owned()),
+            (2, "$['a'][1]".to_owned()),
+            (1, "$['a'][1]['b']".to_owned()),
+            (0, "$['c']".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn same_node_from_several_queries() {
+    let value = json!([1, 2]);
+    assert_eq!(
+        merged(&["$[1]", "$[*]", "$[1]"], &value),
+        vec![
+            (1, "$[0]".to_owned()),
+            (0, "$[1]".to_owned()),
+            (1, "$[1]".to_owned()),
+            (2, "$[1]".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn parents_before_children() {
+    let value = json!({"a": {"b": {"c": 1}}});
+    assert_eq!(
+        merged(&["$..c", "$.a", "$..b"], &value),
+        vec![
+            (1, "$['a']".to_owned()),
+            (2, "$['a']['b']".to_owned()),
+            (0, "$['a']['b']['c']".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn empty_queries() {
+    let value = json!({"a": 1});
+    assert!(merged(&[], &value).is_empty());
+    assert_eq!(
+        merged(&["$.x", "$.a", "$.y"], &value),
+        vec![(1, "$['a']".to_owned())]
+    );
+}
+
+#[test]
+fn size_hint() {
+    let value = json!([1, 2, 3]);
+    let mut it = merge(&queries(&["$[*]", "$[0]"]), &value, &ENV);
+    assert_eq!(it.size_hint(), (4, Some(4)));
+    it.next();
+    assert_eq!(it.size_hint(), (3, Some(3)));
+}
+
+#[test]
+fn document_index() {
+    let value = json!({"a": [true, false]});
+    let index = DocumentIndex::new(&value);
+    assert_eq!(index.position(&value), Some(0));
+    assert_eq!(index.position(&value["a"][1]), Some(3));
+    assert_eq!(index.position(&json!(true)), None);
+
+    let mut nodes = find("$.a[1, 0]", &value).unwrap();
+    index.sort(&mut nodes);
+    let paths: Vec<String> = nodes.iter().map(|node| node.path()).collect();
+    assert_eq!(paths, vec!["$['a'][0]", "$['a'][1]"]);
+}
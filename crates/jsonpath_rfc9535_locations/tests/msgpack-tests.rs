@@ -0,0 +1,98 @@
+#![cfg(feature = "msgpack")]
+
+use jsonpath_rfc9535_locations::{document::DuplicateKeys, env::Environment, msgpack};
+use rmpv::Value as MsgPackValue;
+use serde_json::json;
+
+fn document() -> MsgPackValue {
+    MsgPackValue::Map(vec![
+        (
+            MsgPackValue::from("readings"),
+            MsgPackValue::Array(vec![
+                MsgPackValue::Map(vec![
+                    (MsgPackValue::from("id"), MsgPackValue::from(1)),
+                    (MsgPackValue::from("temp"), MsgPackValue::F32(21.5)),
+                ]),
+                MsgPackValue::Map(vec![
+                    (MsgPackValue::from("id"), MsgPackValue::from(u64::MAX)),
+                    (MsgPackValue::from("temp"), MsgPackValue::F64(f64::INFINITY)),
+                ]),
+            ]),
+        ),
+        (
+            MsgPackValue::from(7),
+            MsgPackValue::Binary(vec![0xfb, 0xff]),
+        ),
+    ])
+}
+
+#[test]
+fn to_json() {
+    assert_eq!(
+        msgpack::to_json(&document()).unwrap(),
+        json!({
+            "readings": [{"id": 1, "temp": 21.5}, {"id": u64::MAX, "temp": null}],
+            "7": "-_8"
+        })
+    );
+}
+
+#[test]
+fn find_borrows_msgpack_values() {
+    let doc = document();
+    let rv = msgpack::find("$.readings[?@.temp > 20].id", &doc).unwrap();
+    assert_eq!(
+        rv,
+        vec![(
+            String::from("$['readings'][0]['id']"),
+            &MsgPackValue::from(1)
+        )]
+    );
+}
+
+#[test]
+fn find_integer_key() {
+    let doc = document();
+    let rv = msgpack::find("$['7']", &doc).unwrap();
+    assert_eq!(
+        rv,
+        vec![(
+            String::from("$['7']"),
+            &MsgPackValue::Binary(vec![0xfb, 0xff])
+        )]
+    );
+}
+
+#[test]
+fn unsupported_key() {
+    let doc = MsgPackValue::Map(vec![(MsgPackValue::Nil, MsgPackValue::Nil)]);
+    let err = msgpack::find("$.*", &doc).unwrap_err();
+    assert_eq!(err.msg, "MessagePack map keys must be strings or integers");
+}
+
+#[test]
+fn duplicate_keys() {
+    let doc = MsgPackValue::Map(vec![
+        (MsgPackValue::from(1), MsgPackValue::from("a")),
+        (MsgPackValue::from("1"), MsgPackValue::from("b")),
+    ]);
+
+    assert_eq!(msgpack::to_json(&doc).unwrap(), json!({"1": "b"}));
+    let rv = msgpack::find("$[?@ == 'b']", &doc).unwrap();
+    assert_eq!(rv, vec![(String::from("$['1']"), &MsgPackValue::from("b"))]);
+
+    let env: &'static Environment = Box::leak(Box::new(
+        Environment::new().with_duplicate_keys(DuplicateKeys::First),
+    ));
+    let rv = msgpack::find_with(env, "$['1']", &doc).unwrap();
+    assert_eq!(rv, vec![(String::from("$['1']"), &MsgPackValue::from("a"))]);
+    assert!(msgpack::find_with(env, "$[?@ == 'b']", &doc)
+        .unwrap()
+        .is_empty());
+
+    let env: &'static Environment = Box::leak(Box::new(
+        Environment::new().with_duplicate_keys(DuplicateKeys::Error),
+    ));
+    let err = msgpack::find_with(env, "$.*", &doc).unwrap_err();
+    assert_eq!(err.msg, "duplicate member name `1`");
+}
@@ -0,0 +1,292 @@
+use std::num::NonZeroUsize;
+
+use jsonpath_rfc9535_locations::{find, node::NodeList, page::PageCursor};
+use serde_json::{json, Value};
+
+const DATA: &str = r#"
+{
+    "products": [
+        {"name": "foo", "price": 10, "weight": 1.5},
+        {"name": "bar", "price": 20, "weight": 2},
+        {"name": "baz", "price": "n/a", "weight": null}
+    ]
+}"#;
+
+#[test]
+fn strings() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.products[*].name", &value).unwrap();
+    assert_eq!(nodes.as_strings().unwrap(), vec!["foo", "bar", "baz"]);
+}
+
+#[test]
+fn strings_type_error() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.products[*].price", &value).unwrap();
+    let err = nodes.as_strings().unwrap_err();
+    assert_eq!(
+        err.msg,
+        "expected a string at $['products'][0]['price'], found an integer"
+    );
+}
+
+#[test]
+fn i64s() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.products[0:2].price", &value).unwrap();
+    assert_eq!(nodes.as_i64s().unwrap(), vec![10, 20]);
+}
+
+#[test]
+fn i64s_type_error() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.products[*].price", &value).unwrap();
+    let err = nodes.as_i64s().unwrap_err();
+    assert_eq!(
+        err.msg,
+        "expected an integer at $['products'][2]['price'], found a string"
+    );
+}
+
+#[test]
+fn f64s() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.products[0:2].weight", &value).unwrap();
+    assert_eq!(nodes.as_f64s().unwrap(), vec![1.5, 2.0]);
+}
+
+#[test]
+fn f64s_type_error() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.products[*].weight", &value).unwrap();
+    let err = nodes.as_f64s().unwrap_err();
+    assert_eq!(
+        err.msg,
+        "expected a number at $['products'][2]['weight'], found null"
+    );
+}
+
+#[test]
+fn empty_node_list() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.nosuchthing", &value).unwrap();
+    assert!(nodes.as_strings().unwrap().is_empty());
+}
+
+#[test]
+fn group_by_parent() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.products[?@.price > 5]['name', 'price']", &value).unwrap();
+    let groups = nodes.group_by_parent();
+    assert_eq!(groups.len(), 2);
+
+    let first: Vec<&Value> = groups["$['products'][0]"].iter().map(|n| n.value).collect();
+    assert_eq!(first, vec![&json!("foo"), &json!(10)]);
+
+    let second: Vec<&Value> = groups["$['products'][1]"].iter().map(|n| n.value).collect();
+    assert_eq!(second, vec![&json!("bar"), &json!(20)]);
+}
+
+#[test]
+fn group_root_by_parent() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$", &value).unwrap();
+    let groups = nodes.group_by_parent();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups["$"].len(), 1);
+}
+
+#[test]
+fn group_by_computed_key() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.products[*].price", &value).unwrap();
+    let groups = nodes.group_by(|node| node.value.is_number());
+    assert_eq!(groups[&true].len(), 2);
+    assert_eq!(groups[&false].len(), 1);
+    assert_eq!(groups[&false][0].path(), "$['products'][2]['price']");
+}
+
+fn paths(nodes: &NodeList) -> Vec<String> {
+    nodes.iter().map(|node| node.path()).collect()
+}
+
+#[test]
+fn union() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let cheap = find("$.products[?@.price < 15]", &value).unwrap();
+    let light = find("$.products[?@.weight < 2]", &value).unwrap();
+    let both = find("$.products[0, 0]", &value).unwrap();
+
+    assert_eq!(paths(&cheap.union(&light)), vec!["$['products'][0]"]);
+    assert_eq!(
+        paths(&light.union(&find("$.products[1:]", &value).unwrap())),
+        vec!["$['products'][0]", "$['products'][1]", "$['products'][2]"]
+    );
+    assert_eq!(paths(&both.union(&both)), vec!["$['products'][0]"]);
+}
+
+#[test]
+fn intersection() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let priced = find("$.products[?@.price > 0]", &value).unwrap();
+    let weighed = find("$.products[?@.weight != null]", &value).unwrap();
+    let named = find("$.products[?@.name != 'foo']", &value).unwrap();
+
+    assert_eq!(
+        paths(&priced.intersection(&weighed)),
+        vec!["$['products'][0]", "$['products'][1]"]
+    );
+    assert_eq!(
+        paths(&priced.intersection(&weighed).intersection(&named)),
+        vec!["$['products'][1]"]
+    );
+    assert!(priced.intersection(&NodeList::new()).is_empty());
+}
+
+#[test]
+fn difference() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let all = find("$.products[*]", &value).unwrap();
+    let priced = find("$.products[?@.price > 0]", &value).unwrap();
+
+    assert_eq!(paths(&all.difference(&priced)), vec!["$['products'][2]"]);
+    assert!(priced.difference(&all).is_empty());
+
+    // Same values at different locations are different nodes.
+    let names = find("$.products[*].name", &value).unwrap();
+    let values = find("$..[?@ == 'foo']", &value).unwrap();
+    assert_eq!(names.difference(&values).len(), 2);
+}
+
+#[test]
+fn paginate_in_location_order() {
+    let page_size = NonZeroUsize::new(2).unwrap();
+    let value = json!({"b": [1, 2], "a": 3, "c": {"d": 4}});
+    let nodes = find("$..*", &value).unwrap();
+    let pages: Vec<Vec<String>> = nodes
+        .paginate(page_size)
+        .map(|page| page.nodes.iter().map(|node| node.path()).collect())
+        .collect();
+
+    assert_eq!(
+        pages,
+        vec![
+            vec!["$['a']", "$['b']"],
+            vec!["$['b'][0]", "$['b'][1]"],
+            vec!["$['c']", "$['c']['d']"],
+        ]
+    );
+}
+
+#[test]
+fn paginate_indices_numerically() {
+    let page_size = NonZeroUsize::new(3).unwrap();
+    let value = json!((0..12).collect::<Vec<i32>>());
+    let nodes = find("$[11, 2, 10]", &value).unwrap();
+    let page = nodes.paginate(page_size).next().unwrap();
+    let values: Vec<&Value> = page.nodes.iter().map(|node| node.value).collect();
+    assert_eq!(values, vec![&json!(2), &json!(10), &json!(11)]);
+    assert!(page.next.is_none());
+}
+
+#[test]
+fn paginate_without_duplicates() {
+    let page_size = NonZeroUsize::new(10).unwrap();
+    let value = json!(["a", "b"]);
+    let nodes = find("$[1, 0, 1, 0]", &value).unwrap();
+    assert_eq!(nodes.paginate(page_size).next().unwrap().nodes.len(), 2);
+}
+
+#[test]
+fn paginate_empty_list() {
+    let page_size = NonZeroUsize::new(5).unwrap();
+    assert!(NodeList::new().paginate(page_size).next().is_none());
+}
+
+#[test]
+fn resume_from_cursor() {
+    let page_size = NonZeroUsize::new(2).unwrap();
+    let value = json!({"users": ["a", "b", "c", "d", "e"]});
+    let nodes = find("$.users.*", &value).unwrap();
+
+    let mut pages = nodes.paginate(page_size);
+    pages.next();
+    let cursor = pages.next().unwrap().next.unwrap();
+    assert_eq!(cursor.to_string(), "$['users'][3]");
+
+    // Recompute the node list, as a later request would.
+    let nodes = find("$.users.*", &value).unwrap();
+    let cursor: PageCursor = cursor.to_string().parse().unwrap();
+    let rest: Vec<Vec<&Value>> = nodes
+        .paginate(page_size)
+        .after(&cursor)
+        .map(|page| page.nodes.iter().map(|node| node.value).collect())
+        .collect();
+    assert_eq!(rest, vec![vec![&json!("e")]]);
+}
+
+#[test]
+fn resume_after_removed_node() {
+    let page_size = NonZeroUsize::new(5).unwrap();
+    let value = json!({"a": 1, "c": 3});
+    let nodes = find("$.*", &value).unwrap();
+    let cursor: PageCursor = "$['b']".parse().unwrap();
+    let page = nodes.paginate(page_size).after(&cursor).next().unwrap();
+    assert_eq!(page.nodes[0].path(), "$['c']");
+}
+
+#[test]
+fn cursor_round_trip() {
+    let page_size = NonZeroUsize::new(2).unwrap();
+    let value = json!({"it's": {"a\\b\n": 1}, "z": 2});
+    let nodes = find("$..*", &value).unwrap();
+    let cursor = nodes.paginate(page_size).next().unwrap().next.unwrap();
+    let parsed: PageCursor = cursor.to_string().parse().unwrap();
+    assert_eq!(parsed, cursor);
+}
+
+#[test]
+fn cursor_must_be_normalized() {
+    assert!("$..a".parse::<PageCursor>().is_err());
+    assert!("$[1, 2]".parse::<PageCursor>().is_err());
+    assert!("$[-1]".parse::<PageCursor>().is_err());
+    assert!("$['a'][0]".parse::<PageCursor>().is_ok());
+}
+
+#[test]
+fn values_and_paths() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let nodes = find("$.products[1:].weight", &value).unwrap();
+    assert_eq!(nodes.values(), vec![&json!(2), &json!(null)]);
+    assert_eq!(
+        nodes.paths(),
+        vec!["$['products'][1]['weight']", "$['products'][2]['weight']"]
+    );
+    assert_eq!(nodes.first_value(), Some(&json!(2)));
+
+    let nodes = find("$.nothing", &value).unwrap();
+    assert!(nodes.values().is_empty());
+    assert_eq!(nodes.first_value(), None);
+}
+
+#[test]
+fn exactly_one() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+
+    let nodes = find("$.products[?@.price == 20].name", &value).unwrap();
+    let node = nodes.exactly_one().unwrap();
+    assert_eq!(node.value, &json!("bar"));
+    assert_eq!(node.path(), "$['products'][1]['name']");
+
+    let nodes = find("$.products[?@.price == 30]", &value).unwrap();
+    assert_eq!(
+        nodes.exactly_one().unwrap_err().msg,
+        "expected exactly one node, found none"
+    );
+
+    let nodes = find("$.products[:2].price", &value).unwrap();
+    assert_eq!(
+        nodes.exactly_one().unwrap_err().msg,
+        "expected exactly one node, found 2: $['products'][0]['price'], $['products'][1]['price']"
+    );
+}
@@ -0,0 +1,97 @@
+use jsonpath_rfc9535_locations::{
+    env::Environment,
+    filter::FilterExpressionResult,
+    find,
+    function::{ExpressionType, FunctionContext, FunctionExtension, FunctionSignature},
+};
+use serde_json::{json, Value};
+
+/// `ratio(a, b)`, which divides two numbers, so `0 / 0` is NaN and `1 / 0`
+/// is infinity.
+struct Ratio;
+
+impl FunctionExtension for Ratio {
+    fn call<'a>(
+        &self,
+        args: Vec<FilterExpressionResult<'a>>,
+        _context: &FunctionContext<'_, 'a>,
+    ) -> FilterExpressionResult<'a> {
+        let number = |rv: &FilterExpressionResult| match rv {
+            FilterExpressionResult::Int(i) => Some(*i as f64),
+            FilterExpressionResult::Float(f) => Some(*f),
+            _ => None,
+        };
+        match (args.first().and_then(number), args.get(1).and_then(number)) {
+            (Some(a), Some(b)) => FilterExpressionResult::Float(a / b),
+            _ => FilterExpressionResult::Nothing,
+        }
+    }
+}
+
+fn paths(query: &str) -> Vec<String> {
+    let mut env = Environment::new();
+    env.function_register.register(
+        "ratio",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value, ExpressionType::Value],
+            return_type: ExpressionType::Value,
+            deterministic: true,
+            pure: true,
+            cost: None,
+        },
+        Box::new(Ratio),
+    );
+    let env: &'static Environment = Box::leak(Box::new(env));
+
+    let value = json!([{"a": 0, "b": 0}, {"a": 1, "b": 0}, {"a": -1, "b": 0}, {"a": 1, "b": 2}]);
+    env.parser()
+        .parse(query)
+        .unwrap()
+        .find(&value, env)
+        .iter()
+        .map(|node| node.path())
+        .collect()
+}
+
+#[test]
+fn comparisons_with_nan_are_false() {
+    for op in ["==", "<", "<=", ">", ">="] {
+        for query in [
+            format!("$[?ratio(@.a, @.b) {op} ratio(@.a, @.b)]"),
+            format!("$[?ratio(@.a, @.b) {op} 0]"),
+            format!("$[?0.5 {op} ratio(@.a, @.b)]"),
+        ] {
+            assert!(!paths(&query).contains(&"$[0]".to_owned()), "{}", query);
+        }
+    }
+
+    // `!=` is the negation of `==`
+    assert_eq!(
+        paths("$[?ratio(@.a, @.b) != ratio(@.a, @.b)]"),
+        vec!["$[0]"]
+    );
+}
+
+#[test]
+fn infinities_are_ordered() {
+    assert_eq!(paths("$[?ratio(@.a, @.b) > 1e300]"), vec!["$[1]"]);
+    assert_eq!(paths("$[?ratio(@.a, @.b) < -1e300]"), vec!["$[2]"]);
+    assert_eq!(paths("$[?ratio(@.a, @.b) == 1e400]"), vec!["$[1]"]);
+    assert_eq!(paths("$[?ratio(@.a, @.b) <= 0.5]"), vec!["$[2]", "$[3]"]);
+}
+
+#[test]
+fn large_integer_literals_do_not_saturate() {
+    let value: Value = json!([1e19, 9.3e18, 1]);
+    let paths = |query| -> Vec<String> {
+        find(query, &value)
+            .unwrap()
+            .iter()
+            .map(|node| node.path())
+            .collect()
+    };
+    assert_eq!(paths("$[?@ == 1e19]"), vec!["$[0]"]);
+    assert_eq!(paths("$[?@ < 1e19]"), vec!["$[1]", "$[2]"]);
+    assert_eq!(paths("$[?@ < 1e400]"), vec!["$[0]", "$[1]", "$[2]"]);
+    assert_eq!(paths("$[?@ > -1e400]"), vec!["$[0]", "$[1]", "$[2]"]);
+}
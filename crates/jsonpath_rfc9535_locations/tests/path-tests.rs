@@ -1,5 +1,5 @@
-use jsonpath_rfc9535_locations::find;
-use serde_json::Value;
+use jsonpath_rfc9535_locations::{find, path::NormalizedPath};
+use serde_json::{json, Value};
 
 #[test]
 fn normalized_path() {
@@ -22,3 +22,43 @@ fn descendant_normalized_path() {
     assert_eq!(nodes.first().unwrap().path(), "$['a']['b'][0]");
     assert_eq!(nodes.last().unwrap().path(), "$['a']['b'][2]");
 }
+
+#[test]
+fn slice_start_beyond_end() {
+    let value: Value = serde_json::from_str("[0]").unwrap();
+    assert!(find("$[3:5]", &value).unwrap().is_empty());
+    assert_eq!(find("$[3::-1]", &value).unwrap().len(), 1);
+}
+
+#[test]
+fn descendant_paths_in_document_order() {
+    let data = r#"{"a": [{"b": 1}, [], {"c": {"b": [2]}}], "b": {}}"#;
+    let value: Value = serde_json::from_str(data).unwrap();
+    let paths: Vec<String> = find("$..b", &value)
+        .unwrap()
+        .iter()
+        .map(|node| node.path())
+        .collect();
+    assert_eq!(
+        paths,
+        vec!["$['b']", "$['a'][0]['b']", "$['a'][2]['c']['b']"]
+    );
+
+    let paths: Vec<String> = find("$.a..[0]", &value)
+        .unwrap()
+        .iter()
+        .map(|node| node.path())
+        .collect();
+    assert_eq!(paths, vec!["$['a'][0]", "$['a'][2]['c']['b'][0]"]);
+}
+
+#[test]
+fn names_are_escaped() {
+    let value = json!({"a'b": {"c\\d": 1}});
+    let nodes = find("$.*.*", &value).unwrap();
+    assert_eq!(nodes[0].path(), r"$['a\'b']['c\\d']");
+    assert_eq!(nodes[0].parent_path(), r"$['a\'b']");
+
+    let path: NormalizedPath = nodes[0].path().parse().unwrap();
+    assert_eq!(path, NormalizedPath::from(&nodes[0]));
+}
@@ -0,0 +1,336 @@
+use jsonpath_rfc9535_locations::{
+    env::Environment,
+    filter::FilterExpressionResult,
+    function::{ExpressionType, FunctionContext, FunctionExtension, FunctionSignature},
+    Query, ENV,
+};
+use serde_json::{json, Value};
+use std::{
+    cell::RefCell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+fn plan(expr: &str) -> String {
+    let doc = json!([
+        {"a": 1, "b": "x", "c": [1, 2, 3]},
+        {"a": 2, "b": "y", "c": []},
+        {"b": "z"},
+    ]);
+
+    let planned = Query::standard(expr).unwrap().plan(&ENV);
+    let want: Vec<&Value> = Query::standard(expr)
+        .unwrap()
+        .find(&doc, &ENV)
+        .iter()
+        .map(|node| node.value)
+        .collect();
+    let got: Vec<&Value> = planned
+        .find(&doc, &ENV)
+        .iter()
+        .map(|node| node.value)
+        .collect();
+
+    assert_eq!(got, want, "{} planned as {}", expr, planned);
+    planned.to_string()
+}
+
+/// The `Debug` representation of each literal `expr` is folded to.
+fn folded(expr: &str) -> Vec<String> {
+    let planned = format!("{:?}", Query::standard(expr).unwrap().plan(&ENV));
+    planned
+        .split("Folded { value: ")
+        .skip(1)
+        .map(|rest| rest.split(", expression: ").next().unwrap().to_owned())
+        .collect()
+}
+
+#[test]
+fn no_filters() {
+    assert_eq!(plan("$[0].a"), "$[0]['a']");
+}
+
+#[test]
+fn fold_comparison_of_literals() {
+    assert_eq!(plan("$[?@.a > 0 && 1 < 2]"), "$[?(@['a'] > 0 && 1 < 2)]");
+    assert_eq!(folded("$[?@.a > 0 && 1 < 2]"), ["True"]);
+    assert_eq!(plan("$[?@.a && 1 > 2]"), "$[?(@['a'] && 1 > 2)]");
+    assert_eq!(folded("$[?@.a && 1 > 2]"), ["False", "False"]);
+    assert_eq!(plan("$[?@.a || 'x' == 'x']"), "$[?(@['a'] || 'x' == 'x')]");
+    assert_eq!(folded("$[?@.a || 'x' == 'x']"), ["True", "True"]);
+}
+
+#[test]
+fn keep_query_operand_of_folded_logical() {
+    assert_eq!(plan("$[?1 == 1 && @.a]"), "$[?(1 == 1 && @['a'])]");
+    assert_eq!(folded("$[?1 == 1 && @.a]"), ["True"]);
+}
+
+#[test]
+fn fold_pure_function_with_literal_arguments() {
+    assert_eq!(
+        plan("$[?@.a == length('ab')]"),
+        "$[?@['a'] == length('ab')]"
+    );
+    assert_eq!(folded("$[?@.a == length('ab')]"), ["Int { value: 2 }"]);
+    assert_eq!(
+        plan("$[?match('ab', 'a.') && @.b == 'x']"),
+        "$[?(match('ab', 'a.') && @['b'] == 'x')]"
+    );
+    assert_eq!(folded("$[?match('ab', 'a.') && @.b == 'x']"), ["True"]);
+}
+
+#[test]
+fn cheapest_operand_first() {
+    assert_eq!(
+        plan("$[?count(@..*) > 1 && @.a == 1]"),
+        "$[?(count(@..[*]) > 1 && @['a'] == 1)]"
+    );
+    assert_eq!(folded("$[?count(@..*) > 1 && @.a == 1]").len(), 1);
+    assert_eq!(
+        plan("$[?search(@.b, 'x|y') || @.a]"),
+        "$[?(search(@['b'], 'x|y') || @['a'])]"
+    );
+    assert_eq!(folded("$[?search(@.b, 'x|y') || @.a]").len(), 1);
+    assert_eq!(plan("$[?@.a || @.b]"), "$[?(@['a'] || @['b'])]");
+    assert!(folded("$[?@.a || @.b]").is_empty());
+}
+
+thread_local! {
+    static CALLS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records its name each time it's called.
+struct Record(&'static str);
+
+impl FunctionExtension for Record {
+    fn call<'a>(
+        &self,
+        _args: Vec<FilterExpressionResult<'a>>,
+        _context: &FunctionContext<'_, 'a>,
+    ) -> FilterExpressionResult<'a> {
+        CALLS.with_borrow_mut(|calls| calls.push(self.0));
+        FilterExpressionResult::Bool(true)
+    }
+}
+
+#[test]
+fn operands_are_ordered_by_declared_cost() {
+    let mut env = Environment::new();
+    for (name, cost) in [("cheap", Some(1)), ("unknown", None), ("dear", Some(1000))] {
+        env.function_register.register(
+            name,
+            FunctionSignature {
+                param_types: vec![ExpressionType::Value],
+                return_type: ExpressionType::Logical,
+                deterministic: true,
+                pure: true,
+                cost,
+            },
+            Box::new(Record(name)),
+        );
+    }
+    let env: &'static Environment = Box::leak(Box::new(env));
+    let calls = |expr: &str| {
+        let query = env.parser().parse(expr).unwrap().plan(env);
+        CALLS.with_borrow_mut(Vec::clear);
+        query.find(&json!([{"a": 1}]), env);
+        CALLS.take()
+    };
+
+    assert_eq!(calls("$[?cheap(@.a) && dear(@.a)]"), ["cheap", "dear"]);
+    assert_eq!(calls("$[?dear(@.a) && cheap(@.a)]"), ["cheap", "dear"]);
+    assert_eq!(calls("$[?dear(@.a) || unknown(@.a)]"), ["unknown"]);
+    assert_eq!(calls("$[?unknown(@.a) || cheap(@.a)]"), ["cheap"]);
+    assert_eq!(
+        env.parser()
+            .parse("$[?dear(@.a) && cheap(@.a)]")
+            .unwrap()
+            .plan(env)
+            .to_string(),
+        "$[?(dear(@['a']) && cheap(@['a']))]"
+    );
+}
+
+#[test]
+fn plan_nested_filters() {
+    assert_eq!(
+        plan("$[?@.c[?@ > 1 && 2 == 2]]"),
+        "$[?@['c'][?(@ > 1 && 2 == 2)]]"
+    );
+}
+
+struct Random;
+
+impl FunctionExtension for Random {
+    fn call<'a>(
+        &self,
+        _args: Vec<FilterExpressionResult<'a>>,
+        _context: &FunctionContext<'_, 'a>,
+    ) -> FilterExpressionResult<'a> {
+        FilterExpressionResult::Int(4)
+    }
+}
+
+#[test]
+fn impure_functions_are_not_folded_or_reordered() {
+    let mut env = Environment::new();
+    env.function_register.register(
+        "random",
+        FunctionSignature {
+            param_types: vec![],
+            return_type: ExpressionType::Value,
+            deterministic: false,
+            pure: false,
+            cost: None,
+        },
+        Box::new(Random),
+    );
+    let env: &'static Environment = Box::leak(Box::new(env));
+
+    let query = env
+        .parser()
+        .parse("$[?count(@..*) > 1 && random() == 4]")
+        .unwrap()
+        .plan(env);
+    assert_eq!(
+        query.to_string(),
+        "$[?(count(@..[*]) > 1 && random() == 4)]"
+    );
+    assert!(!format!("{query:?}").contains("Folded"));
+}
+
+#[test]
+fn count_up_to() {
+    let doc = json!({"a": [1, 2, 3, {"b": [4, 5]}]});
+    let query = Query::standard("$..*").unwrap();
+    assert_eq!(query.count_up_to(&doc, &ENV, usize::MAX), 8);
+    assert_eq!(query.find(&doc, &ENV).len(), 8);
+    assert_eq!(query.count_up_to(&doc, &ENV, 3), 3);
+    assert_eq!(query.count_up_to(&doc, &ENV, 0), 0);
+
+    let query = Query::standard("$.a[?@ > 1][::-1]").unwrap();
+    assert_eq!(query.count_up_to(&doc, &ENV, 10), 0);
+    let query = Query::standard("$.a[-1].b[::-1]").unwrap();
+    assert_eq!(query.count_up_to(&doc, &ENV, 10), 2);
+}
+
+#[test]
+fn bounded_count_comparisons() {
+    for op in ["==", "!=", "<", "<=", ">", ">="] {
+        for n in [-1, 0, 1, 2, 3, 4] {
+            assert_eq!(
+                plan(&format!("$[?count(@.c[*]) {op} {n}]")),
+                format!("$[?count(@['c'][*]) {op} {n}]")
+            );
+            plan(&format!("$[?{n} {op} count(@.*)]"));
+            plan(&format!("$[?count($..[?@ == 1]) {op} {n}]"));
+        }
+    }
+}
+
+static TICKS: AtomicUsize = AtomicUsize::new(0);
+
+struct Tick;
+
+impl FunctionExtension for Tick {
+    fn call<'a>(
+        &self,
+        _args: Vec<FilterExpressionResult<'a>>,
+        _context: &FunctionContext<'_, 'a>,
+    ) -> FilterExpressionResult<'a> {
+        TICKS.fetch_add(1, Ordering::SeqCst);
+        FilterExpressionResult::Bool(true)
+    }
+}
+
+#[test]
+fn bounded_count_stops_early() {
+    let mut env = Environment::new();
+    env.function_register.register(
+        "tick",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value],
+            return_type: ExpressionType::Logical,
+            deterministic: false,
+            pure: false,
+            cost: None,
+        },
+        Box::new(Tick),
+    );
+    let env: &'static Environment = Box::leak(Box::new(env));
+
+    let doc = json!([{"items": (0..10_000).collect::<Vec<i32>>()}]);
+    let query = env
+        .parser()
+        .parse("$[?count(@.items[?tick(@)]) > 2]")
+        .unwrap()
+        .plan(env);
+
+    assert_eq!(query.find(&doc, env).len(), 1);
+    assert_eq!(TICKS.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn push_independent_filters_before_wildcards() {
+    assert_eq!(plan("$[*][?$[0].a]"), "$[?$[0]['a']][*]");
+    assert_eq!(plan("$[*][*][?$[0].a == 1]"), "$[?$[0]['a'] == 1][*][*]");
+    assert_eq!(plan("$[0][*][?$[?@.a]]"), "$[0][?$[?@['a']]][*]");
+    assert_eq!(
+        plan("$[*][?length($) == 3].b"),
+        "$[?length($) == 3][*]['b']"
+    );
+}
+
+#[test]
+fn filters_on_the_current_node_stay_put() {
+    assert_eq!(plan("$[*][?@.a]"), "$[*][?@['a']]");
+    assert_eq!(
+        plan("$[*][?$[0].a && @ > 1]"),
+        "$[*][?($[0]['a'] && @ > 1)]"
+    );
+    assert_eq!(plan("$[*][?count(@.*) > 1]"), "$[*][?count(@[*]) > 1]");
+    assert_eq!(plan("$..*[?$[0].a]"), "$..[*][?$[0]['a']]");
+    assert_eq!(plan("$[*, 0][?$[0].a]"), "$[*, 0][?$[0]['a']]");
+    assert_eq!(
+        plan("$[*][?$[0].a, ?$[1].a]"),
+        "$[*][?$[0]['a'], ?$[1]['a']]"
+    );
+}
+
+#[test]
+fn pushed_down_filters_select_the_same_nodes() {
+    let docs = [
+        json!({"on": true, "items": [{"a": [1, 2]}, {"a": {"x": 3}}, [4, [5]]]}),
+        json!({"on": false, "items": {"p": {"q": [6, 7]}, "r": 8}}),
+        json!({"items": [[], {}, "s", [[9]]]}),
+        json!([[1, [2, 3]], {"on": 1}]),
+    ];
+    let exprs = [
+        "$.items[*][?$.on]",
+        "$.items[*][?$.on == true]",
+        "$.items[*][*][?$.on]",
+        "$.items[*][?$.on][*]",
+        "$[*][?$.items[?@.a]]",
+        "$[*][?count($..*) > 5]",
+        "$[*][?!$.on].on",
+        "$.items[*][?length($.items) >= 3][0]",
+    ];
+
+    for doc in &docs {
+        for expr in exprs {
+            let planned = Query::standard(expr).unwrap().plan(&ENV);
+            let want: Vec<String> = Query::standard(expr)
+                .unwrap()
+                .find(doc, &ENV)
+                .iter()
+                .map(|node| node.path())
+                .collect();
+            let got: Vec<String> = planned
+                .find(doc, &ENV)
+                .iter()
+                .map(|node| node.path())
+                .collect();
+            assert_eq!(got, want, "{} planned as {} on {}", expr, planned, doc);
+        }
+    }
+}
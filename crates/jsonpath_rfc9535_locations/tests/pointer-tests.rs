@@ -0,0 +1,44 @@
+use jsonpath_rfc9535_locations::{errors::JSONPathErrorType, find, Query};
+use serde_json::json;
+
+#[test]
+fn singular_queries_to_pointers() {
+    for (expr, want) in [
+        ("$", ""),
+        ("$.a", "/a"),
+        ("$.a[0].b", "/a/0/b"),
+        ("$['a/b']['m~n']", "/a~1b/m~0n"),
+        ("$['~1']", "/~01"),
+        ("$['']", "/"),
+        ("$['ü']", "/ü"),
+    ] {
+        let query = Query::standard(expr).unwrap();
+        assert_eq!(query.to_json_pointer().unwrap(), want, "{}", expr);
+    }
+}
+
+#[test]
+fn non_singular_queries_are_errors() {
+    for expr in ["$.*", "$..a", "$[0, 1]", "$[1:]", "$[?@.a]", "$.a[-1]"] {
+        let err = Query::standard(expr)
+            .unwrap()
+            .to_json_pointer()
+            .unwrap_err();
+        assert!(matches!(err.kind, JSONPathErrorType::TypeError), "{}", expr);
+    }
+}
+
+#[test]
+fn node_pointers_resolve_to_node_values() {
+    let value = json!({"a/b": [{"m~n": 1}, {"": [true]}], "c": "x"});
+    let nodes = find("$..*", &value).unwrap();
+    assert_eq!(nodes.len(), 7);
+    for node in nodes.iter() {
+        assert_eq!(value.pointer(&node.to_json_pointer()), Some(node.value));
+    }
+
+    let root = find("$", &value).unwrap();
+    assert_eq!(root[0].to_json_pointer(), "");
+    let nodes = find("$['a/b'][0]['m~n']", &value).unwrap();
+    assert_eq!(nodes[0].to_json_pointer(), "/a~1b/0/m~0n");
+}
@@ -0,0 +1,132 @@
+use jsonpath_rfc9535_locations::{
+    errors::JSONPathErrorType,
+    predicate::{examples, PredicateSet},
+};
+use serde_json::json;
+
+#[test]
+fn match_rules() {
+    let mut rules = PredicateSet::new();
+    rules.add(1, "@.a == 1 && @.b").unwrap();
+    rules.add(2, "@.a == 1 || @.c").unwrap();
+    rules.add(3, "!@.b").unwrap();
+    rules.add(4, "$.d[?@ > 2]").unwrap();
+
+    assert_eq!(rules.len(), 4);
+    assert_eq!(rules.matches(&json!({"a": 1, "b": true})), vec![&1, &2]);
+    assert_eq!(
+        rules.matches(&json!({"c": null, "d": [1, 3]})),
+        vec![&2, &3, &4]
+    );
+    assert!(rules.matches(&json!({"a": 2, "b": 1})).is_empty());
+}
+
+#[test]
+fn shared_operands_are_stored_once() {
+    let mut rules = PredicateSet::new();
+    rules.add("x", "@.a == 1 && @.b").unwrap();
+    rules.add("y", "@.a == 1 || @.c").unwrap();
+    assert_eq!(rules.predicate_count(), 5);
+
+    rules.add("z", "@.a == 1 && @.b").unwrap();
+    assert_eq!(rules.predicate_count(), 5);
+    assert_eq!(
+        rules.matches(&json!({"a": 1, "b": 0})),
+        vec![&"x", &"y", &"z"]
+    );
+}
+
+#[test]
+fn strings_are_not_confused() {
+    let mut rules = PredicateSet::new();
+    rules.add("quote", r#"@.a == "'""#).unwrap();
+    rules.add("escaped", r"@.a == '\''").unwrap();
+    rules.add("other", "@.a == '\"'").unwrap();
+    assert_eq!(rules.predicate_count(), 2);
+    assert_eq!(
+        rules.matches(&json!({"a": "'"})),
+        vec![&"quote", &"escaped"]
+    );
+}
+
+#[test]
+fn empty_set() {
+    let rules: PredicateSet<u32> = PredicateSet::default();
+    assert!(rules.is_empty());
+    assert!(rules.matches(&json!({})).is_empty());
+}
+
+#[test]
+fn syntax_errors() {
+    let mut rules = PredicateSet::new();
+    let err = rules.add(1, "@.a ==").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::SyntaxError));
+    assert!(rules.is_empty());
+}
+
+#[test]
+fn rules_must_be_a_single_filter() {
+    let mut rules = PredicateSet::new();
+    assert!(rules.add(1, "@.a], 'b'[?@.c").is_err());
+    assert!(rules.add(2, "@.a]['b'][?@.c").is_err());
+    assert!(rules.add(3, "@.a, 0").is_err());
+    assert!(rules.is_empty());
+}
+
+#[test]
+fn examples_satisfy_and_violate() {
+    for expr in [
+        "@.a == 1 && @.b",
+        "@.a == 1 || @.c",
+        "!@.b",
+        "@.n >= 2 && @.n < 10",
+        "'x' != @.s",
+        "@.a == @.b",
+        "match(@.code, 'a.c')",
+        "!(@.x > 1 || @.y)",
+    ] {
+        let mut rules = PredicateSet::new();
+        rules.add((), expr).unwrap();
+        let examples = examples(expr).unwrap();
+
+        assert!(!examples.satisfying.is_empty(), "{}", expr);
+        assert!(!examples.violating.is_empty(), "{}", expr);
+        for doc in &examples.satisfying {
+            assert_eq!(rules.matches(doc).len(), 1, "{} on {}", expr, doc);
+        }
+        for doc in &examples.violating {
+            assert!(rules.matches(doc).is_empty(), "{} on {}", expr, doc);
+        }
+    }
+}
+
+#[test]
+fn examples_are_near_misses() {
+    let examples = examples("@.level == 'error' && @.duration > 100").unwrap();
+    assert_eq!(
+        examples.satisfying,
+        vec![json!({"level": "error", "duration": 101})]
+    );
+    assert_eq!(
+        examples.violating,
+        vec![
+            json!({"level": "errora", "duration": 101}),
+            json!({"duration": 101}),
+            json!({"level": "error", "duration": 100}),
+            json!({"level": "error"}),
+        ]
+    );
+}
+
+#[test]
+fn undecidable_examples() {
+    let rv = examples("@.a || !@.a").unwrap();
+    assert!(!rv.satisfying.is_empty());
+    assert!(rv.violating.is_empty());
+
+    let rv = examples("length(@.a) == 3").unwrap();
+    assert!(rv.satisfying.is_empty());
+    assert_eq!(rv.violating, vec![json!({})]);
+
+    assert!(examples("@.a ==").is_err());
+}
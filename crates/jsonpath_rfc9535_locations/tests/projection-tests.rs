@@ -0,0 +1,55 @@
+use jsonpath_rfc9535_locations::Projection;
+use serde_json::json;
+
+#[test]
+fn project_rows() {
+    let value = json!({"users": [{"name": "Sue", "age": 30}, {"name": "John"}]});
+    let projection =
+        Projection::new("$.users[*]", &[("name", "$.name"), ("age", "$.age")]).unwrap();
+    let rows = projection.project(&value);
+    assert_eq!(
+        rows,
+        vec![
+            vec![Some(&json!("Sue")), Some(&json!(30))],
+            vec![Some(&json!("John")), None]
+        ]
+    );
+}
+
+#[test]
+fn to_csv() {
+    let value = json!({
+        "users": [
+            {"name": "Sue, Jr.", "tags": ["a", "b"], "note": "says \"hi\""},
+            {"name": "John", "tags": [], "note": null}
+        ]
+    });
+    let projection = Projection::new(
+        "$.users[*]",
+        &[("name", "$.name"), ("tags", "$.tags"), ("note", "$.note")],
+    )
+    .unwrap();
+    assert_eq!(
+        projection.to_csv(&value),
+        "name,tags,note\r\n\"Sue, Jr.\",\"[\"\"a\"\",\"\"b\"\"]\",\"says \"\"hi\"\"\"\r\nJohn,[],null\r\n"
+    );
+}
+
+#[test]
+fn to_tsv() {
+    let value = json!({"users": [{"name": "Sue, Jr.", "id": 1}, {"name": "a\tb"}]});
+    let projection = Projection::new("$.users[*]", &[("name", "$.name"), ("id", "$.id")]).unwrap();
+    assert_eq!(
+        projection.to_tsv(&value),
+        "name\tid\r\nSue, Jr.\t1\r\n\"a\tb\"\t\r\n"
+    );
+}
+
+#[test]
+fn non_singular_column() {
+    let err = Projection::new("$.users[*]", &[("tags", "$.tags[*]")]).unwrap_err();
+    assert_eq!(
+        err.msg,
+        "column 'tags' must be a singular query, found $['tags'][*]"
+    );
+}
@@ -0,0 +1,96 @@
+use jsonpath_rfc9535_locations::{
+    errors::JSONPathErrorType, find, query_set::QuerySet, Query, ENV,
+};
+use serde_json::{json, Value};
+
+fn value() -> Value {
+    json!({
+        "store": {
+            "book": [
+                {"title": "a", "price": 8, "tags": ["x"]},
+                {"title": "b", "price": 12, "tags": ["y", "z"]},
+                {"title": "c", "price": 22}
+            ],
+            "bicycle": {"price": 19}
+        }
+    })
+}
+
+fn paths(nodes: &[jsonpath_rfc9535_locations::node::Node]) -> Vec<String> {
+    nodes.iter().map(|node| node.path()).collect()
+}
+
+#[test]
+fn shared_prefixes_are_evaluated_once() {
+    let mut queries = QuerySet::new();
+    queries.add(1, "$.store.book[?@.price < 20].title").unwrap();
+    queries.add(2, "$.store.book[?@.price < 20].price").unwrap();
+    queries.add(3, "$['store']['book'][?@.price < 20]").unwrap();
+    queries.add(4, "$.store.bicycle.price").unwrap();
+
+    assert_eq!(queries.len(), 4);
+    // store, book, filter, title, price and bicycle
+    assert_eq!(queries.segment_count(), 6);
+    // store, book, filter, title, price, bicycle and its price
+    assert_eq!(queries.step_count(), 7);
+}
+
+#[test]
+fn results_match_individual_queries() {
+    let exprs = [
+        "$",
+        "$.store.book[?@.price < 20].title",
+        "$.store.book[?@.price < 20]",
+        "$..price",
+        "$.store..price",
+        "$..[?@.price > 10].title",
+        "$.store.book[*].tags[0]",
+        "$.store.book[-1:]",
+        "$.nosuchthing.price",
+    ];
+
+    let mut queries = QuerySet::new();
+    for expr in exprs {
+        queries.add(expr, expr).unwrap();
+    }
+
+    let value = value();
+    let results = queries.find(&value);
+    assert_eq!(results.len(), exprs.len());
+
+    for ((id, nodes), expr) in results.iter().zip(exprs) {
+        assert_eq!(**id, expr);
+        let want = find(expr, &value).unwrap();
+        assert_eq!(paths(nodes), paths(&want), "{}", expr);
+    }
+}
+
+#[test]
+fn compiled_queries() {
+    let mut queries = QuerySet::new();
+    queries.insert("a", Query::standard("$.store.bicycle").unwrap());
+    queries.insert("b", Query::standard("$.store.bicycle.price").unwrap());
+    assert_eq!(queries.step_count(), 3);
+
+    let value = value();
+    let want = Query::standard("$.store.bicycle.price")
+        .unwrap()
+        .find(&value, &ENV);
+    let results = queries.find(&value);
+    assert_eq!(paths(&results[1].1), paths(&want));
+}
+
+#[test]
+fn empty_set() {
+    let queries: QuerySet<u32> = QuerySet::default();
+    assert!(queries.is_empty());
+    assert!(queries.find(&json!({})).is_empty());
+}
+
+#[test]
+fn syntax_errors() {
+    let mut queries = QuerySet::new();
+    let err = queries.add(1, "$.a[").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::SyntaxError));
+    assert!(queries.is_empty());
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "raw_value")]
+
+use jsonpath_rfc9535_locations::raw;
+use serde_json::value::RawValue;
+
+const DATA: &str = r#"{
+    "users": [
+        {"name": "Sue", "score": 1.0e2, "tags": [ "a" , "b" ]},
+        {"name": "Jörg", "score": 7}
+    ],
+    "meta": {"count": 2}
+}"#;
+
+fn find(expr: &str) -> Vec<(String, String)> {
+    let value: &RawValue = serde_json::from_str(DATA).unwrap();
+    raw::find(expr, value)
+        .unwrap()
+        .into_iter()
+        .map(|(path, raw)| (path, raw.get().to_owned()))
+        .collect()
+}
+
+#[test]
+fn fragments_are_not_reserialized() {
+    assert_eq!(
+        find("$.users[0].score"),
+        vec![("$['users'][0]['score']".to_owned(), "1.0e2".to_owned())]
+    );
+    assert_eq!(
+        find("$.users[1].name"),
+        vec![("$['users'][1]['name']".to_owned(), r#""Jörg""#.to_owned())]
+    );
+    assert_eq!(find("$.users[0].tags")[0].1, r#"[ "a" , "b" ]"#);
+}
+
+#[test]
+fn selection_order() {
+    let found = find("$..[?@.name].name");
+    assert_eq!(
+        found.into_iter().map(|(_, raw)| raw).collect::<Vec<_>>(),
+        vec![r#""Sue""#, r#""Jörg""#]
+    );
+
+    let found = find("$.users[1, 0, 1].score");
+    assert_eq!(
+        found.into_iter().map(|(_, raw)| raw).collect::<Vec<_>>(),
+        vec!["7", "1.0e2", "7"]
+    );
+}
+
+#[test]
+fn root_and_nested_values() {
+    let found = find("$");
+    assert_eq!(found, vec![("$".to_owned(), DATA.to_owned())]);
+
+    let found = find("$..count");
+    assert_eq!(
+        found,
+        vec![("$['meta']['count']".to_owned(), "2".to_owned())]
+    );
+}
+
+#[test]
+fn nothing_selected() {
+    assert!(find("$.nosuchthing").is_empty());
+}
+
+#[test]
+fn syntax_error() {
+    let value: &RawValue = serde_json::from_str("{}").unwrap();
+    assert!(raw::find("$.", value).is_err());
+}
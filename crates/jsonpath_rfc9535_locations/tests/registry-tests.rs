@@ -0,0 +1,114 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use jsonpath_rfc9535_locations::{
+    env::Environment,
+    filter::FilterExpressionResult,
+    function::{ExpressionType, FunctionContext, FunctionSignature, Functions},
+    Query, ENV, STANDARD_ENV,
+};
+use serde_json::{json, Value};
+
+#[test]
+fn standard_functions_match_dynamic_functions() {
+    let value = json!([
+        {"a": "abc", "b": [1, 2]},
+        {"a": "xyz", "b": []},
+        {"a": "ab", "b": [3]},
+    ]);
+
+    for query in [
+        "$[?length(@.a) == 3]",
+        "$[?count(@.b[*]) > 0]",
+        "$[?match(@.a, 'a.*')]",
+        "$[?search(@.a, 'y')]",
+        "$[?value(@.b[0]) == 3]",
+        "$[?count(@..*) > 2 && length(@.b) < 2]",
+    ] {
+        let query = Query::standard(query).unwrap();
+        let want: Vec<String> = query.find(&value, &ENV).iter().map(|n| n.path()).collect();
+        let got: Vec<String> = query
+            .find(&value, &STANDARD_ENV)
+            .iter()
+            .map(|n| n.path())
+            .collect();
+        assert_eq!(got, want, "{}", query);
+    }
+}
+
+#[test]
+fn standard_signatures() {
+    let functions = &STANDARD_ENV.function_register;
+    for name in ["count", "length", "match", "search", "value"] {
+        let sig = functions.signature(name).unwrap();
+        let param_types = functions.param_types(name).unwrap();
+        assert_eq!(
+            format!("{:?}", param_types),
+            format!("{:?}", sig.param_types),
+            "{}",
+            name
+        );
+        assert!(matches!(param_types, Cow::Borrowed(_)));
+    }
+    assert!(functions.signature("nosuchthing").is_none());
+}
+
+#[test]
+fn plan_with_standard_functions() {
+    let query = Query::standard("$[?count(@.*) > 1 && 1 == 1]")
+        .unwrap()
+        .plan(&STANDARD_ENV);
+    assert_eq!(query.to_string(), "$[?(count(@[*]) > 1 && 1 == 1)]");
+    assert_eq!(query.find(&json!([[1, 2], [3]]), &STANDARD_ENV).len(), 1);
+}
+
+/// A registry with one function, `double(value)`.
+struct Doubler {
+    signature: FunctionSignature,
+}
+
+impl Doubler {
+    fn new() -> Self {
+        Doubler {
+            signature: FunctionSignature {
+                param_types: vec![ExpressionType::Value],
+                return_type: ExpressionType::Value,
+                deterministic: true,
+                pure: true,
+                cost: None,
+            },
+        }
+    }
+}
+
+impl Functions for Doubler {
+    fn signature(&self, name: &str) -> Option<&FunctionSignature> {
+        (name == "double").then_some(&self.signature)
+    }
+
+    fn signatures(&self) -> HashMap<String, FunctionSignature> {
+        HashMap::from([("double".to_owned(), self.signature.clone())])
+    }
+
+    fn call<'a>(
+        &self,
+        _name: &str,
+        args: Vec<FilterExpressionResult<'a>>,
+        _context: &FunctionContext<'_, 'a, Self>,
+    ) -> FilterExpressionResult<'a> {
+        match args.first() {
+            Some(FilterExpressionResult::Int(i)) => FilterExpressionResult::Int(i * 2),
+            _ => FilterExpressionResult::Nothing,
+        }
+    }
+}
+
+#[test]
+fn custom_registry() {
+    let env: &'static Environment<Doubler> =
+        Box::leak(Box::new(Environment::with_functions(Doubler::new())));
+
+    let value: Value = json!([{"a": 1, "b": 2}, {"a": 2, "b": 3}]);
+    let query = env.parser().parse("$[?double(@.a) == @.b]").unwrap();
+    let paths: Vec<String> = query.find(&value, env).iter().map(|n| n.path()).collect();
+    assert_eq!(paths, vec!["$[0]"]);
+}
@@ -0,0 +1,72 @@
+use jsonpath_rfc9535_locations::{
+    find, node::PathElement, path::NormalizedPath, resolve_path, resolve_path_mut,
+};
+use serde_json::json;
+
+#[test]
+fn resolve_paths_from_a_query() {
+    let value = json!({"a": [{"b": 1}, {"b": 2}], "c": {"b": 3}});
+    let nodes = find("$..b", &value).unwrap();
+    for node in nodes.iter() {
+        let path = NormalizedPath::from(node);
+        assert_eq!(path.to_string(), node.path());
+        assert_eq!(resolve_path(&path, &value), Some(node.value));
+    }
+}
+
+#[test]
+fn parse_and_display() {
+    let path: NormalizedPath = "$.a[0]['it\\'s']".parse().unwrap();
+    assert_eq!(
+        path.elements(),
+        &[
+            PathElement::Name("a".to_owned()),
+            PathElement::Index(0),
+            PathElement::Name("it's".to_owned()),
+        ]
+    );
+    assert_eq!(path.to_string(), "$['a'][0]['it\\'s']");
+    assert_eq!(path.to_string().parse::<NormalizedPath>().unwrap(), path);
+    assert_eq!("$".parse::<NormalizedPath>().unwrap().elements(), &[]);
+}
+
+#[test]
+fn not_normalized_paths() {
+    for path in [
+        "$..a",
+        "$[*]",
+        "$[-1]",
+        "$['a', 'b']",
+        "$[0:1]",
+        "$[?@]",
+        "$.",
+    ] {
+        assert!(path.parse::<NormalizedPath>().is_err(), "{}", path);
+    }
+}
+
+#[test]
+fn missing_values() {
+    let value = json!({"a": [1]});
+    for path in ["$['b']", "$['a'][1]", "$['a']['0']", "$['a'][0][0]"] {
+        let path: NormalizedPath = path.parse().unwrap();
+        assert_eq!(resolve_path(&path, &value), None, "{}", path);
+    }
+}
+
+#[test]
+fn mutate_values() {
+    let mut value = json!({"a": [1, {"b": 2}]});
+    let path = NormalizedPath::new(vec![
+        PathElement::Name("a".to_owned()),
+        PathElement::Index(1),
+        PathElement::Name("b".to_owned()),
+    ]);
+    *resolve_path_mut(&path, &mut value).unwrap() = json!(3);
+    assert_eq!(value, json!({"a": [1, {"b": 3}]}));
+
+    let root = NormalizedPath::new(Vec::new());
+    *resolve_path_mut(&root, &mut value).unwrap() = json!(null);
+    assert_eq!(value, json!(null));
+    assert!(resolve_path_mut(&path, &mut value).is_none());
+}
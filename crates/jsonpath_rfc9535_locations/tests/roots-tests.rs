@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use jsonpath_rfc9535_locations::{errors::JSONPathErrorType, find_in_roots};
+use serde_json::{json, Value};
+
+fn roots<'a>(docs: &[(&str, &'a Value)]) -> HashMap<String, &'a Value> {
+    docs.iter()
+        .map(|(name, value)| (name.to_string(), *value))
+        .collect()
+}
+
+#[test]
+fn select_document_by_name() {
+    let users = json!({"admins": ["alice", "bob"]});
+    let config = json!({"admins": ["root"]});
+    let roots = roots(&[("users", &users), ("config", &config)]);
+
+    let nodes = find_in_roots("$doc('users').admins[0]", &roots).unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].value, &json!("alice"));
+    assert_eq!(nodes[0].path(), "$['users']['admins'][0]");
+
+    let nodes = find_in_roots("$doc(\"config\")", &roots).unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].value, &config);
+    assert_eq!(nodes[0].path(), "$['config']");
+}
+
+#[test]
+fn query_every_document() {
+    let users = json!({"admins": ["alice", "bob"]});
+    let config = json!({"admins": ["root"]});
+    let empty = json!({});
+    let roots = roots(&[("users", &users), ("config", &config), ("empty", &empty)]);
+
+    let nodes = find_in_roots("$.admins[*]", &roots).unwrap();
+    let paths: Vec<String> = nodes.iter().map(|node| node.path()).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "$['config']['admins'][0]",
+            "$['users']['admins'][0]",
+            "$['users']['admins'][1]",
+        ]
+    );
+}
+
+#[test]
+fn root_in_filter_is_the_selected_document() {
+    let a = json!({"limit": 2, "items": [1, 2, 3]});
+    let b = json!({"limit": 1, "items": [1, 2, 3]});
+    let roots = roots(&[("a", &a), ("b", &b)]);
+
+    let nodes = find_in_roots("$doc('b').items[?@ > $.limit]", &roots).unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(
+        find_in_roots("$.items[?@ > $.limit]", &roots)
+            .unwrap()
+            .len(),
+        3
+    );
+}
+
+#[test]
+fn escaped_document_name() {
+    let doc = json!([1]);
+    let roots = roots(&[("it's", &doc)]);
+    let nodes = find_in_roots("$doc('it\\'s')[0]", &roots).unwrap();
+    assert_eq!(nodes[0].path(), r"$['it\'s'][0]");
+}
+
+#[test]
+fn unknown_document() {
+    let doc = json!([1]);
+    let roots = roots(&[("a", &doc)]);
+    let err = find_in_roots("$doc('b')[0]", &roots).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::NameError));
+    assert_eq!(err.msg, "unknown document `b`");
+}
+
+#[test]
+fn malformed_document_selector() {
+    let doc = json!([1]);
+    let roots = roots(&[("a", &doc)]);
+    for expr in ["$doc(a)", "$doc('a'", "$doc('a", "$doc('a')x"] {
+        let err = find_in_roots(expr, &roots).unwrap_err();
+        assert!(
+            matches!(err.kind, JSONPathErrorType::SyntaxError),
+            "{expr}: {err}"
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use jsonpath_rfc9535_locations::Query;
+
+fn query(expr: &str) -> Query {
+    Query::standard(expr).unwrap()
+}
+
+#[test]
+fn member_names() {
+    assert!(query("$.a.b").could_match_member("a"));
+    assert!(!query("$.a.b").could_match_member("b"));
+    assert!(query("$['x', 'a']").could_match_member("a"));
+    assert!(!query("$[0].a").could_match_member("a"));
+    assert!(!query("$").could_match_member("a"));
+}
+
+#[test]
+fn wildcards_filters_and_descendants_could_match_anything() {
+    assert!(query("$.*").could_match_member("a"));
+    assert!(query("$[?@.b]").could_match_member("a"));
+    assert!(query("$..b").could_match_member("a"));
+    assert!(query("$.*").could_match_index(7));
+    assert!(query("$..b").could_match_index(7));
+}
+
+#[test]
+fn indices() {
+    assert!(query("$[1].a").could_match_index(1));
+    assert!(!query("$[1].a").could_match_index(2));
+    assert!(!query("$.a").could_match_index(0));
+    assert!(query("$[-1]").could_match_index(0));
+    assert!(query("$[-1]").could_match_index(100));
+}
+
+#[test]
+fn slices() {
+    assert!(query("$[1:3]").could_match_index(2));
+    assert!(!query("$[1:3]").could_match_index(3));
+    assert!(!query("$[1:3]").could_match_index(0));
+    assert!(query("$[::2]").could_match_index(4));
+    assert!(!query("$[::2]").could_match_index(3));
+    assert!(query("$[-2:]").could_match_index(3));
+    assert!(!query("$[:-1:0]").could_match_index(0));
+    assert!(query("$[5:1:-1]").could_match_index(2));
+    assert!(!query("$[5:1:-1]").could_match_index(1));
+    assert!(!query("$[5:1:-1]").could_match_index(6));
+    assert!(query("$[::-2]").could_match_index(3));
+}
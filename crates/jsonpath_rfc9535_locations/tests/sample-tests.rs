@@ -0,0 +1,75 @@
+use jsonpath_rfc9535_locations::{Query, ENV};
+use serde_json::{json, Value};
+
+fn sample(expr: &str) -> Option<Value> {
+    let query = Query::standard(expr).unwrap();
+    let doc = query.sample_document();
+    if let Some(doc) = &doc {
+        assert!(!query.find(doc, &ENV).is_empty(), "{} on {}", expr, doc);
+    }
+    doc
+}
+
+#[test]
+fn root() {
+    assert_eq!(sample("$"), Some(json!(null)));
+}
+
+#[test]
+fn names_and_indices() {
+    assert_eq!(
+        sample("$.a[2].b"),
+        Some(json!({"a": [null, null, {"b": null}]}))
+    );
+}
+
+#[test]
+fn negative_index() {
+    assert_eq!(sample("$[-2]"), Some(json!([null, null])));
+}
+
+#[test]
+fn wildcard_and_descendant() {
+    assert_eq!(sample("$.*..x"), Some(json!([{"x": null}])));
+}
+
+#[test]
+fn slice() {
+    assert_eq!(sample("$[3:5]"), Some(json!([null, null, null, null])));
+    assert_eq!(sample("$[::-2]"), Some(json!([null])));
+}
+
+#[test]
+fn empty_slice() {
+    assert_eq!(sample("$[0:0]"), None);
+    assert_eq!(sample("$[::0]"), None);
+}
+
+#[test]
+fn filter_comparisons() {
+    assert_eq!(
+        sample("$.products[?@.price > 5 && @.name == 'foo'].name"),
+        Some(json!({"products": [{"name": "foo", "price": 6}]}))
+    );
+    assert_eq!(sample("$[?5 > @.n]"), Some(json!([{"n": 4}])));
+    assert_eq!(sample("$[?@.n < 'x']"), Some(json!([{"n": ""}])));
+}
+
+#[test]
+fn filter_existence_and_or() {
+    assert_eq!(sample("$[?@.a || @.b]"), Some(json!([{"a": null}])));
+    assert_eq!(sample("$[?@[1]]"), Some(json!([[null, null]])));
+}
+
+#[test]
+fn filter_match() {
+    assert_eq!(
+        sample("$[?match(@.code, 'a.c')]"),
+        Some(json!([{"code": "a.c"}]))
+    );
+}
+
+#[test]
+fn unsatisfiable_filter() {
+    assert_eq!(sample("$[?@.a == 1 && @.a == 2]"), None);
+}
@@ -0,0 +1,12 @@
+#![cfg(feature = "reexport_serde_json")]
+
+use jsonpath_rfc9535_locations::{find, serde_json::json};
+
+jsonpath_rfc9535_locations::assert_serde_json_version!();
+
+#[test]
+fn reexported_values() {
+    let value = json!({"a": [1, 2]});
+    let nodes = find("$.a[1]", &value).unwrap();
+    assert_eq!(nodes.len(), 1);
+}
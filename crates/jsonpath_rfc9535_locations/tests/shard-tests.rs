@@ -0,0 +1,103 @@
+use std::{num::NonZeroUsize, thread};
+
+use jsonpath_rfc9535_locations::{node::NodeList, Query, ENV};
+use serde_json::{json, Value};
+
+fn shard_count(n: usize) -> NonZeroUsize {
+    NonZeroUsize::new(n).unwrap()
+}
+
+fn sharded(query: &str, value: &Value, n: usize) -> Vec<String> {
+    let query = Query::standard(query).unwrap();
+    let nodes = thread::scope(|scope| {
+        let workers: Vec<_> = query
+            .find_sharded(value, shard_count(n), &ENV)
+            .into_iter()
+            .map(|shard| scope.spawn(move || shard.find()))
+            .collect();
+        NodeList::from_shards(workers.into_iter().map(|worker| worker.join().unwrap()))
+    });
+    nodes.paths()
+}
+
+fn data() -> Value {
+    json!({
+        "items": (0..10).map(|i| json!({"id": i, "tags": ["a", "b"]})).collect::<Vec<_>>(),
+        "limit": 4,
+        "meta": {"a": 1, "b": {"c": 2}}
+    })
+}
+
+#[test]
+fn same_nodes_as_find() {
+    let value = data();
+    for query in [
+        "$",
+        "$.items[*].id",
+        "$.items[?@.id < $.limit].tags[1]",
+        "$.items[?@.id > 6]",
+        "$.items[1, 0, 1]",
+        "$.items[::-3].id",
+        "$..c",
+        "$..*",
+        "$.meta[?@]",
+        "$.nosuchthing[*]",
+        "$.items[?@.id == 3, ?@.id == 1]",
+    ] {
+        let want = Query::standard(query).unwrap().find(&value, &ENV).paths();
+        for n in [1, 2, 3, 7, 64] {
+            assert_eq!(sharded(query, &value, n), want, "{query} with {n} shards");
+        }
+    }
+}
+
+#[test]
+fn filters_are_split_between_shards() {
+    let value = json!([1, 2, 3, 4, 5, 6]);
+    let query = Query::standard("$[?@ > 1]").unwrap();
+    let shards = query.find_sharded(&value, shard_count(4), &ENV);
+    assert_eq!(
+        shards.iter().map(|shard| shard.len()).collect::<Vec<_>>(),
+        vec![2, 2, 1, 1]
+    );
+    assert_eq!(
+        shards
+            .into_iter()
+            .map(|shard| shard.find().into_node_list().paths())
+            .collect::<Vec<_>>(),
+        vec![
+            vec!["$[1]".to_owned()],
+            vec!["$[2]".to_owned(), "$[3]".to_owned()],
+            vec!["$[4]".to_owned()],
+            vec!["$[5]".to_owned()],
+        ]
+    );
+}
+
+#[test]
+fn more_shards_than_nodes() {
+    let value = json!({"a": [1, 2]});
+    let query = Query::standard("$.a[*]").unwrap();
+    let shards = query.find_sharded(&value, shard_count(5), &ENV);
+    assert_eq!(shards.len(), 5);
+    assert_eq!(shards.iter().filter(|shard| shard.is_empty()).count(), 3);
+}
+
+#[test]
+fn shard_node_list() {
+    let value = json!([0, 1, 2, 3, 4, 5, 6]);
+    let nodes = Query::standard("$[*]").unwrap().find(&value, &ENV);
+    let shards = nodes.shard(shard_count(3));
+    assert_eq!(
+        shards
+            .iter()
+            .map(|nodes| nodes.clone().into_node_list().paths())
+            .collect::<Vec<_>>(),
+        vec![
+            vec!["$[0]", "$[1]", "$[2]"],
+            vec!["$[3]", "$[4]"],
+            vec!["$[5]", "$[6]"],
+        ]
+    );
+    assert_eq!(NodeList::from_shards(shards).len(), 7);
+}
@@ -0,0 +1,80 @@
+#![cfg(feature = "simd")]
+
+use jsonpath_rfc9535_locations::{find, json_like};
+use serde_json::Value;
+
+const DATA: &str = r#"{
+    "store": {
+        "book": [
+            {"title": "a", "price": 8, "tags": ["x"]},
+            {"title": "b", "price": 12.5, "tags": ["y", "z"]},
+            {"title": "c", "price": 22, "meta": {"tags": ["y", "z"]}}
+        ],
+        "bicycle": {"price": 19, "color": "red"},
+        "big": 18446744073709551615,
+        "nothing": null
+    }
+}"#;
+
+const QUERIES: [&str; 10] = [
+    "$.store.book[1].title",
+    "$.store.book[-1:]",
+    "$..price",
+    "$..*",
+    "$.store.book[?@.price < 10].title",
+    "$.store.book[?@.tags == $.store.book[2].meta.tags].title",
+    "$.store.book[?count(@.*) > 3]",
+    "$.store.book[?match(@.title, '[bc]') && length(@.tags) == 2]",
+    "$.store[?@ == 18446744073709551615]",
+    "$.store[?@ == null]",
+];
+
+/// Paths and values selected by `query`, sorted by path, as simd-json
+/// doesn't preserve member order.
+fn sorted<J: serde::Serialize>(nodes: Vec<(String, &J)>) -> Vec<(String, Value)> {
+    let mut nodes: Vec<(String, Value)> = nodes
+        .into_iter()
+        .map(|(path, value)| (path, serde_json::to_value(value).unwrap()))
+        .collect();
+    nodes.sort_by(|a, b| a.0.cmp(&b.0));
+    nodes
+}
+
+#[test]
+fn owned_values() {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    let mut bytes = DATA.as_bytes().to_vec();
+    let simd_value = simd_json::to_owned_value(&mut bytes).unwrap();
+
+    for query in QUERIES {
+        let expected = sorted(
+            find(query, &value)
+                .unwrap()
+                .iter()
+                .map(|node| (node.path(), node.value))
+                .collect(),
+        );
+        let got = sorted(
+            json_like::find(query, &simd_value)
+                .unwrap()
+                .into_iter()
+                .map(|node| (node.path(), node.value))
+                .collect(),
+        );
+        assert_eq!(got, expected, "{query}");
+    }
+}
+
+#[test]
+fn borrowed_values() {
+    let mut bytes = DATA.as_bytes().to_vec();
+    let simd_value = simd_json::to_borrowed_value(&mut bytes).unwrap();
+
+    let nodes = json_like::find("$.store.book[?@.price > 10].title", &simd_value).unwrap();
+    let mut titles: Vec<&str> = nodes
+        .iter()
+        .map(|node| json_like::JsonLike::as_str(node.value).unwrap())
+        .collect();
+    titles.sort();
+    assert_eq!(titles, vec!["b", "c"]);
+}
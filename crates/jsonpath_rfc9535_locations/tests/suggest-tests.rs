@@ -0,0 +1,61 @@
+use jsonpath_rfc9535_locations::suggest::suggest;
+use serde_json::{json, Value};
+
+fn data() -> Value {
+    json!({
+        "users": [
+            {"name": "a", "nickname": "b", "address": {"city": "x"}},
+            {"name": "c", "age": 3, "first name": "d"},
+        ],
+        "count": 2
+    })
+}
+
+#[test]
+fn shorthand_names() {
+    let value = data();
+    assert_eq!(suggest("$.u", &value), vec!["$.users"]);
+    assert_eq!(suggest("$.users", &value), vec!["$.users"]);
+    assert_eq!(suggest("$.", &value), vec!["$.count", "$.users"]);
+    assert_eq!(
+        suggest("$.users[*].n", &value),
+        vec!["$.users[*].name", "$.users[*].nickname"]
+    );
+    assert_eq!(
+        suggest("$.users[1].", &value),
+        vec![
+            "$.users[1].age",
+            "$.users[1]['first name']",
+            "$.users[1].name"
+        ]
+    );
+}
+
+#[test]
+fn bracketed_names() {
+    let value = data();
+    assert_eq!(
+        suggest("$.users[1]['f", &value),
+        vec!["$.users[1]['first name']"]
+    );
+    assert_eq!(suggest("$['c", &value), vec!["$['count']"]);
+
+    let value = json!({"it's": 1});
+    assert_eq!(suggest("$['it", &value), vec!["$['it\\'s']"]);
+}
+
+#[test]
+fn descendant_names() {
+    let value = data();
+    assert_eq!(suggest("$..ci", &value), vec!["$..city"]);
+    assert_eq!(suggest("$.users[0]..c", &value), vec!["$.users[0]..city"]);
+}
+
+#[test]
+fn nothing_to_complete() {
+    let value = data();
+    assert!(suggest("$['users']", &value).is_empty());
+    assert!(suggest("$.users[0", &value).is_empty());
+    assert!(suggest("$.nope.", &value).is_empty());
+    assert!(suggest("$[?@.a ==", &value).is_empty());
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "toml")]
+
+use jsonpath_rfc9535_locations::{document::DuplicateKeys, env::Environment, toml};
+use serde_json::json;
+
+const DATA: &str = r#"
+title = "example"
+released = 1979-05-27T07:32:00Z
+
+[[servers]]
+name = "alpha"
+port = 8080
+
+[[servers]]
+name = "beta"
+port = 9090
+"#;
+
+#[test]
+fn to_json() {
+    let doc: ::toml::Value = ::toml::from_str(DATA).unwrap();
+    assert_eq!(
+        toml::to_json(&doc),
+        json!({
+            "title": "example",
+            "released": "1979-05-27T07:32:00Z",
+            "servers": [{"name": "alpha", "port": 8080}, {"name": "beta", "port": 9090}]
+        })
+    );
+}
+
+#[test]
+fn find_borrows_toml_values() {
+    let doc: ::toml::Value = ::toml::from_str(DATA).unwrap();
+    let rv = toml::find("$.servers[?@.port > 9000].name", &doc).unwrap();
+    assert_eq!(
+        rv,
+        vec![(
+            String::from("$['servers'][1]['name']"),
+            &::toml::Value::String(String::from("beta"))
+        )]
+    );
+}
+
+#[test]
+fn compare_datetime_as_string() {
+    let doc: ::toml::Value = ::toml::from_str(DATA).unwrap();
+    let rv = toml::find("$[?@ == '1979-05-27T07:32:00Z']", &doc).unwrap();
+    assert_eq!(rv.len(), 1);
+    assert!(matches!(rv[0].1, ::toml::Value::Datetime(_)));
+}
+
+#[test]
+fn duplicate_keys() {
+    // TOML rejects duplicate keys itself, so every policy sees the same
+    // document.
+    assert!(::toml::from_str::<::toml::Value>("a = 1\na = 2").is_err());
+
+    let doc: ::toml::Value = ::toml::from_str(DATA).unwrap();
+    for duplicate_keys in [
+        DuplicateKeys::First,
+        DuplicateKeys::Last,
+        DuplicateKeys::Error,
+    ] {
+        let env: &'static Environment = Box::leak(Box::new(
+            Environment::new().with_duplicate_keys(duplicate_keys),
+        ));
+        let rv = toml::find_with(env, "$.servers[*].name", &doc).unwrap();
+        assert_eq!(rv.len(), 2);
+    }
+}
@@ -0,0 +1,121 @@
+use jsonpath_rfc9535_locations::{
+    find, find_with_options,
+    options::FindOptions,
+    trace::{Outcome, Trace},
+    Query, ENV,
+};
+use serde_json::{json, Value};
+
+fn traced(expr: &str, value: &Value) -> Trace {
+    let query = Query::standard(expr).unwrap();
+    let (nodes, trace) = query.find_traced(value, &ENV);
+
+    let paths: Vec<String> = nodes.iter().map(|node| node.path()).collect();
+    let expected: Vec<String> = find(expr, value)
+        .unwrap()
+        .iter()
+        .map(|node| node.path())
+        .collect();
+    assert_eq!(paths, expected, "{}", expr);
+
+    trace
+}
+
+fn events(trace: &Trace) -> Vec<(usize, &str, &str, Outcome)> {
+    trace
+        .events()
+        .map(|event| (event.segment, event.selector, event.path, event.outcome))
+        .collect()
+}
+
+#[test]
+fn child_segments() {
+    let value = json!({"a": [1, 2, 3]});
+    let trace = traced("$.a[0, -1]", &value);
+
+    assert_eq!(trace.query, "$['a'][0, -1]");
+    assert_eq!(
+        events(&trace),
+        vec![
+            (0, "'a'", "$['a']", Outcome::Selected),
+            (1, "0", "$['a'][0]", Outcome::Selected),
+            (1, "-1", "$['a'][2]", Outcome::Selected),
+        ]
+    );
+}
+
+#[test]
+fn filters_record_rejected_nodes() {
+    let value = json!([{"n": 1}, {"n": 5}, "x"]);
+    let trace = traced("$[?@.n > 2]", &value);
+
+    assert_eq!(
+        events(&trace),
+        vec![
+            (0, "?@['n'] > 2", "$[0]", Outcome::Rejected),
+            (0, "?@['n'] > 2", "$[1]", Outcome::Selected),
+            (0, "?@['n'] > 2", "$[2]", Outcome::Rejected),
+        ]
+    );
+}
+
+#[test]
+fn descendant_segments() {
+    let value = json!({"a": {"b": {"b": 1}}});
+    let trace = traced("$..b", &value);
+
+    assert_eq!(
+        events(&trace),
+        vec![
+            (0, "'b'", "$['a']['b']", Outcome::Selected),
+            (0, "'b'", "$['a']['b']['b']", Outcome::Selected),
+        ]
+    );
+}
+
+#[test]
+fn paths_are_stored_once() {
+    let value = json!([1, 2]);
+    let trace = traced("$[*, 0, ?@ > 1]", &value);
+
+    assert_eq!(trace.paths, vec!["$[0]", "$[1]"]);
+    assert_eq!(trace.steps.len(), 5);
+}
+
+#[test]
+fn traces_round_trip_through_json() {
+    let value = json!({"users": [{"age": 12}, {"age": 42}]});
+    let trace = traced("$.users[?@.age > 18].age", &value);
+
+    let saved = serde_json::to_value(&trace).unwrap();
+    assert_eq!(saved["steps"][1]["outcome"], json!("rejected"));
+
+    let replayed: Trace = serde_json::from_value(saved).unwrap();
+    assert_eq!(replayed, trace);
+}
+
+#[test]
+fn display() {
+    let value = json!([{"n": 1}, {"n": 5}]);
+    let trace = traced("$[?@.n > 2].n", &value);
+
+    assert_eq!(
+        trace.to_string(),
+        "$[?@['n'] > 2]['n']\n\
+         0\t?@['n'] > 2\t$[0]\trejected\n\
+         0\t?@['n'] > 2\t$[1]\tselected\n\
+         1\t'n'\t$[1]['n']\tselected"
+    );
+}
+
+#[test]
+fn find_options() {
+    let value = json!([1, 2]);
+
+    let result = find_with_options("$[1]", &value, &FindOptions::new()).unwrap();
+    assert!(result.trace.is_none());
+
+    let result = find_with_options("$[1]", &value, &FindOptions::new().trace(true)).unwrap();
+    assert_eq!(result.found.len(), 1);
+    assert_eq!(result.trace.unwrap().paths, vec!["$[1]"]);
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "yaml")]
+
+use jsonpath_rfc9535_locations::{document::DuplicateKeys, env::Environment, yaml};
+use serde_json::json;
+use serde_yaml::Value as YamlValue;
+
+const DATA: &str = r#"
+defaults: &defaults
+  retries: 3
+services:
+  - name: web
+    config: *defaults
+  - name: worker
+    config: !custom
+      retries: 5
+ports:
+  80: http
+  true: enabled
+"#;
+
+#[test]
+fn to_json() {
+    let doc: YamlValue = serde_yaml::from_str(DATA).unwrap();
+    assert_eq!(
+        yaml::to_json(&doc).unwrap(),
+        json!({
+            "defaults": {"retries": 3},
+            "services": [
+                {"name": "web", "config": {"retries": 3}},
+                {"name": "worker", "config": {"retries": 5}}
+            ],
+            "ports": {"80": "http", "true": "enabled"}
+        })
+    );
+}
+
+#[test]
+fn find_through_alias_and_tag() {
+    let doc: YamlValue = serde_yaml::from_str(DATA).unwrap();
+    let rv = yaml::find("$.services[*].config.retries", &doc).unwrap();
+    assert_eq!(
+        rv,
+        vec![
+            (
+                String::from("$['services'][0]['config']['retries']"),
+                &YamlValue::from(3)
+            ),
+            (
+                String::from("$['services'][1]['config']['retries']"),
+                &YamlValue::from(5)
+            )
+        ]
+    );
+}
+
+#[test]
+fn find_non_string_key() {
+    let doc: YamlValue = serde_yaml::from_str(DATA).unwrap();
+    let rv = yaml::find("$.ports['80']", &doc).unwrap();
+    assert_eq!(
+        rv,
+        vec![(String::from("$['ports']['80']"), &YamlValue::from("http"))]
+    );
+}
+
+#[test]
+fn unsupported_key() {
+    let doc: YamlValue = serde_yaml::from_str("[1, 2]: x").unwrap();
+    let err = yaml::find("$.*", &doc).unwrap_err();
+    assert_eq!(
+        err.msg,
+        "YAML mapping keys must be strings, numbers, booleans or null"
+    );
+}
+
+#[test]
+fn duplicate_keys() {
+    let doc: YamlValue = serde_yaml::from_str("1: a\n'1': b").unwrap();
+
+    assert_eq!(yaml::to_json(&doc).unwrap(), json!({"1": "b"}));
+    let rv = yaml::find("$[?@ == 'b']", &doc).unwrap();
+    assert_eq!(rv, vec![(String::from("$['1']"), &YamlValue::from("b"))]);
+
+    let env: &'static Environment = Box::leak(Box::new(
+        Environment::new().with_duplicate_keys(DuplicateKeys::First),
+    ));
+    let rv = yaml::find_with(env, "$['1']", &doc).unwrap();
+    assert_eq!(rv, vec![(String::from("$['1']"), &YamlValue::from("a"))]);
+    assert!(yaml::find_with(env, "$[?@ == 'b']", &doc)
+        .unwrap()
+        .is_empty());
+
+    let env: &'static Environment = Box::leak(Box::new(
+        Environment::new().with_duplicate_keys(DuplicateKeys::Error),
+    ));
+    let err = yaml::find_with(env, "$.*", &doc).unwrap_err();
+    assert_eq!(err.msg, "duplicate member name `1`");
+}
@@ -138,7 +138,7 @@ impl fmt::Display for Selector {
                         .unwrap_or(String::from("1")),
                 )
             }
-            Selector::Wild { .. } => f.write_char('*'),
+            Selector::Wild => f.write_char('*'),
             Selector::Filter { expression, .. } => write!(f, "?{expression}"),
         }
     }
@@ -225,9 +225,9 @@ impl FilterExpression {
     pub fn is_literal(&self) -> bool {
         matches!(
             self,
-            FilterExpression::True { .. }
-                | FilterExpression::False { .. }
-                | FilterExpression::Null { .. }
+            FilterExpression::True
+                | FilterExpression::False
+                | FilterExpression::Null
                 | FilterExpression::String { .. }
                 | FilterExpression::Int { .. }
                 | FilterExpression::Float { .. }
@@ -238,9 +238,9 @@ impl FilterExpression {
 impl fmt::Display for FilterExpression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            FilterExpression::True { .. } => f.write_str("true"),
-            FilterExpression::False { .. } => f.write_str("false"),
-            FilterExpression::Null { .. } => f.write_str("null"),
+            FilterExpression::True => f.write_str("true"),
+            FilterExpression::False => f.write_str("false"),
+            FilterExpression::Null => f.write_str("null"),
             FilterExpression::String { value, .. } => write!(f, "'{value}'"),
             FilterExpression::Int { value, .. } => write!(f, "{value}"),
             FilterExpression::Float { value, .. } => write!(f, "{value}"),
@@ -7,7 +7,13 @@
 
 use std::{collections::HashMap, ops::RangeInclusive};
 
-use pest::{iterators::Pair, Parser};
+use jsonpath_rfc9535::{parser::check_nesting_depth, span::Span};
+
+use pest::{
+    error::{Error, ErrorVariant, InputLocation},
+    iterators::Pair,
+    Parser,
+};
 use pest_derive::Parser;
 
 use crate::{
@@ -15,6 +21,9 @@ use crate::{
     errors::JSONPathError,
 };
 
+/// The default [`JSONPathParser::max_nesting_depth`].
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = jsonpath_rfc9535::parser::DEFAULT_MAX_NESTING_DEPTH;
+
 #[derive(Parser)]
 #[grammar = "jsonpath.pest"]
 struct JSONPath;
@@ -79,6 +88,16 @@ pub fn standard_functions() -> HashMap<String, FunctionSignature> {
 pub struct JSONPathParser {
     pub index_range: RangeInclusive<i64>,
     pub functions: HashMap<String, FunctionSignature>,
+    /// Skip a leading byte order mark (U+FEFF) instead of rejecting it.
+    pub skip_bom: bool,
+    /// The deepest brackets and parentheses can nest, outside of string
+    /// literals, before parsing fails with a
+    /// [`JSONPathErrorType::DepthError`](crate::errors::JSONPathErrorType::DepthError).
+    ///
+    /// `$[?@.a]` has a depth of one and `$[?count(@[?@.a]) > 1]` a depth of
+    /// three. Queries are checked before they're given to pest, which
+    /// recurses at each level, so this bounds its stack use.
+    pub max_nesting_depth: usize,
 }
 
 impl Default for JSONPathParser {
@@ -92,12 +111,28 @@ impl JSONPathParser {
         JSONPathParser {
             index_range: ((-2_i64).pow(53) + 1..=2_i64.pow(53) - 1),
             functions: standard_functions(),
+            skip_bom: false,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
         }
     }
 
     pub fn parse(&self, query: &str) -> Result<Query, JSONPathError> {
-        let segments: Result<Vec<_>, _> = JSONPath::parse(Rule::jsonpath, query)
-            .map_err(|err| JSONPathError::syntax(err.to_string()))?
+        let start = match query.strip_prefix('\u{FEFF}') {
+            Some(_) if !self.skip_bom => {
+                return Err(JSONPathError::syntax_at(
+                    String::from("unexpected byte order mark"),
+                    Span::new(0, 0),
+                ))
+            }
+            Some(_) => '\u{FEFF}'.len_utf8(),
+            None => 0,
+        };
+
+        check_nesting_depth(query, self.max_nesting_depth)
+            .map_err(|err| JSONPathError::depth_at(err.msg, err.span))?;
+
+        let segments: Result<Vec<_>, _> = JSONPath::parse(Rule::jsonpath, &query[start..])
+            .map_err(|err| syntax_error(query, start, err))?
             .map(|segment| self.parse_segment(segment))
             .collect();
 
@@ -374,13 +409,23 @@ impl JSONPathParser {
                     .parse::<f64>()
                     .map_err(|_| JSONPathError::syntax(String::from("invalid float literal")))?,
             })
+        } else if let Ok(value) = n.parse::<i64>() {
+            Ok(FilterExpression::Int { value })
         } else {
-            Ok(FilterExpression::Int {
-                value: n
-                    .parse::<f64>()
-                    .map_err(|_| JSONPathError::syntax(String::from("invalid integer literal")))?
-                    as i64,
-            })
+            let value = n
+                .parse::<f64>()
+                .map_err(|_| JSONPathError::syntax(String::from("invalid integer literal")))?;
+
+            // Integers with an exponent, like `1e3`, go through a float.
+            // Those out of range for an i64, like `1e400`, stay floats rather
+            // than saturating at `i64::MAX`.
+            if value.abs() < i64::MAX as f64 {
+                Ok(FilterExpression::Int {
+                    value: value as i64,
+                })
+            } else {
+                Ok(FilterExpression::Float { value })
+            }
         }
     }
 
@@ -727,6 +772,46 @@ fn unescape_string(value: &str) -> String {
     rv
 }
 
+/// Convert a pest error into a syntax error, using the same message and
+/// position as the hand-crafted parser in `jsonpath_rfc9535` for trailing
+/// content after a complete query, queries ending after a dot and control
+/// characters. `start` is the offset into `query` that parsing started from,
+/// after any skipped byte order mark.
+fn syntax_error(query: &str, start: usize, err: Error<Rule>) -> JSONPathError {
+    let position = start
+        + match err.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((pos, _)) => pos,
+        };
+
+    let before = query[start..position].trim_end_matches([' ', '\t', '\n', '\r']);
+    let complete = || JSONPath::parse(Rule::jsonpath, before).is_ok();
+
+    let msg = match query[position..].chars().next() {
+        Some(ch) if ch.is_ascii_control() && !matches!(ch, '\t' | '\n' | '\r') => {
+            format!("unexpected control character U+{:04X}", ch as u32)
+        }
+        Some(ch) if complete() => format!(
+            "expected '.', '..' or a bracketed selection, found '{}'",
+            ch
+        ),
+        None if before.len() < position - start && complete() => {
+            String::from("unexpected trailing whitespace")
+        }
+        None if matches!(
+            &err.variant,
+            ErrorVariant::ParsingError { positives, .. }
+                if positives.contains(&Rule::member_name_shorthand)
+        ) =>
+        {
+            String::from("unexpected end of query")
+        }
+        _ => err.to_string(),
+    };
+
+    JSONPathError::syntax_at(msg, Span::new(position, position).with_chars(query))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
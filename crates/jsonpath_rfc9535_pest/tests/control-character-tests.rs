@@ -0,0 +1,42 @@
+use jsonpath_rfc9535_pest::{JSONPathParser, Query};
+
+#[test]
+fn byte_order_mark() {
+    let err = Query::standard("\u{FEFF}$.a").unwrap_err();
+    assert_eq!(err.msg, "unexpected byte order mark");
+    assert_eq!(err.span.map(|span| span.start), Some(0));
+
+    let mut parser = JSONPathParser::new();
+    parser.skip_bom = true;
+    assert_eq!(parser.parse("\u{FEFF}$.a").unwrap().to_string(), "$['a']");
+    assert_eq!(parser.parse("$.a").unwrap().to_string(), "$['a']");
+
+    let err = parser.parse("\u{FEFF}$.a]").unwrap_err();
+    assert_eq!(err.span.map(|span| span.start), Some(6));
+}
+
+#[test]
+fn control_characters() {
+    for (query, position) in [
+        ("$\u{0}", 1),
+        ("$.a\u{1}b", 3),
+        ("$[\u{1}0]", 2),
+        ("$..\u{2}", 3),
+        ("$[0:\u{3}]", 4),
+        ("$[?@.a\u{1}==1]", 6),
+        ("$[?@.a==\u{7F}1]", 8),
+    ] {
+        let err = Query::standard(query).unwrap_err();
+        assert_eq!(
+            (err.msg, err.span.map(|span| span.start)),
+            (
+                format!(
+                    "unexpected control character U+{:04X}",
+                    query[position..].chars().next().unwrap() as u32
+                ),
+                Some(position)
+            ),
+            "{query:?}"
+        );
+    }
+}
@@ -1,3 +1,5 @@
+#![forbid(unsafe_code)]
+
 pub mod ast;
 pub mod errors;
 pub mod parser;
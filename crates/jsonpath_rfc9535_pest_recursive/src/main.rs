@@ -1,3 +1,5 @@
+#![forbid(unsafe_code)]
+
 use jsonpath_rfc9535_pest_recursive::JSONPathParser;
 
 fn main() {
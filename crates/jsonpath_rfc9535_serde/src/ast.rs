@@ -5,16 +5,31 @@
 //! filter selector is a tree of [`FilterExpression`]s.
 //!
 //! [RFC 9535]: https://datatracker.ietf.org/doc/html/rfc9535
+
 use itertools::Itertools;
+use jsonpath_rfc9535::{
+    number::{self, Number},
+    resolve_index,
+    span::Span,
+};
 use lazy_static::lazy_static;
 use serde_json::Value;
 use std::{
+    borrow::Cow,
+    cell::RefCell,
     cmp,
+    collections::HashMap,
     fmt::{self, Write},
+    ops::Range,
+    time::Instant,
 };
 
 use crate::{
-    env::Environment, errors::JSONPathError, function::ExpressionType, parser::JSONPathParser,
+    env::Environment,
+    errors::JSONPathError,
+    function::ExpressionType,
+    parser::JSONPathParser,
+    stats::{EvalStats, NothingReason},
 };
 
 lazy_static! {
@@ -51,16 +66,211 @@ pub type NodeList<'a> = Vec<Node<'a>>;
 struct QueryContext<'a, 'b> {
     env: &'b Environment,
     root: &'a Value,
+    stats: Option<&'b RefCell<EvalStats>>,
+    /// Results of absolute queries found in filters, so we don't traverse
+    /// from the root once per node. `None` for queries that call
+    /// non-deterministic functions, which are evaluated every time.
+    root_queries: RefCell<HashMap<QueryId, Option<NodeList<'a>>>>,
+    /// Results of absolute queries evaluated by [`Query::bind_root`].
+    bound_root_queries: Option<&'b HashMap<QueryId, Option<NodeList<'a>>>>,
+    descendants: RefCell<DescendantIndex<'a>>,
+    /// When evaluation must finish, from the environment's timeout.
+    deadline: Option<Instant>,
+}
+
+/// Identifies an absolute query in a filter by its address, which doesn't
+/// change while the query it's part of is being evaluated.
+type QueryId = usize;
+
+fn query_id(query: &Query) -> QueryId {
+    query as *const Query as QueryId
+}
+
+/// Nodes visited by descendant segments, in document order, so a subtree
+/// visited once doesn't need to be walked again.
+#[derive(Default)]
+struct DescendantIndex<'a> {
+    nodes: NodeList<'a>,
+    // The range of `nodes` holding each visited value and its descendants.
+    ranges: HashMap<*const Value, Range<usize>>,
+}
+
+impl<'a> DescendantIndex<'a> {
+    fn insert(&mut self, node: &Node<'a>, context: &QueryContext) -> Result<(), JSONPathError> {
+        context.check_deadline()?;
+        let start = self.nodes.len();
+        self.nodes.push(node.clone());
+
+        match node.value {
+            Value::Object(obj) => {
+                for (k, v) in obj {
+                    self.insert(&node.new_child_member(v, k), context)?;
+                }
+            }
+            Value::Array(arr) => {
+                for (i, e) in arr.iter().enumerate() {
+                    self.insert(&node.new_child_element(e, i), context)?;
+                }
+            }
+            _ => (),
+        }
+
+        self.ranges
+            .insert(node.value as *const Value, start..self.nodes.len());
+        Ok(())
+    }
+
+    /// `node` and its descendants, if they've been indexed. `node` might
+    /// have been indexed with a different location, like when it was the
+    /// target of a relative query, so we rebase locations on `node`'s.
+    fn get(&self, node: &Node<'a>) -> Option<NodeList<'a>> {
+        let range = self.ranges.get(&(node.value as *const Value))?;
+        let indexed = self.nodes.get(range.clone())?;
+        let prefix = indexed.first()?.location.len();
+        Some(
+            indexed
+                .iter()
+                .map(|n| Node {
+                    value: n.value,
+                    location: format!(
+                        "{}{}",
+                        node.location,
+                        n.location.get(prefix..).unwrap_or_default()
+                    ),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl<'a, 'b> QueryContext<'a, 'b> {
+    fn new(root: &'a Value, env: &'b Environment, stats: Option<&'b RefCell<EvalStats>>) -> Self {
+        QueryContext {
+            env,
+            root,
+            stats,
+            root_queries: RefCell::new(HashMap::new()),
+            bound_root_queries: None,
+            descendants: RefCell::new(DescendantIndex::default()),
+            deadline: env.timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+
+    fn check_deadline(&self) -> Result<(), JSONPathError> {
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Err(JSONPathError::limit(format!(
+                "query timed out after {:?}",
+                self.env.timeout.unwrap_or_default()
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_nodes(&self, count: usize) -> Result<(), JSONPathError> {
+        if count > self.env.max_nodes {
+            Err(JSONPathError::limit(format!(
+                "too many nodes ({}, limit {})",
+                count, self.env.max_nodes
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Append `more` to the nodes selected so far by a segment, failing as
+    /// soon as there are too many of them.
+    fn extend_nodes(
+        &self,
+        nodes: &mut NodeList<'a>,
+        more: NodeList<'a>,
+    ) -> Result<(), JSONPathError> {
+        self.check_nodes(nodes.len() + more.len())?;
+        nodes.extend(more);
+        Ok(())
+    }
+
+    fn check_depth(&self, node: &Node<'a>) -> Result<(), JSONPathError> {
+        if self.env.max_depth != usize::MAX && deeper_than(node.value, self.env.max_depth) {
+            Err(JSONPathError::limit(format!(
+                "{} is nested too deeply (limit {})",
+                node.location, self.env.max_depth
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// `node` and all of its descendants, from the descendant index if this
+    /// subtree has been visited before.
+    fn descendants(&self, node: &Node<'a>) -> Result<NodeList<'a>, JSONPathError> {
+        if let Some(nodes) = self.descendants.borrow().get(node) {
+            if let Some(stats) = self.stats {
+                stats.borrow_mut().descendant_index_hits += 1;
+            }
+            return Ok(nodes);
+        }
+
+        self.check_depth(node)?;
+
+        if self.env.descendant_index_limit == 0 {
+            let mut nodes = NodeList::new();
+            visit(node, &mut nodes, self)?;
+            return Ok(nodes);
+        }
+
+        let mut index = self.descendants.borrow_mut();
+        let start = index.nodes.len();
+        index.insert(node, self)?;
+        let nodes = index.nodes.get(start..).unwrap_or_default().to_vec();
+
+        // Forget this subtree if it takes us over the limit.
+        if index.nodes.len() > self.env.descendant_index_limit {
+            let DescendantIndex { nodes, ranges } = &mut *index;
+            for n in nodes.drain(start..) {
+                ranges.remove(&(n.value as *const Value));
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    /// Resolve `query` against the root value, reusing the result from an
+    /// earlier evaluation of the same query in this context.
+    fn resolve_root_query(&self, query: &Query) -> Result<NodeList<'a>, JSONPathError> {
+        let id = query_id(query);
+
+        if let Some(Some(nodes)) = self.bound_root_queries.and_then(|bound| bound.get(&id)) {
+            return Ok(nodes.clone());
+        }
+
+        match self.root_queries.borrow().get(&id) {
+            Some(Some(nodes)) => {
+                if let Some(stats) = self.stats {
+                    stats.borrow_mut().root_query_cache_hits += 1;
+                }
+                return Ok(nodes.clone());
+            }
+            Some(None) => return query.resolve(self.root, self),
+            None => (),
+        }
+
+        let nodes = query.resolve(self.root, self)?;
+        let cached = query.is_deterministic(self.env).then(|| nodes.clone());
+        self.root_queries.borrow_mut().insert(id, cached);
+        Ok(nodes)
+    }
 }
 
-// TODO: UInt
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum FilterExpressionResult<'a> {
     Bool(bool),
     Int(i64),
+    /// An integer too big for an `i64`.
+    UInt(u64),
     Float(f64),
     Null,
-    String(String),
+    String(Cow<'a, str>),
     Array(&'a Value),
     Object(&'a Value),
     Nodes(NodeList<'a>),
@@ -68,20 +278,32 @@ pub enum FilterExpressionResult<'a> {
 }
 
 impl<'a> FilterExpressionResult<'a> {
+    /// The result of a singular query argument, `Nothing` if the query
+    /// selected nothing.
+    pub fn from_singular(value: Option<&'a Value>) -> Self {
+        value.map_or(
+            FilterExpressionResult::Nothing,
+            FilterExpressionResult::from_json_value,
+        )
+    }
+
     pub fn from_json_value(value: &'a Value) -> Self {
         match value {
             Value::Bool(v) => FilterExpressionResult::Bool(*v),
             Value::Null => FilterExpressionResult::Null,
             Value::Number(n) => {
-                if n.is_f64() {
-                    FilterExpressionResult::Float(n.as_f64().unwrap())
-                } else if n.is_i64() {
-                    FilterExpressionResult::Int(n.as_i64().unwrap())
+                if let Some(i) = n.as_i64() {
+                    FilterExpressionResult::Int(i)
+                } else if let Some(u) = n.as_u64() {
+                    FilterExpressionResult::UInt(u)
                 } else {
-                    FilterExpressionResult::Int(n.as_i64().unwrap()) // XXX:
+                    n.as_f64().map_or(
+                        FilterExpressionResult::Nothing,
+                        FilterExpressionResult::Float,
+                    )
                 }
             }
-            Value::String(s) => FilterExpressionResult::String(s.to_owned()),
+            Value::String(s) => FilterExpressionResult::String(Cow::Borrowed(s)),
             Value::Array(_) => FilterExpressionResult::Array(value),
             Value::Object(_) => FilterExpressionResult::Object(value),
         }
@@ -91,23 +313,121 @@ impl<'a> FilterExpressionResult<'a> {
 #[derive(Debug)]
 pub struct Query {
     pub segments: Vec<Segment>,
+    /// The text this query was parsed from, which the spans of its segments
+    /// and filter expressions refer to. `None` for queries nested in a
+    /// filter, and for queries that weren't parsed.
+    pub text: Option<String>,
 }
 
 impl Query {
     pub fn new(segments: Vec<Segment>) -> Self {
-        Query { segments }
+        Query {
+            segments,
+            text: None,
+        }
     }
 
     pub fn standard(expr: &str) -> Result<Self, JSONPathError> {
         PARSER.parse(expr)
     }
 
-    pub fn find<'a, 'b>(
+    pub fn find<'a>(
         &self,
         value: &'a Value,
-        env: &'b Environment,
+        env: &Environment,
     ) -> Result<NodeList<'a>, JSONPathError> {
-        let context = QueryContext { root: value, env };
+        let context = QueryContext::new(value, env, None);
+        self.resolve(value, &context)
+            .map_err(|err| err.in_query(self))
+    }
+
+    /// Like [`Query::find`], but passing each node to `f` as it's selected
+    /// and stopping at the first error from `f`. The last segment is
+    /// resolved one input node at a time; earlier segments are resolved in
+    /// full, within the environment's limits.
+    pub(crate) fn find_each<'a>(
+        &self,
+        value: &'a Value,
+        env: &Environment,
+        mut f: impl FnMut(Node<'a>) -> Result<(), JSONPathError>,
+    ) -> Result<(), JSONPathError> {
+        let context = QueryContext::new(value, env, None);
+        let root_node = Node {
+            value,
+            location: String::from("$"),
+        };
+
+        // Queries end with `Segment::Eoi`, which selects nothing new.
+        let segments = match self.segments.split_last() {
+            Some((Segment::Eoi, segments)) => segments,
+            _ => &self.segments[..],
+        };
+
+        let Some((last, segments)) = segments.split_last() else {
+            return f(root_node);
+        };
+
+        let nodes = segments
+            .iter()
+            .try_fold(vec![root_node], |nodes, segment| {
+                segment
+                    .resolve(nodes, &context)
+                    .map_err(|err| segment.locate(err))
+            })
+            .map_err(|err| err.in_query(self))?;
+
+        for node in nodes {
+            last.resolve(vec![node], &context)
+                .map_err(|err| last.locate(err).in_query(self))?
+                .into_iter()
+                .try_for_each(&mut f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Same as `find`, but also records comparisons that had a _Nothing_
+    /// operand, so you can see why a filter didn't match.
+    pub fn find_with_stats<'a>(
+        &self,
+        value: &'a Value,
+        env: &Environment,
+    ) -> Result<(NodeList<'a>, EvalStats), JSONPathError> {
+        let stats = RefCell::new(EvalStats::default());
+        let context = QueryContext::new(value, env, Some(&stats));
+        let nodes = self
+            .resolve(value, &context)
+            .map_err(|err| err.in_query(self))?;
+        Ok((nodes, stats.into_inner()))
+    }
+
+    /// Like [`Query::find`], but descendant segments apply their selectors
+    /// to each descendant in parallel, using rayon's global thread pool.
+    /// Nodes are in the same order as they would be from `find`.
+    ///
+    /// Absolute queries in filters are evaluated once, before anything else,
+    /// and their results are shared by all threads.
+    #[cfg(feature = "rayon")]
+    pub fn find_parallel<'a>(
+        &self,
+        value: &'a Value,
+        env: &Environment,
+    ) -> Result<NodeList<'a>, JSONPathError> {
+        let context = QueryContext::new(value, env, None);
+        let mut queries = Vec::new();
+        self.collect_root_queries(&mut queries);
+
+        for query in queries {
+            context
+                .resolve_root_query(query)
+                .map_err(|err| err.in_query(self))?;
+        }
+
+        let root_queries = context.root_queries.take();
+        let context = QueryContext {
+            bound_root_queries: Some(&root_queries),
+            ..context
+        };
 
         let root_node = Node {
             value,
@@ -117,8 +437,136 @@ impl Query {
         self.segments
             .iter()
             .try_fold(vec![root_node], |nodes, segment| {
-                segment.resolve(nodes, &context)
+                segment
+                    .resolve_parallel(nodes, &context)
+                    .map_err(|err| segment.locate(err))
             })
+            .map_err(|err| err.in_query(self))
+    }
+
+    /// Evaluate this query's absolute sub-queries against `root`, returning a
+    /// [`BoundQuery`] that reuses their results every time it is evaluated.
+    pub fn bind_root<'q, 'a, 'e>(
+        &'q self,
+        root: &'a Value,
+        env: &'e Environment,
+    ) -> Result<BoundQuery<'q, 'a, 'e>, JSONPathError> {
+        let context = QueryContext::new(root, env, None);
+        let mut queries = Vec::new();
+        self.collect_root_queries(&mut queries);
+
+        for query in queries {
+            context
+                .resolve_root_query(query)
+                .map_err(|err| err.in_query(self))?;
+        }
+
+        Ok(BoundQuery {
+            query: self,
+            root,
+            env,
+            root_queries: context.root_queries.into_inner(),
+        })
+    }
+
+    fn collect_root_queries<'q>(&'q self, queries: &mut Vec<&'q Query>) {
+        for segment in &self.segments {
+            if let Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. } = segment
+            {
+                for selector in selectors {
+                    if let Selector::Filter { expression } = selector {
+                        expression.collect_root_queries(queries);
+                    }
+                }
+            }
+        }
+    }
+
+    fn resolve<'a>(
+        &self,
+        value: &'a Value,
+        context: &QueryContext<'a, '_>,
+    ) -> Result<NodeList<'a>, JSONPathError> {
+        let root_node = Node {
+            value,
+            location: String::from("$"),
+        };
+
+        self.segments
+            .iter()
+            .try_fold(vec![root_node], |nodes, segment| {
+                segment
+                    .resolve(nodes, context)
+                    .map_err(|err| segment.locate(err))
+            })
+    }
+
+    /// Resolve this query's segments, starting with the one at `start`,
+    /// against `value`, which is at `location` in a document we don't have.
+    /// The query must not refer to the root value.
+    pub(crate) fn resolve_from<'a>(
+        &self,
+        start: usize,
+        value: &'a Value,
+        location: String,
+        env: &Environment,
+    ) -> Result<NodeList<'a>, JSONPathError> {
+        let context = QueryContext::new(value, env, None);
+        self.segments
+            .get(start..)
+            .unwrap_or_default()
+            .iter()
+            .try_fold(vec![Node { value, location }], |nodes, segment| {
+                segment
+                    .resolve(nodes, &context)
+                    .map_err(|err| segment.locate(err))
+            })
+            .map_err(|err| err.in_query(self))
+    }
+
+    /// The number of filter selectors in the segment at `index` that select
+    /// `value`, a child of a value in a document we don't have. The query
+    /// must not refer to the root value.
+    pub(crate) fn filters_matching(
+        &self,
+        index: usize,
+        value: &Value,
+        env: &Environment,
+    ) -> Result<usize, JSONPathError> {
+        let Some(
+            segment @ (Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }),
+        ) = self.segments.get(index)
+        else {
+            return Ok(0);
+        };
+
+        let context = QueryContext::new(value, env, None);
+        let mut count = 0;
+        for selector in selectors {
+            if let Selector::Filter { expression } = selector {
+                let rv = expression
+                    .evaluate(value, &context)
+                    .map_err(|err| segment.locate(err).in_query(self))?;
+                if is_truthy(rv) {
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Return `true` if a filter in this query, or in a query nested in one
+    /// of its filters, refers to the root value with `$`.
+    pub(crate) fn refers_to_root(&self) -> bool {
+        self.segments.iter().any(|segment| match segment {
+            Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. } => {
+                selectors.iter().any(|selector| match selector {
+                    Selector::Filter { expression } => expression.refers_to_root(),
+                    _ => false,
+                })
+            }
+            Segment::Eoi => false,
+        })
     }
 
     // Same as `find`, but uses explicit `for` loops and vectors to collect intermediate nodes.
@@ -127,7 +575,7 @@ impl Query {
         value: &'a Value,
         env: &'b Environment,
     ) -> Result<NodeList<'a>, JSONPathError> {
-        let context = QueryContext { root: value, env };
+        let context = QueryContext::new(value, env, None);
 
         let mut nodes: NodeList<'a> = vec![Node {
             value,
@@ -135,7 +583,9 @@ impl Query {
         }];
 
         for segment in self.segments.iter() {
-            nodes = segment.resolve_loop(nodes, &context)?;
+            nodes = segment
+                .resolve_loop(nodes, &context)
+                .map_err(|err| segment.locate(err).in_query(self))?;
         }
 
         Ok(nodes)
@@ -145,6 +595,45 @@ impl Query {
         self.segments.is_empty()
     }
 
+    /// Resolve a singular query against `value` directly, without building a
+    /// node list. Returns `None` if the query selects nothing, or if it is
+    /// not singular.
+    pub fn singular_value<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| segment.singular_value(value))
+    }
+
+    /// The path, starting with `identifier`, of the first member or element
+    /// this singular query expects that does not exist in `value`.
+    fn missing_path(&self, identifier: &str, value: &Value) -> String {
+        let mut path = String::from(identifier);
+        let mut value = Some(value);
+        for segment in &self.segments {
+            path.push_str(&segment.to_string());
+            value = value.and_then(|v| segment.singular_value(v));
+            if value.is_none() {
+                break;
+            }
+        }
+        path
+    }
+
+    /// Return `true` if this query gives the same nodes every time it is
+    /// resolved against the same value, which it might not if it calls a
+    /// function registered as non-deterministic.
+    fn is_deterministic(&self, env: &Environment) -> bool {
+        self.segments.iter().all(|segment| match segment {
+            Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. } => {
+                selectors.iter().all(|selector| match selector {
+                    Selector::Filter { expression } => expression.is_deterministic(env),
+                    _ => true,
+                })
+            }
+            Segment::Eoi => true,
+        })
+    }
+
     pub fn is_singular(&self) -> bool {
         self.segments.iter().all(|segment| {
             if let Segment::Child { selectors, .. } = segment {
@@ -158,6 +647,35 @@ impl Query {
     }
 }
 
+/// A [`Query`] with the results of its absolute sub-queries computed ahead of
+/// time, from [`Query::bind_root`].
+pub struct BoundQuery<'q, 'a, 'e> {
+    query: &'q Query,
+    root: &'a Value,
+    env: &'e Environment,
+    root_queries: HashMap<QueryId, Option<NodeList<'a>>>,
+}
+
+impl<'q, 'a, 'e> BoundQuery<'q, 'a, 'e> {
+    /// Find nodes matching the query in the bound root value.
+    pub fn find(&self) -> Result<NodeList<'a>, JSONPathError> {
+        self.find_at(self.root)
+    }
+
+    /// Find nodes matching the query, starting from `value` instead of the
+    /// bound root value. `$` in filters still refers to the bound root, but
+    /// node locations are relative to `value`.
+    pub fn find_at(&self, value: &'a Value) -> Result<NodeList<'a>, JSONPathError> {
+        let context = QueryContext {
+            bound_root_queries: Some(&self.root_queries),
+            ..QueryContext::new(self.root, self.env, None)
+        };
+        self.query
+            .resolve(value, &context)
+            .map_err(|err| err.in_query(self.query))
+    }
+}
+
 impl fmt::Display for Query {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -174,57 +692,154 @@ impl fmt::Display for Query {
 
 #[derive(Debug)]
 pub enum Segment {
-    Child { selectors: Vec<Selector> },
-    Recursive { selectors: Vec<Selector> },
+    Child {
+        selectors: Vec<Selector>,
+        span: Span,
+    },
+    Recursive {
+        selectors: Vec<Selector>,
+        span: Span,
+    },
     Eoi,
 }
 
 impl Segment {
+    /// Attribute `err` to this segment, unless a part of the segment, like a
+    /// function call in a filter, is already responsible for it.
+    fn locate(&self, err: JSONPathError) -> JSONPathError {
+        match self {
+            Segment::Child { span, .. } | Segment::Recursive { span, .. } => err.at(*span),
+            Segment::Eoi => err,
+        }
+    }
+
+    fn singular_value<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        match self {
+            Segment::Child { selectors, .. } => match selectors.as_slice() {
+                [Selector::Name { name }] => value.as_object()?.get(name),
+                [Selector::Index { index }] => {
+                    let array = value.as_array()?;
+                    array.get(resolve_index(*index, array.len())?)
+                }
+                _ => None,
+            },
+            Segment::Eoi => Some(value),
+            Segment::Recursive { .. } => None,
+        }
+    }
+
     fn resolve<'a>(
         &self,
         nodes: NodeList<'a>,
-        context: &QueryContext,
+        context: &QueryContext<'a, '_>,
     ) -> Result<NodeList<'a>, JSONPathError> {
         match self {
-            Segment::Child { selectors } => nodes
+            Segment::Child { selectors, .. } => nodes
                 .iter()
                 .flat_map(|node| selectors.iter().map(|s| s.resolve(node, context)))
-                .flatten_ok()
-                .collect(),
-            Segment::Recursive { selectors } => nodes
-                .iter()
-                .flat_map(|n| visit(n))
-                .flat_map(|node| selectors.iter().map(move |s| s.resolve(&node, context)))
-                .flatten_ok()
-                .collect(),
-            Segment::Eoi {} => Ok(nodes),
+                .try_fold(NodeList::new(), |mut nodes, selected| {
+                    context.extend_nodes(&mut nodes, selected?)?;
+                    Ok(nodes)
+                }),
+            Segment::Recursive { selectors, .. } => {
+                nodes.iter().try_fold(NodeList::new(), |mut nodes, node| {
+                    for descendant in context.descendants(node)? {
+                        for selector in selectors {
+                            context.extend_nodes(
+                                &mut nodes,
+                                selector.resolve(&descendant, context)?,
+                            )?;
+                        }
+                    }
+                    Ok(nodes)
+                })
+            }
+            Segment::Eoi => Ok(nodes),
         }
     }
 
+    /// Like `resolve`, but a descendant segment resolves its selectors
+    /// against each descendant on rayon's thread pool. Each thread gets its
+    /// own context, sharing the deadline and bound root queries of `context`.
+    #[cfg(feature = "rayon")]
+    fn resolve_parallel<'a>(
+        &self,
+        nodes: NodeList<'a>,
+        context: &QueryContext<'a, '_>,
+    ) -> Result<NodeList<'a>, JSONPathError> {
+        use rayon::prelude::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let Segment::Recursive { selectors, .. } = self else {
+            return self.resolve(nodes, context);
+        };
+
+        let descendants = nodes
+            .iter()
+            .map(|node| context.descendants(node))
+            .flatten_ok()
+            .collect::<Result<NodeList, _>>()?;
+
+        let (root, env) = (context.root, context.env);
+        let (deadline, bound_root_queries) = (context.deadline, context.bound_root_queries);
+        // Shared by every thread, so the limit on selected nodes applies to
+        // the segment as a whole.
+        let selected = AtomicUsize::new(0);
+
+        let nodes: NodeList<'a> = descendants
+            .par_iter()
+            .map_init(
+                || QueryContext {
+                    deadline,
+                    bound_root_queries,
+                    ..QueryContext::new(root, env, None)
+                },
+                |context, node| {
+                    let nodes = selectors
+                        .iter()
+                        .map(|s| s.resolve(node, context))
+                        .flatten_ok()
+                        .collect::<Result<NodeList, _>>()?;
+                    let count = selected.fetch_add(nodes.len(), Ordering::Relaxed) + nodes.len();
+                    context.check_nodes(count)?;
+                    Ok(nodes)
+                },
+            )
+            .collect::<Result<Vec<NodeList>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        Ok(nodes)
+    }
+
     fn resolve_loop<'a>(
         &self,
         nodes: NodeList<'a>,
-        context: &QueryContext,
+        context: &QueryContext<'a, '_>,
     ) -> Result<NodeList<'a>, JSONPathError> {
         let mut _nodes: NodeList<'a> = Vec::new();
         match self {
-            Segment::Child { selectors } => {
+            Segment::Child { selectors, .. } => {
                 for node in nodes.iter() {
                     for selector in selectors {
-                        _nodes.extend(selector.resolve(node, context)?)
+                        context.extend_nodes(&mut _nodes, selector.resolve(node, context)?)?;
                     }
                 }
             }
-            Segment::Recursive { selectors } => {
+            Segment::Recursive { selectors, .. } => {
                 for node in nodes.iter() {
-                    for _node in visit(node).iter() {
+                    for _node in context.descendants(node)?.iter() {
                         for selector in selectors {
-                            _nodes.extend(selector.resolve_loop(_node, context)?)
+                            context.extend_nodes(
+                                &mut _nodes,
+                                selector.resolve_loop(_node, context)?,
+                            )?;
                         }
                     }
                 }
             }
-            Segment::Eoi {} => _nodes = nodes,
+            Segment::Eoi => _nodes = nodes,
         }
         Ok(_nodes)
     }
@@ -280,11 +895,12 @@ pub enum Selector {
 }
 
 impl Selector {
-    fn resolve<'a, 'b>(
+    fn resolve<'a>(
         &self,
-        node: &'b Node<'a>,
-        context: &QueryContext,
+        node: &Node<'a>,
+        context: &QueryContext<'a, '_>,
     ) -> Result<NodeList<'a>, JSONPathError> {
+        context.check_deadline()?;
         match self {
             Selector::Name { name } => {
                 if let Some(v) = node.value.get(name) {
@@ -295,9 +911,10 @@ impl Selector {
             }
             Selector::Index { index } => {
                 if let Some(array) = node.value.as_array() {
-                    let norm = norm_index(*index, array.len());
-                    if let Some(v) = array.get(norm) {
-                        Ok(vec![node.new_child_element(v, norm)])
+                    if let Some((norm, value)) = resolve_index(*index, array.len())
+                        .and_then(|norm| Some((norm, array.get(norm)?)))
+                    {
+                        Ok(vec![node.new_child_element(value, norm)])
                     } else {
                         Ok(Vec::new())
                     }
@@ -315,7 +932,7 @@ impl Selector {
                     Ok(Vec::new())
                 }
             }
-            Selector::Wild {} => match node.value {
+            Selector::Wild => match node.value {
                 Value::Array(arr) => Ok(arr
                     .iter()
                     .enumerate()
@@ -346,11 +963,12 @@ impl Selector {
         }
     }
 
-    fn resolve_loop<'a, 'b>(
+    fn resolve_loop<'a>(
         &self,
-        node: &'b Node<'a>,
-        context: &QueryContext,
+        node: &Node<'a>,
+        context: &QueryContext<'a, '_>,
     ) -> Result<NodeList<'a>, JSONPathError> {
+        context.check_deadline()?;
         let mut nodes: NodeList = Vec::new();
         match self {
             Selector::Name { name } => {
@@ -360,9 +978,10 @@ impl Selector {
             }
             Selector::Index { index } => {
                 if let Some(array) = node.value.as_array() {
-                    let norm = norm_index(*index, array.len());
-                    if let Some(v) = array.get(norm) {
-                        nodes.push(node.new_child_element(v, norm));
+                    if let Some((norm, value)) = resolve_index(*index, array.len())
+                        .and_then(|norm| Some((norm, array.get(norm)?)))
+                    {
+                        nodes.push(node.new_child_element(value, norm));
                     }
                 }
             }
@@ -373,7 +992,7 @@ impl Selector {
                     }
                 }
             }
-            Selector::Wild {} => match node.value {
+            Selector::Wild => match node.value {
                 Value::Array(array) => {
                     for (i, element) in array.iter().enumerate() {
                         nodes.push(node.new_child_element(element, i));
@@ -431,7 +1050,7 @@ impl fmt::Display for Selector {
                         .unwrap_or(String::from("1")),
                 )
             }
-            Selector::Wild { .. } => f.write_char('*'),
+            Selector::Wild => f.write_char('*'),
             Selector::Filter { expression, .. } => write!(f, "?{expression}"),
         }
     }
@@ -486,6 +1105,10 @@ pub enum FilterExpression {
     Int {
         value: i64,
     },
+    /// An integer literal too big for an `i64`.
+    UInt {
+        value: u64,
+    },
     Float {
         value: f64,
     },
@@ -501,6 +1124,7 @@ pub enum FilterExpression {
         left: Box<FilterExpression>,
         operator: ComparisonOperator,
         right: Box<FilterExpression>,
+        span: Span,
     },
     RelativeQuery {
         query: Box<Query>,
@@ -511,6 +1135,7 @@ pub enum FilterExpression {
     Function {
         name: String,
         args: Vec<FilterExpression>,
+        span: Span,
     },
 }
 
@@ -518,30 +1143,32 @@ impl FilterExpression {
     pub fn is_literal(&self) -> bool {
         matches!(
             self,
-            FilterExpression::True { .. }
-                | FilterExpression::False { .. }
-                | FilterExpression::Null { .. }
+            FilterExpression::True
+                | FilterExpression::False
+                | FilterExpression::Null
                 | FilterExpression::String { .. }
                 | FilterExpression::Int { .. }
+                | FilterExpression::UInt { .. }
                 | FilterExpression::Float { .. }
         )
     }
 }
 
 impl FilterExpression {
-    fn evaluate<'a, 'b: 'a>(
-        &self,
-        current: &'a Value,
-        context: &QueryContext<'a, 'b>,
+    fn evaluate<'a, 'v: 'a>(
+        &'a self,
+        current: &'v Value,
+        context: &QueryContext<'v, '_>,
     ) -> Result<FilterExpressionResult<'a>, JSONPathError> {
         match self {
             FilterExpression::True => Ok(FilterExpressionResult::Bool(true)),
             FilterExpression::False => Ok(FilterExpressionResult::Bool(false)),
             FilterExpression::Null => Ok(FilterExpressionResult::Null),
             FilterExpression::String { value } => {
-                Ok(FilterExpressionResult::String(value.to_owned()))
+                Ok(FilterExpressionResult::String(Cow::Borrowed(value)))
             }
             FilterExpression::Int { value } => Ok(FilterExpressionResult::Int(*value)),
+            FilterExpression::UInt { value } => Ok(FilterExpressionResult::UInt(*value)),
             FilterExpression::Float { value } => Ok(FilterExpressionResult::Float(*value)),
             FilterExpression::Not { expression } => {
                 expression.evaluate(current, context).map(|rv| {
@@ -571,49 +1198,218 @@ impl FilterExpression {
                 left,
                 operator,
                 right,
+                span,
             } => {
-                if compare(
-                    left.evaluate(current, context)?,
-                    operator,
-                    right.evaluate(current, context)?,
-                ) {
+                let left_value = left.evaluate_value(current, context)?;
+                let right_value = right.evaluate_value(current, context)?;
+
+                if let Some(stats) = context.stats {
+                    for (operand, value) in [(left, &left_value), (right, &right_value)] {
+                        if is_nothing(value) {
+                            stats
+                                .borrow_mut()
+                                .record(self.to_string(), operand.nothing_reason(current, context));
+                        }
+                    }
+                }
+
+                if context.env.strict_nan && (is_nan(&left_value) || is_nan(&right_value)) {
+                    return Err(
+                        JSONPathError::runtime(format!("can't compare NaN: {}", self)).at(*span),
+                    );
+                }
+
+                if compare(left_value, operator, right_value) {
                     Ok(FilterExpressionResult::Bool(true))
                 } else {
                     Ok(FilterExpressionResult::Bool(false))
                 }
             }
             FilterExpression::RelativeQuery { query } => Ok(FilterExpressionResult::Nodes(
-                query.find(current, context.env)?,
+                query.resolve(current, context)?,
             )),
             FilterExpression::RootQuery { query } => Ok(FilterExpressionResult::Nodes(
-                query.find(context.root, context.env)?,
+                context.resolve_root_query(query)?,
             )),
-            FilterExpression::Function { name, args } => {
-                let fn_ext = context.env.function_register.get(name).ok_or_else(|| {
-                    JSONPathError::name(format!("missing function definition for {}", name))
-                })?;
+            FilterExpression::Function { name, args, span } => {
+                let register = &context.env.function_register;
+                let (Some(fn_ext), Some(signature)) =
+                    (register.get(name), register.signature(name))
+                else {
+                    return Err(JSONPathError::name(format!(
+                        "missing function definition for {}",
+                        name
+                    ))
+                    .at(*span));
+                };
 
+                let param_types = &signature.param_types;
                 let _args: Result<Vec<_>, _> = args
                     .iter()
-                    .map(|expr| expr.evaluate(current, context))
                     .enumerate()
-                    .map(|(i, rv)| unpack_result(rv?, &fn_ext.sig().param_types, i))
+                    .map(|(i, expr)| match param_types.get(i) {
+                        // So `length(@.big_array)` never builds a node list.
+                        Some(ExpressionType::Value) => {
+                            unpack_result(expr.evaluate_value(current, context)?, param_types, i)
+                        }
+                        _ => unpack_result(expr.evaluate(current, context)?, param_types, i),
+                    })
                     .collect();
 
-                Ok(fn_ext.call(_args?))
+                let _args = _args?;
+
+                if context.env.strict_value && name == "value" {
+                    if let [FilterExpressionResult::Nodes(nodes)] = _args.as_slice() {
+                        if nodes.len() > 1 {
+                            // Locations from a relative query are relative to
+                            // the current node.
+                            let identifier = match args.first() {
+                                Some(FilterExpression::RelativeQuery { .. }) => "@",
+                                _ => "$",
+                            };
+                            return Err(JSONPathError::runtime(format!(
+                                "value() expected at most one node, found {}: {}",
+                                nodes.len(),
+                                nodes
+                                    .iter()
+                                    .map(|node| format!("{}{}", identifier, &node.location[1..]))
+                                    .join(", ")
+                            ))
+                            .at(*span));
+                        }
+                    }
+                }
+
+                Ok(fn_ext.call(_args))
             }
         }
     }
 }
 
+impl FilterExpression {
+    /// Like [`FilterExpression::evaluate`], but resolving singular queries
+    /// directly to their value, without building a node list or looking in
+    /// the root query cache, for operands that only need the value.
+    fn evaluate_value<'a, 'v: 'a>(
+        &'a self,
+        current: &'v Value,
+        context: &QueryContext<'v, '_>,
+    ) -> Result<FilterExpressionResult<'a>, JSONPathError> {
+        match self {
+            FilterExpression::RelativeQuery { query } if query.is_singular() => Ok(
+                FilterExpressionResult::from_singular(query.singular_value(current)),
+            ),
+            FilterExpression::RootQuery { query } if query.is_singular() => Ok(
+                FilterExpressionResult::from_singular(query.singular_value(context.root)),
+            ),
+            _ => self.evaluate(current, context),
+        }
+    }
+
+    fn refers_to_root(&self) -> bool {
+        match self {
+            FilterExpression::Not { expression } => expression.refers_to_root(),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                left.refers_to_root() || right.refers_to_root()
+            }
+            FilterExpression::RelativeQuery { query } => query.refers_to_root(),
+            FilterExpression::RootQuery { .. } => true,
+            FilterExpression::Function { args, .. } => args.iter().any(|arg| arg.refers_to_root()),
+            _ => false,
+        }
+    }
+
+    fn collect_root_queries<'q>(&'q self, queries: &mut Vec<&'q Query>) {
+        match self {
+            FilterExpression::Not { expression } => expression.collect_root_queries(queries),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                left.collect_root_queries(queries);
+                right.collect_root_queries(queries);
+            }
+            FilterExpression::RelativeQuery { query } => query.collect_root_queries(queries),
+            FilterExpression::RootQuery { query } => {
+                queries.push(query);
+                query.collect_root_queries(queries);
+            }
+            FilterExpression::Function { args, .. } => {
+                args.iter()
+                    .for_each(|arg| arg.collect_root_queries(queries));
+            }
+            _ => (),
+        }
+    }
+
+    fn is_deterministic(&self, env: &Environment) -> bool {
+        match self {
+            FilterExpression::Not { expression } => expression.is_deterministic(env),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                left.is_deterministic(env) && right.is_deterministic(env)
+            }
+            FilterExpression::RelativeQuery { query } | FilterExpression::RootQuery { query } => {
+                query.is_deterministic(env)
+            }
+            FilterExpression::Function { name, args, .. } => {
+                env.function_register
+                    .signature(name)
+                    .is_some_and(|signature| signature.deterministic)
+                    && args.iter().all(|arg| arg.is_deterministic(env))
+            }
+            _ => true,
+        }
+    }
+
+    /// Explain why this expression, evaluated against `current`, resulted
+    /// in _Nothing_.
+    fn nothing_reason(&self, current: &Value, context: &QueryContext) -> NothingReason {
+        match self {
+            FilterExpression::RelativeQuery { query } if query.is_singular() => {
+                NothingReason::MissingMember {
+                    path: query.missing_path("@", current),
+                }
+            }
+            FilterExpression::RootQuery { query } if query.is_singular() => {
+                NothingReason::MissingMember {
+                    path: query.missing_path("$", context.root),
+                }
+            }
+            FilterExpression::Function { args, .. } => {
+                // Don't record comparisons in nested filters a second time.
+                let quiet = QueryContext::new(context.root, context.env, None);
+                args.iter()
+                    .find_map(|arg| match arg {
+                        FilterExpression::RelativeQuery { query } => query
+                            .resolve(current, &quiet)
+                            .is_ok_and(|nodes| nodes.is_empty())
+                            .then(|| arg.nothing_reason(current, context)),
+                        FilterExpression::RootQuery { query } => query
+                            .resolve(context.root, &quiet)
+                            .is_ok_and(|nodes| nodes.is_empty())
+                            .then(|| arg.nothing_reason(current, context)),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| NothingReason::Function {
+                        call: self.to_string(),
+                    })
+            }
+            _ => NothingReason::EmptyNodeList {
+                query: self.to_string(),
+            },
+        }
+    }
+}
+
 impl fmt::Display for FilterExpression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            FilterExpression::True { .. } => f.write_str("true"),
-            FilterExpression::False { .. } => f.write_str("false"),
-            FilterExpression::Null { .. } => f.write_str("null"),
+            FilterExpression::True => f.write_str("true"),
+            FilterExpression::False => f.write_str("false"),
+            FilterExpression::Null => f.write_str("null"),
             FilterExpression::String { value, .. } => write!(f, "'{value}'"),
             FilterExpression::Int { value, .. } => write!(f, "{value}"),
+            FilterExpression::UInt { value, .. } => write!(f, "{value}"),
             FilterExpression::Float { value, .. } => write!(f, "{value}"),
             FilterExpression::Not { expression, .. } => write!(f, "!{expression}"),
             FilterExpression::Logical {
@@ -667,30 +1463,49 @@ impl fmt::Display for FilterExpression {
     }
 }
 
-fn visit<'a, 'b>(node: &'b Node<'a>) -> NodeList<'a> {
-    let mut nodes: NodeList = vec![node.clone()];
+/// Append `node` and its descendants to `nodes`, in document order.
+fn visit<'a>(
+    node: &Node<'a>,
+    nodes: &mut NodeList<'a>,
+    context: &QueryContext,
+) -> Result<(), JSONPathError> {
+    context.check_deadline()?;
+    nodes.push(node.clone());
 
     match node.value {
         Value::Object(obj) => {
-            obj.iter()
-                .for_each(|(k, v)| nodes.extend(visit(&node.new_child_member(v, k))));
+            for (k, v) in obj {
+                visit(&node.new_child_member(v, k), nodes, context)?;
+            }
+        }
+        Value::Array(arr) => {
+            for (i, e) in arr.iter().enumerate() {
+                visit(&node.new_child_element(e, i), nodes, context)?;
+            }
         }
-        Value::Array(arr) => arr
-            .iter()
-            .enumerate()
-            .for_each(|(i, e)| nodes.extend(visit(&node.new_child_element(e, i)))),
         _ => (),
     }
 
-    nodes
+    Ok(())
+}
+
+/// `true` if `value` has children nested more than `limit` levels deep.
+fn deeper_than(value: &Value, limit: usize) -> bool {
+    match value {
+        Value::Array(arr) => arr.iter().any(|v| limit == 0 || deeper_than(v, limit - 1)),
+        Value::Object(obj) => obj
+            .values()
+            .any(|v| limit == 0 || deeper_than(v, limit - 1)),
+        _ => false,
+    }
 }
 
-fn slice<'a>(
-    array: &'a Vec<Value>,
+fn slice(
+    array: &[Value],
     start: Option<i64>,
     stop: Option<i64>,
     step: Option<i64>,
-) -> Vec<(i64, &'a Value)> {
+) -> Vec<(i64, &Value)> {
     let array_length = array.len() as i64; // TODO: try_from
     if array_length == 0 {
         return Vec::new();
@@ -702,14 +1517,20 @@ fn slice<'a>(
         return Vec::new();
     }
 
-    let n_start = match start {
-        Some(i) => {
-            if i < 0 {
-                cmp::max(array_length + i, 0)
-            } else {
-                cmp::min(i, array_length - 1)
-            }
+    // Normalize negative indices, then clamp them to the range of indices a
+    // slice in the direction of `n_step` can start or stop at, as described
+    // in RFC 9535 section 2.3.4.2.2.
+    let bound = |i: i64| {
+        let i = if i < 0 { array_length + i } else { i };
+        if n_step > 0 {
+            i.clamp(0, array_length)
+        } else {
+            i.clamp(-1, array_length - 1)
         }
+    };
+
+    let n_start = match start {
+        Some(i) => bound(i),
         None => {
             if n_step < 0 {
                 array_length - 1
@@ -720,13 +1541,7 @@ fn slice<'a>(
     };
 
     let n_stop = match stop {
-        Some(i) => {
-            if i < 0 {
-                cmp::max(array_length + i, -1)
-            } else {
-                cmp::min(i, array_length)
-            }
-        }
+        Some(i) => bound(i),
         None => {
             if n_step < 0 {
                 -1
@@ -741,12 +1556,16 @@ fn slice<'a>(
     // TODO: try_from instead of as
     if n_step > 0 {
         for i in (n_start..n_stop).step_by(n_step as usize) {
-            sliced_array.push((i, array.get(i as usize).unwrap()));
+            if let Some(value) = array.get(i as usize) {
+                sliced_array.push((i, value));
+            }
         }
     } else {
         let mut i = n_start;
         while i > n_stop {
-            sliced_array.push((i, array.get(i as usize).unwrap()));
+            if let Some(value) = array.get(i as usize) {
+                sliced_array.push((i, value));
+            }
             i += n_step;
         }
     }
@@ -754,11 +1573,19 @@ fn slice<'a>(
     sliced_array
 }
 
+fn is_nothing(rv: &FilterExpressionResult) -> bool {
+    match rv {
+        FilterExpressionResult::Nothing => true,
+        FilterExpressionResult::Nodes(nodes) => nodes.is_empty(),
+        _ => false,
+    }
+}
+
 pub fn is_truthy(rv: FilterExpressionResult) -> bool {
     match rv {
         FilterExpressionResult::Nothing => false,
         FilterExpressionResult::Nodes(nodes) => !nodes.is_empty(),
-        FilterExpressionResult::Bool(v) => v == true,
+        FilterExpressionResult::Bool(v) => v,
         _ => true,
     }
 }
@@ -767,7 +1594,7 @@ pub fn is_truthy_ref(rv: &FilterExpressionResult) -> bool {
     match rv {
         FilterExpressionResult::Nothing => false,
         FilterExpressionResult::Nodes(nodes) => !nodes.is_empty(),
-        FilterExpressionResult::Bool(v) => *v == true,
+        FilterExpressionResult::Bool(v) => *v,
         _ => true,
     }
 }
@@ -785,17 +1612,21 @@ fn logical(
 
 fn nodes_or_singular<'a>(rv: FilterExpressionResult<'a>) -> FilterExpressionResult<'a> {
     match rv {
-        FilterExpressionResult::Nodes(ref nodes) => {
-            if nodes.len() == 1 {
-                FilterExpressionResult::from_json_value(nodes.first().unwrap().value)
-            } else {
-                rv
-            }
-        }
+        FilterExpressionResult::Nodes(ref nodes) => match nodes.as_slice() {
+            [node] => FilterExpressionResult::from_json_value(node.value),
+            _ => rv,
+        },
         _ => rv,
     }
 }
 
+fn is_nan(rv: &FilterExpressionResult) -> bool {
+    matches!(rv, FilterExpressionResult::Float(f) if f.is_nan())
+}
+
+/// Compare two filter expression results following RFC 9535. Numbers
+/// compare as IEEE 754 doubles, so NaN is neither equal to nor ordered
+/// against anything, including itself, and `!=` is the negation of `==`.
 fn compare(
     left: FilterExpressionResult,
     op: &ComparisonOperator,
@@ -814,24 +1645,29 @@ fn compare(
     }
 }
 
+/// A numeric result as a number that compares exactly with the others.
+fn as_number(rv: &FilterExpressionResult) -> Option<Number> {
+    match rv {
+        FilterExpressionResult::Int(i) => Some(Number::Int((*i).into())),
+        FilterExpressionResult::UInt(u) => Some(Number::Int((*u).into())),
+        FilterExpressionResult::Float(f) => Some(Number::Float(*f)),
+        _ => None,
+    }
+}
+
 fn eq(left: &FilterExpressionResult, right: &FilterExpressionResult) -> bool {
+    if let (Some(l), Some(r)) = (as_number(left), as_number(right)) {
+        return number::compare(l, r) == Some(cmp::Ordering::Equal);
+    }
+
     use FilterExpressionResult::*;
     match (left, right) {
         (Nothing, Nothing) => true,
         (Nodes(nodes), Nothing) | (Nothing, Nodes(nodes)) => nodes.is_empty(),
         (Nothing, _) | (_, Nothing) => false,
-        (Nodes(left), Nodes(right)) => {
-            if left.is_empty() && right.is_empty() {
-                true
-            } else {
-                // Only singular queries can be compared
-                unreachable!()
-            }
-        }
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Int(r)) => l == r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Float(r)) => l == r,
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Float(r)) => *l as f64 == *r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Int(r)) => *l == *r as f64,
+        // Only singular queries can be compared, so non-empty node lists
+        // never get here.
+        (Nodes(left), Nodes(right)) => left.is_empty() && right.is_empty(),
         (FilterExpressionResult::Null, FilterExpressionResult::Null) => true,
         (FilterExpressionResult::Bool(l), FilterExpressionResult::Bool(r)) => l == r,
         (FilterExpressionResult::String(l), FilterExpressionResult::String(r)) => l == r,
@@ -842,13 +1678,13 @@ fn eq(left: &FilterExpressionResult, right: &FilterExpressionResult) -> bool {
 }
 
 fn lt(left: &FilterExpressionResult, right: &FilterExpressionResult) -> bool {
+    if let (Some(l), Some(r)) = (as_number(left), as_number(right)) {
+        return number::compare(l, r) == Some(cmp::Ordering::Less);
+    }
+
     match (left, right) {
         (FilterExpressionResult::String(l), FilterExpressionResult::String(r)) => l < r,
         (FilterExpressionResult::Bool(_), FilterExpressionResult::Bool(_)) => false,
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Int(r)) => l < r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Float(r)) => l < r,
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Float(r)) => (*l as f64) < *r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Int(r)) => *l < *r as f64,
         _ => false,
     }
 }
@@ -858,26 +1694,16 @@ fn unpack_result<'a>(
     param_types: &[ExpressionType],
     index: usize,
 ) -> Result<FilterExpressionResult<'a>, JSONPathError> {
-    if matches!(param_types.get(index).unwrap(), ExpressionType::Nodes) {
+    if matches!(param_types.get(index), Some(ExpressionType::Nodes)) {
         return Ok(rv);
     }
 
     match &rv {
-        FilterExpressionResult::Nodes(nodes) => match nodes.len() {
-            0 => Ok(FilterExpressionResult::Nothing),
-            1 => Ok(FilterExpressionResult::from_json_value(
-                nodes.first().unwrap().value,
-            )),
+        FilterExpressionResult::Nodes(nodes) => match nodes.as_slice() {
+            [] => Ok(FilterExpressionResult::Nothing),
+            [node] => Ok(FilterExpressionResult::from_json_value(node.value)),
             _ => Ok(rv),
         },
         _ => Ok(rv),
     }
 }
-
-fn norm_index(index: i64, length: usize) -> usize {
-    if index < 0 && length >= index.abs() as usize {
-        (length as i64 + index) as usize
-    } else {
-        index as usize
-    }
-}
@@ -1,27 +1,134 @@
-use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 use crate::{
-    ast::NodeList,
+    ast::{FilterExpressionResult, NodeList},
     errors::JSONPathError,
-    function::FunctionRegister,
-    standard_functions::{Count, Length, Match, Search, Value},
-    Query,
+    function::{FunctionRegister, FunctionSignature},
+    options::{FindOptions, FindResult},
+    parser::{JSONPathParser, DEFAULT_MAX_NESTING_DEPTH},
+    standard_functions::{Count, Length, Match, RegexLimits, Search, Value},
+    stats::EvalStats,
 };
 
 pub struct Environment {
     pub function_register: FunctionRegister,
+    /// When `true`, calling `value()` with more than one node is an error
+    /// listing the matched paths, instead of resulting in _Nothing_. This
+    /// helps find "singular" queries that actually match several nodes.
+    pub strict_value: bool,
+    /// When `true`, comparing NaN, which function extensions can return,
+    /// is an error instead of being false.
+    pub strict_nan: bool,
+    /// The maximum number of nodes to remember from descendant segments
+    /// during one `find`, so later descendant segments over the same
+    /// subtrees don't walk them again. `0` disables the descendant index.
+    pub descendant_index_limit: usize,
+    /// The maximum number of nodes selected by any segment of a query,
+    /// including segments of queries in filters. Exceeding it is an error.
+    pub max_nodes: usize,
+    /// The maximum depth below a node that a descendant segment will visit.
+    /// Exceeding it is an error.
+    pub max_depth: usize,
+    /// How long one `find` is allowed to take before failing.
+    pub timeout: Option<Duration>,
+    /// See [`JSONPathParser::max_query_length`]. Applies to queries parsed
+    /// by [`Environment::parser`] and [`Environment::find`].
+    pub max_query_length: usize,
+    /// See [`JSONPathParser::max_nesting_depth`].
+    pub max_nesting_depth: usize,
+    /// See [`JSONPathParser::max_string_length`].
+    pub max_string_length: usize,
+}
+
+/// The order in which wildcard and descendant segments visit object members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberOrder {
+    /// Members are visited in the order they appear in the document. This
+    /// is the case when serde_json's `preserve_order` feature is enabled.
+    Document,
+    /// Members are visited in lexicographic order of their names.
+    Lexical,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Environment {
     pub fn new() -> Self {
-        let mut function_register: FunctionRegister = HashMap::new();
-        function_register.insert("count".to_string(), Box::new(Count::new()));
-        function_register.insert("length".to_string(), Box::new(Length::new()));
-        function_register.insert("match".to_string(), Box::new(Match::new()));
-        function_register.insert("search".to_string(), Box::new(Search::new()));
-        function_register.insert("value".to_string(), Box::new(Value::new()));
+        let mut function_register = FunctionRegister::new();
+        function_register.register_standard("count", Box::new(Count::new()));
+        function_register.register_standard("length", Box::new(Length::new()));
+        function_register.register_standard("match", Box::new(Match::new()));
+        function_register.register_standard("search", Box::new(Search::new()));
+        function_register.register_standard("value", Box::new(Value::new()));
+
+        Self {
+            function_register,
+            strict_value: false,
+            strict_nan: false,
+            descendant_index_limit: 100_000,
+            max_nodes: usize::MAX,
+            max_depth: usize::MAX,
+            timeout: None,
+            max_query_length: usize::MAX,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_string_length: usize::MAX,
+        }
+    }
+
+    /// Register function extension `name`, implemented by a closure.
+    ///
+    /// Queries calling the function must be parsed by a parser that knows
+    /// its signature, like the one returned by [`Environment::parser`].
+    pub fn add_function<F>(&mut self, name: &str, signature: FunctionSignature, function: F)
+    where
+        F: for<'a> Fn(Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a>
+            + Sync
+            + 'static,
+    {
+        self.function_register
+            .register(name, signature, Box::new(function));
+    }
+
+    /// A parser that knows the signatures of this environment's functions,
+    /// with this environment's limits on queries.
+    pub fn parser(&self) -> JSONPathParser {
+        let mut parser = JSONPathParser::new();
+        parser.add_functions(&self.function_register);
+        parser.max_query_length = self.max_query_length;
+        parser.max_nesting_depth = self.max_nesting_depth;
+        parser.max_string_length = self.max_string_length;
+        parser
+    }
+
+    /// The order in which wildcard and descendant segments visit object
+    /// members.
+    ///
+    /// This follows serde_json's `Map`, so it reflects `preserve_order`
+    /// whether it was enabled by this crate's feature of the same name or
+    /// by any other crate in the dependency graph.
+    pub fn ordering(&self) -> MemberOrder {
+        let mut map = serde_json::Map::new();
+        map.insert("b".to_string(), serde_json::Value::Null);
+        map.insert("a".to_string(), serde_json::Value::Null);
+        match map.keys().next().map(String::as_str) {
+            Some("b") => MemberOrder::Document,
+            _ => MemberOrder::Lexical,
+        }
+    }
 
-        Self { function_register }
+    /// Replace the `match()` and `search()` functions with ones that compile
+    /// patterns subject to `limits`.
+    ///
+    /// Use this when evaluating queries with user-supplied patterns.
+    pub fn set_regex_limits(&mut self, limits: RegexLimits) {
+        self.function_register
+            .register_standard("match", Box::new(Match::with_limits(limits.clone())));
+        self.function_register
+            .register_standard("search", Box::new(Search::with_limits(limits)));
     }
 
     pub fn find<'a>(
@@ -29,8 +136,17 @@ impl Environment {
         expr: &str,
         value: &'a serde_json::Value,
     ) -> Result<NodeList<'a>, JSONPathError> {
-        let query = Query::standard(expr)?;
-        query.find(value, &self)
+        let query = self.parser().parse(expr)?;
+        query.find(value, self)
+    }
+
+    pub fn find_with_stats<'a>(
+        &self,
+        expr: &str,
+        value: &'a serde_json::Value,
+    ) -> Result<(NodeList<'a>, EvalStats), JSONPathError> {
+        let query = self.parser().parse(expr)?;
+        query.find_with_stats(value, self)
     }
 
     pub fn find_loop<'a>(
@@ -38,7 +154,33 @@ impl Environment {
         expr: &str,
         value: &'a serde_json::Value,
     ) -> Result<NodeList<'a>, JSONPathError> {
-        let query = Query::standard(expr)?;
-        query.find_loop(value, &self)
+        let query = self.parser().parse(expr)?;
+        query.find_loop(value, self)
+    }
+
+    /// Same as `find`, configured by `options`. Nodes are counted as the
+    /// query's last segment selects them, so evaluation stops soon after
+    /// there are more than `options` allows.
+    pub fn find_with_options<'a>(
+        &self,
+        expr: &str,
+        value: &'a serde_json::Value,
+        options: &FindOptions,
+    ) -> Result<FindResult<'a>, JSONPathError> {
+        let started = Instant::now();
+        options.check_query(expr).map_err(JSONPathError::limit)?;
+        let query = self.parser().parse(expr)?;
+
+        let mut collector = options.collector(started);
+        query.find_each(value, self, |node| {
+            collector.push(node).map_err(JSONPathError::limit)
+        })?;
+
+        let (found, stats) = collector.finish(value);
+        Ok(FindResult {
+            found,
+            stats,
+            trace: None,
+        })
     }
 }
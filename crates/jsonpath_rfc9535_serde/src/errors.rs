@@ -1,28 +1,54 @@
 use std::fmt;
 
-#[derive(Debug)]
-pub enum JSONPathErrorType {
-    LexerError,
-    SyntaxError,
-    TypeError,
-    NameError,
-}
+pub use jsonpath_rfc9535::errors::JSONPathErrorType;
+use jsonpath_rfc9535::{
+    errors::{snippet, EngineError},
+    span::Span,
+};
+
+use crate::Query;
 
 #[derive(Debug)]
 pub struct JSONPathError {
     pub kind: JSONPathErrorType,
     pub msg: String,
+    /// Where in the query a syntax error was found, or the part of the
+    /// query that failed, for errors found while evaluating a query, like a
+    /// call to a missing function or a resource limit.
+    pub span: Option<Span>,
+    /// The text of the query that failed, for errors found while evaluating
+    /// a query, so `span` can be shown against it with [`render`]. Queries
+    /// that weren't parsed give their canonical form.
+    ///
+    /// [`render`]: JSONPathError::render
+    pub query: Option<String>,
 }
 
 impl JSONPathError {
     pub fn new(error: JSONPathErrorType, msg: String) -> Self {
-        Self { kind: error, msg }
+        Self {
+            kind: error,
+            msg,
+            span: None,
+            query: None,
+        }
     }
 
     pub fn syntax(msg: String) -> Self {
         Self {
             kind: JSONPathErrorType::SyntaxError,
             msg,
+            span: None,
+            query: None,
+        }
+    }
+
+    pub fn syntax_at(msg: String, span: Span) -> Self {
+        Self {
+            kind: JSONPathErrorType::SyntaxError,
+            msg,
+            span: Some(span),
+            query: None,
         }
     }
 
@@ -30,6 +56,8 @@ impl JSONPathError {
         Self {
             kind: JSONPathErrorType::TypeError,
             msg,
+            span: None,
+            query: None,
         }
     }
 
@@ -37,17 +65,70 @@ impl JSONPathError {
         Self {
             kind: JSONPathErrorType::NameError,
             msg,
+            span: None,
+            query: None,
         }
     }
-}
 
-impl fmt::Display for JSONPathErrorType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            JSONPathErrorType::LexerError => f.write_str("lexer error:"),
-            JSONPathErrorType::SyntaxError => f.write_str("syntax error:"),
-            JSONPathErrorType::TypeError => f.write_str("type error:"),
-            JSONPathErrorType::NameError => f.write_str("name error:"),
+    pub fn runtime(msg: String) -> Self {
+        Self {
+            kind: JSONPathErrorType::RuntimeError,
+            msg,
+            span: None,
+            query: None,
+        }
+    }
+
+    pub fn limit(msg: String) -> Self {
+        Self {
+            kind: JSONPathErrorType::LimitError,
+            msg,
+            span: None,
+            query: None,
+        }
+    }
+
+    pub fn depth_at(msg: String, span: Span) -> Self {
+        Self {
+            kind: JSONPathErrorType::DepthError,
+            msg,
+            span: Some(span),
+            query: None,
+        }
+    }
+
+    pub fn document(msg: String) -> Self {
+        Self {
+            kind: JSONPathErrorType::DocumentError,
+            msg,
+            span: None,
+            query: None,
+        }
+    }
+
+    /// Set the span of the part of the query that failed, unless a more
+    /// specific part has already been set.
+    pub(crate) fn at(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    /// Set the text of the query that failed, unless the error already has
+    /// one.
+    pub(crate) fn in_query(mut self, query: &Query) -> Self {
+        if self.query.is_none() {
+            self.query = Some(query.text.clone().unwrap_or_else(|| query.to_string()));
+        }
+        self
+    }
+
+    /// This error's kind and message, followed by the line of `query` with
+    /// carets under the syntax error, or under the part of the query that
+    /// failed during evaluation, if it has a location.
+    pub fn render(&self, query: &str) -> String {
+        match self.span {
+            Some(span) => format!("{} {}\n{}", self.kind, self.msg, snippet(query, span)),
+            None => format!("{} {}", self.kind, self.msg),
         }
     }
 }
@@ -59,3 +140,17 @@ impl fmt::Display for JSONPathError {
         write!(f, "{}", self.msg)
     }
 }
+
+impl EngineError for JSONPathError {
+    fn kind(&self) -> JSONPathErrorType {
+        self.kind
+    }
+
+    fn into_msg(self) -> String {
+        self.msg
+    }
+
+    fn span(&self) -> Option<Span> {
+        self.span
+    }
+}
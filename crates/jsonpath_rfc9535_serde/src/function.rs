@@ -1,68 +1,25 @@
-use std::collections::HashMap;
+pub use jsonpath_rfc9535::{
+    function::FunctionRegistry, standard_functions, ExpressionType, FunctionSignature,
+};
 
 use crate::ast::FilterExpressionResult;
 
-#[derive(Debug)]
-pub enum ExpressionType {
-    Logical,
-    Nodes,
-    Value,
-}
-
-pub struct FunctionSignature {
-    pub param_types: Vec<ExpressionType>,
-    pub return_type: ExpressionType,
-}
-
-pub fn standard_functions() -> HashMap<String, FunctionSignature> {
-    let mut functions = HashMap::new();
-
-    functions.insert(
-        "count".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
-        },
-    );
-
-    functions.insert(
-        "length".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value],
-            return_type: ExpressionType::Value,
-        },
-    );
-
-    functions.insert(
-        "match".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        },
-    );
-
-    functions.insert(
-        "search".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        },
-    );
-
-    functions.insert(
-        "value".to_owned(),
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
-        },
-    );
-
-    functions
-}
-
+/// The implementation of a function extension. Signatures are kept
+/// alongside implementations in a [`FunctionRegister`].
+///
+/// Closures taking and returning `FilterExpressionResult`s are function
+/// extensions too. See [`Environment::add_function`](crate::env::Environment::add_function).
 pub trait FunctionExtension {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a>;
-    fn sig(&self) -> FunctionSignature;
 }
 
-pub type FunctionRegister = HashMap<String, Box<dyn FunctionExtension + Sync>>;
+impl<F> FunctionExtension for F
+where
+    F: for<'a> Fn(Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a>,
+{
+    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
+        self(args)
+    }
+}
+
+pub type FunctionRegister = FunctionRegistry<dyn FunctionExtension + Sync>;
@@ -1,9 +1,15 @@
-use crate::{ast::NodeList, env::Environment, errors::JSONPathError, Query};
+use crate::{
+    ast::NodeList,
+    env::Environment,
+    errors::JSONPathError,
+    options::{FindOptions, FindResult},
+    Query,
+};
 use lazy_static::lazy_static;
 use serde_json::Value;
 
 lazy_static! {
-    static ref ENV: Environment = Environment::new();
+    pub(crate) static ref ENV: Environment = Environment::new();
 }
 
 pub fn find<'a>(expr: &str, value: &'a Value) -> Result<NodeList<'a>, JSONPathError> {
@@ -16,3 +22,20 @@ pub fn find_loop<'a>(expr: &str, value: &'a Value) -> Result<NodeList<'a>, JSONP
     let query = Query::standard(expr)?;
     query.find_loop(value, &ENV)
 }
+
+/// Same as `find`, but descendant segments are evaluated in parallel. See
+/// [`Query::find_parallel`].
+#[cfg(feature = "rayon")]
+pub fn find_parallel<'a>(expr: &str, value: &'a Value) -> Result<NodeList<'a>, JSONPathError> {
+    let query = Query::standard(expr)?;
+    query.find_parallel(value, &ENV)
+}
+
+/// Same as `find`, configured by `options`.
+pub fn find_with_options<'a>(
+    expr: &str,
+    value: &'a Value,
+    options: &FindOptions,
+) -> Result<FindResult<'a>, JSONPathError> {
+    ENV.find_with_options(expr, value, options)
+}
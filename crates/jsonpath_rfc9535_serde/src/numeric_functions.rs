@@ -0,0 +1,106 @@
+//! Optional `round()`, `floor()`, `ceil()` and `abs()` function extensions.
+//!
+//! None of these are registered by default. Add them all to an
+//! [`Environment`] with [`Environment::add_numeric_functions`], then parse
+//! queries with [`Environment::parser`].
+//!
+//! ```
+//! use jsonpath_rfc9535_serde::env::Environment;
+//! use serde_json::json;
+//!
+//! let mut env = Environment::new();
+//! env.add_numeric_functions();
+//!
+//! let value = json!([{"score": 4.6}, {"score": 5.4}, {"score": 6}]);
+//! let query = env.parser().parse("$[?round(@.score) == 5]").unwrap();
+//! assert_eq!(query.find(&value, &env).unwrap().len(), 2);
+//! ```
+//!
+//! Each takes one `ValueType` argument and returns a `ValueType`. Integers
+//! are returned unchanged by `round()`, `floor()` and `ceil()`. Arguments
+//! that aren't numbers, including _Nothing_, result in _Nothing_.
+use crate::{
+    ast::FilterExpressionResult,
+    env::Environment,
+    function::{ExpressionType, FunctionExtension, FunctionSignature},
+};
+
+/// The signature shared by every numeric function.
+pub fn numeric_signature() -> FunctionSignature {
+    FunctionSignature {
+        param_types: vec![ExpressionType::Value],
+        return_type: ExpressionType::Value,
+        deterministic: true,
+        pure: true,
+        cost: None,
+    }
+}
+
+/// Apply `float` to a float argument, leaving integers alone.
+fn round_with<'a>(
+    args: Vec<FilterExpressionResult<'a>>,
+    float: fn(f64) -> f64,
+) -> FilterExpressionResult<'a> {
+    match args.into_iter().next() {
+        Some(FilterExpressionResult::Float(f)) => FilterExpressionResult::Float(float(f)),
+        Some(n @ (FilterExpressionResult::Int(_) | FilterExpressionResult::UInt(_))) => n,
+        _ => FilterExpressionResult::Nothing,
+    }
+}
+
+/// The nearest integer, rounding half-way cases away from zero.
+pub struct Round;
+
+impl FunctionExtension for Round {
+    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
+        round_with(args, f64::round)
+    }
+}
+
+pub struct Floor;
+
+impl FunctionExtension for Floor {
+    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
+        round_with(args, f64::floor)
+    }
+}
+
+pub struct Ceil;
+
+impl FunctionExtension for Ceil {
+    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
+        round_with(args, f64::ceil)
+    }
+}
+
+/// The absolute value of a number. `abs(i64::MIN)` is an unsigned integer.
+pub struct Abs;
+
+impl FunctionExtension for Abs {
+    fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
+        match args.into_iter().next() {
+            Some(FilterExpressionResult::Int(i)) => match i.checked_abs() {
+                Some(i) => FilterExpressionResult::Int(i),
+                None => FilterExpressionResult::UInt(i.unsigned_abs()),
+            },
+            Some(FilterExpressionResult::Float(f)) => FilterExpressionResult::Float(f.abs()),
+            Some(n @ FilterExpressionResult::UInt(_)) => n,
+            _ => FilterExpressionResult::Nothing,
+        }
+    }
+}
+
+impl Environment {
+    /// Register `round()`, `floor()`, `ceil()` and `abs()`, replacing any
+    /// existing functions with the same names.
+    pub fn add_numeric_functions(&mut self) {
+        self.function_register
+            .register("round", numeric_signature(), Box::new(Round));
+        self.function_register
+            .register("floor", numeric_signature(), Box::new(Floor));
+        self.function_register
+            .register("ceil", numeric_signature(), Box::new(Ceil));
+        self.function_register
+            .register("abs", numeric_signature(), Box::new(Abs));
+    }
+}
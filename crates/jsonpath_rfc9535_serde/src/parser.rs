@@ -7,16 +7,25 @@
 
 use std::{collections::HashMap, ops::RangeInclusive};
 
-use pest::{iterators::Pair, Parser};
+use jsonpath_rfc9535::{parser::check_nesting_depth, span::Span};
+
+use pest::{
+    error::{Error, ErrorVariant, InputLocation},
+    iterators::{Pair, Pairs},
+    Parser,
+};
 use pest_derive::Parser;
 
 use crate::{
     ast::{ComparisonOperator, FilterExpression, LogicalOperator, Query, Segment, Selector},
     errors::JSONPathError,
-    function::{standard_functions, ExpressionType, FunctionSignature},
+    function::{standard_functions, ExpressionType, FunctionRegistry, FunctionSignature},
     unescape::unescape,
 };
 
+/// The default [`JSONPathParser::max_nesting_depth`].
+pub const DEFAULT_MAX_NESTING_DEPTH: usize = jsonpath_rfc9535::parser::DEFAULT_MAX_NESTING_DEPTH;
+
 #[derive(Parser)]
 #[grammar = "jsonpath.pest"]
 struct JSONPath;
@@ -24,6 +33,21 @@ struct JSONPath;
 pub struct JSONPathParser {
     pub index_range: RangeInclusive<i64>,
     pub functions: HashMap<String, FunctionSignature>,
+    /// The maximum length of a query, in bytes.
+    pub max_query_length: usize,
+    /// The deepest brackets and parentheses can nest, outside of string
+    /// literals, before parsing fails with a
+    /// [`JSONPathErrorType::DepthError`](crate::errors::JSONPathErrorType::DepthError).
+    ///
+    /// `$[?@.a]` has a depth of one and `$[?count(@[?@.a]) > 1]` a depth of
+    /// three. Queries are checked before they're given to pest, which
+    /// recurses at each level, so this bounds its stack use.
+    pub max_nesting_depth: usize,
+    /// The maximum length of a string literal or quoted name, in bytes,
+    /// before escape sequences are decoded.
+    pub max_string_length: usize,
+    /// Skip a leading byte order mark (U+FEFF) instead of rejecting it.
+    pub skip_bom: bool,
 }
 
 impl Default for JSONPathParser {
@@ -37,33 +61,75 @@ impl JSONPathParser {
         JSONPathParser {
             index_range: ((-2_i64).pow(53) + 1..=2_i64.pow(53) - 1),
             functions: standard_functions(),
+            max_query_length: usize::MAX,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_string_length: usize::MAX,
+            skip_bom: false,
         }
     }
 
+    /// Register the signature of each function in `registry`, replacing any
+    /// existing signatures with the same names.
+    pub fn add_functions<T: ?Sized>(&mut self, registry: &FunctionRegistry<T>) {
+        self.functions.extend(registry.signatures());
+    }
+
     pub fn parse(&self, query: &str) -> Result<Query, JSONPathError> {
-        let segments: Result<Vec<_>, _> = JSONPath::parse(Rule::jsonpath, query)
-            .map_err(|err| JSONPathError::syntax(err.to_string()))?
+        if query.len() > self.max_query_length {
+            return Err(JSONPathError::limit(format!(
+                "query is too long ({} bytes, limit {})",
+                query.len(),
+                self.max_query_length
+            )));
+        }
+
+        let start = match query.strip_prefix('\u{FEFF}') {
+            Some(_) if !self.skip_bom => {
+                return Err(JSONPathError::syntax_at(
+                    String::from("unexpected byte order mark"),
+                    Span::new(0, 0),
+                ))
+            }
+            Some(_) => '\u{FEFF}'.len_utf8(),
+            None => 0,
+        };
+
+        check_nesting_depth(query, self.max_nesting_depth)
+            .map_err(|err| JSONPathError::depth_at(err.msg, err.span))?;
+
+        let segments: Result<Vec<_>, _> = JSONPath::parse(Rule::jsonpath, &query[start..])
+            .map_err(|err| syntax_error(query, start, err))?
             .map(|segment| self.parse_segment(segment))
             .collect();
 
-        Ok(Query {
+        let mut query = Query {
             segments: segments?,
-        })
+            text: Some(query.to_owned()),
+        };
+
+        if start > 0 {
+            shift_segments(&mut query.segments, '\u{FEFF}');
+        }
+
+        Ok(query)
     }
 
     fn parse_segment(&self, segment: Pair<Rule>) -> Result<Segment, JSONPathError> {
         Ok(match segment.as_rule() {
             Rule::child_segment => Segment::Child {
-                selectors: self.parse_segment_inner(segment.into_inner().next().unwrap())?,
+                span: span(&segment),
+                selectors: self.parse_segment_inner(next_pair(&mut segment.into_inner())?)?,
             },
             Rule::descendant_segment => Segment::Recursive {
-                selectors: self.parse_segment_inner(segment.into_inner().next().unwrap())?,
+                span: span(&segment),
+                selectors: self.parse_segment_inner(next_pair(&mut segment.into_inner())?)?,
             },
             Rule::name_segment | Rule::index_segment => Segment::Child {
-                selectors: vec![self.parse_selector(segment.into_inner().next().unwrap())?],
+                span: span(&segment),
+                selectors: vec![self.parse_selector(next_pair(&mut segment.into_inner())?)?],
             },
             Rule::EOI => Segment::Eoi,
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -81,17 +147,14 @@ impl JSONPathParser {
                 // for child_segment
                 name: segment.as_str().to_owned(),
             }],
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
     fn parse_selector(&self, selector: Pair<Rule>) -> Result<Selector, JSONPathError> {
         Ok(match selector.as_rule() {
-            Rule::double_quoted => Selector::Name {
-                name: unescape(selector.as_str())?,
-            },
-            Rule::single_quoted => Selector::Name {
-                name: unescape(&selector.as_str().replace("\\'", "'"))?,
+            Rule::double_quoted | Rule::single_quoted => Selector::Name {
+                name: self.parse_string_literal(selector)?,
             },
             Rule::wildcard_selector => Selector::Wild,
             Rule::slice_selector => self.parse_slice_selector(selector)?,
@@ -103,7 +166,7 @@ impl JSONPathParser {
                 // for name_segment
                 name: selector.as_str().to_owned(),
             },
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -117,7 +180,7 @@ impl JSONPathParser {
                 Rule::start => start = Some(self.parse_i_json_int(i.as_str())?),
                 Rule::stop => stop = Some(self.parse_i_json_int(i.as_str())?),
                 Rule::step => step = Some(self.parse_i_json_int(i.as_str())?),
-                _ => unreachable!(),
+                rule => return Err(unexpected(rule)),
             }
         }
 
@@ -127,7 +190,7 @@ impl JSONPathParser {
     fn parse_filter_selector(&self, selector: Pair<Rule>) -> Result<Selector, JSONPathError> {
         Ok(Selector::Filter {
             expression: Box::new(
-                self.parse_logical_or_expression(selector.into_inner().next().unwrap(), true)?,
+                self.parse_logical_or_expression(next_pair(&mut selector.into_inner())?, true)?,
             ),
         })
     }
@@ -138,7 +201,7 @@ impl JSONPathParser {
         assert_compared: bool,
     ) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let mut or_expr = self.parse_logical_and_expression(it.next().unwrap(), assert_compared)?;
+        let mut or_expr = self.parse_logical_and_expression(next_pair(&mut it)?, assert_compared)?;
 
         if assert_compared {
             self.assert_compared(&or_expr)?;
@@ -165,7 +228,7 @@ impl JSONPathParser {
         assert_compared: bool,
     ) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let mut and_expr = self.parse_basic_expression(it.next().unwrap())?;
+        let mut and_expr = self.parse_basic_expression(next_pair(&mut it)?)?;
 
         if assert_compared {
             self.assert_compared(&and_expr)?;
@@ -188,24 +251,39 @@ impl JSONPathParser {
         Ok(and_expr)
     }
 
+    fn parse_string_literal(&self, literal: Pair<Rule>) -> Result<String, JSONPathError> {
+        let value = literal.as_str();
+        if value.len() > self.max_string_length {
+            return Err(JSONPathError::limit(format!(
+                "string literal is too long (limit {} bytes)",
+                self.max_string_length
+            )));
+        }
+
+        match literal.as_rule() {
+            Rule::single_quoted => unescape(&value.replace("\\'", "'")),
+            _ => unescape(value),
+        }
+    }
+
     fn parse_basic_expression(&self, expr: Pair<Rule>) -> Result<FilterExpression, JSONPathError> {
         match expr.as_rule() {
             Rule::paren_expr => self.parse_paren_expression(expr),
             Rule::comparison_expr => self.parse_comparison_expression(expr),
             Rule::test_expr => self.parse_test_expression(expr),
-            _ => unreachable!(),
+            rule => Err(unexpected(rule)),
         }
     }
 
     fn parse_paren_expression(&self, expr: Pair<Rule>) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let p = it.next().unwrap();
+        let p = next_pair(&mut it)?;
         match p.as_rule() {
             Rule::logical_not_op => Ok(FilterExpression::Not {
-                expression: Box::new(self.parse_logical_or_expression(it.next().unwrap(), true)?),
+                expression: Box::new(self.parse_logical_or_expression(next_pair(&mut it)?, true)?),
             }),
             Rule::logical_or_expr => self.parse_logical_or_expression(p, true),
-            _ => unreachable!(),
+            rule => Err(unexpected(rule)),
         }
     }
 
@@ -213,20 +291,25 @@ impl JSONPathParser {
         &self,
         expr: Pair<Rule>,
     ) -> Result<FilterExpression, JSONPathError> {
+        let span = span(&expr);
         let mut it = expr.into_inner();
-        let left = self.parse_comparable(it.next().unwrap())?;
+        let left = self.parse_comparable(next_pair(&mut it)?)?;
 
-        let operator = match it.next().unwrap().as_str() {
+        let operator = match next_pair(&mut it)?.as_str() {
             "==" => ComparisonOperator::Eq,
             "!=" => ComparisonOperator::Ne,
             "<=" => ComparisonOperator::Le,
             ">=" => ComparisonOperator::Ge,
             "<" => ComparisonOperator::Lt,
             ">" => ComparisonOperator::Gt,
-            _ => unreachable!(),
+            op => {
+                return Err(JSONPathError::syntax(format!(
+                    "unexpected comparison operator `{op}`"
+                )))
+            }
         };
 
-        let right = self.parse_comparable(it.next().unwrap())?;
+        let right = self.parse_comparable(next_pair(&mut it)?)?;
         self.assert_comparable(&left)?;
         self.assert_comparable(&right)?;
 
@@ -234,17 +317,15 @@ impl JSONPathParser {
             left: Box::new(left),
             operator,
             right: Box::new(right),
+            span,
         })
     }
 
     fn parse_comparable(&self, expr: Pair<Rule>) -> Result<FilterExpression, JSONPathError> {
         Ok(match expr.as_rule() {
             Rule::number => self.parse_number(expr)?,
-            Rule::double_quoted => FilterExpression::String {
-                value: unescape(expr.as_str())?,
-            },
-            Rule::single_quoted => FilterExpression::String {
-                value: unescape(&expr.as_str().replace("\\'", "'"))?,
+            Rule::double_quoted | Rule::single_quoted => FilterExpression::String {
+                value: self.parse_string_literal(expr)?,
             },
             Rule::true_literal => FilterExpression::True,
             Rule::false_literal => FilterExpression::False,
@@ -258,6 +339,7 @@ impl JSONPathParser {
                 FilterExpression::RelativeQuery {
                     query: Box::new(Query {
                         segments: segments?,
+                        text: None,
                     }),
                 }
             }
@@ -270,11 +352,12 @@ impl JSONPathParser {
                 FilterExpression::RootQuery {
                     query: Box::new(Query {
                         segments: segments?,
+                        text: None,
                     }),
                 }
             }
             Rule::function_expr => self.parse_function_expression(expr)?,
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -286,7 +369,7 @@ impl JSONPathParser {
         // TODO: change pest grammar to indicate positive or negative exponent?
         let mut it = expr.into_inner();
         let mut is_float = false;
-        let mut n = it.next().unwrap().as_str().to_string(); // int
+        let mut n = next_pair(&mut it)?.as_str().to_string(); // int
 
         if let Some(pair) = it.next() {
             match pair.as_rule() {
@@ -301,7 +384,7 @@ impl JSONPathParser {
                     }
                     n.push_str(exp_str);
                 }
-                _ => unreachable!(),
+                rule => return Err(unexpected(rule)),
             }
         }
 
@@ -319,22 +402,38 @@ impl JSONPathParser {
                     .parse::<f64>()
                     .map_err(|_| JSONPathError::syntax(String::from("invalid float literal")))?,
             })
+        } else if let Ok(value) = n.parse::<i64>() {
+            Ok(FilterExpression::Int { value })
+        } else if let Ok(value) = n.parse::<u64>() {
+            Ok(FilterExpression::UInt { value })
         } else {
-            Ok(FilterExpression::Int {
-                value: n
-                    .parse::<f64>()
-                    .map_err(|_| JSONPathError::syntax(String::from("invalid integer literal")))?
-                    as i64,
-            })
+            let value = n
+                .parse::<f64>()
+                .map_err(|_| JSONPathError::syntax(String::from("invalid integer literal")))?;
+
+            // Integers with an exponent, like `1e3`, go through a float.
+            // Those out of range for an i64 or u64, like `1e400`, stay floats
+            // rather than saturating.
+            if value.abs() < i64::MAX as f64 {
+                Ok(FilterExpression::Int {
+                    value: value as i64,
+                })
+            } else if value > 0.0 && value < u64::MAX as f64 {
+                Ok(FilterExpression::UInt {
+                    value: value as u64,
+                })
+            } else {
+                Ok(FilterExpression::Float { value })
+            }
         }
     }
 
     fn parse_test_expression(&self, expr: Pair<Rule>) -> Result<FilterExpression, JSONPathError> {
         let mut it = expr.into_inner();
-        let pair = it.next().unwrap();
+        let pair = next_pair(&mut it)?;
         Ok(match pair.as_rule() {
             Rule::logical_not_op => FilterExpression::Not {
-                expression: Box::new(self.parse_test_expression_inner(it.next().unwrap())?),
+                expression: Box::new(self.parse_test_expression_inner(next_pair(&mut it)?)?),
             },
             _ => self.parse_test_expression_inner(pair)?,
         })
@@ -354,6 +453,7 @@ impl JSONPathParser {
                 FilterExpression::RelativeQuery {
                     query: Box::new(Query {
                         segments: segments?,
+                        text: None,
                     }),
                 }
             }
@@ -366,11 +466,12 @@ impl JSONPathParser {
                 FilterExpression::RootQuery {
                     query: Box::new(Query {
                         segments: segments?,
+                        text: None,
                     }),
                 }
             }
             Rule::function_expr => self.parse_function_expression(expr)?,
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -378,24 +479,23 @@ impl JSONPathParser {
         &self,
         expr: Pair<Rule>,
     ) -> Result<FilterExpression, JSONPathError> {
+        let span = span(&expr);
         let mut it = expr.into_inner();
-        let name = it.next().unwrap().as_str();
+        let name = next_pair(&mut it)?.as_str();
         let args: Result<Vec<_>, _> = it.map(|ex| self.parse_function_argument(ex)).collect();
 
         Ok(FilterExpression::Function {
             name: name.to_string(),
             args: self.assert_well_typed(name, args?)?,
+            span,
         })
     }
 
     fn parse_function_argument(&self, expr: Pair<Rule>) -> Result<FilterExpression, JSONPathError> {
         Ok(match expr.as_rule() {
             Rule::number => self.parse_number(expr)?,
-            Rule::double_quoted => FilterExpression::String {
-                value: unescape(expr.as_str())?,
-            },
-            Rule::single_quoted => FilterExpression::String {
-                value: unescape(&expr.as_str().replace("\\'", "'"))?,
+            Rule::double_quoted | Rule::single_quoted => FilterExpression::String {
+                value: self.parse_string_literal(expr)?,
             },
             Rule::true_literal => FilterExpression::True,
             Rule::false_literal => FilterExpression::False,
@@ -409,6 +509,7 @@ impl JSONPathParser {
                 FilterExpression::RelativeQuery {
                     query: Box::new(Query {
                         segments: segments?,
+                        text: None,
                     }),
                 }
             }
@@ -421,12 +522,13 @@ impl JSONPathParser {
                 FilterExpression::RootQuery {
                     query: Box::new(Query {
                         segments: segments?,
+                        text: None,
                     }),
                 }
             }
             Rule::logical_or_expr => self.parse_logical_or_expression(expr, false)?,
             Rule::function_expr => self.parse_function_expression(expr)?,
-            _ => unreachable!(),
+            rule => return Err(unexpected(rule)),
         })
     }
 
@@ -522,8 +624,7 @@ impl JSONPathParser {
         }
 
         // correct argument types?
-        for (idx, typ) in signature.param_types.iter().enumerate() {
-            let arg = &args[idx];
+        for (idx, (typ, arg)) in signature.param_types.iter().zip(&args).enumerate() {
             match typ {
                 ExpressionType::Value => {
                     if !self.is_value_type(arg) {
@@ -607,6 +708,116 @@ impl JSONPathParser {
     }
 }
 
+/// The span of `pair` in the text it was parsed from.
+fn span(pair: &Pair<Rule>) -> Span {
+    let span = pair.as_span();
+    Span::new(span.start(), span.end()).with_chars(span.get_input())
+}
+
+/// Move the spans in `segments` along by `prefix`, for a query parsed after
+/// a skipped byte order mark.
+fn shift_segments(segments: &mut [Segment], prefix: char) {
+    for segment in segments {
+        if let Segment::Child { selectors, span } | Segment::Recursive { selectors, span } = segment
+        {
+            shift_span(span, prefix);
+            for selector in selectors {
+                if let Selector::Filter { expression } = selector {
+                    shift_expression(expression, prefix);
+                }
+            }
+        }
+    }
+}
+
+fn shift_expression(expr: &mut FilterExpression, prefix: char) {
+    match expr {
+        FilterExpression::Not { expression } => shift_expression(expression, prefix),
+        FilterExpression::Logical { left, right, .. } => {
+            shift_expression(left, prefix);
+            shift_expression(right, prefix);
+        }
+        FilterExpression::Comparison {
+            left, right, span, ..
+        } => {
+            shift_span(span, prefix);
+            shift_expression(left, prefix);
+            shift_expression(right, prefix);
+        }
+        FilterExpression::RelativeQuery { query } | FilterExpression::RootQuery { query } => {
+            shift_segments(&mut query.segments, prefix)
+        }
+        FilterExpression::Function { args, span, .. } => {
+            shift_span(span, prefix);
+            for arg in args {
+                shift_expression(arg, prefix);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn shift_span(span: &mut Span, prefix: char) {
+    // Char offsets differ from byte offsets after a non-ASCII prefix, even
+    // if the rest of the query is ASCII.
+    let (start, end) = span.chars.unwrap_or((span.start, span.end));
+    span.chars = Some((start + 1, end + 1));
+    span.start += prefix.len_utf8();
+    span.end += prefix.len_utf8();
+}
+
+/// Convert a pest error into a syntax error, using the same message and
+/// position as the hand-crafted parser in `jsonpath_rfc9535` for trailing
+/// content after a complete query, queries ending after a dot and control
+/// characters. `start` is the offset into `query` that parsing started from,
+/// after any skipped byte order mark.
+fn syntax_error(query: &str, start: usize, err: Error<Rule>) -> JSONPathError {
+    let position = start
+        + match err.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((pos, _)) => pos,
+        };
+
+    let before = query[start..position].trim_end_matches([' ', '\t', '\n', '\r']);
+    let complete = || JSONPath::parse(Rule::jsonpath, before).is_ok();
+
+    let msg = match query[position..].chars().next() {
+        Some(ch) if ch.is_ascii_control() && !matches!(ch, '\t' | '\n' | '\r') => {
+            format!("unexpected control character U+{:04X}", ch as u32)
+        }
+        Some(ch) if complete() => format!(
+            "expected '.', '..' or a bracketed selection, found '{}'",
+            ch
+        ),
+        None if before.len() < position - start && complete() => {
+            String::from("unexpected trailing whitespace")
+        }
+        None if matches!(
+            &err.variant,
+            ErrorVariant::ParsingError { positives, .. }
+                if positives.contains(&Rule::member_name_shorthand)
+        ) =>
+        {
+            String::from("unexpected end of query")
+        }
+        _ => err.to_string(),
+    };
+
+    JSONPathError::syntax_at(msg, Span::new(position, position).with_chars(query))
+}
+
+/// The next pair in `pairs`, which the grammar guarantees is there.
+fn next_pair<'i>(pairs: &mut Pairs<'i, Rule>) -> Result<Pair<'i, Rule>, JSONPathError> {
+    pairs
+        .next()
+        .ok_or_else(|| JSONPathError::syntax(String::from("unexpected end of parse tree")))
+}
+
+/// An error for a rule the grammar doesn't produce where it was found.
+fn unexpected(rule: Rule) -> JSONPathError {
+    JSONPathError::syntax(format!("unexpected {rule:?} in parse tree"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1061,3 +1272,4 @@ mod tests {
         whitespace__selectors__return_between_recursive_descent_and_name_0: "$..\ra",
     }
 }
+
@@ -0,0 +1,88 @@
+//! Resource limits for evaluating untrusted queries.
+//!
+//! Limits are spread across [`JSONPathParser`] and [`Environment`]. A
+//! [`SandboxProfile`] bundles them so they can be configured together.
+use std::time::Duration;
+
+use crate::{
+    env::Environment, parser::DEFAULT_MAX_NESTING_DEPTH, standard_functions::RegexLimits,
+    JSONPathParser,
+};
+
+/// Parser and evaluator limits, applied with [`SandboxProfile::apply`].
+#[derive(Debug, Clone)]
+pub struct SandboxProfile {
+    /// See [`JSONPathParser::max_query_length`].
+    pub max_query_length: usize,
+    /// See [`JSONPathParser::max_nesting_depth`].
+    pub max_nesting_depth: usize,
+    /// See [`JSONPathParser::max_string_length`].
+    pub max_string_length: usize,
+    /// See [`Environment::max_nodes`].
+    pub max_nodes: usize,
+    /// See [`Environment::max_depth`].
+    pub max_depth: usize,
+    /// See [`Environment::timeout`].
+    pub timeout: Option<Duration>,
+    /// Limits for `match()` and `search()` patterns.
+    pub regex_limits: RegexLimits,
+}
+
+impl Default for SandboxProfile {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+impl SandboxProfile {
+    /// No limits, the same as a new parser and environment, apart from the
+    /// parser's default nesting depth, which bounds its stack use.
+    pub fn unlimited() -> Self {
+        Self {
+            max_query_length: usize::MAX,
+            max_nesting_depth: DEFAULT_MAX_NESTING_DEPTH,
+            max_string_length: usize::MAX,
+            max_nodes: usize::MAX,
+            max_depth: usize::MAX,
+            timeout: None,
+            regex_limits: RegexLimits::default(),
+        }
+    }
+
+    /// Limits suitable for evaluating queries from untrusted users against
+    /// modest documents, where many tenants share one process.
+    pub fn strict() -> Self {
+        Self {
+            max_query_length: 1024,
+            max_nesting_depth: 8,
+            max_string_length: 512,
+            max_nodes: 10_000,
+            max_depth: 64,
+            timeout: Some(Duration::from_millis(100)),
+            regex_limits: RegexLimits {
+                size_limit: 1 << 16,
+                dfa_size_limit: 1 << 18,
+                nest_limit: 16,
+                max_pattern_length: 256,
+                deny: Vec::new(),
+            },
+        }
+    }
+
+    /// Apply these limits to `parser` and `env`. Queries parsed by `env`'s
+    /// own parser, like those given to [`Environment::find`], are limited
+    /// too.
+    pub fn apply(&self, parser: &mut JSONPathParser, env: &mut Environment) {
+        parser.max_query_length = self.max_query_length;
+        parser.max_nesting_depth = self.max_nesting_depth;
+        parser.max_string_length = self.max_string_length;
+        env.max_query_length = self.max_query_length;
+        env.max_nesting_depth = self.max_nesting_depth;
+        env.max_string_length = self.max_string_length;
+        env.max_nodes = self.max_nodes;
+        env.max_depth = self.max_depth;
+        env.timeout = self.timeout;
+        env.descendant_index_limit = env.descendant_index_limit.min(self.max_nodes);
+        env.set_regex_limits(self.regex_limits.clone());
+    }
+}
@@ -1,12 +1,12 @@
-use std::{num::NonZeroUsize, sync::Mutex};
+use std::{
+    num::NonZeroUsize,
+    sync::{Mutex, PoisonError},
+};
 
 use lru::LruCache;
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 
-use crate::{
-    ast::FilterExpressionResult,
-    function::{ExpressionType, FunctionExtension, FunctionSignature},
-};
+use crate::{ast::FilterExpressionResult, function::FunctionExtension};
 
 pub struct Count;
 
@@ -24,16 +24,9 @@ impl Default for Count {
 
 impl FunctionExtension for Count {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match args.first().unwrap() {
+        match args.first().unwrap_or(&FilterExpressionResult::Nothing) {
             FilterExpressionResult::Nodes(nodes) => FilterExpressionResult::Int(nodes.len() as i64),
-            _ => unreachable!(),
-        }
-    }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
+            _ => FilterExpressionResult::Nothing,
         }
     }
 }
@@ -54,37 +47,92 @@ impl Default for Length {
 
 impl FunctionExtension for Length {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match args.first().unwrap() {
-            // TODO: UInt
+        match args.first().unwrap_or(&FilterExpressionResult::Nothing) {
             FilterExpressionResult::String(s) => {
                 FilterExpressionResult::Int(s.chars().count() as i64)
             }
             FilterExpressionResult::Array(a) => {
-                FilterExpressionResult::Int(a.as_array().unwrap().len() as i64)
+                FilterExpressionResult::Int(a.as_array().map_or(0, Vec::len) as i64)
             }
             FilterExpressionResult::Object(o) => {
-                FilterExpressionResult::Int(o.as_object().unwrap().len() as i64)
+                FilterExpressionResult::Int(o.as_object().map_or(0, serde_json::Map::len) as i64)
             }
             _ => FilterExpressionResult::Nothing,
         }
     }
+}
+
+/// Limits on the regular expressions compiled by `match()` and `search()`.
+///
+/// Patterns that exceed a limit, or are denied, are treated like invalid
+/// patterns, so `match()` and `search()` return `false`.
+#[derive(Debug, Clone)]
+pub struct RegexLimits {
+    /// The approximate maximum size, in bytes, of a compiled pattern. See
+    /// [`RegexBuilder::size_limit`].
+    pub size_limit: usize,
+    /// The approximate maximum size, in bytes, of the cache used by the lazy
+    /// DFA when matching one pattern. See [`RegexBuilder::dfa_size_limit`].
+    pub dfa_size_limit: usize,
+    /// The maximum depth of nested groups and repetitions. See
+    /// [`RegexBuilder::nest_limit`].
+    pub nest_limit: u32,
+    /// The maximum length of a pattern, in bytes.
+    pub max_pattern_length: usize,
+    /// Patterns matching any of these are rejected without being compiled,
+    /// like patterns with nested counted repetitions (`(a{100}){100}`).
+    pub deny: Vec<Regex>,
+}
+
+impl Default for RegexLimits {
+    fn default() -> Self {
+        Self {
+            size_limit: 10 * (1 << 20),
+            dfa_size_limit: 2 * (1 << 20),
+            nest_limit: 250,
+            max_pattern_length: usize::MAX,
+            deny: Vec::new(),
+        }
+    }
+}
 
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value],
-            return_type: ExpressionType::Value,
+impl RegexLimits {
+    fn compile(&self, pattern: &str, source: &str) -> Option<Regex> {
+        if pattern.len() > self.max_pattern_length
+            || self.deny.iter().any(|re| re.is_match(pattern))
+        {
+            return None;
         }
+
+        RegexBuilder::new(source)
+            .size_limit(self.size_limit)
+            .dfa_size_limit(self.dfa_size_limit)
+            .nest_limit(self.nest_limit)
+            .build()
+            .ok()
     }
 }
 
+/// The number of compiled patterns `match` and `search` keep.
+const REGEX_CACHE_SIZE: NonZeroUsize = match NonZeroUsize::new(100) {
+    Some(size) => size,
+    None => NonZeroUsize::MIN,
+};
+
 pub struct Match {
     cache: Mutex<LruCache<String, Regex>>,
+    limits: RegexLimits,
 }
 
 impl Match {
     pub fn new() -> Self {
+        Self::with_limits(RegexLimits::default())
+    }
+
+    pub fn with_limits(limits: RegexLimits) -> Self {
         Self {
-            cache: Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap())),
+            cache: Mutex::new(LruCache::new(REGEX_CACHE_SIZE)),
+            limits,
         }
     }
 }
@@ -97,21 +145,21 @@ impl Default for Match {
 
 impl FunctionExtension for Match {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match (args.first().unwrap(), args.get(1).unwrap()) {
-            (FilterExpressionResult::String(s), FilterExpressionResult::String(p)) => {
+        match (args.first(), args.get(1)) {
+            (Some(FilterExpressionResult::String(s)), Some(FilterExpressionResult::String(p))) => {
                 // TODO: fail early if p is known to be invalid
-                let mut cache = self.cache.lock().unwrap();
+                let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
 
-                match cache.get(p) {
+                match cache.get(p.as_ref()) {
                     Some(re) => FilterExpressionResult::Bool(re.is_match(s)),
                     None => {
                         if !iregexp::check(p) {
                             return FilterExpressionResult::Bool(false);
                         }
 
-                        if let Ok(re) = Regex::new(&full_match(&p)) {
+                        if let Some(re) = self.limits.compile(p, &full_match(p)) {
                             let rv = re.is_match(s);
-                            cache.push(p.to_owned(), re);
+                            cache.push(p.to_string(), re);
                             FilterExpressionResult::Bool(rv)
                         } else {
                             FilterExpressionResult::Bool(false)
@@ -122,23 +170,22 @@ impl FunctionExtension for Match {
             _ => FilterExpressionResult::Bool(false),
         }
     }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        }
-    }
 }
 
 pub struct Search {
     cache: Mutex<LruCache<String, Regex>>,
+    limits: RegexLimits,
 }
 
 impl Search {
     pub fn new() -> Self {
+        Self::with_limits(RegexLimits::default())
+    }
+
+    pub fn with_limits(limits: RegexLimits) -> Self {
         Self {
-            cache: Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap())),
+            cache: Mutex::new(LruCache::new(REGEX_CACHE_SIZE)),
+            limits,
         }
     }
 }
@@ -151,21 +198,21 @@ impl Default for Search {
 
 impl FunctionExtension for Search {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match (args.first().unwrap(), args.get(1).unwrap()) {
-            (FilterExpressionResult::String(s), FilterExpressionResult::String(p)) => {
+        match (args.first(), args.get(1)) {
+            (Some(FilterExpressionResult::String(s)), Some(FilterExpressionResult::String(p))) => {
                 // TODO: fail early if p is known to be invalid
-                let mut cache = self.cache.lock().unwrap();
+                let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
 
-                match cache.get(p) {
+                match cache.get(p.as_ref()) {
                     Some(re) => FilterExpressionResult::Bool(re.is_match(s)),
                     None => {
                         if !iregexp::check(p) {
                             return FilterExpressionResult::Bool(false);
                         }
 
-                        if let Ok(re) = Regex::new(&map_regex(&p)) {
+                        if let Some(re) = self.limits.compile(p, &map_regex(p)) {
                             let rv = re.is_match(s);
-                            cache.push(p.to_owned(), re);
+                            cache.push(p.to_string(), re);
                             FilterExpressionResult::Bool(rv)
                         } else {
                             FilterExpressionResult::Bool(false)
@@ -176,13 +223,6 @@ impl FunctionExtension for Search {
             _ => FilterExpressionResult::Bool(false),
         }
     }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        }
-    }
 }
 
 pub struct Value;
@@ -201,22 +241,15 @@ impl Default for Value {
 
 impl FunctionExtension for Value {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match args.first().unwrap() {
+        match args.first().unwrap_or(&FilterExpressionResult::Nothing) {
             FilterExpressionResult::Nodes(nodes) => {
-                if nodes.len() == 1 {
-                    FilterExpressionResult::from_json_value(nodes.first().unwrap().value)
+                if let [node] = nodes.as_slice() {
+                    FilterExpressionResult::from_json_value(node.value)
                 } else {
                     FilterExpressionResult::Nothing
                 }
             }
-            _ => unreachable!(),
-        }
-    }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
+            _ => FilterExpressionResult::Nothing,
         }
     }
 }
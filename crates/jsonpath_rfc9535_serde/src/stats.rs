@@ -0,0 +1,85 @@
+//! Diagnostics collected while evaluating a query.
+//!
+//! Comparing _Nothing_ with anything other than _Nothing_ is `false`, so a
+//! filter that refers to a missing member silently fails to match. When
+//! evaluated with [`Query::find_with_stats`](crate::Query::find_with_stats),
+//! each comparison with a _Nothing_ operand is recorded, along with why that
+//! operand was _Nothing_.
+use std::fmt;
+
+/// Why a comparison operand resulted in _Nothing_.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NothingReason {
+    /// A singular query stopped at `path`, the first member or element that
+    /// does not exist.
+    MissingMember { path: String },
+    /// A function argument query selected no nodes.
+    EmptyNodeList { query: String },
+    /// A function returned _Nothing_ for arguments that were not themselves
+    /// _Nothing_.
+    Function { call: String },
+}
+
+impl fmt::Display for NothingReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NothingReason::MissingMember { path } => write!(f, "{path} does not exist"),
+            NothingReason::EmptyNodeList { query } => write!(f, "{query} selected no nodes"),
+            NothingReason::Function { call } => write!(f, "{call} returned Nothing"),
+        }
+    }
+}
+
+/// A comparison that had a _Nothing_ operand, and how many times it
+/// happened for the same reason.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NothingTrace {
+    pub comparison: String,
+    pub reason: NothingReason,
+    pub count: usize,
+}
+
+impl fmt::Display for NothingTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} in `{}` ({} time{})",
+            self.reason,
+            self.comparison,
+            self.count,
+            if self.count == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Statistics gathered by [`Query::find_with_stats`](crate::Query::find_with_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvalStats {
+    /// Comparisons with a _Nothing_ operand, in the order they were first
+    /// seen.
+    pub nothing: Vec<NothingTrace>,
+    /// The number of times an absolute query in a filter was answered from
+    /// the results of an earlier evaluation during the same `find`. Singular
+    /// queries are resolved directly, so they're never counted.
+    pub root_query_cache_hits: usize,
+    /// The number of times a descendant segment reused nodes from an
+    /// earlier visit of the same subtree.
+    pub descendant_index_hits: usize,
+}
+
+impl EvalStats {
+    pub(crate) fn record(&mut self, comparison: String, reason: NothingReason) {
+        match self
+            .nothing
+            .iter_mut()
+            .find(|trace| trace.comparison == comparison && trace.reason == reason)
+        {
+            Some(trace) => trace.count += 1,
+            None => self.nothing.push(NothingTrace {
+                comparison,
+                reason,
+                count: 1,
+            }),
+        }
+    }
+}
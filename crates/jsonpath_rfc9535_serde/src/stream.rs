@@ -0,0 +1,422 @@
+//! Evaluate a query against a JSON document as it is read.
+//!
+//! [`find_stream`] reads a document from any [`Read`] without building a
+//! `Value` for all of it. Name, wildcard, non-negative index and slice
+//! selectors, and descendant segments, are matched against member names and
+//! array indices as they are read, and subtrees the query can't select from
+//! are skipped. A `Value` is only built for nodes the query selects, for
+//! each child tested by a filter selector, one at a time, and for the
+//! children of a segment that needs to see them all, like a negative index.
+//! The rest of the query is then evaluated against that subtree in memory.
+//!
+//! Queries with filters referring to the root value, `$`, need the whole
+//! document, so it is read into memory first.
+//!
+//! ```
+//! use jsonpath_rfc9535_serde::stream::find_stream;
+//!
+//! let document = r#"{"features": [{"id": 1, "tags": ["a"]}, {"id": 2, "tags": []}]}"#;
+//! let nodes = find_stream("$.features[?length(@.tags) > 0].id", document.as_bytes()).unwrap();
+//!
+//! assert_eq!(nodes.len(), 1);
+//! assert_eq!(nodes[0].location, "$['features'][0]['id']");
+//! assert_eq!(nodes[0].value, 1);
+//! ```
+//!
+//! `find_stream` selects the same nodes as [`find`](crate::find), but they
+//! are returned in the order they appear in the document, and object members
+//! are visited in document order even without the `preserve_order` feature.
+//! Nodes selected from a subtree evaluated in memory are in the order `find`
+//! would give them.
+use std::{fmt, io::Read};
+
+use serde::{
+    de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize,
+};
+use serde_json::Value;
+
+use crate::{
+    ast::{Node, Segment, Selector},
+    env::Environment,
+    errors::JSONPathError,
+    jsonpath::ENV,
+    Query,
+};
+
+/// A node selected by [`find_stream`], which owns its value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamNode {
+    pub value: Value,
+    pub location: String,
+}
+
+impl From<Node<'_>> for StreamNode {
+    fn from(node: Node<'_>) -> Self {
+        StreamNode {
+            value: node.value.clone(),
+            location: node.location,
+        }
+    }
+}
+
+/// Find nodes matching `expr` in the JSON document read from `reader`.
+pub fn find_stream<R: Read>(expr: &str, reader: R) -> Result<Vec<StreamNode>, JSONPathError> {
+    Query::standard(expr)?.find_stream(reader, &ENV)
+}
+
+impl Query {
+    /// Same as `find`, but reads the document from `reader`, only building
+    /// values for the parts of it this query needs.
+    pub fn find_stream<R: Read>(
+        &self,
+        reader: R,
+        env: &Environment,
+    ) -> Result<Vec<StreamNode>, JSONPathError> {
+        if self.refers_to_root() {
+            let value: Value = serde_json::from_reader(reader)
+                .map_err(|err| JSONPathError::document(err.to_string()))?;
+            return Ok(self
+                .find(&value, env)?
+                .into_iter()
+                .map(StreamNode::from)
+                .collect());
+        }
+
+        let mut stream = Stream {
+            query: self,
+            env,
+            nodes: Vec::new(),
+            error: None,
+        };
+
+        let states = vec![stream.skip_eoi(0)];
+        let mut de = serde_json::Deserializer::from_reader(reader);
+        let rv = Seed {
+            stream: &mut stream,
+            states,
+            path: Path { parent: None },
+        }
+        .deserialize(&mut de)
+        .and_then(|_| de.end());
+
+        if let Some(err) = stream.error {
+            return Err(err);
+        }
+        rv.map_err(|err| JSONPathError::document(err.to_string()))?;
+        Ok(stream.nodes)
+    }
+}
+
+/// A member name or array index.
+#[derive(Clone, Copy)]
+enum Key<'k> {
+    Name(&'k str),
+    Index(usize),
+}
+
+struct Stream<'q, 'e> {
+    query: &'q Query,
+    env: &'e Environment,
+    nodes: Vec<StreamNode>,
+    /// An error from evaluating part of the query in memory, which we can't
+    /// pass through the deserializer without losing its kind.
+    error: Option<JSONPathError>,
+}
+
+impl Stream<'_, '_> {
+    /// The index of the first segment at or after `index` that isn't
+    /// `Segment::Eoi`.
+    fn skip_eoi(&self, mut index: usize) -> usize {
+        while matches!(self.query.segments.get(index), Some(Segment::Eoi)) {
+            index += 1;
+        }
+        index
+    }
+
+    /// Return `true` if the segment at `index` can be matched against a
+    /// value's children one at a time, by their keys or, for filters, by
+    /// loading each child.
+    fn is_streamable(&self, index: usize) -> bool {
+        match self.query.segments.get(index) {
+            Some(Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) => {
+                selectors.iter().all(|selector| match selector {
+                    Selector::Name { .. } | Selector::Wild => true,
+                    Selector::Index { index } => *index >= 0,
+                    Selector::Slice { start, stop, step } => {
+                        start.unwrap_or(0) >= 0 && stop.unwrap_or(0) >= 0 && step.unwrap_or(1) > 0
+                    }
+                    Selector::Filter { .. } => true,
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// The segments to resolve against a child at `key`, given the segments
+    /// to resolve against its parent. The index one past the last segment
+    /// means the child is a result.
+    fn child_states(&self, states: &[usize], key: Key) -> Vec<usize> {
+        let mut child_states = Vec::new();
+        for &index in states {
+            let Some(segment) = self.query.segments.get(index) else {
+                continue;
+            };
+            match segment {
+                Segment::Child { selectors, .. } => {
+                    for _ in selectors.iter().filter(|s| matches_key(s, key)) {
+                        child_states.push(self.skip_eoi(index + 1));
+                    }
+                }
+                Segment::Recursive { selectors, .. } => {
+                    for _ in selectors.iter().filter(|s| matches_key(s, key)) {
+                        child_states.push(self.skip_eoi(index + 1));
+                    }
+                    // Descendants of the child are still in scope.
+                    child_states.push(index);
+                }
+                Segment::Eoi => (),
+            }
+        }
+        child_states
+    }
+
+    /// Return `true` if a segment at `states` has a filter, so children have
+    /// to be loaded to find out if they're selected.
+    fn has_filter(&self, states: &[usize]) -> bool {
+        states
+            .iter()
+            .any(|&index| match self.query.segments.get(index) {
+                Some(Segment::Child { selectors, .. } | Segment::Recursive { selectors, .. }) => {
+                    selectors
+                        .iter()
+                        .any(|selector| matches!(selector, Selector::Filter { .. }))
+                }
+                _ => false,
+            })
+    }
+
+    /// The segments to resolve against child `value`, given the segments to
+    /// resolve against its parent, for filter selectors that select it.
+    fn filtered_states(
+        &self,
+        states: &[usize],
+        value: &Value,
+    ) -> Result<Vec<usize>, JSONPathError> {
+        let mut child_states = Vec::new();
+        for &index in states {
+            let count = self.query.filters_matching(index, value, self.env)?;
+            child_states.extend(std::iter::repeat_n(self.skip_eoi(index + 1), count));
+        }
+        Ok(child_states)
+    }
+
+    /// Keep `err` to return from [`Query::find_stream`], and abort
+    /// deserialization.
+    fn fail<E: de::Error>(&mut self, err: JSONPathError) -> E {
+        let msg = err.msg.clone();
+        self.error = Some(err);
+        E::custom(msg)
+    }
+
+    /// Resolve the segments at `states` against `value` in memory.
+    fn evaluate(
+        &mut self,
+        value: &Value,
+        location: &str,
+        states: &[usize],
+    ) -> Result<(), JSONPathError> {
+        for &index in states {
+            if index >= self.query.segments.len() {
+                self.nodes.push(StreamNode {
+                    value: value.clone(),
+                    location: location.to_owned(),
+                });
+            } else {
+                let nodes = self
+                    .query
+                    .resolve_from(index, value, location.to_owned(), self.env)?;
+                self.nodes.extend(nodes.into_iter().map(StreamNode::from));
+            }
+        }
+
+        // Children tested by a filter are evaluated one at a time, so the
+        // limit on selected nodes is checked against all of them here.
+        if self.nodes.len() > self.env.max_nodes {
+            return Err(JSONPathError::limit(format!(
+                "too many nodes ({}, limit {})",
+                self.nodes.len(),
+                self.env.max_nodes
+            ))
+            .in_query(self.query));
+        }
+        Ok(())
+    }
+}
+
+fn matches_key(selector: &Selector, key: Key) -> bool {
+    match (selector, key) {
+        (Selector::Wild, _) => true,
+        (Selector::Name { name }, Key::Name(key)) => name == key,
+        (Selector::Index { index }, Key::Index(i)) => usize::try_from(*index) == Ok(i),
+        (Selector::Slice { start, stop, step }, Key::Index(i)) => {
+            let (start, step) = (start.unwrap_or(0), step.unwrap_or(1));
+            let i = i as i64;
+            i >= start && stop.is_none_or(|stop| i < stop) && (i - start) % step == 0
+        }
+        _ => false,
+    }
+}
+
+/// The location of a value being read, written out only when a node is
+/// selected from it.
+struct Path<'p> {
+    parent: Option<(&'p Path<'p>, Key<'p>)>,
+}
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.parent {
+            Some((parent, Key::Name(name))) => write!(f, "{parent}['{name}']"),
+            Some((parent, Key::Index(i))) => write!(f, "{parent}[{i}]"),
+            None => f.write_str("$"),
+        }
+    }
+}
+
+/// Reads one value, with the segments to resolve against it.
+struct Seed<'s, 'p, 'q, 'e> {
+    stream: &'s mut Stream<'q, 'e>,
+    states: Vec<usize>,
+    path: Path<'p>,
+}
+
+impl<'q, 'e> Seed<'_, '_, 'q, 'e> {
+    fn child<'c>(&'c mut self, key: Key<'c>) -> Option<Seed<'c, 'c, 'q, 'e>> {
+        let states = self.stream.child_states(&self.states, key);
+        if states.is_empty() {
+            return None;
+        }
+
+        Some(Seed {
+            stream: &mut *self.stream,
+            states,
+            path: Path {
+                parent: Some((&self.path, key)),
+            },
+        })
+    }
+
+    /// Find nodes in child `value`, which was loaded because a filter
+    /// selector needs to test it.
+    fn filter_child<E: de::Error>(&mut self, key: Key, value: &Value) -> Result<(), E> {
+        let mut states = self.stream.child_states(&self.states, key);
+        let rv = self
+            .stream
+            .filtered_states(&self.states, value)
+            .and_then(|filtered| {
+                states.extend(filtered);
+                if states.is_empty() {
+                    return Ok(());
+                }
+                let path = Path {
+                    parent: Some((&self.path, key)),
+                };
+                self.stream.evaluate(value, &path.to_string(), &states)
+            });
+        rv.map_err(|err| self.stream.fail(err))
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for Seed<'_, '_, '_, '_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self
+            .states
+            .iter()
+            .all(|&index| self.stream.is_streamable(index))
+        {
+            return deserializer.deserialize_any(self);
+        }
+
+        let value = Value::deserialize(deserializer)?;
+        self.stream
+            .evaluate(&value, &self.path.to_string(), &self.states)
+            .map_err(|err| self.stream.fail(err))
+    }
+}
+
+impl<'de> Visitor<'de> for Seed<'_, '_, '_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_bool<E: de::Error>(self, _v: bool) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_i64<E: de::Error>(self, _v: i64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_u64<E: de::Error>(self, _v: u64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_f64<E: de::Error>(self, _v: f64) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_str<E: de::Error>(self, _v: &str) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        let filtered = self.stream.has_filter(&self.states);
+        for i in 0.. {
+            let key = Key::Index(i);
+            let more = if filtered {
+                match seq.next_element::<Value>()? {
+                    Some(value) => self.filter_child(key, &value).map(|_| true)?,
+                    None => false,
+                }
+            } else {
+                match self.child(key) {
+                    Some(seed) => seq.next_element_seed(seed)?.is_some(),
+                    None => seq.next_element::<IgnoredAny>()?.is_some(),
+                }
+            };
+            if !more {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(mut self, mut map: A) -> Result<(), A::Error> {
+        let filtered = self.stream.has_filter(&self.states);
+        while let Some(name) = map.next_key::<String>()? {
+            let key = Key::Name(&name);
+            if filtered {
+                let value = map.next_value::<Value>()?;
+                self.filter_child(key, &value)?;
+            } else {
+                match self.child(key) {
+                    Some(seed) => map.next_value_seed(seed)?,
+                    None => map.next_value::<IgnoredAny>().map(|_| ())?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,63 @@
+use jsonpath_rfc9535_serde::{env::Environment, Query};
+use serde_json::{json, Value};
+
+fn data() -> Value {
+    json!({
+        "threshold": 2,
+        "groups": [
+            {"id": "a", "items": [1, 2, 3]},
+            {"id": "b", "items": [4, 1]},
+        ]
+    })
+}
+
+#[test]
+fn bound_find_is_find() {
+    let env = Environment::new();
+    let data = data();
+    for expr in [
+        "$.groups[*].items[?@ > $.threshold]",
+        "$.groups[?count(@.items[?@ > $.threshold]) > 1].id",
+        "$.groups[?@.items[0] == $.groups[1].items[1]].id",
+        "$..[?@ == $.threshold]",
+        "$.threshold",
+    ] {
+        let query = Query::standard(expr).unwrap();
+        assert_eq!(
+            query.bind_root(&data, &env).unwrap().find().unwrap(),
+            query.find(&data, &env).unwrap(),
+            "{}",
+            expr
+        );
+    }
+}
+
+#[test]
+fn find_at_uses_the_bound_root() {
+    let env = Environment::new();
+    let data = data();
+    let query = Query::standard("$[?@ > $.threshold]").unwrap();
+    let bound = query.bind_root(&data, &env).unwrap();
+
+    let found: Vec<Vec<(String, &Value)>> = data["groups"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|group| {
+            bound
+                .find_at(&group["items"])
+                .unwrap()
+                .into_iter()
+                .map(|node| (node.location, node.value))
+                .collect()
+        })
+        .collect();
+
+    assert_eq!(
+        found,
+        vec![
+            vec![(String::from("$[2]"), &json!(3))],
+            vec![(String::from("$[0]"), &json!(4))],
+        ]
+    );
+}
@@ -0,0 +1,145 @@
+use jsonpath_rfc9535_serde::{
+    ast::FilterExpressionResult,
+    env::Environment,
+    function::{ExpressionType, FunctionExtension, FunctionSignature},
+};
+use serde_json::{json, Value};
+
+fn data() -> Value {
+    json!({
+        "config": {"threshold": 2, "names": ["b", "c"]},
+        "items": [
+            {"name": "a", "v": 1},
+            {"name": "b", "v": 2},
+            {"name": "c", "v": 3},
+            {"name": "d", "v": 4},
+        ]
+    })
+}
+
+fn names(env: &Environment, query: &str) -> (Vec<Value>, usize) {
+    let data = data();
+    let (nodes, stats) = env.find_with_stats(query, &data).unwrap();
+    assert_eq!(nodes, env.find(query, &data).unwrap());
+    (
+        nodes
+            .iter()
+            .map(|node| node.value["name"].clone())
+            .collect(),
+        stats.root_query_cache_hits,
+    )
+}
+
+#[test]
+fn singular_root_query() {
+    // Singular queries are resolved directly, without the cache.
+    assert_eq!(
+        names(&Environment::new(), "$.items[?@.v > $.config.threshold]"),
+        (vec![json!("c"), json!("d")], 0)
+    );
+}
+
+#[test]
+fn root_query_with_filter() {
+    assert_eq!(
+        names(
+            &Environment::new(),
+            "$.items[?count($.config.names[?@ == 'b' || @ == 'c']) == @.v]"
+        ),
+        (vec![json!("b")], 3)
+    );
+}
+
+struct Always;
+
+impl FunctionExtension for Always {
+    fn call<'a>(&self, _args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
+        FilterExpressionResult::Bool(true)
+    }
+}
+
+fn always_hits(deterministic: bool) -> usize {
+    let mut env = Environment::new();
+    env.function_register.register(
+        "always",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value],
+            return_type: ExpressionType::Logical,
+            deterministic,
+            pure: deterministic,
+            cost: None,
+        },
+        Box::new(Always),
+    );
+
+    let parser = env.parser();
+
+    let data = data();
+    let (nodes, stats) = parser
+        .parse("$.items[?count($.config.names[?always(@)]) == @.v]")
+        .unwrap()
+        .find_with_stats(&data, &env)
+        .unwrap();
+    assert_eq!(nodes.len(), 1);
+    stats.root_query_cache_hits
+}
+
+#[test]
+fn deterministic_function_extensions_are_cached() {
+    assert_eq!(always_hits(true), 3);
+}
+
+#[test]
+fn non_deterministic_function_extensions_are_not_cached() {
+    assert_eq!(always_hits(false), 0);
+}
+
+fn descendants(query: &str) -> usize {
+    let data = json!({
+        "a": {"b": [1, {"a": {"b": 2}}], "c": {"b": 3}},
+        "d": [{"a": [{"b": 4}]}],
+    });
+    let without_index = Environment {
+        descendant_index_limit: 0,
+        ..Environment::new()
+    };
+    let want = without_index.find(query, &data).unwrap();
+    let (got, stats) = Environment::new().find_with_stats(query, &data).unwrap();
+    assert_eq!(got, want, "{}", query);
+    stats.descendant_index_hits
+}
+
+#[test]
+fn nested_descendant_segments() {
+    assert_eq!(descendants("$..a..b"), 3);
+    assert_eq!(descendants("$..a..b..*"), 8);
+    assert_eq!(descendants("$.a..b"), 0);
+}
+
+#[test]
+fn descendant_segments_in_filters() {
+    assert_eq!(descendants("$..[?@..b]"), 13);
+    assert_eq!(descendants("$..[?count(@..b) > 1]"), 13);
+}
+
+#[test]
+fn descendant_index_limit() {
+    let data = json!({"a": {"b": {"a": {"b": 1}}}});
+    let env = Environment {
+        descendant_index_limit: 4,
+        ..Environment::new()
+    };
+    let (nodes, stats) = env.find_with_stats("$..a..b", &data).unwrap();
+    assert_eq!(
+        nodes
+            .iter()
+            .map(|node| node.location.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            "$['a']['b']",
+            "$['a']['b']['a']['b']",
+            "$['a']['b']['a']['b']"
+        ]
+    );
+    assert_eq!(stats.descendant_index_hits, 1);
+}
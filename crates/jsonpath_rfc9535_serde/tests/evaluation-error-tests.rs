@@ -0,0 +1,173 @@
+use jsonpath_rfc9535_serde::{
+    ast::FilterExpressionResult,
+    env::Environment,
+    errors::{JSONPathError, JSONPathErrorType},
+    function::{ExpressionType, FunctionSignature},
+    Query,
+};
+use serde_json::json;
+
+/// The part of `query` that `err` blames.
+fn blamed<'q>(err: &JSONPathError, query: &'q str) -> &'q str {
+    let span = err.span.expect("an evaluation error has a span");
+    &query[span.start..span.end]
+}
+
+#[test]
+fn missing_function() {
+    let mut env = Environment::new();
+    env.add_function(
+        "typeof",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value],
+            return_type: ExpressionType::Value,
+            deterministic: true,
+            pure: true,
+            cost: None,
+        },
+        |_| FilterExpressionResult::Nothing,
+    );
+
+    let query_text = "$.a[?@.b && typeof(@.c)  ==  'x']";
+    let query = env.parser().parse(query_text).unwrap();
+    let err = query
+        .find(&json!({"a": [{"b": 1}]}), &Environment::new())
+        .unwrap_err();
+
+    assert!(matches!(err.kind, JSONPathErrorType::NameError));
+    assert_eq!(blamed(&err, query_text), "typeof(@.c)");
+    assert_eq!(err.query.as_deref(), Some(query_text));
+    assert_eq!(
+        err.render(err.query.as_deref().unwrap()),
+        "name error: missing function definition for typeof\n  \
+         $.a[?@.b && typeof(@.c)  ==  'x']\n              ^^^^^^^^^^^"
+    );
+}
+
+#[test]
+fn too_many_nodes() {
+    let env = Environment {
+        max_nodes: 3,
+        ..Default::default()
+    };
+    let data = json!([[1, 2], [3, 4]]);
+
+    let query_text = "$[*]..[*]";
+    let query = Query::standard(query_text).unwrap();
+    for err in [
+        query.find(&data, &env).unwrap_err(),
+        query.find_loop(&data, &env).unwrap_err(),
+    ] {
+        assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+        assert_eq!(blamed(&err, query_text), "..[*]");
+        assert_eq!(err.query.as_deref(), Some("$[*]..[*]"));
+    }
+}
+
+#[test]
+fn innermost_node_is_blamed() {
+    let env = Environment {
+        max_nodes: 3,
+        ..Default::default()
+    };
+    let data = json!([[1, 2], [3, 4]]);
+
+    // The segment in the nested query, not the filter's segment.
+    let query_text = "$[?count($.*.*) > 1]";
+    let err = Query::standard(query_text)
+        .unwrap()
+        .find(&data, &env)
+        .unwrap_err();
+    assert_eq!(blamed(&err, query_text), ".*");
+    assert_eq!(&query_text[err.span.unwrap().start - 2..][..4], ".*.*");
+    assert_eq!(err.query.as_deref(), Some(query_text));
+}
+
+#[test]
+fn strict_nan() {
+    let mut env = Environment::new();
+    env.add_function(
+        "nan",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value],
+            return_type: ExpressionType::Value,
+            deterministic: true,
+            pure: true,
+            cost: None,
+        },
+        |_| FilterExpressionResult::Float(f64::NAN),
+    );
+    env.strict_nan = true;
+
+    let query_text = "$.a[?@.b > 0 || nan(@) < 1]";
+    let query = env.parser().parse(query_text).unwrap();
+    let err = query.find(&json!({"a": [{"b": 0}]}), &env).unwrap_err();
+
+    assert!(matches!(err.kind, JSONPathErrorType::RuntimeError));
+    assert_eq!(blamed(&err, query_text), "nan(@) < 1");
+}
+
+#[test]
+fn spans_skip_byte_order_mark() {
+    let mut parser = Environment::new().parser();
+    parser.skip_bom = true;
+    let env = Environment {
+        max_nodes: 1,
+        ..Default::default()
+    };
+
+    let query_text = "\u{FEFF}$.a[*]";
+    let err = parser
+        .parse(query_text)
+        .unwrap()
+        .find(&json!({"a": [1, 2]}), &env)
+        .unwrap_err();
+    assert_eq!(blamed(&err, query_text), "[*]");
+    assert_eq!(err.span.unwrap().char_range(), 4..7);
+}
+
+#[test]
+fn spans_count_chars() {
+    let env = Environment {
+        max_nodes: 1,
+        ..Default::default()
+    };
+
+    let query_text = "$['ü'][*]";
+    let err = Query::standard(query_text)
+        .unwrap()
+        .find(&json!({"ü": [1, 2]}), &env)
+        .unwrap_err();
+    assert_eq!(blamed(&err, query_text), "[*]");
+    assert_eq!(err.span.unwrap().char_range(), 6..9);
+}
+
+#[test]
+fn parse_errors_have_no_query() {
+    let err = Query::standard("$[").unwrap_err();
+    assert_eq!(err.span.map(|span| span.start..span.end), Some(2..2));
+    assert!(err.query.is_none());
+}
+
+#[test]
+fn convert_to_the_shared_error_type() {
+    use jsonpath_rfc9535::{JSONPathError as Error, JSONPathErrorType as Kind};
+
+    let env = Environment {
+        max_nodes: 3,
+        ..Default::default()
+    };
+    let query_text = "$[*]..[*]";
+    let err: Error = Query::standard(query_text)
+        .unwrap()
+        .find(&json!([[1, 2], [3, 4]]), &env)
+        .unwrap_err()
+        .into();
+    assert_eq!(err.kind, Kind::LimitError);
+    assert_eq!(err.kind.code(), 5);
+    assert_eq!(err.span.start..err.span.end, 4..9);
+
+    let err = Error::from(Query::standard("$[?@.a ==]").unwrap_err());
+    assert_eq!(err.kind, Kind::SyntaxError);
+    assert_eq!(err.span.start..err.span.end, 9..9);
+}
@@ -0,0 +1,147 @@
+use std::borrow::Cow;
+
+use jsonpath_rfc9535_serde::{
+    ast::FilterExpressionResult,
+    env::Environment,
+    errors::JSONPathErrorType,
+    function::{ExpressionType, FunctionSignature},
+    Query,
+};
+use serde_json::{json, Value};
+
+fn upper() -> FunctionSignature {
+    FunctionSignature {
+        param_types: vec![ExpressionType::Value],
+        return_type: ExpressionType::Value,
+        deterministic: true,
+        pure: true,
+        cost: None,
+    }
+}
+
+fn env() -> Environment {
+    let mut env = Environment::new();
+    env.add_function("upper", upper(), |args| match args.first() {
+        Some(FilterExpressionResult::String(s)) => {
+            FilterExpressionResult::String(Cow::Owned(s.to_uppercase()))
+        }
+        _ => FilterExpressionResult::Nothing,
+    });
+    env
+}
+
+fn names(env: &Environment, query: &str) -> Vec<Value> {
+    let value = json!([{"name": "a", "code": "ab"}, {"name": "b", "code": "AB"}]);
+    env.parser()
+        .parse(query)
+        .unwrap()
+        .find(&value, env)
+        .unwrap()
+        .iter()
+        .map(|node| node.value.clone())
+        .collect()
+}
+
+#[test]
+fn closures_are_function_extensions() {
+    let env = env();
+    assert_eq!(
+        names(&env, "$[?upper(@.code) == 'AB'].name"),
+        vec![json!("a"), json!("b")]
+    );
+    assert_eq!(
+        names(&env, "$[?upper(@.code) == @.code].name"),
+        vec![json!("b")]
+    );
+}
+
+#[test]
+fn environment_parsers_know_registered_signatures() {
+    let env = env();
+    assert!(env.parser().parse("$[?upper(@.code) == 'AB']").is_ok());
+
+    let err = Query::standard("$[?upper(@.code) == 'AB']").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::NameError));
+
+    let err = env.parser().parse("$[?upper(@.code)]").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::TypeError));
+}
+
+#[test]
+fn standard_functions_are_registered_with_signatures() {
+    let env = Environment::new();
+    for name in ["count", "length", "match", "search", "value"] {
+        assert!(env.function_register.contains(name), "{}", name);
+        assert!(env.function_register.signature(name).is_some(), "{}", name);
+    }
+    assert_eq!(env.function_register.len(), 5);
+}
+
+#[test]
+fn closures_replace_standard_functions() {
+    let mut env = Environment::new();
+    env.add_function(
+        "length",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value],
+            return_type: ExpressionType::Value,
+            deterministic: true,
+            pure: true,
+            cost: None,
+        },
+        |_| FilterExpressionResult::Int(7),
+    );
+    assert_eq!(names(&env, "$[?length(@.code) == 7].name").len(), 2);
+}
+
+#[test]
+fn numeric_functions_are_opt_in() {
+    let err = Environment::new()
+        .parser()
+        .parse("$[?round(@.a) == 1]")
+        .unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::NameError));
+
+    let mut env = Environment::new();
+    env.add_numeric_functions();
+    for name in ["round", "floor", "ceil", "abs"] {
+        assert!(env.function_register.contains(name), "{}", name);
+    }
+    let err = env.parser().parse("$[?round(@.a)]").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::TypeError));
+}
+
+#[test]
+fn numeric_functions() {
+    let mut env = Environment::new();
+    env.add_numeric_functions();
+    let value = json!([-2.5, -1, 0.4, 2.5, 3, "3", null, i64::MIN]);
+    let select = |query: &str| -> Vec<Value> {
+        env.parser()
+            .parse(query)
+            .unwrap()
+            .find(&value, &env)
+            .unwrap()
+            .iter()
+            .map(|node| node.value.clone())
+            .collect()
+    };
+
+    assert_eq!(select("$[?round(@) == 3]"), vec![json!(2.5), json!(3)]);
+    assert_eq!(select("$[?round(@) == -3]"), vec![json!(-2.5)]);
+    assert_eq!(select("$[?floor(@) == 2]"), vec![json!(2.5)]);
+    assert_eq!(select("$[?ceil(@) == 1]"), vec![json!(0.4)]);
+    assert_eq!(select("$[?abs(@) == 2.5]"), vec![json!(-2.5), json!(2.5)]);
+    assert_eq!(select("$[?abs(@) == 1]"), vec![json!(-1)]);
+    assert_eq!(
+        select("$[?abs(@) == 9223372036854775808]"),
+        vec![json!(i64::MIN)]
+    );
+
+    // Non-numbers and missing values are Nothing.
+    assert_eq!(
+        select("$[?round(@) == round(@.x)]"),
+        vec![json!("3"), json!(null)]
+    );
+    assert!(select("$[?abs(@) == '3']").is_empty());
+}
@@ -0,0 +1,102 @@
+use jsonpath_rfc9535_serde::{
+    ast::FilterExpressionResult,
+    env::Environment,
+    errors::JSONPathErrorType,
+    function::{ExpressionType, FunctionSignature},
+};
+use serde_json::{json, Value};
+
+/// An environment with `ratio(a, b)`, which divides two numbers, so `0 / 0`
+/// is NaN and `1 / 0` is infinity.
+fn env() -> Environment {
+    let mut env = Environment::new();
+    env.add_function(
+        "ratio",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value, ExpressionType::Value],
+            return_type: ExpressionType::Value,
+            deterministic: true,
+            pure: true,
+            cost: None,
+        },
+        |args| {
+            let number = |rv: &FilterExpressionResult| match rv {
+                FilterExpressionResult::Int(i) => Some(*i as f64),
+                FilterExpressionResult::Float(f) => Some(*f),
+                _ => None,
+            };
+            match (args.first().and_then(number), args.get(1).and_then(number)) {
+                (Some(a), Some(b)) => FilterExpressionResult::Float(a / b),
+                _ => FilterExpressionResult::Nothing,
+            }
+        },
+    );
+    env
+}
+
+fn data() -> Value {
+    json!([{"a": 0, "b": 0}, {"a": 1, "b": 0}, {"a": -1, "b": 0}, {"a": 1, "b": 2}])
+}
+
+fn indices(env: &Environment, query: &str) -> Vec<String> {
+    let value = data();
+    env.parser()
+        .parse(query)
+        .unwrap()
+        .find(&value, env)
+        .unwrap()
+        .iter()
+        .map(|node| node.location.clone())
+        .collect()
+}
+
+#[test]
+fn comparisons_with_nan_are_false() {
+    let env = env();
+    for op in ["==", "<", "<=", ">", ">="] {
+        for query in [
+            format!("$[?ratio(@.a, @.b) {op} ratio(@.a, @.b)]"),
+            format!("$[?ratio(@.a, @.b) {op} 0]"),
+            format!("$[?0.5 {op} ratio(@.a, @.b)]"),
+        ] {
+            assert!(
+                !indices(&env, &query).contains(&"$[0]".to_owned()),
+                "{}",
+                query
+            );
+        }
+    }
+
+    // `!=` is the negation of `==`
+    assert_eq!(
+        indices(&env, "$[?ratio(@.a, @.b) != ratio(@.a, @.b)]"),
+        vec!["$[0]"]
+    );
+}
+
+#[test]
+fn infinities_are_ordered() {
+    let env = env();
+    assert_eq!(indices(&env, "$[?ratio(@.a, @.b) > 1e300]"), vec!["$[1]"]);
+    assert_eq!(indices(&env, "$[?ratio(@.a, @.b) < -1e300]"), vec!["$[2]"]);
+    assert_eq!(indices(&env, "$[?ratio(@.a, @.b) == 1e400]"), vec!["$[1]"]);
+    assert_eq!(
+        indices(&env, "$[?ratio(@.a, @.b) <= 0.5]"),
+        vec!["$[2]", "$[3]"]
+    );
+}
+
+#[test]
+fn strict_mode_rejects_nan() {
+    let mut env = env();
+    env.strict_nan = true;
+
+    let value = data();
+    let query = env.parser().parse("$[?ratio(@.a, @.b) > 0]").unwrap();
+    let err = query.find(&value, &env).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::RuntimeError));
+
+    // Infinities are fine
+    let value = json!([{"a": 1, "b": 0}, {"a": -1, "b": 0}, {"a": 1, "b": 2}]);
+    assert_eq!(query.find(&value, &env).unwrap().len(), 2);
+}
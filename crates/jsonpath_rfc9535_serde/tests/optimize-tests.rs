@@ -0,0 +1,62 @@
+use jsonpath_rfc9535_serde::{env::Environment, Query};
+use serde_json::{json, Value};
+
+fn locations(expr: &str, value: &Value, env: &Environment) -> Vec<String> {
+    Query::standard(expr)
+        .unwrap()
+        .find(value, env)
+        .unwrap()
+        .iter()
+        .map(|node| node.location.clone())
+        .collect()
+}
+
+#[test]
+fn optimized_queries_select_the_same_nodes() {
+    let env = Environment::new();
+    let docs = [
+        json!({"a": [{"b": 1, "c": [2, 3]}, {"b": 2}], "n": 2}),
+        json!([{"a": {"a": 1}}, [1, [2, {"a": 3}]], "x", null]),
+        json!({"a": {"b": {"c": true}}, "b": [{}, {"c": 1}]}),
+        json!([[], [0], [0, 1], [0, 1, 2], [0, 1, 2, 3, 4, 5, 6], {"0": 0}]),
+    ];
+    let exprs = [
+        "$.a[?@.b && 1 == 1]",
+        "$.a[?@.b || 1 == 2].c",
+        "$..[?1 == 1]",
+        "$..[?1 == 2, 0]",
+        "$[?'a' < 'b', ?null != null].a",
+        "$.a[?@.b == 1 || !(1 <= 1.0)]",
+        "$[?@.a[?1 == 2]]",
+        "$[?!@.a[?1 == 2]]",
+        "$..[?count(@[?false == true]) == 0]",
+        "$.a[1:1].b",
+        "$[*][5:6]",
+        "$[*][-3:-2, 1:0]",
+        "$[*][:1]",
+        "$[*][-1:0]",
+        "$[*][:-2:-1]",
+        "$[*][5:4:-1]",
+        "$[*][0:-1:-1]",
+        "$[*][0:100][0]",
+        "$..a[?$.n > 1 && 2 >= 1]",
+    ];
+
+    for doc in &docs {
+        for expr in exprs {
+            let optimized = jsonpath_rfc9535::Query::standard(expr)
+                .unwrap()
+                .optimized()
+                .try_to_standard_string()
+                .unwrap();
+            assert_eq!(
+                locations(&optimized, doc, &env),
+                locations(expr, doc, &env),
+                "{} optimized as {} on {}",
+                expr,
+                optimized,
+                doc
+            );
+        }
+    }
+}
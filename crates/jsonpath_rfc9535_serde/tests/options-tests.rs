@@ -0,0 +1,154 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use jsonpath_rfc9535_serde::{
+    ast::FilterExpressionResult,
+    env::Environment,
+    errors::JSONPathErrorType,
+    find_with_options,
+    function::{ExpressionType, FunctionSignature},
+    options::{FindOptions, Found, Output, ResultOrder},
+};
+use serde_json::{json, Value};
+
+fn paths(query: &str, value: &Value, options: FindOptions) -> Vec<String> {
+    match find_with_options(query, value, &options.output(Output::Paths))
+        .unwrap()
+        .found
+    {
+        Found::Paths(paths) => paths,
+        found => panic!("expected paths, found {:?}", found),
+    }
+}
+
+#[test]
+fn default_options() {
+    let value = json!([1, 2, 3]);
+    let result = find_with_options("$[2, 0, 2]", &value, &FindOptions::new()).unwrap();
+    let Found::Nodes(nodes) = result.found else {
+        panic!("expected nodes")
+    };
+    let values: Vec<&Value> = nodes.iter().map(|node| node.value).collect();
+    assert_eq!(values, vec![&json!(3), &json!(1), &json!(3)]);
+    assert!(result.stats.is_none());
+}
+
+#[test]
+fn values_output() {
+    let value = json!({"a": [1, 2]});
+    let options = FindOptions::new().output(Output::Values);
+    let result = find_with_options("$.a[*]", &value, &options).unwrap();
+    let Found::Values(values) = result.found else {
+        panic!("expected values")
+    };
+    assert_eq!(values, vec![&json!(1), &json!(2)]);
+}
+
+#[test]
+fn dedup() {
+    let value = json!(["a", "b"]);
+    assert_eq!(
+        paths("$[1, 0, 1]", &value, FindOptions::new().dedup(true)),
+        vec!["$[1]", "$[0]"]
+    );
+}
+
+#[test]
+fn dedup_keeps_equal_values_at_different_locations() {
+    let value = json!(["a", "a"]);
+    assert_eq!(
+        paths("$[0, 1]", &value, FindOptions::new().dedup(true)),
+        vec!["$[0]", "$[1]"]
+    );
+}
+
+#[test]
+fn document_order() {
+    let value = json!({"a": [[1, 2], 3], "c": 4});
+    let options = FindOptions::new().order(ResultOrder::Document);
+    assert_eq!(
+        paths("$.a[1, 0][1, 0]", &value, options.clone()),
+        vec!["$['a'][0][0]", "$['a'][0][1]"]
+    );
+    assert_eq!(
+        paths("$['c', 'a']", &value, options.clone()),
+        vec!["$['a']", "$['c']"]
+    );
+    assert_eq!(
+        paths("$..[1]", &value, options),
+        vec!["$['a'][0][1]", "$['a'][1]"]
+    );
+}
+
+#[test]
+fn max_nodes() {
+    let value = json!([1, 2, 3]);
+    let options = FindOptions::new().max_nodes(2);
+    let err = find_with_options("$[*]", &value, &options).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+    assert_eq!(err.msg, "query selected more than 2 nodes (limit 2)");
+
+    let options = FindOptions::new().max_nodes(2).dedup(true);
+    assert!(find_with_options("$[0, 0, 1]", &value, &options).is_ok());
+}
+
+#[test]
+fn max_query_length() {
+    let options = FindOptions::new().max_query_length(4);
+    assert!(find_with_options("$.ab", &json!({}), &options).is_ok());
+    let err = find_with_options("$.abc", &json!({}), &options).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+    assert_eq!(err.msg, "query is too long (limit 4 bytes)");
+}
+
+#[test]
+fn stats() {
+    let value = json!([1, 2]);
+    let options = FindOptions::new().stats(true).dedup(true);
+    let result = find_with_options("$[0, 0, 1]", &value, &options).unwrap();
+    assert_eq!(result.found.len(), 2);
+    assert_eq!(result.stats.unwrap().selected, 3);
+}
+
+fn counting_env(calls: &'static AtomicUsize) -> Environment {
+    let mut env = Environment::new();
+    env.add_function(
+        "seen",
+        FunctionSignature {
+            param_types: vec![ExpressionType::Value],
+            return_type: ExpressionType::Logical,
+            deterministic: false,
+            pure: false,
+            cost: None,
+        },
+        move |_| {
+            calls.fetch_add(1, Ordering::Relaxed);
+            FilterExpressionResult::Bool(true)
+        },
+    );
+    env
+}
+
+#[test]
+fn environment_functions() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let env = counting_env(&CALLS);
+    let value = json!([1, 2]);
+    let result = env
+        .find_with_options("$[?seen(@)]", &value, &FindOptions::new())
+        .unwrap();
+    assert_eq!(result.found.len(), 2);
+    assert!(find_with_options("$[?seen(@)]", &value, &FindOptions::new()).is_err());
+}
+
+#[test]
+fn max_nodes_stops_evaluation() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+    let env = counting_env(&CALLS);
+    let value = json!([[1, 2], [3, 4], [5, 6], [7, 8]]);
+    let options = FindOptions::new().max_nodes(3);
+    let err = env
+        .find_with_options("$[*][?seen(@)]", &value, &options)
+        .unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+    assert_eq!(CALLS.load(Ordering::Relaxed), 4);
+}
@@ -0,0 +1,73 @@
+//! Member order in results follows serde_json's map implementation. With the
+//! `preserve_order` feature, members are visited in document order. Without
+//! it, members are visited in lexicographic order of their names.
+use jsonpath_rfc9535_serde::{
+    env::{Environment, MemberOrder},
+    find,
+};
+use serde_json::Value;
+
+const DATA: &str = r#"{"b": 1, "a": {"d": 2, "c": [3]}}"#;
+
+fn paths(query: &str) -> Vec<String> {
+    let value: Value = serde_json::from_str(DATA).unwrap();
+    find(query, &value)
+        .unwrap()
+        .iter()
+        .map(|node| node.location.to_owned())
+        .collect()
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn wildcard_document_order() {
+    assert_eq!(paths("$.*"), vec!["$['b']", "$['a']"]);
+}
+
+#[test]
+#[cfg(not(feature = "preserve_order"))]
+fn wildcard_sorted_order() {
+    assert_eq!(paths("$.*"), vec!["$['a']", "$['b']"]);
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn descendant_document_order() {
+    assert_eq!(
+        paths("$..*"),
+        vec![
+            "$['b']",
+            "$['a']",
+            "$['a']['d']",
+            "$['a']['c']",
+            "$['a']['c'][0]"
+        ]
+    );
+}
+
+#[test]
+#[cfg(not(feature = "preserve_order"))]
+fn descendant_sorted_order() {
+    assert_eq!(
+        paths("$..*"),
+        vec![
+            "$['a']",
+            "$['b']",
+            "$['a']['c']",
+            "$['a']['d']",
+            "$['a']['c'][0]"
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "preserve_order")]
+fn document_ordering() {
+    assert_eq!(Environment::new().ordering(), MemberOrder::Document);
+}
+
+#[test]
+#[cfg(not(feature = "preserve_order"))]
+fn lexical_ordering() {
+    assert_eq!(Environment::new().ordering(), MemberOrder::Lexical);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "rayon")]
+
+use jsonpath_rfc9535_serde::{
+    ast::Node, env::Environment, errors::JSONPathErrorType, find, find_parallel, Query,
+};
+use serde_json::{json, Value};
+
+fn locations(nodes: &[Node]) -> Vec<String> {
+    nodes.iter().map(|node| node.location.clone()).collect()
+}
+
+fn document() -> Value {
+    let items: Vec<Value> = (0..500)
+        .map(|i| json!({"id": i, "tags": ["a", "b"], "child": {"id": i * 2, "n": [i]}}))
+        .collect();
+    json!({"items": items, "limit": 250, "id": -1})
+}
+
+#[test]
+fn parallel_results_match_sequential_results() {
+    let env = Environment::new();
+    let value = document();
+
+    for expr in [
+        "$..id",
+        "$..*",
+        "$..[0, 1]",
+        "$.items..n[0]",
+        "$..[?@.id > $.limit]",
+        "$..[?@.id > $.limit].child..id",
+        "$..[?count(@..id) > 1 && @.id < 10]",
+        "$..tags[-1]",
+        "$.items[0]",
+    ] {
+        let query = Query::standard(expr).unwrap();
+        let want = query.find(&value, &env).unwrap();
+        let got = query.find_parallel(&value, &env).unwrap();
+        assert_eq!(locations(&got), locations(&want), "{}", expr);
+    }
+}
+
+#[test]
+fn find_parallel_with_the_default_environment() {
+    let value = json!([{"a": [1, {"a": 2}]}, {"b": {"a": 3}}]);
+    assert_eq!(
+        locations(&find_parallel("$..a", &value).unwrap()),
+        locations(&find("$..a", &value).unwrap())
+    );
+}
+
+#[test]
+fn parallel_evaluation_checks_limits() {
+    let env = Environment {
+        max_nodes: 100,
+        ..Environment::new()
+    };
+    let err = Query::standard("$..id")
+        .unwrap()
+        .find_parallel(&document(), &env)
+        .unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+}
@@ -0,0 +1,74 @@
+use jsonpath_rfc9535_serde::{env::Environment, standard_functions::RegexLimits};
+use regex::Regex;
+use serde_json::{json, Value};
+
+fn count(env: &Environment, query: &str) -> usize {
+    let data = json!(["aaa", "abc", "b"]);
+    env.find(query, &data).unwrap().len()
+}
+
+#[test]
+fn default_limits() {
+    let env = Environment::new();
+    assert_eq!(count(&env, "$[?match(@, 'a{1,3}')]"), 1);
+    assert_eq!(count(&env, "$[?search(@, 'a')]"), 2);
+}
+
+#[test]
+fn pattern_too_large() {
+    let mut env = Environment::new();
+    env.set_regex_limits(RegexLimits {
+        size_limit: 1000,
+        ..Default::default()
+    });
+    assert_eq!(count(&env, "$[?search(@, 'a')]"), 2);
+    assert_eq!(count(&env, "$[?search(@, '(a|b){50}')]"), 0);
+    assert_eq!(count(&env, "$[?match(@, '(a|b){50}')]"), 0);
+}
+
+#[test]
+fn nesting_too_deep() {
+    let mut env = Environment::new();
+    env.set_regex_limits(RegexLimits {
+        nest_limit: 10,
+        ..Default::default()
+    });
+    assert_eq!(count(&env, "$[?match(@, '((a))+')]"), 1);
+    assert_eq!(count(&env, "$[?match(@, '((((((((((a))))))))))+')]"), 0);
+}
+
+#[test]
+fn pattern_too_long() {
+    let mut env = Environment::new();
+    env.set_regex_limits(RegexLimits {
+        max_pattern_length: 3,
+        ..Default::default()
+    });
+    assert_eq!(count(&env, "$[?search(@, 'a.c')]"), 1);
+    assert_eq!(count(&env, "$[?search(@, 'a.c|b')]"), 0);
+}
+
+#[test]
+fn denied_patterns() {
+    let mut env = Environment::new();
+    env.set_regex_limits(RegexLimits {
+        deny: vec![Regex::new(r"\{\d+(,\d*)?\}\)*\{").unwrap()],
+        ..Default::default()
+    });
+    assert_eq!(count(&env, "$[?match(@, 'a{3}')]"), 1);
+    assert_eq!(count(&env, "$[?match(@, '(a{1}){3}')]"), 0);
+    assert_eq!(count(&env, "$[?search(@, '((a{1,}){1})')]"), 0);
+}
+
+#[test]
+fn limits_do_not_affect_other_functions() {
+    let mut env = Environment::new();
+    env.set_regex_limits(RegexLimits {
+        max_pattern_length: 0,
+        ..Default::default()
+    });
+    let data = json!([[1, 2], [1]]);
+    let nodes = env.find("$[?count(@[*]) == 2]", &data).unwrap();
+    assert_eq!(nodes.len(), 1);
+    assert_eq!(nodes[0].value, &json!([1, 2]) as &Value);
+}
@@ -0,0 +1,232 @@
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use jsonpath_rfc9535_serde::{
+    ast::FilterExpressionResult,
+    env::Environment,
+    errors::{JSONPathError, JSONPathErrorType},
+    function::{ExpressionType, FunctionSignature},
+    sandbox::SandboxProfile,
+    JSONPathParser,
+};
+use serde_json::{json, Value};
+
+fn sandbox(profile: SandboxProfile) -> (JSONPathParser, Environment) {
+    let mut parser = JSONPathParser::new();
+    let mut env = Environment::new();
+    profile.apply(&mut parser, &mut env);
+    (parser, env)
+}
+
+fn assert_limit_error(err: JSONPathError, msg: &str) {
+    assert!(matches!(err.kind, JSONPathErrorType::LimitError), "{err}");
+    assert_eq!(err.msg, msg);
+}
+
+fn nested(depth: usize) -> Value {
+    (0..depth).fold(json!(1), |value, _| json!([value]))
+}
+
+#[test]
+fn unlimited_by_default() {
+    let (parser, env) = sandbox(SandboxProfile::default());
+    let data = nested(500);
+    let query = parser.parse("$..[?count(@[?@[?@]]) >= 0]").unwrap();
+    assert!(query.find(&data, &env).is_ok());
+}
+
+#[test]
+fn query_too_long() {
+    let (parser, _) = sandbox(SandboxProfile {
+        max_query_length: 5,
+        ..Default::default()
+    });
+    assert!(parser.parse("$.abc").is_ok());
+    assert_limit_error(
+        parser.parse("$.abcd").unwrap_err(),
+        "query is too long (6 bytes, limit 5)",
+    );
+}
+
+#[test]
+fn query_nested_too_deeply() {
+    let (parser, _) = sandbox(SandboxProfile {
+        max_nesting_depth: 2,
+        ..Default::default()
+    });
+    assert!(parser.parse("$[?@[?@.a]]").is_ok());
+    let err = parser.parse("$[?@[?@[?@.a]]]").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::DepthError), "{err}");
+    assert_eq!(err.msg, "query nesting depth exceeds the maximum of 2");
+    assert_eq!(err.span.map(|span| span.start), Some(7));
+}
+
+#[test]
+fn string_literal_too_long() {
+    let (parser, _) = sandbox(SandboxProfile {
+        max_string_length: 5,
+        ..Default::default()
+    });
+    assert!(parser.parse("$['abcde'][?@.a == \"vwxyz\"]").is_ok());
+
+    for query in [
+        "$['abcdef']",
+        "$[?@.a == 'abcdef']",
+        "$[?length('abcdef') > 1]",
+        r#"$["\\\\\\"]"#,
+    ] {
+        assert_limit_error(
+            parser.parse(query).unwrap_err(),
+            "string literal is too long (limit 5 bytes)",
+        );
+    }
+}
+
+#[test]
+fn too_many_nodes() {
+    let (parser, env) = sandbox(SandboxProfile {
+        max_nodes: 3,
+        ..Default::default()
+    });
+    let data = json!([[1, 2], [3, 4]]);
+
+    let query = parser.parse("$[*]").unwrap();
+    assert_eq!(query.find(&data, &env).unwrap().len(), 2);
+    assert_eq!(query.find_loop(&data, &env).unwrap().len(), 2);
+
+    let query = parser.parse("$[*][*]").unwrap();
+    assert_limit_error(
+        query.find(&data, &env).unwrap_err(),
+        "too many nodes (4, limit 3)",
+    );
+    assert_limit_error(
+        query.find_loop(&data, &env).unwrap_err(),
+        "too many nodes (4, limit 3)",
+    );
+
+    // Including nodes selected by queries in filters.
+    let query = parser.parse("$[?count($[*][*]) > 1]").unwrap();
+    assert!(query.find(&data, &env).is_err());
+}
+
+#[test]
+fn too_many_nodes_stops_evaluation() {
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    let (_, mut env) = sandbox(SandboxProfile {
+        max_nodes: 5,
+        ..Default::default()
+    });
+    env.add_function(
+        "called",
+        FunctionSignature {
+            param_types: vec![],
+            return_type: ExpressionType::Logical,
+            deterministic: false,
+            pure: false,
+            cost: None,
+        },
+        |_| {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            FilterExpressionResult::Bool(true)
+        },
+    );
+
+    // The segment fails once the second array's elements are selected, so
+    // the filter isn't applied to the third array.
+    let data = json!([[1, 2, 3], [4, 5, 6], [7, 8, 9]]);
+    assert_limit_error(
+        env.find("$[*][?called()]", &data).unwrap_err(),
+        "too many nodes (6, limit 5)",
+    );
+    assert_eq!(CALLS.load(Ordering::Relaxed), 6);
+}
+
+#[test]
+fn environment_parses_with_profile_limits() {
+    let (_, env) = sandbox(SandboxProfile {
+        max_query_length: 5,
+        max_nesting_depth: 1,
+        ..Default::default()
+    });
+    let data = json!({"abc": 1});
+
+    assert_eq!(env.find("$.abc", &data).unwrap().len(), 1);
+    assert_limit_error(
+        env.find("$.abcd", &data).unwrap_err(),
+        "query is too long (6 bytes, limit 5)",
+    );
+
+    let (_, env) = sandbox(SandboxProfile {
+        max_nesting_depth: 1,
+        ..Default::default()
+    });
+    let err = env.find("$[?@[?@.a]]", &data).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::DepthError), "{err}");
+}
+
+#[test]
+fn nested_too_deeply() {
+    let (parser, env) = sandbox(SandboxProfile {
+        max_depth: 3,
+        ..Default::default()
+    });
+
+    let query = parser.parse("$..*").unwrap();
+    assert_eq!(query.find(&nested(3), &env).unwrap().len(), 3);
+    assert_limit_error(
+        query.find(&nested(4), &env).unwrap_err(),
+        "$ is nested too deeply (limit 3)",
+    );
+
+    // Only subtrees visited by descendant segments are checked.
+    let query = parser.parse("$[0][0][0][0]").unwrap();
+    assert!(query.find(&nested(5), &env).is_ok());
+    let query = parser.parse("$[0]..*").unwrap();
+    assert!(query.find(&nested(4), &env).is_ok());
+}
+
+#[test]
+fn timeout() {
+    let (parser, env) = sandbox(SandboxProfile {
+        timeout: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    let data = json!([1, 2, 3]);
+    let query = parser.parse("$[*]").unwrap();
+    assert_limit_error(
+        query.find(&data, &env).unwrap_err(),
+        "query timed out after 0ns",
+    );
+    assert!(parser.parse("$").unwrap().find(&data, &env).is_ok());
+}
+
+#[test]
+fn strict_regex_limits() {
+    let (parser, env) = sandbox(SandboxProfile::strict());
+    let data = json!(["ab", "abab"]);
+
+    let query = parser.parse("$[?match(@, '(ab)+')]").unwrap();
+    assert_eq!(query.find(&data, &env).unwrap().len(), 2);
+
+    let pattern = "a".repeat(300);
+    let query = parser
+        .parse(&format!("$[?search(@, '{pattern}|b')]"))
+        .unwrap();
+    assert!(query.find(&data, &env).unwrap().is_empty());
+}
+
+#[test]
+fn strict_profile() {
+    let (parser, env) = sandbox(SandboxProfile::strict());
+    let data = json!({"users": [{"name": "a"}, {"name": "b"}]});
+    let query = parser.parse("$.users[?@.name == 'b'].name").unwrap();
+    assert_eq!(query.find(&data, &env).unwrap()[0].value, &json!("b"));
+
+    assert!(parser.parse(&format!("$.{}", "a".repeat(1024))).is_err());
+
+    let data = Value::Array((0..10_001).map(Value::from).collect());
+    assert!(parser.parse("$[*]").unwrap().find(&data, &env).is_err());
+}
@@ -0,0 +1,109 @@
+use jsonpath_rfc9535_serde::{
+    env::Environment,
+    stats::{EvalStats, NothingReason, NothingTrace},
+};
+use serde_json::{json, Value};
+
+fn data() -> Value {
+    json!({
+        "limit": 2,
+        "users": [
+            {"name": "a", "address": {"city": "x"}, "tags": ["x"]},
+            {"name": "b", "address": {}, "tags": []},
+            {"name": "c", "tags": ["y", "z"]},
+        ]
+    })
+}
+
+fn stats(query: &str) -> EvalStats {
+    let data = data();
+    let (nodes, stats) = Environment::new().find_with_stats(query, &data).unwrap();
+    let want = Environment::new().find(query, &data).unwrap();
+    assert_eq!(nodes, want);
+    stats
+}
+
+#[test]
+fn no_nothing() {
+    assert_eq!(stats("$.users[?@.name == 'a']"), EvalStats::default());
+}
+
+#[test]
+fn missing_member_path() {
+    assert_eq!(
+        stats("$.users[?@.address.city == 'x']").nothing,
+        vec![
+            NothingTrace {
+                comparison: String::from("@['address']['city'] == 'x'"),
+                reason: NothingReason::MissingMember {
+                    path: String::from("@['address']['city']"),
+                },
+                count: 1,
+            },
+            NothingTrace {
+                comparison: String::from("@['address']['city'] == 'x'"),
+                reason: NothingReason::MissingMember {
+                    path: String::from("@['address']"),
+                },
+                count: 1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn missing_root_member() {
+    let stats = stats("$.users[?count(@.tags[*]) <= $.max]");
+    assert_eq!(stats.nothing.len(), 1);
+    assert_eq!(
+        stats.nothing[0].to_string(),
+        "$['max'] does not exist in `count(@['tags'][*]) <= $['max']` (3 times)"
+    );
+}
+
+#[test]
+fn empty_node_list_argument() {
+    let stats = stats("$.users[?value(@.tags[?@ == 'x']) == 'x']");
+    assert_eq!(
+        stats.nothing,
+        vec![NothingTrace {
+            comparison: String::from("value(@['tags'][?@ == 'x']) == 'x'"),
+            reason: NothingReason::EmptyNodeList {
+                query: String::from("@['tags'][?@ == 'x']"),
+            },
+            count: 2,
+        }]
+    );
+}
+
+#[test]
+fn function_result() {
+    let stats = stats("$.users[?length(@.name) == value(@.tags[*])]");
+    assert_eq!(
+        stats.nothing.iter().map(|t| &t.reason).collect::<Vec<_>>(),
+        vec![
+            &NothingReason::EmptyNodeList {
+                query: String::from("@['tags'][*]"),
+            },
+            &NothingReason::Function {
+                call: String::from("value(@['tags'][*])"),
+            },
+        ]
+    );
+    assert_eq!(stats.nothing[1].count, 1);
+}
+
+#[test]
+fn nested_filters() {
+    let stats = stats("$[?@[?@.nosuchthing == 1]]");
+    assert_eq!(
+        stats.nothing,
+        vec![NothingTrace {
+            comparison: String::from("@['nosuchthing'] == 1"),
+            reason: NothingReason::MissingMember {
+                path: String::from("@['nosuchthing']"),
+            },
+            count: 3,
+        }]
+    );
+}
@@ -0,0 +1,157 @@
+use std::{
+    io::{self, Read},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use jsonpath_rfc9535_serde::{
+    ast::FilterExpressionResult,
+    env::Environment,
+    errors::JSONPathErrorType,
+    find, find_stream,
+    function::{ExpressionType, FunctionSignature},
+    stream::StreamNode,
+    Query,
+};
+use serde_json::{json, Value};
+
+fn data() -> Value {
+    json!({
+        "store": {
+            "books": [
+                {"title": "a", "price": 8, "tags": ["x"]},
+                {"title": "b", "price": 12, "tags": []},
+                {"title": "c", "price": 5, "tags": ["x", "y"]}
+            ],
+            "bicycle": {"color": "red", "price": 20}
+        },
+        "limit": 10
+    })
+}
+
+/// Nodes selected by `find`, sorted by location.
+fn expected(query: &str) -> Vec<StreamNode> {
+    let value = data();
+    let mut nodes: Vec<StreamNode> = find(query, &value)
+        .unwrap()
+        .into_iter()
+        .map(StreamNode::from)
+        .collect();
+    nodes.sort_by(|a, b| a.location.cmp(&b.location));
+    nodes
+}
+
+fn streamed(query: &str) -> Vec<StreamNode> {
+    let text = data().to_string();
+    let mut nodes = find_stream(query, text.as_bytes()).unwrap();
+    nodes.sort_by(|a, b| a.location.cmp(&b.location));
+    nodes
+}
+
+#[test]
+fn same_nodes_as_find() {
+    for query in [
+        "$",
+        "$.store",
+        "$.store.books[1].title",
+        "$.store.books[*].title",
+        "$.store.books[0:2].price",
+        "$.store.books[::2]",
+        "$.store.books[-1].title",
+        "$.store.books[?@.price < 10].title",
+        "$.store.books[?length(@.tags) > 1]",
+        "$..price",
+        "$..*",
+        "$..books..[0]",
+        "$.store..[?@.price > 10]",
+        "$..[?@.price > 10].color",
+        "$.store.books[?@.price < 10, 1].title",
+        "$.store.books[0, 0].title",
+        "$.nosuchthing",
+        "$.store.books[5]",
+    ] {
+        assert_eq!(streamed(query), expected(query), "{query}");
+    }
+}
+
+#[test]
+fn root_queries_read_the_whole_document() {
+    let query = "$.store.books[?@.price > $.limit].title";
+    assert_eq!(streamed(query), expected(query));
+    assert_eq!(streamed(query)[0].value, json!("b"));
+}
+
+#[test]
+fn document_order() {
+    let nodes = find_stream("$['b', 'a']", r#"{"b": 2, "a": 1}"#.as_bytes()).unwrap();
+    let locations: Vec<&str> = nodes.iter().map(|node| node.location.as_str()).collect();
+    assert_eq!(locations, vec!["$['b']", "$['a']"]);
+
+    let nodes = find_stream("$[2, 0]", "[0, 1, 2]".as_bytes()).unwrap();
+    let values: Vec<&Value> = nodes.iter().map(|node| &node.value).collect();
+    assert_eq!(values, vec![&json!(0), &json!(2)]);
+}
+
+#[test]
+fn skipped_subtrees_are_still_checked() {
+    let err = find_stream("$.a", r#"{"a": 1, "b": [1, }"#.as_bytes()).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::DocumentError));
+
+    let err = find_stream("$.a", r#"{"a": 1} {}"#.as_bytes()).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::DocumentError));
+}
+
+#[test]
+fn errors_evaluating_subtrees_keep_their_kind() {
+    let env = Environment {
+        max_nodes: 1,
+        ..Environment::new()
+    };
+    let query = Query::standard("$.a[?@ > 0]").unwrap();
+    let err = query
+        .find_stream(r#"{"a": [1, 2, 3]}"#.as_bytes(), &env)
+        .unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::LimitError));
+}
+
+/// Bytes read by [`Counting`] readers.
+static READ: AtomicUsize = AtomicUsize::new(0);
+
+/// Reads from a byte slice, adding the number of bytes read to `READ`.
+struct Counting<'a>(&'a [u8]);
+
+impl Read for Counting<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.read(buf)?;
+        READ.fetch_add(n, Ordering::SeqCst);
+        Ok(n)
+    }
+}
+
+#[test]
+fn filters_test_children_as_they_are_read() {
+    static FIRST_TEST: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+    let mut env = Environment::new();
+    env.add_function(
+        "tested",
+        FunctionSignature {
+            param_types: vec![],
+            return_type: ExpressionType::Logical,
+            deterministic: false,
+            pure: false,
+            cost: None,
+        },
+        |_| {
+            FIRST_TEST.fetch_min(READ.load(Ordering::SeqCst), Ordering::SeqCst);
+            FilterExpressionResult::Bool(true)
+        },
+    );
+
+    let text = format!("[{}]", vec![r#"{"a": 1}"#; 1000].join(", "));
+    let query = env.parser().parse("$[?tested() && @.a == 1].a").unwrap();
+    let nodes = query.find_stream(Counting(text.as_bytes()), &env).unwrap();
+
+    assert_eq!(nodes.len(), 1000);
+    assert_eq!(nodes[999].location, "$[999]['a']");
+    assert!(FIRST_TEST.load(Ordering::SeqCst) < text.len() / 2);
+}
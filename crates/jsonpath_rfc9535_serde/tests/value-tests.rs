@@ -0,0 +1,75 @@
+use jsonpath_rfc9535_serde::{env::Environment, errors::JSONPathErrorType};
+use serde_json::{json, Value};
+
+fn data() -> Value {
+    json!({
+        "users": [
+            {"name": "a", "tags": ["x"]},
+            {"name": "b", "tags": ["x", "y"]},
+        ]
+    })
+}
+
+fn names(env: &Environment, query: &str) -> Vec<Value> {
+    env.find(query, &data())
+        .unwrap()
+        .iter()
+        .map(|node| node.value.clone())
+        .collect()
+}
+
+#[test]
+fn multiple_nodes_are_nothing_by_default() {
+    let env = Environment::new();
+    assert_eq!(
+        names(&env, "$.users[?value(@.tags[*]) == 'x'].name"),
+        vec![json!("a")]
+    );
+}
+
+#[test]
+fn strict_value_single_node() {
+    let env = Environment {
+        strict_value: true,
+        ..Environment::new()
+    };
+    assert_eq!(
+        names(&env, "$.users[?value(@.tags[0]) == 'x'].name"),
+        vec![json!("a"), json!("b")]
+    );
+    assert_eq!(
+        names(&env, "$.users[?value(@.nosuchthing) == 'x'].name"),
+        Vec::<Value>::new()
+    );
+}
+
+#[test]
+fn strict_value_multiple_nodes() {
+    let env = Environment {
+        strict_value: true,
+        ..Environment::new()
+    };
+    let err = env
+        .find("$.users[?value(@.tags[*]) == 'x']", &data())
+        .unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::RuntimeError));
+    assert_eq!(
+        err.msg,
+        "value() expected at most one node, found 2: @['tags'][0], @['tags'][1]"
+    );
+}
+
+#[test]
+fn strict_value_root_query() {
+    let env = Environment {
+        strict_value: true,
+        ..Environment::new()
+    };
+    let err = env
+        .find("$.users[?value($..name) == 'a']", &data())
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "value() expected at most one node, found 2: $['users'][0]['name'], $['users'][1]['name']"
+    );
+}
@@ -1,17 +1,28 @@
-use std::collections::HashMap;
+use std::time::Instant;
 
 use crate::{
     errors::JSONPathError,
     function::FunctionRegister,
     node::NodeList,
+    options::{FindOptions, FindResult},
     standard_functions::{Count, Length, Match, Search, Value},
-    Query,
+    JSONPathParser,
 };
 
 pub struct Environment {
     pub function_register: FunctionRegister,
 }
 
+/// The order in which wildcard and descendant segments visit object members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberOrder {
+    /// Members are visited in the order they appear in the document. This
+    /// is the case when serde_json's `preserve_order` feature is enabled.
+    Document,
+    /// Members are visited in lexicographic order of their names.
+    Lexical,
+}
+
 impl Default for Environment {
     fn default() -> Self {
         Self::new()
@@ -20,22 +31,71 @@ impl Default for Environment {
 
 impl Environment {
     pub fn new() -> Self {
-        let mut function_register: FunctionRegister = HashMap::new();
-        function_register.insert("count".to_string(), Box::new(Count::new()));
-        function_register.insert("length".to_string(), Box::new(Length::new()));
-        function_register.insert("match".to_string(), Box::new(Match::new()));
-        function_register.insert("search".to_string(), Box::new(Search::new()));
-        function_register.insert("value".to_string(), Box::new(Value::new()));
+        let mut function_register = FunctionRegister::new();
+        function_register.register_standard("count", Box::new(Count::new()));
+        function_register.register_standard("length", Box::new(Length::new()));
+        function_register.register_standard("match", Box::new(Match::new()));
+        function_register.register_standard("search", Box::new(Search::new()));
+        function_register.register_standard("value", Box::new(Value::new()));
 
         Self { function_register }
     }
 
+    /// A parser that knows the signatures of this environment's functions.
+    pub fn parser(&self) -> JSONPathParser {
+        let mut parser = JSONPathParser::new();
+        parser.functions = self.function_register.signatures();
+        parser
+    }
+
+    /// The order in which wildcard and descendant segments visit object
+    /// members.
+    ///
+    /// This follows serde_json's `Map`, so it reflects `preserve_order`
+    /// whether it was enabled by this crate's feature of the same name or
+    /// by any other crate in the dependency graph.
+    pub fn ordering(&self) -> MemberOrder {
+        let mut map = serde_json::Map::new();
+        map.insert("b".to_string(), serde_json::Value::Null);
+        map.insert("a".to_string(), serde_json::Value::Null);
+        match map.keys().next().map(String::as_str) {
+            Some("b") => MemberOrder::Document,
+            _ => MemberOrder::Lexical,
+        }
+    }
+
     pub fn find<'a>(
         &'static self,
         expr: &str,
         value: &'a serde_json::Value,
     ) -> Result<NodeList<'a>, JSONPathError> {
-        let query = Query::standard(expr)?;
+        let query = self.parser().parse(expr)?;
         Ok(query.find(value, self))
     }
+
+    /// Same as `find`, configured by `options`. Nodes are counted as the
+    /// query selects them, so evaluation stops soon after there are more
+    /// than `options` allows.
+    pub fn find_with_options<'a>(
+        &'static self,
+        expr: &str,
+        value: &'a serde_json::Value,
+        options: &FindOptions,
+    ) -> Result<FindResult<'a>, JSONPathError> {
+        let started = Instant::now();
+        options.check_query(expr).map_err(JSONPathError::limit)?;
+        let query = self.parser().parse(expr)?;
+
+        let mut collector = options.collector(started);
+        query
+            .find_each(value, self, |node| collector.push(node))
+            .map_err(JSONPathError::limit)?;
+
+        let (found, stats) = collector.finish(value);
+        Ok(FindResult {
+            found,
+            stats,
+            trace: None,
+        })
+    }
 }
@@ -1,5 +1,6 @@
-use std::fmt;
+use std::{borrow::Cow, cmp::Ordering, fmt};
 
+use jsonpath_rfc9535::number::{self, Number};
 use serde_json::Value;
 
 use crate::{env::Environment, function::ExpressionType, node::NodeList, Query};
@@ -15,6 +16,10 @@ pub enum FilterExpression {
     Int {
         value: i64,
     },
+    /// An integer literal too big for an `i64`.
+    UInt {
+        value: u64,
+    },
     Float {
         value: f64,
     },
@@ -47,19 +52,43 @@ impl FilterExpression {
     pub fn is_literal(&self) -> bool {
         matches!(
             self,
-            FilterExpression::True { .. }
-                | FilterExpression::False { .. }
-                | FilterExpression::Null { .. }
+            FilterExpression::True
+                | FilterExpression::False
+                | FilterExpression::Null
                 | FilterExpression::String { .. }
                 | FilterExpression::Int { .. }
+                | FilterExpression::UInt { .. }
                 | FilterExpression::Float { .. }
         )
     }
+
+    /// The name of the first function this expression calls for which `f`
+    /// is `true`, including those in filters of embedded queries.
+    pub(crate) fn find_call(&self, f: &dyn Fn(&str) -> bool) -> Option<&str> {
+        match self {
+            FilterExpression::Not { expression } => expression.find_call(f),
+            FilterExpression::Logical { left, right, .. }
+            | FilterExpression::Comparison { left, right, .. } => {
+                left.find_call(f).or_else(|| right.find_call(f))
+            }
+            FilterExpression::RelativeQuery { query } | FilterExpression::RootQuery { query } => {
+                query.find_call(f)
+            }
+            FilterExpression::Function { name, args } => {
+                if f(name) {
+                    Some(name)
+                } else {
+                    args.iter().find_map(|arg| arg.find_call(f))
+                }
+            }
+            _ => None,
+        }
+    }
 }
 
 impl FilterExpression {
-    pub fn evaluate<'v>(
-        &self,
+    pub fn evaluate<'e: 'v, 'v>(
+        &'e self,
         env: &'static Environment,
         root: &'v Value,
         current: &'v Value,
@@ -68,8 +97,11 @@ impl FilterExpression {
             FilterExpression::True => FilterExpressionResult::Bool(true),
             FilterExpression::False => FilterExpressionResult::Bool(false),
             FilterExpression::Null => FilterExpressionResult::Null,
-            FilterExpression::String { value } => FilterExpressionResult::String(value.to_owned()),
+            FilterExpression::String { value } => {
+                FilterExpressionResult::String(Cow::Borrowed(value))
+            }
             FilterExpression::Int { value } => FilterExpressionResult::Int(*value),
+            FilterExpression::UInt { value } => FilterExpressionResult::UInt(*value),
             FilterExpression::Float { value } => FilterExpressionResult::Float(*value),
             FilterExpression::Not { expression } => {
                 if !is_truthy(expression.evaluate(env, root, current)) {
@@ -115,16 +147,37 @@ impl FilterExpression {
                 FilterExpressionResult::Nodes(query.find(root, env))
             }
             FilterExpression::Function { name, args } => {
-                let fn_ext = env
-                    .function_register
-                    .get(name)
-                    .unwrap_or_else(|| panic!("unknown function '{}'", name));
+                let (Some(fn_ext), Some(signature)) = (
+                    env.function_register.get(name),
+                    env.function_register.signature(name),
+                ) else {
+                    // Only a query evaluated with a different environment
+                    // than it was parsed with calls an unknown function,
+                    // which `Query::try_find` reports.
+                    return FilterExpressionResult::Nothing;
+                };
 
+                let param_types = &signature.param_types;
                 let _args = args
                     .iter()
-                    .map(|expr| expr.evaluate(env, root, current))
                     .enumerate()
-                    .map(|(i, rv)| unpack_result(rv, &fn_ext.sig().param_types, i))
+                    .map(|(i, expr)| match (param_types.get(i), expr) {
+                        // Resolve singular queries directly when a value is
+                        // expected, so `length(@.big_array)` never builds a
+                        // node list.
+                        (
+                            Some(ExpressionType::Value),
+                            FilterExpression::RelativeQuery { query },
+                        ) if query.is_singular() => {
+                            FilterExpressionResult::from_singular(query.singular_value(current))
+                        }
+                        (Some(ExpressionType::Value), FilterExpression::RootQuery { query })
+                            if query.is_singular() =>
+                        {
+                            FilterExpressionResult::from_singular(query.singular_value(root))
+                        }
+                        _ => unpack_result(expr.evaluate(env, root, current), param_types, i),
+                    })
                     .collect();
 
                 fn_ext.call(_args)
@@ -136,11 +189,12 @@ impl FilterExpression {
 impl fmt::Display for FilterExpression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            FilterExpression::True { .. } => f.write_str("true"),
-            FilterExpression::False { .. } => f.write_str("false"),
-            FilterExpression::Null { .. } => f.write_str("null"),
+            FilterExpression::True => f.write_str("true"),
+            FilterExpression::False => f.write_str("false"),
+            FilterExpression::Null => f.write_str("null"),
             FilterExpression::String { value, .. } => write!(f, "'{value}'"),
             FilterExpression::Int { value, .. } => write!(f, "{value}"),
+            FilterExpression::UInt { value, .. } => write!(f, "{value}"),
             FilterExpression::Float { value, .. } => write!(f, "{value}"),
             FilterExpression::Not { expression, .. } => write!(f, "!{expression}"),
             FilterExpression::Logical {
@@ -233,12 +287,15 @@ impl fmt::Display for ComparisonOperator {
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum FilterExpressionResult<'a> {
     Bool(bool),
     Int(i64),
+    /// An integer too big for an `i64`.
+    UInt(u64),
     Float(f64),
     Null,
-    String(String),
+    String(Cow<'a, str>),
     Array(&'a Value),
     Object(&'a Value),
     Nodes(NodeList<'a>),
@@ -246,18 +303,32 @@ pub enum FilterExpressionResult<'a> {
 }
 
 impl<'a> FilterExpressionResult<'a> {
+    /// The result of a singular query argument, `Nothing` if the query
+    /// selected nothing.
+    pub fn from_singular(value: Option<&'a Value>) -> Self {
+        value.map_or(
+            FilterExpressionResult::Nothing,
+            FilterExpressionResult::from_json_value,
+        )
+    }
+
     pub fn from_json_value(value: &'a Value) -> Self {
         match value {
             Value::Bool(v) => FilterExpressionResult::Bool(*v),
             Value::Null => FilterExpressionResult::Null,
             Value::Number(n) => {
-                if n.is_f64() {
-                    FilterExpressionResult::Float(n.as_f64().unwrap())
+                if let Some(i) = n.as_i64() {
+                    FilterExpressionResult::Int(i)
+                } else if let Some(u) = n.as_u64() {
+                    FilterExpressionResult::UInt(u)
                 } else {
-                    FilterExpressionResult::Int(n.as_i64().unwrap())
+                    n.as_f64().map_or(
+                        FilterExpressionResult::Nothing,
+                        FilterExpressionResult::Float,
+                    )
                 }
             }
-            Value::String(s) => FilterExpressionResult::String(s.to_owned()),
+            Value::String(s) => FilterExpressionResult::String(Cow::Borrowed(s)),
             Value::Array(_) => FilterExpressionResult::Array(value),
             Value::Object(_) => FilterExpressionResult::Object(value),
         }
@@ -295,13 +366,10 @@ pub fn logical(
 
 fn nodes_or_singular(rv: FilterExpressionResult<'_>) -> FilterExpressionResult<'_> {
     match rv {
-        FilterExpressionResult::Nodes(ref nodes) => {
-            if nodes.len() == 1 {
-                FilterExpressionResult::from_json_value(nodes.first().unwrap().value)
-            } else {
-                rv
-            }
-        }
+        FilterExpressionResult::Nodes(ref nodes) => match nodes.as_slice() {
+            [node] => FilterExpressionResult::from_json_value(node.value),
+            _ => rv,
+        },
         _ => rv,
     }
 }
@@ -324,24 +392,29 @@ pub fn compare(
     }
 }
 
+/// A numeric result as a number that compares exactly with the others.
+fn as_number(rv: &FilterExpressionResult) -> Option<Number> {
+    match rv {
+        FilterExpressionResult::Int(i) => Some(Number::Int((*i).into())),
+        FilterExpressionResult::UInt(u) => Some(Number::Int((*u).into())),
+        FilterExpressionResult::Float(f) => Some(Number::Float(*f)),
+        _ => None,
+    }
+}
+
 fn eq(left: &FilterExpressionResult, right: &FilterExpressionResult) -> bool {
+    if let (Some(l), Some(r)) = (as_number(left), as_number(right)) {
+        return number::compare(l, r) == Some(Ordering::Equal);
+    }
+
     use FilterExpressionResult::*;
     match (left, right) {
         (Nothing, Nothing) => true,
         (Nodes(nodes), Nothing) | (Nothing, Nodes(nodes)) => nodes.is_empty(),
         (Nothing, _) | (_, Nothing) => false,
-        (Nodes(left), Nodes(right)) => {
-            if left.is_empty() && right.is_empty() {
-                true
-            } else {
-                // Only singular queries can be compared
-                unreachable!()
-            }
-        }
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Int(r)) => l == r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Float(r)) => l == r,
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Float(r)) => *l as f64 == *r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Int(r)) => *l == *r as f64,
+        // Only singular queries can be compared, so non-empty node lists
+        // never get here.
+        (Nodes(left), Nodes(right)) => left.is_empty() && right.is_empty(),
         (FilterExpressionResult::Null, FilterExpressionResult::Null) => true,
         (FilterExpressionResult::Bool(l), FilterExpressionResult::Bool(r)) => l == r,
         (FilterExpressionResult::String(l), FilterExpressionResult::String(r)) => l == r,
@@ -352,13 +425,13 @@ fn eq(left: &FilterExpressionResult, right: &FilterExpressionResult) -> bool {
 }
 
 fn lt(left: &FilterExpressionResult, right: &FilterExpressionResult) -> bool {
+    if let (Some(l), Some(r)) = (as_number(left), as_number(right)) {
+        return number::compare(l, r) == Some(Ordering::Less);
+    }
+
     match (left, right) {
         (FilterExpressionResult::String(l), FilterExpressionResult::String(r)) => l < r,
         (FilterExpressionResult::Bool(_), FilterExpressionResult::Bool(_)) => false,
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Int(r)) => l < r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Float(r)) => l < r,
-        (FilterExpressionResult::Int(l), FilterExpressionResult::Float(r)) => (*l as f64) < *r,
-        (FilterExpressionResult::Float(l), FilterExpressionResult::Int(r)) => *l < *r as f64,
         _ => false,
     }
 }
@@ -368,14 +441,14 @@ pub fn unpack_result<'a>(
     param_types: &[ExpressionType],
     index: usize,
 ) -> FilterExpressionResult<'a> {
-    if matches!(param_types.get(index).unwrap(), ExpressionType::Nodes) {
+    if matches!(param_types.get(index), Some(ExpressionType::Nodes)) {
         return rv;
     }
 
     match &rv {
-        FilterExpressionResult::Nodes(nodes) => match nodes.len() {
-            0 => FilterExpressionResult::Nothing,
-            1 => FilterExpressionResult::from_json_value(nodes.first().unwrap().value),
+        FilterExpressionResult::Nodes(nodes) => match nodes.as_slice() {
+            [] => FilterExpressionResult::Nothing,
+            [node] => FilterExpressionResult::from_json_value(node.value),
             _ => rv,
         },
         _ => rv,
@@ -1,4 +1,10 @@
-use crate::{env::Environment, errors::JSONPathError, node::NodeList, Query};
+use crate::{
+    env::Environment,
+    errors::JSONPathError,
+    node::NodeList,
+    options::{FindOptions, FindResult},
+    Query,
+};
 use lazy_static::lazy_static;
 use serde_json::Value;
 
@@ -10,3 +16,12 @@ pub fn find<'a>(expr: &str, value: &'a Value) -> Result<NodeList<'a>, JSONPathEr
     let query = Query::standard(expr)?;
     Ok(query.find(value, &ENV))
 }
+
+/// Same as `find`, configured by `options`.
+pub fn find_with_options<'a>(
+    expr: &str,
+    value: &'a Value,
+    options: &FindOptions,
+) -> Result<FindResult<'a>, JSONPathError> {
+    ENV.find_with_options(expr, value, options)
+}
@@ -1,8 +1,11 @@
+#![forbid(unsafe_code)]
+
 // use jsonpath_rfc9535_locations::Query;
 use jsonpath_rfc9535_singular::jsonpath::find;
 use serde_json::Value;
+use std::error::Error;
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
     let data = r#"
       {
         "a": {
@@ -19,13 +22,14 @@ fn main() {
         }
       }"#;
 
-    let v: Value = serde_json::from_str(data).unwrap();
+    let v: Value = serde_json::from_str(data)?;
     let q = "$.a.j[$['c d'].x.y]";
 
-    let rv = find(q, &v).unwrap();
+    let rv = find(q, &v)?;
     println!("{:?}", rv.len());
     println!("{:?}", rv);
     // let query = Query::standard(q);
     // println!("{:?}", v.is_object());
     // println!("{:?}", query.unwrap().segments.len());
+    Ok(())
 }
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use jsonpath_rfc9535::resolve_index;
 use lazy_static::lazy_static;
 use serde_json::Value;
 
@@ -44,10 +45,75 @@ impl Query {
             })
     }
 
+    /// Like [`Query::find`], but failing with a name error if the query
+    /// calls a function `env` doesn't have, which happens if it was parsed
+    /// with a different environment. `find` evaluates such calls to nothing.
+    pub fn try_find<'v>(
+        &self,
+        value: &'v Value,
+        env: &'static Environment,
+    ) -> Result<NodeList<'v>, JSONPathError> {
+        if let Some(name) = self.find_call(&|name| env.function_register.get(name).is_none()) {
+            return Err(JSONPathError::name(format!("unknown function `{name}`")));
+        }
+        Ok(self.find(value, env))
+    }
+
+    /// The name of the first function called by a filter in this query for
+    /// which `f` is `true`.
+    pub(crate) fn find_call(&self, f: &dyn Fn(&str) -> bool) -> Option<&str> {
+        self.segments.iter().find_map(|segment| match segment {
+            Segment::Child { selectors } | Segment::Recursive { selectors } => {
+                selectors.iter().find_map(|selector| match selector {
+                    Selector::Filter { expression } => expression.find_call(f),
+                    Selector::SingularQuery { query } => query.find_call(f),
+                    _ => None,
+                })
+            }
+            Segment::Eoi => None,
+        })
+    }
+
+    /// Like [`Query::find`], but passing each node to `f` as it's selected,
+    /// one input node at a time, and stopping at the first error from `f`.
+    pub(crate) fn find_each<'v, E>(
+        &self,
+        value: &'v Value,
+        env: &'static Environment,
+        mut f: impl FnMut(Node<'v>) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let root_node = Node {
+            value,
+            location: ConsList::new(),
+        };
+
+        select_each(&self.segments, root_node, env, value, &mut f)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.segments.is_empty()
     }
 
+    /// Resolve a singular query against `value` directly, without building a
+    /// node list. Returns `None` if the query selects nothing, or if it is
+    /// not singular.
+    pub fn singular_value<'v>(&self, value: &'v Value) -> Option<&'v Value> {
+        self.segments
+            .iter()
+            .try_fold(value, |value, segment| match segment {
+                Segment::Child { selectors } => match selectors.as_slice() {
+                    [Selector::Name { name }] => value.as_object()?.get(name),
+                    [Selector::Index { index }] => {
+                        let array = value.as_array()?;
+                        array.get(resolve_index(*index, array.len())?)
+                    }
+                    _ => None,
+                },
+                Segment::Eoi => Some(value),
+                Segment::Recursive { .. } => None,
+            })
+    }
+
     pub fn is_singular(&self) -> bool {
         self.segments.iter().all(|segment| {
             if let Segment::Child { selectors, .. } = segment {
@@ -61,6 +127,23 @@ impl Query {
     }
 }
 
+/// Pass each node `segments` select from `node` to `f`, depth first.
+fn select_each<'v, E>(
+    segments: &[Segment],
+    node: Node<'v>,
+    env: &'static Environment,
+    root: &'v Value,
+    f: &mut impl FnMut(Node<'v>) -> Result<(), E>,
+) -> Result<(), E> {
+    match segments.split_first() {
+        None => f(node),
+        Some((segment, rest)) => segment
+            .resolve(vec![node], env, root)
+            .into_iter()
+            .try_for_each(|node| select_each(rest, node, env, root, f)),
+    }
+}
+
 impl fmt::Display for Query {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -36,7 +36,7 @@ impl Segment {
                 .into_iter()
                 .flat_map(move |node| self.visit(env, node.value, selectors, root, node.location))
                 .collect(),
-            Segment::Eoi {} => nodes,
+            Segment::Eoi => nodes,
         }
     }
 
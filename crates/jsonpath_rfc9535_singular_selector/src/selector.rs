@@ -1,8 +1,6 @@
-use std::{
-    cmp,
-    fmt::{self, Write},
-};
+use std::fmt::{self, Write};
 
+use jsonpath_rfc9535::resolve_index;
 use serde_json::Value;
 
 use crate::{
@@ -51,7 +49,7 @@ impl Selector {
                 .collect(),
             Selector::Index { index } => value
                 .as_array()
-                .and_then(|array| Some((norm_index(*index, array.len())?, array)))
+                .and_then(|array| Some((resolve_index(*index, array.len())?, array)))
                 .and_then(|(i, array)| Some((i, array.get(i)?)))
                 .map(|(i, v)| Node::new_array_element(v, location, i))
                 .into_iter()
@@ -60,7 +58,7 @@ impl Selector {
                 .as_array()
                 .and_then(|array| slice(array, location, *start, *stop, *step))
                 .unwrap_or_default(),
-            Selector::Wild {} => match value {
+            Selector::Wild => match value {
                 Value::Array(arr) => arr
                     .iter()
                     .enumerate()
@@ -91,21 +89,19 @@ impl Selector {
             Selector::SingularQuery { query } => {
                 let nodes = query.find(root, env);
 
-                if nodes.len() != 1 {
+                let [node] = nodes.as_slice() else {
                     return Vec::new();
-                }
+                };
 
-                match nodes.first().unwrap().value {
+                match node.value {
                     Value::Number(index) => {
-                        if !(index.is_i64()) {
+                        let Some(index) = index.as_i64() else {
                             return Vec::new();
-                        }
+                        };
 
                         value
                             .as_array()
-                            .and_then(|array| {
-                                Some((norm_index(index.as_i64().unwrap(), array.len())?, array))
-                            })
+                            .and_then(|array| Some((resolve_index(index, array.len())?, array)))
                             .and_then(|(i, array)| Some((i, array.get(i)?)))
                             .map(|(i, v)| Node::new_array_element(v, location, i))
                             .into_iter()
@@ -146,24 +142,13 @@ impl fmt::Display for Selector {
                         .unwrap_or(String::from("1")),
                 )
             }
-            Selector::Wild { .. } => f.write_char('*'),
+            Selector::Wild => f.write_char('*'),
             Selector::Filter { expression, .. } => write!(f, "?{expression}"),
             Selector::SingularQuery { query } => write!(f, "{query}"),
         }
     }
 }
 
-fn norm_index(index: i64, length: usize) -> Option<usize> {
-    if index < 0 {
-        index
-            .checked_abs()
-            .and_then(|i| usize::try_from(i).ok())
-            .and_then(|i| length.checked_sub(i))
-    } else {
-        usize::try_from(index).ok()
-    }
-}
-
 fn slice<'v>(
     array: &'v [Value],
     location: &Location,
@@ -183,14 +168,19 @@ fn slice<'v>(
         return None;
     }
 
-    let n_start = match start {
-        Some(i) => {
-            if i < 0 {
-                cmp::max(len + i as i128, 0)
-            } else {
-                cmp::min(i as i128, len - 1)
-            }
+    // Clamp normalized bounds to `0..=len`, or to `-1..=len - 1` when slicing
+    // backwards, so out of range bounds select nothing rather than the ends.
+    let bound = |i: i64| {
+        let i = if i < 0 { len + i as i128 } else { i as i128 };
+        if step > 0 {
+            i.clamp(0, len)
+        } else {
+            i.clamp(-1, len - 1)
         }
+    };
+
+    let n_start = match start {
+        Some(i) => bound(i),
         None => {
             if step < 0 {
                 len - 1
@@ -201,13 +191,7 @@ fn slice<'v>(
     };
 
     let n_stop = match stop {
-        Some(i) => {
-            if i < 0 {
-                cmp::max(len + i as i128, -1)
-            } else {
-                cmp::min(i as i128, len)
-            }
-        }
+        Some(i) => bound(i),
         None => {
             if step < 0 {
                 -1
@@ -223,22 +207,14 @@ fn slice<'v>(
         let step = usize::try_from(step).ok()?;
         for i in (n_start..n_stop).step_by(step) {
             let index = usize::try_from(i).ok()?;
-            slice.push(Node::new_array_element(
-                array.get(index).unwrap(),
-                location,
-                index,
-            ));
+            slice.push(Node::new_array_element(array.get(index)?, location, index));
         }
     } else {
         let step = step as i128;
         let mut i = n_start;
         while i > n_stop {
             let index = usize::try_from(i).ok()?;
-            slice.push(Node::new_array_element(
-                array.get(index).unwrap(),
-                location,
-                index,
-            ));
+            slice.push(Node::new_array_element(array.get(index)?, location, index));
             i += step;
         }
     }
@@ -1,12 +1,12 @@
-use std::{num::NonZeroUsize, sync::Mutex};
+use std::{
+    num::NonZeroUsize,
+    sync::{Mutex, PoisonError},
+};
 
 use lru::LruCache;
 use regex::Regex;
 
-use crate::{
-    filter::FilterExpressionResult,
-    function::{ExpressionType, FunctionExtension, FunctionSignature},
-};
+use crate::{filter::FilterExpressionResult, function::FunctionExtension};
 
 pub struct Count;
 
@@ -24,16 +24,9 @@ impl Default for Count {
 
 impl FunctionExtension for Count {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match args.first().unwrap() {
+        match args.first().unwrap_or(&FilterExpressionResult::Nothing) {
             FilterExpressionResult::Nodes(nodes) => FilterExpressionResult::Int(nodes.len() as i64),
-            _ => unreachable!(),
-        }
-    }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
+            _ => FilterExpressionResult::Nothing,
         }
     }
 }
@@ -54,29 +47,27 @@ impl Default for Length {
 
 impl FunctionExtension for Length {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match args.first().unwrap() {
-            // TODO: UInt
+        match args.first().unwrap_or(&FilterExpressionResult::Nothing) {
             FilterExpressionResult::String(s) => {
                 FilterExpressionResult::Int(s.chars().count() as i64)
             }
             FilterExpressionResult::Array(a) => {
-                FilterExpressionResult::Int(a.as_array().unwrap().len() as i64)
+                FilterExpressionResult::Int(a.as_array().map_or(0, Vec::len) as i64)
             }
             FilterExpressionResult::Object(o) => {
-                FilterExpressionResult::Int(o.as_object().unwrap().len() as i64)
+                FilterExpressionResult::Int(o.as_object().map_or(0, serde_json::Map::len) as i64)
             }
             _ => FilterExpressionResult::Nothing,
         }
     }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value],
-            return_type: ExpressionType::Value,
-        }
-    }
 }
 
+/// The number of compiled patterns `match` and `search` keep.
+const REGEX_CACHE_SIZE: NonZeroUsize = match NonZeroUsize::new(100) {
+    Some(size) => size,
+    None => NonZeroUsize::MIN,
+};
+
 pub struct Match {
     cache: Mutex<LruCache<String, Regex>>,
 }
@@ -84,7 +75,7 @@ pub struct Match {
 impl Match {
     pub fn new() -> Self {
         Self {
-            cache: Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap())),
+            cache: Mutex::new(LruCache::new(REGEX_CACHE_SIZE)),
         }
     }
 }
@@ -97,12 +88,12 @@ impl Default for Match {
 
 impl FunctionExtension for Match {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match (args.first().unwrap(), args.get(1).unwrap()) {
-            (FilterExpressionResult::String(s), FilterExpressionResult::String(p)) => {
+        match (args.first(), args.get(1)) {
+            (Some(FilterExpressionResult::String(s)), Some(FilterExpressionResult::String(p))) => {
                 // TODO: fail early if p is known to be invalid
-                let mut cache = self.cache.lock().unwrap();
+                let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
 
-                match cache.get(p) {
+                match cache.get(p.as_ref()) {
                     Some(re) => FilterExpressionResult::Bool(re.is_match(s)),
                     None => {
                         if !iregexp::check(p) {
@@ -111,7 +102,7 @@ impl FunctionExtension for Match {
 
                         if let Ok(re) = Regex::new(&full_match(p)) {
                             let rv = re.is_match(s);
-                            cache.push(p.to_owned(), re);
+                            cache.push(p.to_string(), re);
                             FilterExpressionResult::Bool(rv)
                         } else {
                             FilterExpressionResult::Bool(false)
@@ -122,13 +113,6 @@ impl FunctionExtension for Match {
             _ => FilterExpressionResult::Bool(false),
         }
     }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        }
-    }
 }
 
 pub struct Search {
@@ -138,7 +122,7 @@ pub struct Search {
 impl Search {
     pub fn new() -> Self {
         Self {
-            cache: Mutex::new(LruCache::new(NonZeroUsize::new(100).unwrap())),
+            cache: Mutex::new(LruCache::new(REGEX_CACHE_SIZE)),
         }
     }
 }
@@ -151,12 +135,12 @@ impl Default for Search {
 
 impl FunctionExtension for Search {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match (args.first().unwrap(), args.get(1).unwrap()) {
-            (FilterExpressionResult::String(s), FilterExpressionResult::String(p)) => {
+        match (args.first(), args.get(1)) {
+            (Some(FilterExpressionResult::String(s)), Some(FilterExpressionResult::String(p))) => {
                 // TODO: fail early if p is known to be invalid
-                let mut cache = self.cache.lock().unwrap();
+                let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
 
-                match cache.get(p) {
+                match cache.get(p.as_ref()) {
                     Some(re) => FilterExpressionResult::Bool(re.is_match(s)),
                     None => {
                         if !iregexp::check(p) {
@@ -165,7 +149,7 @@ impl FunctionExtension for Search {
 
                         if let Ok(re) = Regex::new(&map_regex(p)) {
                             let rv = re.is_match(s);
-                            cache.push(p.to_owned(), re);
+                            cache.push(p.to_string(), re);
                             FilterExpressionResult::Bool(rv)
                         } else {
                             FilterExpressionResult::Bool(false)
@@ -176,13 +160,6 @@ impl FunctionExtension for Search {
             _ => FilterExpressionResult::Bool(false),
         }
     }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Value, ExpressionType::Value],
-            return_type: ExpressionType::Logical,
-        }
-    }
 }
 
 pub struct Value;
@@ -201,22 +178,15 @@ impl Default for Value {
 
 impl FunctionExtension for Value {
     fn call<'a>(&self, args: Vec<FilterExpressionResult<'a>>) -> FilterExpressionResult<'a> {
-        match args.first().unwrap() {
+        match args.first().unwrap_or(&FilterExpressionResult::Nothing) {
             FilterExpressionResult::Nodes(nodes) => {
-                if nodes.len() == 1 {
-                    FilterExpressionResult::from_json_value(nodes.first().unwrap().value)
+                if let [node] = nodes.as_slice() {
+                    FilterExpressionResult::from_json_value(node.value)
                 } else {
                     FilterExpressionResult::Nothing
                 }
             }
-            _ => unreachable!(),
-        }
-    }
-
-    fn sig(&self) -> FunctionSignature {
-        FunctionSignature {
-            param_types: vec![ExpressionType::Nodes],
-            return_type: ExpressionType::Value,
+            _ => FilterExpressionResult::Nothing,
         }
     }
 }
@@ -0,0 +1,19 @@
+use jsonpath_rfc9535_singular::{errors::JSONPathErrorType, ENV};
+use serde_json::json;
+
+#[test]
+fn function_missing_from_environment() {
+    let mut parser = ENV.parser();
+    let signature = parser.functions["length"].clone();
+    parser.functions.insert(String::from("size"), signature);
+    let query = parser.parse("$[?size(@) > 1]").unwrap();
+    let value = json!(["a", "bc"]);
+
+    // The query was parsed with `size`, but the default environment doesn't
+    // have it, so the function evaluates to nothing.
+    assert!(query.find(&value, &ENV).is_empty());
+
+    let err = query.try_find(&value, &ENV).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::NameError));
+    assert_eq!(err.msg, "unknown function `size`");
+}
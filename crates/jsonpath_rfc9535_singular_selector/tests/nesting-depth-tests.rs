@@ -0,0 +1,48 @@
+use jsonpath_rfc9535_singular::{errors::JSONPathErrorType, JSONPathParser, Query};
+
+#[test]
+fn nesting_depth_limit() {
+    let mut parser = JSONPathParser::new();
+    parser.max_nesting_depth = 3;
+    assert!(parser.parse("$[?count(@[?@.a]) > 1]").is_ok());
+    assert!(parser.parse("$[?@['[[[['] == '((((']").is_ok());
+
+    let err = parser.parse("$[?count(@[?(@.a)]) > 1]").unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::DepthError));
+    assert_eq!(err.msg, "query nesting depth exceeds the maximum of 3");
+    assert_eq!(err.span.map(|span| span.start), Some(12));
+}
+
+#[test]
+fn deeply_nested_filters() {
+    let query = format!("${}{}", "[?@".repeat(100_000), "]".repeat(100_000));
+    let err = Query::standard(&query).unwrap_err();
+    assert!(matches!(err.kind, JSONPathErrorType::DepthError));
+    assert_eq!(err.span.map(|span| span.start), Some(193));
+}
+
+#[test]
+fn nesting_depth_corpus() {
+    for line in include_str!("../../../tests/nesting-depth.tsv").lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let [query, depth, offset] = line.split('\t').collect::<Vec<_>>()[..] else {
+            panic!("malformed corpus line {line:?}");
+        };
+        let depth: usize = depth.parse().unwrap();
+        let mut parser = JSONPathParser::new();
+        parser.max_nesting_depth = depth;
+        assert!(parser.parse(query).is_ok(), "{query:?}");
+
+        if depth > 0 {
+            parser.max_nesting_depth = depth - 1;
+            let err = parser.parse(query).unwrap_err();
+            assert!(
+                matches!(err.kind, JSONPathErrorType::DepthError),
+                "{query:?}"
+            );
+            assert_eq!(err.span.map(|span| span.start), Some(offset.parse().unwrap()), "{query:?}");
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use jsonpath_rfc9535_singular::find;
+use serde_json::Value;
+
+fn paths(query: &str) -> Vec<String> {
+    let value: Value = serde_json::from_str(
+        "[9007199254740993, 9007199254740992.0, 18446744073709551615, -9223372036854775808, 1.5]",
+    )
+    .unwrap();
+    find(query, &value)
+        .unwrap()
+        .iter()
+        .map(|node| node.path())
+        .collect()
+}
+
+#[test]
+fn integers_above_2_pow_53_compare_exactly() {
+    assert_eq!(paths("$[?@ == 9007199254740992]"), vec!["$[1]"]);
+    assert_eq!(paths("$[?@ == 9007199254740993]"), vec!["$[0]"]);
+    assert_eq!(paths("$[?@ > 9007199254740992]"), vec!["$[0]", "$[2]"]);
+    assert_eq!(
+        paths("$[?@ <= 9007199254740992.0]"),
+        vec!["$[1]", "$[3]", "$[4]"]
+    );
+}
+
+#[test]
+fn unsigned_integers_above_i64_max() {
+    assert_eq!(paths("$[?@ > 9223372036854775807]"), vec!["$[2]"]);
+    assert_eq!(
+        paths("$[?@ < 1.8446744073709552e19]"),
+        vec!["$[0]", "$[1]", "$[2]", "$[3]", "$[4]"]
+    );
+    assert!(paths("$[?@ == 1.8446744073709552e19]").is_empty());
+}
+
+#[test]
+fn integer_literals_above_i64_max() {
+    assert_eq!(paths("$[?@ == 18446744073709551615]"), vec!["$[2]"]);
+    assert_eq!(paths("$[?@ > 1e19]"), vec!["$[2]"]);
+    assert!(paths("$[?@ == 18446744073709551616]").is_empty());
+    assert_eq!(
+        paths("$[?@ < 18446744073709551615]"),
+        vec!["$[0]", "$[1]", "$[3]", "$[4]"]
+    );
+}
+
+#[test]
+fn integers_and_fractions() {
+    assert_eq!(paths("$[?@ < 2 && @ > 1]"), vec!["$[4]"]);
+    assert_eq!(paths("$[?@ == -9223372036854775808]"), vec!["$[3]"]);
+    assert_eq!(paths("$[?@ == -9.223372036854775808e18]"), vec!["$[3]"]);
+}
@@ -22,3 +22,10 @@ fn descendant_normalized_path() {
     assert_eq!(nodes.first().unwrap().path(), "$['a']['b'][0]");
     assert_eq!(nodes.last().unwrap().path(), "$['a']['b'][2]");
 }
+
+#[test]
+fn slice_start_beyond_end() {
+    let value: Value = serde_json::from_str("[0]").unwrap();
+    assert!(find("$[3:5]", &value).unwrap().is_empty());
+    assert_eq!(find("$[3::-1]", &value).unwrap().len(), 1);
+}
@@ -0,0 +1,17 @@
+use jsonpath_rfc9535_singular::find;
+use serde_json::Value;
+
+#[test]
+fn slice_start_beyond_end() {
+    let value: Value = serde_json::from_str("[0]").unwrap();
+    assert!(find("$[3:5]", &value).unwrap().is_empty());
+    assert_eq!(find("$[3::-1]", &value).unwrap().len(), 1);
+}
+
+#[test]
+fn negative_step_with_start_before_the_first_element() {
+    let value: Value = serde_json::from_str("[0, 1, 2]").unwrap();
+    assert!(find("$[-10::-1]", &value).unwrap().is_empty());
+    assert!(find("$[-10:-20:-1]", &value).unwrap().is_empty());
+    assert_eq!(find("$[-3::-1]", &value).unwrap().len(), 1);
+}